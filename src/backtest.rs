@@ -0,0 +1,141 @@
+//! Backtest Binary Entry Point
+//!
+//! Replays the prices recorded by the monitor (Price table) through the decisions of a market maker config,
+//! with synthetic pools instead of the Tycho stream and RPC calls, see `shd::maker::backtest`. Writes the PnL
+//! series, the fills, the decisions per gate and a JSON report into the output directory.
+use std::path::Path;
+
+use clap::Parser;
+use shd::{
+    data::neon::{self, pull},
+    maker::{
+        audit::{self, rotated},
+        backtest::{self, Backtest},
+        exec::ExecStrategyFactory,
+        feed::PriceFeedFactory,
+    },
+    types::{
+        builder::MarketMakerBuilder,
+        cli::BacktestCli,
+        config::{load_market_maker_config, LogFormat, MoniEnvConfig},
+    },
+    utils::constants::BACKTEST_PAGE_ROWS,
+};
+use tracing_subscriber::EnvFilter;
+
+/// Unix ms of a `--from`/`--to` flag, as a naive UTC time.
+fn time(flag: Option<&str>, default: u64) -> Result<chrono::NaiveDateTime, String> {
+    let ms = flag.map(audit::parse_time).transpose()?.unwrap_or(default);
+    chrono::DateTime::from_timestamp_millis(ms as i64)
+        .map(|time| time.naive_utc())
+        .ok_or_else(|| format!("Time out of range: {} ms", ms))
+}
+
+/// Loads the config and the price rows, replays them and writes the report.
+async fn run(cli: BacktestCli) -> Result<(), String> {
+    let mut config = load_market_maker_config(&cli.config).map_err(|e| format!("Failed to load config {}: {}", cli.config, e))?;
+    // Decisions are audited into the output directory, nothing is published nor broadcast
+    let out = Path::new(&cli.out);
+    std::fs::create_dir_all(out).map_err(|e| format!("Failed to create {}: {}", out.display(), e))?;
+    let decisions = out.join("decisions.jsonl");
+    let _ = std::fs::remove_file(&decisions);
+    let _ = std::fs::remove_file(rotated(&decisions));
+    config.audit_log_path = Some(decisions.to_string_lossy().to_string());
+    config.audit_log_max_bytes = u64::MAX;
+    config.publish_events = false;
+    config.dry_run = true;
+
+    let base = backtest::token(&config, &config.base_token_address, &config.base_token, cli.base_decimals)?;
+    let quote = backtest::token(&config, &config.quote_token_address, &config.quote_token, cli.quote_decimals)?;
+    let prefix = cli.instance.clone().unwrap_or_else(|| config.id());
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    let mk = MarketMakerBuilder::new()
+        .config(config)
+        .feed(feed)
+        .execution(execution)
+        .tokens(base, quote)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let env = MoniEnvConfig::new();
+    env.validate().map_err(|e| format!("Invalid database environment: {}", e))?;
+    let db = neon::connect(env).await.map_err(|e| format!("Failed to connect to the database: {}", e))?;
+    let instances = pull::instances_matching(&db, &prefix).await.map_err(|e| e.to_string())?;
+    if instances.is_empty() {
+        return Err(format!("No instance matching '{}'", prefix));
+    }
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let (from, to) = (time(cli.from.as_deref(), 0)?, time(cli.to.as_deref(), now)?);
+    let ids = instances.iter().map(|instance| instance.id.clone()).collect::<Vec<String>>();
+    // Read and replayed by pages, the initial quote balance priced on the first replayable row
+    let mut mk = Some(mk);
+    let mut bt: Option<Backtest> = None;
+    let (mut after, mut replayed, mut skipped) = (None, 0, 0);
+    loop {
+        let prices = pull::prices_between(&db, &ids, from, to, after.take(), BACKTEST_PAGE_ROWS).await.map_err(|e| e.to_string())?;
+        let full = prices.len() as u64 == BACKTEST_PAGE_ROWS;
+        after = prices.last().map(|price| (price.created_at, price.id.clone()));
+        let (rows, page_skipped) = backtest::rows(prices);
+        skipped += page_skipped;
+        for (timestamp_ms, msg) in rows.iter() {
+            let bt = match bt.as_mut() {
+                Some(bt) => bt,
+                None => {
+                    let mk = mk.take().ok_or("Market maker already replayed")?;
+                    let quote_balance = cli.quote_balance.unwrap_or(cli.base_balance * msg.reference_price);
+                    bt.insert(Backtest::new(mk, cli.model(), cli.gas_price_gwei, cli.eth_usd, cli.base_balance, quote_balance))
+                }
+            };
+            bt.step(*timestamp_ms, msg).await?;
+            replayed += 1;
+        }
+        if !full {
+            break;
+        }
+    }
+    let Some(bt) = bt else {
+        return Err("No replayable price row in the range".to_string());
+    };
+    tracing::info!(
+        "🧪 Replayed {} price rows of {} instances matching '{}' ({} downsampled rows skipped)",
+        replayed,
+        ids.len(),
+        prefix,
+        skipped
+    );
+    bt.mk.audit.flush();
+    let decisions = audit::read(&decisions.to_string_lossy(), None, None).unwrap_or_default();
+    let report = bt.report(&prefix, skipped, &decisions);
+    report.write(out).map_err(|e| format!("Failed to write the report into {}: {}", out.display(), e))?;
+    tracing::info!(
+        "🧪 Blocks {} to {}: {} trades, PnL {:+.4} {} net of {:.2} $ of gas, report in {}",
+        report.first_block,
+        report.last_block,
+        report.trades,
+        report.pnl_quote,
+        bt.mk.quote.symbol,
+        report.gas_usd,
+        out.display()
+    );
+    for gate in report.gates.iter() {
+        tracing::info!("   {:<16} {:>8} ({:.1}%)", gate.gate, gate.count, gate.share_pct);
+    }
+    Ok(())
+}
+
+/// Application entry point. Replays the recorded prices of a config, exits non-zero on failure.
+#[tokio::main]
+async fn main() {
+    let cli = BacktestCli::parse();
+    let filter = match cli.log_level.as_deref() {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::from_default_env(),
+    };
+    shd::utils::misc::logging(filter, LogFormat::from_env(), "backtest");
+    dotenv::from_filename(&cli.secrets).ok();
+    if let Err(e) = run(cli).await {
+        tracing::error!("Backtest failed: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -5,60 +5,79 @@
 //! orchestrates the market making operations across different blockchain networks.
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use clap::Parser;
 use shd::error::{MarketMakerError, Result};
-use shd::types::config::MarketMakerConfig;
+use shd::types::{
+    cli::{MakerCli, MakerCommand},
+    config::MarketMakerConfig,
+};
+use shd::utils::constants::ALERT_FLUSH_TIMEOUT_MS;
+use shd::utils::evm::RpcPool;
 use shd::{
-    maker::{exec::ExecStrategyFactory, feed::PriceFeedFactory},
-    types::{builder::MarketMakerBuilder, config::EnvConfig, maker::MarketMaker, moni::NewInstanceMessage, tycho::TychoStreamState},
+    maker::{control::Control, exec::ExecStrategyFactory, feed::PriceFeedFactory, frozen::FrozenContext, health::HealthState, multi::MultiPairRunner, shutdown::Shutdown},
+    types::{
+        builder::MarketMakerBuilder,
+        config::{EnvConfig, LogFormat},
+        maker::MarketMaker,
+        moni::{AlertKind, ControlCommand, EndInstanceMessage, NewAlertMessage, NewInstanceMessage},
+        tycho::TychoStreamState,
+    },
 };
 use tokio::sync::RwLock;
-use tracing::Level;
 use tracing_subscriber::EnvFilter;
 use tycho_common::models::token::Token; // Changed from tycho_simulation::models in 0.181.3
 
 /// Handles allowance for base and quote tokens.
 ///
 /// If `infinite_approval` is enabled, approves `u128::MAX` for both base and quote
-/// tokens on the Tycho router.
+/// tokens on the Tycho router. With `permit2_approval`, on Permit2, which the signed permits draw from.
 async fn init_allowance(config: MarketMakerConfig, env: EnvConfig) {
-    tracing::info!("config.infinite_approval: {:?}", config.infinite_approval);
+    tracing::info!("config.infinite_approval: {:?} | config.permit2_approval: {:?}", config.infinite_approval, config.permit2_approval);
 
     // Skip allowance check if skip_approval is enabled
-    if !config.infinite_approval {
+    if !config.infinite_approval && !config.permit2_approval {
         tracing::info!("infinite_approval is false, skipping allowance check, and approving at each trade");
         return;
     }
 
-    let spender = config.tycho_router_address.clone();
-    // let spender = config.permit2_address.clone();
+    let spender = if config.permit2_approval {
+        config.permit2_address.clone()
+    } else {
+        config.tycho_router_address.clone()
+    };
 
     tracing::info!(
-        "Checking allowance for {} on Permit2 {} | For {} and {}",
+        "Checking allowance for {} on spender {} | For {} and {}",
         config.wallet_public_key.clone(),
         spender.clone(),
         config.base_token.clone(),
         config.quote_token.clone()
     );
 
-    // Allowance
-    let base_allowance = shd::utils::evm::allowance(config.rpc_url.clone(), config.wallet_public_key.clone(), spender.clone(), config.base_token_address.clone()).await;
-
-    let quote_allowance = shd::utils::evm::allowance(config.rpc_url.clone(), config.wallet_public_key.clone(), spender.clone(), config.quote_token_address.clone()).await;
-
-    match (base_allowance, quote_allowance) {
-        (Ok(base_allowance), Ok(quote_allowance)) => {
+    // Allowances of both tokens in one multicall
+    let tokens = vec![config.base_token_address.clone(), config.quote_token_address.clone()];
+    match shd::utils::evm::allowances(&config, spender.clone(), tokens).await.as_deref() {
+        Ok([base_allowance, quote_allowance]) => {
+            let (base_allowance, quote_allowance) = (*base_allowance, *quote_allowance);
             tracing::info!("Allowance: {:?} | {:?}", base_allowance, quote_allowance);
             // Check if allowance is enough (half max u128)
             let target = u128::MAX / 2;
             let amount = u128::MAX;
-            if base_allowance < target {
+            // The native token is sent as value, it has no allowance
+            let native = |address: &str| shd::utils::evm::is_native(address);
+            if native(&config.base_token_address) {
+                tracing::info!("Base token is native, no allowance needed");
+            } else if base_allowance < target {
                 tracing::warn!("Base allowance is not enough: {} < {}", base_allowance, target);
                 let _ = shd::utils::evm::approve(config.clone(), env.clone(), spender.clone(), config.base_token_address.clone(), amount).await;
             } else {
                 tracing::info!("Base allowance is enough: {} >= {}", base_allowance, target);
             }
-            if quote_allowance < target {
+            if native(&config.quote_token_address) {
+                tracing::info!("Quote token is native, no allowance needed");
+            } else if quote_allowance < target {
                 tracing::warn!("Quote allowance is not enough: {} < {}", quote_allowance, target);
                 let _ = shd::utils::evm::approve(config.clone(), env.clone(), spender.clone(), config.quote_token_address.clone(), amount).await;
             } else {
@@ -71,12 +90,39 @@ async fn init_allowance(config: MarketMakerConfig, env: EnvConfig) {
     }
 }
 
+/// Publishes the end of an instance once its loop has stopped, so the monitor closes it (`ended_at`).
+fn end(config: &MarketMakerConfig, identifier: String, control: &Control, shutdown: &Shutdown) {
+    let reason = if control.is_killed() {
+        "killed"
+    } else if shutdown.is_requested() {
+        "shutdown"
+    } else {
+        "stream failed"
+    };
+    tracing::info!("Market maker {} stopped ({})", identifier, reason);
+    if config.publish_events {
+        let _ = shd::data::r#pub::end(EndInstanceMessage {
+            identifier,
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Starts the health server (`/healthz`, `/readyz`) when HEALTH_PORT is set.
+async fn health(env: &EnvConfig, states: Vec<HealthState>) -> Result<()> {
+    if env.health_port == 0 {
+        return Ok(());
+    }
+    shd::maker::health::spawn(env.health_port, states).await.map(|_| ()).map_err(MarketMakerError::Config)
+}
+
 /// Main market maker runtime.
 ///
 /// Publishes instance start events if configured, initializes shared state cache,
 /// and runs the market maker. If a panic occurs, let it propagate - the process
 /// manager (Docker Compose) will handle restarts with proper resource cleanup.
-async fn run(mut mk: MarketMaker, identifier: String, config: MarketMakerConfig, env: EnvConfig, tokens: Vec<Token>) -> Result<()> {
+/// On SIGTERM, the execution in progress gets `shutdown_grace_period_ms` to complete before the instance is closed.
+async fn run(mut mk: MarketMaker, identifier: String, config: MarketMakerConfig, env: EnvConfig, tokens: Vec<Token>, shutdown: Shutdown) -> Result<()> {
     let commit = shd::utils::misc::commit().unwrap_or_default();
 
     // Publish instance start event if configured
@@ -96,54 +142,369 @@ async fn run(mut mk: MarketMaker, identifier: String, config: MarketMakerConfig,
         protosims: HashMap::new(),
         components: HashMap::new(),
         atks: tokens.clone(),
+        block: 0,
     }));
 
-    // Spawn heartbeat task
-    shd::utils::uptime::heartbeats(env.testing, env.heartbeat.clone()).await;
+    // Listen to operator commands (pause, resume, kill), and report the trading state in the heartbeat
+    shd::data::sub::control(vec![(identifier.clone(), mk.control.clone())]);
+    shd::utils::uptime::heartbeats(
+        env.testing,
+        env.heartbeat.clone(),
+        Duration::from_secs(env.heartbeat_interval_s),
+        Duration::from_millis(env.heartbeat_timeout_ms),
+        "maker",
+        vec![(mk.control.clone(), mk.health.clone())],
+    )
+    .await;
+    if config.publish_events {
+        shd::utils::uptime::statuses(vec![(identifier.clone(), mk.control.clone())]);
+    }
+    health(&env, vec![mk.health.clone()]).await?;
 
     // Run the market maker - panics will propagate and terminate the process,
     // allowing Docker Compose restart policy to handle recovery with proper cleanup
     let state = Arc::clone(&cache);
-    mk.run(state, env).await;
+    let grace = Duration::from_millis(config.shutdown_grace_period_ms);
+    shd::maker::shutdown::drain(mk.run(state, env), &shutdown, grace).await;
+    end(&config, identifier, &mk.control, &shutdown);
 
     Ok(())
 }
 
+/// Multi-pair runtime, one market maker per config path over a shared Tycho stream.
+///
+/// Same lifecycle as `run`, publishing one instance start and end event per pair. The grace period is the longest of the pairs.
+async fn run_multi(makers: Vec<MarketMaker>, env: EnvConfig, tokens: Vec<Token>, shutdown: Shutdown) -> Result<()> {
+    let commit = shd::utils::misc::commit().unwrap_or_default();
+    let configs = makers.iter().map(|mk| mk.config.clone()).collect::<Vec<MarketMakerConfig>>();
+    let runner = MultiPairRunner::new(makers).map_err(MarketMakerError::Config)?;
+
+    for (config, identifier) in configs.iter().zip(runner.identifiers()) {
+        if config.publish_events {
+            let _ = shd::data::r#pub::instance(NewInstanceMessage {
+                config: config.clone(),
+                identifier: identifier.clone(),
+                commit: commit.clone(),
+            });
+        }
+        tracing::info!("Starting market maker (id: {}) for network {}", identifier, config.network_name.as_str());
+    }
+    tracing::info!("♻️  MarketMaker program commit: {:?}", commit);
+
+    // Initialize shared state cache, fed once for all pairs
+    let cache = Arc::new(RwLock::new(TychoStreamState {
+        protosims: HashMap::new(),
+        components: HashMap::new(),
+        atks: tokens.clone(),
+        block: 0,
+    }));
+
+    let controls = runner.controls();
+    shd::data::sub::control(controls.clone());
+    shd::utils::uptime::heartbeats(
+        env.testing,
+        env.heartbeat.clone(),
+        Duration::from_secs(env.heartbeat_interval_s),
+        Duration::from_millis(env.heartbeat_timeout_ms),
+        "maker",
+        controls.iter().map(|(_, control)| control.clone()).zip(runner.healths()).collect(),
+    )
+    .await;
+    shd::utils::uptime::statuses(
+        configs
+            .iter()
+            .zip(controls.iter())
+            .filter(|(config, _)| config.publish_events)
+            .map(|(_, instance)| instance.clone())
+            .collect(),
+    );
+    health(&env, runner.healths()).await?;
+
+    let grace = Duration::from_millis(configs.iter().map(|c| c.shutdown_grace_period_ms).max().unwrap_or_default());
+    shd::maker::shutdown::drain(runner.run(Arc::clone(&cache), env), &shutdown, grace).await;
+    for (config, (identifier, control)) in configs.iter().zip(controls) {
+        end(config, identifier, &control, &shutdown);
+    }
+
+    Ok(())
+}
+
+/// Builds a market maker for one pair config: validates its tokens, creates its strategies and checks allowances.
+///
+/// With a frozen context, the market maker reads it instead of the network and runs the dry run strategy.
+async fn build(config: MarketMakerConfig, env: EnvConfig, tokens: &[Token], frozen: Option<&FrozenContext>) -> Result<MarketMaker> {
+    // Validate base and quote tokens exist in the token list
+    let base = tokens
+        .iter()
+        .find(|t| t.address.to_string() == config.base_token_address.to_lowercase())
+        .ok_or_else(|| MarketMakerError::TokenNotFound(format!("Base token not found: {}", config.base_token_address)))?;
+
+    let quote = tokens
+        .iter()
+        .find(|t| t.address.to_string() == config.quote_token_address.to_lowercase())
+        .ok_or_else(|| MarketMakerError::TokenNotFound(format!("Quote token not found: {}", config.quote_token_address)))?;
+
+    tracing::info!("Base token: {} | Quote token: {}", base.symbol, quote.symbol);
+
+    // Cross-check the Tycho decimals and symbols against the chain, wrong decimals would scale every amount
+    for token in [base, quote] {
+        if shd::utils::evm::is_native(&token.address.to_string()) {
+            tracing::info!("🪙 {} is the native token, no ERC20 to verify", token.symbol);
+            continue;
+        }
+        let metadata = shd::utils::evm::erc20_metadata(&config, &token.address.to_string()).await.map_err(MarketMakerError::Network)?;
+        if let Err(e) = metadata.matches(token) {
+            tracing::error!("🪙 {}", e);
+            return Err(MarketMakerError::TokenNotFound(e));
+        }
+        tracing::info!("🪙 {} verified on-chain: {} decimals", metadata.symbol, metadata.decimals);
+    }
+
+    // Create dynamic components based on configuration
+    let feed = PriceFeedFactory::create(config.price_feed_config.r#type.as_str());
+    let execution = ExecStrategyFactory::from_config(&config);
+
+    // Build market maker instance with all components
+    let mut mk = MarketMakerBuilder::new()
+        .config(config.clone())
+        .feed(feed)
+        .execution(execution)
+        .tokens(base.clone(), quote.clone())
+        .build()?;
+    if let Some(frozen) = frozen {
+        mk.freeze(frozen.clone());
+    }
+
+    // Initialize allowance for base and quote tokens, if infinite_approval is true, we approve u128::MAX for both base and quote tokens
+    if config.dry_run {
+        tracing::info!("🧪 Dry run, skipping allowance check");
+    } else {
+        init_allowance(config.clone(), env.clone()).await;
+    }
+
+    // Fetch initial market price for validation
+    if let Ok(price) = mk.market_data.market_price(&mk).await {
+        tracing::info!("First market price: {:?} ({})", price, config.price_feed_config.r#type);
+    } else {
+        tracing::error!("Failed to fetch the first market price");
+    }
+
+    Ok(mk)
+}
+
+/// Loads, validates and prints the configurations with the flags applied, for `--print-config`.
+///
+/// Needs no secrets, so CI can check the config files. Returns false if a config is invalid.
+fn print_config(cli: &MakerCli) -> bool {
+    match cli.configs() {
+        Ok(configs) => {
+            for config in configs.iter() {
+                match serde_json::to_string_pretty(config) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => {
+                        eprintln!("Failed to serialize config: {}", e);
+                        return false;
+                    }
+                }
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("Invalid config: {}", e);
+            false
+        }
+    }
+}
+
+/// Prints the decisions of an audit file within a time range, for `maker audit`. Returns false on error.
+fn audit(file: &str, from: Option<&str>, to: Option<&str>, pool: Option<&str>) -> bool {
+    let range = (from.map(shd::maker::audit::parse_time).transpose(), to.map(shd::maker::audit::parse_time).transpose());
+    let (from, to) = match range {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    match shd::maker::audit::read(file, from, to) {
+        Ok(decisions) => {
+            for decision in decisions.iter().filter(|decision| pool.is_none_or(|pool| decision.pool.eq_ignore_ascii_case(pool))) {
+                println!("{}", shd::maker::audit::describe(decision));
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            false
+        }
+    }
+}
+
+/// Quotes a swap on the pools selected by `pool`, for `maker quote`. Returns true if the best one would trade.
+async fn quote(config: MarketMakerConfig, env: &EnvConfig, pool: &str, amount: f64, sell_quote: bool) -> bool {
+    match shd::maker::quote::run(config, env, pool, amount, !sell_quote).await {
+        Ok(quotes) => {
+            for (x, quote) in quotes.iter().enumerate() {
+                if x > 0 {
+                    println!();
+                }
+                println!("{}", quote);
+            }
+            quotes[0].clears()
+        }
+        Err(e) => {
+            eprintln!("Quote failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Prints the valued wallet inventory, every `watch` seconds if set, for `maker inventory`. Returns false on error.
+async fn inventory(config: MarketMakerConfig, env: &EnvConfig, watch: Option<u64>, json: bool) -> bool {
+    let print = |report: &shd::maker::valuation::InventoryReport| {
+        if json {
+            match serde_json::to_string(report) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("Failed to serialize the inventory: {}", e),
+            }
+        } else {
+            println!("{}\n", report);
+        }
+    };
+    match shd::maker::valuation::watch(config, env, watch.map(Duration::from_secs), print).await {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Inventory failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Sets an allowance from the signer wallet and prints the allowance read back, for `maker approve`. Returns false on error.
+#[allow(clippy::too_many_arguments)]
+async fn approve(config: MarketMakerConfig, env: &EnvConfig, token: &str, spender: Option<&str>, amount: Option<f64>, max: bool, revoke: bool, force: bool) -> bool {
+    match shd::maker::approval::run(&config, env, token, spender, amount, max, revoke, force).await {
+        Ok(approval) => {
+            println!("{}", approval);
+            approval.status
+        }
+        Err(e) => {
+            eprintln!("Approval failed: {}", e);
+            false
+        }
+    }
+}
+
 /// Initializes and configures the market maker application.
 ///
 /// Sets up logging, loads configuration from TOML and environment files,
 /// fetches tokens from Tycho API, validates base/quote tokens, creates
 /// price feed and execution strategy, then builds and starts the market maker.
-async fn initialize() -> Result<()> {
-    // Initialize logging with environment-based configuration
-    let filter = EnvFilter::from_default_env();
-    tracing_subscriber::fmt().with_max_level(Level::TRACE).with_env_filter(filter).init();
-
-    // Load secrets from environment-specific file
-    let secrets = match std::env::var("SECRET_PATH") {
-        Ok(path) => path,
-        Err(_) => {
-            tracing::error!("SECRET_PATH environment variable is required");
-            std::process::exit(1);
-        }
+async fn initialize(cli: MakerCli) -> Result<()> {
+    // Initialize logging, from --log-level or RUST_LOG, as JSON with LOG_FORMAT=json (process env, read before the secrets)
+    let filter = match cli.log_level.as_deref() {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::from_default_env(),
+    };
+    shd::utils::misc::logging(filter, LogFormat::from_env(), "maker");
+
+    // Load secrets from environment-specific file (--secrets or SECRET_PATH)
+    let Some(secrets) = cli.secrets.clone() else {
+        tracing::error!("--secrets or the SECRET_PATH environment variable is required");
+        std::process::exit(1);
     };
     tracing::info!("Loading secrets from: {}", secrets);
 
     // Load environment variables and validate configuration
     dotenv::from_filename(secrets).ok();
-    let env = EnvConfig::new();
+    let env = if cli.config.is_some() { EnvConfig::from_paths(cli.paths()) } else { EnvConfig::new() };
     env.print();
-
-    // Load market maker configuration from TOML file
-    tracing::info!("MarketMaker Config Path: '{}'", env.path);
-    let config = match shd::types::config::load_market_maker_config(env.path.as_str()) {
-        Ok(config) => config,
-        Err(e) => return Err(MarketMakerError::Config(format!("Failed to load config: {}", e))),
+    shd::data::publisher::configure(env.events_transport);
+    shd::data::keys::configure(env.redis_legacy_keys);
+    shd::utils::alert::configure(shd::utils::alert::from_env(&env));
+
+    // Load market maker configurations from TOML files, one per pair (--config or CONFIG_PATHS), or the single CONFIG_PATH
+    let paths = if env.paths.is_empty() { vec![env.path.clone()] } else { env.paths.clone() };
+    let mut configs = vec![];
+    for path in paths.iter() {
+        tracing::info!("MarketMaker Config Path: '{}'", path);
+        let mut config = match shd::types::config::load_market_maker_config(path.as_str()) {
+            Ok(config) => config,
+            Err(e) => return Err(MarketMakerError::Config(format!("Failed to load config {}: {}", path, e))),
+        };
+        if let Err(e) = cli.apply(&mut config) {
+            return Err(MarketMakerError::Config(format!("Invalid config {} with the command line flags: {}", path, e)));
+        }
+        config.print();
+        shd::utils::evm::register_errors(&config.revert_errors);
+        tracing::debug!("🤖 MarketMaker Config Identifier: '{}'", config.id());
+        configs.push(config);
+    }
+    let config = configs[0].clone();
+    let frozen = match cli.frozen_context.as_deref().map(FrozenContext::load).transpose() {
+        Ok(frozen) => frozen,
+        Err(e) => return Err(MarketMakerError::Config(e)),
+    };
+    if cli.preflight {
+        let mut passed = true;
+        for config in configs.iter() {
+            let report = shd::maker::preflight::preflight(config, &env).await;
+            println!("{}", report);
+            if !report.passed() {
+                shd::utils::alert::notify(NewAlertMessage {
+                    identifier: config.id(),
+                    kind: AlertKind::PreflightFailed,
+                    message: report.to_string(),
+                    pnl: None,
+                });
+            }
+            passed &= report.passed();
+        }
+        shd::utils::alert::flush(Duration::from_millis(ALERT_FLUSH_TIMEOUT_MS)).await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    if let Some(MakerCommand::Quote { pool, amount, sell_quote }) = cli.command.as_ref() {
+        std::process::exit(if quote(config, &env, pool, *amount, *sell_quote).await { 0 } else { 1 });
+    }
+    if let Some(MakerCommand::Inventory { watch, json }) = cli.command.as_ref() {
+        std::process::exit(if inventory(config, &env, *watch, *json).await { 0 } else { 1 });
+    }
+    if let Err(e) = env.validate() {
+        return Err(MarketMakerError::Config(format!("Invalid environment: {}", e)));
+    }
+    // The signer must hold the key of the wallet the configs trade from (decrypts the keystore once)
+    let wallet = match shd::utils::signer::WalletSigner::from_env(&env) {
+        Ok(wallet) => wallet.address().to_string(),
+        Err(e) => return Err(MarketMakerError::Config(format!("Failed to load the {} signer: {}", env.signer_type.as_str(), e))),
     };
-    config.print();
-    tracing::debug!("🤖 MarketMaker Config Identifier: '{}'", config.id());
+    if let Some(config) = configs.iter().find(|c| !c.wallet_public_key.eq_ignore_ascii_case(&wallet)) {
+        return Err(MarketMakerError::Config(format!(
+            "Signer address {} differs from wallet_public_key {} of {}",
+            wallet,
+            config.wallet_public_key,
+            config.id()
+        )));
+    }
+    if let Some(MakerCommand::Approve {
+        token,
+        spender,
+        amount,
+        max,
+        revoke,
+        force,
+    }) = cli.command.as_ref()
+    {
+        std::process::exit(if approve(config, &env, token, spender.as_deref(), *amount, *max, *revoke, *force).await {
+            0
+        } else {
+            1
+        });
+    }
+    if config.dry_run {
+        tracing::warn!("🧪 Dry run: trades are simulated, never broadcast");
+    }
 
-    if config.publish_events {
+    if configs.iter().any(|c| c.publish_events) {
         tracing::info!("📕  PublishEvent mode enabled. Publishing ping event to make sure Redis and Monitor are running");
 
         const MAX_RETRIES: u32 = 5;
@@ -175,56 +536,108 @@ async fn initialize() -> Result<()> {
         }
     }
 
+    // Make sure each RPC serves the chain of its config, transactions are signed for chain_id
+    for config in configs.iter() {
+        for url in config.rpc_endpoints() {
+            match shd::utils::evm::chain_id(url.clone()).await {
+                Ok(id) if id == config.chain_id => tracing::info!("RPC {} serves chain {} ({})", url, id, config.network_name),
+                Ok(id) => {
+                    return Err(MarketMakerError::Config(format!(
+                        "RPC {} serves chain {}, but the config targets {} with chain_id {}",
+                        url, id, config.network_name, config.chain_id
+                    )))
+                }
+                Err(e) => return Err(MarketMakerError::Config(format!("Failed to read the chain id of RPC {}: {}", url, e))),
+            }
+        }
+    }
+
     // Validate network connectivity and get latest block
-    let latest = shd::utils::evm::latest(config.rpc_url.clone()).await;
+    let latest = shd::utils::evm::latest(&RpcPool::of(&config)).await;
     tracing::info!("Launching Tycho Market Maker | 🧪 Testing mode: {:?} | Latest block: {}", env.testing, latest);
 
-    // Fetch available tokens from Tycho API
-    let tokens = shd::maker::tycho::tokens(config.clone(), Some(env.tycho_api_key.as_str()))
-        .await
-        .ok_or_else(|| MarketMakerError::Config("Failed to fetch tokens from Tycho API".into()))?;
-
-    // Validate base and quote tokens exist in the token list
-    let base = tokens
-        .iter()
-        .find(|t| t.address.to_string() == config.base_token_address.to_lowercase())
-        .ok_or_else(|| MarketMakerError::TokenNotFound(format!("Base token not found: {}", config.base_token_address)))?;
-
-    let quote = tokens
-        .iter()
-        .find(|t| t.address.to_string() == config.quote_token_address.to_lowercase())
-        .ok_or_else(|| MarketMakerError::TokenNotFound(format!("Quote token not found: {}", config.quote_token_address)))?;
-
-    tracing::info!("Base token: {} | Quote token: {}", base.symbol, quote.symbol);
+    // Available tokens, from the token cache when fresh enough, the base and quote tokens always from the Tycho API
+    let addresses = configs.iter().flat_map(|c| [c.base_token_address.clone(), c.quote_token_address.clone()]).collect::<Vec<String>>();
+    let tokens = shd::maker::universe::universe(config.clone(), Some(env.tycho_api_key.as_str()), addresses).await?;
 
-    // Create dynamic components based on configuration
-    let feed = PriceFeedFactory::create(config.price_feed_config.r#type.as_str());
-    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    let mut makers = vec![];
+    for config in configs {
+        makers.push(build(config, env.clone(), &tokens, frozen.as_ref()).await?);
+    }
 
-    // Build market maker instance with all components
-    let _mk = MarketMakerBuilder::create(config.clone(), feed, execution, base.clone(), quote.clone()).map_err(|e| MarketMakerError::Config(format!("Failed to build Market Maker: {}", e)))?;
+    // Stop between blocks on SIGTERM (docker stop) or Ctrl-C, instead of dying mid-broadcast
+    let shutdown = Shutdown::default();
+    shutdown.listen();
+    for mk in makers.iter_mut() {
+        mk.shutdown = shutdown.clone();
+    }
 
-    // Initialize allowance for base and quote tokens, if infinite_approval is true, we approve u128::MAX for both base and quote tokens
-    let _ = init_allowance(config.clone(), env.clone()).await;
+    // Panics are recorded and published before the restart, and a crash loop starts paused
+    let identifiers = makers.iter().map(|mk| mk.identifier.clone()).collect::<Vec<String>>();
+    shd::utils::crash::install(identifiers, makers.iter().any(|mk| mk.config.publish_events));
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let crashes = shd::utils::crash::recent(&shd::utils::crash::path(), now, 3_600_000);
+    for mk in makers.iter() {
+        if shd::utils::crash::exceeded(crashes, mk.config.max_crashes_per_hour) {
+            mk.control.apply(ControlCommand::Pause);
+            let message = format!(
+                "Started paused after {} crashes in the last hour (max_crashes_per_hour = {}), resume on the control channel",
+                crashes, mk.config.max_crashes_per_hour
+            );
+            tracing::warn!("💥 {}: {}", mk.identifier, message);
+            let alert = NewAlertMessage {
+                identifier: mk.identifier.clone(),
+                kind: AlertKind::Crash,
+                message,
+                pnl: None,
+            };
+            if mk.config.publish_events {
+                let _ = shd::data::r#pub::alert(alert.clone());
+            }
+            shd::utils::alert::notify(alert);
+        }
+    }
 
-    // Fetch initial market price for validation
-    if let Ok(price) = _mk.fetch_market_price().await {
-        tracing::info!("First market price: {:?} ({})", price, config.price_feed_config.r#type);
+    if makers.len() == 1 {
+        let mk = makers.remove(0);
+        let identifier = mk.identifier.clone();
+        let _ = run(mk, identifier, config, env, tokens, shutdown).await;
     } else {
-        tracing::error!("Failed to fetch the first market price");
+        let _ = run_multi(makers, env, tokens, shutdown).await;
     }
 
-    let identifier = _mk.identifier.clone();
-    let _ = run(_mk, identifier, config, env, tokens).await;
-
     Ok(())
 }
 
-/// Application entry point. Initializes and runs the market maker.
+/// Application entry point. Initializes and runs the market maker, or prints its configuration or its schema.
 #[tokio::main]
 async fn main() {
-    if let Err(e) = initialize().await {
+    let cli = MakerCli::parse();
+    if cli.schema {
+        match serde_json::to_string_pretty(&shd::types::config::config_schema()) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize the config schema: {}", e);
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+    if cli.print_config {
+        std::process::exit(if print_config(&cli) { 0 } else { 1 });
+    }
+    if let Some(MakerCommand::Audit { file, from, to, pool }) = cli.command.as_ref() {
+        std::process::exit(if audit(file, from.as_deref(), to.as_deref(), pool.as_deref()) { 0 } else { 1 });
+    }
+    // Only a panic reaching the main future is a crash, those caught on the way are logged only
+    if let Err(e) = shd::utils::crash::guard(initialize(cli)).await {
         tracing::error!("Market maker failed to start: {}", e);
         std::process::exit(1);
     }
+    // Deliver the queued events (end of instance included), alerts and spans before exiting
+    shd::data::publisher::flush(std::time::Duration::from_millis(shd::utils::constants::PUBLISH_FLUSH_TIMEOUT_MS)).await;
+    shd::utils::alert::flush(Duration::from_millis(ALERT_FLUSH_TIMEOUT_MS)).await;
+    shd::utils::otel::shutdown();
+    // The control listener blocks on its Redis subscription, exit without waiting for it
+    std::process::exit(0);
 }
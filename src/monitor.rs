@@ -2,9 +2,15 @@
 //!
 //! Standalone monitoring service that listens to market maker events and stores them
 //! in the database for analysis and tracking. Connects to Neon PostgreSQL, listens
-//! to Redis pub/sub for market maker events, and provides real-time performance monitoring.
-use shd::{types::config::MoniEnvConfig, utils::constants::CHANNEL_REDIS};
-use tracing::Level;
+//! to Redis pub/sub (or streams, with EVENTS_TRANSPORT=streams) for market maker events, and provides real-time performance monitoring.
+use clap::Parser;
+use shd::{
+    data::keys,
+    types::{
+        cli::{MonitorCli, MonitorCommand},
+        config::{EventsTransport, LogFormat, MoniEnvConfig},
+    },
+};
 use tracing_subscriber::EnvFilter;
 
 /// Main entry point for the monitoring service.
@@ -13,14 +19,26 @@ use tracing_subscriber::EnvFilter;
 /// and starts listening to Redis pub/sub for market maker events.
 #[tokio::main]
 async fn main() {
-    // Initialize logging with environment-based configuration
-    let filter = EnvFilter::from_default_env();
-    tracing_subscriber::fmt().with_max_level(Level::TRACE).with_env_filter(filter).init();
+    let cli = MonitorCli::parse();
+
+    // Initialize logging, from --log-level or RUST_LOG, as JSON with LOG_FORMAT=json
+    let filter = match cli.log_level.as_deref() {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::from_default_env(),
+    };
+    shd::utils::misc::logging(filter, LogFormat::from_env(), "monitor");
 
     // Load monitor-specific environment configuration
-    dotenv::from_filename("config/secrets/.env.monitor.global").ok();
+    dotenv::from_filename(&cli.secrets).ok();
     let env = MoniEnvConfig::new();
     env.print();
+    if let Err(e) = env.validate() {
+        tracing::error!("Invalid monitor environment: {}", e);
+        return;
+    }
+
+    // Compatibility mode: also reads the pre-v1 channel and streams
+    shd::data::keys::configure(env.redis_legacy_keys);
 
     // Log current commit for debugging
     let commit = shd::utils::misc::commit();
@@ -29,14 +47,43 @@ async fn main() {
     tracing::info!("Launching MM monitoring program | 🧪 Testing mode: {:?}", env.testing);
 
     // Initialize and test database connection
-    tracing::info!("🐘 Init and test connection to Neon, Prisma, SeaORM, to PgSQL");
+    tracing::info!("🐘 Init and test connection to the {} database (SeaORM)", env.database_backend.as_str());
 
     // Establish database connection with error handling
-    let Ok(db) = shd::data::neon::connect(env.clone()).await else {
+    // One pooled connection for the whole process, health-checked and reconnected by its supervisor
+    let Ok(neon) = shd::data::neon::Neon::connect(env.clone()).await else {
         tracing::error!("Failed to connect to Neon database");
         return;
     };
 
+    // Create the missing tables, on a fresh Postgres or SQLite database
+    if let Err(err) = shd::data::migration::migrate(&neon.db().await).await {
+        tracing::error!("Failed to migrate the database: {}", err);
+        return;
+    }
+
+    // `monitor retry`: replay the dead letters and exit
+    if let Some(MonitorCommand::Retry { limit }) = cli.command {
+        match shd::data::dead_letter::retry(&neon.db().await, limit).await {
+            Ok(report) => {
+                tracing::info!("☠️  Dead letters replayed: {} stored, {} still failing", report.replayed, report.failed);
+                std::process::exit(if report.failed == 0 { 0 } else { 1 });
+            }
+            Err(err) => {
+                tracing::error!("Failed to replay the dead letters: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    neon.supervise();
+    // Downsample then purge old price rows, hourly
+    shd::data::retention::schedule(neon.clone(), env.clone());
+    // Fetch the receipts of the trades stored before they were mined
+    shd::data::backfill::schedule(neon.clone(), env.clone());
+    // Report the dead-letter counter, for alerting
+    shd::data::dead_letter::report();
+    let db = neon.db().await;
+
     tracing::info!("🐘 Neon connected");
 
     // Validate database connectivity by fetching configurations
@@ -51,11 +98,28 @@ async fn main() {
     }
 
     // Spawn heartbeat task
-    shd::utils::uptime::heartbeats(env.testing, env.heartbeat.clone()).await;
+    shd::utils::uptime::heartbeats(
+        env.testing,
+        env.heartbeat.clone(),
+        std::time::Duration::from_secs(env.heartbeat_interval_s),
+        std::time::Duration::from_millis(env.heartbeat_timeout_ms),
+        "monitor",
+        vec![],
+    )
+    .await;
 
-    // Start listening to Redis pub/sub channel for market maker events
-    tracing::info!("🐘 Starting infinite listening of the Redis pub-sub channel: {}, for MM events", CHANNEL_REDIS);
-    shd::data::sub::listen(env.clone()).await;
+    match env.events_transport {
+        EventsTransport::PubSub => {
+            // Start listening to Redis pub/sub channel for market maker events
+            tracing::info!("🐘 Starting infinite listening of the Redis pub-sub channels: {}, for MM events", keys::channel_events_pattern());
+            shd::data::sub::listen(neon.clone()).await;
+        }
+        EventsTransport::Streams => {
+            // Consume the event streams, acknowledging each entry once stored
+            tracing::info!("🐘 Starting infinite consumption of the Redis streams {}:stream:*, for MM events", keys::prefix());
+            shd::data::sub::consume(neon.clone()).await;
+        }
+    }
 
     tracing::info!("Monitoring program finished");
 }
@@ -0,0 +1,93 @@
+//! Receipt Backfill Module
+//!
+//! `neon::handle` looks the receipt of a trade up when it is received, but a transaction mined a few blocks
+//! later (e.g. a Flashbots bundle) has none yet, and the trade used to be stored without it forever.
+//! The backfill job periodically fetches the receipt of every broadcast trade still missing one, against the
+//! RPC of its instance config, and updates the row. Past `trade_receipt_max_age_minutes` a trade still without
+//! receipt is marked `dropped` in its broadcast data, so it is no longer retried.
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait};
+
+use crate::{
+    data::neon::{pull, receipt_data, update, Neon},
+    entity::instance,
+    types::config::{MarketMakerConfig, MoniEnvConfig},
+    utils::{
+        constants::RECEIPT_BACKFILL_INTERVAL_SECS,
+        evm::{fetch_receipt, RpcPool},
+    },
+};
+
+/// Trades handled by one run.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BackfillReport {
+    pub filled: usize,  // Receipt found and stored
+    pub dropped: usize, // Too old, marked dropped
+    pub pending: usize, // Still missing, retried next run
+}
+
+/// Fetches the missing receipts, marking dropped the trades older than the max age still without one.
+pub async fn backfill(db: &DatabaseConnection, env: &MoniEnvConfig, now: NaiveDateTime) -> Result<BackfillReport, DbErr> {
+    let mut report = BackfillReport::default();
+    let horizon = now - Duration::minutes(env.trade_receipt_max_age_minutes as i64);
+    let mut configs: HashMap<String, Option<MarketMakerConfig>> = HashMap::new();
+    for (trade, mut msg) in pull::trades_missing_receipt(db).await? {
+        let config = match configs.get(&trade.instance_id) {
+            Some(config) => config.clone(),
+            None => {
+                let config = instance::Entity::find_by_id(trade.instance_id.clone())
+                    .one(db)
+                    .await?
+                    .and_then(|instance| serde_json::from_value::<MarketMakerConfig>(instance.config).ok());
+                configs.insert(trade.instance_id.clone(), config.clone());
+                config
+            }
+        };
+        let Some(broadcast) = msg.data.broadcast.as_mut() else {
+            continue;
+        };
+        let receipt = match config.as_ref() {
+            Some(config) => fetch_receipt(&RpcPool::of(config), broadcast.hash.clone()).await,
+            None => Err(format!("No configuration for instance {}", trade.instance_id)),
+        };
+        match receipt {
+            Ok(receipt) => {
+                broadcast.receipt = Some(receipt_data(&receipt));
+                if let Some(config) = config.as_ref() {
+                    msg.data.realize(&config.wallet_public_key);
+                }
+                report.filled += 1;
+            }
+            Err(_) if trade.created_at < horizon => {
+                tracing::warn!("🧾 No receipt for {} after {} min, marked dropped", broadcast.hash, env.trade_receipt_max_age_minutes);
+                broadcast.dropped = true;
+                report.dropped += 1;
+            }
+            Err(_) => {
+                report.pending += 1;
+                continue;
+            }
+        }
+        update::trade(db, &trade, &msg).await?;
+    }
+    Ok(report)
+}
+
+/// Spawns the backfill job, every `RECEIPT_BACKFILL_INTERVAL_SECS`.
+pub fn schedule(neon: Neon, env: MoniEnvConfig) {
+    tokio::spawn(async move {
+        loop {
+            match backfill(&neon.db().await, &env, chrono::Utc::now().naive_utc()).await {
+                Ok(report) if report == BackfillReport::default() => {}
+                Ok(report) => tracing::info!("🧾 Receipt backfill: {} filled, {} marked dropped, {} still pending", report.filled, report.dropped, report.pending),
+                Err(err) => {
+                    tracing::error!("🧾 Receipt backfill failed: {}", err);
+                    neon.failed();
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(RECEIPT_BACKFILL_INTERVAL_SECS)).await;
+        }
+    });
+}
@@ -0,0 +1,128 @@
+//! Dead Letter Module
+//!
+//! A message the monitor cannot store used to be logged and lost. `deliver` retries a failed insert
+//! `DEAD_LETTER_MAX_ATTEMPTS` times with exponential backoff, then writes the raw payload and the error
+//! to the `DeadLetter` table, as it does right away for unparseable or unknown messages.
+//! `monitor retry` replays the table through `neon::handle`, marking each row resolved once stored.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sea_orm::{prelude::Uuid, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+
+use crate::{
+    data::{
+        neon::{handle, Neon},
+        sub::parse,
+    },
+    entity::dead_letter,
+    types::moni::ParsedMessage,
+    utils::constants::{DEAD_LETTER_BACKOFF_MS, DEAD_LETTER_MAX_ATTEMPTS, DEAD_LETTER_REPORT_INTERVAL_SECS},
+};
+
+/// Messages dead-lettered by this process, for alerting.
+static DEAD_LETTERED: AtomicU64 = AtomicU64::new(0);
+
+/// Messages dead-lettered since the monitor started.
+pub fn dead_lettered() -> u64 {
+    DEAD_LETTERED.load(Ordering::Relaxed)
+}
+
+/// Spawns the report of the dead-letter counter, every `DEAD_LETTER_REPORT_INTERVAL_SECS`, logged with the
+/// `dead_lettered` metric so that an alert can follow its rate even when no message fails.
+pub fn report() {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(DEAD_LETTER_REPORT_INTERVAL_SECS));
+        loop {
+            tick.tick().await;
+            let total = dead_lettered();
+            tracing::info!(metric = "dead_lettered", total, "☠️  {} messages dead-lettered since start", total);
+        }
+    });
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetryReport {
+    pub replayed: usize, // Stored, marked resolved
+    pub failed: usize,   // Still failing, kept with the new error
+}
+
+/// Writes a message the monitor could not store to the dead-letter table.
+pub async fn record(db: &DatabaseConnection, payload: &str, error: &str, attempts: i32) -> Result<(), DbErr> {
+    let now = chrono::Utc::now().naive_utc();
+    dead_letter::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        payload: Set(payload.to_string()),
+        error: Set(error.to_string()),
+        attempts: Set(attempts),
+        resolved_at: Set(None),
+    }
+    .insert(db)
+    .await?;
+    let total = DEAD_LETTERED.fetch_add(1, Ordering::Relaxed) + 1;
+    tracing::warn!(metric = "message_dead_lettered", total, "☠️  Message dead-lettered ({} since start): {}", total, error);
+    Ok(())
+}
+
+/// Handles a received payload, retrying database failures with backoff before dead-lettering it.
+///
+/// Returns an error only when the message could be neither stored nor dead-lettered, i.e. the database is down,
+/// so a stream entry stays pending.
+pub async fn deliver(neon: &Neon, payload: &str) -> Result<(), String> {
+    let message = match parse(payload) {
+        Ok(ParsedMessage::Unknown(data)) => return record(&neon.db().await, payload, &format!("Unknown message type: {}", data), 0).await.map_err(|e| e.to_string()),
+        Ok(message) => message,
+        Err(e) => return record(&neon.db().await, payload, &e, 0).await.map_err(|e| e.to_string()),
+    };
+    let mut backoff = DEAD_LETTER_BACKOFF_MS;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let Err(e) = handle(&message, &neon.db().await).await else {
+            return Ok(());
+        };
+        neon.failed();
+        if attempts >= DEAD_LETTER_MAX_ATTEMPTS {
+            return record(&neon.db().await, payload, &e, attempts).await.map_err(|e| e.to_string());
+        }
+        tracing::warn!("Failed to store message (attempt {}/{}): {}. Retrying in {} ms", attempts, DEAD_LETTER_MAX_ATTEMPTS, e, backoff);
+        tokio::time::sleep(std::time::Duration::from_millis(backoff)).await;
+        backoff *= 2;
+    }
+}
+
+/// Replays the unresolved dead letters through the handler, oldest first.
+pub async fn retry(db: &DatabaseConnection, limit: Option<u64>) -> Result<RetryReport, DbErr> {
+    let letters = dead_letter::Entity::find()
+        .filter(dead_letter::Column::ResolvedAt.is_null())
+        .order_by_asc(dead_letter::Column::CreatedAt)
+        .limit(limit)
+        .all(db)
+        .await?;
+    let mut report = RetryReport::default();
+    for letter in letters {
+        let outcome = match parse(&letter.payload) {
+            Ok(ParsedMessage::Unknown(data)) => Err(format!("Unknown message type: {}", data)),
+            Ok(message) => handle(&message, db).await,
+            Err(e) => Err(e),
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let attempts = letter.attempts + 1;
+        let mut row: dead_letter::ActiveModel = letter.into();
+        row.updated_at = Set(now);
+        row.attempts = Set(attempts);
+        match outcome {
+            Ok(_) => {
+                row.resolved_at = Set(Some(now));
+                report.replayed += 1;
+            }
+            Err(e) => {
+                row.error = Set(e);
+                report.failed += 1;
+            }
+        }
+        row.update(db).await?;
+    }
+    Ok(report)
+}
@@ -7,11 +7,12 @@ use tokio::time::sleep;
 use redis::{
     aio::MultiplexedConnection,
     from_redis_value,
-    streams::{StreamRangeReply, StreamReadOptions, StreamReadReply},
+    streams::{StreamClaimReply, StreamMaxlen, StreamPendingCountReply, StreamRangeReply, StreamReadOptions, StreamReadReply},
     AsyncCommands, Client, RedisError,
 };
 
 use crate::types::misc::StreamState;
+use crate::utils::constants::{STREAM_PAYLOAD_FIELD, STREAM_READ_COUNT};
 
 /// Tests Redis connection by sending a PING command.
 pub async fn ping() {
@@ -148,6 +149,28 @@ pub async fn set<T: Serialize>(key: &str, data: T) {
     }
 }
 
+/// Stores a JSON-serialized object in Redis, expiring after `ttl` seconds.
+pub async fn set_ex<T: Serialize>(key: &str, data: T, ttl: u64) {
+    let data = match serde_json::to_string(&data) {
+        Ok(data) => data,
+        Err(err) => {
+            tracing::error!("📕 Failed to serialize JSON object: {}", err);
+            return;
+        }
+    };
+    match connect().await {
+        Ok(mut co) => {
+            let result: redis::RedisResult<()> = redis::cmd("SET").arg(key).arg(data).arg("EX").arg(ttl).query_async(&mut co).await;
+            if let Err(err) = result {
+                tracing::error!("📕 Failed to set value for key '{}': {}", key, err);
+            }
+        }
+        Err(e) => {
+            tracing::error!("📕 Redis connection error: {}", e);
+        }
+    }
+}
+
 /// Retrieves and deserializes a JSON object from Redis.
 pub async fn get<T: Serialize + DeserializeOwned>(key: &str) -> Option<T> {
     let time = std::time::SystemTime::now();
@@ -181,3 +204,87 @@ pub async fn get<T: Serialize + DeserializeOwned>(key: &str) -> Option<T> {
         }
     }
 }
+
+/// Entry of a Redis stream.
+#[derive(Debug, Clone)]
+pub struct StreamEntry {
+    pub key: String,
+    pub id: String,
+    pub payload: Option<String>, // None when the entry has no payload field
+}
+
+/// Position of a stream entry id ("<ms>-<seq>"), to order entries read from several streams.
+pub fn stream_id_order(id: &str) -> (u64, u64) {
+    let (ms, seq) = id.split_once('-').unwrap_or((id, "0"));
+    (ms.parse().unwrap_or_default(), seq.parse().unwrap_or_default())
+}
+
+/// Appends a payload to a stream, trimmed to about `maxlen` entries. Returns the entry id.
+pub async fn xadd(co: &mut MultiplexedConnection, key: &str, payload: &str, maxlen: usize) -> Result<String, RedisError> {
+    co.xadd_maxlen(key, StreamMaxlen::Approx(maxlen), "*", &[(STREAM_PAYLOAD_FIELD, payload)]).await
+}
+
+/// Creates a consumer group reading a stream from its first entry, and the stream if needed. An existing group is kept.
+pub async fn xgroup(co: &mut MultiplexedConnection, key: &str, group: &str) -> Result<(), RedisError> {
+    let created: redis::RedisResult<()> = co.xgroup_create_mkstream(key, group, "0").await;
+    match created {
+        Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+        other => other,
+    }
+}
+
+/// Reads the entries of a consumer group across streams, oldest first.
+///
+/// With `pending`, returns the entries already delivered to this consumer and not acknowledged yet.
+/// Otherwise returns new entries, waiting at most `block_ms` for one.
+pub async fn xreadgroup(co: &mut MultiplexedConnection, keys: &[String], group: &str, consumer: &str, pending: bool, block_ms: usize) -> Result<Vec<StreamEntry>, RedisError> {
+    let mut options = StreamReadOptions::default().group(group, consumer).count(STREAM_READ_COUNT);
+    if !pending {
+        options = options.block(block_ms);
+    }
+    let ids = vec![if pending { "0" } else { ">" }; keys.len()];
+    let reply: Option<StreamReadReply> = co.xread_options(keys, &ids, &options).await?;
+    let mut entries = vec![];
+    for stream in reply.map(|reply| reply.keys).unwrap_or_default() {
+        for entry in stream.ids {
+            entries.push(StreamEntry {
+                key: stream.key.clone(),
+                payload: entry.get::<String>(STREAM_PAYLOAD_FIELD),
+                id: entry.id,
+            });
+        }
+    }
+    entries.sort_by_key(|entry| stream_id_order(&entry.id));
+    Ok(entries)
+}
+
+/// Acknowledges an entry, removing it from the pending entries of the group.
+pub async fn xack(co: &mut MultiplexedConnection, key: &str, group: &str, id: &str) -> Result<(), RedisError> {
+    let _: usize = co.xack(key, group, &[id]).await?;
+    Ok(())
+}
+
+/// Claims the entries pending on the other consumers of the group (e.g. a monitor restarted under another name).
+/// The pending list is read by pages of `STREAM_READ_COUNT` entries, each starting after the last id of the previous
+/// one, until drained. Returns the number of entries claimed.
+pub async fn xclaim(co: &mut MultiplexedConnection, key: &str, group: &str, consumer: &str) -> Result<usize, RedisError> {
+    let mut start = "-".to_string();
+    let mut claimed = 0;
+    loop {
+        let pending: StreamPendingCountReply = co.xpending_count(key, group, &start, "+", STREAM_READ_COUNT).await?;
+        let Some(last) = pending.ids.last() else {
+            break;
+        };
+        // Exclusive range, the claimed entries stay pending under this consumer
+        start = format!("({}", last.id);
+        let ids = pending.ids.iter().filter(|p| p.consumer != consumer).map(|p| p.id.clone()).collect::<Vec<String>>();
+        if !ids.is_empty() {
+            let reply: StreamClaimReply = co.xclaim(key, group, consumer, 0, &ids).await?;
+            claimed += reply.ids.len();
+        }
+        if pending.ids.len() < STREAM_READ_COUNT {
+            break;
+        }
+    }
+    Ok(claimed)
+}
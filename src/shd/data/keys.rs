@@ -0,0 +1,136 @@
+//! Redis Keys Module
+//!
+//! Every Redis channel, stream and key the bots and the monitor use, in one place. Names start with the
+//! `tycho_market_maker:v1` schema prefix, and the per-instance ones embed the instance identifier, so two bots
+//! on the same Redis never write to the same key. Each event goes to the channel of its instance and type
+//! (`tycho_market_maker:v1:events:<identifier>:<type>`), the monitor subscribes to them all by pattern.
+//!
+//! Compatibility mode (REDIS_LEGACY_KEYS, on by default for this release) also publishes the events in their pre-v1
+//! shape on the pre-v1 global channel and streams, listens to the legacy control channel and honors the legacy breaker resume
+//! key, so a monitor or tooling not upgraded yet keeps working while the makers are rolled out.
+use std::sync::OnceLock;
+
+use crate::{
+    types::moni::MessageType,
+    utils::constants::{BREAKER_RESUME_KEY, CHANNEL_REDIS, CONTROL_CHANNEL_REDIS, REDIS_KEYS_PREFIX, REDIS_KEYS_VERSION, TRADE_JOURNAL_KEY},
+};
+
+/// Compatibility mode of the process, set once at startup (off if never set).
+static LEGACY: OnceLock<bool> = OnceLock::new();
+
+/// Sets the compatibility mode, before the first publication. Returns false if already set.
+pub fn configure(legacy: bool) -> bool {
+    LEGACY.set(legacy).is_ok()
+}
+
+/// Whether the legacy channels and keys are used too.
+pub fn legacy() -> bool {
+    LEGACY.get().copied().unwrap_or(false)
+}
+
+/// Versioned prefix of every name, e.g. `tycho_market_maker:v1`.
+pub fn prefix() -> String {
+    format!("{}:{}", REDIS_KEYS_PREFIX, REDIS_KEYS_VERSION)
+}
+
+/// Pub/sub channel of the events of one type of an instance.
+pub fn channel(identifier: &str, kind: &MessageType) -> String {
+    format!("{}:events:{}:{}", prefix(), identifier, kind.as_str())
+}
+
+pub fn channel_trades(identifier: &str) -> String {
+    channel(identifier, &MessageType::NewTrade)
+}
+
+pub fn channel_prices(identifier: &str) -> String {
+    channel(identifier, &MessageType::NewPrices)
+}
+
+/// Channel of the connectivity pings, not tied to an instance.
+pub fn channel_ping() -> String {
+    format!("{}:events:ping", prefix())
+}
+
+/// Pattern matching the event channels of every instance, subscribed by the monitor.
+pub fn channel_events_pattern() -> String {
+    format!("{}:events:*", prefix())
+}
+
+/// Operator commands channel (pause, resume, kill), targeting an instance through the message itself.
+pub fn channel_control() -> String {
+    format!("{}:control", prefix())
+}
+
+/// Stream of one message type (streams transport), shared by the instances and consumed by the monitor group.
+pub fn stream(kind: &MessageType) -> String {
+    format!("{}:stream:{}", prefix(), kind.as_str())
+}
+
+/// Sampled decision audit records of an instance (pub/sub channel, or stream with the streams transport), not consumed by the monitor.
+pub fn channel_audit(identifier: &str) -> String {
+    format!("{}:audit:{}", prefix(), identifier)
+}
+
+/// Trading state of an instance (running, paused, killed), refreshed with the heartbeat.
+pub fn key_status(identifier: &str) -> String {
+    format!("{}:instance:{}:status", prefix(), identifier)
+}
+
+/// Last heartbeat of an instance (unix seconds), expiring when the instance stops beating.
+pub fn key_heartbeat(identifier: &str) -> String {
+    format!("{}:instance:{}:heartbeat", prefix(), identifier)
+}
+
+/// Set to true by the operator to resume the circuit breaker of an instance.
+pub fn key_breaker_resume(identifier: &str) -> String {
+    format!("{}:instance:{}:breaker_resume", prefix(), identifier)
+}
+
+/// Recent trades journal, per configuration so it outlives the instance identifier.
+pub fn key_journal(config_id: &str) -> String {
+    format!("{}:journal:{}", prefix(), config_id)
+}
+
+/// Pre-v1 names, used in compatibility mode only.
+pub mod legacy {
+    use super::*;
+
+    /// Global channel of every event.
+    pub fn channel_events() -> String {
+        CHANNEL_REDIS.to_string()
+    }
+
+    pub fn channel_control() -> String {
+        CONTROL_CHANNEL_REDIS.to_string()
+    }
+
+    pub fn stream(kind: &MessageType) -> String {
+        format!("{}:{}", CHANNEL_REDIS, kind.as_str())
+    }
+
+    pub fn key_breaker_resume(identifier: &str) -> String {
+        format!("{}:{}", BREAKER_RESUME_KEY, identifier)
+    }
+
+    pub fn key_journal(config_id: &str) -> String {
+        format!("{}:{}", TRADE_JOURNAL_KEY, config_id)
+    }
+}
+
+/// Control channels an instance listens to.
+pub fn control_channels() -> Vec<String> {
+    let mut channels = vec![channel_control()];
+    if legacy() {
+        channels.push(legacy::channel_control());
+    }
+    channels
+}
+
+/// Keys resuming the circuit breaker of an instance.
+pub fn breaker_resume_keys(identifier: &str) -> Vec<String> {
+    let mut keys = vec![key_breaker_resume(identifier)];
+    if legacy() {
+        keys.push(legacy::key_breaker_resume(identifier));
+    }
+    keys
+}
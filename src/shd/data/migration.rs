@@ -0,0 +1,109 @@
+//! Migration Module
+//!
+//! The monitor used to expect its tables to exist (pushed to Neon with Prisma), so running it against a fresh
+//! database meant replaying `prisma/schema.prisma` by hand. `migrate` now creates the tables from the sea-orm
+//! entities, for Postgres and SQLite alike, when the monitor starts. Applied migrations are recorded in
+//! `seaql_migrations` (the table of sea-orm-migration), and every change is skipped when already there, so a
+//! database already pushed with Prisma is left as is.
+use sea_orm::{
+    sea_query::{Alias, ColumnDef, Index, Query, Table},
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, EntityTrait, Schema, Statement, TransactionTrait,
+};
+
+use crate::entity::{configuration, dead_letter, instance, pnl, price, trade};
+
+/// Table recording the applied migrations.
+const MIGRATIONS_TABLE: &str = "seaql_migrations";
+
+/// Every migration, in order. Append only, never edit one already released.
+pub const MIGRATIONS: &[&str] = &["m20250901_000001_monitor_tables", "m20250901_000002_dead_letter", "m20250915_000003_trade_idempotency_key"];
+
+/// Unique index of a trade per instance and idempotency key (broadcast hash, or client key without one).
+pub const TRADE_IDEMPOTENCY_INDEX: &str = "Trade_instanceId_idempotencyKey_key";
+
+/// Applies one migration.
+async fn up(version: &str, txn: &DatabaseTransaction) -> Result<(), DbErr> {
+    let backend = txn.get_database_backend();
+    match version {
+        "m20250901_000001_monitor_tables" => {
+            create(txn, configuration::Entity).await?;
+            create(txn, instance::Entity).await?;
+            create(txn, price::Entity).await?;
+            create(txn, pnl::Entity).await?;
+            create(txn, trade::Entity).await
+        }
+        "m20250901_000002_dead_letter" => create(txn, dead_letter::Entity).await,
+        "m20250915_000003_trade_idempotency_key" => {
+            if !has_column(txn, "Trade", "idempotencyKey").await? {
+                let alter = Table::alter().table(trade::Entity).add_column(ColumnDef::new(trade::Column::IdempotencyKey).text().null()).to_owned();
+                txn.execute(backend.build(&alter)).await?;
+            }
+            // Rows stored before have no key (NULL), which the index does not compare
+            let index = Index::create()
+                .if_not_exists()
+                .unique()
+                .name(TRADE_IDEMPOTENCY_INDEX)
+                .table(trade::Entity)
+                .col(trade::Column::InstanceId)
+                .col(trade::Column::IdempotencyKey)
+                .to_owned();
+            txn.execute(backend.build(&index)).await.map(|_| ())
+        }
+        _ => Err(DbErr::Migration(format!("Unknown migration {}", version))),
+    }
+}
+
+/// Creates the table of an entity, unless it exists.
+async fn create<E: EntityTrait>(txn: &DatabaseTransaction, entity: E) -> Result<(), DbErr> {
+    let backend = txn.get_database_backend();
+    let mut statement = Schema::new(backend).create_table_from_entity(entity);
+    statement.if_not_exists();
+    txn.execute(backend.build(&statement)).await.map(|_| ())
+}
+
+/// Whether a column exists, e.g. created with its table from the current entity.
+async fn has_column(txn: &DatabaseTransaction, table: &str, column: &str) -> Result<bool, DbErr> {
+    let backend = txn.get_database_backend();
+    let sql = match backend {
+        DbBackend::Sqlite => "SELECT name FROM pragma_table_info(?) WHERE name = ?",
+        _ => "SELECT column_name FROM information_schema.columns WHERE table_name = $1 AND column_name = $2",
+    };
+    let found = txn.query_one(Statement::from_sql_and_values(backend, sql, [table.into(), column.into()])).await?;
+    Ok(found.is_some())
+}
+
+/// Versions of the migrations already applied.
+pub async fn applied(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    let backend = db.get_database_backend();
+    let select = Query::select().column(Alias::new("version")).from(Alias::new(MIGRATIONS_TABLE)).to_owned();
+    let rows = db.query_all(backend.build(&select)).await?;
+    rows.iter().map(|row| row.try_get::<String>("", "version")).collect()
+}
+
+/// Applies the pending migrations, each in its own transaction. Returns how many were applied.
+pub async fn migrate(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let backend = db.get_database_backend();
+    let tracking = Table::create()
+        .table(Alias::new(MIGRATIONS_TABLE))
+        .if_not_exists()
+        .col(ColumnDef::new(Alias::new("version")).string().not_null().primary_key())
+        .col(ColumnDef::new(Alias::new("applied_at")).big_integer().not_null())
+        .to_owned();
+    db.execute(backend.build(&tracking)).await?;
+    let done = applied(db).await?;
+    let mut count = 0;
+    for version in MIGRATIONS.iter().filter(|version| !done.iter().any(|applied| applied == *version)) {
+        let txn = db.begin().await?;
+        up(version, &txn).await?;
+        let record = Query::insert()
+            .into_table(Alias::new(MIGRATIONS_TABLE))
+            .columns([Alias::new("version"), Alias::new("applied_at")])
+            .values_panic([(*version).into(), chrono::Utc::now().timestamp().into()])
+            .to_owned();
+        txn.execute(backend.build(&record)).await?;
+        txn.commit().await?;
+        tracing::info!("🐘 Migration {} applied", version);
+        count += 1;
+    }
+    Ok(count)
+}
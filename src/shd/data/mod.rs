@@ -1,7 +1,13 @@
 //! Data Access Layer Module
 //!
-//! Data access layer for Redis pub/sub communication and database operations.
+//! Data access layer for Redis pub/sub communication (buffered by `publisher`) and database operations.
+pub mod backfill;
+pub mod dead_letter;
 pub mod helpers;
+pub mod keys;
+pub mod migration;
 pub mod neon;
 pub mod r#pub;
+pub mod publisher;
+pub mod retention;
 pub mod sub;
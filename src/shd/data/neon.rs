@@ -1,23 +1,51 @@
 // main.rs
 
-use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, DbErr, EntityTrait, Set};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use alloy::rpc::types::TransactionReceipt;
+use sea_orm::{ActiveModelTrait, ConnectOptions, Database, DatabaseConnection, DbErr, EntityTrait, Set};
 use serde_json::json;
+use tokio::sync::{Notify, RwLock};
 
 use crate::{
     entity::instance,
     types::{
-        config::{MarketMakerConfig, MoniEnvConfig},
+        config::{DatabaseType, MarketMakerConfig, MoniEnvConfig},
         maker::ReceiptData,
         moni::ParsedMessage,
     },
-    utils::evm::fetch_receipt_with_retry,
+    utils::constants::DB_HEALTH_CHECK_INTERVAL_SECS,
+    utils::evm::{fetch_receipt_with_retry, RpcPool},
 };
 use sea_orm::prelude::Uuid;
 
-/// Establishes a connection to PostgreSQL database using SeaORM.
+/// Connection options of the database of the environment.
+///
+/// A SQLite file is created if missing, and SQLite gets a single pooled connection, never recycled: an in-memory
+/// database (`sqlite::memory:`) lives and dies with its connection, and a file one takes one writer at a time anyway.
+pub fn options(env: &MoniEnvConfig) -> ConnectOptions {
+    match env.database_backend {
+        DatabaseType::Postgres => ConnectOptions::new(env.database_url.clone()),
+        DatabaseType::Sqlite => {
+            let mut url = env.database_url.clone();
+            if url.starts_with("sqlite://") && !url.contains("mode=") {
+                url = format!("{}{}mode=rwc", url, if url.contains('?') { '&' } else { '?' });
+            }
+            let mut options = ConnectOptions::new(url);
+            let forever = std::time::Duration::from_secs(u32::MAX as u64);
+            options.max_connections(1).min_connections(1).idle_timeout(forever).max_lifetime(forever);
+            options
+        }
+    }
+}
+
+/// Establishes a connection to the database (Postgres, or SQLite for local development) using SeaORM.
 pub async fn connect(env: MoniEnvConfig) -> Result<DatabaseConnection, DbErr> {
     // tracing::info!("Connecting to database: {}", env.database_url);
-    match Database::connect(env.database_url.clone()).await {
+    match Database::connect(options(&env)).await {
         Ok(db) => {
             tracing::info!("Successfully connected to database");
             Ok(db)
@@ -29,17 +57,99 @@ pub async fn connect(env: MoniEnvConfig) -> Result<DatabaseConnection, DbErr> {
     }
 }
 
-/// Handle different message types (from Redis pub-sub, to then push to DB)
-pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
-    // Connect to database once for this message
-    let db = match connect(env.clone()).await {
-        Ok(db) => db,
-        Err(err) => {
-            tracing::error!("Failed to connect to database for message handling: {}", err.to_string());
-            return;
+/// Database connection of the monitor, opened once and shared by every message.
+///
+/// sea-orm pools connections internally, so one `DatabaseConnection` serves the whole process.
+/// `supervise` pings it periodically, or as soon as a message fails to store, and reconnects
+/// only when the ping fails.
+#[derive(Clone)]
+pub struct Neon {
+    db: Arc<RwLock<DatabaseConnection>>,
+    options: Option<ConnectOptions>, // None when built from an existing connection (no reconnection)
+    connects: Arc<AtomicU64>,
+    failed: Arc<Notify>,
+}
+
+impl Neon {
+    /// Connects to the database of the environment.
+    pub async fn connect(env: MoniEnvConfig) -> Result<Self, DbErr> {
+        let db = connect(env.clone()).await?;
+        let mut neon = Self::from_connection(db);
+        neon.options = Some(options(&env));
+        Ok(neon)
+    }
+
+    /// Wraps an existing connection, e.g. a SQLite one in tests.
+    pub fn from_connection(db: DatabaseConnection) -> Self {
+        Self {
+            db: Arc::new(RwLock::new(db)),
+            options: None,
+            connects: Arc::new(AtomicU64::new(1)),
+            failed: Arc::new(Notify::new()),
         }
-    };
+    }
+
+    /// Current connection (a cheap handle on the pool).
+    pub async fn db(&self) -> DatabaseConnection {
+        self.db.read().await.clone()
+    }
 
+    /// Connections opened so far, 1 until the supervisor had to reconnect.
+    pub fn connects(&self) -> u64 {
+        self.connects.load(Ordering::Relaxed)
+    }
+
+    /// Reports a failed query, waking the supervisor up.
+    pub fn failed(&self) {
+        self.failed.notify_one();
+    }
+
+    /// Health-checks the connection every `DB_HEALTH_CHECK_INTERVAL_SECS` or after a failure, and reconnects when the ping fails.
+    pub fn supervise(&self) {
+        let Some(options) = self.options.clone() else {
+            return;
+        };
+        let neon = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let _ = tokio::time::timeout(std::time::Duration::from_secs(DB_HEALTH_CHECK_INTERVAL_SECS), neon.failed.notified()).await;
+                let ping = neon.db.read().await.ping().await;
+                let Err(err) = ping else {
+                    continue;
+                };
+                tracing::warn!("🐘 Database ping failed: {}. Reconnecting", err);
+                match Database::connect(options.clone()).await {
+                    Ok(db) => {
+                        *neon.db.write().await = db;
+                        neon.connects.fetch_add(1, Ordering::Relaxed);
+                        tracing::info!("🐘 Database reconnected");
+                    }
+                    Err(err) => tracing::error!("🐘 Database reconnection failed: {}", err),
+                }
+            }
+        });
+    }
+}
+
+/// Receipt fields stored with a trade.
+pub fn receipt_data(receipt: &TransactionReceipt) -> ReceiptData {
+    ReceiptData {
+        status: receipt.status(),
+        gas_used: receipt.gas_used as u128, // Alloy 1.0: gas_used is u64, cast to u128
+        effective_gas_price: receipt.effective_gas_price,
+        error: None,
+        transaction_hash: receipt.transaction_hash.to_string(),
+        transaction_index: receipt.transaction_index.unwrap_or_default(),
+        block_number: receipt.block_number.unwrap_or_default(),
+        transfers: crate::utils::evm::transfers(receipt.inner.logs()),
+    }
+}
+
+/// Handle different message types (from Redis pub-sub or streams, to then push to DB)
+///
+/// Returns an error when the database failed, so a stream entry stays pending and is handled again.
+/// Messages that can never be stored (unknown instance, trade that did not land) are not errors.
+pub async fn handle(msg: &ParsedMessage, db: &DatabaseConnection) -> Result<(), String> {
     match msg {
         ParsedMessage::Ping => {
             tracing::info!("Ping received !");
@@ -49,11 +159,11 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
             let config_hash = msg.config.hash();
             tracing::info!("Config Keccak256: {}", config_hash);
 
-            let cfgs = match pull::configurations(&db).await {
+            let cfgs = match pull::configurations(db).await {
                 Ok(cfgs) => cfgs,
                 Err(err) => {
                     tracing::error!("   => Failed to pull configurations: {}", err);
-                    return;
+                    return Err(err.to_string());
                 }
             };
 
@@ -65,22 +175,25 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
                     Ok(mmc) => mmc,
                     Err(err) => {
                         tracing::error!("   => Failed to deserialize configuration: {}", err);
-                        return;
+                        return Ok(());
                     }
                 };
                 tracing::info!("    => Configuration: {}: Keccak256: {}", mmc.id(), cfg.hash);
 
-                let instances = match pull::instances(&db).await {
+                let instances = match pull::open_instances_for_configuration(db, &cfg.id).await {
                     Ok(instances) => instances,
                     Err(err) => {
                         tracing::error!("Failed to pull instances: {}", err);
-                        return;
+                        return Err(err.to_string());
                     }
                 };
 
-                tracing::info!("    => Got {} instances for this configuration", instances.len());
+                // Only instances of the same pair and wallet, other bots sharing the configuration row are left untouched
+                let prefix = format!("{}-instance-", msg.config.id());
+                let instances = instances.into_iter().filter(|instance| instance.identifier.starts_with(&prefix)).collect::<Vec<_>>();
+                tracing::info!("    => Got {} open instances for this configuration", instances.len());
 
-                // Closing the last instance, if any
+                // Closing the latest open instance, if any
                 if let Some(instance) = instances.last() {
                     tracing::info!(
                         "    => Closing last instance (with id: {}) | Initially started at: {}  ⚠️   Make sure to stop the container associated with this instance !",
@@ -88,30 +201,33 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
                         instance.started_at
                     );
                     let mut instance: instance::ActiveModel = instance.clone().into();
-                    // ! Incorrect because when new config is created, the instance is not closed because it's not attached to the new config
                     instance.ended_at = Set(Some(chrono::Utc::now().naive_utc()));
 
-                    if let Err(err) = instance.update(&db).await {
+                    if let Err(err) = instance.update(db).await {
                         tracing::error!("    => Error closing last instance: {}", err);
+                        return Err(err.to_string());
                     }
                 } else {
-                    tracing::info!("    => No instances found for this configuration");
+                    tracing::info!("    => No open instance found for this configuration");
                 }
 
-                if let Err(err) = create::instance(&db, cfg, msg.config.clone(), msg.identifier.clone(), msg.commit.clone()).await {
+                if let Err(err) = create::instance(db, cfg, msg.config.clone(), msg.identifier.clone(), msg.commit.clone()).await {
                     tracing::error!("    => Error attaching instance to configuration: {}", err);
+                    return Err(err.to_string());
                 }
             } else {
                 tracing::info!("Configuration hash not found in DB. Creating it, and the instance with it ...");
 
-                match create::configuration(&db, msg.config.clone()).await {
+                match create::configuration(db, msg.config.clone()).await {
                     Ok(cfg) => {
-                        if let Err(err) = create::instance(&db, &cfg, msg.config.clone(), msg.identifier.clone(), msg.commit.clone()).await {
+                        if let Err(err) = create::instance(db, &cfg, msg.config.clone(), msg.identifier.clone(), msg.commit.clone()).await {
                             tracing::error!("    => Error attaching instance to configuration: {}", err);
+                            return Err(err.to_string());
                         }
                     }
                     Err(err) => {
                         tracing::error!("    => Error creating configuration: {}", err);
+                        return Err(err.to_string());
                     }
                 }
             }
@@ -119,17 +235,18 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
         ParsedMessage::NewPrices(msg) => {
             tracing::info!("NewPrices received, with reference_price: {} and instance identifier: {}", msg.reference_price, msg.identifier);
 
-            let instances = match pull::instances(&db).await {
+            let instances = match pull::instances(db).await {
                 Ok(instances) => instances,
                 Err(err) => {
                     tracing::error!("   => Error finding instance by hash: {}", err);
-                    return;
+                    return Err(err.to_string());
                 }
             };
 
             if let Some(instance) = instances.into_iter().find(|inst| inst.identifier == msg.identifier) {
-                if let Err(err) = create::price(&db, &instance, msg).await {
+                if let Err(err) = create::price(db, &instance, msg).await {
                     tracing::error!("   => Error storing price data: {}", err);
+                    return Err(err.to_string());
                 }
             } else {
                 tracing::warn!("   => Instance not found for hash: {}", msg.identifier);
@@ -138,11 +255,11 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
         ParsedMessage::NewTrade(msg) => {
             tracing::info!(" 🔹 NewTrade received, with instance identifier: {}", msg.identifier);
 
-            let instances = match pull::instances(&db).await {
+            let instances = match pull::instances(db).await {
                 Ok(instances) => instances,
                 Err(err) => {
                     tracing::error!("   => Error finding instance by hash: {}", err);
-                    return;
+                    return Err(err.to_string());
                 }
             };
 
@@ -151,7 +268,7 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
                     Ok(config) => config,
                     Err(err) => {
                         tracing::error!("   => Failed to find instance configuration: {}", err.to_string());
-                        return;
+                        return Ok(());
                     }
                 };
 
@@ -161,53 +278,97 @@ pub async fn handle(msg: &ParsedMessage, env: MoniEnvConfig) {
                         let hash = broadcast.hash.clone();
                         if !hash.is_empty() {
                             tracing::info!("Fetching receipt on network {} for transaction {} (with retry)", config.network_name, hash);
-                            let swap_receipt = fetch_receipt_with_retry(config.rpc_url.clone(), hash.clone(), 10, 3000).await;
+                            let swap_receipt = fetch_receipt_with_retry(&RpcPool::of(&config), hash.clone(), 10, 3000).await;
                             if let Ok(swap_receipt) = swap_receipt {
-                                let swap_receipt_data = ReceiptData {
-                                    status: swap_receipt.status(),
-                                    gas_used: swap_receipt.gas_used as u128, // Alloy 1.0: gas_used is u64, cast to u128
-                                    effective_gas_price: swap_receipt.effective_gas_price,
-                                    error: None,
-                                    transaction_hash: swap_receipt.transaction_hash.to_string(),
-                                    transaction_index: swap_receipt.transaction_index.unwrap_or_default(),
-                                    block_number: swap_receipt.block_number.unwrap_or_default(),
-                                };
                                 let mut broadcast = broadcast.clone();
-                                broadcast.receipt = Some(swap_receipt_data);
+                                broadcast.receipt = Some(receipt_data(&swap_receipt));
                                 updated.data.broadcast = Some(broadcast.clone());
+                                updated.data.realize(&config.wallet_public_key);
                             } else {
-                                tracing::warn!("Trade did not land on-chain (no receipt for {}), skipping DB storage", hash);
-                                return;
+                                // Not mined yet (e.g. a bundle included a few blocks later), the backfill job fetches it later
+                                tracing::warn!("No receipt yet for {}, storing the trade without it", hash);
                             }
                         }
                     }
                     None => {
                         tracing::warn!("No broadcast struct found for trade on instance: {}", instance.id);
-                        return;
+                        return Ok(());
                     }
                 }
 
-                if let Err(err) = create::trade(&db, &instance, &updated).await {
+                if let Err(err) = create::trade(db, &instance, &updated).await {
                     tracing::error!("Error storing trade data: {}", err);
+                    return Err(err.to_string());
                 }
                 tracing::info!("Trade data stored successfully");
             } else {
                 tracing::warn!("Instance not found for hash: {}", msg.identifier);
             }
         }
+        ParsedMessage::NewPnl(msg) => {
+            tracing::info!(
+                "NewPnl received, cumulative {:.2} USD over {} trades, with instance identifier: {}",
+                msg.pnl.cumulative_usd,
+                msg.pnl.trades,
+                msg.identifier
+            );
+
+            let instances = match pull::instances(db).await {
+                Ok(instances) => instances,
+                Err(err) => {
+                    tracing::error!("   => Error finding instance by hash: {}", err);
+                    return Err(err.to_string());
+                }
+            };
+
+            if let Some(instance) = instances.into_iter().find(|inst| inst.identifier == msg.identifier) {
+                if let Err(err) = create::pnl(db, &instance, msg).await {
+                    tracing::error!("   => Error storing PnL data: {}", err);
+                    return Err(err.to_string());
+                }
+            } else {
+                tracing::warn!("   => Instance not found for hash: {}", msg.identifier);
+            }
+        }
+        ParsedMessage::NewAlert(msg) => {
+            tracing::warn!("🚨 NewAlert received ({:?}) with instance identifier: {} | {}", msg.kind, msg.identifier, msg.message);
+        }
+        ParsedMessage::EndInstance(msg) => {
+            tracing::info!("EndInstance received ({}) with instance identifier: {}", msg.reason, msg.identifier);
+
+            let instance = match pull::instance(db, &msg.identifier).await {
+                Ok(instance) => instance,
+                Err(err) => {
+                    tracing::error!("   => Error finding instance by hash: {}", err);
+                    return Err(err.to_string());
+                }
+            };
+
+            if let Some(instance) = instance {
+                let mut instance: instance::ActiveModel = instance.into();
+                instance.ended_at = Set(Some(chrono::Utc::now().naive_utc()));
+                if let Err(err) = instance.update(db).await {
+                    tracing::error!("   => Error closing instance: {}", err);
+                    return Err(err.to_string());
+                }
+            } else {
+                tracing::warn!("   => Instance not found for hash: {}", msg.identifier);
+            }
+        }
         ParsedMessage::Unknown(data) => {
             tracing::warn!("Unknown message type: {:?}", data);
         }
     }
+    Ok(())
 }
 
 pub mod create {
     use crate::types::{
         config::MarketMakerConfig,
-        moni::{NewPricesMessage, NewTradeMessage},
+        moni::{NewPnlMessage, NewPricesMessage, NewTradeMessage},
     };
 
-    use crate::entity::{configuration, instance, price, trade};
+    use crate::entity::{configuration, instance, pnl, price, trade};
 
     use super::*;
 
@@ -281,14 +442,43 @@ pub mod create {
         }
     }
 
-    /// Insert a new trade record and return its full Model
+    /// Insert a new PnL record and return its full Model
+    pub async fn pnl(db: &DatabaseConnection, instance: &instance::Model, msg: &NewPnlMessage) -> Result<pnl::Model, sea_orm::DbErr> {
+        let now = chrono::Utc::now().naive_utc();
+        let model = pnl::ActiveModel {
+            created_at: Set(now),
+            updated_at: Set(now),
+            instance_id: Set(instance.id.clone()),
+            value: Set(json!(msg)),
+            id: Set(Uuid::new_v4().to_string()),
+        };
+        match model.insert(db).await {
+            Ok(inserted) => Ok(inserted),
+            Err(err) => {
+                tracing::error!("Error inserting: {}", err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Insert a new trade record and return its full Model.
+    /// A trade already stored under the same key (broadcast hash, else client key) is updated instead, e.g. when the maker republishes it.
     pub async fn trade(db: &DatabaseConnection, instance: &instance::Model, msg: &NewTradeMessage) -> Result<trade::Model, sea_orm::DbErr> {
+        let key = msg.key();
+        if let Some(key) = key.as_ref() {
+            if let Some(existing) = pull::trade_by_key(db, &instance.id, key).await? {
+                tracing::info!("Trade {} already stored, merging into {}", key, existing.id);
+                let merged = merge(&existing, msg);
+                return update::trade(db, &existing, &merged).await;
+            }
+        }
         let now = chrono::Utc::now().naive_utc();
         let model = trade::ActiveModel {
             created_at: Set(now),
             updated_at: Set(now),
             instance_id: Set(instance.id.clone()),
             values: Set(json!(msg)),
+            idempotency_key: Set(key),
             id: Set(Uuid::new_v4().to_string()),
         };
         match model.insert(db).await {
@@ -299,11 +489,46 @@ pub mod create {
             }
         }
     }
+
+    /// Newer message of a stored trade, keeping the receipt already known if the newer one has none.
+    fn merge(existing: &trade::Model, msg: &NewTradeMessage) -> NewTradeMessage {
+        let mut merged = msg.clone();
+        let Ok(stored) = serde_json::from_value::<NewTradeMessage>(existing.values.clone()) else {
+            return merged;
+        };
+        if let (Some(broadcast), Some(previous)) = (merged.data.broadcast.as_mut(), stored.data.broadcast) {
+            if broadcast.receipt.is_none() {
+                broadcast.receipt = previous.receipt;
+                broadcast.dropped |= previous.dropped;
+                merged.data.realized = merged.data.realized.take().or(stored.data.realized);
+            }
+        }
+        merged
+    }
+}
+
+pub mod update {
+    use crate::entity::trade;
+    use crate::types::moni::NewTradeMessage;
+
+    use super::*;
+
+    /// Replaces the message stored with a trade, e.g. once its receipt is known.
+    pub async fn trade(db: &DatabaseConnection, model: &trade::Model, msg: &NewTradeMessage) -> Result<trade::Model, sea_orm::DbErr> {
+        let mut model: trade::ActiveModel = model.clone().into();
+        model.values = Set(json!(msg));
+        model.updated_at = Set(chrono::Utc::now().naive_utc());
+        model.update(db).await
+    }
 }
 
 pub mod pull {
 
-    use crate::entity::{configuration, instance, price, trade};
+    use chrono::NaiveDateTime;
+    use sea_orm::{ColumnTrait, Condition, QueryFilter, QueryOrder, QuerySelect};
+
+    use crate::entity::{configuration, instance, pnl, price, trade};
+    use crate::types::moni::NewTradeMessage;
 
     use super::*;
 
@@ -311,6 +536,21 @@ pub mod pull {
         instance::Entity::find().all(db).await
     }
 
+    /// Instance of an identifier, looked up by its column rather than among every instance.
+    pub async fn instance(db: &DatabaseConnection, identifier: &str) -> Result<Option<instance::Model>, sea_orm::DbErr> {
+        instance::Entity::find().filter(instance::Column::Identifier.eq(identifier)).one(db).await
+    }
+
+    /// Instances of a configuration not ended yet, oldest first.
+    pub async fn open_instances_for_configuration(db: &DatabaseConnection, cfg_id: &str) -> Result<Vec<instance::Model>, sea_orm::DbErr> {
+        instance::Entity::find()
+            .filter(instance::Column::ConfigurationId.eq(cfg_id))
+            .filter(instance::Column::EndedAt.is_null())
+            .order_by_asc(instance::Column::StartedAt)
+            .all(db)
+            .await
+    }
+
     pub async fn configurations(db: &DatabaseConnection) -> Result<Vec<configuration::Model>, sea_orm::DbErr> {
         crate::entity::configuration::Entity::find().all(db).await
     }
@@ -319,7 +559,61 @@ pub mod pull {
         trade::Entity::find().all(db).await
     }
 
+    /// Trade of an instance stored under an idempotency key, see `NewTradeMessage::key`.
+    pub async fn trade_by_key(db: &DatabaseConnection, instance_id: &str, key: &str) -> Result<Option<trade::Model>, sea_orm::DbErr> {
+        trade::Entity::find()
+            .filter(trade::Column::InstanceId.eq(instance_id))
+            .filter(trade::Column::IdempotencyKey.eq(key))
+            .one(db)
+            .await
+    }
+
+    /// Broadcast trades still waiting for their receipt (not marked dropped), oldest first.
+    pub async fn trades_missing_receipt(db: &DatabaseConnection) -> Result<Vec<(trade::Model, NewTradeMessage)>, sea_orm::DbErr> {
+        let trades = trade::Entity::find().order_by_asc(trade::Column::CreatedAt).all(db).await?;
+        Ok(trades
+            .into_iter()
+            .filter_map(|trade| {
+                let msg = serde_json::from_value::<NewTradeMessage>(trade.values.clone()).ok()?;
+                let broadcast = msg.data.broadcast.as_ref()?;
+                (broadcast.receipt.is_none() && !broadcast.hash.is_empty() && !broadcast.dropped).then_some((trade, msg))
+            })
+            .collect())
+    }
+
     pub async fn prices(db: &DatabaseConnection) -> Result<Vec<price::Model>, sea_orm::DbErr> {
         price::Entity::find().all(db).await
     }
+
+    /// Instances whose identifier starts with `prefix`: one instance, or every run of a pair with `MarketMakerConfig::id`. Oldest first.
+    pub async fn instances_matching(db: &DatabaseConnection, prefix: &str) -> Result<Vec<instance::Model>, sea_orm::DbErr> {
+        instance::Entity::find()
+            .filter(instance::Column::Identifier.starts_with(prefix))
+            .order_by_asc(instance::Column::StartedAt)
+            .all(db)
+            .await
+    }
+
+    /// Page of at most `limit` price rows of the instances created within [from, to], oldest first, after the row
+    /// keyed `after` (creation time and id of the last row of the previous page).
+    pub async fn prices_between(
+        db: &DatabaseConnection, instance_ids: &[String], from: NaiveDateTime, to: NaiveDateTime, after: Option<(NaiveDateTime, String)>, limit: u64,
+    ) -> Result<Vec<price::Model>, sea_orm::DbErr> {
+        let mut query = price::Entity::find()
+            .filter(price::Column::InstanceId.is_in(instance_ids.iter().cloned()))
+            .filter(price::Column::CreatedAt.gte(from))
+            .filter(price::Column::CreatedAt.lte(to));
+        if let Some((created_at, id)) = after {
+            query = query.filter(
+                Condition::any()
+                    .add(price::Column::CreatedAt.gt(created_at))
+                    .add(Condition::all().add(price::Column::CreatedAt.eq(created_at)).add(price::Column::Id.gt(id))),
+            );
+        }
+        query.order_by_asc(price::Column::CreatedAt).order_by_asc(price::Column::Id).limit(limit).all(db).await
+    }
+
+    pub async fn pnls(db: &DatabaseConnection) -> Result<Vec<pnl::Model>, sea_orm::DbErr> {
+        pnl::Entity::find().all(db).await
+    }
 }
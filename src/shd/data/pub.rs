@@ -1,12 +1,62 @@
-use crate::types::moni::{MessageType, NewInstanceMessage, NewPricesMessage, NewTradeMessage, RedisMessage};
-use crate::utils::constants::CHANNEL_REDIS;
+use crate::data::keys;
+use crate::types::moni::{EndInstanceMessage, Envelope, MessageType, NewAlertMessage, NewInstanceMessage, NewPnlMessage, NewPricesMessage, NewTradeMessage, RedisMessage};
 
 use redis::Commands;
 use serde::Serialize;
 use serde_json;
 
-/// Publishes any serializable message to Redis pubsub.
-pub fn publish<T: Serialize>(event: &T) -> Result<(), String> {
+/// Serialized event, with the channels and streams it goes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub channels: Vec<String>,
+    pub streams: Vec<String>,
+    pub payload: String,
+}
+
+/// Messages of an event: the envelope on the channel of its instance and the stream of its kind and, in compatibility
+/// mode, the previous `RedisMessage` shape on the legacy global channel and stream, as read by a monitor not upgraded yet.
+pub fn outgoing(event: &Envelope) -> Result<Vec<Outgoing>, String> {
+    let Some(kind) = MessageType::from_kind(&event.kind) else {
+        tracing::error!("Unknown message kind: {}", event.kind);
+        return Err(format!("Unknown message kind: {}", event.kind));
+    };
+    let Ok(payload) = serde_json::to_string(event) else {
+        tracing::error!("Failed to serialize message");
+        return Err("Failed to serialize message".to_string());
+    };
+    let mut messages = vec![Outgoing {
+        channels: vec![keys::channel(&event.identifier, &kind)],
+        streams: vec![keys::stream(&kind)],
+        payload,
+    }];
+    if keys::legacy() {
+        let legacy = RedisMessage {
+            message: kind.clone(),
+            timestamp: event.published_at / 1_000,
+            data: event.payload.clone(),
+            mirrored: true,
+        };
+        let payload = serde_json::to_string(&legacy).map_err(|e| format!("Failed to serialize legacy message: {}", e))?;
+        messages.push(Outgoing {
+            channels: vec![keys::legacy::channel_events()],
+            streams: vec![keys::legacy::stream(&kind)],
+            payload,
+        });
+    }
+    Ok(messages)
+}
+
+/// Queues an event envelope for Redis, see `outgoing` and `data::publisher`. Never blocks on Redis.
+pub fn publish(event: &Envelope) -> Result<(), String> {
+    let droppable = event.kind == MessageType::NewPrices.as_str();
+    for message in outgoing(event)? {
+        crate::data::publisher::enqueue(message.channels, message.streams, message.payload, droppable);
+    }
+    Ok(())
+}
+
+/// Publishes any serializable message on the ping channel right away, on a new connection.
+pub fn send<T: Serialize>(event: &T) -> Result<(), String> {
     let start_time = std::time::SystemTime::now();
 
     let Ok(client) = crate::data::helpers::pubsub() else {
@@ -24,7 +74,7 @@ pub fn publish<T: Serialize>(event: &T) -> Result<(), String> {
         return Err("Failed to serialize message".to_string());
     };
 
-    match conn.publish::<&str, &str, ()>(CHANNEL_REDIS, &msg) {
+    match conn.publish::<String, &str, ()>(keys::channel_ping(), &msg) {
         Ok(_) => {
             let _elapsed = start_time.elapsed().unwrap_or_default().as_millis();
             // tracing::debug!("Message has been sent (of size: {}) | Took {} ms", msg.len(), elapsed);
@@ -37,42 +87,46 @@ pub fn publish<T: Serialize>(event: &T) -> Result<(), String> {
     }
 }
 
-/// Publishes a ping message to verify Redis connectivity.
+/// Publishes a ping message to verify Redis connectivity, bypassing the queue (on the ping channel, whatever the transport).
 pub fn ping() -> Result<(), String> {
-    let message = RedisMessage {
-        message: MessageType::Ping,
-        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
-        data: serde_json::to_value(()).unwrap(),
-    };
-    publish(&message)
+    send(&Envelope::new(&MessageType::Ping, "", serde_json::to_value(()).unwrap()))
 }
 
 /// Publishes a new market maker instance creation event.
 pub fn instance(msg: NewInstanceMessage) -> Result<(), String> {
-    let message = RedisMessage {
-        message: MessageType::NewInstance,
-        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
-        data: serde_json::to_value(msg).unwrap(),
-    };
-    publish(&message)
+    publish(&Envelope::new(&MessageType::NewInstance, &msg.identifier, serde_json::to_value(&msg).unwrap()))
 }
 
 /// Publishes price update events from the market maker.
 pub fn prices(msg: NewPricesMessage) -> Result<(), String> {
-    let message = RedisMessage {
-        message: MessageType::NewPrices,
-        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
-        data: serde_json::to_value(msg).unwrap(),
-    };
-    publish(&message)
+    publish(&Envelope::new(&MessageType::NewPrices, &msg.identifier, serde_json::to_value(&msg).unwrap()))
 }
 
 /// Publishes trade execution events from the market maker.
 pub fn trade(msg: NewTradeMessage) -> Result<(), String> {
-    let message = RedisMessage {
-        message: MessageType::NewTrade,
-        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
-        data: serde_json::to_value(msg).unwrap(),
-    };
-    publish(&message)
+    publish(&Envelope::new(&MessageType::NewTrade, &msg.identifier, serde_json::to_value(&msg).unwrap()))
+}
+
+/// Publishes realized PnL events from the market maker.
+pub fn pnl(msg: NewPnlMessage) -> Result<(), String> {
+    publish(&Envelope::new(&MessageType::NewPnl, &msg.identifier, serde_json::to_value(&msg).unwrap()))
+}
+
+/// Publishes operational alerts from the market maker.
+pub fn alert(msg: NewAlertMessage) -> Result<(), String> {
+    publish(&Envelope::new(&MessageType::NewAlert, &msg.identifier, serde_json::to_value(&msg).unwrap()))
+}
+
+/// Publishes an alert right away, bypassing the queue, within `timeout`. For the last gasp of a crashing process.
+pub fn alert_now(msg: NewAlertMessage, timeout: std::time::Duration) -> Result<(), String> {
+    let event = Envelope::new(&MessageType::NewAlert, &msg.identifier, serde_json::to_value(&msg).map_err(|e| e.to_string())?);
+    for message in outgoing(&event)? {
+        crate::data::publisher::publish_now(&message.channels, &message.streams, &message.payload, timeout)?;
+    }
+    Ok(())
+}
+
+/// Publishes the end of a market maker instance, once its loop has stopped.
+pub fn end(msg: EndInstanceMessage) -> Result<(), String> {
+    publish(&Envelope::new(&MessageType::EndInstance, &msg.identifier, serde_json::to_value(&msg).unwrap()))
 }
@@ -0,0 +1,274 @@
+//! Buffered Redis Publisher
+//!
+//! Events used to be published fire-and-forget on a fresh connection, so a Redis restart lost every event of
+//! the outage and left gaps in the monitor price history. Publications now go into a bounded in-memory queue,
+//! drained in order by a background task owning the connection from `data::helpers::connect`. The task
+//! reconnects with exponential backoff and replays the queue once Redis is back. Under pressure the oldest
+//! price messages are dropped, never trades, and the hot path only ever takes the queue lock.
+//! Depending on EVENTS_TRANSPORT, a message goes to the pub/sub channels of its instance or is appended to the stream
+//! of its type, see `data::keys`.
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use redis::AsyncCommands;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::{
+    types::config::EventsTransport,
+    utils::constants::{EVENT_STREAM_MAXLEN, PUBLISH_BACKOFF_MAX_MS, PUBLISH_BACKOFF_MIN_MS, PUBLISH_QUEUE_CAPACITY, STREAM_PAYLOAD_FIELD},
+};
+
+/// Process-wide publisher, its drain task is spawned on the first publication.
+static PUBLISHER: OnceLock<Publisher> = OnceLock::new();
+
+/// Transport of the process, set once at startup (pub/sub if never set).
+static TRANSPORT: OnceLock<EventsTransport> = OnceLock::new();
+
+/// Serialized message waiting for Redis.
+#[derive(Debug, Clone)]
+pub struct QueuedMessage {
+    pub channels: Vec<String>, // Pub/sub channels, used by the pub/sub transport
+    pub streams: Vec<String>,  // Streams of the message type, used by the streams transport
+    pub payload: String,
+    pub droppable: bool, // Price updates, superseded by the next one
+}
+
+/// Queue depth and delivery counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PublisherStats {
+    pub depth: usize,    // Messages waiting for Redis
+    pub published: u64,  // Messages delivered
+    pub dropped: u64,    // Price messages dropped under pressure
+    pub reconnects: u64, // Connections opened after a failure
+    pub connected: bool, // Drain task holds a working connection
+}
+
+/// Bounded FIFO of pending messages. Only droppable messages are evicted, oldest first.
+#[derive(Debug)]
+pub struct PublishQueue {
+    capacity: usize,
+    messages: VecDeque<QueuedMessage>,
+    stats: PublisherStats,
+}
+
+impl PublishQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: VecDeque::new(),
+            stats: PublisherStats::default(),
+        }
+    }
+
+    /// Enqueues a message. When full, the oldest price message makes room, or the new one is dropped if it is
+    /// a price itself. Other messages are always kept, even past the capacity.
+    pub fn push(&mut self, message: QueuedMessage) {
+        if self.messages.len() >= self.capacity {
+            if let Some(index) = self.messages.iter().position(|queued| queued.droppable) {
+                self.messages.remove(index);
+                self.stats.dropped += 1;
+            } else if message.droppable {
+                self.stats.dropped += 1;
+                return;
+            }
+        }
+        self.messages.push_back(message);
+        self.stats.depth = self.messages.len();
+    }
+
+    /// Oldest pending message, left in the queue until delivered.
+    pub fn front(&self) -> Option<QueuedMessage> {
+        self.messages.front().cloned()
+    }
+
+    /// Removes the oldest message once Redis has accepted it.
+    pub fn delivered(&mut self) {
+        if self.messages.pop_front().is_some() {
+            self.stats.published += 1;
+        }
+        self.stats.depth = self.messages.len();
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn stats(&self) -> PublisherStats {
+        self.stats
+    }
+}
+
+/// Queue shared with the drain task.
+struct Publisher {
+    queue: Arc<Mutex<PublishQueue>>,
+    notify: Arc<Notify>,
+}
+
+impl Publisher {
+    /// Creates the queue and spawns its drain task on the current Tokio runtime.
+    fn start() -> Self {
+        let queue = Arc::new(Mutex::new(PublishQueue::new(PUBLISH_QUEUE_CAPACITY)));
+        let notify = Arc::new(Notify::new());
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(drain(queue.clone(), notify.clone()));
+            }
+            Err(_) => tracing::error!("📕 No Tokio runtime, published events stay queued"),
+        }
+        Self { queue, notify }
+    }
+}
+
+fn publisher() -> &'static Publisher {
+    PUBLISHER.get_or_init(Publisher::start)
+}
+
+/// Sets the transport of the published events, before the first publication. Returns false if already set.
+pub fn configure(transport: EventsTransport) -> bool {
+    TRANSPORT.set(transport).is_ok()
+}
+
+/// Enqueues a serialized message for the drain task. Never waits on Redis.
+pub fn enqueue(channels: Vec<String>, streams: Vec<String>, payload: String, droppable: bool) {
+    let publisher = publisher();
+    publisher.queue.lock().unwrap().push(QueuedMessage {
+        channels,
+        streams,
+        payload,
+        droppable,
+    });
+    publisher.notify.notify_one();
+}
+
+/// Current queue depth and delivery counters.
+pub fn stats() -> PublisherStats {
+    match PUBLISHER.get() {
+        Some(publisher) => publisher.queue.lock().unwrap().stats(),
+        None => PublisherStats::default(),
+    }
+}
+
+/// Publishes a message right away on a blocking connection, bypassing the queue, within `timeout` per Redis call.
+///
+/// For the last gasp of a crashing process, whose queue has no time left to drain.
+pub fn publish_now(channels: &[String], streams: &[String], payload: &str, timeout: Duration) -> Result<(), String> {
+    let client = crate::data::helpers::pubsub().map_err(|e| e.to_string())?;
+    let mut co = client.get_connection_with_timeout(timeout).map_err(|e| e.to_string())?;
+    co.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    co.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    match TRANSPORT.get().copied().unwrap_or_default() {
+        EventsTransport::PubSub => {
+            for channel in channels.iter() {
+                redis::cmd("PUBLISH").arg(channel).arg(payload).query::<()>(&mut co).map_err(|e| e.to_string())?;
+            }
+        }
+        EventsTransport::Streams => {
+            for stream in streams.iter() {
+                redis::cmd("XADD")
+                    .arg(stream)
+                    .arg("MAXLEN")
+                    .arg("~")
+                    .arg(EVENT_STREAM_MAXLEN)
+                    .arg("*")
+                    .arg(STREAM_PAYLOAD_FIELD)
+                    .arg(payload)
+                    .query::<String>(&mut co)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Waits until the queue is drained, at most `timeout`. Returns false if messages are still pending.
+pub async fn flush(timeout: Duration) -> bool {
+    let Some(publisher) = PUBLISHER.get() else {
+        return true;
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if publisher.queue.lock().unwrap().is_empty() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            let depth = publisher.queue.lock().unwrap().len();
+            tracing::warn!("📕 {} event(s) still queued for Redis at exit", depth);
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Publishes the queue in order, reconnecting with exponential backoff whenever Redis fails.
+async fn drain(queue: Arc<Mutex<PublishQueue>>, notify: Arc<Notify>) {
+    let transport = TRANSPORT.get().copied().unwrap_or_default();
+    let mut backoff = PUBLISH_BACKOFF_MIN_MS;
+    let mut failed = false;
+    loop {
+        let mut co = match crate::data::helpers::connect().await {
+            Ok(co) => co,
+            Err(e) => {
+                let depth = queue.lock().unwrap().len();
+                tracing::warn!("📕 Redis unreachable ({}), {} event(s) queued. Retrying in {} ms", e, depth, backoff);
+                failed = true;
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                backoff = (backoff * 2).min(PUBLISH_BACKOFF_MAX_MS);
+                continue;
+            }
+        };
+        {
+            let mut queue = queue.lock().unwrap();
+            queue.stats.connected = true;
+            if failed {
+                queue.stats.reconnects += 1;
+                tracing::info!("📕 Redis reconnected, replaying {} queued event(s)", queue.len());
+            }
+        }
+        backoff = PUBLISH_BACKOFF_MIN_MS;
+        failed = false;
+        loop {
+            let next = queue.lock().unwrap().front();
+            let Some(message) = next else {
+                notify.notified().await;
+                continue;
+            };
+            // Delivered once on every destination, a destination failing replays the whole message
+            let mut sent = Ok(());
+            match transport {
+                EventsTransport::PubSub => {
+                    for channel in message.channels.iter() {
+                        sent = co.publish::<&str, &str, ()>(channel, &message.payload).await;
+                        if sent.is_err() {
+                            break;
+                        }
+                    }
+                }
+                EventsTransport::Streams => {
+                    for stream in message.streams.iter() {
+                        sent = crate::data::helpers::xadd(&mut co, stream, &message.payload, EVENT_STREAM_MAXLEN).await.map(|_| ());
+                        if sent.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            match sent {
+                Ok(_) => queue.lock().unwrap().delivered(),
+                Err(e) => {
+                    tracing::warn!("📕 Publish failed: {}, reconnecting", e);
+                    queue.lock().unwrap().stats.connected = false;
+                    failed = true;
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Price Retention Module
+//!
+//! The price table gets one JSON row per published block and instance. Past `price_downsample_after_days`,
+//! the raw rows of an instance are aggregated into one row per `price_downsample_minutes` bucket (OHLC of the
+//! reference price, min/max of each component), and past `price_retention_days` rows are deleted.
+//! A bucket is inserted and its raw rows deleted in one transaction, and aggregated rows are never aggregated
+//! again, so a run interrupted or repeated leaves the table consistent. The rows are read by pages of
+//! `PRICE_RETENTION_PAGE_ROWS`, keyed by creation time then id, so a run never holds the whole backlog in memory.
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDateTime};
+use sea_orm::{prelude::Uuid, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait};
+use serde_json::json;
+
+use crate::{
+    data::neon::Neon,
+    entity::price,
+    types::{
+        config::MoniEnvConfig,
+        moni::{ComponentPriceRange, DownsampledPrices, NewPricesMessage, PriceOhlc},
+    },
+    utils::constants::{PRICE_RETENTION_INTERVAL_SECS, PRICE_RETENTION_PAGE_ROWS},
+};
+
+/// Rows touched by one run.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetentionReport {
+    pub compacted: usize,   // Raw rows replaced by an aggregate
+    pub downsampled: usize, // Aggregated rows inserted
+    pub purged: u64,        // Rows older than the retention deleted
+}
+
+/// Raw rows of one instance in one bucket, and their aggregate.
+#[derive(Debug, Clone)]
+pub struct PriceBucket {
+    pub instance_id: String,
+    pub start: NaiveDateTime,
+    pub ids: Vec<String>,
+    pub prices: DownsampledPrices,
+}
+
+/// Start of the bucket of `minutes` containing `time`.
+pub fn bucket_start(time: NaiveDateTime, minutes: u64) -> NaiveDateTime {
+    let width = (minutes.max(1) * 60) as i64;
+    let seconds = time.and_utc().timestamp();
+    DateTime::from_timestamp(seconds - seconds.rem_euclid(width), 0).unwrap_or_default().naive_utc()
+}
+
+/// Groups the raw price rows per instance and bucket, oldest first. Aggregated rows and rows that do not parse are left out.
+pub fn downsample(rows: &[price::Model], minutes: u64) -> Vec<PriceBucket> {
+    let mut sorted = rows.iter().collect::<Vec<&price::Model>>();
+    sorted.sort_by_key(|row| row.created_at);
+    let mut buckets: BTreeMap<(String, NaiveDateTime), PriceBucket> = BTreeMap::new();
+    for row in sorted {
+        if row.value.get("downsampled").is_some() {
+            continue;
+        }
+        let Ok(msg) = serde_json::from_value::<NewPricesMessage>(row.value.clone()) else {
+            tracing::warn!("🐘 Price row {} does not parse, kept as is", row.id);
+            continue;
+        };
+        let start = bucket_start(row.created_at, minutes);
+        let price = msg.reference_price;
+        let bucket = buckets.entry((row.instance_id.clone(), start)).or_insert_with(|| PriceBucket {
+            instance_id: row.instance_id.clone(),
+            start,
+            ids: vec![],
+            prices: DownsampledPrices {
+                identifier: msg.identifier.clone(),
+                downsampled: true,
+                bucket_minutes: minutes,
+                rows: 0,
+                first_block: msg.block,
+                last_block: msg.block,
+                reference_price: PriceOhlc {
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                },
+                components: vec![],
+                max_stream_lag_blocks: None,
+            },
+        });
+        bucket.ids.push(row.id.clone());
+        let prices = &mut bucket.prices;
+        prices.rows += 1;
+        prices.first_block = prices.first_block.min(msg.block);
+        prices.last_block = prices.last_block.max(msg.block);
+        prices.reference_price.high = prices.reference_price.high.max(price);
+        prices.reference_price.low = prices.reference_price.low.min(price);
+        prices.reference_price.close = price;
+        prices.max_stream_lag_blocks = prices.max_stream_lag_blocks.max(msg.stream_lag_blocks);
+        for component in msg.components.iter() {
+            match prices.components.iter_mut().find(|range| range.address == component.address) {
+                Some(range) => {
+                    range.min = range.min.min(component.price);
+                    range.max = range.max.max(component.price);
+                }
+                None => prices.components.push(ComponentPriceRange {
+                    address: component.address.clone(),
+                    r#type: component.r#type.clone(),
+                    min: component.price,
+                    max: component.price,
+                }),
+            }
+        }
+    }
+    let mut buckets = buckets.into_values().collect::<Vec<PriceBucket>>();
+    buckets.sort_by_key(|bucket| bucket.start);
+    buckets
+}
+
+/// Purges the rows older than the retention, then aggregates the raw rows of the buckets ended before the downsample horizon.
+pub async fn compact(db: &DatabaseConnection, env: &MoniEnvConfig, now: NaiveDateTime) -> Result<RetentionReport, DbErr> {
+    compact_paged(db, env, now, PRICE_RETENTION_PAGE_ROWS).await
+}
+
+/// `compact`, reading the rows to aggregate by pages of `page_rows`.
+///
+/// The rows of the last bucket of a full page are left to the next page, so a bucket is aggregated once. A bucket
+/// larger than a page is aggregated page by page, into several rows.
+pub async fn compact_paged(db: &DatabaseConnection, env: &MoniEnvConfig, now: NaiveDateTime, page_rows: u64) -> Result<RetentionReport, DbErr> {
+    let mut report = RetentionReport::default();
+    if env.price_retention_days > 0 {
+        let horizon = now - Duration::days(env.price_retention_days as i64);
+        report.purged = price::Entity::delete_many().filter(price::Column::CreatedAt.lt(horizon)).exec(db).await?.rows_affected;
+    }
+    if env.price_downsample_after_days > 0 {
+        // Whole buckets only, so a bucket is never aggregated twice
+        let horizon = bucket_start(now - Duration::days(env.price_downsample_after_days as i64), env.price_downsample_minutes);
+        let page_rows = page_rows.max(1);
+        let mut cursor: Option<(NaiveDateTime, String)> = None;
+        loop {
+            let mut query = price::Entity::find().filter(price::Column::CreatedAt.lt(horizon));
+            if let Some((created_at, id)) = cursor.clone() {
+                query = query.filter(
+                    Condition::any()
+                        .add(price::Column::CreatedAt.gt(created_at))
+                        .add(Condition::all().add(price::Column::CreatedAt.eq(created_at)).add(price::Column::Id.gt(id))),
+                );
+            }
+            let mut rows = query.order_by_asc(price::Column::CreatedAt).order_by_asc(price::Column::Id).limit(page_rows).all(db).await?;
+            let full = rows.len() as u64 == page_rows;
+            if full {
+                // The last bucket may go on in the next page
+                let last = rows.last().map(|row| bucket_start(row.created_at, env.price_downsample_minutes));
+                let complete = rows.iter().filter(|row| Some(bucket_start(row.created_at, env.price_downsample_minutes)) != last).count();
+                if complete > 0 {
+                    rows.truncate(complete);
+                }
+            }
+            cursor = rows.last().map(|row| (row.created_at, row.id.clone()));
+            aggregate(db, &rows, env.price_downsample_minutes, now, &mut report).await?;
+            if !full {
+                break;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Replaces the raw rows of each bucket of `rows` by their aggregate, one transaction per bucket.
+async fn aggregate(db: &DatabaseConnection, rows: &[price::Model], minutes: u64, now: NaiveDateTime, report: &mut RetentionReport) -> Result<(), DbErr> {
+    for bucket in downsample(rows, minutes) {
+        let txn = db.begin().await?;
+        price::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            created_at: Set(bucket.start),
+            updated_at: Set(now),
+            instance_id: Set(bucket.instance_id.clone()),
+            value: Set(json!(bucket.prices)),
+        }
+        .insert(&txn)
+        .await?;
+        price::Entity::delete_many().filter(price::Column::Id.is_in(bucket.ids.clone())).exec(&txn).await?;
+        txn.commit().await?;
+        report.compacted += bucket.ids.len();
+        report.downsampled += 1;
+    }
+    Ok(())
+}
+
+/// Spawns the retention job, every `PRICE_RETENTION_INTERVAL_SECS`. Does nothing when both horizons are 0.
+pub fn schedule(neon: Neon, env: MoniEnvConfig) {
+    if env.price_retention_days == 0 && env.price_downsample_after_days == 0 {
+        tracing::info!("🐘 Price retention disabled, the price table is kept whole");
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            match compact(&neon.db().await, &env, chrono::Utc::now().naive_utc()).await {
+                Ok(report) => tracing::info!(
+                    "🐘 Price retention: {} raw rows compacted into {} rows, {} rows purged",
+                    report.compacted,
+                    report.downsampled,
+                    report.purged
+                ),
+                Err(err) => {
+                    tracing::error!("🐘 Price retention failed: {}", err);
+                    neon.failed();
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(PRICE_RETENTION_INTERVAL_SECS)).await;
+        }
+    });
+}
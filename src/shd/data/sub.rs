@@ -1,31 +1,78 @@
-use crate::types::config::MoniEnvConfig;
-use crate::types::moni::{MessageType, NewInstanceMessage, NewPricesMessage, NewTradeMessage, ParsedMessage, RedisMessage};
-use crate::utils::constants::CHANNEL_REDIS;
-use serde_json;
+use crate::data::dead_letter::deliver;
+use crate::data::helpers::{xack, xclaim, xgroup, xreadgroup};
+use crate::data::keys;
+use crate::data::neon::Neon;
+use crate::maker::control::Control;
+use crate::types::config::strip_unknown_config_fields;
+use crate::types::moni::{
+    schema_major, ControlCommand, ControlMessage, EndInstanceMessage, Envelope, MessageType, NewAlertMessage, NewInstanceMessage, NewPnlMessage, NewPricesMessage, NewTradeMessage, ParsedMessage,
+    RedisMessage,
+};
+use crate::utils::constants::{EVENTS_CONSUMER_GROUP, EVENT_SCHEMA_VERSION};
+use serde_json::{self, Value};
 
 /// Parses a JSON string from Redis into a strongly-typed ParsedMessage.
+///
+/// Reads both the versioned envelope and the previous format, so a rolling deploy drops no event. Envelopes of another
+/// major version are rejected (and dead-lettered), unknown kinds are returned as `ParsedMessage::Unknown`.
 pub fn parse(value: &str) -> Result<ParsedMessage, String> {
-    let rdmsg: RedisMessage = serde_json::from_str(value).map_err(|e| format!("Failed to parse Redis message: {}", e))?;
+    let json: Value = serde_json::from_str(value).map_err(|e| format!("Failed to parse Redis message: {}", e))?;
+    if json.get("schema_version").is_none() {
+        let rdmsg: RedisMessage = serde_json::from_value(json).map_err(|e| format!("Failed to parse Redis message: {}", e))?;
+        return decode(rdmsg.message, rdmsg.data);
+    }
+    let envelope: Envelope = serde_json::from_value(json).map_err(|e| format!("Failed to parse Redis envelope: {}", e))?;
+    if envelope.major() != schema_major(EVENT_SCHEMA_VERSION) {
+        return Err(format!("Unsupported schema version {} (reads {})", envelope.schema_version, EVENT_SCHEMA_VERSION));
+    }
+    match MessageType::from_kind(&envelope.kind) {
+        Some(kind) => decode(kind, envelope.payload),
+        None => Ok(ParsedMessage::Unknown(Value::String(envelope.kind))),
+    }
+}
 
-    match rdmsg.message {
+/// Deserializes the payload of a message of a known type.
+fn decode(kind: MessageType, mut data: Value) -> Result<ParsedMessage, String> {
+    match kind {
         MessageType::Ping => Ok(ParsedMessage::Ping),
         MessageType::NewInstance => {
-            let msg: NewInstanceMessage = serde_json::from_value(rdmsg.data).map_err(|e| format!("Failed to parse NewInstance message: {}", e))?;
+            if let Some(config) = data.get_mut("config") {
+                strip_unknown_config_fields(config);
+            }
+            let msg: NewInstanceMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse NewInstance message: {}", e))?;
             Ok(ParsedMessage::NewInstance(msg))
         }
         MessageType::NewTrade => {
-            let msg: NewTradeMessage = serde_json::from_value(rdmsg.data).map_err(|e| format!("Failed to parse NewTrade message: {}", e))?;
+            let msg: NewTradeMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse NewTrade message: {}", e))?;
             Ok(ParsedMessage::NewTrade(msg))
         }
         MessageType::NewPrices => {
-            let msg: NewPricesMessage = serde_json::from_value(rdmsg.data).map_err(|e| format!("Failed to parse NewPrices message: {}", e))?;
+            let msg: NewPricesMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse NewPrices message: {}", e))?;
             Ok(ParsedMessage::NewPrices(msg))
         }
+        MessageType::NewPnl => {
+            let msg: NewPnlMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse NewPnl message: {}", e))?;
+            Ok(ParsedMessage::NewPnl(msg))
+        }
+        MessageType::NewAlert => {
+            let msg: NewAlertMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse NewAlert message: {}", e))?;
+            Ok(ParsedMessage::NewAlert(msg))
+        }
+        MessageType::EndInstance => {
+            let msg: EndInstanceMessage = serde_json::from_value(data).map_err(|e| format!("Failed to parse EndInstance message: {}", e))?;
+            Ok(ParsedMessage::EndInstance(msg))
+        }
     }
 }
 
-/// Continuously listens to Redis pub/sub channel for market maker events.
-pub async fn listen(env: MoniEnvConfig) {
+/// True for a pre-v1 message copied from an envelope by a maker in compatibility mode: the envelope itself is read.
+pub fn mirrored(payload: &str) -> bool {
+    serde_json::from_str::<RedisMessage>(payload).is_ok_and(|message| message.mirrored)
+}
+
+/// Continuously listens to the Redis pub/sub channels of every instance for market maker events, and to the legacy
+/// global channel in compatibility mode, for the makers not upgraded yet.
+pub async fn listen(neon: Neon) {
     let Ok(client) = crate::data::helpers::pubsub() else {
         tracing::error!("Error while getting connection 3");
         return;
@@ -37,12 +84,19 @@ pub async fn listen(env: MoniEnvConfig) {
     };
 
     let mut pubsub = conn.as_pubsub();
-    tracing::info!("Redis pub-sub channel: '{}'", CHANNEL_REDIS);
+    tracing::info!("Redis pub-sub channels: '{}'", keys::channel_events_pattern());
 
-    let Ok(_) = pubsub.subscribe(CHANNEL_REDIS) else {
-        tracing::error!("Failed to subscribe to channel");
+    let Ok(_) = pubsub.psubscribe(keys::channel_events_pattern()) else {
+        tracing::error!("Failed to subscribe to channels");
         return;
     };
+    if keys::legacy() {
+        tracing::info!("Redis legacy pub-sub channel: '{}'", keys::legacy::channel_events());
+        let Ok(_) = pubsub.subscribe(keys::legacy::channel_events()) else {
+            tracing::error!("Failed to subscribe to the legacy channel");
+            return;
+        };
+    }
 
     loop {
         let Ok(msg) = pubsub.get_message() else {
@@ -56,17 +110,145 @@ pub async fn listen(env: MoniEnvConfig) {
         };
 
         // tracing::trace!("New message received (size: {})", payload.len());
+        if mirrored(&payload) {
+            continue;
+        }
 
-        match parse(&payload) {
-            Ok(parsed_message) => {
-                crate::data::neon::handle(&parsed_message, env.clone()).await;
-            }
-            Err(e) => {
-                tracing::error!("Failed to parse message: {}", e);
-            }
+        // Pub/sub cannot replay a message: failures are dead-lettered, and lost only if the database is down
+        if let Err(e) = deliver(&neon, &payload).await {
+            tracing::error!("Message lost, neither stored nor dead-lettered: {}", e);
         }
 
         // Sleep for 100ms ?
         tokio::time::sleep(std::time::Duration::from_millis(250)).await;
     }
 }
+
+/// Consumer name of this monitor in the group, stable across restarts of the same container.
+pub fn consumer() -> String {
+    std::env::var("HOSTNAME").ok().filter(|h| !h.is_empty()).unwrap_or_else(|| "monitor".to_string())
+}
+
+/// Continuously consumes the event streams (streams transport), and the legacy ones in compatibility mode, reconnecting
+/// on failure.
+///
+/// Entries are acknowledged only once stored (or dead-lettered), so a monitor restart or a database outage loses nothing:
+/// on startup, the entries left pending by any consumer of the group are claimed and handled first.
+pub async fn consume(neon: Neon) {
+    let mut keys = MessageType::ALL.iter().map(|kind| kind.stream()).collect::<Vec<String>>();
+    if keys::legacy() {
+        keys.extend(MessageType::ALL.iter().map(keys::legacy::stream));
+    }
+    let consumer = consumer();
+    tracing::info!("Redis streams: {} (group '{}', consumer '{}')", keys.join(", "), EVENTS_CONSUMER_GROUP, consumer);
+    loop {
+        if let Err(e) = drain(&neon, &keys, &consumer).await {
+            tracing::error!("Event streams error: {}. Reconnecting in 5 seconds", e);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+}
+
+/// Creates the group, claims the pending entries, then handles entries until Redis fails.
+async fn drain(neon: &Neon, keys: &[String], consumer: &str) -> Result<(), String> {
+    let mut co = crate::data::helpers::connect().await.map_err(|e| e.to_string())?;
+    for key in keys.iter() {
+        xgroup(&mut co, key, EVENTS_CONSUMER_GROUP).await.map_err(|e| e.to_string())?;
+        let claimed = xclaim(&mut co, key, EVENTS_CONSUMER_GROUP, consumer).await.map_err(|e| e.to_string())?;
+        if claimed > 0 {
+            tracing::info!("Claimed {} pending entries of {}", claimed, key);
+        }
+    }
+    loop {
+        // Entries delivered but not acknowledged first (claimed, or failed to store), then new ones
+        let mut entries = xreadgroup(&mut co, keys, EVENTS_CONSUMER_GROUP, consumer, true, 0).await.map_err(|e| e.to_string())?;
+        let pending = !entries.is_empty();
+        if !pending {
+            entries = xreadgroup(&mut co, keys, EVENTS_CONSUMER_GROUP, consumer, false, 5_000).await.map_err(|e| e.to_string())?;
+        }
+        for entry in entries {
+            if mirrored(entry.payload.as_deref().unwrap_or_default()) {
+                xack(&mut co, &entry.key, EVENTS_CONSUMER_GROUP, &entry.id).await.map_err(|e| e.to_string())?;
+                continue;
+            }
+            // Stored or dead-lettered (unparseable, or still failing after the retries): acknowledged either way
+            match deliver(neon, entry.payload.as_deref().unwrap_or_default()).await {
+                Ok(_) => xack(&mut co, &entry.key, EVENTS_CONSUMER_GROUP, &entry.id).await.map_err(|e| e.to_string())?,
+                Err(e) => {
+                    // Database down: kept pending, retried after a pause, in order
+                    tracing::warn!("Entry {} of {} not stored ({}), retrying in 5 seconds", entry.id, entry.key, e);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Parses an operator command from the control channel.
+///
+/// Accepts a JSON `ControlMessage` ({"command": "pause", "identifier": "..."}) or a bare
+/// command word ("pause", "resume", "kill", "refresh_inventory") targeting every instance.
+pub fn parse_control(value: &str) -> Result<ControlMessage, String> {
+    let value = value.trim();
+    if value.starts_with('{') {
+        return serde_json::from_str(value).map_err(|e| format!("Failed to parse control message: {}", e));
+    }
+    let command = match value.to_lowercase().as_str() {
+        "pause" => ControlCommand::Pause,
+        "resume" => ControlCommand::Resume,
+        "kill" => ControlCommand::Kill,
+        "refresh_inventory" => ControlCommand::RefreshInventory,
+        other => return Err(format!("Unknown control command: '{}'", other)),
+    };
+    Ok(ControlMessage { command, identifier: None })
+}
+
+/// Spawns the control channel listener, applying operator commands to the matching instances.
+///
+/// Runs on a blocking thread (sync pub-sub connection) and reconnects on failure.
+pub fn control(controls: Vec<(String, Control)>) {
+    tracing::info!("Redis control channels: '{}' ({} instances)", keys::control_channels().join("', '"), controls.len());
+    tokio::task::spawn_blocking(move || loop {
+        if let Err(e) = follow(&controls) {
+            tracing::error!("Control channel error: {}. Reconnecting in 5 seconds", e);
+        }
+        if controls.iter().all(|(_, control)| control.is_killed()) {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    });
+}
+
+/// Subscribes to the control channel and applies commands until the connection fails or every instance is killed.
+fn follow(controls: &[(String, Control)]) -> Result<(), String> {
+    let client = crate::data::helpers::pubsub().map_err(|e| e.to_string())?;
+    let mut conn = client.get_connection().map_err(|e| e.to_string())?;
+    let mut pubsub = conn.as_pubsub();
+    for channel in keys::control_channels() {
+        pubsub.subscribe(channel).map_err(|e| e.to_string())?;
+    }
+    loop {
+        let msg = pubsub.get_message().map_err(|e| e.to_string())?;
+        let Ok(payload) = msg.get_payload::<String>() else {
+            tracing::error!("Error while getting control payload");
+            continue;
+        };
+        let message = match parse_control(&payload) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::error!("{}", e);
+                continue;
+            }
+        };
+        for (identifier, control) in controls.iter() {
+            if message.identifier.as_ref().is_none_or(|target| target == identifier) {
+                let state = control.apply(message.command);
+                tracing::warn!("🕹️  Control command {:?} for {}: now {}", message.command, identifier, state.as_str());
+            }
+        }
+        if controls.iter().all(|(_, control)| control.is_killed()) {
+            return Ok(());
+        }
+    }
+}
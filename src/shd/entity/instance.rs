@@ -36,6 +36,8 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     Configuration,
+    #[sea_orm(has_many = "super::pnl::Entity")]
+    Pnl,
     #[sea_orm(has_many = "super::price::Entity")]
     Price,
     #[sea_orm(has_many = "super::trade::Entity")]
@@ -48,6 +50,12 @@ impl Related<super::configuration::Entity> for Entity {
     }
 }
 
+impl Related<super::pnl::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pnl.def()
+    }
+}
+
 impl Related<super::price::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Price.def()
@@ -3,6 +3,8 @@
 pub mod prelude;
 
 pub mod configuration;
+pub mod dead_letter;
 pub mod instance;
+pub mod pnl;
 pub mod price;
 pub mod trade;
@@ -1,6 +1,8 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
 pub use super::configuration::Entity as Configuration;
+pub use super::dead_letter::Entity as DeadLetter;
 pub use super::instance::Entity as Instance;
+pub use super::pnl::Entity as Pnl;
 pub use super::price::Entity as Price;
 pub use super::trade::Entity as Trade;
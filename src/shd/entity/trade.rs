@@ -16,6 +16,8 @@ pub struct Model {
     pub instance_id: String,
     #[sea_orm(column_type = "JsonBinary")]
     pub values: Json,
+    #[sea_orm(column_name = "idempotencyKey", column_type = "Text", nullable)]
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
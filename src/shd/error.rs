@@ -3,8 +3,13 @@
 //! Centralized error handling for the market maker application.
 //! This module defines the main error types and provides a unified error handling
 //! system for configuration, database, network, and execution errors.
+use alloy::providers::{PendingTransactionError, WatchTxError};
+use alloy::transports::{RpcError, TransportErrorKind};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::maker::tycho::TychoApiError;
+
 /// Main error type for market maker operations.
 #[derive(Error, Debug)]
 pub enum MarketMakerError {
@@ -20,6 +25,9 @@ pub enum MarketMakerError {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("Authentication error: {0}")]
+    Auth(String),
+
     #[error("Token not found: {0}")]
     TokenNotFound(String),
 
@@ -29,6 +37,9 @@ pub enum MarketMakerError {
     #[error("Execution error: {0}")]
     Execution(String),
 
+    #[error("Build error: {0}")]
+    Build(String),
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -42,8 +53,186 @@ pub enum MarketMakerError {
 /// Type alias for Result with MarketMakerError.
 pub type Result<T> = std::result::Result<T, MarketMakerError>;
 
+impl From<TychoApiError> for MarketMakerError {
+    fn from(err: TychoApiError) -> Self {
+        match err {
+            TychoApiError::Auth(_) => MarketMakerError::Auth(err.to_string()),
+            TychoApiError::Other(e) => MarketMakerError::Network(format!("Tycho API: {}", e)),
+        }
+    }
+}
+
 impl From<std::env::VarError> for MarketMakerError {
     fn from(err: std::env::VarError) -> Self {
         MarketMakerError::EnvVar(err.to_string())
     }
 }
+
+/// Failure of the execution pipeline (simulation, broadcast), classified for the policy of the caller. Each variant
+/// displays its message as is, the text recorded with the trades (`broadcast_error`) being unchanged by the variant.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExecError {
+    #[error("{message}")]
+    Rpc { message: String, retryable: bool },
+
+    #[error("{reason}")]
+    Simulation { reason: String },
+
+    // Building or signing a transaction
+    #[error("{0}")]
+    Encoding(String),
+
+    #[error("{0}")]
+    NonceConflict(String),
+
+    #[error("{0}")]
+    InsufficientFunds(String),
+
+    #[error("{0}")]
+    BuilderRejected(String),
+
+    #[error("{0}")]
+    Timeout(String),
+}
+
+/// Variant of an `ExecError`, recorded with the trades next to its text (`error_kind`) to rebuild the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecErrorKind {
+    Rpc,
+    RetryableRpc,
+    Simulation,
+    Encoding,
+    NonceConflict,
+    InsufficientFunds,
+    BuilderRejected,
+    Timeout,
+}
+
+/// What the caller does with a failed execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecPolicy {
+    Retry, // Transient, a next block tries again, logged only
+    Alert, // Needs the operator (funds, endpoints, builders)
+    Abort, // The trade itself failed, dropped and logged
+}
+
+impl ExecError {
+    /// Error of a provider call, prefixed by `context`. A JSON-RPC error response is the node rejecting the request,
+    /// told apart by its message (nodes share the -32000 code), a transport error is retryable when alloy says so.
+    pub fn transport(context: &str, error: &RpcError<TransportErrorKind>) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error {
+            RpcError::ErrorResp(payload) => {
+                let reason = payload.message.to_lowercase();
+                let any = |patterns: &[&str]| patterns.iter().any(|pattern| reason.contains(pattern));
+                if payload.code == 3 || any(&["execution reverted"]) {
+                    ExecError::Simulation { reason: message }
+                } else if any(&["nonce too low", "nonce too high", "replacement transaction underpriced", "already known"]) {
+                    ExecError::NonceConflict(message)
+                } else if any(&["insufficient funds"]) {
+                    ExecError::InsufficientFunds(message)
+                } else {
+                    ExecError::Rpc {
+                        message,
+                        retryable: payload.is_retry_err(),
+                    }
+                }
+            }
+            RpcError::Transport(kind) => ExecError::Rpc {
+                message,
+                retryable: kind.is_retry_err(),
+            },
+            _ => ExecError::Rpc { message, retryable: false },
+        }
+    }
+
+    /// Error of a pending transaction (receipt), prefixed by `context`: a timeout of the watcher, or of its provider call.
+    pub fn pending(context: &str, error: &PendingTransactionError) -> Self {
+        match error {
+            PendingTransactionError::TransportError(e) => Self::transport(context, e),
+            PendingTransactionError::TxWatcher(WatchTxError::Timeout) => ExecError::Timeout(format!("{}: {}", context, error)),
+            _ => ExecError::Rpc {
+                message: format!("{}: {}", context, error),
+                retryable: false,
+            },
+        }
+    }
+
+    /// Classifies the text of a provider or RPC error, e.g. `nonce too low` or `HTTP error 429`. Only for the text of an
+    /// error recorded without its variant (`error_kind`), the call sites build theirs. An unknown error is a non-retryable RPC error.
+    pub fn classify(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let lower = message.to_lowercase();
+        let any = |patterns: &[&str]| patterns.iter().any(|pattern| lower.contains(pattern));
+        if any(&["nonce too low", "nonce too high", "replacement transaction underpriced", "already known"]) {
+            ExecError::NonceConflict(message)
+        } else if any(&["insufficient funds"]) {
+            ExecError::InsufficientFunds(message)
+        } else if any(&["timed out", "timeout", "deadline has elapsed", "deadline passed"]) {
+            ExecError::Timeout(message)
+        } else if any(&["all builders rejected"]) {
+            ExecError::BuilderRejected(message)
+        } else if any(&["execution reverted", "simulation failed"]) {
+            ExecError::Simulation { reason: message }
+        } else if any(&[
+            "http error 429",
+            "http error 502",
+            "http error 503",
+            "http error 504",
+            "too many requests",
+            "rate limit",
+            "connection refused",
+            "connection reset",
+            "error sending request",
+        ]) {
+            ExecError::Rpc { message, retryable: true }
+        } else {
+            ExecError::Rpc { message, retryable: false }
+        }
+    }
+
+    /// Variant of the error, as recorded.
+    pub fn kind(&self) -> ExecErrorKind {
+        match self {
+            ExecError::Rpc { retryable: false, .. } => ExecErrorKind::Rpc,
+            ExecError::Rpc { retryable: true, .. } => ExecErrorKind::RetryableRpc,
+            ExecError::Simulation { .. } => ExecErrorKind::Simulation,
+            ExecError::Encoding(_) => ExecErrorKind::Encoding,
+            ExecError::NonceConflict(_) => ExecErrorKind::NonceConflict,
+            ExecError::InsufficientFunds(_) => ExecErrorKind::InsufficientFunds,
+            ExecError::BuilderRejected(_) => ExecErrorKind::BuilderRejected,
+            ExecError::Timeout(_) => ExecErrorKind::Timeout,
+        }
+    }
+
+    /// Error of a recorded variant and text.
+    pub fn of(kind: ExecErrorKind, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match kind {
+            ExecErrorKind::Rpc => ExecError::Rpc { message, retryable: false },
+            ExecErrorKind::RetryableRpc => ExecError::Rpc { message, retryable: true },
+            ExecErrorKind::Simulation => ExecError::Simulation { reason: message },
+            ExecErrorKind::Encoding => ExecError::Encoding(message),
+            ExecErrorKind::NonceConflict => ExecError::NonceConflict(message),
+            ExecErrorKind::InsufficientFunds => ExecError::InsufficientFunds(message),
+            ExecErrorKind::BuilderRejected => ExecError::BuilderRejected(message),
+            ExecErrorKind::Timeout => ExecError::Timeout(message),
+        }
+    }
+
+    /// Policy of the variant, the one place deciding between retrying, alerting and aborting.
+    pub fn policy(&self) -> ExecPolicy {
+        match self {
+            ExecError::Rpc { retryable: true, .. } | ExecError::Timeout(_) | ExecError::NonceConflict(_) => ExecPolicy::Retry,
+            ExecError::Rpc { retryable: false, .. } | ExecError::InsufficientFunds(_) | ExecError::BuilderRejected(_) => ExecPolicy::Alert,
+            ExecError::Simulation { .. } | ExecError::Encoding(_) => ExecPolicy::Abort,
+        }
+    }
+}
+
+impl From<ExecError> for MarketMakerError {
+    fn from(err: ExecError) -> Self {
+        MarketMakerError::Execution(err.to_string())
+    }
+}
@@ -8,5 +8,7 @@ pub mod entity;
 pub mod error;
 pub mod maker;
 pub mod opti;
+#[cfg(feature = "test-utils")]
+pub mod testing;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,51 @@
+//! Router Allowance Module
+//!
+//! Without `infinite_approval` nor Permit2, a swap is preceded by an approval of its input amount to the router, ~45k
+//! gas and a nonce. The router allowances of the base and quote tokens are read along with the balance watch and kept
+//! up to date from our own trades, so that the approval is only built when the cached allowance does not cover the
+//! input. A token never read, or left unknown by a failed trade, is approved as before.
+use std::collections::HashMap;
+
+/// Router allowances of the wallet, owned by the market maker so they survive iterations.
+#[derive(Debug, Clone, Default)]
+pub struct RouterAllowances {
+    entries: HashMap<String, u128>, // Token address (lowercase) => allowance of the router, raw amount
+}
+
+impl RouterAllowances {
+    /// Stores an allowance read from chain.
+    pub fn store(&mut self, token: &str, allowance: u128) {
+        self.entries.insert(token.to_lowercase(), allowance);
+    }
+
+    /// Returns true when the cached allowance of the token covers `amount`, false if unknown.
+    pub fn covers(&self, token: &str, amount: u128) -> bool {
+        self.entries.get(&token.to_lowercase()).is_some_and(|allowance| *allowance >= amount)
+    }
+
+    /// Settles a swap of ours selling up to `amount` of the token. A swap spending the cached allowance lowers it (an
+    /// uint256 max allowance is never spent), anything else (approval sent, failed or unknown outcome) drops the entry
+    /// until the next read.
+    pub fn settle(&mut self, token: &str, amount: u128, approved: bool, succeeded: bool) {
+        let id = token.to_lowercase();
+        match self.entries.get_mut(&id) {
+            Some(allowance) if !approved && succeeded => {
+                if *allowance != u128::MAX {
+                    *allowance = allowance.saturating_sub(amount);
+                }
+            }
+            _ => {
+                self.entries.remove(&id);
+            }
+        }
+    }
+
+    /// Drops every cached allowance, the tokens are approved until the next read.
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
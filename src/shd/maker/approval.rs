@@ -0,0 +1,127 @@
+//! Manual Approval Module
+//!
+//! Sets the allowance of a spender (the Tycho router by default) on the base, quote or any token for
+//! `maker approve`: the approval is simulated from the wallet, broadcast with the signer of the exec
+//! strategies, then the allowance is read back once the receipt is in.
+use std::{fmt, time::Instant};
+
+use alloy_primitives::{Address, U256};
+use num_traits::ToPrimitive;
+
+use crate::{
+    opti::math::powered,
+    types::{
+        config::{EnvConfig, MarketMakerConfig},
+        sol::IERC20,
+    },
+    utils::{
+        evm::{allowance, approve, create_provider, RpcPool},
+        signer::WalletSigner,
+    },
+};
+
+/// Token selectors of `maker approve` naming the base or quote token of the config instead of an address.
+pub const BASE_TOKEN: &str = "base";
+pub const QUOTE_TOKEN: &str = "quote";
+
+/// Address of the token named by `token`: the base or quote of the config, or an address.
+pub fn token_address(config: &MarketMakerConfig, token: &str) -> Result<String, String> {
+    if token.eq_ignore_ascii_case(BASE_TOKEN) {
+        return Ok(config.base_token_address.clone());
+    }
+    if token.eq_ignore_ascii_case(QUOTE_TOKEN) {
+        return Ok(config.quote_token_address.clone());
+    }
+    token.parse::<Address>().map(|address| address.to_string()).map_err(|e| format!("Invalid token {}: {}", token, e))
+}
+
+/// Raw amount approved: `u128::MAX` with `max`, 0 with `revoke`, otherwise `amount` (normalized) scaled by the token decimals.
+pub fn raw_amount(amount: Option<f64>, max: bool, revoke: bool, decimals: u32) -> Result<u128, String> {
+    match (amount, max, revoke) {
+        (None, true, false) => Ok(u128::MAX),
+        (None, false, true) => Ok(0),
+        (Some(amount), false, false) if amount.is_finite() && amount > 0. => powered(amount, decimals)
+            .to_u128()
+            .ok_or_else(|| format!("Amount {} overflows a u128 with {} decimals", amount, decimals)),
+        (Some(amount), false, false) => Err(format!("Invalid amount {}, use --revoke to approve 0", amount)),
+        _ => Err("Set exactly one of --amount, --max and --revoke".to_string()),
+    }
+}
+
+/// Outcome of a manual approval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Approval {
+    pub token: String,
+    pub symbol: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: u128,
+    pub tx_hash: String,
+    pub block: u64,
+    pub status: bool,
+    pub allowance: u128, // Read back after the receipt
+}
+
+impl fmt::Display for Approval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let amount = if self.amount == u128::MAX { "max".to_string() } else { self.amount.to_string() };
+        writeln!(f, "Token      {} ({})", self.symbol, self.token)?;
+        writeln!(f, "Owner      {} | Spender {}", self.owner, self.spender)?;
+        writeln!(f, "Approved   {}", amount)?;
+        writeln!(f, "Tx         {} at block {}: {}", self.tx_hash, self.block, if self.status { "success" } else { "reverted" })?;
+        write!(f, "Allowance  {}", self.allowance)
+    }
+}
+
+/// Approves `spender` (default: `tycho_router_address`) on `token` from the signer wallet, then reads the allowance back.
+///
+/// Refused when `env.testing` is set unless `force`, the approval is simulated with an eth_call before being broadcast.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(config: &MarketMakerConfig, env: &EnvConfig, token: &str, spender: Option<&str>, amount: Option<f64>, max: bool, revoke: bool, force: bool) -> Result<Approval, String> {
+    if env.testing && !force {
+        return Err("Testing mode is on, pass --force to approve anyway".to_string());
+    }
+    let token = token_address(config, token)?;
+    let spender = spender.unwrap_or(config.tycho_router_address.as_str());
+    let spender = spender.parse::<Address>().map_err(|e| format!("Invalid spender {}: {}", spender, e))?;
+    let owner = WalletSigner::from_env(env)?.address();
+
+    let pool = RpcPool::of(config);
+    let address = token.parse::<Address>().map_err(|e| format!("Invalid token {}: {}", token, e))?;
+    let name = &token;
+    let metadata = pool.call(|url| async move {
+        let provider = create_provider(&url);
+        let contract = IERC20::new(address, &provider);
+        let symbol = contract.symbol().call().await.map_err(|e| format!("Failed to read the symbol of {}: {:?}", name, e))?;
+        let decimals = contract.decimals().call().await.map_err(|e| format!("Failed to read the decimals of {}: {:?}", name, e))?;
+        Ok::<_, String>((symbol, decimals))
+    });
+    let (symbol, decimals) = metadata.await?;
+    let raw = raw_amount(amount, max, revoke, decimals as u32)?;
+
+    // Same call from the wallet, a reverting or false approval is not broadcast. A revert is still an answer of the endpoint.
+    let url = pool.read_url();
+    let provider = create_provider(&url);
+    let start = Instant::now();
+    let simulated = IERC20::new(address, &provider).approve(spender, U256::from(raw)).from(owner).call().await;
+    pool.report(&url, start.elapsed(), simulated.as_ref().map_or_else(|e| e.as_revert_data().is_some(), |_| true));
+    match simulated {
+        Ok(true) => tracing::info!("Approval of {} {} to {} simulated", raw, symbol, spender),
+        Ok(false) => return Err(format!("Simulated approval of {} returned false", symbol)),
+        Err(e) => return Err(format!("Simulated approval of {} failed: {:?}", symbol, e)),
+    }
+
+    let receipt = approve(config.clone(), env.clone(), spender.to_string(), token.clone(), raw).await?;
+    let allowance = pool.call(|url| allowance(url, owner.to_string(), spender.to_string(), token.clone())).await?;
+    Ok(Approval {
+        token,
+        symbol,
+        owner: owner.to_string(),
+        spender: spender.to_string(),
+        amount: raw,
+        tx_hash: receipt.transaction_hash.to_string(),
+        block: receipt.block_number.unwrap_or_default(),
+        status: receipt.status(),
+        allowance,
+    })
+}
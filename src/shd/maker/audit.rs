@@ -0,0 +1,244 @@
+//! Decision Audit Module
+//!
+//! "Why didn't the bot trade at 14:32?" used to mean reading debug logs, when they were kept. With
+//! `audit_log_path` set, every evaluated block records one decision per target pool: spot, reference,
+//! spread, the gate that rejected it (or none when an order was created) and the numbers the gate looked
+//! at. Records are appended to a local JSONL file by a background writer, off the block loop, rotated to
+//! `<path>.1` past `audit_log_max_bytes`, and one in `audit_publish_every` is also published on the audit channel
+//! of the instance. `maker audit` pretty-prints the records of a time range.
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::keys, types::config::MarketMakerConfig, utils::background::BackgroundWriter};
+
+/// Gate rejecting a target pool, from the evaluation to the execution.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Gate {
+    Cooldown,       // Traded recently, by this process or the previous one
+    SanityBand,     // Spot implausibly far from the reference (quarantine)
+    WatchSpread,    // Spread within the watch threshold
+    CircuitBreaker, // Daily loss breaker open
+    Inventory,      // Nothing to sell
+    Simulation,     // Balances, optimizer or quote failed
+    ExposureLimit,  // Bought token would exceed max_token_exposure_pct
+    MinNotional,    // Worth less than min_amount_worth_usd
+    Profitability,  // Profit net of gas below the execution threshold
+    Removed,        // Component removed from the stream before the preparation of its order
+    Stale,          // Profit gone or state behind the chain head at execution time
+    Timeout,        // Past the latency budget of the block update
+}
+
+impl Gate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gate::Cooldown => "cooldown",
+            Gate::SanityBand => "sanity band",
+            Gate::WatchSpread => "watch spread",
+            Gate::CircuitBreaker => "circuit breaker",
+            Gate::Inventory => "inventory",
+            Gate::Simulation => "simulation",
+            Gate::ExposureLimit => "exposure limit",
+            Gate::MinNotional => "min notional",
+            Gate::Profitability => "profitability",
+            Gate::Removed => "removed",
+            Gate::Stale => "stale",
+            Gate::Timeout => "timeout",
+        }
+    }
+}
+
+/// Decision taken on one target pool at one block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Decision {
+    pub timestamp_ms: u64,
+    pub identifier: String,
+    pub block: u64,
+    pub pool: String,
+    pub spot: f64,
+    pub reference: f64,
+    pub spread_bps: f64,
+    pub gate: Option<Gate>, // None when an order was created
+    #[serde(default)]
+    pub values: BTreeMap<String, f64>, // Numbers compared by the gate, e.g. threshold_bps
+}
+
+/// Appending side of an audit file.
+#[derive(Debug)]
+struct Writer {
+    path: PathBuf,
+    max_bytes: u64,
+    size: u64,
+    file: Option<File>,
+}
+
+impl Writer {
+    fn open(&mut self) -> std::io::Result<&mut File> {
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_bytes {
+            self.file = None;
+            std::fs::rename(&self.path, rotated(&self.path))?;
+        }
+        let file = self.open()?;
+        writeln!(file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Audit file of one market maker, written by its background writer.
+#[derive(Debug)]
+struct Audit {
+    writer: Arc<Mutex<Writer>>,
+    background: BackgroundWriter,
+    publish_every: u64, // 0 never publishes
+    recorded: AtomicU64,
+}
+
+/// Cloneable handle on the audit file of one market maker, disabled without `audit_log_path`.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    inner: Option<Arc<Audit>>,
+}
+
+impl AuditLog {
+    pub fn new(config: &MarketMakerConfig) -> Self {
+        let Some(path) = config.audit_log_path.clone().filter(|path| !path.is_empty()) else {
+            return Self::default();
+        };
+        Self {
+            inner: Some(Arc::new(Audit {
+                writer: Arc::new(Mutex::new(Writer {
+                    path: PathBuf::from(path),
+                    max_bytes: config.audit_log_max_bytes,
+                    size: 0,
+                    file: None,
+                })),
+                background: BackgroundWriter::spawn("audit-writer"),
+                publish_every: if config.publish_events { config.audit_publish_every } else { 0 },
+                recorded: AtomicU64::new(0),
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Queues a decision to the file, and publishes it when sampled. Failures are logged, never raised.
+    pub fn record(&self, decision: &Decision) {
+        let Some(inner) = self.inner.as_ref() else {
+            return;
+        };
+        let Ok(line) = serde_json::to_string(decision) else {
+            return;
+        };
+        let recorded = inner.recorded.fetch_add(1, Ordering::Relaxed);
+        if inner.publish_every > 0 && recorded % inner.publish_every == 0 {
+            let name = keys::channel_audit(&decision.identifier);
+            crate::data::publisher::enqueue(vec![name.clone()], vec![name], line.clone(), true);
+        }
+        let writer = inner.writer.clone();
+        inner.background.run(move || {
+            let mut writer = writer.lock().unwrap();
+            if let Err(e) = writer.write(&line) {
+                tracing::warn!("📝 Failed to write the audit log {}: {}", writer.path.display(), e);
+            }
+        });
+    }
+
+    /// Waits for the decisions recorded so far to be written.
+    pub fn flush(&self) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner.background.flush();
+        }
+    }
+}
+
+/// Previous audit file, replaced at each rotation.
+pub fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Decisions of an audit file (rotated file first) within [from, to] (unix ms), oldest first. Unreadable lines are skipped.
+pub fn read(path: &str, from_ms: Option<u64>, to_ms: Option<u64>) -> Result<Vec<Decision>, String> {
+    let path = PathBuf::from(path);
+    let mut decisions = vec![];
+    let mut found = false;
+    for file in [rotated(&path), path.clone()] {
+        let Ok(file) = File::open(&file) else {
+            continue;
+        };
+        found = true;
+        for line in BufReader::new(file).lines().map_while(|line| line.ok()) {
+            if let Ok(decision) = serde_json::from_str::<Decision>(&line) {
+                if from_ms.is_none_or(|from| decision.timestamp_ms >= from) && to_ms.is_none_or(|to| decision.timestamp_ms <= to) {
+                    decisions.push(decision);
+                }
+            }
+        }
+    }
+    if !found {
+        return Err(format!("No audit file at {}", path.display()));
+    }
+    decisions.sort_by_key(|decision| decision.timestamp_ms);
+    Ok(decisions)
+}
+
+/// Unix ms of a UTC time, as RFC 3339 or `YYYY-MM-DD HH:MM[:SS]`.
+pub fn parse_time(text: &str) -> Result<u64, String> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Ok(time.timestamp_millis().max(0) as u64);
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| time.and_utc().timestamp_millis().max(0) as u64)
+        .ok_or_else(|| format!("Invalid time '{}' (expected RFC 3339 or YYYY-MM-DD HH:MM[:SS], UTC)", text))
+}
+
+/// One line per decision, for the `audit` command.
+pub fn describe(decision: &Decision) -> String {
+    let time = chrono::DateTime::from_timestamp_millis(decision.timestamp_ms as i64)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_default();
+    let outcome = match decision.gate {
+        Some(gate) => format!("rejected by {}", gate.as_str()),
+        None => "order created".to_string(),
+    };
+    let values = decision.values.iter().map(|(key, value)| format!("{}={:.4}", key, value)).collect::<Vec<String>>().join(" ");
+    format!(
+        "{} | b#{} | {} | spot {:.5} vs ref {:.5} | {:>8.2} bps | {}{}",
+        time,
+        decision.block,
+        decision.pool,
+        decision.spot,
+        decision.reference,
+        decision.spread_bps,
+        outcome,
+        if values.is_empty() { String::new() } else { format!(" ({})", values) }
+    )
+}
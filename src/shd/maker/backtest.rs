@@ -0,0 +1,521 @@
+//! Backtest Module
+//!
+//! Replays the `NewPricesMessage` rows recorded by the monitor through the decision logic, to tune spreads offline.
+//! Each row rebuilds its target pools as synthetic pools quoting the recorded spot price: a swap fills at spot minus
+//! the pool fee and an impact growing with its share of the pool liquidity (`FillModel`). `evaluate` and `readjust`
+//! then run unchanged, against the balances of the synthetic pools and a market context derived from the reference
+//! price, and the best order of each row is filled against the replayed inventory. The decisions land in the audit
+//! log of the run, counted per gate in the report.
+use std::{any::Any, collections::HashMap, fmt::Write as _, path::Path, str::FromStr, time::Instant};
+
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use tycho_common::{
+    dto::ProtocolStateDelta,
+    models::token::Token,
+    simulation::{
+        errors::{SimulationError, TransitionError},
+        protocol_sim::{Balances, GetAmountOutResult, ProtocolSim},
+    },
+    Bytes,
+};
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+use crate::{
+    entity::price,
+    maker::{
+        audit::Decision,
+        tycho::{chain, PoolBalances},
+    },
+    opti::skew::{self, InventorySkew},
+    types::{
+        config::MarketMakerConfig,
+        maker::{ComponentPriceData, ExecutionOrder, Inventory, MarketContext, MarketMaker, TradeDirection},
+        moni::NewPricesMessage,
+        tycho::ProtoSimComp,
+    },
+    utils::constants::{
+        BASIS_POINT_DENO, DEFAULT_BACKTEST_FEE_BPS, DEFAULT_BACKTEST_IMPACT_BPS_PER_PCT, DEFAULT_BACKTEST_IMPACT_EXPONENT, DEFAULT_BACKTEST_LIQUIDITY, DEFAULT_SWAP_GAS, PERCENT_MULTIPLIER,
+    },
+};
+
+/// How the synthetic pools fill a swap.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FillModel {
+    pub fee_bps: f64,            // Pool fee, taken from every fill
+    pub liquidity: f64,          // Base token on each side of a pool, the quote side holds its value at spot
+    pub impact_bps_per_pct: f64, // Average execution impact of a fill taking 1% of the liquidity
+    pub impact_exponent: f64,    // Curvature of the impact: 1 linear (constant-product like), 0.5 square root
+    pub gas_units: u64,          // Gas of a swap
+}
+
+impl Default for FillModel {
+    fn default() -> Self {
+        Self {
+            fee_bps: DEFAULT_BACKTEST_FEE_BPS,
+            liquidity: DEFAULT_BACKTEST_LIQUIDITY,
+            impact_bps_per_pct: DEFAULT_BACKTEST_IMPACT_BPS_PER_PCT,
+            impact_exponent: DEFAULT_BACKTEST_IMPACT_EXPONENT,
+            gas_units: DEFAULT_SWAP_GAS,
+        }
+    }
+}
+
+impl FillModel {
+    /// Average execution impact (fraction) of a fill worth `amount` base token, at most 100%.
+    pub fn impact(&self, amount: f64) -> f64 {
+        if self.liquidity <= 0. || amount <= 0. {
+            return 0.;
+        }
+        let pct = amount / self.liquidity * PERCENT_MULTIPLIER;
+        (self.impact_bps_per_pct * pct.powf(self.impact_exponent) / BASIS_POINT_DENO).min(1.)
+    }
+
+    /// Synthetic target pool quoting a recorded component price.
+    ///
+    /// The component is typed as a UniswapV2 pool with a `fee` attribute in bps, so the watch spread adds the model fee.
+    pub fn target(&self, cpd: &ComponentPriceData, base: &Token, quote: &Token) -> ProtoSimComp {
+        let id = Bytes::from_str(&cpd.address).unwrap_or_default();
+        let attributes = HashMap::from([("fee".to_string(), Bytes::from((self.fee_bps.round().max(0.) as u64).to_be_bytes().to_vec()))]);
+        let component = ProtocolComponent::new(
+            id,
+            cpd.r#type.clone(),
+            "uniswap_v2_pool".to_string(),
+            base.chain,
+            vec![base.clone(), quote.clone()],
+            vec![],
+            attributes,
+            Bytes::default(),
+            chrono::NaiveDateTime::default(),
+        );
+        ProtoSimComp {
+            component,
+            protosim: Box::new(SyntheticPool {
+                base: base.clone(),
+                quote: quote.clone(),
+                spot: cpd.price,
+                model: *self,
+            }),
+        }
+    }
+}
+
+/// Pool quoting a fixed spot price (quote per base), filling through the fill model.
+#[derive(Debug, Clone)]
+pub struct SyntheticPool {
+    pub base: Token,
+    pub quote: Token,
+    pub spot: f64,
+    pub model: FillModel,
+}
+
+impl ProtocolSim for SyntheticPool {
+    fn fee(&self) -> f64 {
+        self.model.fee_bps / BASIS_POINT_DENO
+    }
+
+    fn spot_price(&self, base: &Token, _quote: &Token) -> Result<f64, SimulationError> {
+        if self.spot <= 0. || !self.spot.is_finite() {
+            return Err(SimulationError::RecoverableError(format!("Invalid spot price {}", self.spot)));
+        }
+        Ok(if base.address == self.base.address { self.spot } else { 1. / self.spot })
+    }
+
+    fn get_amount_out(&self, amount_in: BigUint, token_in: &Token, token_out: &Token) -> Result<GetAmountOutResult, SimulationError> {
+        self.spot_price(&self.base, &self.quote)?;
+        let amount = amount_in.to_f64().unwrap_or(0.) / 10f64.powi(token_in.decimals as i32);
+        let selling_base = token_in.address == self.base.address;
+        let (worth_base, gross) = if selling_base { (amount, amount * self.spot) } else { (amount / self.spot, amount / self.spot) };
+        let impact = self.model.impact(worth_base);
+        let out = gross * (1. - self.fee()) * (1. - impact);
+        // The marginal price moves (1 + exponent) times the average impact, the derivative of the impacted amount
+        let shift = (impact * (1. + self.model.impact_exponent)).min(0.99);
+        let mut new_state = self.clone();
+        new_state.spot = if selling_base { self.spot * (1. - shift) } else { self.spot * (1. + shift) };
+        Ok(GetAmountOutResult {
+            amount: BigUint::from((out.max(0.) * 10f64.powi(token_out.decimals as i32)).floor() as u128),
+            gas: BigUint::from(self.model.gas_units),
+            new_state: Box::new(new_state),
+        })
+    }
+
+    fn get_limits(&self, sell_token: Bytes, _buy_token: Bytes) -> Result<(BigUint, BigUint), SimulationError> {
+        let base = BigUint::from((self.model.liquidity * 10f64.powi(self.base.decimals as i32)) as u128);
+        let quote = BigUint::from((self.model.liquidity * self.spot * 10f64.powi(self.quote.decimals as i32)) as u128);
+        Ok(if sell_token == self.base.address { (base, quote) } else { (quote, base) })
+    }
+
+    fn delta_transition(&mut self, _delta: ProtocolStateDelta, _tokens: &HashMap<Bytes, Token>, _balances: &Balances) -> Result<(), TransitionError<String>> {
+        Ok(()) // Rebuilt from each recorded row, never streamed
+    }
+
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn eq(&self, other: &dyn ProtocolSim) -> bool {
+        other.as_any().downcast_ref::<SyntheticPool>().is_some_and(|o| o.spot == self.spot && o.model == self.model)
+    }
+}
+
+/// Balances of the synthetic pools of a row, from the fill model liquidity.
+pub struct FillBalances {
+    pub model: FillModel,
+    pub base: Token,
+    pub quote: Token,
+    pub spots: HashMap<String, f64>, // Keyed by component id
+}
+
+#[async_trait]
+impl PoolBalances for FillBalances {
+    async fn get(&self, cp: &ProtocolComponent) -> Option<HashMap<String, u128>> {
+        let spot = self.spots.get(&cp.id.to_string().to_lowercase())?;
+        Some(HashMap::from([
+            (self.base.address.to_string().to_lowercase(), (self.model.liquidity * 10f64.powi(self.base.decimals as i32)) as u128),
+            (
+                self.quote.address.to_string().to_lowercase(),
+                (self.model.liquidity * spot * 10f64.powi(self.quote.decimals as i32)) as u128,
+            ),
+        ]))
+    }
+}
+
+/// Token of the replayed pair, built from the config as the Tycho token list is not fetched.
+pub fn token(config: &MarketMakerConfig, address: &str, symbol: &str, decimals: u32) -> Result<Token, String> {
+    let (chain, _) = chain(config.network_name.as_str().to_string()).ok_or_else(|| format!("Unsupported network {}", config.network_name))?;
+    Ok(Token {
+        address: Bytes::from_str(address).map_err(|e| format!("Invalid token address {}: {}", address, e))?,
+        symbol: symbol.to_string(),
+        decimals,
+        gas: vec![Some(0)],
+        chain: chain.into(),
+        quality: 100,
+        tax: 0,
+    })
+}
+
+/// Market context of a replayed row: gas at `gas_price_gwei`, token prices in ETH from the reference, one side being the gas token.
+///
+/// Without `eth_usd`, the reference is the ETH price when the base is the gas token, assuming a USD quote.
+pub fn context(config: &MarketMakerConfig, base: &Token, quote: &Token, reference: f64, block: u64, gas_price_gwei: f64, eth_usd: Option<f64>) -> Result<MarketContext, String> {
+    if reference <= 0. || !reference.is_finite() {
+        return Err(format!("Invalid reference price {} at block {}", reference, block));
+    }
+    let gas = config.gas_token_symbol.to_lowercase();
+    let base_is_gas = base.address.to_string().to_lowercase() == gas;
+    let (base_to_eth, quote_to_eth) = if base_is_gas {
+        (1., 1. / reference)
+    } else if quote.address.to_string().to_lowercase() == gas {
+        (reference, 1.)
+    } else {
+        return Err(format!("Neither {} nor {} is the gas token, their ETH prices are unknown", base.symbol, quote.symbol));
+    };
+    let eth_to_usd = match eth_usd {
+        Some(price) => price,
+        None if base_is_gas => reference,
+        None => return Err("The base token is not the gas token, the ETH price in USD must be given".to_string()),
+    };
+    let gas_price = (gas_price_gwei * 1e9) as u128;
+    Ok(MarketContext {
+        base_to_eth,
+        quote_to_eth,
+        eth_to_usd,
+        max_fee_per_gas: gas_price,
+        max_priority_fee_per_gas: 0,
+        native_gas_price: gas_price,
+        block,
+        age_ms: 0,
+    })
+}
+
+/// Replayable rows of the price table, with their unix ms, by block. Downsampled rows, and the degraded rows of a
+/// snapshot published before the stream was ready, are skipped and counted.
+pub fn rows(prices: Vec<price::Model>) -> (Vec<(u64, NewPricesMessage)>, usize) {
+    let total = prices.len();
+    let mut rows = prices
+        .into_iter()
+        .filter_map(|price| {
+            let msg = serde_json::from_value::<NewPricesMessage>(price.value).ok().filter(|msg| !msg.degraded)?;
+            Some((price.created_at.and_utc().timestamp_millis().max(0) as u64, msg))
+        })
+        .collect::<Vec<(u64, NewPricesMessage)>>();
+    rows.sort_by_key(|(_, msg)| msg.block);
+    let skipped = total - rows.len();
+    (rows, skipped)
+}
+
+/// Fill of the best order of a row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BacktestFill {
+    pub timestamp_ms: u64,
+    pub block: u64,
+    pub pool: String,
+    pub direction: TradeDirection,
+    pub selling_amount: f64, // Normalized, in the sold token
+    pub buying_amount: f64,  // Normalized, in the bought token
+    pub spot: f64,
+    pub reference: f64,
+    pub profit_bps: f64, // Net of gas, against the reference
+    pub gas_usd: f64,
+}
+
+/// Replayed inventory after a row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BacktestPoint {
+    pub timestamp_ms: u64,
+    pub block: u64,
+    pub reference: f64,
+    pub base_balance: f64,  // Normalized
+    pub quote_balance: f64, // Normalized
+    pub trades: u64,        // Fills so far
+    pub gas_usd: f64,       // Gas paid so far
+    pub pnl_quote: f64,     // Inventory value at the reference, net of gas, minus the value of the initial inventory held
+}
+
+/// Decisions stopped by one gate, or orders created when `gate` is "order created".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GateStat {
+    pub gate: String,
+    pub count: u64,
+    pub share_pct: f64,
+}
+
+/// Decisions of the run per gate, most frequent first.
+pub fn gates(decisions: &[Decision]) -> Vec<GateStat> {
+    let mut counts = HashMap::<&str, u64>::new();
+    for decision in decisions.iter() {
+        *counts.entry(decision.gate.map(|gate| gate.as_str()).unwrap_or("order created")).or_default() += 1;
+    }
+    let total = decisions.len().max(1) as f64;
+    let mut stats = counts
+        .into_iter()
+        .map(|(gate, count)| GateStat {
+            gate: gate.to_string(),
+            count,
+            share_pct: count as f64 / total * PERCENT_MULTIPLIER,
+        })
+        .collect::<Vec<GateStat>>();
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.gate.cmp(&b.gate)));
+    stats
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub identifier: String,
+    pub rows: usize,
+    pub skipped: usize, // Downsampled and degraded rows, not replayable
+    pub first_block: u64,
+    pub last_block: u64,
+    pub model: FillModel,
+    pub trades: u64,
+    pub gas_usd: f64,
+    pub pnl_quote: f64,
+    pub gates: Vec<GateStat>,
+    pub fills: Vec<BacktestFill>,
+    pub series: Vec<BacktestPoint>,
+}
+
+impl BacktestReport {
+    /// Writes `series.csv`, `fills.csv`, `gates.csv` and `report.json` into `dir`.
+    pub fn write(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let mut series = "timestamp_ms,block,reference,base_balance,quote_balance,trades,gas_usd,pnl_quote\n".to_string();
+        for p in self.series.iter() {
+            let _ = writeln!(
+                series,
+                "{},{},{},{},{},{},{},{}",
+                p.timestamp_ms, p.block, p.reference, p.base_balance, p.quote_balance, p.trades, p.gas_usd, p.pnl_quote
+            );
+        }
+        std::fs::write(dir.join("series.csv"), series)?;
+        let mut fills = "timestamp_ms,block,pool,direction,selling_amount,buying_amount,spot,reference,profit_bps,gas_usd\n".to_string();
+        for f in self.fills.iter() {
+            let _ = writeln!(
+                fills,
+                "{},{},{},{:?},{},{},{},{},{},{}",
+                f.timestamp_ms, f.block, f.pool, f.direction, f.selling_amount, f.buying_amount, f.spot, f.reference, f.profit_bps, f.gas_usd
+            );
+        }
+        std::fs::write(dir.join("fills.csv"), fills)?;
+        let mut gates = "gate,count,share_pct\n".to_string();
+        for g in self.gates.iter() {
+            let _ = writeln!(gates, "{},{},{:.2}", g.gate, g.count, g.share_pct);
+        }
+        std::fs::write(dir.join("gates.csv"), gates)?;
+        std::fs::write(dir.join("report.json"), serde_json::to_string_pretty(self).map_err(std::io::Error::other)?)
+    }
+}
+
+/// Replay of price rows through one market maker, with its own inventory.
+pub struct Backtest {
+    pub mk: MarketMaker,
+    pub model: FillModel,
+    pub gas_price_gwei: f64,
+    pub eth_usd: Option<f64>,
+    pub inventory: Inventory,
+    pub initial: Inventory,
+    pub fills: Vec<BacktestFill>,
+    pub series: Vec<BacktestPoint>,
+    spots: HashMap<String, f64>, // Previous spot of each pool, a moved pool lifts its cooldown
+    gas_usd: f64,
+    gas_quote: f64,
+}
+
+impl Backtest {
+    /// Starts a replay from normalized balances.
+    pub fn new(mk: MarketMaker, model: FillModel, gas_price_gwei: f64, eth_usd: Option<f64>, base_balance: f64, quote_balance: f64) -> Self {
+        let inventory = Inventory {
+            base_balance: (base_balance * 10f64.powi(mk.base.decimals as i32)) as u128,
+            quote_balance: (quote_balance * 10f64.powi(mk.quote.decimals as i32)) as u128,
+            nonce: 0,
+            native_balance: 0,
+        };
+        Self {
+            mk,
+            model,
+            gas_price_gwei,
+            eth_usd,
+            initial: inventory.clone(),
+            inventory,
+            fills: vec![],
+            series: vec![],
+            spots: HashMap::new(),
+            gas_usd: 0.,
+            gas_quote: 0.,
+        }
+    }
+
+    /// Replays one row: evaluation, readjustment against the synthetic pools, then the fill of the best order.
+    pub async fn step(&mut self, timestamp_ms: u64, msg: &NewPricesMessage) -> Result<(), String> {
+        let (block, reference) = (msg.block, msg.reference_price);
+        // Each row has its own synthetic pools, even within a block
+        self.mk.sim_cache.roll(block);
+        let context = context(&self.mk.config, &self.mk.base, &self.mk.quote, reference, block, self.gas_price_gwei, self.eth_usd)?;
+        let cpds = msg
+            .components
+            .iter()
+            .filter(|cpd| cpd.price > 0. && cpd.price.is_finite())
+            .cloned()
+            .collect::<Vec<ComponentPriceData>>();
+        let targets = cpds.iter().map(|cpd| self.model.target(cpd, &self.mk.base, &self.mk.quote)).collect::<Vec<ProtoSimComp>>();
+        let spots = targets
+            .iter()
+            .zip(cpds.iter())
+            .map(|(psc, cpd)| (psc.component.id.to_string().to_lowercase(), cpd.price))
+            .collect::<HashMap<String, f64>>();
+        for (id, spot) in spots.iter() {
+            if self.spots.get(id).is_some_and(|previous| previous != spot) {
+                self.mk.cooldown.observe(id, block);
+            }
+        }
+        self.mk.cooldown.prune(block);
+        self.mk.volatility.update(reference);
+        let _ = self.mk.quarantine.observe(&cpds, reference, block, Instant::now());
+
+        let skew = if self.mk.config.skew_gain_bps_per_pct > 0. {
+            skew::compute(
+                &self.inventory,
+                &self.mk.base,
+                &self.mk.quote,
+                &context,
+                self.mk.config.target_inventory_ratio,
+                self.mk.config.skew_gain_bps_per_pct,
+                self.mk.config.min_watch_spread_bps,
+            )
+        } else {
+            InventorySkew::neutral(self.mk.config.min_watch_spread_bps, self.mk.config.target_inventory_ratio)
+        };
+        let adjustments = self.mk.engine.evaluate(&self.mk, &targets, cpds.iter().map(|cpd| cpd.price).collect(), reference, &skew, block);
+        if !adjustments.is_empty() {
+            let balances = FillBalances {
+                model: self.model,
+                base: self.mk.base.clone(),
+                quote: self.mk.quote.clone(),
+                spots: spots.clone(),
+            };
+            let mut orders = self.mk.engine.readjust(&self.mk, context.clone(), self.inventory.clone(), adjustments, &balances).await;
+            // Like the live loop, only the most profitable order of a block executes
+            orders.sort_by(|a, b| b.calculation.profit_delta_bps.partial_cmp(&a.calculation.profit_delta_bps).unwrap_or(std::cmp::Ordering::Equal));
+            if let Some(order) = orders.first() {
+                self.fill(timestamp_ms, order, &context);
+            }
+        }
+        self.spots = spots;
+        self.series.push(self.point(timestamp_ms, block, reference));
+        Ok(())
+    }
+
+    /// Applies a filled order to the inventory, and cools its pool down.
+    fn fill(&mut self, timestamp_ms: u64, order: &ExecutionOrder, context: &MarketContext) {
+        let calculation = &order.calculation;
+        let (sold, bought) = (calculation.amount_in_raw.to_u128().unwrap_or(u128::MAX), calculation.amount_out_raw.to_u128().unwrap_or(u128::MAX));
+        if calculation.base_to_quote {
+            self.inventory.base_balance = self.inventory.base_balance.saturating_sub(sold);
+            self.inventory.quote_balance = self.inventory.quote_balance.saturating_add(bought);
+        } else {
+            self.inventory.quote_balance = self.inventory.quote_balance.saturating_sub(sold);
+            self.inventory.base_balance = self.inventory.base_balance.saturating_add(bought);
+        }
+        self.mk.cooldown.record(&order.adjustment.psc.component.id.to_string(), context.block);
+        self.gas_usd += calculation.gas_cost_usd;
+        self.gas_quote += calculation.gas_cost_eth / context.quote_to_eth;
+        self.fills.push(BacktestFill {
+            timestamp_ms,
+            block: context.block,
+            pool: order.adjustment.psc.component.id.to_string().to_lowercase(),
+            direction: order.adjustment.direction.clone(),
+            selling_amount: calculation.selling_amount,
+            buying_amount: calculation.amount_out_normalized,
+            spot: order.adjustment.spot,
+            reference: order.adjustment.reference,
+            profit_bps: calculation.profit_delta_bps,
+            gas_usd: calculation.gas_cost_usd,
+        });
+    }
+
+    fn point(&self, timestamp_ms: u64, block: u64, reference: f64) -> BacktestPoint {
+        let base_pow = 10f64.powi(self.mk.base.decimals as i32);
+        let quote_pow = 10f64.powi(self.mk.quote.decimals as i32);
+        let (base, quote) = (self.inventory.base_balance as f64 / base_pow, self.inventory.quote_balance as f64 / quote_pow);
+        let (base0, quote0) = (self.initial.base_balance as f64 / base_pow, self.initial.quote_balance as f64 / quote_pow);
+        BacktestPoint {
+            timestamp_ms,
+            block,
+            reference,
+            base_balance: base,
+            quote_balance: quote,
+            trades: self.fills.len() as u64,
+            gas_usd: self.gas_usd,
+            pnl_quote: (base - base0) * reference + (quote - quote0) - self.gas_quote,
+        }
+    }
+
+    /// Report of the replayed rows, with the decisions read back from the audit log of the run.
+    pub fn report(&self, identifier: &str, skipped: usize, decisions: &[Decision]) -> BacktestReport {
+        BacktestReport {
+            identifier: identifier.to_string(),
+            rows: self.series.len(),
+            skipped,
+            first_block: self.series.first().map(|p| p.block).unwrap_or_default(),
+            last_block: self.series.last().map(|p| p.block).unwrap_or_default(),
+            model: self.model,
+            trades: self.fills.len() as u64,
+            gas_usd: self.gas_usd,
+            pnl_quote: self.series.last().map(|p| p.pnl_quote).unwrap_or_default(),
+            gates: gates(decisions),
+            fills: self.fills.clone(),
+            series: self.series.clone(),
+        }
+    }
+}
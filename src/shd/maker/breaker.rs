@@ -0,0 +1,76 @@
+//! Circuit Breaker Module
+//!
+//! Stops new executions once the rolling realized loss exceeds the daily limit,
+//! until a cooldown elapses or an operator resumes explicitly.
+use crate::maker::pnl::PnlTracker;
+
+/// State change reported by `CircuitBreaker::check`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakerEvent {
+    /// Rolling loss crossed the limit, executions are suspended
+    Opened { loss_usd: f64 },
+    /// Cooldown elapsed, executions resume
+    CooldownElapsed,
+}
+
+/// Daily loss circuit breaker, owned by the market maker so it survives stream reconnections.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    max_daily_loss_usd: f64, // 0 disables the breaker
+    cooldown_ms: u128,       // 0 means explicit resume only
+    opened_at_ms: Option<u128>,
+    resumed_at_ms: u128, // Losses realized before the last resume are not counted again
+}
+
+impl CircuitBreaker {
+    pub fn new(max_daily_loss_usd: f64, cooldown_ms: u128) -> Self {
+        Self {
+            max_daily_loss_usd,
+            cooldown_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true while executions are suspended.
+    pub fn is_open(&self) -> bool {
+        self.opened_at_ms.is_some()
+    }
+
+    /// Updates the breaker from the realized PnL. Returns the state change, if any.
+    pub fn check(&mut self, pnl: &PnlTracker, now_ms: u128) -> Option<BreakerEvent> {
+        if self.max_daily_loss_usd <= 0. {
+            return None;
+        }
+        match self.opened_at_ms {
+            Some(opened_at_ms) => {
+                if self.cooldown_ms > 0 && now_ms.saturating_sub(opened_at_ms) >= self.cooldown_ms {
+                    self.resume(now_ms);
+                    return Some(BreakerEvent::CooldownElapsed);
+                }
+                None
+            }
+            None => {
+                let realized = pnl.realized_since(self.resumed_at_ms, now_ms);
+                if realized < -self.max_daily_loss_usd {
+                    self.opened_at_ms = Some(now_ms);
+                    return Some(BreakerEvent::Opened { loss_usd: -realized });
+                }
+                None
+            }
+        }
+    }
+
+    /// Closes the breaker (cooldown or explicit resume command).
+    pub fn resume(&mut self, now_ms: u128) {
+        self.opened_at_ms = None;
+        self.resumed_at_ms = now_ms;
+    }
+
+    /// Drops every item while the breaker is open.
+    pub fn gate<T>(&self, items: Vec<T>) -> Vec<T> {
+        if self.is_open() {
+            return vec![];
+        }
+        items
+    }
+}
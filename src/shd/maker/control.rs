@@ -0,0 +1,76 @@
+//! Operator Control Module
+//!
+//! Trading state shared between the Redis control listener and the market maker loop.
+//! Pause keeps the instance streaming and publishing prices without trading, resume
+//! restores trading, and kill makes the loop exit. A killed instance cannot be resumed.
+//! Refresh inventory asks the loop to read the wallet inventory from chain again.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use tokio::sync::watch;
+
+use crate::types::moni::{ControlCommand, TradingState};
+
+/// Cloneable handle on the trading state of one market maker.
+#[derive(Debug, Clone)]
+pub struct Control {
+    state: Arc<watch::Sender<TradingState>>,
+    refresh: Arc<AtomicBool>, // Inventory refresh requested, consumed by the loop
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(watch::Sender::new(TradingState::Running)),
+            refresh: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Control {
+    pub fn state(&self) -> TradingState {
+        *self.state.borrow()
+    }
+
+    /// Applies an operator command and returns the resulting state.
+    pub fn apply(&self, command: ControlCommand) -> TradingState {
+        if command == ControlCommand::RefreshInventory {
+            self.refresh.store(true, Ordering::Relaxed);
+            return self.state();
+        }
+        self.state.send_if_modified(|state| {
+            let next = match (*state, command) {
+                (TradingState::Killed, _) => TradingState::Killed,
+                (_, ControlCommand::Pause) => TradingState::Paused,
+                (_, ControlCommand::Resume) => TradingState::Running,
+                (_, ControlCommand::Kill) => TradingState::Killed,
+                (current, ControlCommand::RefreshInventory) => current,
+            };
+            let changed = next != *state;
+            *state = next;
+            changed
+        });
+        self.state()
+    }
+
+    /// Returns true once per inventory refresh request.
+    pub fn take_refresh(&self) -> bool {
+        self.refresh.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state() == TradingState::Paused
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.state() == TradingState::Killed
+    }
+
+    /// Resolves once the instance is killed, so a loop waiting on its stream can exit between blocks.
+    pub async fn killed(&self) {
+        let mut rx = self.state.subscribe();
+        let _ = rx.wait_for(|state| *state == TradingState::Killed).await;
+    }
+}
@@ -0,0 +1,55 @@
+//! Pool Cooldown Module
+//!
+//! Right after a trade, the indexed state of the traded pool may not include it yet and
+//! still shows the spread we just closed. The cooldown keeps such pools out of evaluation
+//! for a few blocks, or until a state update newer than the inclusion block arrives.
+use std::collections::HashMap;
+
+/// Per-component cooldown, owned by the market maker so it survives iterations.
+#[derive(Debug, Clone, Default)]
+pub struct PoolCooldown {
+    blocks: u64,                   // 0 disables the cooldown
+    entries: HashMap<String, u64>, // Component id (lowercase) => inclusion block of our last trade
+}
+
+impl PoolCooldown {
+    pub fn new(blocks: u64) -> Self {
+        Self { blocks, ..Default::default() }
+    }
+
+    /// Records a trade on the component, included (or expected) at the given block.
+    pub fn record(&mut self, component: &str, block: u64) {
+        if self.blocks == 0 {
+            return;
+        }
+        self.entries.insert(component.to_lowercase(), block);
+    }
+
+    /// Lifts the cooldown once the component state reflects a block later than our inclusion block.
+    pub fn observe(&mut self, component: &str, block: u64) {
+        let id = component.to_lowercase();
+        if self.entries.get(&id).is_some_and(|included| block > *included) {
+            self.entries.remove(&id);
+        }
+    }
+
+    /// Returns true while the component must be skipped at the given block.
+    pub fn is_cooling(&self, component: &str, block: u64) -> bool {
+        self.entries.get(&component.to_lowercase()).is_some_and(|included| block < included.saturating_add(self.blocks))
+    }
+
+    /// Drops the entries whose window has elapsed.
+    pub fn prune(&mut self, block: u64) {
+        let blocks = self.blocks;
+        self.entries.retain(|_, included| block < included.saturating_add(blocks));
+    }
+
+    /// Number of components currently cooling down.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,140 @@
+//! Decision Engine Module
+//!
+//! The market maker composes three parts, each behind a trait so that it is plugged through the builder rather than by
+//! forking `impl.rs`: the market data (`MarketDataSource`: reference price, market context, inventory), the decision
+//! logic (`DecisionEngine`: pricing, evaluation and sizing of the readjustments), and the execution (`Execution`:
+//! encoding of the orders, then simulation and broadcast with the `ExecStrategy`). Each method has the behavior of the
+//! default implementation unless overridden, and receives the market maker for its config, tokens and state.
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tycho_common::{models::token::Token, simulation::protocol_sim::ProtocolSim};
+
+use crate::{
+    error::ExecError,
+    maker::{latency::Deadline, permit2::SignedPermit, tycho::PoolBalances},
+    opti::{routing::TokenGraph, skew::InventorySkew},
+    types::{
+        config::EnvConfig,
+        maker::{CompReadjustment, ComponentPriceData, ExecutionOrder, Inventory, MarketContext, MarketMaker, Trade, TradeData},
+        tycho::ProtoSimComp,
+    },
+};
+
+/// Reads the market data the decisions are made on.
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    /// Returns the source name for logging purposes.
+    fn name(&self) -> String;
+
+    /// Reference price of the pair, quote per base, from the price feed.
+    async fn market_price(&self, mk: &MarketMaker) -> Result<f64, String> {
+        mk.fetch_market_price().await
+    }
+
+    /// Gas fees, ETH/USD price, token to ETH prices and block of the market, None if any is missing.
+    async fn market_context(&self, mk: &MarketMaker, graph: &TokenGraph, protosims: &HashMap<String, Box<dyn ProtocolSim>>, tokens: Vec<Token>) -> Option<MarketContext> {
+        mk.fetch_market_context(graph, protosims, tokens).await
+    }
+
+    /// Wallet balances and nonce, read from chain.
+    async fn inventory(&self, mk: &MarketMaker, env: EnvConfig) -> Result<Inventory, String> {
+        mk.fetch_inventory(env).await
+    }
+}
+
+/// Market data of the market maker unless another source is plugged: the price feed and the chain.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChainDataSource;
+
+#[async_trait]
+impl MarketDataSource for ChainDataSource {
+    fn name(&self) -> String {
+        "Chain_Data_Source".to_string()
+    }
+}
+
+/// Turns the orders into transactions and executes them.
+#[async_trait]
+pub trait Execution: Send + Sync {
+    /// Returns the execution name for logging purposes.
+    fn name(&self) -> String;
+
+    /// Transactions of the orders (wrap, approval, swap, unwrap), encoded with the Tycho router encoder.
+    fn prepare(&self, mk: &MarketMaker, orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, context: MarketContext, inventory: Inventory, permits: &HashMap<String, SignedPermit>) -> Vec<Trade> {
+        mk.prepare(orders, tdata, context, inventory, permits)
+    }
+
+    /// Simulates and broadcasts the trades with the `ExecStrategy` of the market maker, within the latency budget.
+    async fn execute(&self, mk: &MarketMaker, trades: Vec<Trade>, env: EnvConfig, deadline: &Deadline) -> Result<Vec<Trade>, ExecError> {
+        mk.execution.execute_within(mk.config.clone(), trades, env, mk.identifier.clone(), deadline).await
+    }
+}
+
+/// Execution of the market maker unless another one is plugged: the router encoding and the `ExecStrategy`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RouterExecution;
+
+#[async_trait]
+impl Execution for RouterExecution {
+    fn name(&self) -> String {
+        "Router_Execution".to_string()
+    }
+}
+
+/// Decides which target pools to readjust and by how much.
+#[async_trait]
+pub trait DecisionEngine: Send + Sync {
+    /// Returns the engine name for logging purposes.
+    fn name(&self) -> String;
+
+    /// Spot prices of the target pools, quote per base.
+    fn prices(&self, mk: &MarketMaker, targets: &[ProtoSimComp], reference: f64) -> Vec<ComponentPriceData> {
+        mk.prices(targets, reference)
+    }
+
+    /// Readjustments of the target pools out of range, `sps` being their spot prices.
+    fn evaluate(&self, mk: &MarketMaker, targets: &[ProtoSimComp], sps: Vec<f64>, reference: f64, skew: &InventorySkew, block: u64) -> Vec<CompReadjustment> {
+        mk.evaluate(targets, sps, reference, skew, block)
+    }
+
+    /// Amount of the selling token to sell for a readjustment (normalized), given the `optimal` amount found by the
+    /// optimizer within the inventory allocation. Clamped to that allocation, the pool and exposure caps still apply.
+    fn size(&self, _mk: &MarketMaker, _adjustment: &CompReadjustment, _context: &MarketContext, _inventory: &Inventory, optimal: f64) -> f64 {
+        optimal
+    }
+
+    /// Profitable orders of the readjustments, sized with `size`.
+    async fn readjust(&self, mk: &MarketMaker, context: MarketContext, inventory: Inventory, adjustments: Vec<CompReadjustment>, pool_balances: &dyn PoolBalances) -> Vec<ExecutionOrder> {
+        mk.readjust(context, inventory, adjustments, pool_balances).await
+    }
+}
+
+/// Engine of the market maker unless another one is plugged: the optimal readjustment of each pool.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultEngine;
+
+#[async_trait]
+impl DecisionEngine for DefaultEngine {
+    fn name(&self) -> String {
+        "Default_Engine".to_string()
+    }
+}
+
+/// Example of a custom sizing: sells `fraction` of the optimal amount of each readjustment, e.g. to rebalance a pool
+/// over several blocks rather than in one trade.
+#[derive(Debug, Clone, Copy)]
+pub struct FractionEngine {
+    pub fraction: f64,
+}
+
+#[async_trait]
+impl DecisionEngine for FractionEngine {
+    fn name(&self) -> String {
+        format!("Fraction_Engine({})", self.fraction)
+    }
+
+    fn size(&self, _mk: &MarketMaker, _adjustment: &CompReadjustment, _context: &MarketContext, _inventory: &Inventory, optimal: f64) -> f64 {
+        optimal * self.fraction
+    }
+}
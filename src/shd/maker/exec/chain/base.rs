@@ -13,12 +13,14 @@
 //! - The sequence of flashblocks is **fixed**, a flashblock cannot preempt another one
 use async_trait::async_trait;
 
-use crate::maker::exec::ExecStrategyName;
+use crate::{maker::exec::ExecStrategyName, types::config::NetworkName};
 
 use super::super::ExecStrategy;
 
 /// Base L2 execution strategy implementation optimized for Base network with flashblock support.
-pub struct BaseExec;
+pub struct BaseExec {
+    rpc: Option<String>,
+}
 
 impl Default for BaseExec {
     fn default() -> Self {
@@ -28,13 +30,18 @@ impl Default for BaseExec {
 
 impl BaseExec {
     pub fn new() -> Self {
-        Self
+        Self { rpc: None }
+    }
+
+    /// Strategy sending to `rpc` instead of the RPC pool of the config (e.g. a local fork).
+    pub fn with_rpc(rpc: String) -> Self {
+        Self { rpc: Some(rpc) }
     }
 }
 
 /// ExecStrategy implementation for Base network.
 ///
-/// Overridden: `name()` returns "Base_Strategy"
+/// Overridden: `name()` returns "Base_Strategy", `rpc()` returns the injected RPC, `network()` returns Base
 ///
 /// Inherited (default implementation): `pre_hook`, `post_hook`, `execute`, `simulate`, `broadcast`
 ///
@@ -45,6 +52,14 @@ impl ExecStrategy for BaseExec {
         ExecStrategyName::BaseStrategy.as_str().to_string()
     }
 
+    fn rpc(&self) -> Option<String> {
+        self.rpc.clone()
+    }
+
+    fn network(&self) -> Option<NetworkName> {
+        Some(NetworkName::Base)
+    }
+
     // TODO: Override broadcast() for flashblock implementation
-    // async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, String>
+    // async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError>
 }
@@ -5,7 +5,7 @@ use async_trait::async_trait;
 use std::str::FromStr;
 
 use alloy::{
-    network::{EthereumWallet, TransactionBuilder},
+    network::TransactionBuilder,
     providers::{Provider, ProviderBuilder},
     signers::local::PrivateKeySigner,
 };
@@ -13,17 +13,21 @@ use alloy_mev::EthMevProviderExt; // Provides bundle_builder() and send_eth_bund
 use alloy_primitives::B256;
 
 use crate::{
+    error::ExecError,
     maker::{exec::ExecStrategyName, tycho::get_alloy_chain},
     types::{
-        config::{EnvConfig, MarketMakerConfig},
+        config::{EnvConfig, MarketMakerConfig, NetworkName},
         maker::{BroadcastData, Trade},
     },
+    utils::{evm::RpcPool, misc::now_ms, signer::WalletSigner},
 };
 
-use super::super::ExecStrategy;
+use super::super::{send, ExecStrategy};
 
 /// Mainnet execution strategy with Flashbots MEV protection.
-pub struct MainnetExec;
+pub struct MainnetExec {
+    rpc: Option<String>,
+}
 
 impl Default for MainnetExec {
     fn default() -> Self {
@@ -33,7 +37,12 @@ impl Default for MainnetExec {
 
 impl MainnetExec {
     pub fn new() -> Self {
-        Self
+        Self { rpc: None }
+    }
+
+    /// Strategy sending to `rpc` instead of the RPC pool of the config (e.g. a local fork).
+    pub fn with_rpc(rpc: String) -> Self {
+        Self { rpc: Some(rpc) }
     }
 }
 
@@ -43,16 +52,31 @@ impl ExecStrategy for MainnetExec {
         ExecStrategyName::MainnetStrategy.as_str().to_string()
     }
 
+    fn rpc(&self) -> Option<String> {
+        self.rpc.clone()
+    }
+
+    fn network(&self) -> Option<NetworkName> {
+        Some(NetworkName::Ethereum)
+    }
+
     /// Broadcasts via Flashbots bundle submission for MEV protection.
-    async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, String> {
+    async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        // An injected RPC (local fork) has no builder behind it, transactions are sent to it directly
+        if let Some(rpc) = self.rpc() {
+            return send(&self.name(), prepared, &mmc, &env, &rpc).await;
+        }
         tracing::info!("{}: broadcasting {} transactions on Mainnet via Flashbots bundle", self.name(), prepared.len());
 
         // Setup provider with wallet
         let _ac = get_alloy_chain(mmc.network_name.as_str().to_string()).expect("Failed to get alloy chain");
-        let rpc = mmc.rpc_url.parse::<url::Url>().unwrap();
-        let pk = env.wallet_private_key.clone();
-        let wallet = PrivateKeySigner::from_bytes(&B256::from_str(&pk).expect("Failed to convert wallet pk to B256")).expect("Failed to create private key signer");
-        let signer = EthereumWallet::from(wallet.clone());
+        let pool = RpcPool::of(&mmc);
+        let endpoint = pool.write_url();
+        let rpc = endpoint.parse::<url::Url>().map_err(|e| ExecError::Rpc {
+            message: format!("Invalid RPC URL {}: {}", endpoint, e),
+            retryable: false,
+        })?;
+        let signer = WalletSigner::from_env(&env).map_err(ExecError::Encoding)?;
 
         let provider = ProviderBuilder::new().with_chain_id(mmc.chain_id).wallet(signer.clone()).connect_http(rpc);
 
@@ -61,7 +85,10 @@ impl ExecStrategy for MainnetExec {
         let bundle_signer = match &env.bundle_signer_key {
             Some(key) => {
                 tracing::info!("{}: Using persistent bundle signer for builder reputation", self.name());
-                PrivateKeySigner::from_bytes(&B256::from_str(key).expect("Failed to parse BUNDLE_SIGNER_KEY")).expect("Failed to create bundle signer")
+                B256::from_str(key)
+                    .map_err(|e| e.to_string())
+                    .and_then(|key| PrivateKeySigner::from_bytes(&key).map_err(|e| e.to_string()))
+                    .map_err(|e| ExecError::Encoding(format!("Invalid BUNDLE_SIGNER_KEY: {}", e)))?
             }
             None => {
                 tracing::warn!("{}: No BUNDLE_SIGNER_KEY configured, using random signer (no builder reputation)", self.name());
@@ -89,21 +116,42 @@ impl ExecStrategy for MainnetExec {
             return Ok(results);
         }
 
-        // Process each trade (each may contain approval + swap)
+        // Process each trade (each may contain wrap + approval + swap + unwrap)
         for trade in prepared.iter() {
             // Get current block and calculate target inclusion block
-            let bnum = provider.get_block_number().await.map_err(|e| format!("Failed to get block number: {:?}", e))?;
+            let start = std::time::Instant::now();
+            let bnum = provider.get_block_number().await;
+            pool.report(&endpoint, start.elapsed(), bnum.is_ok());
+            let bnum = match bnum {
+                Ok(bnum) => bnum,
+                Err(e) => {
+                    tracing::warn!("{}: Failed to get block number, not broadcasting: {:?}", self.name(), e);
+                    results.push(BroadcastData::failed(&trade.metadata.trade_id, ExecError::transport("Failed to get block number", &e)));
+                    continue;
+                }
+            };
             let target_block = bnum + mmc.inclusion_block_delay;
+            // A bundle only lands on its target block, which must not be past the deadline of the trade
+            let valid_until = mmc.valid_until(trade.metadata.context.block);
+            if target_block > valid_until {
+                tracing::warn!("{}: Deadline passed: target block {} past block {}, not broadcasting", self.name(), target_block, valid_until);
+                results.push(BroadcastData::failed(
+                    &trade.metadata.trade_id,
+                    ExecError::Timeout(format!("Deadline passed: target block {} past block {}", target_block, valid_until)),
+                ));
+                continue;
+            }
 
             tracing::info!("{}: Current block: {}, target inclusion: {} (delay: {})", self.name(), bnum, target_block, mmc.inclusion_block_delay);
 
-            let mut bd = BroadcastData::default();
+            let mut bd = BroadcastData {
+                trade_id: trade.metadata.trade_id.clone(),
+                ..Default::default()
+            };
             let time = std::time::SystemTime::now();
 
             // Record broadcast timestamp
-            let now = std::time::SystemTime::now();
-            let broadcasted_at_ms = now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
-            bd.broadcasted_at_ms = broadcasted_at_ms;
+            bd.broadcasted_at_ms = now_ms();
 
             // Build and get expected transaction hash (for tracking)
             match trade.swap.clone().build(&signer).await {
@@ -117,26 +165,34 @@ impl ExecStrategy for MainnetExec {
                 }
             }
 
-            // Build bundle using the new bundle_builder() API
-            let mut bundle_builder = provider.bundle_builder().on_block(target_block);
-
-            // Add approval transaction if needed (when infinite_approval is false)
-            if let Some(approval) = &trade.approve {
-                bundle_builder = bundle_builder
-                    .add_transaction_request(approval.clone())
-                    .await
-                    .map_err(|e| format!("Failed to add approval to bundle: {:?}", e))?;
-                tracing::info!("{}: Added approval tx to bundle", self.name());
+            // Build bundle using the new bundle_builder() API: wrap (auto_wrap_native), approval (infinite_approval false),
+            // swap, then unwrap (unwrap_to_native_above), the bundle lands only if every transaction succeeds
+            let txs = [
+                ("wrap", trade.wrap.as_ref()),
+                ("approval", trade.approve.as_ref()),
+                ("swap", Some(&trade.swap)),
+                ("unwrap", trade.unwrap.as_ref()),
+            ];
+            let bundle = async {
+                let mut bundle_builder = provider.bundle_builder().on_block(target_block);
+                for (kind, tx) in txs.into_iter().filter_map(|(kind, tx)| tx.map(|tx| (kind, tx))) {
+                    bundle_builder = bundle_builder
+                        .add_transaction_request(tx.clone())
+                        .await
+                        .map_err(|e| ExecError::Encoding(format!("Failed to add {} to bundle: {:?}", kind, e)))?;
+                    tracing::info!("{}: Added {} tx to bundle", self.name(), kind);
+                }
+                Ok::<_, ExecError>(bundle_builder.build())
             }
-
-            // Add the swap transaction
-            bundle_builder = bundle_builder
-                .add_transaction_request(trade.swap.clone())
-                .await
-                .map_err(|e| format!("Failed to add swap to bundle: {:?}", e))?;
-
-            // Finalize the bundle
-            let bundle = bundle_builder.build();
+            .await;
+            let bundle = match bundle {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    tracing::error!("{}: Failed to build the bundle, not broadcasting: {}", self.name(), e);
+                    results.push(BroadcastData::failed(&trade.metadata.trade_id, e));
+                    continue;
+                }
+            };
 
             tracing::info!("{}: Sending bundle to builders (targeting block {})...", self.name(), target_block);
 
@@ -174,13 +230,16 @@ impl ExecStrategy for MainnetExec {
             if successful_builders == 0 {
                 tracing::error!("{}: All builders rejected the bundle!", self.name());
                 let all_errors = rejection_errors.join(" | ");
-                bd.broadcast_error = Some(format!("All builders rejected bundle: {}", all_errors));
-                return Err(bd.broadcast_error.clone().unwrap());
+                results.push(BroadcastData::failed(
+                    &trade.metadata.trade_id,
+                    ExecError::BuilderRejected(format!("All builders rejected bundle: {}", all_errors)),
+                ));
+                continue;
             } else if !rejection_errors.is_empty() {
                 // At least one builder accepted, but some rejected
                 // Log rejections for debugging but don't mark as failed
                 tracing::info!("{}: Trade successful despite {} rejections: {}", self.name(), rejection_errors.len(), rejection_errors.join(" | "));
-                bd.broadcast_error = None;
+                bd.succeed();
             }
 
             results.push(bd);
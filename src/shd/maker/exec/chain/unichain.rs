@@ -4,12 +4,14 @@
 //! See: <https://docs.unichain.org/docs/technical-information/advanced-txn>
 use async_trait::async_trait;
 
-use crate::maker::exec::ExecStrategyName;
+use crate::{maker::exec::ExecStrategyName, types::config::NetworkName};
 
 use super::super::ExecStrategy;
 
 /// Unichain execution strategy implementation.
-pub struct UnichainExec;
+pub struct UnichainExec {
+    rpc: Option<String>,
+}
 
 impl Default for UnichainExec {
     fn default() -> Self {
@@ -19,7 +21,12 @@ impl Default for UnichainExec {
 
 impl UnichainExec {
     pub fn new() -> Self {
-        Self
+        Self { rpc: None }
+    }
+
+    /// Strategy sending to `rpc` instead of the RPC pool of the config (e.g. a local fork).
+    pub fn with_rpc(rpc: String) -> Self {
+        Self { rpc: Some(rpc) }
     }
 }
 
@@ -29,6 +36,14 @@ impl ExecStrategy for UnichainExec {
         ExecStrategyName::UnichainStrategy.as_str().to_string()
     }
 
+    fn rpc(&self) -> Option<String> {
+        self.rpc.clone()
+    }
+
+    fn network(&self) -> Option<NetworkName> {
+        Some(NetworkName::Unichain)
+    }
+
     // TODO: Override broadcast() for Unichain advanced transaction features
-    // async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, String>
+    // async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError>
 }
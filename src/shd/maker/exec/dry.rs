@@ -0,0 +1,51 @@
+//! Dry Run Execution Strategy
+//!
+//! Network-agnostic strategy selected by `dry_run` (or `--dry-run`): trades are simulated
+//! as usual, unless `skip_simulation`, but never broadcast nor published. Each trade reports
+//! a dry run outcome, neither a success nor an error: it keeps its simulation status and the inventory is left as is.
+use async_trait::async_trait;
+
+use crate::error::ExecError;
+use crate::maker::exec::ExecStrategyName;
+use crate::types::{
+    config::{EnvConfig, MarketMakerConfig},
+    maker::{BroadcastData, Trade},
+};
+
+use super::ExecStrategy;
+
+/// Dry run execution strategy implementation.
+pub struct DryRunExec;
+
+impl Default for DryRunExec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DryRunExec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// ExecStrategy implementation for dry runs.
+///
+/// Overridden: `name()` returns "DryRun_Strategy", `broadcast` and `post_hook` send nothing
+///
+/// Inherited (default implementation): `pre_hook`, `execute`, `simulate`
+#[async_trait]
+impl ExecStrategy for DryRunExec {
+    fn name(&self) -> String {
+        ExecStrategyName::DryRunStrategy.as_str().to_string()
+    }
+
+    async fn post_hook(&self, _config: &MarketMakerConfig, trades: Vec<Trade>, _identifier: String) {
+        tracing::info!("{}: {} trade(s) not published", self.name(), trades.len());
+    }
+
+    async fn broadcast(&self, prepared: Vec<Trade>, _mmc: MarketMakerConfig, _env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        tracing::info!("🧪 {}: {} trade(s) not broadcast", self.name(), prepared.len());
+        Ok(prepared.iter().map(|trade| BroadcastData::dry(&trade.metadata.trade_id)).collect())
+    }
+}
@@ -3,26 +3,33 @@
 //! Network-specific execution strategies including simulation, broadcasting,
 //! and transaction management for Ethereum, Base, and Unichain networks.
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::result::Result;
 use std::str::FromStr;
 
 use alloy::{
     providers::{Provider, ProviderBuilder},
     rpc::types::simulate::{SimBlock, SimulatePayload},
-    signers::local::PrivateKeySigner,
 };
-use alloy_primitives::B256;
+use sea_orm::prelude::Uuid;
+use tracing::Instrument;
 
 use crate::{
-    maker::tycho::get_alloy_chain,
+    error::ExecError,
+    maker::{
+        latency::{time_out, Deadline, Stage},
+        tycho::get_alloy_chain,
+    },
     types::{
         config::{EnvConfig, MarketMakerConfig, NetworkName},
-        maker::{BroadcastData, SimulatedData, Trade, TradeStatus},
+        maker::{BroadcastData, ReceiptData, SimulatedData, Trade, TradeStatus},
         moni::NewTradeMessage,
     },
+    utils::{evm::RpcPool, head::HeadCache, misc::now_ms, signer::WalletSigner},
 };
 
 pub mod chain;
+pub mod dry;
 
 /// Available execution strategy names.
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +37,7 @@ pub enum ExecStrategyName {
     MainnetStrategy,
     BaseStrategy,
     UnichainStrategy,
+    DryRunStrategy,
 }
 
 impl ExecStrategyName {
@@ -38,6 +46,7 @@ impl ExecStrategyName {
             ExecStrategyName::MainnetStrategy => "Mainnet_Strategy",
             ExecStrategyName::BaseStrategy => "Base_Strategy",
             ExecStrategyName::UnichainStrategy => "Unichain_Strategy",
+            ExecStrategyName::DryRunStrategy => "DryRun_Strategy",
         }
     }
 }
@@ -54,6 +63,38 @@ impl ExecStrategyFactory {
             Err(_) => panic!("Unknown network '{}', please check the network name in the config file", network),
         }
     }
+
+    /// Network strategy sending to `rpc` instead of the RPC pool of the config (e.g. a local fork).
+    pub fn with_rpc(network: &str, rpc: String) -> Box<dyn ExecStrategy> {
+        match NetworkName::from_str(network) {
+            Ok(NetworkName::Ethereum) => Box::new(chain::mainnet::MainnetExec::with_rpc(rpc)),
+            Ok(NetworkName::Base) => Box::new(chain::base::BaseExec::with_rpc(rpc)),
+            Ok(NetworkName::Unichain) => Box::new(chain::unichain::UnichainExec::with_rpc(rpc)),
+            Err(_) => panic!("Unknown network '{}', please check the network name in the config file", network),
+        }
+    }
+
+    /// Strategy of a config: the dry run strategy if `dry_run` is set, the network one otherwise.
+    pub fn from_config(config: &MarketMakerConfig) -> Box<dyn ExecStrategy> {
+        if config.dry_run {
+            return Box::new(dry::DryRunExec::new());
+        }
+        Self::create(config.network_name.as_str())
+    }
+}
+
+/// `trade` span of an execution, carrying the correlation ids, pools and amounts of its trades (comma-separated when batched).
+pub fn span(trades: &[Trade], strategy: &str, network: &str) -> tracing::Span {
+    let join = |field: fn(&Trade) -> String| trades.iter().map(field).collect::<Vec<String>>().join(",");
+    tracing::info_span!(
+        "trade",
+        trade_id = %join(|trade| trade.metadata.trade_id.clone()),
+        pool = %join(|trade| trade.metadata.metadata.pool.clone()),
+        amount_in = %join(|trade| trade.metadata.metadata.amount_in_normalized.to_string()),
+        amount_out_expected = %join(|trade| trade.metadata.metadata.amount_out_expected.to_string()),
+        strategy,
+        network
+    )
 }
 
 /// Trait defining the interface for execution strategies.
@@ -62,6 +103,16 @@ pub trait ExecStrategy: Send + Sync {
     /// Returns the strategy name for logging purposes.
     fn name(&self) -> String;
 
+    /// RPC endpoint injected into the strategy (e.g. a local fork), used instead of the RPC pool of the config.
+    fn rpc(&self) -> Option<String> {
+        None
+    }
+
+    /// Network the strategy sends to, checked against the config by the builder. None for a strategy sending nowhere (dry run).
+    fn network(&self) -> Option<NetworkName> {
+        None
+    }
+
     /// Pre-execution hook called before transaction execution.
     async fn pre_hook(&self) {
         tracing::info!("{} default_pre_exec_hook", self.name());
@@ -76,68 +127,145 @@ pub trait ExecStrategy: Send + Sync {
                 let _ = crate::data::r#pub::trade(NewTradeMessage {
                     identifier: identifier.clone(), // Use passed identifier for trade tracking
                     data: trade.metadata.clone(),
+                    // Trade id, generated once, so a republished message stays the same trade
+                    idempotency_key: if trade.metadata.trade_id.is_empty() {
+                        Uuid::new_v4().to_string()
+                    } else {
+                        trade.metadata.trade_id.clone()
+                    },
                 });
             }
         }
     }
 
-    /// Executes prepared transactions with simulation, broadcasting, and status updates.
-    async fn execute(&self, config: MarketMakerConfig, prepared: Vec<Trade>, env: EnvConfig, identifier: String) -> Result<Vec<Trade>, String> {
-        self.pre_hook().await;
-        tracing::info!("{} Executing {} trades", self.name(), prepared.len());
-        let mut trades = if config.skip_simulation {
-            tracing::info!("🚀 Skipping simulation - direct execution enabled");
-            prepared.clone()
-        } else {
-            let mut updated = prepared.clone();
-            let smd = self.simulate(config.clone(), updated.clone(), env.clone()).await?;
-            for (x, smd) in smd.iter().enumerate() {
-                updated[x].metadata.simulation = Some(smd.clone());
-                if !smd.status {
-                    updated[x].metadata.status = TradeStatus::SimulationFailed;
-                } else {
-                    updated[x].metadata.status = TradeStatus::SimulationSucceeded;
+    /// Executes prepared transactions with simulation, broadcasting, and status updates, within the `trade` span.
+    async fn execute(&self, config: MarketMakerConfig, prepared: Vec<Trade>, env: EnvConfig, identifier: String) -> Result<Vec<Trade>, ExecError> {
+        self.execute_within(config, prepared, env, identifier, &Deadline::unbounded()).await
+    }
+
+    /// `execute` within the latency budget of the block update: checked before the simulation and the broadcast,
+    /// nothing more is sent past it. The trades aborted are published with the `Timeout` status.
+    async fn execute_within(&self, config: MarketMakerConfig, prepared: Vec<Trade>, env: EnvConfig, identifier: String, deadline: &Deadline) -> Result<Vec<Trade>, ExecError> {
+        let span = span(&prepared, &self.name(), config.network_name.as_str());
+        async move {
+            self.pre_hook().await;
+            tracing::info!("{} Executing {} trades", self.name(), prepared.len());
+            let mut trades = if config.skip_simulation {
+                tracing::info!("🚀 Skipping simulation - direct execution enabled");
+                prepared.clone()
+            } else {
+                if let Err(e) = deadline.check(Stage::Simulate) {
+                    let mut aborted = prepared;
+                    aborted.iter_mut().for_each(|trade| time_out(trade, &e));
+                    self.post_hook(&config, aborted, identifier).await;
+                    return Err(e);
+                }
+                let mut updated = prepared.clone();
+                let smd = self.simulate(config.clone(), updated.clone(), env.clone()).instrument(tracing::info_span!("simulate")).await?;
+                // Matched by trade id, a trade without a result of its own is not broadcast
+                let mut results = smd.into_iter().map(|smd| (smd.trade_id.clone(), smd)).collect::<HashMap<String, SimulatedData>>();
+                for trade in updated.iter_mut() {
+                    let smd = results.remove(&trade.metadata.trade_id).unwrap_or_else(|| SimulatedData {
+                        trade_id: trade.metadata.trade_id.clone(),
+                        error: Some("No simulation result".to_string()),
+                        ..Default::default()
+                    });
+                    if let Some(error) = smd.error.as_ref().filter(|_| !smd.status) {
+                        tracing::warn!("Trade {} failed its simulation, not broadcast: {}", trade.metadata.trade_id, error);
+                    }
+                    trade.metadata.status = if smd.status { TradeStatus::SimulationSucceeded } else { TradeStatus::SimulationFailed };
+                    trade.metadata.simulation = Some(smd);
                 }
+                updated
+            };
+
+            // A trade failing its simulation is not broadcast, its status stays SimulationFailed
+            let broadcastable = trades
+                .iter()
+                .enumerate()
+                .filter(|(_, trade)| trade.metadata.status != TradeStatus::SimulationFailed)
+                .map(|(x, _)| x)
+                .collect::<Vec<usize>>();
+            let prepared = broadcastable.iter().map(|x| trades[*x].clone()).collect::<Vec<Trade>>();
+            // Every trade failed its simulation: published as is, nothing to broadcast
+            if prepared.is_empty() && !trades.is_empty() {
+                self.post_hook(&config, trades.clone(), identifier).await;
+                return Ok(trades);
+            }
+            if let Err(e) = deadline.check(Stage::Broadcast) {
+                // Published with their simulation, the trades failing it keep their status
+                broadcastable.iter().for_each(|x| time_out(&mut trades[*x], &e));
+                self.post_hook(&config, trades, identifier).await;
+                return Err(e);
+            }
+            let bd = match self.broadcast(prepared, config.clone(), env).instrument(tracing::info_span!("broadcast")).await {
+                Ok(bd) => bd,
+                Err(e) => {
+                    // Published as failed with the error, the trades failing their simulation keep their status
+                    for x in broadcastable.iter().copied() {
+                        trades[x].metadata.status = TradeStatus::BroadcastFailed;
+                        trades[x].metadata.broadcast = Some(BroadcastData::failed(&trades[x].metadata.trade_id, e.clone()));
+                    }
+                    self.post_hook(&config, trades, identifier).await;
+                    return Err(e);
+                }
+            };
+            // Matched by trade id. Testing mode broadcasts nothing, a trade without a result keeps its status
+            let mut results = bd.into_iter().map(|bd| (bd.trade_id.clone(), bd)).collect::<HashMap<String, BroadcastData>>();
+            let received = results.len();
+            for x in broadcastable.iter().copied() {
+                let Some(bd) = results.remove(&trades[x].metadata.trade_id) else {
+                    if received > 0 {
+                        tracing::warn!("{}: No broadcast result for trade {}, left as is", self.name(), trades[x].metadata.trade_id);
+                    }
+                    continue;
+                };
+                // Not broadcast by the dry run, keeps its simulation status
+                if bd.dry_run {
+                    trades[x].metadata.broadcast = Some(bd);
+                    continue;
+                }
+                trades[x].metadata.status = if bd.succeeded() { TradeStatus::BroadcastSucceeded } else { TradeStatus::BroadcastFailed };
+                trades[x].metadata.broadcast = Some(bd);
+                trades[x].metadata.realize(&config.wallet_public_key);
             }
-            updated
-        };
 
-        let bd = self.broadcast(trades.clone(), config.clone(), env).await?;
-        for (x, bd) in bd.iter().enumerate() {
-            trades[x].metadata.broadcast = Some(bd.clone());
-            if bd.broadcast_error.is_some() {
-                trades[x].metadata.status = TradeStatus::BroadcastFailed;
-            } else {
-                trades[x].metadata.status = TradeStatus::BroadcastSucceeded;
+            if !results.is_empty() {
+                tracing::warn!("{}: {} broadcast results without a matching trade, ignored", self.name(), results.len());
             }
-        }
 
-        self.post_hook(&config, trades.clone(), identifier).await;
-        Ok(trades)
+            self.post_hook(&config, trades.clone(), identifier).await;
+            Ok(trades)
+        }
+        .instrument(span)
+        .await
     }
 
     /// Simulates transactions to validate they will succeed before execution.
-    async fn simulate(&self, config: MarketMakerConfig, trades: Vec<Trade>, env: EnvConfig) -> Result<Vec<SimulatedData>, String> {
+    async fn simulate(&self, config: MarketMakerConfig, trades: Vec<Trade>, env: EnvConfig) -> Result<Vec<SimulatedData>, ExecError> {
         tracing::info!("{}: Simulating {} trades", self.name(), trades.len());
         let chain = get_alloy_chain(config.network_name.as_str().to_string()).expect("Failed to get alloy chain");
-        let rpc = config.rpc_url.parse::<url::Url>().unwrap().clone(); // ! Custom per network
-        let pk = env.wallet_private_key.clone();
-        let wallet = PrivateKeySigner::from_bytes(&B256::from_str(&pk).expect("Failed to convert swapper pk to B256")).expect("Failed to private key signer");
+        let pool = RpcPool::of(&config);
+        let endpoint = self.rpc().unwrap_or_else(|| pool.read_url());
+        let rpc = endpoint.parse::<url::Url>().map_err(|e| ExecError::Rpc {
+            message: format!("Invalid RPC URL {}: {}", endpoint, e),
+            retryable: false,
+        })?;
+        let wallet = WalletSigner::from_env(&env).map_err(ExecError::Encoding)?;
         tracing::debug!("Wallet configured: {:?}", wallet.address().to_string().to_lowercase());
-        let signer = alloy::network::EthereumWallet::from(wallet.clone());
-        let provider = ProviderBuilder::new().with_chain(chain).wallet(signer.clone()).connect_http(rpc.clone());
+        let provider = ProviderBuilder::new().with_chain(chain).wallet(wallet.clone()).connect_http(rpc.clone());
 
         let mut output = vec![];
         for (idx, tx) in trades.iter().enumerate() {
             let time = std::time::Instant::now();
-            let _simulation_start = std::time::SystemTime::now();
-            let mut calls = vec![];
-            if let Some(approval) = &tx.approve {
-                calls.push(approval.clone());
-            }
-            calls.push(tx.swap.clone());
+            // Wrap, approval, swap and unwrap are simulated in sequence, the swap position depends on the first two
+            let request = tx.request();
+            let calls = request.calls();
+            let swap_index = request.swap_index();
+            let approve_index = request.approve.as_ref().map(|_| swap_index - 1);
+            let expected = calls.len();
 
-            tracing::debug!("Preparing simulation #{} with {} call(s)", idx, calls.len());
+            tracing::debug!("Preparing simulation #{} with {} call(s)", idx, expected);
 
             let payload = SimulatePayload {
                 block_state_calls: vec![SimBlock {
@@ -149,69 +277,58 @@ pub trait ExecStrategy: Send + Sync {
                 validation: true,
                 return_full_transactions: true,
             };
-            let mut smd = SimulatedData::default();
-            match provider.simulate(&payload).await {
+            let mut smd = SimulatedData {
+                trade_id: tx.metadata.trade_id.clone(),
+                ..Default::default()
+            };
+            let simulated = provider.simulate(&payload).await;
+            pool.report(&endpoint, time.elapsed(), simulated.is_ok());
+            smd.simulated_at_ms = now_ms();
+            smd.simulated_took_ms = time.elapsed().as_millis();
+            match simulated {
                 Ok(output) => {
-                    let now: std::time::SystemTime = std::time::SystemTime::now();
-                    let simulated_at_ms = now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
-                    smd.simulated_at_ms = simulated_at_ms;
                     for block in output.iter() {
                         tracing::trace!("🔮 Simulated on block #{} ...", block.inner.header.number);
-                        match block.calls.len() {
-                            1 => {
-                                // Swap only
-                                tracing::trace!("   => No approval needed, only swap");
-                                let swap = &block.calls[0];
-                                let took = time.elapsed().as_millis();
-                                smd.simulated_took_ms = took;
-                                smd.estimated_gas = swap.gas_used as u128;
-                                smd.status = swap.status;
-
-                                if !swap.status {
-                                    let reason = swap.error.clone().unwrap().message;
-                                    tracing::error!("   => Simulation failed on swap-only call. No broadcast. Reason: {}", reason);
-                                    tracing::error!("   🔍 DEBUG: Full swap error details:");
-                                    tracing::error!("      Error: {:#?}", swap.error);
-                                    tracing::error!("      Gas used: {}", swap.gas_used);
-                                    // tracing::error!("      Return data: {:?}", swap.return_data);
-                                    tracing::error!("      Logs: {:?}", swap.logs);
-                                    smd.error = Some(reason);
-                                } else {
-                                    tracing::info!("    => Swap simulation: Gas: {} | Status: {}", swap.gas_used, swap.status);
-                                }
+                        smd.simulated_block = block.inner.header.number;
+                        if block.calls.len() != expected {
+                            tracing::error!("Invalid number of calls in simulation: {} (expected {})", block.calls.len(), expected);
+                            smd.status = false;
+                            smd.error = Some(format!("Invalid number of calls: {}", block.calls.len()));
+                            continue;
+                        }
+                        let swap = &block.calls[swap_index];
+                        smd.estimated_gas = swap.gas_used as u128;
+                        // A failing wrap, swap or unwrap fails the trade (approval status is ignored for now)
+                        match block.calls.iter().enumerate().find(|(x, call)| Some(*x) != approve_index && !call.status) {
+                            Some((x, call)) => {
+                                let message = call.error.clone().map(|e| e.message).unwrap_or_default();
+                                let reason = crate::utils::evm::revert_reason(&message, &call.return_data);
+                                let label = if x == swap_index { "swap".to_string() } else { format!("call #{}", x) };
+                                tracing::error!("   => Simulation failed on {} of {}. No broadcast. Reason: {}", label, expected, reason);
+                                tracing::error!("   🔍 DEBUG: Full error details:");
+                                tracing::error!("      Error: {:#?}", call.error);
+                                tracing::error!("      Gas used: {}", call.gas_used);
+                                tracing::error!("      Logs: {:?}", call.logs);
+                                smd.status = false;
+                                smd.error = Some(reason);
                             }
-                            2 => {
-                                // Approve + Swap
-                                tracing::trace!(" - Approval needed, simulating both swap and approval");
-                                let approval = &block.calls[0]; // Approval is ignored for now
-                                let swap = &block.calls[1];
-                                tracing::trace!(" - Approval simulation: Gas: {} | Status: {}", approval.gas_used, approval.status);
-                                let took = time.elapsed().as_millis();
-                                smd.simulated_took_ms = took;
-                                smd.estimated_gas = swap.gas_used as u128;
-                                smd.status = swap.status;
-                                if !swap.status {
-                                    let reason = swap.error.clone().unwrap().message;
-                                    dbg!(&swap);
-                                    tracing::error!("   => Simulation failed on swap call. No broadcast. Reason: {}", reason);
-                                    smd.error = Some(reason);
-                                } else {
-                                    tracing::info!("    => Approval simulation: Gas: {} | Status: {}", approval.gas_used, approval.status);
-                                    tracing::info!("    => Swap simulation: Gas: {} | Status: {}", swap.gas_used, swap.status);
+                            None => {
+                                smd.status = true;
+                                for (x, call) in block.calls.iter().enumerate() {
+                                    let label = if x == swap_index { "Swap".to_string() } else { format!("Call #{}", x) };
+                                    tracing::info!("    => {} simulation: Gas: {} | Status: {}", label, call.gas_used, call.status);
                                 }
                             }
-                            _ => {
-                                tracing::error!("Invalid number of calls in simulation: {}", block.calls.len());
-                                smd.status = false;
-                                smd.error = Some(format!("Invalid number of calls: {}", block.calls.len()));
-                            }
                         }
                     }
                 }
                 Err(e) => {
                     tracing::error!("Failed to simulate: {:?}", e);
                     smd.status = false;
-                    smd.error = Some(format!("Simulation error: {:?}", e));
+                    smd.error = Some(match crate::utils::evm::rpc_revert(&e) {
+                        Some(revert) => format!("Simulation error: {}", revert),
+                        None => format!("Simulation error: {:?}", e),
+                    });
                 }
             };
             output.push(smd);
@@ -220,91 +337,166 @@ pub trait ExecStrategy: Send + Sync {
     }
 
     /// Broadcasts transactions to the network.
-    async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, String> {
-        tracing::info!("{}: Broadcasting {} trades", self.name(), prepared.len());
-        let alloy_chain = get_alloy_chain(mmc.network_name.as_str().to_string()).expect("Failed to get alloy chain");
-        let rpc = mmc.rpc_url.parse::<url::Url>().unwrap().clone();
-        let pk = env.wallet_private_key.clone();
-        let wallet = PrivateKeySigner::from_bytes(&B256::from_str(&pk).expect("Failed to convert swapper pk to B256")).expect("Failed to private key signer");
-        let signer = alloy::network::EthereumWallet::from(wallet.clone());
-        let provider = ProviderBuilder::new().with_chain(alloy_chain).wallet(signer.clone()).connect_http(rpc.clone());
-
-        if env.testing {
-            tracing::info!("Skipping broadcast ! Testing mode enabled");
-            return Ok(Vec::new());
+    async fn broadcast(&self, prepared: Vec<Trade>, mmc: MarketMakerConfig, env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        let name = self.name();
+        match self.rpc() {
+            Some(rpc) => send(&name, prepared, &mmc, &env, &rpc).await,
+            None => RpcPool::of(&mmc).write(|rpc| async move { send(&name, prepared, &mmc, &env, &rpc).await }).await,
         }
+    }
+}
 
-        let mut output = Vec::new();
-        for (x, tx) in prepared.iter().enumerate() {
-            tracing::debug!("   => Tx: #{} | Broadcasting on {}", x, mmc.network_name.as_str().to_string());
-            if tx.metadata.simulation.is_some() && !tx.metadata.simulation.as_ref().unwrap().status {
-                tracing::warn!("⚠️  Simulation failed for tx: #{}, but BROADCASTING ANYWAY for testing!", x);
-                if let Some(ref sim) = tx.metadata.simulation {
-                    if let Some(ref error) = sim.error {
-                        tracing::warn!("   Simulation error was: {}", error);
-                    }
+/// Sends the transactions of each trade one by one to `rpc` (wrap, approval, swap, then unwrap once the swap landed),
+/// waiting for the swap receipt before the next trade.
+pub async fn send(name: &str, prepared: Vec<Trade>, mmc: &MarketMakerConfig, env: &EnvConfig, rpc: &str) -> Result<Vec<BroadcastData>, ExecError> {
+    tracing::info!("{}: Broadcasting {} trades", name, prepared.len());
+    let alloy_chain = get_alloy_chain(mmc.network_name.as_str().to_string()).expect("Failed to get alloy chain");
+    let rpc = rpc.parse::<url::Url>().map_err(|e| ExecError::Rpc {
+        message: format!("Invalid RPC URL {}: {}", rpc, e),
+        retryable: false,
+    })?;
+    let wallet = WalletSigner::from_env(env).map_err(ExecError::Encoding)?;
+    let provider = ProviderBuilder::new().with_chain(alloy_chain).wallet(wallet.clone()).connect_http(rpc.clone());
+
+    if env.testing {
+        tracing::info!("Skipping broadcast ! Testing mode enabled");
+        return Ok(Vec::new());
+    }
+
+    let mut output = Vec::new();
+    for (x, tx) in prepared.iter().enumerate() {
+        tracing::debug!("   => Tx: #{} | Broadcasting on {}", x, mmc.network_name.as_str().to_string());
+        // One result per trade, a trade failing its simulation is reported rather than skipped
+        if let Some(simulation) = tx.metadata.simulation.as_ref().filter(|simulation| !simulation.status) {
+            tracing::warn!("⚠️  Simulation failed for tx: #{}, not broadcasting: {}", x, simulation.error.clone().unwrap_or_default());
+            output.push(BroadcastData::failed(
+                &tx.metadata.trade_id,
+                ExecError::Simulation {
+                    reason: format!("Simulation failed: {}", simulation.error.clone().unwrap_or_default()),
+                },
+            ));
+            continue;
+        }
+
+        // Past its deadline the trade was priced on stale pool states (e.g. behind the receipts of the previous trades), it is dropped
+        let valid_until = mmc.valid_until(tx.metadata.context.block);
+        let head = match HeadCache::of(mmc).and_then(|cache| cache.fresh(std::time::Duration::from_secs(mmc.ws_max_head_age_s))) {
+            Some(head) => Some(head.number),
+            None => provider.get_block_number().await.ok(),
+        };
+        if let Some(head) = head.filter(|head| *head > valid_until) {
+            tracing::warn!("   => Tx: #{} | Deadline passed: head {} past block {}, not broadcasting", x, head, valid_until);
+            output.push(BroadcastData::failed(
+                &tx.metadata.trade_id,
+                ExecError::Timeout(format!("Deadline passed: head {} past block {}", head, valid_until)),
+            ));
+            continue;
+        }
+
+        // Handle optional wrap transaction, its nonce comes first so it lands before the swap
+        let time = std::time::SystemTime::now();
+        if let Some(wrap_tx) = &tx.wrap {
+            match provider.send_transaction(wrap_tx.clone()).await {
+                Ok(wrap) => {
+                    let took = time.elapsed().unwrap_or_default().as_millis();
+                    tracing::debug!("   => Explorer: {}tx/{} | Wrap shoot took {} ms", mmc.explorer_url, wrap.tx_hash(), took);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to send wrap transaction: {:?}", e);
+                    output.push(BroadcastData::failed(&tx.metadata.trade_id, ExecError::transport("Failed to send wrap transaction", &e)));
+                    continue;
                 }
-                // Continue with broadcast instead of skipping
-                continue;
             }
+        }
 
-            // Handle optional approval transaction
-            let time = std::time::SystemTime::now();
-            let _approval = if let Some(approval_tx) = &tx.approve {
-                match provider.send_transaction(approval_tx.clone()).await {
-                    Ok(approve) => {
-                        let took = time.elapsed().unwrap_or_default().as_millis();
-                        tracing::debug!("   => Explorer: {}tx/{} | Approval shoot took {} ms", mmc.explorer_url, approve.tx_hash(), took);
-                        Some(approve)
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to send approval transaction: {:?}", e);
-                        None
-                    }
+        // Handle optional approval transaction
+        let time = std::time::SystemTime::now();
+        let _approval = if let Some(approval_tx) = &tx.approve {
+            match provider.send_transaction(approval_tx.clone()).await {
+                Ok(approve) => {
+                    let took = time.elapsed().unwrap_or_default().as_millis();
+                    tracing::debug!("   => Explorer: {}tx/{} | Approval shoot took {} ms", mmc.explorer_url, approve.tx_hash(), took);
+                    Some(approve)
                 }
-            } else {
-                tracing::debug!("   => Skipping approval transaction (♾️  infinite_approval enabled)");
-                None
-            };
+                Err(e) => {
+                    tracing::error!("Failed to send approval transaction: {:?}", e);
+                    None
+                }
+            }
+        } else {
+            tracing::debug!("   => Skipping approval transaction (♾️  infinite_approval enabled)");
+            None
+        };
 
-            let time = std::time::SystemTime::now();
-            let mut bd = BroadcastData::default();
-            // Send swap transaction
-            match provider.send_transaction(tx.swap.clone()).await {
-                Ok(swap) => {
-                    let took = time.elapsed().unwrap_or_default().as_millis();
-                    let now = std::time::SystemTime::now();
-                    let broadcasted_at_ms = now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
-                    let tx_description = if tx.approve.is_some() { "Swap (+ approval)" } else { "Swap only" };
-                    tracing::debug!("   => Explorer: {}tx/{} | {} broadcast took {} ms", mmc.explorer_url, swap.tx_hash(), tx_description, took);
-                    bd.broadcasted_at_ms = broadcasted_at_ms;
-                    bd.broadcasted_took_ms = took;
-                    bd.hash = swap.tx_hash().to_string();
-                    // Wait for receipt, else, it would cause nonce issues if we send the next tx too soon
-                    let time = std::time::SystemTime::now();
-                    match swap.get_receipt().await {
-                        Ok(receipt) => {
-                            let took = time.elapsed().unwrap_or_default().as_millis();
-                            tracing::debug!(
-                                "   => Swap transaction receipt received, tx included at block: {:?} with status: {:?} | Took {} ms to get receipt",
-                                receipt.block_number,
-                                receipt.status(),
-                                took
-                            );
+        let time = std::time::SystemTime::now();
+        let mut bd = BroadcastData {
+            trade_id: tx.metadata.trade_id.clone(),
+            broadcasted_at_ms: now_ms(),
+            ..Default::default()
+        };
+        // Send swap transaction
+        match provider.send_transaction(tx.swap.clone()).await {
+            Ok(swap) => {
+                let took = time.elapsed().unwrap_or_default().as_millis();
+                let tx_description = match (tx.wrap.is_some(), tx.approve.is_some()) {
+                    (true, true) => "Swap (+ wrap, approval)",
+                    (true, false) => "Swap (+ wrap)",
+                    (false, true) => "Swap (+ approval)",
+                    (false, false) => "Swap only",
+                };
+                tracing::debug!("   => Explorer: {}tx/{} | {} broadcast took {} ms", mmc.explorer_url, swap.tx_hash(), tx_description, took);
+                bd.broadcasted_took_ms = took;
+                bd.hash = swap.tx_hash().to_string();
+                // Wait for receipt, else, it would cause nonce issues if we send the next tx too soon
+                let time = std::time::SystemTime::now();
+                match swap.get_receipt().await {
+                    Ok(receipt) => {
+                        let took = time.elapsed().unwrap_or_default().as_millis();
+                        tracing::debug!(
+                            "   => Swap transaction receipt received, tx included at block: {:?} with status: {:?} | Took {} ms to get receipt",
+                            receipt.block_number,
+                            receipt.status(),
+                            took
+                        );
+                        // Unwrap only once the swap delivered the wrapped token
+                        if let Some(unwrap_tx) = tx.unwrap.as_ref().filter(|_| receipt.status()) {
+                            match provider.send_transaction(unwrap_tx.clone()).await {
+                                Ok(unwrap) => tracing::debug!("   => Explorer: {}tx/{} | Unwrap sent", mmc.explorer_url, unwrap.tx_hash()),
+                                Err(e) => tracing::error!("Failed to send unwrap transaction: {:?}", e),
+                            }
                         }
-                        Err(e) => {
-                            tracing::error!("Failed to get swap transaction receipt: {:?}", e.to_string());
-                            bd.broadcast_error = Some(format!("Failed to get swap transaction receipt: {:?}", e.to_string()));
+                        // A receipt carries no revert data, the swap is replayed on the state of its block to decode it
+                        let error = if receipt.status() {
+                            None
+                        } else {
+                            crate::utils::evm::replay_revert(&provider, tx.swap.clone(), receipt.block_number.unwrap_or_default()).await
+                        };
+                        if let Some(reason) = error.as_ref() {
+                            tracing::error!("   => Swap reverted: {}", reason);
                         }
+                        bd.receipt = Some(ReceiptData {
+                            status: receipt.status(),
+                            gas_used: receipt.gas_used as u128,
+                            effective_gas_price: receipt.effective_gas_price,
+                            error,
+                            transaction_hash: receipt.transaction_hash.to_string(),
+                            transaction_index: receipt.transaction_index.unwrap_or_default(),
+                            block_number: receipt.block_number.unwrap_or_default(),
+                            transfers: crate::utils::evm::transfers(receipt.inner.logs()),
+                        });
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to get swap transaction receipt: {:?}", e.to_string());
+                        bd.fail(ExecError::pending("Failed to get swap transaction receipt", &e));
                     }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to send swap transaction: {:?}", e);
-                    bd.broadcast_error = Some(format!("Failed to send swap transaction: {:?}", e));
                 }
             }
-            output.push(bd);
+            Err(e) => {
+                tracing::error!("Failed to send swap transaction: {:?}", e);
+                bd.fail(ExecError::transport("Failed to send swap transaction", &e));
+            }
         }
-        Ok(output)
+        output.push(bd);
     }
+    Ok(output)
 }
@@ -10,6 +10,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::types::{config::MarketMakerConfig, sol::IChainLinkPF};
+use crate::utils::evm::RpcPool;
 
 /// Interface for external price feed implementations.
 #[async_trait]
@@ -77,7 +78,7 @@ impl PriceFeed for ChainlinkPriceFeed {
     /// Fetches price from Chainlink oracle, optionally inverting if configured.
     async fn get(&self, mmc: MarketMakerConfig) -> Result<f64, String> {
         let rev = mmc.price_feed_config.reverse;
-        match chainlink(mmc.rpc_url.clone(), mmc.price_feed_config.source.clone()).await {
+        match RpcPool::of(&mmc).call(|url| chainlink(url, mmc.price_feed_config.source.clone())).await {
             Ok(price) => match rev {
                 true => Ok(1. / price),
                 false => Ok(price),
@@ -0,0 +1,61 @@
+//! Frozen Context Module
+//!
+//! Pins everything the decisions read outside of the Tycho stream (reference price, gas prices, ETH/USD,
+//! inventory) to a JSON fixture for `--frozen-context`, so the profitability math can be debugged against
+//! live or replayed pool states. A frozen market maker always runs the dry run strategy.
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maker::exec::dry::DryRunExec,
+    types::maker::{Inventory, MarketContext, MarketMaker},
+};
+
+/// Fixture of `--frozen-context`, `context` and `inventory` with the fields of `MarketContext` and `Inventory`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrozenContext {
+    pub reference_price: f64, // Quote per base, returned by fetch_market_price
+    pub context: MarketContext,
+    pub inventory: Inventory,
+}
+
+impl FrozenContext {
+    /// Reads and checks a fixture. Any missing, unknown or mistyped field is an error, with its line and column.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("Failed to read frozen context {}: {}", path, e))?;
+        let frozen = serde_json::from_str::<FrozenContext>(&raw).map_err(|e| format!("Invalid frozen context {}: {}", path, e))?;
+        frozen.validate().map_err(|e| format!("Invalid frozen context {}: {}", path, e))?;
+        Ok(frozen)
+    }
+
+    /// Prices must be positive, a zero would silently zero the gas and USD math.
+    pub fn validate(&self) -> Result<(), String> {
+        let prices = [
+            ("reference_price", self.reference_price),
+            ("context.base_to_eth", self.context.base_to_eth),
+            ("context.quote_to_eth", self.context.quote_to_eth),
+            ("context.eth_to_usd", self.context.eth_to_usd),
+        ];
+        match prices.iter().find(|(_, price)| !price.is_finite() || *price <= 0.) {
+            Some((name, price)) => Err(format!("{} must be positive, got {}", name, price)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl MarketMaker {
+    /// Reads the reference price, market context and inventory from `frozen` from now on, and swaps the execution
+    /// strategy for the dry run one so nothing can be broadcast.
+    pub fn freeze(&mut self, frozen: FrozenContext) {
+        tracing::warn!(
+            "🧊 Frozen context: reference {} | ETH {} $ | gas {} wei | block {} | dry run forced",
+            frozen.reference_price,
+            frozen.context.eth_to_usd,
+            frozen.context.native_gas_price,
+            frozen.context.block
+        );
+        self.config.dry_run = true;
+        self.execution = Box::new(DryRunExec::new());
+        self.frozen = Some(frozen);
+    }
+}
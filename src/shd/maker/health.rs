@@ -0,0 +1,275 @@
+//! Health Module
+//!
+//! Liveness and readiness of the market makers of the process, served over HTTP for the Docker and Kubernetes probes.
+//! A wedged Tycho stream (no message for minutes) keeps the process alive, so a restart relying on the process
+//! exiting never comes. `/healthz` answers 200 as long as the process serves requests. `/readyz` answers 200 only
+//! when every instance has its stream ready, got a Tycho message within `stream_staleness_threshold_s`, got an answer
+//! from its price feed, and holds a Redis connection when it publishes events, 503 with the reasons otherwise.
+//! The server is the process' operational endpoint, the place for the metrics too. The same state, with the last block,
+//! trade, breaker and inventory, makes the heartbeat payload (`utils::uptime`).
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    data::publisher::PublisherStats,
+    types::{
+        config::MarketMakerConfig,
+        moni::{HeartbeatPayload, InventorySummary, TradingState},
+    },
+};
+
+/// Health of one market maker, updated by its loop.
+#[derive(Debug)]
+struct Health {
+    identifier: String,
+    staleness_threshold_ms: u128,
+    publishes: bool,       // Redis must be reachable
+    ready: bool,           // Stream initialised (first message processed)
+    last_message_ms: u128, // Last Tycho message (unix ms), 0 if none yet
+    feed_ok: bool,         // Last price feed call answered
+    stream_lag_blocks: Option<u64>,
+    last_readiness: Option<bool>,
+    flaps: u64,                 // Readiness changes seen by the probes
+    block: u64,                 // Last block received
+    last_trade_ms: Option<u64>, // Last executed trade (unix ms)
+    breaker_open: bool,
+    inventory: Option<InventorySummary>,
+    reconnects: u64,               // Stream reconnections since start
+    time_to_ready_ms: Option<u64>, // From the last disconnect to the stream ready again
+}
+
+/// Cloneable handle on the health of one market maker, shared between its loop and the HTTP server.
+#[derive(Debug, Clone)]
+pub struct HealthState {
+    inner: Arc<Mutex<Health>>,
+}
+
+/// Readiness of one instance, and why it is not ready.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Readiness {
+    pub identifier: String,
+    pub ready: bool,
+    pub reasons: Vec<String>,
+    pub last_message_ms: u64,
+    pub flaps: u64,
+    pub stream_lag_blocks: Option<u64>, // Gauge, last sampled delay of the stream behind the chain head
+    pub reconnects: u64,                // Counter, stream reconnections since start
+    pub time_to_ready_ms: Option<u64>,  // Gauge, from the last disconnect to the stream ready again
+}
+
+impl HealthState {
+    pub fn new(identifier: &str, config: &MarketMakerConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Health {
+                identifier: identifier.to_string(),
+                staleness_threshold_ms: config.stream_staleness_threshold_s as u128 * 1_000,
+                publishes: config.publish_events,
+                ready: false,
+                last_message_ms: 0,
+                feed_ok: false,
+                stream_lag_blocks: None,
+                last_readiness: None,
+                flaps: 0,
+                block: 0,
+                last_trade_ms: None,
+                breaker_open: false,
+                inventory: None,
+                reconnects: 0,
+                time_to_ready_ms: None,
+            })),
+        }
+    }
+
+    /// Records a Tycho message received at `now` (unix ms).
+    pub fn message(&self, now: u128) {
+        self.inner.lock().unwrap().last_message_ms = now;
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.inner.lock().unwrap().ready = ready;
+    }
+
+    /// Records the outcome of the last price feed call.
+    pub fn feed(&self, ok: bool) {
+        self.inner.lock().unwrap().feed_ok = ok;
+    }
+
+    /// Records the last sampled lag of the stream behind the chain head, in blocks.
+    pub fn lag(&self, blocks: Option<u64>) {
+        self.inner.lock().unwrap().stream_lag_blocks = blocks;
+    }
+
+    /// Records a reconnection of the stream.
+    pub fn reconnecting(&self) {
+        self.inner.lock().unwrap().reconnects += 1;
+    }
+
+    /// Records the stream ready again `elapsed_ms` after its disconnect.
+    pub fn recovered(&self, elapsed_ms: u64) {
+        self.inner.lock().unwrap().time_to_ready_ms = Some(elapsed_ms);
+    }
+
+    pub fn block(&self, block: u64) {
+        self.inner.lock().unwrap().block = block;
+    }
+
+    /// Records a trade executed at `now` (unix ms).
+    pub fn traded(&self, now: u64) {
+        self.inner.lock().unwrap().last_trade_ms = Some(now);
+    }
+
+    pub fn breaker(&self, open: bool) {
+        self.inner.lock().unwrap().breaker_open = open;
+    }
+
+    pub fn inventory(&self, inventory: InventorySummary) {
+        self.inner.lock().unwrap().inventory = Some(inventory);
+    }
+
+    /// Heartbeat payload of the instance at `now` (unix ms).
+    pub fn heartbeat(&self, commit: &str, state: TradingState, now: u64, failures: u64) -> HeartbeatPayload {
+        let health = self.inner.lock().unwrap();
+        HeartbeatPayload {
+            identifier: health.identifier.clone(),
+            commit: commit.to_string(),
+            timestamp_ms: now,
+            state,
+            block: health.block,
+            last_trade_ms: health.last_trade_ms,
+            stream_ready: health.ready,
+            breaker_open: health.breaker_open,
+            inventory: health.inventory.clone(),
+            failures,
+        }
+    }
+
+    /// Readiness at `now` (unix ms), Redis being reachable or not.
+    pub fn check(&self, now: u128, redis: bool) -> Readiness {
+        let health = self.inner.lock().unwrap();
+        let mut reasons = vec![];
+        if !health.ready {
+            reasons.push("stream not ready".to_string());
+        }
+        if health.last_message_ms == 0 {
+            reasons.push("no Tycho message received".to_string());
+        } else if now.saturating_sub(health.last_message_ms) > health.staleness_threshold_ms {
+            reasons.push(format!(
+                "stream stale: last Tycho message {} s ago (threshold {} s)",
+                now.saturating_sub(health.last_message_ms) / 1_000,
+                health.staleness_threshold_ms / 1_000
+            ));
+        }
+        if !health.feed_ok {
+            reasons.push("price feed not responding".to_string());
+        }
+        if health.publishes && !redis {
+            reasons.push("Redis unreachable".to_string());
+        }
+        Readiness {
+            identifier: health.identifier.clone(),
+            ready: reasons.is_empty(),
+            reasons,
+            last_message_ms: health.last_message_ms as u64,
+            flaps: health.flaps,
+            stream_lag_blocks: health.stream_lag_blocks,
+            reconnects: health.reconnects,
+            time_to_ready_ms: health.time_to_ready_ms,
+        }
+    }
+
+    /// Readiness as seen by a probe, logging when it changes since the previous probe.
+    pub fn probe(&self, now: u128, redis: bool) -> Readiness {
+        let mut readiness = self.check(now, redis);
+        let mut health = self.inner.lock().unwrap();
+        if health.last_readiness.is_some_and(|previous| previous != readiness.ready) {
+            health.flaps += 1;
+            readiness.flaps = health.flaps;
+            if readiness.ready {
+                tracing::info!("💚 {} ready again ({} readiness changes)", health.identifier, health.flaps);
+            } else {
+                tracing::warn!("💔 {} not ready: {} ({} readiness changes)", health.identifier, readiness.reasons.join(", "), health.flaps);
+            }
+        }
+        health.last_readiness = Some(readiness.ready);
+        readiness
+    }
+}
+
+/// Status code and JSON body answering a GET on `path`. `/readyz` also reports the queue of the Redis publisher.
+pub fn respond(path: &str, states: &[HealthState], now: u128, publisher: PublisherStats) -> (u16, String) {
+    match path {
+        "/healthz" => (200, serde_json::json!({ "alive": true }).to_string()),
+        "/readyz" => {
+            let instances = states.iter().map(|state| state.probe(now, publisher.connected)).collect::<Vec<Readiness>>();
+            let ready = instances.iter().all(|instance| instance.ready);
+            let body = serde_json::json!({ "ready": ready, "instances": instances, "publisher": publisher }).to_string();
+            (if ready { 200 } else { 503 }, body)
+        }
+        _ => (404, serde_json::json!({ "error": format!("Unknown path {}", path) }).to_string()),
+    }
+}
+
+/// Answers one HTTP request, then closes the connection.
+async fn handle(mut socket: TcpStream, states: &[HealthState]) {
+    let mut buffer = [0u8; 1024];
+    let Ok(read) = socket.read(&mut buffer).await else {
+        return;
+    };
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut line = request.lines().next().unwrap_or_default().split_whitespace();
+    let (status, body) = match (line.next(), line.next()) {
+        (Some("GET"), Some(path)) => {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+            respond(path.split('?').next().unwrap_or_default(), states, now, crate::data::publisher::stats())
+        }
+        _ => (405, serde_json::json!({ "error": "Only GET is supported" }).to_string()),
+    };
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+/// Serves the health endpoints on a bound listener, until the process exits.
+pub async fn serve(listener: TcpListener, states: Vec<HealthState>) {
+    let states = Arc::new(states);
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                let states = Arc::clone(&states);
+                tokio::spawn(async move { handle(socket, &states).await });
+            }
+            Err(e) => tracing::warn!("Health server failed to accept a connection: {}", e),
+        }
+    }
+}
+
+/// Binds the health server on `port` (all interfaces) and spawns it. Returns the bound address.
+pub async fn spawn(port: u16, states: Vec<HealthState>) -> Result<SocketAddr, String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| format!("Failed to bind the health server on port {}: {}", port, e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    tracing::info!("💓 Health server listening on {} (/healthz, /readyz)", address);
+    tokio::spawn(serve(listener, states));
+    Ok(address)
+}
@@ -1,38 +1,69 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
-    maker::tycho::{cpname, get_component_balances},
-    opti::routing,
+    error::{ExecError, ExecPolicy},
+    maker::{
+        audit::{Decision, Gate},
+        breaker::BreakerEvent,
+        journal::{self, JournalEntry},
+        lag::LagTransition,
+        latency::{time_out, Deadline, Stage},
+        permit2::SignedPermit,
+        simcache::CachedSim,
+        sizing::{blocking, net_price, swap_value, SizedReadjustment, Sizing, SwapValue},
+        snapshot::{self, PoolSnapshot},
+        tycho::{amm_fee_to_bps, component_balances_local, cpname, excluded_v4_hook, pair_indices, retry_auth, PoolBalances, SolutionEncoder, TychoApiError, TychoBalances, TychoEncoder},
+        valuation::{cap_worth, worth},
+        watcher::ExternalMovement,
+    },
+    opti::{
+        adaptive::AdaptiveThreshold,
+        exposure::{Exposure, ExposureLimit},
+        impact,
+        math::{powered, shift_bps, TerminationReason},
+        routing::{self, TokenGraph},
+        skew::{self, InventorySkew},
+    },
     types::{
-        config::EnvConfig,
+        config::{EnvConfig, RebalanceMode},
         maker::{
-            CompReadjustment, ComponentPriceData, ExecutionOrder, Inventory, MarketContext, MarketMaker, PreTradeData, SwapCalculation, Trade, TradeData, TradeDirection, TradeStatus, TradeTxRequest,
+            CompReadjustment, ComponentPriceData, ExecutionOrder, Inventory, MarketContext, MarketMaker, PnlSnapshot, PoolDepth, PreTradeData, RealizedData, ReceiptData, SwapCalculation, Trade,
+            TradeData, TradeDirection, TradeStatus, TradeTxRequest,
         },
-        moni::NewPricesMessage,
-        tycho::{ProtoSimComp, PsbConfig, SharedTychoStreamState},
+        moni::{AlertKind, InventorySummary, NewAlertMessage, NewPnlMessage, NewPricesMessage},
+        tycho::{ProtoSimComp, PsbConfig, SharedTychoStreamState, SharedUpdate},
     },
-    utils::constants::{
-        ADD_TVL_THRESHOLD, APPROVE_FN_SIGNATURE, BASIS_POINT_DENO, DEFAULT_APPROVE_GAS, DEFAULT_SWAP_GAS, MAX_POOL_PRICE_DEVIATION_PCT, MIN_AMOUNT_WORTH_USD, NULL_ADDRESS, PERCENT_MULTIPLIER,
+    utils::{
+        constants::{
+            APPROVE_FN_SIGNATURE, BASIS_POINT_DENO, DEFAULT_APPROVE_GAS, DEFAULT_SWAP_GAS, DEFAULT_WRAP_GAS, MAX_POOL_PRICE_DEVIATION_PCT, NULL_ADDRESS, PERCENT_MULTIPLIER,
+            SNAPSHOT_MONITOR_INTERVAL_MS,
+        },
+        evm::{is_native, received, sent, GasOracle, GasQuote, RpcPool},
+        head::{BlockHead, HeadCache},
+        multicall::Read,
     },
 };
 use alloy::{
-    providers::{Provider, ProviderBuilder},
+    providers::Provider,
     rpc::types::{TransactionInput, TransactionRequest},
     sol_types::{SolCall, SolValue},
 };
 
 use alloy_primitives::{Address, U256};
-use futures::StreamExt;
+use futures::{FutureExt, Stream, StreamExt};
 use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::Instrument;
 use tycho_client::feed::component_tracker::ComponentFilter;
 use tycho_common::models::token::Token;
 use tycho_common::simulation::protocol_sim::ProtocolSim; // ProtocolSim trait for protocol simulation
-use tycho_execution::encoding::{
-    evm::encoder_builders::TychoRouterEncoderBuilder,
-    models::{EncodedSolution, Solution, SwapBuilder, Transaction, UserTransferType},
-};
-use tycho_simulation::protocol::models::ProtocolComponent;
+use tycho_execution::encoding::models::{EncodedSolution, Solution, SwapBuilder, Transaction};
 
 use alloy_primitives::keccak256;
 use alloy_primitives::Bytes as AlloyBytes;
@@ -88,7 +119,7 @@ sol! {
 ///
 /// Computes the function selector (first 4 bytes of keccak256 hash) and
 /// combines it with the ABI-encoded arguments.
-fn encode_input(signature: &str, args: Vec<u8>) -> Vec<u8> {
+pub(crate) fn encode_input(signature: &str, args: Vec<u8>) -> Vec<u8> {
     // Compute function selector (first 4 bytes of keccak256 hash of signature)
     let hash = keccak256(signature.as_bytes());
     let selector = &hash[..4];
@@ -115,12 +146,136 @@ impl MarketContext {
     }
 }
 
+impl ExecutionOrder {
+    /// Amounts of the executed swap, raw: sent and received by `wallet` in the transfers of its receipt. A leg without
+    /// transfer, a native one, falls back on the input sent to the router and the expected output (the target exact out).
+    pub fn executed(&self, receipt: &ReceiptData, wallet: &str) -> (u128, u128) {
+        let calculation = &self.calculation;
+        let spent = match sent(&receipt.transfers, &self.adjustment.selling.address.to_string(), wallet) {
+            0 => MarketMaker::router_input(self),
+            raw => raw,
+        };
+        let expected = if calculation.exact_out {
+            calculation.amount_out_min_powered
+        } else {
+            calculation.amount_out_powered
+        };
+        let received = match received(&receipt.transfers, &self.adjustment.buying.address.to_string(), wallet) {
+            0 => expected.floor() as u128,
+            raw => raw,
+        };
+        (spent, received)
+    }
+}
+
+impl Inventory {
+    /// Checks the native balance covers the configured floor plus the projected gas of the pending trades.
+    pub fn preflight(&self, min_native_balance_wei: u128, projected_gas_wei: u128) -> Result<(), String> {
+        let required = min_native_balance_wei.saturating_add(projected_gas_wei);
+        if self.native_balance < required {
+            return Err(format!(
+                "Native balance too low: {:.6} ETH < {:.6} ETH required (min_native_balance_wei {} + projected gas {})",
+                self.native_balance as f64 / 1e18,
+                required as f64 / 1e18,
+                min_native_balance_wei,
+                projected_gas_wei
+            ));
+        }
+        Ok(())
+    }
+
+    /// Native balance that can be wrapped, keeping the floor and a gas reserve.
+    pub fn wrappable(&self, min_native_balance_wei: u128, gas_reserve_wei: u128) -> u128 {
+        self.native_balance.saturating_sub(min_native_balance_wei.saturating_add(gas_reserve_wei))
+    }
+
+    /// Applies an executed swap to the inventory from its receipt, as amounts in token units (powered): the amounts
+    /// `wallet` sent and received, see `ExecutionOrder::executed`. A reverted swap only costs gas and a nonce.
+    /// Returns false without changes when there is no receipt, the outcome is then unknown.
+    pub fn settle(&mut self, order: &ExecutionOrder, receipt: Option<&ReceiptData>, wallet: &str) -> bool {
+        let Some(receipt) = receipt else {
+            return false;
+        };
+        self.nonce += 1;
+        self.native_balance = self.native_balance.saturating_sub(receipt.gas_used.saturating_mul(receipt.effective_gas_price));
+        if receipt.status {
+            let (spent, received) = order.executed(receipt, wallet);
+            if order.calculation.base_to_quote {
+                self.base_balance = self.base_balance.saturating_sub(spent);
+                self.quote_balance = self.quote_balance.saturating_add(received);
+            } else {
+                self.quote_balance = self.quote_balance.saturating_sub(spent);
+                self.base_balance = self.base_balance.saturating_add(received);
+            }
+        }
+        true
+    }
+}
+
+impl TradeTxRequest {
+    /// Transactions in submission order: wrap, approval, swap, unwrap.
+    pub fn calls(&self) -> Vec<TransactionRequest> {
+        self.wrap
+            .iter()
+            .chain(self.approve.iter())
+            .chain(std::iter::once(&self.swap))
+            .chain(self.unwrap.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Position of the swap in `calls`.
+    pub fn swap_index(&self) -> usize {
+        self.wrap.iter().count() + self.approve.iter().count()
+    }
+
+    /// Assigns consecutive nonces, from the given one, in submission order.
+    pub fn sequence(&mut self, nonce: u64) {
+        let txs = self.wrap.iter_mut().chain(self.approve.iter_mut()).chain(std::iter::once(&mut self.swap)).chain(self.unwrap.iter_mut());
+        for (x, tx) in txs.enumerate() {
+            tx.nonce = Some(nonce + x as u64);
+        }
+    }
+}
+
+impl Trade {
+    /// Transaction requests of the trade, without its metadata.
+    pub fn request(&self) -> TradeTxRequest {
+        TradeTxRequest {
+            wrap: self.wrap.clone(),
+            approve: self.approve.clone(),
+            swap: self.swap.clone(),
+            unwrap: self.unwrap.clone(),
+        }
+    }
+}
+
+/// Upper bound of the gas paid by the given trades (gas limit * max fee, approval, wrap and unwrap included).
+pub fn projected_gas_wei(trades: &[Trade]) -> u128 {
+    trades
+        .iter()
+        .flat_map(|trade| trade.request().calls())
+        .map(|tx| (tx.gas.unwrap_or_default() as u128).saturating_mul(tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default()))
+        .fold(0u128, |acc, cost| acc.saturating_add(cost))
+}
+
+/// Native amount the given trades wrap, leaving the wallet with their deposits.
+pub fn wrapped_wei(trades: &[Trade]) -> u128 {
+    trades
+        .iter()
+        .filter_map(|trade| trade.wrap.as_ref().and_then(|tx| tx.value))
+        .fold(0u128, |acc, value| acc.saturating_add(value.saturating_to::<u128>()))
+}
+
 /// Internal methods for MarketMaker - not part of the public trait interface.
 impl MarketMaker {
     /// Fetches ETH/USD price for gas cost calculations.
     ///
     /// Uses Chainlink oracle if configured, falls back to CoinGecko.
     async fn fetch_eth_usd(&self) -> Result<f64, String> {
+        if let Some(frozen) = &self.frozen {
+            return Ok(frozen.context.eth_to_usd);
+        }
         if self.config.gas_token_chainlink_price_feed.is_empty() {
             tracing::warn!("No gas oracle feed found, using Coingecko");
             if let Some(price) = super::feed::coingecko_eth_usd().await {
@@ -130,22 +285,31 @@ impl MarketMaker {
             // return Ok(3500.0);
             return Err("No gas oracle feed found, even using Coingecko".to_string());
         }
-        super::feed::chainlink(self.config.rpc_url.clone(), self.config.gas_token_chainlink_price_feed.clone()).await
+        RpcPool::of(&self.config)
+            .call(|url| super::feed::chainlink(url, self.config.gas_token_chainlink_price_feed.clone()))
+            .await
     }
 
-    /// Calculates spot prices for all protocol components (pools).
-    fn prices(&self, psc: &[ProtoSimComp]) -> Vec<ComponentPriceData> {
+    /// Calculates the spot prices (quote per base) of the protocol components (pools).
+    ///
+    /// The base and quote tokens are located by address in the component tokens, whatever their order or number.
+    /// Components without exactly one of each are skipped, as are prices out of the sanity band of the `reference`
+    /// whose inverse is within it: an orientation error, that would trade in the wrong direction. Other prices out of the
+    /// band are kept, for the quarantine to report them.
+    pub fn prices(&self, psc: &[ProtoSimComp], reference: f64) -> Vec<ComponentPriceData> {
         let mut ss = Vec::new();
         for proto in psc.iter() {
-            let token0 = proto.component.tokens[0].address.to_string().to_lowercase();
-            let is0base = token0 == self.base.address.to_string().to_lowercase();
-            let protosim = proto.protosim.clone();
-            let result = if is0base {
-                protosim.spot_price(&proto.component.tokens[0], &proto.component.tokens[1])
-            } else {
-                protosim.spot_price(&proto.component.tokens[1], &proto.component.tokens[0])
+            let (b, q) = match pair_indices(&proto.component, &self.base, &self.quote) {
+                Ok(indices) => indices,
+                Err(e) => {
+                    tracing::warn!("Skipping spot price of component {}: {}", proto.component.id, e);
+                    continue;
+                }
             };
-            match result {
+            match proto.protosim.spot_price(&proto.component.tokens[b], &proto.component.tokens[q]) {
+                Ok(price) if self.inverted(price, reference) => {
+                    tracing::warn!("Skipping spot price of component {}: {} looks inverted against the reference {}", proto.component.id, price, reference);
+                }
                 Ok(price) => {
                     ss.push(ComponentPriceData {
                         address: proto.component.id.to_string().to_lowercase(),
@@ -161,90 +325,375 @@ impl MarketMaker {
         ss
     }
 
+    /// Evaluates the latest snapshot against the live reference while the stream is not ready, monitor-only: the signals
+    /// are logged and the snapshot prices published as degraded, no order is built from a possibly stale state.
+    pub(crate) async fn monitor_only(&mut self) {
+        let Some(snapshot) = self.snapshot.latest().cloned() else {
+            return;
+        };
+        let reference = match self.market_data.market_price(self).await {
+            Ok(price) if price > 0.0 => price,
+            _ => {
+                tracing::warn!("📸 Monitor-only: failed to fetch the reference price");
+                return;
+            }
+        };
+        let age_s = snapshot::now_s().saturating_sub(snapshot.saved_at_s);
+        for signal in snapshot::evaluate(&self.config, &snapshot, reference) {
+            match signal.direction {
+                Some(direction) => tracing::info!(
+                    "📸 Monitor-only (snapshot of block {}, {} s old) | Pool {}: spread {:.2} bps above {:.2} bps, would {:?}, not executed",
+                    snapshot.block,
+                    age_s,
+                    signal.component,
+                    signal.spread_bps,
+                    signal.threshold_bps,
+                    direction
+                ),
+                None => tracing::debug!(
+                    "📸 Monitor-only | Pool {}: spread {:.2} bps (threshold {:.2} bps)",
+                    signal.component,
+                    signal.spread_bps,
+                    signal.threshold_bps
+                ),
+            }
+        }
+        if self.config.publish_events {
+            let _ = crate::data::r#pub::prices(NewPricesMessage {
+                identifier: self.identifier.clone(),
+                reference_price: reference,
+                components: snapshot.prices(),
+                block: snapshot.block,
+                depth: None,
+                state: self.control.state(),
+                sigma_bps: 0.0,
+                execution_threshold_bps: self.execution_threshold_bps(),
+                adaptive_bps: self.adaptive.widening_bps(),
+                quarantined: self.quarantine.len(),
+                stream_lag_blocks: None,
+                degraded: true,
+            });
+        }
+    }
+
+    /// Returns true if a spot price is out of the sanity band of the reference while its inverse is within it.
+    fn inverted(&self, price: f64, reference: f64) -> bool {
+        if reference <= 0. || price <= 0. {
+            return false;
+        }
+        let spread_bps = |spot: f64| (spot - reference) / reference * BASIS_POINT_DENO;
+        !self.quarantine.is_plausible(spread_bps(price)) && self.quarantine.is_plausible(spread_bps(1. / price))
+    }
+
     /// Fetches current wallet token balances and transaction nonce.
-    async fn fetch_inventory(&self, _env: EnvConfig) -> Result<Inventory, String> {
-        let provider = ProviderBuilder::new().connect_http(self.config.rpc_url.clone().parse().expect("Failed to parse RPC_URL"));
+    pub async fn fetch_inventory(&self, _env: EnvConfig) -> Result<Inventory, String> {
+        if let Some(frozen) = &self.frozen {
+            return Ok(frozen.inventory.clone());
+        }
         let tokens = [self.base.clone(), self.quote.clone()];
         let addresses = tokens.iter().map(|t| t.address.to_string()).collect::<Vec<String>>();
-        match crate::utils::evm::balances(&provider, self.config.wallet_public_key.clone(), addresses).await {
-            Ok(balances) => match provider.get_transaction_count(self.config.wallet_public_key.to_string().parse().unwrap()).await {
-                Ok(nonce) => {
-                    let mut msgs = vec![];
-                    for (x, tk) in tokens.iter().enumerate() {
-                        let balance = balances.get(x).cloned().unwrap_or_default();
-                        let divided = balance as f64 / 10f64.powi(tk.decimals as i32);
-                        msgs.push(format!("{:.5} of {}", divided, tk.symbol));
-                    }
-                    tracing::debug!("💵  Inventory evaluation: Nonce {} | Wallet {} | Holding {}", nonce, self.config.wallet_public_key, msgs.join(" and "));
-                    Ok(Inventory {
-                        base_balance: balances[0],
-                        quote_balance: balances[1],
-                        nonce,
-                    })
+        let owner = self.config.wallet_public_key.parse::<Address>().map_err(|e| e.to_string())?;
+        let mut reads = vec![];
+        for address in addresses.iter() {
+            if is_native(address) {
+                reads.push(Read::Native { owner });
+                continue;
+            }
+            let token = address.parse::<Address>().map_err(|e| e.to_string())?;
+            reads.push(Read::Balance { token, owner });
+        }
+        reads.push(Read::Native { owner });
+        // Balances and native balance in one multicall, alongside the nonce, from the same endpoint, failing over as a whole
+        let read = RpcPool::of(&self.config)
+            .call(|url| {
+                let (reads, multicall) = (reads.clone(), self.config.multicall_address.clone());
+                async move {
+                    let provider = crate::utils::evm::create_provider(&url);
+                    let (mut results, nonce) = tokio::join!(crate::utils::multicall::read(&provider, &multicall, &reads), async { provider.get_transaction_count(owner).await });
+                    let nonce = nonce.map_err(|e| format!("Failed to get nonce: {}", e))?;
+                    let native = results.pop().flatten().ok_or_else(|| "Failed to get native balance".to_string())?;
+                    let balances = results.into_iter().map(Option::unwrap_or_default).collect::<Vec<u128>>();
+                    Ok::<_, String>((balances, nonce, native))
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to get nonce: {:?}", e);
-                    Err(e.to_string())
+            })
+            .await;
+        let (mut balances, nonce, native_balance) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                tracing::warn!("{}", e);
+                return Err(e);
+            }
+        };
+        // A native leg trades the native balance above the gas kept aside
+        for (x, address) in addresses.iter().enumerate() {
+            if is_native(address) {
+                balances[x] = balances[x].saturating_sub(self.config.min_native_balance_wei);
+            }
+        }
+        let mut msgs = vec![];
+        for (x, tk) in tokens.iter().enumerate() {
+            let balance = balances.get(x).cloned().unwrap_or_default();
+            let divided = balance as f64 / 10f64.powi(tk.decimals as i32);
+            msgs.push(format!("{:.5} of {}", divided, tk.symbol));
+        }
+        msgs.push(format!("{:.5} of native gas token", native_balance as f64 / 1e18));
+        tracing::debug!("💵  Inventory evaluation: Nonce {} | Wallet {} | Holding {}", nonce, self.config.wallet_public_key, msgs.join(" and "));
+        Ok(Inventory {
+            base_balance: balances[0],
+            quote_balance: balances[1],
+            nonce,
+            native_balance,
+        })
+    }
+
+    /// Native gas kept aside when wrapping: a wrap, an approval, a swap and an unwrap at the current max fee.
+    fn wrap_gas_reserve(&self, context: &MarketContext) -> u128 {
+        let gas = DEFAULT_WRAP_GAS * 2 + DEFAULT_APPROVE_GAS + DEFAULT_SWAP_GAS;
+        (gas as u128).saturating_mul(context.max_fee_per_gas)
+    }
+
+    /// Inventory as seen by the strategy: with auto_wrap_native, the wrappable native balance adds to the wrapped token side.
+    fn effective_inventory(&self, inventory: &Inventory, context: &MarketContext) -> Inventory {
+        let mut effective = inventory.clone();
+        if self.config.auto_wrap_native {
+            let wrappable = inventory.wrappable(self.config.min_native_balance_wei, self.wrap_gas_reserve(context));
+            match self.config.wrapped_native_side() {
+                Some(true) => effective.base_balance = effective.base_balance.saturating_add(wrappable),
+                Some(false) => effective.quote_balance = effective.quote_balance.saturating_add(wrappable),
+                None => {}
+            }
+        }
+        effective
+    }
+
+    /// Min executable spread widened by the reference price volatility, capped by vol_widening_cap_bps, then by the
+    /// realized shortfall of the trades in adaptive mode, capped by adaptive_threshold_max_bps.
+    pub fn execution_threshold_bps(&self) -> f64 {
+        self.volatility
+            .threshold_bps(self.config.min_executable_spread_bps, self.config.vol_multiplier, self.config.vol_widening_cap_bps)
+            + self.adaptive.widening_bps()
+    }
+
+    /// Puts the pool of a sent trade in cooldown and records it in the trade journal, returning the journal entries to
+    /// persist. A trade never sent (failed simulation or broadcast, testing mode) or reverted left the pool untouched.
+    pub fn record_traded(&mut self, trade: &Trade, order: &ExecutionOrder, block: u64) -> Option<Vec<JournalEntry>> {
+        let broadcast = trade.metadata.broadcast.as_ref().filter(|b| b.succeeded())?;
+        let receipt = broadcast.receipt.as_ref();
+        if receipt.is_some_and(|r| !r.status) {
+            return None;
+        }
+        // Sent without a receipt yet: may still be propagating
+        let included = receipt.map(|r| r.block_number).unwrap_or(block);
+        let component = order.adjustment.psc.component.id.to_string();
+        self.cooldown.record(&component, included);
+        let entry = JournalEntry {
+            component,
+            direction: order.adjustment.direction.clone(),
+            block: included,
+            amount: order.calculation.selling_amount,
+        };
+        self.journal.record(entry, block)
+    }
+
+    /// Feeds the realized shortfall of an included trade to the adaptive threshold, logging and publishing each adjustment.
+    pub fn adapt_threshold(&mut self, calculation: &SwapCalculation, realized: Option<&RealizedData>) {
+        if !self.config.adaptive_threshold {
+            return;
+        }
+        let Some(realized) = realized else {
+            return;
+        };
+        let shortfall_bps = AdaptiveThreshold::shortfall_bps(calculation, realized);
+        if let Some(adjustment) = self.adaptive.record(shortfall_bps) {
+            tracing::info!(
+                metric = "adaptive_threshold",
+                shortfall_bps = adjustment.shortfall_bps,
+                widening_bps = adjustment.widening_bps,
+                "🎚️  Adaptive threshold: widening {:.2} -> {:.2} bps after {} trades (rolling shortfall {:.2} bps, last {:.2} bps)",
+                adjustment.previous_bps,
+                adjustment.widening_bps,
+                adjustment.trades,
+                adjustment.shortfall_bps,
+                shortfall_bps
+            );
+            let message = format!(
+                "Execution threshold widening {:.2} -> {:.2} bps after {} trades (rolling shortfall {:.2} bps)",
+                adjustment.previous_bps, adjustment.widening_bps, adjustment.trades, adjustment.shortfall_bps
+            );
+            self.alert(AlertKind::ThresholdAdjusted, message, None);
+        }
+    }
+
+    /// True when another pair of the process trades from the same wallet (shared nonce lock).
+    fn shares_wallet(&self) -> bool {
+        Arc::strong_count(&self.nonce_lock) > 1
+    }
+
+    /// Returns the cached inventory, refetched from chain when missing or older than inventory_refresh_interval_ms.
+    ///
+    /// Pairs sharing a wallet do not see each other's trades in their cache, they always read from chain.
+    async fn inventory(&mut self, env: EnvConfig) -> Result<Inventory, String> {
+        if !self.shares_wallet() {
+            if let Some(inventory) = self.inventory.fresh(std::time::Instant::now()) {
+                return Ok(inventory.clone());
+            }
+        }
+        let inventory = self.market_data.inventory(self, env).await?;
+        self.inventory.store(inventory.clone(), std::time::Instant::now());
+        self.health.inventory(InventorySummary {
+            base_symbol: self.base.symbol.clone(),
+            base_balance: inventory.base_balance as f64 / 10f64.powi(self.base.decimals as i32),
+            quote_symbol: self.quote.symbol.clone(),
+            quote_balance: inventory.quote_balance as f64 / 10f64.powi(self.quote.decimals as i32),
+            native_balance: inventory.native_balance as f64 / 1e18,
+        });
+        Ok(inventory)
+    }
+
+    /// Drops the cached inventory and reads it again from chain (operator command, tests).
+    pub async fn force_refresh_inventory(&mut self, env: EnvConfig) -> Result<Inventory, String> {
+        self.inventory.invalidate();
+        let inventory = self.inventory(env).await?;
+        tracing::info!("💵  Inventory refreshed from chain: nonce {}", inventory.nonce);
+        Ok(inventory)
+    }
+
+    /// Compares balances just read from chain with the cached ones, then caches them.
+    ///
+    /// A base or quote balance moved past external_movement_threshold_bps outside of our executions is logged
+    /// and alerted, and order creation is skipped on the next evaluation.
+    pub fn reconcile(&mut self, onchain: Inventory, now: std::time::Instant) -> Option<ExternalMovement> {
+        let movement = self.inventory.cached().and_then(|cached| self.watcher.compare(cached, &onchain));
+        self.inventory.store(onchain, now);
+        self.watcher.checked(now);
+        if let Some(movement) = &movement {
+            let message = format!("Wallet balances moved outside of our executions: {}", movement);
+            tracing::warn!("👀 {}, pausing order creation for one block", message);
+            self.alert(AlertKind::ExternalMovement, message, None);
+            self.watcher.pause();
+        }
+        movement
+    }
+
+    /// Reads the balances from chain and reconciles them with the cache, dropping the cache if the read fails.
+    ///
+    /// Skipped for pairs sharing a wallet, the trades of the other pairs would read as external movements.
+    async fn watch_balances(&mut self, env: EnvConfig) {
+        let now = std::time::Instant::now();
+        if self.shares_wallet() {
+            self.watcher.checked(now);
+            return;
+        }
+        match self.market_data.inventory(self, env).await {
+            Ok(onchain) => {
+                self.reconcile(onchain, now);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to compare the cached balances with the chain: {}", e);
+                self.inventory.invalidate();
+            }
+        }
+        if !self.config.infinite_approval && !self.config.permit2_approval && self.frozen.is_none() {
+            self.refresh_allowances().await;
+        }
+    }
+
+    /// Reads the router allowances of the base and quote tokens (native excluded), dropped from the cache on failure.
+    async fn refresh_allowances(&mut self) {
+        let tokens = [&self.base, &self.quote]
+            .iter()
+            .map(|t| t.address.to_string())
+            .filter(|address| !is_native(address))
+            .collect::<Vec<String>>();
+        match crate::utils::evm::allowances(&self.config, self.config.tycho_router_address.clone(), tokens.clone()).await {
+            Ok(allowances) => {
+                for (token, allowance) in tokens.iter().zip(allowances) {
+                    self.allowances.store(token, allowance);
                 }
-            },
+            }
             Err(e) => {
-                tracing::warn!("Failed to get inventory: {:?}", e);
-                Err(e.to_string())
+                tracing::warn!("Failed to read the router allowances, approving every swap: {}", e);
+                self.allowances.invalidate();
             }
         }
     }
 
+    /// Fetches the wallet nonce, counting pending transactions (possibly sent by another pair on the same wallet).
+    async fn fetch_nonce(&self) -> Result<u64, String> {
+        let wallet = self.config.wallet_public_key.parse::<Address>().map_err(|e| e.to_string())?;
+        RpcPool::of(&self.config)
+            .call(|url| async move { crate::utils::evm::create_provider(&url).get_transaction_count(wallet).pending().await })
+            .await
+    }
+
+    /// Block head of the `ws_rpc_url` subscription, if the socket is up and the head younger than `ws_max_head_age_s`.
+    pub fn fresh_head(&self) -> Option<BlockHead> {
+        HeadCache::of(&self.config).and_then(|cache| cache.fresh(Duration::from_secs(self.config.ws_max_head_age_s)))
+    }
+
     /// Fetches market context including token/ETH prices, gas fees, and block number.
-    async fn fetch_market_context(&self, components: Vec<ProtocolComponent>, protosims: &HashMap<std::string::String, Box<dyn ProtocolSim>>, tokens: Vec<Token>) -> Option<MarketContext> {
-        let time = std::time::SystemTime::now();
-        match crate::utils::evm::eip1559_fees(self.config.rpc_url.clone()).await {
-            Ok(eip1559_fees) => {
-                let native_gas_price = crate::utils::evm::gas_price(self.config.rpc_url.clone()).await;
-                let eth_to_usd = self.fetch_eth_usd().await;
-                let provider = match self.config.rpc_url.clone().parse() {
-                    Ok(url) => ProviderBuilder::new().connect_http(url),
-                    Err(e) => {
-                        tracing::error!("Failed to parse RPC URL: {}", e);
-                        return None;
-                    }
-                };
-                // Alloy 1.0: get_block_by_number() no longer takes hydrated parameter
-                let block: alloy::rpc::types::Block = match provider.get_block_by_number(alloy::eips::BlockNumberOrTag::Latest).await {
-                    Ok(Some(b)) => b,
-                    Ok(None) => {
-                        tracing::error!("Failed to fetch latest block: block not found");
-                        return None;
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to fetch latest block: {}", e);
-                        return None;
-                    }
-                };
-                let base_to_eth_vp = routing::find_path(components.clone(), self.base.address.to_string().to_lowercase(), self.config.gas_token_symbol.to_lowercase());
-                let quote_to_eth_vp = routing::find_path(components.clone(), self.quote.address.to_string().to_lowercase(), self.config.gas_token_symbol.to_lowercase());
-                match (base_to_eth_vp, quote_to_eth_vp, eth_to_usd) {
-                    (Ok(base_to_eth_vp), Ok(quote_to_eth_vp), Ok(eth_to_usd)) => {
-                        let mut to_eth_ptss = vec![];
-                        for cp in components.iter() {
-                            let id = cp.id.to_string().to_lowercase();
-                            if base_to_eth_vp.comp_path.contains(&id) || quote_to_eth_vp.comp_path.contains(&id) {
-                                match protosims.get(&id) {
-                                    Some(protosim) => {
-                                        to_eth_ptss.push(ProtoSimComp {
-                                            component: cp.clone(),
-                                            protosim: protosim.clone(),
-                                        });
-                                    }
-                                    None => {
-                                        tracing::error!("contains: couldn't find protosim for component {}", cp.id);
-                                    }
+    #[tracing::instrument(name = "context", level = "debug", skip_all)]
+    pub async fn fetch_market_context(&self, graph: &TokenGraph, protosims: &HashMap<std::string::String, Box<dyn ProtocolSim>>, tokens: Vec<Token>) -> Option<MarketContext> {
+        if let Some(frozen) = &self.frozen {
+            return Some(frozen.context.clone());
+        }
+        let rpc = RpcPool::of(&self.config);
+        // Gas fees and ETH/USD price of the shared oracle while fresh, read again otherwise
+        let oracle = GasOracle::of(&self.config);
+        let now = std::time::Instant::now();
+        let cached = match oracle.fresh(now) {
+            Some(cached) => Ok(cached),
+            None => self.fetch_gas_quote().await.map(|quote| {
+                oracle.store(quote.clone(), now, crate::utils::evm::jitter());
+                (quote, Duration::ZERO)
+            }),
+        };
+        match cached {
+            Ok((quote, age)) => {
+                let eip1559_fees = quote.fees;
+                // Block head of the websocket subscription while fresh, polled over HTTP otherwise
+                let head = self.fresh_head();
+                let native_gas_price = head.and_then(|head| head.gas_price(eip1559_fees.max_priority_fee_per_gas)).unwrap_or(quote.gas_price);
+                let eth_to_usd = quote.eth_to_usd;
+                let number = match head {
+                    Some(head) => head.number,
+                    None => {
+                        // Alloy 1.0: get_block_by_number() no longer takes hydrated parameter
+                        let latest = rpc
+                            .call(|url| async move {
+                                match crate::utils::evm::create_provider(&url).get_block_by_number(alloy::eips::BlockNumberOrTag::Latest).await {
+                                    Ok(Some(b)) => Ok(b),
+                                    Ok(None) => Err("block not found".to_string()),
+                                    Err(e) => Err(e.to_string()),
                                 }
+                            })
+                            .await;
+                        match latest {
+                            Ok(b) => b.header.number,
+                            Err(e) => {
+                                tracing::error!("Failed to fetch latest block: {}", e);
+                                return None;
                             }
                         }
-                        let base_to_eth = routing::quote(to_eth_ptss.clone(), tokens.clone(), base_to_eth_vp.token_path.clone());
-                        let quote_to_eth = routing::quote(to_eth_ptss.clone(), tokens.clone(), quote_to_eth_vp.token_path.clone());
-                        let elasped = time.elapsed().unwrap_or_default().as_millis();
-                        tracing::debug!("Market context fetched in {} ms", elasped);
+                    }
+                };
+                let base_to_eth_vp = routing::find_path(
+                    graph,
+                    self.base.address.to_string().to_lowercase(),
+                    self.config.gas_token_symbol.to_lowercase(),
+                    &self.config.routing_protocol_whitelist,
+                );
+                let quote_to_eth_vp = routing::find_path(
+                    graph,
+                    self.quote.address.to_string().to_lowercase(),
+                    self.config.gas_token_symbol.to_lowercase(),
+                    &self.config.routing_protocol_whitelist,
+                );
+                match (base_to_eth_vp, quote_to_eth_vp) {
+                    (Ok(base_to_eth_vp), Ok(quote_to_eth_vp)) => {
+                        let whitelist = &self.config.routing_protocol_whitelist;
+                        let base_to_eth = routing::quote(graph, protosims, &tokens, &base_to_eth_vp, whitelist);
+                        let quote_to_eth = routing::quote(graph, protosims, &tokens, &quote_to_eth_vp, whitelist);
                         match (base_to_eth, quote_to_eth) {
                             (Some(base_to_eth), Some(quote_to_eth)) => Some(MarketContext {
                                 base_to_eth,
@@ -253,7 +702,8 @@ impl MarketMaker {
                                 max_fee_per_gas: eip1559_fees.max_fee_per_gas,
                                 max_priority_fee_per_gas: eip1559_fees.max_priority_fee_per_gas,
                                 native_gas_price,
-                                block: block.header.number,
+                                block: number,
+                                age_ms: age.as_millis() as u64,
                             }),
                             _ => {
                                 tracing::warn!("Failed to get base/ETH quote");
@@ -261,29 +711,75 @@ impl MarketMaker {
                             }
                         }
                     }
-                    (Err(e), _, _) => {
+                    (Err(e), _) => {
                         tracing::error!("Failed to find path for base to ETH: {:?}", e);
                         None
                     }
-                    (_, Err(e), _) => {
+                    (_, Err(e)) => {
                         tracing::error!("Failed to find path for quote to ETH: {:?}", e);
                         None
                     }
-                    (_, _, Err(_)) => {
-                        tracing::error!("Failed to fetch ETH/USD price.");
-                        None
-                    }
                 }
             }
             Err(e) => {
-                tracing::error!("Failed to fetch EIP-1559 fees: {:?}", e);
+                tracing::error!("{}", e);
                 None
             }
         }
     }
 
+    /// Reads the EIP-1559 fees, gas price and ETH/USD price from the network, for the gas oracle.
+    async fn fetch_gas_quote(&self) -> Result<GasQuote, String> {
+        let rpc = RpcPool::of(&self.config);
+        let strategy = self.config.fee_strategy();
+        let fees = rpc
+            .call(|url| crate::utils::evm::eip1559_fees(url, strategy))
+            .await
+            .map_err(|e| format!("Failed to fetch EIP-1559 fees: {:?}", e))?;
+        let gas_price = rpc
+            .call(|url| async move {
+                let provider = crate::utils::evm::create_provider(&url);
+                provider.get_gas_price().await
+            })
+            .await
+            .unwrap_or_default();
+        let eth_to_usd = self.fetch_eth_usd().await.map_err(|e| format!("Failed to fetch ETH/USD price: {}", e))?;
+        Ok(GasQuote { fees, gas_price, eth_to_usd })
+    }
+
+    /// Computes the depth ladder of every target pool, using the market context for USD sizing.
+    async fn depth(&self, targets: &[ProtoSimComp], graph: &TokenGraph, protosims: &HashMap<String, Box<dyn ProtocolSim>>, tokens: Vec<Token>) -> Option<Vec<PoolDepth>> {
+        let context = self.market_data.market_context(self, graph, protosims, tokens).await?;
+        let mut output = vec![];
+        for psc in targets.iter() {
+            match impact::ladder(psc, &self.base, &self.quote, &context, &self.config.depth_ladder_usd) {
+                Ok(depth) => output.push(depth),
+                Err(e) => tracing::warn!("Failed to compute depth for {}: {}", cpname(psc.component.clone()), e),
+            }
+        }
+        Some(output)
+    }
+
+    /// Records the decision taken on a target pool at a block, when the audit is enabled. No gate means an order was created.
+    fn decide(&self, block: u64, psc: &ProtoSimComp, spot: f64, reference: f64, gate: Option<Gate>, values: &[(&str, f64)]) {
+        if !self.audit.is_enabled() {
+            return;
+        }
+        self.audit.record(&Decision {
+            timestamp_ms: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            identifier: self.identifier.clone(),
+            block,
+            pool: psc.component.id.to_string().to_lowercase(),
+            spot,
+            reference,
+            spread_bps: if reference > 0. { (spot - reference) / reference * BASIS_POINT_DENO } else { 0. },
+            gate,
+            values: values.iter().map(|(key, value)| (key.to_string(), *value)).collect(),
+        });
+    }
+
     /// Creates pre-trade data from an execution order.
-    fn pre_trade_data(&self, order: &ExecutionOrder) -> PreTradeData {
+    pub fn pre_trade_data(&self, order: &ExecutionOrder) -> PreTradeData {
         PreTradeData {
             pool: order.adjustment.psc.component.id.to_string(),
             base_token: order.adjustment.selling.symbol.clone(),
@@ -291,6 +787,8 @@ impl MarketMaker {
             trade_direction: order.adjustment.direction.clone(),
             amount_in_normalized: order.calculation.selling_amount,
             amount_out_expected: order.calculation.buying_amount,
+            token_out: order.adjustment.buying.address.to_string(),
+            token_out_decimals: order.adjustment.buying.decimals,
             spot_price: order.adjustment.spot,
             reference_price: order.adjustment.reference,
             slippage_tolerance_bps: self.config.max_slippage_pct * BASIS_POINT_DENO,
@@ -299,8 +797,153 @@ impl MarketMaker {
         }
     }
 
+    /// Returns true if a target pool's spot moved by more than its watch spread since its last evaluation, or was never evaluated.
+    ///
+    /// The watch spread of a pool is the one `evaluate` applies to it: the protocol override or min_watch_spread_bps, plus
+    /// its fee with add_pool_fee_to_spread.
+    pub fn pools_moved(&self, targets: &[ProtoSimComp], cpds: &[ComponentPriceData]) -> bool {
+        cpds.iter().any(|cpd| match self.evaluated_spots.get(&cpd.address) {
+            Some(last) if *last > 0. => {
+                let fee_bps = targets
+                    .iter()
+                    .find(|psc| psc.component.id.to_string().to_lowercase() == cpd.address)
+                    .map(|psc| amm_fee_to_bps(psc.component.clone()))
+                    .unwrap_or_default();
+                (cpd.price - last).abs() / last * BASIS_POINT_DENO > self.config.watch_spread_bps(&cpd.r#type, fee_bps)
+            }
+            _ => true,
+        })
+    }
+
+    /// Drops the orders against a component removed from the stream within the last `tombstone_blocks` blocks, its pool
+    /// being no longer tracked by Tycho and its state possibly stale. Checked right before the preparation of the orders.
+    pub fn drop_removed(&self, orders: Vec<ExecutionOrder>, block: u64) -> Vec<ExecutionOrder> {
+        orders
+            .into_iter()
+            .filter(|order| {
+                let psc = &order.adjustment.psc;
+                let Some(removed) = self.tombstones.removed_at(&psc.component.id.to_string(), block) else {
+                    return true;
+                };
+                tracing::warn!(
+                    metric = "order_dropped_removed_component",
+                    "🪦 Dropping trade {} on {}: component removed from the stream at block {}",
+                    order.trade_id,
+                    psc.component.id,
+                    removed
+                );
+                self.decide(block, psc, order.adjustment.spot, order.adjustment.reference, Some(Gate::Removed), &[("removed_block", removed as f64)]);
+                false
+            })
+            .collect()
+    }
+
+    /// Protosim of the component quoting through the simulation cache of the block, None when the cache is disabled.
+    fn cached_sim(&self, component: &str, protosim: &dyn ProtocolSim) -> Option<CachedSim> {
+        self.sim_cache.enabled().then(|| CachedSim::new(component, protosim, self.sim_cache.clone()))
+    }
+
+    /// Profit net of gas of an order, in bps of its reference, quoted again on `protosim` with the amount sold by its
+    /// calculation. Same valuation as `readjust`, the gas cost kept as estimated there.
+    pub fn requote_profit_bps(order: &ExecutionOrder, protosim: &dyn ProtocolSim) -> Result<f64, String> {
+        let (selling, buying, calculation) = (&order.adjustment.selling, &order.adjustment.buying, &order.calculation);
+        let result = protosim.get_amount_out(calculation.amount_in_raw.clone(), selling, buying).map_err(|e| e.to_string())?;
+        let amount_out_normalized = result.amount.to_f64().unwrap_or(0.0) / 10f64.powi(buying.decimals as i32);
+        let reference = order.adjustment.reference;
+        let (_, _, delta) = net_price(
+            calculation.selling_amount,
+            amount_out_normalized,
+            calculation.gas_cost_in_output_token,
+            calculation.base_to_quote,
+            reference,
+        );
+        Ok(delta / reference * BASIS_POINT_DENO)
+    }
+
+    /// Reason to abort an order at execution time, None if still fresh: the chain head more than `stale_block_tolerance`
+    /// blocks ahead of the latest `state_block`, its component gone from the latest `protosims`, or its profit requoted
+    /// on them below `min_executable_spread_bps`. The execution threshold already let it through, only the static floor is
+    /// checked again.
+    pub fn staleness(&self, order: &ExecutionOrder, protosims: &HashMap<String, Box<dyn ProtocolSim>>, state_block: u64, head_block: u64) -> Option<(String, f64)> {
+        if head_block > state_block.saturating_add(self.config.stale_block_tolerance) {
+            let behind = head_block - state_block;
+            return Some((format!("state of block {} is {} blocks behind the head {}", state_block, behind, head_block), behind as f64));
+        }
+        let id = order.adjustment.psc.component.id.to_string().to_lowercase();
+        let Some(protosim) = protosims.get(&id) else {
+            return Some(("component no longer tracked".to_string(), 0.));
+        };
+        // Quoted on the latest state itself, never from the simulation cache of the block
+        match Self::requote_profit_bps(order, protosim.as_ref()) {
+            Ok(profit_bps) if profit_bps >= self.config.min_executable_spread_bps => None,
+            Ok(profit_bps) => Some((format!("profit {:.2} bps below {:.2} bps", profit_bps, self.config.min_executable_spread_bps), profit_bps)),
+            Err(e) => Some((format!("requote failed: {}", e), 0.)),
+        }
+    }
+
+    /// Final freshness check of the prepared trades, right before their execution. A stale trade is not executed: its
+    /// status set to `StalePrice`, logged with the `trade_killed_stale` metric and published as is, as is a trade without
+    /// a matching order. Returns the trades to execute with their orders, in order.
+    pub async fn drop_stale(
+        &self, trades: Vec<Trade>, orders: Vec<ExecutionOrder>, protosims: &HashMap<String, Box<dyn ProtocolSim>>, state_block: u64, head_block: u64,
+    ) -> (Vec<Trade>, Vec<ExecutionOrder>) {
+        let mut fresh = (vec![], vec![]);
+        let mut stale = vec![];
+        for mut trade in trades {
+            let Some(order) = orders.iter().find(|order| order.trade_id == trade.metadata.trade_id) else {
+                tracing::warn!(metric = "trade_killed_stale", "🥀 Aborting trade {}: no matching order to revalidate it", trade.metadata.trade_id);
+                trade.metadata.status = TradeStatus::StalePrice;
+                stale.push(trade);
+                continue;
+            };
+            let psc = &order.adjustment.psc;
+            match self.staleness(order, protosims, state_block, head_block) {
+                None => {
+                    fresh.0.push(trade);
+                    fresh.1.push(order.clone());
+                }
+                Some((reason, value)) => {
+                    tracing::warn!(metric = "trade_killed_stale", "🥀 Aborting trade {} on {}: {}", order.trade_id, psc.component.id, reason);
+                    self.decide(
+                        head_block,
+                        psc,
+                        order.adjustment.spot,
+                        order.adjustment.reference,
+                        Some(Gate::Stale),
+                        &[("value", value), ("state_block", state_block as f64)],
+                    );
+                    trade.metadata.status = TradeStatus::StalePrice;
+                    stale.push(trade);
+                }
+            }
+        }
+        if !stale.is_empty() {
+            self.execution.post_hook(&self.config, stale, self.identifier.clone()).await;
+        }
+        fresh
+    }
+
+    /// Readjustments of a block update aborted past its latency budget, before their orders were built: recorded in the
+    /// audit with the `Timeout` gate and logged with the `readjustment_killed_timeout` metric.
+    fn time_out_readjustments(&self, block: u64, readjustments: &[CompReadjustment], deadline: &Deadline, error: &ExecError) {
+        for adjustment in readjustments {
+            tracing::warn!(metric = "readjustment_killed_timeout", "⏱️  Aborting readjustment of {}: {}", adjustment.psc.component.id, error);
+            self.decide(
+                block,
+                &adjustment.psc,
+                adjustment.spot,
+                adjustment.reference,
+                Some(Gate::Timeout),
+                &[("elapsed_ms", deadline.elapsed_ms() as f64)],
+            );
+        }
+    }
+
     /// Evaluates if pools are out of range and returns readjustment orders.
-    fn evaluate(&self, targets: &[ProtoSimComp], sps: Vec<f64>, reference: f64) -> Vec<CompReadjustment> {
+    ///
+    /// Each direction uses its own threshold from the inventory skew.
+    #[tracing::instrument(name = "evaluate", level = "debug", skip_all, fields(block, targets = targets.len(), reference))]
+    pub fn evaluate(&self, targets: &[ProtoSimComp], sps: Vec<f64>, reference: f64, skew: &InventorySkew, block: u64) -> Vec<CompReadjustment> {
         let mut orders = vec![];
         if sps.is_empty() {
             tracing::warn!("No spot prices available to evaluate (targets: {})", targets.len());
@@ -311,9 +954,42 @@ impl MarketMaker {
             return vec![];
         }
         for (i, psc) in targets.iter().enumerate() {
+            if self.cooldown.is_cooling(&psc.component.id.to_string(), block) {
+                tracing::debug!("===> Skipping pool {}: cooling down after our last trade", cpname(psc.component.clone()));
+                self.decide(block, psc, sps[i], reference, Some(Gate::Cooldown), &[("cooldown_blocks", self.config.pool_cooldown_blocks as f64)]);
+                continue;
+            }
+            if self.journal.is_recent(&psc.component.id.to_string(), block) {
+                tracing::debug!(
+                    "===> Skipping pool {}: traded by the previous process less than {} blocks ago",
+                    cpname(psc.component.clone()),
+                    self.config.pool_cooldown_blocks
+                );
+                self.decide(
+                    block,
+                    psc,
+                    sps[i],
+                    reference,
+                    Some(Gate::Cooldown),
+                    &[("cooldown_blocks", self.config.pool_cooldown_blocks as f64), ("journal", 1.)],
+                );
+                continue;
+            }
             let spot = sps[i];
             let spread = spot - reference;
             let spread_bps = spread / reference * BASIS_POINT_DENO;
+            if !self.quarantine.is_plausible(spread_bps) {
+                tracing::debug!("===> Skipping pool {}: quarantined, spread of {:.0} bps", cpname(psc.component.clone()), spread_bps);
+                self.decide(
+                    block,
+                    psc,
+                    spot,
+                    reference,
+                    Some(Gate::SanityBand),
+                    &[("max_plausible_spread_bps", self.config.max_plausible_spread_bps)],
+                );
+                continue;
+            }
             let symbol = if spread_bps < 0_f64 { "buy 📈" } else { "sell 📉" };
             tracing::debug!(
                 "===> Evaluating pool {}: Spot: {:.5} | Reference: {:.5} | Spread: {:.5} | Spread BPS: {:<3.2} | Should {}",
@@ -324,7 +1000,9 @@ impl MarketMaker {
                 spread_bps,
                 symbol
             );
-            if spread_bps.abs() > self.config.min_watch_spread_bps {
+            let watch_spread_bps = self.config.watch_spread_bps(&psc.component.protocol_system, amm_fee_to_bps(psc.component.clone()));
+            let threshold = skew.threshold_bps(spread_bps, watch_spread_bps - self.config.min_watch_spread_bps);
+            if spread_bps.abs() > threshold {
                 match spread_bps > 0. {
                     true => {
                         orders.push(CompReadjustment {
@@ -351,6 +1029,8 @@ impl MarketMaker {
                         });
                     }
                 };
+            } else {
+                self.decide(block, psc, spot, reference, Some(Gate::WatchSpread), &[("threshold_bps", threshold)]);
             }
         }
         orders
@@ -359,97 +1039,146 @@ impl MarketMaker {
     /// Performs inventory rebalancing based on spread opportunities.
     ///
     /// Calculates optimal trade sizes and validates profitability after gas costs.
-    async fn readjust(&self, context: MarketContext, inventory: Inventory, mut adjustments: Vec<CompReadjustment>, env: EnvConfig) -> Vec<ExecutionOrder> {
+    /// Pool balances, gas and token prices come from the arguments, so a backtest runs it without RPC.
+    #[tracing::instrument(name = "readjust", level = "debug", skip_all, fields(block = context.block, adjustments = adjustments.len()))]
+    pub async fn readjust(&self, context: MarketContext, inventory: Inventory, mut adjustments: Vec<CompReadjustment>, pool_balances: &dyn PoolBalances) -> Vec<ExecutionOrder> {
         adjustments.sort_by(|a, b| a.spread_bps.partial_cmp(&b.spread_bps).unwrap_or(std::cmp::Ordering::Equal));
+        let inventory = self.effective_inventory(&inventory, &context);
+        let execution_threshold_bps = self.execution_threshold_bps();
+        // Projected after each accepted order, so orders of the same block add up against max_token_exposure_pct
+        let mut exposure = Exposure::new(&inventory, &self.base, &self.quote, &context);
+        let max_share = self.config.max_token_exposure_pct / PERCENT_MULTIPLIER;
         let mut orders = vec![];
-        for adjustment in &adjustments {
-            let balances_opt = get_component_balances(self.config.clone(), adjustment.psc.component.clone(), env.tycho_api_key.clone()).await;
-            let balances = match balances_opt {
-                Some(b) => b,
-                None => {
-                    tracing::warn!("Failed to get component balances");
+        // Read from the streamed state when the protocol allows it, the others once for all the adjustments, a single request per protocol system
+        let local = adjustments.iter().map(|a| component_balances_local(&a.psc)).collect::<Vec<_>>();
+        let components = adjustments.iter().zip(&local).filter(|(_, l)| l.is_none()).map(|(a, _)| a.psc.component.clone()).collect::<Vec<_>>();
+        let fetched = if components.is_empty() {
+            HashMap::new()
+        } else {
+            pool_balances.batch(&components, context.block).await
+        };
+        // Sized concurrently on their own pool, then bounded in spread order by the limits shared by the orders of the block
+        let mut sizings = futures::stream::iter(adjustments.iter().zip(local).enumerate())
+            .map(|(i, (adjustment, local))| {
+                let balances = local.or_else(|| fetched.get(&adjustment.psc.component.id.to_string().to_lowercase()).cloned());
+                let (context, inventory) = (&context, &inventory);
+                async move { (i, self.size_readjustment(adjustment, balances, context, inventory).await) }
+            })
+            .buffer_unordered(self.config.readjust_concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        sizings.sort_by_key(|(i, _)| *i);
+        for (adjustment, (_, sizing)) in adjustments.iter().zip(sizings) {
+            let SizedReadjustment {
+                base_to_quote,
+                selling_amount,
+                exact_out_target,
+                termination,
+                bracket_width,
+                inventory_balance_normalized,
+                max_alloc,
+                pool_buying_balance_normalized,
+            } = match sizing {
+                Sizing::Sized(sized) => *sized,
+                Sizing::Skipped(gate, values) => {
+                    self.decide(context.block, &adjustment.psc, adjustment.spot, adjustment.reference, Some(gate), &values);
                     continue;
                 }
             };
-            let buying = &adjustment.buying;
-            let buying_pow = 10f64.powi(buying.decimals as i32);
-            let buying_addr = buying.address.to_string().to_lowercase();
-            let pool_buying_balance = match balances.get(&buying_addr) {
-                Some(bal) => bal,
-                None => {
-                    tracing::warn!("Failed to get buying balance for {}", buying_addr);
-                    continue;
-                }
+            let (selling, buying) = (&adjustment.selling, &adjustment.buying);
+            let (selling_pow, buying_pow) = (10f64.powi(selling.decimals as i32), 10f64.powi(buying.decimals as i32));
+            let buying_amount = if base_to_quote { selling_amount * adjustment.spot } else { selling_amount / adjustment.spot };
+
+            // Share of the pool balance of the bought token taken by the swap
+            let max_pool_buying = pool_buying_balance_normalized * self.config.max_pool_share_bps / BASIS_POINT_DENO;
+            let (selling_amount, buying_amount, exact_out_target) = if buying_amount > max_pool_buying {
+                let clamped = selling_amount * max_pool_buying / buying_amount;
+                tracing::info!(
+                    "   => Pool share: buying {:.5} {} out of {:.5} exceeds {} bps, selling clamped to {:.5} {}",
+                    buying_amount,
+                    buying.symbol,
+                    pool_buying_balance_normalized,
+                    self.config.max_pool_share_bps,
+                    clamped,
+                    selling.symbol
+                );
+                // The exact out target no longer fits, the clamped amount is sold exact in
+                (clamped, max_pool_buying, None)
+            } else {
+                (selling_amount, buying_amount, exact_out_target)
             };
-            let pool_buying_balance_normalized = (*pool_buying_balance as f64) / buying_pow;
-            if pool_buying_balance_normalized < f64::EPSILON {
-                tracing::warn!("Cannot readjust, skipping due to pool_buying_balance_normalized < 0 !");
-                continue;
-            }
-            let selling = &adjustment.selling;
-            let selling_pow = 10f64.powi(selling.decimals as i32);
-            let selling_addr = selling.address.to_string().to_lowercase();
-            let pool_selling_balance = match balances.get(&selling_addr) {
-                Some(bal) => bal,
-                None => {
-                    tracing::warn!("Failed to get selling balance for {}", selling_addr);
+
+            // Cumulative exposure of the bought token
+            let (selling_amount, buying_amount, exact_out_target) = match exposure.limit(base_to_quote, selling_amount, buying_amount, max_share) {
+                ExposureLimit::Within => (selling_amount, buying_amount, exact_out_target),
+                ExposureLimit::Clamped(clamped) => {
+                    tracing::info!(
+                        "   => Exposure: selling {:.5} {} would bring {} to {:.2}% (max {:.2}%), clamped to {:.5} {}",
+                        selling_amount,
+                        selling.symbol,
+                        buying.symbol,
+                        exposure.after(base_to_quote, selling_amount, buying_amount).share(!base_to_quote) * PERCENT_MULTIPLIER,
+                        self.config.max_token_exposure_pct,
+                        clamped,
+                        selling.symbol
+                    );
+                    // The exact out target no longer fits, the clamped amount is sold exact in
+                    (clamped, buying_amount * clamped / selling_amount, None)
+                }
+                ExposureLimit::Rejected => {
+                    let current = exposure.share(!base_to_quote) * PERCENT_MULTIPLIER;
+                    let projected = exposure.after(base_to_quote, selling_amount, buying_amount).share(!base_to_quote) * PERCENT_MULTIPLIER;
+                    tracing::warn!(
+                        "   => Exposure: rejecting {} to {} order, {} would reach {:.2}% (currently {:.2}%, max {:.2}%)",
+                        selling.symbol,
+                        buying.symbol,
+                        buying.symbol,
+                        projected,
+                        current,
+                        self.config.max_token_exposure_pct
+                    );
+                    // Above the limit before any order of this block: moved there by something else than our trades
+                    if orders.is_empty() && current > self.config.max_token_exposure_pct {
+                        self.alert(
+                            AlertKind::ExposureLimit,
+                            format!(
+                                "{} exposure of {:.2}% already above max_token_exposure_pct ({:.2}%)",
+                                buying.symbol, current, self.config.max_token_exposure_pct
+                            ),
+                            None,
+                        );
+                    }
+                    self.decide(
+                        context.block,
+                        &adjustment.psc,
+                        adjustment.spot,
+                        adjustment.reference,
+                        Some(Gate::ExposureLimit),
+                        &[("current_pct", current), ("projected_pct", projected), ("max_pct", self.config.max_token_exposure_pct)],
+                    );
                     continue;
                 }
             };
-            let pool_selling_balance_normalized = (*pool_selling_balance as f64) / selling_pow;
-            if pool_selling_balance_normalized < f64::EPSILON {
-                tracing::warn!("Cannot readjust, skipping due to pool_selling_balance_normalized < 0 !");
-                continue;
-            }
-
-            if context.eth_to_usd <= 0. {
-                tracing::warn!("Cannot readjust, skipping due to eth_to_usd <= 0 !");
-                continue;
-            }
-
-            // Use TradeDirection from adjustment to determine swap direction
-            let base_to_quote = adjustment.direction == TradeDirection::Buy;
-
-            // Optimal amount computation using binary search
-            let inventory_balance = if base_to_quote { inventory.base_balance } else { inventory.quote_balance };
-
-            // Skip if inventory balance is 0
-            if inventory_balance == 0 {
-                // tracing::warn!(
-                //     "Skipping trade: No {} balance available (0.00). Cannot execute {} trade.",
-                //     selling.symbol,
-                //     if base_to_quote { "sell" } else { "buy" }
-                // );
-                continue;
-            }
-
-            let inventory_balance_normalized = (inventory_balance as f64) / selling_pow;
-            let max_alloc = inventory_balance_normalized * self.config.max_inventory_ratio;
-
-            // Run optimization to find optimal swap amount
-
-            // tracing::info!("Pool {}: find_optimal_swap_amount ...", cpname(adjustment.psc.component.clone()),);
-            let optimization_result = crate::opti::math::find_optimal_swap_amount(&*adjustment.psc.protosim, selling, buying, adjustment.reference, base_to_quote, max_alloc);
-
-            let selling_amount = match optimization_result {
-                Ok(opt) => {
-                    // tracing::info!(
-                    //     "   => Optimization complete: Optimal qty: {:.5} {} | Exec price: {:.5} | Impact: {:.2} bps | Simulations: {}",
-                    //     opt.optimal_qty,
-                    //     selling.symbol,
-                    //     opt.execution_price,
-                    //     opt.price_impact_bps,
-                    //     opt.simulation_count,
-                    // );
-                    opt.optimal_qty
-                }
-                Err(e) => {
-                    tracing::error!("   => Optimization failed: {}. Skipping trade.", e);
-                    continue; // Skip this adjustment if optimization fails
+            // Notional of the swap, the amounts scaled down to max_trade_usd
+            let (selling_amount, buying_amount, exact_out_target) = match self.config.max_trade_usd.and_then(|max| cap_worth(selling_amount, base_to_quote, &context, max)) {
+                Some(clamped) => {
+                    let (_, worth_usd) = worth(selling_amount, base_to_quote, &context);
+                    let max_usd = self.config.max_trade_usd.unwrap_or_default();
+                    tracing::info!(
+                        "   => Max trade: selling {:.5} {} worth {:.2} $ exceeds {:.2} $, clamped to {:.5} {} (-{:.2} $)",
+                        selling_amount,
+                        selling.symbol,
+                        worth_usd,
+                        max_usd,
+                        clamped,
+                        selling.symbol,
+                        worth_usd - max_usd
+                    );
+                    // The exact out target no longer fits, the clamped amount is sold exact in
+                    (clamped, buying_amount * clamped / selling_amount, None)
                 }
+                None => (selling_amount, buying_amount, exact_out_target),
             };
-
-            let buying_amount = if base_to_quote { selling_amount * adjustment.spot } else { selling_amount / adjustment.spot };
             // ---
             let pool_msg = format!(
                 "Pool {} | Tycho Spot: {:>12.5} vs ref {:>12.5} | Spread: {:>7.2} {} = {:>5.0} bps",
@@ -466,33 +1195,62 @@ impl MarketMaker {
             );
             tracing::debug!("{} | {}", pool_msg, inventory_msg);
             let powered_selling_amount = selling_amount * selling_pow;
-            let powered_selling_amount_bg = BigUint::from(powered_selling_amount.floor() as u128);
+            let powered_selling_amount_bg = powered(selling_amount, selling.decimals);
             let powered_buying_amount = buying_amount * buying_pow;
-            let (selling_amount_worth_eth, buying_amount_worth_eth) = if base_to_quote {
-                (selling_amount * context.base_to_eth, buying_amount * context.quote_to_eth)
-            } else {
-                (selling_amount * context.quote_to_eth, buying_amount * context.base_to_eth)
-            };
-            let (selling_amount_worth_usd, buying_amount_worth_usd) = (selling_amount_worth_eth * context.eth_to_usd, buying_amount_worth_eth * context.eth_to_usd);
+            let (_, selling_amount_worth_usd) = worth(selling_amount, base_to_quote, &context);
+            let (_, buying_amount_worth_usd) = worth(buying_amount, !base_to_quote, &context);
 
-            let is_amount_worth_usd_enough = selling_amount_worth_usd > MIN_AMOUNT_WORTH_USD;
+            let is_amount_worth_usd_enough = selling_amount_worth_usd > self.config.min_amount_worth_usd;
 
             if !is_amount_worth_usd_enough {
                 tracing::info!("Skipping readjustment due to amount worth USD not being enough");
+                self.decide(
+                    context.block,
+                    &adjustment.psc,
+                    adjustment.spot,
+                    adjustment.reference,
+                    Some(Gate::MinNotional),
+                    &[("worth_usd", selling_amount_worth_usd), ("min_usd", self.config.min_amount_worth_usd)],
+                );
                 continue;
             }
 
+            // Final quote on the pool itself, never from the simulation cache: the amounts and gas of the order
             match adjustment.psc.protosim.get_amount_out(powered_selling_amount_bg.clone(), selling, buying) {
                 Ok(result) => {
                     let amount_out_powered = result.amount.to_f64().unwrap_or(0.0);
                     let amount_out_normalized = amount_out_powered / 10f64.powi(buying.decimals as i32);
                     let slippage_bps = self.config.max_slippage_pct * BASIS_POINT_DENO;
-                    let amount_out_min_normalized = amount_out_normalized * (BASIS_POINT_DENO - slippage_bps) / BASIS_POINT_DENO;
+                    // Exact in bounds the output by the slippage. Exact out bounds its output by the target and its input by
+                    // the slippage: the reverse quote raised by the tolerance, within the inventory, is the router amountIn
+                    let (amount_out_min_normalized, amount_in_max_raw) = match exact_out_target {
+                        Some(target) => {
+                            let balance = if base_to_quote { inventory.base_balance } else { inventory.quote_balance };
+                            (target, shift_bps(&powered_selling_amount_bg, slippage_bps).min(BigUint::from(balance)))
+                        }
+                        None => (amount_out_normalized * (BASIS_POINT_DENO - slippage_bps) / BASIS_POINT_DENO, powered_selling_amount_bg.clone()),
+                    };
                     let amount_out_min_powered = amount_out_min_normalized * buying_pow;
+                    let amount_in_max_normalized = amount_in_max_raw.to_f64().unwrap_or(0.0) / selling_pow;
+                    if exact_out_target.is_some() {
+                        tracing::debug!(
+                            "   => Exact out: up to {:.5} {} for at least {:.5} {}",
+                            amount_in_max_normalized,
+                            selling.symbol,
+                            amount_out_min_normalized,
+                            buying.symbol
+                        );
+                    }
                     let gas_units = result.gas.to_string().parse::<u128>().unwrap_or_default();
-                    let gas_cost_eth = (gas_units.saturating_mul(context.native_gas_price)) as f64 / 1e18;
-                    let gas_cost_usd = gas_cost_eth * context.eth_to_usd;
-                    let gas_cost_in_output = if base_to_quote { gas_cost_eth / context.quote_to_eth } else { gas_cost_eth / context.base_to_eth };
+                    let SwapValue {
+                        gas_cost_eth,
+                        gas_cost_usd,
+                        gas_cost_in_output,
+                        average_sell_price,
+                        average_sell_price_net_gas,
+                        profit_delta: potential_profit_delta,
+                        profit_bps: potential_profit_delta_spread_bps,
+                    } = swap_value(selling_amount, amount_out_normalized, gas_units, base_to_quote, adjustment.reference, &context);
                     tracing::info!(
                         "   => Swap: {:.5} {} for {:.5} {} | Gas cost : {:.5} $ | Gas cost in output: {:.5} %",
                         selling_amount,
@@ -502,27 +1260,7 @@ impl MarketMaker {
                         gas_cost_usd,
                         gas_cost_in_output * PERCENT_MULTIPLIER
                     );
-                    let average_sell_price = if base_to_quote {
-                        amount_out_normalized / selling_amount
-                    } else {
-                        1. / (amount_out_normalized / selling_amount)
-                    };
-                    let delta = average_sell_price - adjustment.spot;
-                    let _price_impact_bps = ((delta / adjustment.spot) * BASIS_POINT_DENO).round();
-                    let average_sell_price_net_gas = if base_to_quote {
-                        (amount_out_normalized - gas_cost_in_output) / selling_amount
-                    } else {
-                        1. / ((amount_out_normalized - gas_cost_in_output) / selling_amount)
-                    };
-                    let delta_net_of_gas = average_sell_price_net_gas - adjustment.spot;
-                    let _price_impact_net_of_gas_bps = ((delta_net_of_gas / adjustment.spot) * BASIS_POINT_DENO).round();
-                    let potential_profit_delta = if base_to_quote {
-                        average_sell_price_net_gas - adjustment.reference
-                    } else {
-                        adjustment.reference - average_sell_price_net_gas
-                    };
-                    let potential_profit_delta_spread_bps = potential_profit_delta / adjustment.reference * BASIS_POINT_DENO;
-                    let is_opportunity_valid = potential_profit_delta_spread_bps > self.config.min_executable_spread_bps;
+                    let is_opportunity_valid = potential_profit_delta_spread_bps > execution_threshold_bps;
                     tracing::info!(
                         "   => Profit: {}  with average_sell_price_net_gas: {:.4} vs reference_price: {:.4} | potential_profit_delta: {:.5} | 👀  potential_profit_delta_spread_bps: {:.2}",
                         if potential_profit_delta > 0. { "🟩" } else { "🟧" },
@@ -531,6 +1269,20 @@ impl MarketMaker {
                         potential_profit_delta,
                         potential_profit_delta_spread_bps
                     );
+                    let gate = (!is_opportunity_valid).then_some(Gate::Profitability);
+                    self.decide(
+                        context.block,
+                        &adjustment.psc,
+                        adjustment.spot,
+                        adjustment.reference,
+                        gate,
+                        &[
+                            ("profit_bps", potential_profit_delta_spread_bps),
+                            ("threshold_bps", execution_threshold_bps),
+                            ("selling_amount", selling_amount),
+                            ("gas_cost_usd", gas_cost_usd),
+                        ],
+                    );
                     if is_opportunity_valid {
                         let calculation = SwapCalculation {
                             base_to_quote,
@@ -552,22 +1304,33 @@ impl MarketMaker {
                             buying_worth_usd: buying_amount_worth_usd,
                             profit_delta_bps: potential_profit_delta_spread_bps,
                             profitable: is_opportunity_valid,
+                            termination,
+                            bracket_width,
+                            exact_out: exact_out_target.is_some(),
+                            amount_in_max_normalized,
+                            amount_in_max_raw,
+                            amount_in_raw: powered_selling_amount_bg.clone(),
+                            amount_out_raw: result.amount.clone(),
                         };
+                        exposure = exposure.after(base_to_quote, selling_amount, amount_out_normalized);
                         let order = ExecutionOrder {
+                            trade_id: sea_orm::prelude::Uuid::new_v4().to_string(),
                             adjustment: adjustment.clone(),
                             calculation,
                         };
                         orders.push(order);
                     } else if potential_profit_delta_spread_bps > 0. {
                         tracing::info!(
-                            "   => 🔸 Potential profit but not enough to reach min_executable_spread_bps (of {:.2}) ! Missing {:.2} bps",
+                            "   => 🔸 Potential profit but not enough to reach the execution threshold (of {:.2}, static {:.2}) ! Missing {:.2} bps",
+                            execution_threshold_bps,
                             self.config.min_executable_spread_bps,
-                            self.config.min_executable_spread_bps - potential_profit_delta_spread_bps
+                            execution_threshold_bps - potential_profit_delta_spread_bps
                         );
                     }
                 }
                 Err(e) => {
                     tracing::warn!("Failed to simulate get amount out: {:?}", e);
+                    self.decide(context.block, &adjustment.psc, adjustment.spot, adjustment.reference, Some(Gate::Simulation), &[]);
                     continue;
                 }
             }
@@ -575,14 +1338,178 @@ impl MarketMaker {
         orders
     }
 
-    /// Builds a Tycho solution struct for the given execution order.
-    fn build_tycho_solution(&self, order: ExecutionOrder) -> Solution {
-        let input = order.adjustment.selling.address;
-        let output = order.adjustment.buying.address;
+    /// Sizes a readjustment on its pool: balances and inventory checks, optimal amount, decision engine and exact out
+    /// target. Independent of the other readjustments of the block, its simulation searches run on the blocking pool.
+    async fn size_readjustment(&self, adjustment: &CompReadjustment, balances: Option<HashMap<String, u128>>, context: &MarketContext, inventory: &Inventory) -> Sizing {
+        let balances = match balances {
+            Some(b) => b,
+            None => {
+                tracing::warn!("Failed to get component balances");
+                return Sizing::Skipped(Gate::Simulation, vec![]);
+            }
+        };
+        let buying = &adjustment.buying;
+        let buying_pow = 10f64.powi(buying.decimals as i32);
+        let buying_addr = buying.address.to_string().to_lowercase();
+        let pool_buying_balance = match balances.get(&buying_addr) {
+            Some(bal) => bal,
+            None => {
+                tracing::warn!("Failed to get buying balance for {}", buying_addr);
+                return Sizing::Skipped(Gate::Simulation, vec![]);
+            }
+        };
+        let pool_buying_balance_normalized = (*pool_buying_balance as f64) / buying_pow;
+        if pool_buying_balance_normalized < f64::EPSILON {
+            tracing::warn!("Cannot readjust, skipping due to pool_buying_balance_normalized < 0 !");
+            return Sizing::Skipped(Gate::Simulation, vec![("pool_buying_balance", 0.)]);
+        }
+        let selling = &adjustment.selling;
+        let selling_pow = 10f64.powi(selling.decimals as i32);
+        let selling_addr = selling.address.to_string().to_lowercase();
+        let pool_selling_balance = match balances.get(&selling_addr) {
+            Some(bal) => bal,
+            None => {
+                tracing::warn!("Failed to get selling balance for {}", selling_addr);
+                return Sizing::Skipped(Gate::Simulation, vec![]);
+            }
+        };
+        let pool_selling_balance_normalized = (*pool_selling_balance as f64) / selling_pow;
+        if pool_selling_balance_normalized < f64::EPSILON {
+            tracing::warn!("Cannot readjust, skipping due to pool_selling_balance_normalized < 0 !");
+            return Sizing::Skipped(Gate::Simulation, vec![("pool_selling_balance", 0.)]);
+        }
+
+        if context.eth_to_usd <= 0. {
+            tracing::warn!("Cannot readjust, skipping due to eth_to_usd <= 0 !");
+            return Sizing::Skipped(Gate::Simulation, vec![("eth_to_usd", context.eth_to_usd)]);
+        }
+
+        // Use TradeDirection from adjustment to determine swap direction
+        let base_to_quote = adjustment.direction == TradeDirection::Buy;
+
+        // Optimal amount computation using binary search
+        let inventory_balance = if base_to_quote { inventory.base_balance } else { inventory.quote_balance };
+
+        // Skip if inventory balance is 0
+        if inventory_balance == 0 {
+            return Sizing::Skipped(Gate::Inventory, vec![("inventory_balance", 0.)]);
+        }
+
+        let inventory_balance_normalized = (inventory_balance as f64) / selling_pow;
+        let max_alloc = inventory_balance_normalized * self.config.max_inventory_ratio;
+        // Owned by the blocking tasks of the searches
+        let protosim: Arc<dyn ProtocolSim> = match self.cached_sim(&adjustment.psc.component.id.to_string(), adjustment.psc.protosim.as_ref()) {
+            Some(sim) => Arc::new(sim),
+            None => Arc::from(adjustment.psc.protosim.clone_box()),
+        };
+        let max_simulations = self.config.opti_max_simulations;
+
+        // Run optimization to find optimal swap amount
+        let (sim, selling_token, buying_token, reference) = (protosim.clone(), selling.clone(), buying.clone(), adjustment.reference);
+        let optimization_result = blocking(move || crate::opti::math::find_optimal_swap_amount(sim.as_ref(), &selling_token, &buying_token, reference, base_to_quote, max_alloc, max_simulations))
+            .await
+            .and_then(|r| r);
+
+        let (selling_amount, termination, bracket_width) = match optimization_result {
+            Ok(opt) => {
+                match opt.termination {
+                    TerminationReason::Converged => tracing::debug!("   => Optimizer converged after {} simulations (bracket {:.8})", opt.simulation_count, opt.bracket_width),
+                    reason => tracing::warn!(
+                        "   => Optimizer stopped early: {:?} after {} simulations (bracket {:.8}), using best effort qty {:.5} {}",
+                        reason,
+                        opt.simulation_count,
+                        opt.bracket_width,
+                        opt.optimal_qty,
+                        selling.symbol
+                    ),
+                }
+                (opt.optimal_qty, opt.termination, opt.bracket_width)
+            }
+            Err(e) => {
+                tracing::error!("   => Optimization failed: {}. Skipping trade.", e);
+                return Sizing::Skipped(Gate::Simulation, vec![]);
+            }
+        };
+
+        // Sized by the decision engine, the optimal amount unless another engine is plugged
+        let selling_amount = self.engine.size(self, adjustment, context, inventory, selling_amount).max(0.).min(max_alloc);
+
+        // Exact out: buy exactly what the depleted token is missing, capped by the output of the optimal readjustment
+        let exact_out_target = match self.config.rebalance_mode {
+            RebalanceMode::ExactOut => self.exact_out_target(adjustment, protosim.as_ref(), context, inventory, selling_amount),
+            RebalanceMode::ExactIn => None,
+        };
+        let (selling_amount, exact_out_target) = match exact_out_target {
+            Some(target) => {
+                let (sim, selling_token, buying_token) = (protosim.clone(), selling.clone(), buying.clone());
+                match blocking(move || crate::opti::math::find_amount_in(sim.as_ref(), &selling_token, &buying_token, target, max_alloc, max_simulations))
+                    .await
+                    .and_then(|r| r)
+                {
+                    Ok(amount_in) => {
+                        tracing::debug!("   => Exact out: buying {:.5} {} for {:.5} {} (reverse quote)", target, buying.symbol, amount_in, selling.symbol);
+                        (amount_in, Some(target))
+                    }
+                    Err(e) => {
+                        tracing::warn!("   => Exact out reverse quote failed: {}. Falling back to exact in.", e);
+                        (selling_amount, None)
+                    }
+                }
+            }
+            None => (selling_amount, None),
+        };
 
-        let amount_in = BigUint::from((order.calculation.powered_selling_amount).floor() as u128);
-        let amount_out = BigUint::from((order.calculation.amount_out_powered).floor() as u128);
-        let amount_out_min = BigUint::from((order.calculation.amount_out_min_powered).floor() as u128);
+        Sizing::Sized(Box::new(SizedReadjustment {
+            base_to_quote,
+            selling_amount,
+            exact_out_target,
+            termination,
+            bracket_width,
+            inventory_balance_normalized,
+            max_alloc,
+            pool_buying_balance_normalized,
+        }))
+    }
+
+    /// Exact out target for the adjustment: the amount of the bought token missing to reach its target inventory share,
+    /// capped by the output of the optimal exact in readjustment. None if the bought token is not below target.
+    fn exact_out_target(&self, adjustment: &CompReadjustment, protosim: &dyn ProtocolSim, context: &MarketContext, inventory: &Inventory, optimal_in: f64) -> Option<f64> {
+        let buying_base = adjustment.direction == TradeDirection::Sell;
+        let missing = skew::deficit(inventory, &self.base, &self.quote, context, self.config.target_inventory_ratio, buying_base)?;
+        let amount_in = powered(optimal_in, adjustment.selling.decimals);
+        let optimal_out = match protosim.get_amount_out(amount_in, &adjustment.selling, &adjustment.buying) {
+            Ok(result) => result.amount.to_f64().unwrap_or(0.0) / 10f64.powi(adjustment.buying.decimals as i32),
+            Err(e) => {
+                tracing::warn!("Failed to simulate the optimal readjustment for exact out: {:?}", e);
+                return None;
+            }
+        };
+        let target = missing.min(optimal_out);
+        (target > 0.).then_some(target)
+    }
+
+    /// Builds a Tycho solution struct for the given execution order.
+    pub fn build_tycho_solution(&self, order: ExecutionOrder) -> Solution {
+        // Tycho and its router know the native token by the zero address only, the EIP-7528 sentinel is mapped onto it
+        let native = |address: tycho_common::Bytes| {
+            if is_native(&address.to_string()) {
+                tycho_common::Bytes::from(Address::ZERO.to_vec())
+            } else {
+                address
+            }
+        };
+        let input = native(order.adjustment.selling.address);
+        let output = native(order.adjustment.buying.address);
+
+        // Raw amounts in BigUint: the exact simulated input and quoted output, the bounds scaled from them
+        let amount_in = order.calculation.amount_in_raw.clone();
+        let amount_in_max = order.calculation.amount_in_max_raw.clone();
+        let amount_out = order.calculation.amount_out_raw.clone();
+        let amount_out_min = if order.calculation.exact_out {
+            powered(order.calculation.amount_out_min_normalized, order.adjustment.buying.decimals)
+        } else {
+            shift_bps(&amount_out, -self.config.max_slippage_pct * BASIS_POINT_DENO)
+        };
 
         tracing::debug!(
             " - {} : Building Tycho solution: Buying {} with {} | Amount in: {} | Amount out: {} | Amount out min: {} {}",
@@ -603,39 +1530,86 @@ impl MarketMaker {
             output.clone(),                         // token_out (buy token)
         )
         .build();
-        // CONSERVATIVE: Solution struct changed in tycho-execution 0.130.1
-        // - Removed: slippage field (DISABLED - now handled at encoder level)
-        // - Removed: expected_amount field (DISABLED - optimization removed)
-        // - Changed: checked_amount from Option<BigUint> to BigUint (now required)
+        // Solution of tycho-execution 0.130.1: no slippage nor expected_amount, checked_amount is the only bound.
+        // It is encoded as the router minAmountOut, which reverts (TychoRouter__NegativeSlippage) below it, however late
+        // the swap is mined: the quoted output minus max_slippage_pct, never the quote itself.
+        let sender = tycho_simulation::tycho_core::Bytes::from_str(self.config.wallet_public_key.to_lowercase().as_str()).unwrap();
+        if order.calculation.exact_out {
+            // Exact out: the given amount is the exact output, checked against the max input
+            return Solution {
+                sender: sender.clone(),
+                receiver: sender,
+                given_token: output.clone(),
+                checked_token: input.clone(),
+                given_amount: amount_out_min,
+                checked_amount: amount_in_max,
+                exact_out: true,
+                swaps: vec![swap],
+                ..Default::default()
+            };
+        }
         Solution {
             // Addresses
-            sender: tycho_simulation::tycho_core::Bytes::from_str(self.config.wallet_public_key.to_lowercase().as_str()).unwrap(),
-            receiver: tycho_simulation::tycho_core::Bytes::from_str(self.config.wallet_public_key.to_lowercase().as_str()).unwrap(),
+            sender: sender.clone(),
+            receiver: sender,
             given_token: input.clone(),
             checked_token: output.clone(),
             // Amount fields
             given_amount: amount_in.clone(),
-            checked_amount: amount_out_min, // Now required BigUint (not Option)
+            checked_amount: amount_out_min, // Slippage-adjusted minimum output
             exact_out: false,               // It's an exact in solution
             swaps: vec![swap.clone()],
             ..Default::default()
         }
     }
 
+    /// Input of an order as sent to the router (amountIn), raw: the simulated input, the max input of an exact out order.
+    pub fn router_input(order: &ExecutionOrder) -> u128 {
+        order.calculation.amount_in_max_raw.to_string().parse().unwrap_or(u128::MAX)
+    }
+
+    /// Router view of a solution. The router only exposes exact in swaps, so an exact out solution is sent as spending
+    /// its max input, with its exact output as the minimum received. The inventory and the PnL book the amounts of the
+    /// receipt transfers.
+    pub fn router_solution(solution: &Solution) -> Solution {
+        if !solution.exact_out {
+            return solution.clone();
+        }
+        Solution {
+            given_token: solution.checked_token.clone(),
+            checked_token: solution.given_token.clone(),
+            given_amount: solution.checked_amount.clone(),
+            checked_amount: solution.given_amount.clone(),
+            exact_out: false,
+            ..solution.clone()
+        }
+    }
+
     /// Builds transaction request for trade execution with gas settings and optional approval.
     fn trade_tx_request(&self, solution: Solution, tx: Transaction, context: MarketContext, inventory: Inventory) -> Result<TradeTxRequest, String> {
         let max_priority_fee_per_gas = context.max_priority_fee_per_gas.max(self.config.min_priority_fee_per_gas as u128);
         let max_fee_per_gas = context.max_fee_per_gas.max(max_priority_fee_per_gas);
 
-        // 1. Approvals - only if infinite_approval is false
-        // FIXED: Direct router approval (not Permit2)
-        // Approval flow: Token.approve(Router, amount) → Router transfers directly
-        let approval = if !self.config.infinite_approval {
-            let amount: u128 = solution.given_amount.clone().to_string().parse().expect("Couldn't convert given_amount to u128");
-            let router_address: Address = self.config.tycho_router_address.parse().expect("Failed to parse Router address");
+        // 1. Approvals - only if infinite_approval is false, and not with Permit2 permits (signed, sent with the swap) nor native input
+        // Approval flow: Token.approve(Router, amount) → Router transfers directly, skipped when the cached allowance covers the amount
+        let amount: u128 = solution
+            .given_amount
+            .clone()
+            .to_string()
+            .parse()
+            .map_err(|e| format!("Couldn't convert given_amount to u128: {:?}", e))?;
+        let approves = !self.config.infinite_approval && !self.config.permit2_approval && !is_native(&solution.given_token.to_string());
+        // Read once, the cache may change in between
+        let covered = approves && self.allowances.covers(&solution.given_token.to_string(), amount);
+        if covered {
+            tracing::debug!("  📝 Router allowance of {} covers {}, no approval tx", solution.given_token, amount);
+        }
+        let approval = if approves && !covered {
+            let router_address: Address = self.config.tycho_router_address.parse().map_err(|e| format!("Failed to parse Router address: {:?}", e))?;
             let args = (router_address, amount);
             let data = encode_input(APPROVE_FN_SIGNATURE, args.abi_encode());
-            let sender = solution.sender.clone().to_string().parse().expect("Failed to parse sender");
+            let sender: Address = solution.sender.to_string().parse().map_err(|e| format!("Failed to parse sender: {:?}", e))?;
+            let given_token: Address = solution.given_token.to_string().parse().map_err(|e| format!("Failed to parse given_token: {:?}", e))?;
 
             tracing::debug!(
                 "  📝 Building approval tx: Token {} approves Router {} for amount {}",
@@ -645,7 +1619,7 @@ impl MarketMaker {
             );
 
             Some(TransactionRequest {
-                to: Some(alloy::primitives::TxKind::Call(solution.given_token.clone().to_string().parse().expect("Failed to parse given_token"))),
+                to: Some(alloy::primitives::TxKind::Call(given_token)),
                 from: Some(sender),
                 value: None,
                 input: TransactionInput {
@@ -656,18 +1630,65 @@ impl MarketMaker {
                 chain_id: Some(self.config.chain_id),
                 max_fee_per_gas: Some(max_fee_per_gas),
                 max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
-                nonce: Some(inventory.nonce),
                 ..Default::default()
             })
         } else {
             None
         };
 
+        // Wrap (sent first) native into the sold wrapped token, when the wrapped balance does not cover the input
+        let wallet: Address = self.config.wallet_public_key.parse().map_err(|e| format!("Failed to parse wallet public key: {:?}", e))?;
+        let wrapped = self.config.gas_token_symbol.parse::<Address>().ok();
+        let sells_wrapped = solution.given_token.to_string().eq_ignore_ascii_case(&self.config.gas_token_symbol);
+        let buys_wrapped = solution.checked_token.to_string().eq_ignore_ascii_case(&self.config.gas_token_symbol);
+        let balance_of = |token: &tycho_common::Bytes| {
+            if token.to_string().eq_ignore_ascii_case(&self.base.address.to_string()) {
+                inventory.base_balance
+            } else {
+                inventory.quote_balance
+            }
+        };
+        let wrap = match wrapped {
+            Some(token) if self.config.auto_wrap_native && sells_wrapped => {
+                let amount_in: u128 = solution.given_amount.to_string().parse().map_err(|e| format!("Couldn't convert given_amount to u128: {:?}", e))?;
+                let wrappable = inventory.wrappable(self.config.min_native_balance_wei, self.wrap_gas_reserve(&context));
+                crate::maker::wrap::wrap_amount(amount_in, balance_of(&solution.given_token), wrappable)?.map(|amount| {
+                    tracing::debug!("  📦 Wrapping {} native before the swap", amount);
+                    crate::maker::wrap::deposit(token, wallet, amount)
+                })
+            }
+            _ => None,
+        };
+
+        // Unwrap (sent last) the bought wrapped token above unwrap_to_native_above
+        let unwrap = match wrapped {
+            Some(token) if self.config.unwrap_to_native_above > 0. && buys_wrapped => {
+                let decimals = if self.config.wrapped_native_side() == Some(true) {
+                    self.base.decimals
+                } else {
+                    self.quote.decimals
+                };
+                let threshold = (self.config.unwrap_to_native_above * 10f64.powi(decimals as i32)).floor() as u128;
+                let amount_out_min: u128 = solution.checked_amount.to_string().parse().map_err(|e| format!("Couldn't convert checked_amount to u128: {:?}", e))?;
+                crate::maker::wrap::unwrap_amount(balance_of(&solution.checked_token), amount_out_min, threshold).map(|amount| {
+                    tracing::debug!("  📦 Unwrapping {} after the swap", amount);
+                    crate::maker::wrap::withdraw(token, wallet, amount)
+                })
+            }
+            _ => None,
+        };
+        let fees = |tx: TransactionRequest| TransactionRequest {
+            chain_id: Some(self.config.chain_id),
+            max_fee_per_gas: Some(max_fee_per_gas),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            ..tx
+        };
+
         // 2. Swap --- No bribe for now ---
         let swap = TransactionRequest {
             to: Some(alloy_primitives::TxKind::Call(Address::from_slice(&tx.to))),
-            from: Some(self.config.wallet_public_key.parse().expect("Failed to parse wallet public key")),
-            value: Some(U256::from(0)),
+            from: Some(wallet),
+            value: Some(U256::from_str(&tx.value.to_string()).map_err(|e| format!("Couldn't convert value to U256: {:?}", e))?),
             input: TransactionInput {
                 input: Some(AlloyBytes::from(tx.data)),
                 data: None,
@@ -676,123 +1697,153 @@ impl MarketMaker {
             chain_id: Some(self.config.chain_id),
             max_fee_per_gas: Some(max_fee_per_gas),
             max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
-            nonce: Some(inventory.nonce + if approval.is_some() { 1 } else { 0 }),
             ..Default::default()
         };
 
-        Ok(TradeTxRequest { approve: approval, swap })
+        let mut request = TradeTxRequest {
+            wrap: wrap.map(fees),
+            approve: approval,
+            swap,
+            unwrap: unwrap.map(fees),
+        };
+        request.sequence(inventory.nonce);
+        Ok(request)
     }
 
     /// Prepares execution orders for on-chain submission.
     ///
     /// Encodes orders into transactions using the Tycho router encoder.
-    fn prepare(&self, orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, context: MarketContext, inventory: Inventory, _env: EnvConfig) -> Vec<Trade> {
+    #[tracing::instrument(name = "encode", level = "debug", skip_all, fields(orders = orders.len()))]
+    pub fn prepare(&self, orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, context: MarketContext, inventory: Inventory, permits: &HashMap<String, SignedPermit>) -> Vec<Trade> {
         tracing::debug!(">>>>>>> Preparing the execution of {} trades <<<<<<<", orders.len());
-        unsafe {
-            std::env::set_var("RPC_URL", self.config.rpc_url.clone());
-        }
-        let (_, chain) = match crate::maker::tycho::chain(self.config.network_name.as_str().to_string()) {
-            Some(c) => c,
-            None => {
-                tracing::error!("Unknown chain: {}, skipping trade preparation", self.config.network_name);
-                return vec![];
-            }
+        let Some(encoder) = TychoEncoder::of(&self.config) else {
+            tracing::error!("Unknown chain: {}, skipping trade preparation", self.config.network_name);
+            return vec![];
         };
+        self.encode_with_permits(orders, tdata, context, inventory, &encoder, permits)
+    }
+
+    /// Builds the solutions of the orders, encodes them with `encoder`, and wraps each router call into its transactions
+    /// (wrap, approval, swap, unwrap), sequenced from the inventory nonce.
+    pub fn encode(&self, orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, context: MarketContext, inventory: Inventory, encoder: &dyn SolutionEncoder) -> Vec<Trade> {
+        self.encode_with_permits(orders, tdata, context, inventory, encoder, &HashMap::new())
+    }
+
+    /// Same as `encode`, with `permit2_approval` the swaps call `singleSwapPermit2` with the permit signed for their
+    /// trade id, and no approval is sent. An order without a permit is skipped.
+    pub fn encode_with_permits(
+        &self, orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, context: MarketContext, inventory: Inventory, encoder: &dyn SolutionEncoder, permits: &HashMap<String, SignedPermit>,
+    ) -> Vec<Trade> {
         let mut output: Vec<Trade> = vec![];
-        let solutions = orders.iter().map(|order| self.build_tycho_solution(order.clone())).collect::<Vec<Solution>>();
+        let solutions = orders
+            .iter()
+            .map(|order| tracing::info_span!("trade", trade_id = %order.trade_id).in_scope(|| Self::router_solution(&self.build_tycho_solution(order.clone()))))
+            .collect::<Vec<Solution>>();
 
         tracing::debug!("Built {} solution(s) for execution", solutions.len());
 
-        // Always use TransferFrom (direct router approval)
-        // - infinite_approval = true:  Router already approved infinitely, no approval TX
-        // - infinite_approval = false: Approval TX approves router, then router transfers directly
-        let user_transfer_type = UserTransferType::TransferFrom;
-
-        tracing::debug!("🔧 Building TychoRouterEncoder with UserTransferType::TransferFrom (direct router approval)");
-        let encoder = TychoRouterEncoderBuilder::new().chain(chain).user_transfer_type(user_transfer_type).build();
-
-        match encoder {
-            Ok(encoder) => {
-                tracing::debug!("✅ Encoder built successfully");
-                match encoder.encode_solutions(solutions.clone()) {
-                    Ok(encoded_solutions) => {
-                        tracing::debug!("✅ Encoded {} solution(s) successfully", encoded_solutions.len());
-                        for i in 0..orders.len() {
-                            let _order = &orders[i];
-                            let solution = &solutions[i];
-                            let encoded_solution: &EncodedSolution = &encoded_solutions[i];
-                            let metadata = tdata[i].clone();
-
-                            tracing::debug!("🔍 DEBUG: EncodedSolution #{}:", i);
-                            tracing::debug!("   Router address: {:?}", encoded_solution.interacting_with);
-                            tracing::debug!("   Function signature: {:?}", encoded_solution.function_signature);
-                            tracing::debug!("   N tokens: {}", encoded_solution.n_tokens);
-                            tracing::debug!("   Permit present: {}", if encoded_solution.permit.is_some() { "Yes" } else { "None" });
-                            tracing::debug!("   Swaps data length: {} bytes", encoded_solution.swaps.len());
-                            if encoded_solution.swaps.len() >= 4 {
-                                let function_selector = &encoded_solution.swaps[0..4];
-                                tracing::debug!("   Function selector (first 4 bytes): 0x{}", hex::encode(function_selector));
-                            }
-                            // tracing::debug!("   Full swaps bytes: 0x{}", hex::encode(&encoded_solution.swaps));
-
-                            // Build proper router function call with ABI encoding
-                            // encoded_solution.swaps is just the swap routing data (105 bytes)
-                            // We need to construct the full singleSwap call with all parameters
-                            //
-                            // Always use singleSwap() with direct router approval:
-                            // - infinite_approval = true:  Router already approved infinitely
-                            // - infinite_approval = false: Approval TX approves router before swap
-
-                            let amount_in_u256 = U256::from_str(&solution.given_amount.to_string()).expect("Failed to convert given_amount");
-                            let min_amount_out_u256 = U256::from_str(&solution.checked_amount.to_string()).expect("Failed to convert checked_amount");
-                            let token_in = Address::from_slice(&solution.given_token);
-                            let token_out = Address::from_slice(&solution.checked_token);
-                            let receiver = Address::from_slice(&solution.receiver);
-
-                            // Always use singleSwap() - direct router approval flow
-                            tracing::debug!("   🔧 Using singleSwap() - direct router approval flow");
-                            let call = ITychoRouter::singleSwapCall {
-                                amountIn: amount_in_u256,
-                                tokenIn: token_in,
-                                tokenOut: token_out,
-                                minAmountOut: min_amount_out_u256,
-                                wrapEth: false,
-                                unwrapEth: false,
-                                receiver,
-                                isTransferFromAllowed: true, // Router has approval (infinite or per-swap)
-                                swapData: AlloyBytes::from(encoded_solution.swaps.clone()),
-                            };
-                            let calldata = call.abi_encode();
+        match encoder.encode(solutions.clone()) {
+            Ok(encoded_solutions) if encoded_solutions.len() != solutions.len() => {
+                tracing::error!("Encoder returned {} solution(s) for {}", encoded_solutions.len(), solutions.len());
+            }
+            Ok(encoded_solutions) => {
+                tracing::debug!("✅ Encoded {} solution(s) successfully", encoded_solutions.len());
+                for i in 0..orders.len() {
+                    let _span = tracing::info_span!("trade", trade_id = %orders[i].trade_id).entered();
+                    let solution = &solutions[i];
+                    let encoded_solution: &EncodedSolution = &encoded_solutions[i];
+                    let metadata = tdata[i].clone();
+
+                    tracing::debug!("🔍 DEBUG: EncodedSolution #{}:", i);
+                    tracing::debug!("   Router address: {:?}", encoded_solution.interacting_with);
+                    tracing::debug!("   Function signature: {:?}", encoded_solution.function_signature);
+                    tracing::debug!("   N tokens: {}", encoded_solution.n_tokens);
+                    tracing::debug!("   Permit present: {}", if encoded_solution.permit.is_some() { "Yes" } else { "None" });
+                    tracing::debug!("   Swaps data length: {} bytes", encoded_solution.swaps.len());
+                    if encoded_solution.swaps.len() >= 4 {
+                        let function_selector = &encoded_solution.swaps[0..4];
+                        tracing::debug!("   Function selector (first 4 bytes): 0x{}", hex::encode(function_selector));
+                    }
+                    // tracing::debug!("   Full swaps bytes: 0x{}", hex::encode(&encoded_solution.swaps));
+
+                    // Build proper router function call with ABI encoding
+                    // encoded_solution.swaps is just the swap routing data (105 bytes)
+                    // We need to construct the full singleSwap call with all parameters
+                    //
+                    // singleSwap() with direct router approval:
+                    // - infinite_approval = true:  Router already approved infinitely
+                    // - infinite_approval = false: Approval TX approves router before swap
+                    // singleSwapPermit2() with permit2_approval: the signed permit lets the router pull through Permit2
+
+                    let amount_in_u256 = U256::from_str(&solution.given_amount.to_string()).expect("Failed to convert given_amount");
+                    let min_amount_out_u256 = U256::from_str(&solution.checked_amount.to_string()).expect("Failed to convert checked_amount");
+                    let token_in = Address::from_slice(&solution.given_token);
+                    let token_out = Address::from_slice(&solution.checked_token);
+                    let receiver = Address::from_slice(&solution.receiver);
+                    // Native input is sent as the transaction value, nothing to approve nor to pull from the wallet
+                    let native_in = is_native(&solution.given_token.to_string());
+
+                    let calldata = if self.config.permit2_approval && !native_in {
+                        let Some(signed) = permits.get(&orders[i].trade_id) else {
+                            tracing::error!("No signed Permit2 permit, skipping the trade");
+                            continue;
+                        };
+                        tracing::debug!("   🔧 Using singleSwapPermit2() - signed permit at nonce {}", signed.permit.details.nonce);
+                        ITychoRouter::singleSwapPermit2Call {
+                            amountIn: amount_in_u256,
+                            tokenIn: token_in,
+                            tokenOut: token_out,
+                            minAmountOut: min_amount_out_u256,
+                            wrapEth: false,
+                            unwrapEth: false,
+                            receiver,
+                            permitSingle: signed.permit.clone(),
+                            signature: AlloyBytes::from(signed.signature.clone()),
+                            swapData: AlloyBytes::from(encoded_solution.swaps.clone()),
+                        }
+                        .abi_encode()
+                    } else {
+                        tracing::debug!("   🔧 Using singleSwap() - direct router approval flow");
+                        ITychoRouter::singleSwapCall {
+                            amountIn: amount_in_u256,
+                            tokenIn: token_in,
+                            tokenOut: token_out,
+                            minAmountOut: min_amount_out_u256,
+                            wrapEth: false,
+                            unwrapEth: false,
+                            receiver,
+                            isTransferFromAllowed: !native_in, // Router has approval (infinite or per-swap)
+                            swapData: AlloyBytes::from(encoded_solution.swaps.clone()),
+                        }
+                        .abi_encode()
+                    };
 
-                            tracing::debug!("   📦 Encoded full router call: {} bytes", calldata.len());
+                    tracing::debug!("   📦 Encoded full router call: {} bytes", calldata.len());
 
-                            let transaction = Transaction {
-                                to: encoded_solution.interacting_with.clone(),
-                                value: BigUint::from(0u128),
-                                data: calldata,
-                            };
+                    let transaction = Transaction {
+                        to: encoded_solution.interacting_with.clone(),
+                        value: if native_in { solution.given_amount.clone() } else { BigUint::from(0u128) },
+                        data: calldata,
+                    };
 
-                            match self.trade_tx_request(solution.clone(), transaction, context.clone(), inventory.clone()) {
-                                Ok(encoded_tx) => {
-                                    output.push(Trade {
-                                        approve: encoded_tx.approve,
-                                        swap: encoded_tx.swap,
-                                        metadata,
-                                    });
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to prepare transaction: {:?}", e);
-                                }
-                            }
+                    match self.trade_tx_request(solution.clone(), transaction, context.clone(), inventory.clone()) {
+                        Ok(encoded_tx) => {
+                            output.push(Trade {
+                                wrap: encoded_tx.wrap,
+                                approve: encoded_tx.approve,
+                                swap: encoded_tx.swap,
+                                unwrap: encoded_tx.unwrap,
+                                metadata,
+                            });
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to prepare transaction: {:?}", e);
                         }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to encode solutions: {:?}", e);
                     }
                 }
             }
             Err(e) => {
-                tracing::error!("Failed to build TychoRouterEncoder: {:?}", e);
+                tracing::error!("{}", e);
             }
         };
         output
@@ -800,309 +1851,918 @@ impl MarketMaker {
 
     /// Fetches current market price from the configured price feed.
     pub async fn fetch_market_price(&self) -> Result<f64, String> {
-        self.feed.get(self.config.clone()).await
+        if let Some(frozen) = &self.frozen {
+            self.health.feed(true);
+            return Ok(frozen.reference_price);
+        }
+        let price = self.feed.get(self.config.clone()).await;
+        self.health.feed(price.is_ok());
+        price
+    }
+
+    /// Returns the realized PnL, cumulated since startup and over the rolling window.
+    pub fn pnl(&self) -> PnlSnapshot {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+        self.pnl.snapshot(now)
+    }
+
+    /// Updates the daily loss circuit breaker, and resumes it when the operator sets the Redis resume key.
+    async fn guard(&mut self, identifier: &str, pnl: &PnlSnapshot) {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+        if self.breaker.is_open() && self.resume_requested(identifier).await {
+            self.breaker.resume(now);
+            tracing::warn!("🟢 Circuit breaker resumed by operator command");
+            self.alert(AlertKind::CircuitBreakerResumed, "Circuit breaker resumed by operator command".to_string(), Some(pnl.clone()));
+            return;
+        }
+        match self.breaker.check(&self.pnl, now) {
+            Some(BreakerEvent::Opened { loss_usd }) => {
+                let message = format!(
+                    "Circuit breaker OPEN: realized loss of {:.2} $ over the last {}h exceeds max_daily_loss_usd ({:.2} $). No new orders until cooldown ({} ms) or `SET {} true` on Redis",
+                    loss_usd,
+                    pnl.window_ms / 3_600_000,
+                    self.config.max_daily_loss_usd,
+                    self.config.breaker_cooldown_ms,
+                    crate::data::keys::key_breaker_resume(identifier)
+                );
+                tracing::error!("🚨🚨🚨 {} 🚨🚨🚨", message);
+                self.alert(AlertKind::CircuitBreakerOpened, message, Some(pnl.clone()));
+            }
+            Some(BreakerEvent::CooldownElapsed) => {
+                tracing::warn!("🟢 Circuit breaker resumed after cooldown of {} ms", self.config.breaker_cooldown_ms);
+                self.alert(AlertKind::CircuitBreakerResumed, "Circuit breaker resumed after cooldown".to_string(), Some(pnl.clone()));
+            }
+            None => {}
+        }
+    }
+
+    /// Returns true, and consumes the keys, when the operator set a breaker resume key of the instance.
+    async fn resume_requested(&self, identifier: &str) -> bool {
+        let mut requested = false;
+        for key in crate::data::keys::breaker_resume_keys(identifier) {
+            if crate::data::helpers::get::<bool>(&key).await.unwrap_or(false) {
+                crate::data::helpers::delete(&key).await;
+                requested = true;
+            }
+        }
+        requested
+    }
+
+    /// Publishes an operational alert, if events are enabled.
+    fn alert(&self, kind: AlertKind, message: String, pnl: Option<PnlSnapshot>) {
+        let alert = NewAlertMessage {
+            identifier: self.identifier.clone(),
+            kind,
+            message,
+            pnl,
+        };
+        if self.config.publish_events {
+            let _ = crate::data::r#pub::alert(alert.clone());
+        }
+        crate::utils::alert::notify(alert);
+    }
+
+    /// Returns true once the instance is killed from the control channel, or the process is shutting down.
+    pub fn stopping(&self) -> bool {
+        self.control.is_killed() || self.shutdown.is_requested()
+    }
+
+    /// Publishes the final PnL, and a kill alert on operator command, and writes the pending audit records and snapshot,
+    /// before the loop exits.
+    fn close(&self) {
+        self.health.set_ready(false);
+        self.audit.flush();
+        self.snapshot.flush();
+        let pnl = self.pnl();
+        let killed = self.control.is_killed();
+        let reason = if killed { "Killed by operator command" } else { "Shutdown requested" };
+        tracing::warn!("🛑 {}, exiting | PnL: {:+.2} $ over {} trades", reason, pnl.cumulative_usd, pnl.trades);
+        if self.config.publish_events {
+            let _ = crate::data::r#pub::pnl(NewPnlMessage {
+                identifier: self.identifier.clone(),
+                pnl: pnl.clone(),
+            });
+        }
+        if killed {
+            self.alert(AlertKind::Killed, reason.to_string(), Some(pnl));
+        }
     }
 
     /// Main market maker runtime loop that monitors pools and executes trades.
     ///
     /// Streams protocol updates, evaluates opportunities, and executes profitable trades.
+    /// Returns once the instance is killed from the control channel, or on shutdown.
     pub async fn run(&mut self, mtx: SharedTychoStreamState, env: EnvConfig) {
-        let mut last_publish = std::time::Instant::now() - std::time::Duration::from_millis(self.config.min_publish_timeframe_ms);
-        let mut last_poll = std::time::Instant::now() - std::time::Duration::from_millis(self.config.poll_interval_ms);
+        let mut auth_attempt = 0;
         loop {
             tracing::debug!("Connecting ProtocolStreamBuilder for {}", self.config.network_name.as_str().to_string());
             let psbc = PsbConfig {
-                filter: ComponentFilter::with_tvl_range(ADD_TVL_THRESHOLD, ADD_TVL_THRESHOLD),
+                filter: ComponentFilter::with_tvl_range(self.config.tvl_remove_threshold, self.config.tvl_add_threshold),
             };
             let state = mtx.read().await;
             let atks = state.atks.clone();
             drop(state);
-            let mut components = vec![];
-            let mut previous_reference_price = 0.0;
-            let mut protosims: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
             let psb = crate::maker::tycho::psb(self.config.clone(), env.tycho_api_key.to_string(), psbc.clone(), atks.clone()).await;
             match psb.build().await {
-                Ok(mut stream) => loop {
-                    // Looping
-                    match stream.next().await {
-                        Some(msg) => match msg {
-                            Ok(msg) => {
-                                let time = std::time::SystemTime::now();
-                                let intro = format!(
-                                    "{} {} stream: b#{} with {} states", // , + {} pairs, - {} pairs",
-                                    self.config.pair_tag,
-                                    self.config.network_name.as_str(),
-                                    msg.block_number_or_timestamp, // Changed from block_number in tycho-simulation 0.181.3
-                                    msg.states.len()
-                                );
+                Ok(stream) => {
+                    auth_attempt = 0;
+                    let stream = stream.map(|msg| msg.map(SharedUpdate::from).map_err(|e| format!("{:?}", e)));
+                    self.consume(stream, atks, env.clone()).await;
+                    if self.stopping() {
+                        return;
+                    }
+                    // The next connection opens with a full snapshot, resynced on the pools kept from this one
+                    self.ready = false;
+                    self.health.reconnecting();
+                    let delay = self.reconnect.disconnected(std::time::Instant::now());
+                    tracing::info!(
+                        "🔌 Reconnecting the {} stream in {} ms (attempt {})",
+                        self.config.network_name,
+                        delay.as_millis(),
+                        self.reconnect.attempts()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    let e = TychoApiError::classify(e.to_string());
+                    if retry_auth(&self.config, "opening the stream", &e, auth_attempt).await {
+                        auth_attempt += 1;
+                        continue;
+                    }
+                    tracing::warn!("Failed to build stream on {}: {}. Exiting.", self.config.network_name.as_str().to_string(), e);
+                    return;
+                }
+            };
+        }
+    }
 
-                                if !self.ready {
-                                    tracing::info!("{}", intro);
-                                    // --- First stream ---
+    /// Pair loop fed by the shared stream instead of its own ProtocolStreamBuilder.
+    ///
+    /// Bootstraps from a cache snapshot, then consumes broadcast updates. A lagging pair
+    /// resubscribes and bootstraps again rather than working on a state with missed deltas.
+    pub async fn run_shared(&mut self, mtx: SharedTychoStreamState, tx: broadcast::Sender<Arc<SharedUpdate>>, env: EnvConfig) {
+        loop {
+            // Subscribe before reading the snapshot, so no update falls between the two
+            let rx = tx.subscribe();
+            let (snapshot, atks) = {
+                let state = mtx.read().await;
+                (state.snapshot(), state.atks.clone())
+            };
+            self.ready = false;
+            self.health.set_ready(false);
+            let updates = futures::stream::unfold(rx, |mut rx| async move {
+                match rx.recv().await {
+                    Ok(update) => Some((Ok(update.as_ref().clone()), rx)),
+                    Err(RecvError::Lagged(missed)) => Some((Err(format!("Lagged behind the shared stream by {} updates", missed)), rx)),
+                    Err(RecvError::Closed) => None,
+                }
+            });
+            let stream = futures::stream::iter(snapshot.map(Ok)).chain(updates);
+            self.consume(Box::pin(stream), atks, env.clone()).await;
+            if self.stopping() {
+                return;
+            }
+        }
+    }
 
-                                    // Fetch reference price first for validation
-                                    let reference_price = match self.fetch_market_price().await {
-                                        Ok(price) if price > 0.0 => {
-                                            tracing::info!("📊 Reference price at initialization: ${:.2}", price);
-                                            price
-                                        }
-                                        _ => {
-                                            tracing::error!("Failed to fetch reference price at initialization, retrying...");
-                                            continue;
-                                        }
-                                    };
+    /// Consumes stream updates until the stream errors or closes, the instance is killed, or the process shuts down.
+    ///
+    /// Shared by the single-pair loop (own Tycho stream) and the multi-pair runner (shared stream).
+    pub async fn consume<S>(&mut self, mut stream: S, atks: Vec<Token>, env: EnvConfig)
+    where
+        S: Stream<Item = Result<SharedUpdate, String>> + Unpin,
+    {
+        let mut last_publish = std::time::Instant::now() - std::time::Duration::from_millis(self.config.min_publish_timeframe_ms);
+        let mut last_poll = std::time::Instant::now() - std::time::Duration::from_millis(self.config.poll_interval_ms);
+        let mut last_depth = std::time::Instant::now() - std::time::Duration::from_millis(self.config.depth_report_interval_ms);
+        let mut last_pnl = std::time::Instant::now() - std::time::Duration::from_millis(self.config.pnl_report_interval_ms);
+        // Kept on the market maker across reconnects, put back once the stream ends
+        let mut pools = std::mem::take(&mut self.pools);
+        let mut previous_reference_price = 0.0;
+        let control = self.control.clone();
+        let shutdown = self.shutdown.clone();
+        // Until the stream is ready, the snapshot of the previous process (or connection) is evaluated monitor-only
+        if !self.ready {
+            if let Some(snapshot) = self.snapshot.restore(snapshot::now_s()) {
+                tracing::info!(
+                    "📸 Monitor-only on the snapshot of block {} ({} target pools) until the stream is ready",
+                    snapshot.block,
+                    snapshot.pools.len()
+                );
+            }
+        }
+        let mut degraded = tokio::time::interval(Duration::from_millis(SNAPSHOT_MONITOR_INTERVAL_MS));
+        // Updates received during an execution, read to revalidate its orders on the latest state, processed next
+        let mut pending: VecDeque<Option<Result<SharedUpdate, String>>> = VecDeque::new();
+        loop {
+            // Kill and shutdown are checked between blocks, an execution in progress completes and publishes first
+            if self.stopping() {
+                self.close();
+                break;
+            }
+            let next = match pending.pop_front() {
+                // Received during the previous execution, processed in order
+                Some(next) => next,
+                None => tokio::select! {
+                    next = stream.next() => next,
+                    _ = degraded.tick(), if !self.ready && self.snapshot.latest().is_some() => {
+                        self.monitor_only().await;
+                        continue;
+                    }
+                    _ = control.killed() => {
+                        self.close();
+                        break;
+                    }
+                    _ = shutdown.requested() => {
+                        self.close();
+                        break;
+                    }
+                },
+            };
+            match next {
+                Some(Ok(msg)) => {
+                    // Latency budget of the pipeline run on this update, from its receipt
+                    let deadline = Deadline::start(self.config.latency_budget(), self.clock.clone());
+                    let time = std::time::SystemTime::now();
+                    self.health.message(time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis());
+                    self.health.block(msg.block_number_or_timestamp);
+                    let intro = format!(
+                        "{} {} stream: b#{} with {} states", // , + {} pairs, - {} pairs",
+                        self.config.pair_tag,
+                        self.config.network_name.as_str(),
+                        msg.block_number_or_timestamp, // Changed from block_number in tycho-simulation 0.181.3
+                        msg.states.len()
+                    );
+                    // Quotes of the previous states are not reused
+                    self.sim_cache.roll(msg.block_number_or_timestamp);
+
+                    if !self.ready {
+                        tracing::info!("{}", intro);
+                        // --- First stream ---
+
+                        // Fetch reference price first for validation
+                        let reference_price = match self.market_data.market_price(self).await {
+                            Ok(price) if price > 0.0 => {
+                                tracing::info!("📊 Reference price at initialization: ${:.2}", price);
+                                price
+                            }
+                            _ => {
+                                tracing::error!("Failed to fetch reference price at initialization, retrying...");
+                                continue;
+                            }
+                        };
 
-                                    protosims = msg.states.clone();
-                                    let mut keys = vec![];
-                                    for (_id, comp) in msg.new_pairs.iter() {
-                                        keys.push(comp.id.to_string().to_lowercase());
+                        // Warm after a reconnect: the snapshot is applied as a diff on the pools of the previous connection
+                        let warm = !pools.is_empty();
+                        if warm {
+                            let diff = pools.resync(&msg);
+                            tracing::info!(
+                                "🔌 Resynced the {} components kept across the reconnect: {} added, {} updated, {} removed",
+                                pools.components.len(),
+                                diff.added,
+                                diff.updated,
+                                diff.removed
+                            );
+                        } else {
+                            pools.protosims = msg.states.clone();
+                        }
+                        let mut keys = vec![];
+                        for (_id, comp) in msg.new_pairs.iter() {
+                            keys.push(comp.id.to_string().to_lowercase());
+                        }
+                        let mut targets = 0;
+                        let mut filtered_out = 0;
+                        let mut excluded = 0;
+                        let mut target_components = vec![];
+
+                        for k in keys.clone() {
+                            if let Some(proto) = msg.states.get(&k.to_string()) {
+                                // Need to make sure protosim exists
+                                let comp = msg.new_pairs.get(&k.to_string()).expect("New pair not found");
+                                let symbols = comp.tokens.iter().map(|t| t.symbol.clone()).collect::<Vec<String>>();
+                                if !comp.id.to_string().contains(NULL_ADDRESS) {
+                                    if !warm {
+                                        pools.components.push(comp.clone());
                                     }
-                                    let mut targets = 0;
-                                    let mut filtered_out = 0;
-                                    let mut target_components = vec![];
-
-                                    for k in keys.clone() {
-                                        if let Some(proto) = msg.states.get(&k.to_string()) {
-                                            // Need to make sure protosim exists
-                                            let comp = msg.new_pairs.get(&k.to_string()).expect("New pair not found");
-                                            let symbols = comp.tokens.iter().map(|t| t.symbol.clone()).collect::<Vec<String>>();
-                                            if !comp.id.to_string().contains(NULL_ADDRESS) {
-                                                components.push(comp.clone());
-                                                // If the component contains both config tokens, add it to the monitored list
-                                                let tks = comp.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect::<Vec<String>>();
-                                                if tks.contains(&self.base.address.to_string().to_lowercase()) && tks.contains(&self.quote.address.to_string().to_lowercase()) {
-                                                    // Calculate spot price for this pool
-                                                    let token0 = comp.tokens[0].address.to_string().to_lowercase();
-                                                    let is0base = token0 == self.base.address.to_string().to_lowercase();
-
-                                                    let spot_price_result = if is0base {
-                                                        proto.spot_price(&comp.tokens[0], &comp.tokens[1])
-                                                    } else {
-                                                        proto.spot_price(&comp.tokens[1], &comp.tokens[0])
-                                                    };
-
-                                                    match spot_price_result {
-                                                        Ok(spot_price) => {
-                                                            let price_deviation = ((spot_price - reference_price).abs() / reference_price) * PERCENT_MULTIPLIER;
-
-                                                            if price_deviation <= MAX_POOL_PRICE_DEVIATION_PCT {
-                                                                targets += 1;
-                                                                target_components.push(comp.clone());
-                                                                tracing::debug!(
-                                                                    "✅ Adding pool: {} | Price: {:.5} | Deviation: {:.2}% | Tokens: {:?}",
-                                                                    cpname(comp.clone()),
-                                                                    spot_price,
-                                                                    price_deviation,
-                                                                    symbols
-                                                                );
-                                                            } else {
-                                                                filtered_out += 1;
-                                                                tracing::debug!(
-                                                                    "⚠️  Filtered out: {} | Price: {:.5} | Deviation: {:.2}% (>{:.1}%) | Tokens: {:?}",
-                                                                    cpname(comp.clone()),
-                                                                    spot_price,
-                                                                    price_deviation,
-                                                                    MAX_POOL_PRICE_DEVIATION_PCT,
-                                                                    symbols
-                                                                );
-                                                            }
-                                                        }
-                                                        Err(e) => {
-                                                            filtered_out += 1;
-                                                            tracing::debug!(" - ❌ Could not get spot price for {}: {:?}", cpname(comp.clone()), e);
-                                                        }
-                                                    }
+                                    // If the component contains both config tokens, add it to the monitored list
+                                    let tks = comp.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect::<Vec<String>>();
+                                    let holds_pair = tks.contains(&self.base.address.to_string().to_lowercase()) && tks.contains(&self.quote.address.to_string().to_lowercase());
+                                    let hook = excluded_v4_hook(&self.config, comp);
+                                    let selected = self.config.targets_pool(&k, holds_pair) && hook.is_none();
+                                    if let (true, Some(hook)) = (holds_pair, &hook) {
+                                        excluded += 1;
+                                        tracing::info!("🪝 Excluded Uniswap V4 pool with hook {}: {} | Tokens: {:?}", hook, cpname(comp.clone()), symbols);
+                                    } else if holds_pair && !selected {
+                                        excluded += 1;
+                                        tracing::info!("⛔ Excluded by pool allow/deny lists: {} | Tokens: {:?}", cpname(comp.clone()), symbols);
+                                    } else if selected && !self.config.pool_allowlist.is_empty() {
+                                        tracing::info!("📌 Included by pool_allowlist: {} | Tokens: {:?}", cpname(comp.clone()), symbols);
+                                    }
+                                    if selected {
+                                        // Calculate spot price for this pool, base and quote located among its tokens
+                                        let spot_price_result =
+                                            pair_indices(comp, &self.base, &self.quote).and_then(|(b, q)| proto.spot_price(&comp.tokens[b], &comp.tokens[q]).map_err(|e| e.to_string()));
+
+                                        match spot_price_result {
+                                            Ok(spot_price) => {
+                                                let price_deviation = ((spot_price - reference_price).abs() / reference_price) * PERCENT_MULTIPLIER;
+
+                                                if price_deviation <= MAX_POOL_PRICE_DEVIATION_PCT {
+                                                    targets += 1;
+                                                    target_components.push(comp.clone());
+                                                    tracing::debug!(
+                                                        "✅ Adding pool: {} | Price: {:.5} | Deviation: {:.2}% | Tokens: {:?}",
+                                                        cpname(comp.clone()),
+                                                        spot_price,
+                                                        price_deviation,
+                                                        symbols
+                                                    );
+                                                } else {
+                                                    filtered_out += 1;
+                                                    tracing::debug!(
+                                                        "⚠️  Filtered out: {} | Price: {:.5} | Deviation: {:.2}% (>{:.1}%) | Tokens: {:?}",
+                                                        cpname(comp.clone()),
+                                                        spot_price,
+                                                        price_deviation,
+                                                        MAX_POOL_PRICE_DEVIATION_PCT,
+                                                        symbols
+                                                    );
                                                 }
                                             }
+                                            Err(e) => {
+                                                filtered_out += 1;
+                                                tracing::debug!(" - ❌ Could not get spot price for {}: {:?}", cpname(comp.clone()), e);
+                                            }
                                         }
                                     }
-                                    self.ready = true;
-                                    tracing::info!(
-                                        "✅ ProtocolStreamBuilder initialised successfully. Monitoring {} targets (filtered {} outside {:.1}% range) on {} total components\n",
-                                        targets,
-                                        filtered_out,
-                                        MAX_POOL_PRICE_DEVIATION_PCT,
-                                        components.len()
-                                    );
-                                } else {
-                                    // --- Update protosims ---
-                                    if !msg.states.is_empty() {
-                                        for x in msg.states.iter() {
-                                            protosims.insert(x.0.clone().to_lowercase(), x.1.clone());
-                                        }
-                                    }
-                                    // --- Update new pairs (add or overwrite) ---
-                                    for x in msg.new_pairs.iter() {
-                                        if let Some(pos) = components.iter().position(|current| current.id.to_string().to_lowercase() == x.0.to_string().to_lowercase()) {
-                                            components[pos] = x.1.clone();
-                                        } else {
-                                            components.push(x.1.clone());
-                                        }
+                                }
+                            }
+                        }
+                        for id in self.config.pool_allowlist.iter() {
+                            if !target_components.iter().any(|c| c.id.to_string().to_lowercase() == id.to_lowercase()) {
+                                tracing::warn!(
+                                    "📌 pool_allowlist entry {} not monitored at startup: missing from the stream, not a {}/{} pool or outside the price range",
+                                    id,
+                                    self.base.symbol,
+                                    self.quote.symbol
+                                );
+                            }
+                        }
+                        if !warm {
+                            pools.graph = TokenGraph::new(&pools.components, &pools.protosims);
+                        }
+                        if !self.journal.is_loaded() {
+                            let entries = journal::load(&self.journal.load_keys()).await;
+                            self.journal.restore(entries, msg.block_number_or_timestamp);
+                            for entry in self.journal.restored() {
+                                tracing::warn!(
+                                    "📓 Traded by the previous process at block {}: {} ({:?}), skipped for {} blocks",
+                                    entry.block,
+                                    entry.component,
+                                    entry.direction,
+                                    self.config.pool_cooldown_blocks
+                                );
+                            }
+                        }
+                        self.ready = true;
+                        self.health.set_ready(true);
+                        if let Some(elapsed) = self.reconnect.ready(std::time::Instant::now()) {
+                            tracing::info!("🔌 Stream ready again {} ms after the disconnect", elapsed.as_millis());
+                            self.health.recovered(elapsed.as_millis() as u64);
+                        }
+                        tracing::info!(
+                            "✅ ProtocolStreamBuilder initialised successfully. Monitoring {} targets (filtered {} outside {:.1}% range, {} excluded by pool lists) on {} total components\n",
+                            targets,
+                            filtered_out,
+                            MAX_POOL_PRICE_DEVIATION_PCT,
+                            excluded,
+                            pools.components.len()
+                        );
+                    } else {
+                        // --- Update protosims, new pairs (add or overwrite) and old pairs ---
+                        for id in msg.states.keys() {
+                            self.cooldown.observe(id, msg.block_number_or_timestamp);
+                        }
+                        self.cooldown.prune(msg.block_number_or_timestamp);
+                        for id in msg.new_pairs.keys() {
+                            self.tombstones.revive(id);
+                        }
+                        for id in msg.removed_pairs.keys() {
+                            tracing::info!("🪦 Component {} removed from the stream at block {}", id, msg.block_number_or_timestamp);
+                            self.tombstones.bury(id, msg.block_number_or_timestamp);
+                        }
+                        self.tombstones.prune(msg.block_number_or_timestamp);
+                        pools.apply(&msg);
+
+                        // Targets = components with both tokens, to monitor
+                        // Components = all components, used to find route, pricing, etc.
+                        let mut targets = vec![];
+                        for cp in pools.components.iter() {
+                            let tks = cp.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect::<Vec<String>>();
+                            let holds_pair = tks.contains(&self.base.address.to_string().to_lowercase()) && tks.contains(&self.quote.address.to_string().to_lowercase());
+                            if self.config.targets_pool(&cp.id.to_string(), holds_pair) && excluded_v4_hook(&self.config, cp).is_none() {
+                                let id = cp.id.to_string().to_lowercase();
+                                match pools.protosims.get(&id) {
+                                    Some(protosim) => {
+                                        targets.push(ProtoSimComp {
+                                            component: cp.clone(),
+                                            protosim: protosim.clone(),
+                                        });
                                     }
-                                    // --- Remove old pairs ---
-                                    for x in msg.removed_pairs.iter() {
-                                        if let Some(pos) = components.iter().position(|current| current.id.to_string().to_lowercase() == x.0.to_string().to_lowercase()) {
-                                            components.swap_remove(pos);
-                                        }
+                                    None => {
+                                        tracing::error!("contains: couldn't find protosim for component {}", cp.id);
                                     }
+                                }
+                            }
+                        }
 
-                                    // Targets = components with both tokens, to monitor
-                                    // Components = all components, used to find route, pricing, etc.
-                                    let mut targets = vec![];
-                                    for cp in components.iter() {
-                                        let tks = cp.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect::<Vec<String>>();
-                                        if tks.contains(&self.base.address.to_string().to_lowercase()) && tks.contains(&self.quote.address.to_string().to_lowercase()) {
-                                            let id = cp.id.to_string().to_lowercase();
-                                            match protosims.get(&id) {
-                                                Some(protosim) => {
-                                                    targets.push(ProtoSimComp {
-                                                        component: cp.clone(),
-                                                        protosim: protosim.clone(),
-                                                    });
-                                                }
-                                                None => {
-                                                    tracing::error!("contains: couldn't find protosim for component {}", cp.id);
-                                                }
-                                            }
-                                        }
-                                    }
+                        // Use poll_interval_ms here to avoid spamming the RPC, DB, etc
+                        // Only continue if the poll_interval_ms has passed
+                        let now = std::time::Instant::now();
+                        if (now.duration_since(last_poll).as_millis() as u64) < self.config.poll_interval_ms {
+                            // tracing::debug!("{} | ⏩  Skipping block update: poll_interval_ms not elapsed", intro);
+                            tokio::time::sleep(tokio::time::Duration::from_millis(self.config.poll_interval_ms)).await;
+                            continue;
+                        }
+                        last_poll = now;
+                        // Parent of the stages of this block (evaluate, readjust, encode, then the `trade` execution)
+                        let block = tracing::info_span!(
+                            "block",
+                            block = msg.block_number_or_timestamp,
+                            network = self.config.network_name.as_str(),
+                            pair = %self.config.pair_tag
+                        );
+
+                        // --- Stream lag behind the chain head, sampled every `stream_lag_sample_every` polled blocks ---
+                        if self.lag.due() {
+                            let head = match self.fresh_head() {
+                                Some(head) => head.number,
+                                None => crate::utils::evm::latest(&RpcPool::of(&self.config)).await,
+                            };
+                            match self.lag.observe(msg.block_number_or_timestamp, head) {
+                                Some(LagTransition::Lagging(lag)) => tracing::warn!(
+                                    "{} | 🐢 Stream lagging {} blocks behind the chain head (max {}), order creation suppressed",
+                                    intro,
+                                    lag,
+                                    self.config.max_stream_lag_blocks
+                                ),
+                                Some(LagTransition::Recovered(lag)) => {
+                                    tracing::info!("{} | 🐇 Stream back to {} blocks behind the chain head, order creation resumed", intro, lag)
+                                }
+                                None => {}
+                            }
+                            self.health.lag(self.lag.lag_blocks());
+                        }
+
+                        if control.take_refresh() {
+                            if let Err(e) = self.force_refresh_inventory(env.clone()).await {
+                                tracing::warn!("Failed to refresh inventory: {}", e);
+                            }
+                        }
+
+                        if let Ok(reference_price) = self.market_data.market_price(self).instrument(block.clone()).await {
+                            let sigma_bps = self.volatility.update(reference_price);
+                            let execution_threshold_bps = self.execution_threshold_bps();
+                            tracing::info!(
+                                "{} | 🌪️  Volatility: {:.2} bps | Execution threshold: {:.2} bps (static {:.2} bps)",
+                                intro,
+                                sigma_bps,
+                                execution_threshold_bps,
+                                self.config.min_executable_spread_bps
+                            );
+                            let cpds = self.engine.prices(self, &targets, reference_price);
+                            // Unpriced targets are left out, the spot prices being evaluated by index
+                            targets.retain(|psc| cpds.iter().any(|cpd| cpd.address == psc.component.id.to_string().to_lowercase()));
+                            if self.snapshot.due(std::time::Instant::now()) {
+                                let mut snapshot = PoolSnapshot::new(&self.config, msg.block_number_or_timestamp, reference_price, &targets, &cpds);
+                                snapshot.adaptive = self.config.adaptive_threshold.then(|| self.adaptive.state());
+                                self.snapshot.record(snapshot, std::time::Instant::now());
+                            }
+                            let identifier = self.identifier.clone();
+                            // --- Sanity band ---
+                            for notice in self.quarantine.observe(&cpds, reference_price, msg.block_number_or_timestamp, std::time::Instant::now()) {
+                                tracing::warn!(
+                                    "{} | 🧪 Pool {} quarantined for {} blocks: spot {:.0} bps away from the reference (max {} bps)",
+                                    intro,
+                                    notice.component,
+                                    notice.blocks,
+                                    notice.spread_bps,
+                                    self.config.max_plausible_spread_bps
+                                );
+                                if notice.alert {
+                                    self.alert(
+                                        AlertKind::PoolQuarantined,
+                                        format!(
+                                            "Pool {} quarantined for {} blocks, spot {:.0} bps away from the reference",
+                                            notice.component, notice.blocks, notice.spread_bps
+                                        ),
+                                        None,
+                                    );
+                                }
+                            }
+                            // --- Price move evaluation ---
+                            let price_move_bps = if previous_reference_price != 0.0 {
+                                ((reference_price - previous_reference_price).abs() / previous_reference_price) * BASIS_POINT_DENO
+                            } else {
+                                // First run - always push to DB since we have no previous price
+                                tracing::info!("First run - always push to DB since we have no previous price");
+                                self.config.min_reference_price_move_bps + 1.0
+                            };
 
-                                    // Use poll_interval_ms here to avoid spamming the RPC, DB, etc
-                                    // Only continue if the poll_interval_ms has passed
+                            // ===== Publish Price event =====
+                            let threshold = price_move_bps > self.config.min_reference_price_move_bps;
+
+                            tracing::info!(
+                                "{} | Price movement {} threshold ({} bps), of {:.2} bps, from {} to {}",
+                                intro,
+                                if threshold { "above" } else { "below" },
+                                self.config.min_reference_price_move_bps,
+                                price_move_bps,
+                                previous_reference_price,
+                                reference_price
+                            );
+
+                            // ===== Realized PnL =====
+                            let pnl = self.pnl();
+                            tracing::info!(
+                                "{} | 💰 PnL: {:+.2} $ over {} trades (gas {:.2} $) | Rolling {}h: {:+.2} $ over {} trades",
+                                intro,
+                                pnl.cumulative_usd,
+                                pnl.trades,
+                                pnl.cumulative_gas_usd,
+                                pnl.window_ms / 3_600_000,
+                                pnl.rolling_usd,
+                                pnl.rolling_trades
+                            );
+                            if self.config.publish_events && self.config.pnl_report_interval_ms > 0 {
+                                let now = std::time::Instant::now();
+                                if now.duration_since(last_pnl).as_millis() as u64 >= self.config.pnl_report_interval_ms {
+                                    let _ = crate::data::r#pub::pnl(NewPnlMessage {
+                                        identifier: identifier.clone(),
+                                        pnl: pnl.clone(),
+                                    });
+                                    last_pnl = now;
+                                }
+                            }
+
+                            // ===== Circuit breaker =====
+                            self.guard(&identifier, &pnl).await;
+                            self.health.breaker(self.breaker.is_open());
+
+                            if threshold {
+                                if self.config.publish_events {
                                     let now = std::time::Instant::now();
-                                    if (now.duration_since(last_poll).as_millis() as u64) < self.config.poll_interval_ms {
-                                        // tracing::debug!("{} | ⏩  Skipping block update: poll_interval_ms not elapsed", intro);
-                                        tokio::time::sleep(tokio::time::Duration::from_millis(self.config.poll_interval_ms)).await;
-                                        continue;
-                                    }
-                                    last_poll = now;
-
-                                    if let Ok(reference_price) = self.fetch_market_price().await {
-                                        let cpds = self.prices(&targets);
-                                        let identifier = self.identifier.clone();
-                                        // --- Price move evaluation ---
-                                        let price_move_bps = if previous_reference_price != 0.0 {
-                                            ((reference_price - previous_reference_price).abs() / previous_reference_price) * BASIS_POINT_DENO
+                                    if now.duration_since(last_publish).as_millis() as u64 >= self.config.min_publish_timeframe_ms {
+                                        // Depth ladder is heavier (market context + simulations), so it has its own interval
+                                        let depth = if self.config.depth_report_interval_ms > 0 && now.duration_since(last_depth).as_millis() as u64 >= self.config.depth_report_interval_ms {
+                                            last_depth = now;
+                                            self.depth(&targets, &pools.graph, &pools.protosims, atks.clone()).await
                                         } else {
-                                            // First run - always push to DB since we have no previous price
-                                            tracing::info!("First run - always push to DB since we have no previous price");
-                                            self.config.min_reference_price_move_bps + 1.0
+                                            None
                                         };
+                                        let _ = crate::data::r#pub::prices(NewPricesMessage {
+                                            identifier: identifier.clone(),
+                                            reference_price,
+                                            components: cpds.clone(),
+                                            block: msg.block_number_or_timestamp, // Changed from block_number in tycho-simulation 0.181.3
+                                            depth,
+                                            state: self.control.state(),
+                                            sigma_bps,
+                                            execution_threshold_bps,
+                                            adaptive_bps: self.adaptive.widening_bps(),
+                                            quarantined: self.quarantine.len(),
+                                            stream_lag_blocks: self.lag.lag_blocks(),
+                                            degraded: false,
+                                        });
+                                        last_publish = now;
+                                    } else {
+                                        tracing::debug!("{} | Skipping publish: min_publish_timeframe_ms not elapsed", intro);
+                                    }
+                                }
+                                previous_reference_price = reference_price;
+                            }
 
-                                        // ===== Publish Price event =====
-                                        let threshold = price_move_bps > self.config.min_reference_price_move_bps;
-
-                                        tracing::info!(
-                                            "{} | Price movement {} threshold ({} bps), of {:.2} bps, from {} to {}",
-                                            intro,
-                                            if threshold { "above" } else { "below" },
-                                            self.config.min_reference_price_move_bps,
-                                            price_move_bps,
-                                            previous_reference_price,
-                                            reference_price
-                                        );
+                            // Publishing is gated on the reference price alone, a pool dislocated on-chain with a stable reference is still evaluated
+                            let pools_moved = self.pools_moved(&targets, &cpds);
+                            if !threshold && !pools_moved {
+                                continue;
+                            }
+                            if !threshold {
+                                tracing::info!("{} | Reference price stable, evaluating pools moved since their last evaluation", intro);
+                            }
 
-                                        if threshold {
-                                            if self.config.publish_events {
-                                                let now = std::time::Instant::now();
-                                                if now.duration_since(last_publish).as_millis() as u64 >= self.config.min_publish_timeframe_ms {
-                                                    let _ = crate::data::r#pub::prices(NewPricesMessage {
-                                                        identifier: identifier.clone(),
-                                                        reference_price,
-                                                        components: cpds.clone(),
-                                                        block: msg.block_number_or_timestamp, // Changed from block_number in tycho-simulation 0.181.3
-                                                    });
-                                                    last_publish = now;
-                                                } else {
-                                                    tracing::debug!("{} | Skipping publish: min_publish_timeframe_ms not elapsed", intro);
-                                                }
-                                            }
-                                            previous_reference_price = reference_price;
-                                        } else {
-                                            continue;
-                                        }
+                            if self.control.is_paused() {
+                                tracing::info!("{} | ⏸️  Paused by operator command, skipping evaluation", intro);
+                                continue;
+                            }
+                            if self.lag.is_lagging() {
+                                tracing::debug!("{} | 🐢 Stream lagging, skipping evaluation", intro);
+                                continue;
+                            }
+                            if self.watcher.due(std::time::Instant::now()) {
+                                self.watch_balances(env.clone()).instrument(block.clone()).await;
+                            }
+                            if self.watcher.take_pause() {
+                                tracing::info!("{} | 👀 External balance movement, skipping evaluation", intro);
+                                continue;
+                            }
 
-                                        // --- Evaluate ---
-                                        let spot_prices = cpds.iter().map(|x| x.price).collect::<Vec<f64>>();
-                                        let readjusments = self.evaluate(&targets, spot_prices, reference_price);
-                                        if readjusments.is_empty() {
+                            // --- Evaluate ---
+                            let spot_prices = cpds.iter().map(|x| x.price).collect::<Vec<f64>>();
+                            // Skew needs the market context and inventory, fetched ahead only when enabled, then reused below
+                            // Past the latency budget the skew stays neutral, the readjustments are then aborted below
+                            let mut prefetched = None;
+                            let skew = if self.config.skew_gain_bps_per_pct > 0. && deadline.check(Stage::Context).is_ok() {
+                                let context = self.market_data.market_context(self, &pools.graph, &pools.protosims, atks.clone()).instrument(block.clone()).await;
+                                let inventory = match deadline.check(Stage::Inventory) {
+                                    Ok(()) => self.inventory(env.clone()).instrument(block.clone()).await,
+                                    Err(e) => Err(e.to_string()),
+                                };
+                                match (context, inventory) {
+                                    (Some(context), Ok(inventory)) => {
+                                        let skew = skew::compute(
+                                            &self.effective_inventory(&inventory, &context),
+                                            &self.base,
+                                            &self.quote,
+                                            &context,
+                                            self.config.target_inventory_ratio,
+                                            self.config.skew_gain_bps_per_pct,
+                                            self.config.min_watch_spread_bps,
+                                        );
+                                        prefetched = Some((context, inventory));
+                                        skew
+                                    }
+                                    _ => {
+                                        tracing::warn!("Failed to compute inventory skew, using symmetric thresholds");
+                                        InventorySkew::neutral(self.config.min_watch_spread_bps, self.config.target_inventory_ratio)
+                                    }
+                                }
+                            } else {
+                                InventorySkew::neutral(self.config.min_watch_spread_bps, self.config.target_inventory_ratio)
+                            };
+                            skew.print();
+                            self.evaluated_spots = cpds.iter().map(|cpd| (cpd.address.clone(), cpd.price)).collect();
+                            let readjusments = block.in_scope(|| self.engine.evaluate(self, &targets, spot_prices, reference_price, &skew, msg.block_number_or_timestamp));
+                            if readjusments.is_empty() {
+                                continue;
+                            }
+                            if self.breaker.is_open() {
+                                for adjustment in readjusments.iter() {
+                                    let values = [("max_daily_loss_usd", self.config.max_daily_loss_usd)];
+                                    self.decide(
+                                        msg.block_number_or_timestamp,
+                                        &adjustment.psc,
+                                        adjustment.spot,
+                                        adjustment.reference,
+                                        Some(Gate::CircuitBreaker),
+                                        &values,
+                                    );
+                                }
+                            }
+                            let readjusments = self.breaker.gate(readjusments);
+                            if readjusments.is_empty() {
+                                tracing::warn!("{} | 🚨 Circuit breaker open, skipping readjustments", intro);
+                                continue;
+                            }
+                            // Already checked when prefetched for the skew
+                            if prefetched.is_none() {
+                                if let Err(e) = deadline.check(Stage::Context) {
+                                    self.time_out_readjustments(msg.block_number_or_timestamp, &readjusments, &deadline, &e);
+                                    continue;
+                                }
+                            }
+                            let context = match prefetched.as_ref() {
+                                Some((context, _)) => Some(context.clone()),
+                                None => self.market_data.market_context(self, &pools.graph, &pools.protosims, atks.clone()).instrument(block.clone()).await,
+                            };
+                            match context {
+                                Some(context) => {
+                                    context.print();
+                                    if prefetched.is_none() {
+                                        if let Err(e) = deadline.check(Stage::Inventory) {
+                                            self.time_out_readjustments(msg.block_number_or_timestamp, &readjusments, &deadline, &e);
                                             continue;
                                         }
-                                        match self.fetch_market_context(components.clone(), &protosims, atks.clone()).await {
-                                            Some(context) => {
-                                                context.print();
-                                                match self.fetch_inventory(env.clone()).await {
-                                                    Ok(inventory) => {
-                                                        let elapsed = time.elapsed().unwrap_or_default().as_millis();
-                                                        let mut orders = self.readjust(context.clone(), inventory.clone(), readjusments, env.clone()).await;
-                                                        tracing::info!("Elapsed from block_update to readjustments: {} ms", elapsed);
-
-                                                        if orders.is_empty() {
+                                    }
+                                    let inventory = match prefetched.take() {
+                                        Some((_, inventory)) => Ok(inventory),
+                                        None => self.inventory(env.clone()).instrument(block.clone()).await,
+                                    };
+                                    match inventory {
+                                        Ok(inventory) => {
+                                            let balances = TychoBalances {
+                                                config: self.config.clone(),
+                                                key: env.tycho_api_key.clone(),
+                                                cache: self.pool_balances.clone(),
+                                            };
+                                            let mut orders = self.engine.readjust(self, context.clone(), inventory.clone(), readjusments, &balances).instrument(block.clone()).await;
+
+                                            if orders.is_empty() {
+                                                continue;
+                                            }
+                                            orders.sort_by(|a, b| b.calculation.profit_delta_bps.partial_cmp(&a.calculation.profit_delta_bps).unwrap_or(std::cmp::Ordering::Equal));
+                                            let orders = match orders.first() {
+                                                Some(order) => vec![order.clone()],
+                                                None => continue,
+                                            };
+                                            // Pairs sharing a wallet execute one at a time, with the nonce read under the lock
+                                            // A pair alone on its wallet is the only sender, its cached nonce is kept up to date
+                                            let shared = self.shares_wallet();
+                                            let wallet = self.nonce_lock.clone();
+                                            let _guard = wallet.lock().await;
+                                            let mut inventory = inventory;
+                                            if shared {
+                                                match self.fetch_nonce().await {
+                                                    Ok(nonce) => inventory.nonce = nonce,
+                                                    Err(e) => {
+                                                        tracing::warn!("Failed to refresh nonce: {}", e);
+                                                        continue;
+                                                    }
+                                                }
+                                            }
+                                            // Removed from the stream while the orders were built, or in the blocks before
+                                            let orders = self.drop_removed(orders, msg.block_number_or_timestamp);
+                                            if orders.is_empty() {
+                                                continue;
+                                            }
+                                            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+                                            let tdata = orders
+                                                .iter()
+                                                .map(|order| TradeData {
+                                                    trade_id: order.trade_id.clone(),
+                                                    status: TradeStatus::Pending,
+                                                    timestamp: now,
+                                                    context: context.clone(),
+                                                    metadata: self.pre_trade_data(order),
+                                                    inventory: inventory.clone(),
+                                                    simulation: None,
+                                                    broadcast: None,
+                                                    realized: None,
+                                                })
+                                                .collect::<Vec<TradeData>>();
+                                            if let Err(e) = deadline.check(Stage::Prepare) {
+                                                // Not encoded yet, published with their order data only
+                                                let aborted = tdata
+                                                    .into_iter()
+                                                    .map(|metadata| {
+                                                        let mut trade = Trade {
+                                                            wrap: None,
+                                                            approve: None,
+                                                            swap: TransactionRequest::default(),
+                                                            unwrap: None,
+                                                            metadata,
+                                                        };
+                                                        time_out(&mut trade, &e);
+                                                        trade
+                                                    })
+                                                    .collect::<Vec<Trade>>();
+                                                self.execution.post_hook(&self.config, aborted, self.identifier.clone()).await;
+                                                continue;
+                                            }
+                                            let permits = if self.config.permit2_approval {
+                                                self.permits(&orders, &env).instrument(block.clone()).await
+                                            } else {
+                                                HashMap::new()
+                                            };
+                                            let trades = block.in_scope(|| self.executor.prepare(self, orders.clone(), tdata.clone(), context.clone(), inventory.clone(), &permits));
+                                            // Arbed or left behind by the chain head during the context, inventory and encoding
+                                            // A frozen context pins the block of its fixture, the head is then the stream's own
+                                            let head = match self.frozen {
+                                                Some(_) => msg.block_number_or_timestamp,
+                                                None => self.fresh_head().map(|head| head.number).unwrap_or(context.block),
+                                            };
+                                            // Requoted on the latest state: the updates already received since this one, read without waiting
+                                            // A stream already ended or errored is not read again
+                                            let mut ended = pending.back().is_some_and(|next| !matches!(next, Some(Ok(_))));
+                                            while !ended {
+                                                let Some(next) = stream.next().now_or_never() else {
+                                                    break;
+                                                };
+                                                ended = !matches!(next, Some(Ok(_)));
+                                                pending.push_back(next);
+                                            }
+                                            let updates = pending.iter().filter_map(|next| next.as_ref().and_then(|next| next.as_ref().ok())).collect::<Vec<&SharedUpdate>>();
+                                            let ids = orders.iter().map(|order| order.adjustment.psc.component.id.to_string()).collect::<Vec<String>>();
+                                            let latest = pools.latest(&ids, &updates);
+                                            let state_block = updates.last().map(|update| update.block_number_or_timestamp).unwrap_or(msg.block_number_or_timestamp);
+                                            let (trades, orders) = self.drop_stale(trades, orders, &latest, state_block, head).instrument(block.clone()).await;
+                                            if trades.is_empty() {
+                                                continue;
+                                            }
+                                            // Refuse to execute without enough native balance for gas (orphaned approvals, burned nonces), wrapped native included
+                                            if let Err(e) = inventory.preflight(self.config.min_native_balance_wei, projected_gas_wei(&trades).saturating_add(wrapped_wei(&trades))) {
+                                                tracing::warn!("⛽ Preflight failed, not executing: {}", e);
+                                                self.alert(AlertKind::LowNativeBalance, e, None);
+                                                continue;
+                                            }
+                                            match self.executor.execute(self, trades.clone(), env.clone(), &deadline).instrument(block.clone()).await {
+                                                Ok(results) => {
+                                                    tracing::info!("Executed {} transactions successfully", results.len());
+                                                    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+                                                    let mut journaled = None;
+                                                    let mut watch = false;
+                                                    if !results.is_empty() {
+                                                        self.health.traded(now as u64);
+                                                    }
+                                                    for (trade, order) in results.iter().zip(orders.iter()) {
+                                                        let broadcast = trade.metadata.broadcast.as_ref();
+                                                        // Not broadcast by the dry run: the inventory, allowances and journal are unchanged
+                                                        if broadcast.is_some_and(|b| b.dry_run) {
                                                             continue;
                                                         }
-                                                        orders.sort_by(|a, b| b.calculation.profit_delta_bps.partial_cmp(&a.calculation.profit_delta_bps).unwrap_or(std::cmp::Ordering::Equal));
-                                                        let orders = match orders.first() {
-                                                            Some(order) => vec![order.clone()],
-                                                            None => continue,
-                                                        };
-                                                        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
-                                                        let tdata = orders
-                                                            .iter()
-                                                            .map(|order| TradeData {
-                                                                status: TradeStatus::Pending,
-                                                                timestamp: now,
-                                                                context: context.clone(),
-                                                                metadata: self.pre_trade_data(order),
-                                                                inventory: inventory.clone(),
-                                                                simulation: None,
-                                                                broadcast: None,
-                                                            })
-                                                            .collect::<Vec<TradeData>>();
-                                                        let trades = self.prepare(orders.clone(), tdata.clone(), context.clone(), inventory.clone(), env.clone());
-                                                        match self.execution.execute(self.config.clone(), trades.clone(), env.clone(), self.identifier.clone()).await {
-                                                            Ok(results) => {
-                                                                tracing::info!("Elapsed from block_update to execution: {} ms", elapsed);
-                                                                tracing::info!("Executed {} transactions successfully", results.len());
+                                                        let receipt = broadcast.and_then(|b| b.receipt.as_ref());
+                                                        // Optimistic inventory update, refetched from chain when the outcome is unknown
+                                                        if let Some(error) = broadcast.and_then(|b| b.error()) {
+                                                            if let ExecError::NonceConflict(_) = error {
+                                                                tracing::warn!("Cached nonce {} rejected ({}), refetching inventory", inventory.nonce, error);
+                                                            }
+                                                            // Failed trade of a broadcast that went on with the others, e.g. a bundle rejected by every builder
+                                                            if error.policy() == ExecPolicy::Alert {
+                                                                self.alert(AlertKind::ExecutionFailed, format!("Broadcast of trade {} failed: {}", trade.metadata.trade_id, error), None);
                                                             }
-                                                            Err(e) => {
-                                                                tracing::error!("Execution failed: {}", e);
+                                                            if trade.request().calls().len() > 1 {
+                                                                // A wrap or unwrap may have landed before the failure
+                                                                self.inventory.invalidate();
                                                             }
+                                                            // Nothing else of ours moved the balances, the chain must still match the cache
+                                                            watch = true;
+                                                        } else if trade.request().calls().len() > 1 {
+                                                            // Approval, wrap and unwrap are not in the swap receipt
+                                                            self.inventory.invalidate();
+                                                        } else {
+                                                            self.inventory.settle(order, receipt, &self.config.wallet_public_key);
+                                                        }
+                                                        self.adapt_threshold(&order.calculation, trade.metadata.realized.as_ref());
+                                                        let succeeded = receipt.is_some_and(|r| r.status);
+                                                        // Pulled by the router as its receipt transfers show, the amountIn sent otherwise
+                                                        let spent = receipt.map(|r| order.executed(r, &self.config.wallet_public_key).0).unwrap_or_else(|| Self::router_input(order));
+                                                        self.allowances.settle(&order.adjustment.selling.address.to_string(), spent, trade.approve.is_some(), succeeded);
+                                                        journaled = self.record_traded(trade, order, context.block).or(journaled);
+                                                        if let Some(entry) = self.pnl.record_trade(order, receipt, &self.config.wallet_public_key, &context, now) {
+                                                            tracing::info!(
+                                                                "💰 Realized PnL: {:+.2} $ (sold {:.2} $, bought {:.2} $, gas {:.2} $)",
+                                                                entry.pnl_usd,
+                                                                entry.sold_usd,
+                                                                entry.bought_usd,
+                                                                entry.gas_usd
+                                                            );
                                                         }
                                                     }
-                                                    Err(e) => {
-                                                        tracing::warn!("Failed to get inventory: {:?}", e);
-                                                        continue;
+                                                    if let Some(entries) = journaled {
+                                                        journal::persist(self.journal.key().to_string(), entries);
+                                                    }
+                                                    if watch {
+                                                        self.watch_balances(env.clone()).instrument(block.clone()).await;
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    if trades.iter().any(|t| t.request().calls().len() > 1) {
+                                                        self.inventory.invalidate();
+                                                    }
+                                                    self.watch_balances(env.clone()).instrument(block.clone()).await;
+                                                    match e.policy() {
+                                                        // Transient, the next block retries with a fresh state and nonce
+                                                        ExecPolicy::Retry => tracing::warn!("Execution failed, retrying next block: {}", e),
+                                                        ExecPolicy::Alert => {
+                                                            tracing::error!("Execution failed: {}", e);
+                                                            self.alert(AlertKind::ExecutionFailed, format!("Execution failed: {}", e), None);
+                                                        }
+                                                        // The trades themselves are broken, nothing an operator can act on
+                                                        ExecPolicy::Abort => tracing::error!("Execution aborted: {}", e),
                                                     }
                                                 }
-                                            }
-                                            None => {
-                                                tracing::warn!("Failed to get market context");
                                             }
                                         }
-                                    } else {
-                                        tracing::error!("Failed to fetch market price");
-                                        continue;
+                                        Err(e) => {
+                                            tracing::warn!("Failed to get inventory: {:?}", e);
+                                            continue;
+                                        }
                                     }
                                 }
+                                None => {
+                                    tracing::warn!("Failed to get market context");
+                                }
                             }
-                            Err(e) => {
-                                tracing::warn!("Stream error: {:?}", e);
-                                break;
-                            }
-                        },
-                        None => {
-                            tracing::warn!("Stream closed. Retrying...");
-                            // Sleep for 1 second
-                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                            break;
+                        } else {
+                            tracing::error!("Failed to fetch market price");
+                            continue;
                         }
                     }
-                },
-                Err(e) => {
-                    tracing::warn!("Failed to build stream on {}: {:?}. Exiting.", self.config.network_name.as_str().to_string(), e.to_string());
-                    return;
                 }
-            };
+                Some(Err(e)) => {
+                    tracing::warn!("Stream error: {}", e);
+                    self.health.set_ready(false);
+                    self.alert(AlertKind::StreamReconnect, format!("Stream error, reconnecting: {}", e), None);
+                    break;
+                }
+                None => {
+                    tracing::warn!("Stream closed. Retrying...");
+                    self.health.set_ready(false);
+                    self.alert(AlertKind::StreamReconnect, "Stream closed, reconnecting".to_string(), None);
+                    break;
+                }
+            }
         }
+        self.pools = pools;
     }
 }
@@ -0,0 +1,59 @@
+//! Inventory Cache Module
+//!
+//! Balances and nonce are read from chain once, then kept up to date from our own
+//! executions, so the readjustment hot path does not wait on RPC calls. The cache is
+//! refetched on an interval, and whenever the outcome of a trade is not known for sure.
+use std::time::{Duration, Instant};
+
+use crate::types::maker::{ExecutionOrder, Inventory, ReceiptData};
+
+/// Wallet inventory cached by the market maker, kept across iterations and reconnections.
+#[derive(Debug, Clone, Default)]
+pub struct InventoryCache {
+    interval_ms: u64,                    // 0 disables the cache
+    entry: Option<(Inventory, Instant)>, // Inventory and the time it was read from chain
+}
+
+impl InventoryCache {
+    pub fn new(interval_ms: u64) -> Self {
+        Self { interval_ms, entry: None }
+    }
+
+    /// Returns the cached inventory, unless it is missing or was read from chain more than the interval ago.
+    pub fn fresh(&self, now: Instant) -> Option<&Inventory> {
+        let (inventory, fetched_at) = self.entry.as_ref()?;
+        (now.saturating_duration_since(*fetched_at) < Duration::from_millis(self.interval_ms)).then_some(inventory)
+    }
+
+    /// Stores an inventory read from chain.
+    pub fn store(&mut self, inventory: Inventory, now: Instant) {
+        self.entry = Some((inventory, now));
+    }
+
+    /// Returns the cached inventory whatever its age.
+    pub fn cached(&self) -> Option<&Inventory> {
+        self.entry.as_ref().map(|(inventory, _)| inventory)
+    }
+
+    /// Settles an executed trade on the cached inventory, without resetting its age.
+    ///
+    /// Drops the cache when the trade outcome is unknown (no receipt).
+    pub fn settle(&mut self, order: &ExecutionOrder, receipt: Option<&ReceiptData>, wallet: &str) {
+        let settled = match self.entry.as_mut() {
+            Some((inventory, _)) => inventory.settle(order, receipt, wallet),
+            None => return,
+        };
+        if !settled {
+            self.invalidate();
+        }
+    }
+
+    /// Drops the cached inventory, the next read goes to chain.
+    pub fn invalidate(&mut self) {
+        self.entry = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry.is_none()
+    }
+}
@@ -0,0 +1,111 @@
+//! Trade Journal Module
+//!
+//! The pool cooldown lives in memory and is lost when the process restarts, while the
+//! first blocks after a restart can still show the dislocation we just traded. Recent trades
+//! are therefore journaled in Redis, and the entries of the previous process are restored
+//! at startup: their components are skipped until `pool_cooldown_blocks` have elapsed since
+//! the trade. Entries expire with their window, and the Redis key with `TRADE_JOURNAL_TTL_SECS`.
+//! In compatibility mode, the journal is restored from the legacy key when the versioned one is missing.
+use serde::{Deserialize, Serialize};
+
+use crate::data::keys;
+use crate::types::maker::TradeDirection;
+use crate::utils::constants::TRADE_JOURNAL_TTL_SECS;
+
+/// One recent trade on a component.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalEntry {
+    pub component: String, // Component id (lowercase)
+    pub direction: TradeDirection,
+    pub block: u64,  // Inclusion block, or the block it was sent at if unknown
+    pub amount: f64, // Selling amount (normalized)
+}
+
+/// Recent trades, persisted for the next process, and those restored from the previous one.
+#[derive(Debug, Clone, Default)]
+pub struct TradeJournal {
+    key: String,                 // Redis key, per configuration so it outlives the instance identifier
+    legacy_key: String,          // Pre-v1 key, read in compatibility mode
+    blocks: u64,                 // Window in blocks (pool_cooldown_blocks), 0 disables the journal
+    entries: Vec<JournalEntry>,  // Trades of this process still within their window
+    restored: Vec<JournalEntry>, // Trades of the previous process, consulted by the evaluation
+    loaded: bool,                // Restored once per process
+}
+
+impl TradeJournal {
+    pub fn new(config_id: &str, blocks: u64) -> Self {
+        Self {
+            key: keys::key_journal(config_id),
+            legacy_key: keys::legacy::key_journal(config_id),
+            blocks,
+            ..Default::default()
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Keys the journal is restored from, in order.
+    pub fn load_keys(&self) -> Vec<String> {
+        let mut keys = vec![self.key.clone()];
+        if keys::legacy() {
+            keys.push(self.legacy_key.clone());
+        }
+        keys
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// Trades of the previous process still consulted by the evaluation.
+    pub fn restored(&self) -> &[JournalEntry] {
+        &self.restored
+    }
+
+    /// Keeps the entries of the previous process still within their window at the given block.
+    pub fn restore(&mut self, entries: Vec<JournalEntry>, block: u64) {
+        self.loaded = true;
+        let blocks = self.blocks;
+        self.restored = entries.into_iter().filter(|entry| block < entry.block.saturating_add(blocks)).collect();
+        // Carried over, so a second restart within the window still skips them
+        self.entries = self.restored.clone();
+    }
+
+    /// Returns true while a trade of the previous process on the component is within its window.
+    pub fn is_recent(&self, component: &str, block: u64) -> bool {
+        let id = component.to_lowercase();
+        self.restored.iter().any(|entry| entry.component == id && block < entry.block.saturating_add(self.blocks))
+    }
+
+    /// Records a trade and drops the elapsed entries. Returns the entries to persist, None when disabled.
+    pub fn record(&mut self, mut entry: JournalEntry, block: u64) -> Option<Vec<JournalEntry>> {
+        if self.blocks == 0 {
+            return None;
+        }
+        entry.component = entry.component.to_lowercase();
+        self.entries.push(entry);
+        let blocks = self.blocks;
+        self.entries.retain(|entry| block < entry.block.saturating_add(blocks));
+        self.restored.retain(|entry| block < entry.block.saturating_add(blocks));
+        Some(self.entries.clone())
+    }
+}
+
+/// Reads the journal left by the previous process, from the first key holding one.
+pub async fn load(keys: &[String]) -> Vec<JournalEntry> {
+    for key in keys.iter() {
+        if let Some(entries) = crate::data::helpers::get::<Vec<JournalEntry>>(key).await {
+            return entries;
+        }
+    }
+    vec![]
+}
+
+/// Writes the journal in the background, so the trade loop never waits on Redis.
+pub fn persist(key: String, entries: Vec<JournalEntry>) {
+    tokio::spawn(async move {
+        crate::data::helpers::set_ex(&key, entries, TRADE_JOURNAL_TTL_SECS).await;
+    });
+}
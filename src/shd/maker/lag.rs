@@ -0,0 +1,66 @@
+//! Stream Lag Module
+//!
+//! Tycho occasionally delivers blocks 10 to 20 seconds behind the chain head, and the opportunities
+//! evaluated on those blocks are stale. Every `stream_lag_sample_every` messages, the block of the
+//! message is compared with the RPC head (`eth_blockNumber`). Past `max_stream_lag_blocks`, order
+//! creation is suppressed until the lag clears, with a single log line when an episode starts and
+//! one when it ends. The last sampled lag is a gauge on `/readyz` and in the published prices.
+
+/// Change of lagging state, returned by `observe` once per episode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LagTransition {
+    Lagging(u64),   // Lag (blocks) that started the episode
+    Recovered(u64), // Lag (blocks) back within the threshold
+}
+
+/// Delay of the stream behind the chain head.
+#[derive(Debug, Clone, Default)]
+pub struct StreamLag {
+    max_lag_blocks: u64, // 0 measures without suppressing
+    sample_every: u64,   // Messages between two samples
+    messages: u64,       // Messages seen since the last sample
+    lag_blocks: Option<u64>,
+    lagging: bool,
+}
+
+impl StreamLag {
+    pub fn new(max_lag_blocks: u64, sample_every: u64) -> Self {
+        Self {
+            max_lag_blocks,
+            sample_every: sample_every.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// Counts a message, true when the head must be sampled (first message, then every `sample_every`).
+    pub fn due(&mut self) -> bool {
+        let due = self.messages == 0;
+        self.messages = (self.messages + 1) % self.sample_every;
+        due
+    }
+
+    /// Records a sample: the block of the stream against the head of the RPC. A head of 0 (RPC failure) is ignored.
+    pub fn observe(&mut self, block: u64, head: u64) -> Option<LagTransition> {
+        if head == 0 {
+            return None;
+        }
+        let lag = head.saturating_sub(block);
+        self.lag_blocks = Some(lag);
+        let lagging = self.max_lag_blocks > 0 && lag > self.max_lag_blocks;
+        if lagging == self.lagging {
+            return None;
+        }
+        self.lagging = lagging;
+        Some(if lagging { LagTransition::Lagging(lag) } else { LagTransition::Recovered(lag) })
+    }
+
+    /// Last sampled lag, in blocks. None before the first sample.
+    pub fn lag_blocks(&self) -> Option<u64> {
+        self.lag_blocks
+    }
+
+    /// True while the stream lags beyond the threshold, order creation is suppressed.
+    pub fn is_lagging(&self) -> bool {
+        self.lagging
+    }
+}
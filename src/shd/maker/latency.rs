@@ -0,0 +1,146 @@
+//! Latency Budget Module
+//!
+//! An opportunity found on a block update is usually gone a few seconds later, and a swap broadcast after that only
+//! pays gas. The time of the receipt of the block update is recorded, and each expensive stage of the pipeline
+//! (market context, inventory, prepare, simulate, broadcast) checks the elapsed time against its deadline first. A
+//! stage started late is reported, and the remaining pipeline is aborted with a `Timeout` past the total budget.
+use std::sync::Arc;
+
+use crate::{
+    error::ExecError,
+    types::{
+        config::LatencyBudget,
+        maker::{Trade, TradeStatus},
+    },
+    utils::misc::now_ms,
+};
+
+/// Source of the current time, in milliseconds.
+pub trait Clock: Send + Sync {
+    fn now_ms(&self) -> u64;
+}
+
+/// Unix time of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        now_ms() as u64
+    }
+}
+
+/// Expensive stages of the pipeline run on a block update, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Context,
+    Inventory,
+    Prepare,
+    Simulate,
+    Broadcast,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Context => "context",
+            Stage::Inventory => "inventory",
+            Stage::Prepare => "prepare",
+            Stage::Simulate => "simulate",
+            Stage::Broadcast => "broadcast",
+        }
+    }
+
+    /// Deadline of the stage in the budget, in milliseconds after the block update.
+    pub fn deadline_ms(&self, budget: &LatencyBudget) -> u64 {
+        match self {
+            Stage::Context => budget.context_ms,
+            Stage::Inventory => budget.inventory_ms,
+            Stage::Prepare => budget.prepare_ms,
+            Stage::Simulate => budget.simulate_ms,
+            Stage::Broadcast => budget.broadcast_ms,
+        }
+    }
+}
+
+/// Latency budget of one block update, started on its receipt.
+#[derive(Clone)]
+pub struct Deadline {
+    budget: LatencyBudget,
+    received_ms: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl Deadline {
+    /// Budget of a block update received now.
+    pub fn start(budget: LatencyBudget, clock: Arc<dyn Clock>) -> Self {
+        let received_ms = clock.now_ms();
+        Self { budget, received_ms, clock }
+    }
+
+    /// No budget: every stage runs whatever the time taken, for the executions outside the stream loop.
+    pub fn unbounded() -> Self {
+        let budget = LatencyBudget {
+            context_ms: 0,
+            inventory_ms: 0,
+            prepare_ms: 0,
+            simulate_ms: 0,
+            broadcast_ms: 0,
+            total_ms: 0,
+        };
+        Self::start(budget, Arc::new(SystemClock))
+    }
+
+    /// Milliseconds since the receipt of the block update.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.now_ms().saturating_sub(self.received_ms)
+    }
+
+    /// Checks the time left before starting `stage`: a `Timeout` past the total budget, logged with the
+    /// `latency_budget_exceeded` metric, a warning with the `latency_stage_late` metric past the deadline of the stage.
+    pub fn check(&self, stage: Stage) -> Result<(), ExecError> {
+        if self.budget.total_ms == 0 {
+            return Ok(());
+        }
+        let elapsed_ms = self.elapsed_ms();
+        if elapsed_ms > self.budget.total_ms {
+            tracing::warn!(
+                metric = "latency_budget_exceeded",
+                stage = stage.as_str(),
+                elapsed_ms,
+                budget_ms = self.budget.total_ms,
+                "⏱️  {} ms since the block update, past the budget of {} ms: aborting before {}",
+                elapsed_ms,
+                self.budget.total_ms,
+                stage.as_str()
+            );
+            return Err(ExecError::Timeout(format!(
+                "Latency budget exceeded before {}: {} ms since the block update (budget {} ms)",
+                stage.as_str(),
+                elapsed_ms,
+                self.budget.total_ms
+            )));
+        }
+        let deadline_ms = stage.deadline_ms(&self.budget);
+        if elapsed_ms > deadline_ms {
+            tracing::warn!(
+                metric = "latency_stage_late",
+                stage = stage.as_str(),
+                elapsed_ms,
+                deadline_ms,
+                "⏱️  Starting {} {} ms after the block update, past its deadline of {} ms",
+                stage.as_str(),
+                elapsed_ms,
+                deadline_ms
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Aborts a trade past the latency budget: its status set to `Timeout`, logged with the `trade_killed_timeout` metric.
+/// The trade is then published as is, never broadcast.
+pub fn time_out(trade: &mut Trade, error: &ExecError) {
+    tracing::warn!(metric = "trade_killed_timeout", "⏱️  Aborting trade {}: {}", trade.metadata.trade_id, error);
+    trade.metadata.status = TradeStatus::Timeout;
+}
@@ -3,7 +3,38 @@
 //! Core market making logic and strategies. This module contains the
 //! implementation of market making algorithms, execution strategies, price feeds,
 //! and Tycho protocol integration for automated trading operations.
+pub mod allowance;
+pub mod approval;
+pub mod audit;
+pub mod backtest;
+pub mod breaker;
+pub mod control;
+pub mod cooldown;
+pub mod engine;
 pub mod exec;
 pub mod feed;
+pub mod frozen;
+pub mod health;
 pub mod r#impl;
+pub mod inventory;
+pub mod journal;
+pub mod lag;
+pub mod latency;
+pub mod multi;
+pub mod permit2;
+pub mod pnl;
+pub mod pools;
+pub mod preflight;
+pub mod quarantine;
+pub mod quote;
+pub mod reconnect;
+pub mod shutdown;
+pub mod simcache;
+pub mod sizing;
+pub mod snapshot;
+pub mod tombstone;
 pub mod tycho;
+pub mod universe;
+pub mod valuation;
+pub mod watcher;
+pub mod wrap;
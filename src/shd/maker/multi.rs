@@ -0,0 +1,195 @@
+//! Multi-Pair Runner
+//!
+//! Runs several market makers in one process. A single ProtocolStreamBuilder feeds the
+//! shared `TychoStreamState` cache and fans updates out to every pair, so each pair no
+//! longer opens its own Tycho stream. Pairs trading from the same wallet share an
+//! execution lock, keeping their nonces in sequence.
+use std::{collections::HashMap, sync::Arc};
+
+use futures::StreamExt;
+use tokio::sync::broadcast;
+use tycho_client::feed::component_tracker::ComponentFilter;
+
+use crate::{
+    maker::{
+        control::Control,
+        health::HealthState,
+        reconnect::Reconnect,
+        tycho::{retry_auth, TychoApiError},
+    },
+    types::{
+        config::{EnvConfig, MarketMakerConfig},
+        maker::MarketMaker,
+        tycho::{PsbConfig, SharedTychoStreamState, SharedUpdate},
+    },
+    utils::constants::SHARED_STREAM_CAPACITY,
+};
+
+/// Runs one market maker per pair over a shared Tycho stream.
+pub struct MultiPairRunner {
+    makers: Vec<MarketMaker>,
+}
+
+impl MultiPairRunner {
+    /// Creates the runner. All pairs must target the same network, since they share one stream.
+    ///
+    /// Duplicated identifiers get an index suffix, and pairs on the same wallet share a nonce lock.
+    pub fn new(mut makers: Vec<MarketMaker>) -> Result<Self, String> {
+        let network = match makers.first() {
+            Some(mk) => mk.config.network_name.as_str().to_string(),
+            None => return Err("No market maker to run".to_string()),
+        };
+        if let Some(mk) = makers.iter().find(|mk| mk.config.network_name.as_str() != network) {
+            return Err(format!(
+                "All pairs must run on the same network: {} is on {}, expected {}",
+                mk.config.pair_tag,
+                mk.config.network_name.as_str(),
+                network
+            ));
+        }
+        // The shared stream is built from the lead (first) config
+        let lead = &makers[0].config;
+        if makers
+            .iter()
+            .any(|mk| mk.config.tvl_add_threshold != lead.tvl_add_threshold || mk.config.tvl_remove_threshold != lead.tvl_remove_threshold)
+        {
+            tracing::warn!(
+                "Pairs have different TVL thresholds, the shared stream uses those of {}: add {} / remove {}",
+                lead.pair_tag,
+                lead.tvl_add_threshold,
+                lead.tvl_remove_threshold
+            );
+        }
+        if makers.iter().any(|mk| mk.config.tycho_protocols != lead.tycho_protocols) {
+            tracing::warn!("Pairs stream different protocols, the shared stream registers those of {}: {:?}", lead.pair_tag, lead.tycho_protocols);
+        }
+        let identifiers = unique_identifiers(makers.iter().map(|mk| mk.identifier.clone()).collect());
+        let mut locks: HashMap<String, Arc<tokio::sync::Mutex<()>>> = HashMap::new();
+        for (mk, identifier) in makers.iter_mut().zip(identifiers) {
+            mk.identifier = identifier;
+            mk.health = HealthState::new(&mk.identifier, &mk.config);
+            mk.nonce_lock = locks.entry(mk.config.wallet_public_key.to_lowercase()).or_insert_with(|| mk.nonce_lock.clone()).clone();
+        }
+        Ok(Self { makers })
+    }
+
+    /// Identifiers of the managed market makers, in configuration order.
+    pub fn identifiers(&self) -> Vec<String> {
+        self.makers.iter().map(|mk| mk.identifier.clone()).collect()
+    }
+
+    /// Health handles of the managed market makers, in configuration order.
+    pub fn healths(&self) -> Vec<HealthState> {
+        self.makers.iter().map(|mk| mk.health.clone()).collect()
+    }
+
+    /// Control handles of the managed market makers, keyed by identifier.
+    pub fn controls(&self) -> Vec<(String, Control)> {
+        self.makers.iter().map(|mk| (mk.identifier.clone(), mk.control.clone())).collect()
+    }
+
+    /// Runs the shared feed and every pair loop concurrently.
+    ///
+    /// Returns when the feed fails to build its stream, mirroring the single-pair behaviour,
+    /// or once every pair has been killed.
+    pub async fn run(self, mtx: SharedTychoStreamState, env: EnvConfig) {
+        let (tx, _) = broadcast::channel::<Arc<SharedUpdate>>(SHARED_STREAM_CAPACITY);
+        let lead = self.makers[0].config.clone();
+        tracing::info!("Running {} pairs on a shared {} stream", self.makers.len(), lead.network_name.as_str());
+        let healths = self.makers.iter().map(|mk| mk.health.clone()).collect::<Vec<HealthState>>();
+        let pairs = self.makers.into_iter().map(|mut mk| {
+            let (mtx, tx, env) = (mtx.clone(), tx.clone(), env.clone());
+            async move { mk.run_shared(mtx, tx, env).await }
+        });
+        let feed = feed(lead, mtx.clone(), tx.clone(), env.clone(), healths);
+        futures::future::select(Box::pin(feed), Box::pin(futures::future::join_all(pairs))).await;
+    }
+}
+
+/// Owns the single ProtocolStreamBuilder: applies each update to the cache, then broadcasts it.
+/// Its reconnections are counted on the health of every pair.
+async fn feed(config: MarketMakerConfig, mtx: SharedTychoStreamState, tx: broadcast::Sender<Arc<SharedUpdate>>, env: EnvConfig, healths: Vec<HealthState>) {
+    let mut reconnect = Reconnect::new(config.stream_restart_delay_ms, config.stream_restart_max_delay_ms);
+    let mut auth_attempt = 0;
+    loop {
+        tracing::debug!("Connecting shared ProtocolStreamBuilder for {}", config.network_name.as_str());
+        let psbc = PsbConfig {
+            filter: ComponentFilter::with_tvl_range(config.tvl_remove_threshold, config.tvl_add_threshold),
+        };
+        let atks = mtx.read().await.atks.clone();
+        let psb = crate::maker::tycho::psb(config.clone(), env.tycho_api_key.to_string(), psbc, atks).await;
+        match psb.build().await {
+            Ok(mut stream) => {
+                auth_attempt = 0;
+                // The first update of a connection is a full snapshot, applied as a diff on what the cache held
+                let mut first = true;
+                loop {
+                    match stream.next().await {
+                        Some(Ok(msg)) => {
+                            let update = SharedUpdate::from(msg);
+                            let mut state = mtx.write().await;
+                            if first {
+                                state.resync(&update);
+                                first = false;
+                                if let Some(elapsed) = reconnect.ready(std::time::Instant::now()) {
+                                    tracing::info!("🔌 Shared stream ready again {} ms after the disconnect", elapsed.as_millis());
+                                    healths.iter().for_each(|health| health.recovered(elapsed.as_millis() as u64));
+                                }
+                            } else {
+                                state.apply(&update);
+                            }
+                            drop(state);
+                            // No receiver is fine, pairs bootstrap from the cache when they subscribe
+                            let _ = tx.send(Arc::new(update));
+                        }
+                        Some(Err(e)) => {
+                            tracing::warn!("Shared stream error: {:?}", e);
+                            break;
+                        }
+                        None => {
+                            tracing::warn!("Shared stream closed. Retrying...");
+                            break;
+                        }
+                    }
+                }
+                healths.iter().for_each(|health| health.reconnecting());
+                let delay = reconnect.disconnected(std::time::Instant::now());
+                tracing::info!(
+                    "🔌 Reconnecting the shared {} stream in {} ms (attempt {})",
+                    config.network_name,
+                    delay.as_millis(),
+                    reconnect.attempts()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                let e = TychoApiError::classify(e.to_string());
+                if retry_auth(&config, "opening the shared stream", &e, auth_attempt).await {
+                    auth_attempt += 1;
+                    continue;
+                }
+                tracing::warn!("Failed to build shared stream on {}: {}. Exiting.", config.network_name.as_str(), e);
+                return;
+            }
+        }
+    }
+}
+
+/// Appends an index to identifiers used more than once, so each pair publishes under its own.
+pub fn unique_identifiers(identifiers: Vec<String>) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for identifier in identifiers.iter() {
+        *counts.entry(identifier.clone()).or_default() += 1;
+    }
+    identifiers
+        .into_iter()
+        .enumerate()
+        .map(|(x, identifier)| {
+            if counts.get(&identifier).copied().unwrap_or_default() > 1 {
+                format!("{}-{}", identifier, x)
+            } else {
+                identifier
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,171 @@
+//! Permit2 Module
+//!
+//! Signature-based approvals for `permit2_approval`: each swap carries a Permit2 `PermitSingle` signed
+//! off-chain (EIP-712) for the router, sent with `singleSwapPermit2`, so no approval transaction is sent
+//! per trade. The tokens only need a one-time infinite approval to Permit2, set at startup.
+use std::{borrow::Cow, collections::HashMap};
+
+use alloy::sol_types::{Eip712Domain, SolStruct};
+use alloy_primitives::{
+    aliases::{U160, U48},
+    Address, B256, U256,
+};
+use num_traits::ToPrimitive;
+use serde_json::json;
+
+use crate::{
+    maker::r#impl::{PermitDetails, PermitSingle},
+    types::{
+        config::EnvConfig,
+        maker::{ExecutionOrder, MarketMaker},
+        sol::IPermit2,
+    },
+    utils::{
+        evm::{create_provider, is_native, RpcPool},
+        signer::WalletSigner,
+    },
+};
+
+/// EIP-712 domain of Permit2: named "Permit2", without a version.
+pub fn domain(chain_id: u64, permit2: Address) -> Eip712Domain {
+    Eip712Domain::new(Some(Cow::Borrowed("Permit2")), None, Some(U256::from(chain_id)), Some(permit2), None)
+}
+
+/// Allowance of `amount` of `token` to `spender`, valid (as its signature) until `expiration`, at the Permit2 `nonce`.
+pub fn permit(token: Address, amount: u128, expiration: u64, nonce: u64, spender: Address) -> PermitSingle {
+    PermitSingle {
+        details: PermitDetails {
+            token,
+            amount: U160::from(amount),
+            expiration: U48::from(expiration),
+            nonce: U48::from(nonce),
+        },
+        spender,
+        sigDeadline: U256::from(expiration),
+    }
+}
+
+/// Digest signed by the wallet, `keccak256(0x1901 ‖ domainSeparator ‖ hashStruct(permit))`.
+pub fn signing_hash(permit: &PermitSingle, domain: &Eip712Domain) -> B256 {
+    permit.eip712_signing_hash(domain)
+}
+
+/// Typed data of a permit, as sent to a remote signer with eth_signTypedData_v4.
+pub fn typed_data(permit: &PermitSingle, chain_id: u64, permit2: Address) -> serde_json::Value {
+    json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "PermitSingle": [
+                { "name": "details", "type": "PermitDetails" },
+                { "name": "spender", "type": "address" },
+                { "name": "sigDeadline", "type": "uint256" }
+            ],
+            "PermitDetails": [
+                { "name": "token", "type": "address" },
+                { "name": "amount", "type": "uint160" },
+                { "name": "expiration", "type": "uint48" },
+                { "name": "nonce", "type": "uint48" }
+            ]
+        },
+        "primaryType": "PermitSingle",
+        "domain": { "name": "Permit2", "chainId": chain_id, "verifyingContract": permit2.to_string() },
+        "message": {
+            "details": {
+                "token": permit.details.token.to_string(),
+                "amount": permit.details.amount.to_string(),
+                "expiration": permit.details.expiration.to_string(),
+                "nonce": permit.details.nonce.to_string()
+            },
+            "spender": permit.spender.to_string(),
+            "sigDeadline": permit.sigDeadline.to_string()
+        }
+    })
+}
+
+/// Permit and its 65 bytes signature (r ‖ s ‖ v), passed to `singleSwapPermit2`.
+#[derive(Debug, Clone)]
+pub struct SignedPermit {
+    pub permit: PermitSingle,
+    pub signature: Vec<u8>,
+}
+
+/// Signs a permit with the wallet signer.
+pub async fn sign(signer: &WalletSigner, permit: PermitSingle, chain_id: u64, permit2: Address) -> Result<SignedPermit, String> {
+    let hash = signing_hash(&permit, &domain(chain_id, permit2));
+    let signature = signer.sign_typed_data(hash, &typed_data(&permit, chain_id, permit2)).await?;
+    Ok(SignedPermit {
+        permit,
+        signature: signature.as_bytes().to_vec(),
+    })
+}
+
+/// Current Permit2 nonce of the (owner, token, spender) allowance, the one the next permit must use.
+pub async fn nonce(rpc: &str, permit2: Address, owner: Address, token: Address, spender: Address) -> Result<u64, String> {
+    let provider = create_provider(rpc);
+    let allowance = IPermit2::new(permit2, &provider)
+        .allowance(owner, token, spender)
+        .call()
+        .await
+        .map_err(|e| format!("Failed to read the Permit2 allowance of {} on {}: {:?}", owner, token, e))?;
+    Ok(allowance.nonce.to::<u64>())
+}
+
+impl MarketMaker {
+    /// Signs one permit per order, keyed by trade id, for the router input of the order.
+    ///
+    /// Nonces start at the Permit2 nonce of each sold token and follow the order sequence, as the swaps are sent.
+    /// An order whose permit could not be signed is left out, and skipped at encoding. Native input needs no permit.
+    pub async fn permits(&self, orders: &[ExecutionOrder], env: &EnvConfig) -> HashMap<String, SignedPermit> {
+        let mut permits = HashMap::new();
+        let parse = |name: &str, address: &str| address.parse::<Address>().map_err(|e| format!("Invalid {} {}: {}", name, address, e));
+        let addresses = parse("permit2_address", &self.config.permit2_address)
+            .and_then(|permit2| Ok((permit2, parse("tycho_router_address", &self.config.tycho_router_address)?)))
+            .and_then(|(permit2, router)| Ok((permit2, router, WalletSigner::from_env(env)?)));
+        let (permit2, router, signer) = match addresses {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                tracing::error!("Cannot sign Permit2 permits: {}", e);
+                return permits;
+            }
+        };
+        let owner = signer.address();
+        let pool = RpcPool::of(&self.config);
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let expiration = self.config.permit_expiration(now);
+        let mut nonces: HashMap<Address, u64> = HashMap::new();
+        for order in orders {
+            let solution = Self::router_solution(&self.build_tycho_solution(order.clone()));
+            if is_native(&solution.given_token.to_string()) {
+                continue; // Sent as value, without permit
+            }
+            let token = Address::from_slice(&solution.given_token);
+            let Some(amount) = solution.given_amount.to_u128() else {
+                tracing::error!("Permit amount {} of trade {} overflows", solution.given_amount, order.trade_id);
+                continue;
+            };
+            let next = match nonces.get(&token) {
+                Some(next) => *next,
+                None => match pool.call(|url| async move { nonce(&url, permit2, owner, token, router).await }).await {
+                    Ok(current) => current,
+                    Err(e) => {
+                        tracing::error!("{}", e);
+                        continue;
+                    }
+                },
+            };
+            match sign(&signer, permit(token, amount, expiration, next, router), self.config.chain_id, permit2).await {
+                Ok(signed) => {
+                    tracing::debug!("  ✍️  Permit2 permit of {} on {} signed at nonce {}", amount, token, next);
+                    nonces.insert(token, next + 1);
+                    permits.insert(order.trade_id.clone(), signed);
+                }
+                Err(e) => tracing::error!("Failed to sign the permit of trade {}: {}", order.trade_id, e),
+            }
+        }
+        permits
+    }
+}
@@ -0,0 +1,100 @@
+//! Realized PnL Module
+//!
+//! Tracks the realized PnL of executed trades, cumulated since startup and over a rolling window.
+use std::collections::VecDeque;
+
+use crate::{
+    maker::valuation::worth,
+    types::maker::{ExecutionOrder, MarketContext, PnlEntry, PnlSnapshot, ReceiptData},
+};
+
+/// Realized PnL tracker, owned by the market maker.
+#[derive(Debug, Clone, Default)]
+pub struct PnlTracker {
+    window_ms: u128,
+    entries: VecDeque<PnlEntry>, // Entries within the rolling window, oldest first
+    trades: usize,
+    cumulative_usd: f64,
+    cumulative_gas_usd: f64,
+}
+
+impl PnlTracker {
+    /// Creates a tracker with the given rolling window.
+    pub fn new(window_ms: u128) -> Self {
+        Self { window_ms, ..Default::default() }
+    }
+
+    /// Records a trade from its receipt: the amounts sent and received by `wallet` in its transfers and the gas paid,
+    /// valued at the market `context`. Trades without receipt did not land and are ignored.
+    ///
+    /// A reverted trade only costs its gas.
+    pub fn record_trade(&mut self, order: &ExecutionOrder, receipt: Option<&ReceiptData>, wallet: &str, context: &MarketContext, timestamp_ms: u128) -> Option<PnlEntry> {
+        let receipt = receipt?;
+        let gas_usd = (receipt.gas_used as f64) * (receipt.effective_gas_price as f64) / 1e18 * context.eth_to_usd;
+        let (sold_usd, bought_usd) = if receipt.status {
+            let (selling, buying) = (&order.adjustment.selling, &order.adjustment.buying);
+            // A native leg is the value of the transaction, not a transfer: the amount encoded is the one booked
+            let (sold, bought) = order.executed(receipt, wallet);
+            let sold = sold as f64 / 10f64.powi(selling.decimals as i32);
+            let bought = bought as f64 / 10f64.powi(buying.decimals as i32);
+            let base_to_quote = order.calculation.base_to_quote;
+            (worth(sold, base_to_quote, context).1, worth(bought, !base_to_quote, context).1)
+        } else {
+            (0.0, 0.0)
+        };
+        let entry = PnlEntry {
+            timestamp_ms,
+            sold_usd,
+            bought_usd,
+            gas_usd,
+            pnl_usd: bought_usd - sold_usd - gas_usd,
+        };
+        self.record(entry.clone());
+        Some(entry)
+    }
+
+    /// Records a realized PnL entry.
+    pub fn record(&mut self, entry: PnlEntry) {
+        self.trades += 1;
+        self.cumulative_usd += entry.pnl_usd;
+        self.cumulative_gas_usd += entry.gas_usd;
+        let timestamp_ms = entry.timestamp_ms;
+        self.entries.push_back(entry);
+        self.prune(timestamp_ms);
+    }
+
+    /// Drops the entries that left the rolling window.
+    fn prune(&mut self, now_ms: u128) {
+        while let Some(first) = self.entries.front() {
+            if now_ms.saturating_sub(first.timestamp_ms) > self.window_ms {
+                self.entries.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Realized PnL of the rolling window at `now_ms`, counting only entries recorded at or after `from_ms`.
+    pub fn realized_since(&self, from_ms: u128, now_ms: u128) -> f64 {
+        self.entries
+            .iter()
+            .filter(|e| e.timestamp_ms >= from_ms && now_ms.saturating_sub(e.timestamp_ms) <= self.window_ms)
+            .map(|e| e.pnl_usd)
+            .sum()
+    }
+
+    /// Returns cumulative and rolling-window PnL at `now_ms`.
+    pub fn snapshot(&self, now_ms: u128) -> PnlSnapshot {
+        let rolling = self.entries.iter().filter(|e| now_ms.saturating_sub(e.timestamp_ms) <= self.window_ms);
+        let (rolling_trades, rolling_usd) = rolling.fold((0, 0.0), |(count, sum), e| (count + 1, sum + e.pnl_usd));
+        PnlSnapshot {
+            timestamp_ms: now_ms,
+            trades: self.trades,
+            cumulative_usd: self.cumulative_usd,
+            cumulative_gas_usd: self.cumulative_gas_usd,
+            window_ms: self.window_ms,
+            rolling_trades,
+            rolling_usd,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Pools Module
+//!
+//! Components, protosims and token graph built from the Tycho stream. They live on the market maker rather than in
+//! its stream loop, so a reconnect keeps them warm: the full snapshot opening the new connection is applied as a diff
+//! (components added, updated and removed since the previous connection) instead of rebuilding everything from it.
+use std::collections::{HashMap, HashSet};
+
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+use crate::{opti::routing::TokenGraph, types::tycho::SharedUpdate, utils::constants::NULL_ADDRESS};
+
+/// Components of the stream with their state.
+#[derive(Default)]
+pub struct StreamPools {
+    // All components, used to find routes and targets
+    pub components: Vec<ProtocolComponent>,
+    // ProtocolSim instances, keyed by lowercase component id
+    pub protosims: HashMap<String, Box<dyn ProtocolSim>>,
+    // Token graph over all components, used for valorisation routing
+    pub graph: TokenGraph,
+}
+
+/// Changes of the components between two connections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl StreamPools {
+    /// True before the first snapshot, a cold start.
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Applies the full snapshot of a new connection on the components kept from the previous one: the components
+    /// missing from it are removed, the others replaced or added. As on a cold start, a component without state or
+    /// with the null address is left out.
+    pub fn resync(&mut self, snapshot: &SharedUpdate) -> SnapshotDiff {
+        let states = snapshot.states.keys().map(|id| id.to_lowercase()).collect::<HashSet<String>>();
+        let listed = snapshot
+            .new_pairs
+            .values()
+            .map(|cp| (cp.id.to_string().to_lowercase(), cp))
+            .filter(|(id, _)| states.contains(id) && !id.contains(NULL_ADDRESS))
+            .collect::<HashMap<String, &ProtocolComponent>>();
+        let mut diff = SnapshotDiff::default();
+        let mut removed = vec![];
+        self.components.retain(|cp| {
+            let id = cp.id.to_string().to_lowercase();
+            let kept = listed.contains_key(&id);
+            if !kept {
+                removed.push(id);
+            }
+            kept
+        });
+        for id in removed.iter() {
+            self.graph.remove(id);
+        }
+        diff.removed = removed.len();
+
+        self.protosims.retain(|id, _| states.contains(id));
+        for (id, state) in snapshot.states.iter() {
+            self.protosims.insert(id.to_lowercase(), state.clone());
+        }
+        for (id, cp) in listed {
+            match self.components.iter().position(|current| current.id.to_string().to_lowercase() == id) {
+                Some(pos) => {
+                    self.components[pos] = cp.clone();
+                    diff.updated += 1;
+                }
+                None => {
+                    self.components.push(cp.clone());
+                    diff.added += 1;
+                }
+            }
+            self.graph.insert(cp.clone(), self.protosims.get(&id).map(|p| p.as_ref()));
+        }
+        diff
+    }
+
+    /// Applies an incremental update: new states, new pairs (added or replaced) and removed pairs, dropped from the
+    /// routing graph along with their state.
+    pub fn apply(&mut self, update: &SharedUpdate) {
+        for (id, state) in update.states.iter() {
+            self.protosims.insert(id.to_lowercase(), state.clone());
+        }
+        for (id, cp) in update.new_pairs.iter() {
+            match self.components.iter().position(|current| current.id.to_string().to_lowercase() == id.to_lowercase()) {
+                Some(pos) => self.components[pos] = cp.clone(),
+                None => self.components.push(cp.clone()),
+            }
+            self.graph.insert(cp.clone(), self.protosims.get(&id.to_lowercase()).map(|p| p.as_ref()));
+        }
+        for id in update.removed_pairs.keys() {
+            if let Some(pos) = self.components.iter().position(|current| current.id.to_string().to_lowercase() == id.to_lowercase()) {
+                self.components.swap_remove(pos);
+            }
+            self.protosims.remove(&id.to_lowercase());
+            self.graph.remove(id);
+        }
+    }
+
+    /// Latest states of the components `ids`: the states of the `updates` received since, applied in order over the
+    /// current ones. A component removed by these updates is left out.
+    pub fn latest(&self, ids: &[String], updates: &[&SharedUpdate]) -> HashMap<String, Box<dyn ProtocolSim>> {
+        let mut latest = HashMap::new();
+        for id in ids.iter().map(|id| id.to_lowercase()) {
+            let mut state = self.protosims.get(&id).cloned();
+            for update in updates {
+                if let Some(updated) = update.states.iter().find(|(key, _)| key.to_lowercase() == id) {
+                    state = Some(updated.1.clone());
+                }
+                if update.removed_pairs.keys().any(|key| key.to_lowercase() == id) {
+                    state = None;
+                }
+            }
+            if let Some(state) = state {
+                latest.insert(id, state);
+            }
+        }
+        latest
+    }
+}
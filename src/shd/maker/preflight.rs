@@ -0,0 +1,233 @@
+//! Startup Preflight Module
+//!
+//! A typo'd RPC URL, an expired Tycho API key or Redis being down used to surface only once the
+//! stream had warmed up. `preflight` checks every external dependency of a config up front and
+//! returns a pass/fail report, printed by `maker --preflight` which exits non-zero on any failure.
+use std::fmt;
+
+use alloy::providers::Provider;
+use alloy_primitives::Address;
+
+use crate::{
+    maker::{feed::PriceFeedFactory, tycho::specific},
+    types::config::{EnvConfig, MarketMakerConfig},
+    utils::{
+        evm::{balances, chain_id, create_provider, RpcPool},
+        signer::WalletSigner,
+    },
+};
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    Warn, // Worth a look, does not fail the preflight
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// One row of the preflight table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Checks of one config, in the order they ran.
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    pub config: String, // Config identifier
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    pub fn new(config: String) -> Self {
+        Self { config, checks: vec![] }
+    }
+
+    /// Adds a check, failed with its error or passed with its detail.
+    pub fn record(&mut self, name: &str, outcome: Result<String, String>) {
+        let (status, detail) = match outcome {
+            Ok(detail) => (CheckStatus::Pass, detail),
+            Err(detail) => (CheckStatus::Fail, detail),
+        };
+        self.push(name, status, detail);
+    }
+
+    pub fn push(&mut self, name: &str, status: CheckStatus, detail: String) {
+        self.checks.push(PreflightCheck {
+            name: name.to_string(),
+            status,
+            detail,
+        });
+    }
+
+    /// Returns true when no check failed (warnings allowed).
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Fail)
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Fail).count()
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    /// Pass/fail table, one row per check.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.checks.iter().map(|check| check.name.len()).max().unwrap_or(0).max("Check".len());
+        writeln!(f, "Preflight {}", self.config)?;
+        writeln!(f, "  {:<width$} | Status | Detail", "Check", width = width)?;
+        writeln!(f, "  {}-|--------|-------", "-".repeat(width))?;
+        for check in self.checks.iter() {
+            writeln!(f, "  {:<width$} | {:<6} | {}", check.name, check.status.as_str(), check.detail, width = width)?;
+        }
+        let verdict = if self.passed() {
+            "passed".to_string()
+        } else {
+            format!("failed ({} of {} checks)", self.failures(), self.checks.len())
+        };
+        write!(f, "  => Preflight {}", verdict)
+    }
+}
+
+/// Checks the external dependencies of a config: RPC endpoints, Tycho API, price feeds, wallet, Redis and contracts.
+pub async fn preflight(config: &MarketMakerConfig, env: &EnvConfig) -> PreflightReport {
+    let mut report = PreflightReport::new(config.id());
+
+    // RPC endpoints reachable and serving the chain of the config
+    for url in config.rpc_endpoints() {
+        let outcome = match chain_id(url.clone()).await {
+            Ok(id) if id == config.chain_id => Ok(format!("{} serves chain {}", url, id)),
+            Ok(id) => Err(format!("{} serves chain {}, config expects {}", url, id, config.chain_id)),
+            Err(e) => Err(format!("{} unreachable: {}", url, e)),
+        };
+        report.record("RPC", outcome);
+    }
+
+    // Tycho API authenticates and knows both tokens
+    let addresses = vec![config.base_token_address.clone(), config.quote_token_address.clone()];
+    let outcome = match specific(config.clone(), Some(env.tycho_api_key.as_str()), addresses.clone()).await {
+        Some(tokens) => {
+            let missing = addresses
+                .iter()
+                .filter(|a| !tokens.iter().any(|t| t.address.to_string().eq_ignore_ascii_case(a)))
+                .cloned()
+                .collect::<Vec<String>>();
+            if missing.is_empty() {
+                Ok(format!("{} and {} found on {}", config.base_token, config.quote_token, config.tycho_api))
+            } else {
+                Err(format!("Token(s) unknown to {}: {}", config.tycho_api, missing.join(", ")))
+            }
+        }
+        None => Err(format!("{} rejected the request (API key or network)", config.tycho_api)),
+    };
+    report.record("Tycho API", outcome);
+
+    // Gas token oracle, used to value gas, and the reference price feed
+    let pool = RpcPool::of(config);
+    let outcome = match pool.call(|url| crate::maker::feed::chainlink(url, config.gas_token_chainlink_price_feed.clone())).await {
+        Ok(price) if price > 0. => Ok(format!("{} answers {:.2}", config.gas_token_chainlink_price_feed, price)),
+        Ok(price) => Err(format!("{} answers {}", config.gas_token_chainlink_price_feed, price)),
+        Err(e) => Err(e),
+    };
+    report.record("Chainlink gas feed", outcome);
+    let outcome = match PriceFeedFactory::create(config.price_feed_config.r#type.as_str()).get(config.clone()).await {
+        Ok(price) if price > 0. && price.is_finite() => Ok(format!("{} {}/{}: {:.5}", config.price_feed_config.r#type, config.base_token, config.quote_token, price)),
+        Ok(price) => Err(format!("{} answers {}", config.price_feed_config.r#type, price)),
+        Err(e) => Err(e),
+    };
+    report.record("Price feed", outcome);
+
+    // Signer holding the key of the configured wallet
+    let outcome = match WalletSigner::from_env(env) {
+        Ok(wallet) if wallet.address().to_string().eq_ignore_ascii_case(&config.wallet_public_key) => Ok(format!("{} signer for {}", env.signer_type.as_str(), wallet.address())),
+        Ok(wallet) => Err(format!("Signer address {} differs from wallet_public_key {}", wallet.address(), config.wallet_public_key)),
+        Err(e) => Err(e),
+    };
+    report.record("Signer", outcome);
+
+    // Wallet balances: gas above the floor, and something to trade
+    match config.wallet_public_key.parse::<Address>() {
+        Ok(wallet) => {
+            let native = pool.call(|url| async move {
+                let provider = create_provider(&url);
+                provider.get_balance(wallet).await
+            });
+            let outcome = match native.await.map(|native| u128::try_from(native).unwrap_or(u128::MAX)) {
+                Ok(native) if native >= config.min_native_balance_wei => Ok(format!("{:.6} ETH", native as f64 / 1e18)),
+                Ok(native) => Err(format!(
+                    "{:.6} ETH below min_native_balance_wei ({:.6} ETH)",
+                    native as f64 / 1e18,
+                    config.min_native_balance_wei as f64 / 1e18
+                )),
+                Err(e) => Err(format!("Failed to read the native balance: {}", e)),
+            };
+            report.record("Native gas balance", outcome);
+            let amounts = pool.call(|url| {
+                let (owner, tokens) = (config.wallet_public_key.clone(), addresses.clone());
+                async move { balances(&create_provider(&url), owner, tokens).await }
+            });
+            match amounts.await {
+                Ok(amounts) if amounts.iter().all(|amount| *amount == 0) => report.push("Token balances", CheckStatus::Fail, format!("No {} nor {} to trade", config.base_token, config.quote_token)),
+                Ok(amounts) if amounts.contains(&0) => report.push(
+                    "Token balances",
+                    CheckStatus::Warn,
+                    format!("{} {} / {} {} (raw), one side only", amounts[0], config.base_token, amounts[1], config.quote_token),
+                ),
+                Ok(amounts) => report.push(
+                    "Token balances",
+                    CheckStatus::Pass,
+                    format!("{} {} / {} {} (raw)", amounts[0], config.base_token, amounts[1], config.quote_token),
+                ),
+                Err(e) => report.push("Token balances", CheckStatus::Fail, e),
+            }
+        }
+        Err(e) => report.push("Wallet", CheckStatus::Fail, format!("Invalid wallet_public_key: {}", e)),
+    }
+
+    // Redis, only needed to publish events
+    if config.publish_events {
+        let outcome = match crate::data::helpers::connect().await {
+            Ok(mut co) => {
+                let pong: redis::RedisResult<String> = redis::cmd("PING").query_async(&mut co).await;
+                pong.map_err(|e| format!("PING failed: {}", e))
+            }
+            Err(e) => Err(format!("Connection failed: {}", e)),
+        };
+        report.record("Redis", outcome);
+    } else {
+        report.push("Redis", CheckStatus::Pass, "Skipped, publish_events disabled".to_string());
+    }
+
+    // Contracts the swaps go through
+    for (name, address) in [("Tycho router", &config.tycho_router_address), ("Permit2", &config.permit2_address)] {
+        let outcome = match address.parse::<Address>() {
+            Ok(parsed) => {
+                let code = pool.call(|url| async move {
+                    let provider = create_provider(&url);
+                    provider.get_code_at(parsed).await
+                });
+                match code.await {
+                    Ok(code) if !code.is_empty() => Ok(format!("{} ({} bytes of code)", address, code.len())),
+                    Ok(_) => Err(format!("No code at {}", address)),
+                    Err(e) => Err(format!("Failed to read the code at {}: {}", address, e)),
+                }
+            }
+            Err(e) => Err(format!("Invalid address {}: {}", address, e)),
+        };
+        report.record(name, outcome);
+    }
+
+    report
+}
@@ -0,0 +1,122 @@
+//! Pool Quarantine Module
+//!
+//! A freshly indexed pool sometimes reports a spot price far away from the reference
+//! (decimals or token ordering), which the evaluation would take for an enormous opportunity.
+//! Pools deviating more than `max_plausible_spread_bps` are quarantined: the evaluation skips
+//! them, they are logged at most once per `QUARANTINE_LOG_INTERVAL_MS`, and an alert is raised
+//! once a pool stays quarantined for `quarantine_alert_blocks` consecutive blocks.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::types::maker::ComponentPriceData;
+use crate::utils::constants::{BASIS_POINT_DENO, QUARANTINE_LOG_INTERVAL_MS};
+
+/// Quarantine episode of one component.
+#[derive(Debug, Clone)]
+struct Quarantined {
+    blocks: u64,        // Consecutive blocks observed out of the band
+    last_block: u64,    // Last block observed, so a block seen twice counts once
+    logged_at: Instant, // Last log line for this component
+    alerted: bool,      // Alert raised for this episode
+}
+
+/// Quarantined pool to report, returned by `observe` when a log line or an alert is due.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuarantineNotice {
+    pub component: String, // Component id (lowercase)
+    pub spread_bps: f64,   // Deviation from the reference at this block
+    pub blocks: u64,       // Consecutive blocks quarantined
+    pub alert: bool,       // Reached `quarantine_alert_blocks`, raised once per episode
+}
+
+/// Components whose spot price is implausibly far from the reference.
+#[derive(Debug, Clone, Default)]
+pub struct PoolQuarantine {
+    max_spread_bps: f64,
+    alert_blocks: u64, // 0 disables the alert
+    entries: HashMap<String, Quarantined>,
+}
+
+impl PoolQuarantine {
+    pub fn new(max_spread_bps: f64, alert_blocks: u64) -> Self {
+        Self {
+            max_spread_bps,
+            alert_blocks,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if a spread (in bps) is within the sanity band.
+    pub fn is_plausible(&self, spread_bps: f64) -> bool {
+        spread_bps.abs() <= self.max_spread_bps
+    }
+
+    /// Updates the quarantine with the spot prices of a block, releasing the pools back within the band.
+    ///
+    /// Returns the pools to log (first block, then once per interval) or to alert on.
+    pub fn observe(&mut self, cpds: &[ComponentPriceData], reference: f64, block: u64, now: Instant) -> Vec<QuarantineNotice> {
+        let mut notices = vec![];
+        if reference <= 0. || !reference.is_finite() {
+            return notices;
+        }
+        let interval = Duration::from_millis(QUARANTINE_LOG_INTERVAL_MS);
+        for cpd in cpds.iter() {
+            let id = cpd.address.to_lowercase();
+            let spread_bps = (cpd.price - reference) / reference * BASIS_POINT_DENO;
+            if self.is_plausible(spread_bps) {
+                if self.entries.remove(&id).is_some() {
+                    tracing::info!("🩺 Pool {} back within {} bps of the reference, released from quarantine", id, self.max_spread_bps);
+                }
+                continue;
+            }
+            let alert_blocks = self.alert_blocks;
+            let mut logged = true;
+            let entry = self.entries.entry(id.clone()).or_insert_with(|| {
+                logged = false;
+                Quarantined {
+                    blocks: 0,
+                    last_block: 0,
+                    logged_at: now,
+                    alerted: false,
+                }
+            });
+            if entry.blocks == 0 || block > entry.last_block {
+                entry.blocks += 1;
+                entry.last_block = block;
+            }
+            let log = !logged || now.duration_since(entry.logged_at) >= interval;
+            if log {
+                entry.logged_at = now;
+            }
+            let alert = alert_blocks > 0 && !entry.alerted && entry.blocks >= alert_blocks;
+            if alert {
+                entry.alerted = true;
+            }
+            if log || alert {
+                notices.push(QuarantineNotice {
+                    component: id,
+                    spread_bps,
+                    blocks: entry.blocks,
+                    alert,
+                });
+            }
+        }
+        // Components no longer targeted leave the quarantine
+        self.entries.retain(|id, _| cpds.iter().any(|cpd| cpd.address.eq_ignore_ascii_case(id)));
+        notices
+    }
+
+    /// Returns true while the component is quarantined.
+    pub fn contains(&self, component: &str) -> bool {
+        self.entries.contains_key(&component.to_lowercase())
+    }
+
+    /// Number of quarantined components, published with the prices.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
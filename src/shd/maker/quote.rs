@@ -0,0 +1,211 @@
+//! One-Shot Quote Module
+//!
+//! Answers "what would the bot do right now for selling 2 ETH into pool X?" for `maker quote`:
+//! the readjust math (spot, `get_amount_out`, fee, gas, net price against the reference) on a
+//! user-supplied amount, over one Tycho snapshot, without creating any order. The amount is capped by
+//! `max_trade_usd` and checked against `min_amount_worth_usd`, and the swap valued by the helpers of `readjust`.
+use std::{collections::HashMap, fmt};
+
+use futures::StreamExt;
+use num_traits::ToPrimitive;
+use tycho_common::{models::token::Token, simulation::protocol_sim::ProtocolSim};
+
+use crate::{
+    maker::{
+        sizing::{swap_value, SwapValue},
+        tycho::{amm_fee_to_bps, excluded_v4_hook},
+        valuation::{cap_worth, worth},
+    },
+    opti::{
+        math::{normalized, powered},
+        routing::TokenGraph,
+    },
+    types::{
+        builder::MarketMakerBuilder,
+        config::{EnvConfig, MarketMakerConfig},
+        maker::{MarketContext, MarketMaker},
+        tycho::ProtoSimComp,
+    },
+    utils::constants::{BASIS_POINT_DENO, NULL_ADDRESS},
+};
+
+/// Pool selector of `maker quote` picking the target with the best net price.
+pub const BEST_TARGET: &str = "best";
+
+/// Outcome of a quote on one pool, prices in quote per base.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub pool: String, // Protocol and component id
+    pub selling: String,
+    pub buying: String,
+    pub base: String,
+    pub quote: String,
+    pub requested: f64, // Amount asked, before the max_trade_usd cap
+    pub amount_in: f64,
+    pub worth_usd: f64,     // Of the amount sold
+    pub min_worth_usd: f64, // min_amount_worth_usd
+    pub amount_out: f64,
+    pub spot: f64,
+    pub reference: f64,
+    pub fee_bps: u128,
+    pub gas_units: u128,
+    pub gas_price_gwei: f64,
+    pub gas_cost_usd: f64,
+    pub gas_cost_in_output: f64,
+    pub price: f64,
+    pub price_net_gas: f64,
+    pub profit_bps: f64,
+    pub threshold_bps: f64,
+}
+
+impl Quote {
+    /// Spread of the pool against the reference, in bps.
+    pub fn spread_bps(&self) -> f64 {
+        (self.spot - self.reference) / self.reference * BASIS_POINT_DENO
+    }
+
+    /// True if the trade clears the execution threshold and the min notional, as readjust would require to create the order.
+    pub fn clears(&self) -> bool {
+        self.profit_bps > self.threshold_bps && self.worth_usd > self.min_worth_usd
+    }
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = format!("{} per {}", self.quote, self.base);
+        writeln!(f, "Pool       {}", self.pool)?;
+        writeln!(f, "Spot       {:.5} {} | Reference {:.5} | Spread {:+.2} bps", self.spot, unit, self.reference, self.spread_bps())?;
+        writeln!(f, "Fee        {} bps", self.fee_bps)?;
+        if self.amount_in < self.requested {
+            writeln!(f, "Capped     {:.5} {} to {:.5} by max_trade_usd", self.requested, self.selling, self.amount_in)?;
+        }
+        writeln!(
+            f,
+            "Swap       {:.5} {} for {:.5} {} | Worth {:.2} $ (min {:.2} $)",
+            self.amount_in, self.selling, self.amount_out, self.buying, self.worth_usd, self.min_worth_usd
+        )?;
+        writeln!(
+            f,
+            "Gas        {} units at {:.3} gwei: {:.4} $ ({:.5} {})",
+            self.gas_units, self.gas_price_gwei, self.gas_cost_usd, self.gas_cost_in_output, self.buying
+        )?;
+        writeln!(f, "Price      {:.5} {}, {:.5} net of gas", self.price, unit, self.price_net_gas)?;
+        write!(
+            f,
+            "Profit     {:+.2} bps against a {:.2} bps threshold: {}",
+            self.profit_bps,
+            self.threshold_bps,
+            if self.clears() { "would trade" } else { "would not trade" }
+        )
+    }
+}
+
+/// Quotes selling `amount` (normalized) of base (`base_to_quote`) or quote into a pool, with the readjust math:
+/// amount capped by `max_trade_usd`, output from `get_amount_out`, gas at the native gas price of the context, net price
+/// compared to `reference`.
+pub fn quote(mk: &MarketMaker, psc: &ProtoSimComp, requested: f64, base_to_quote: bool, reference: f64, context: &MarketContext) -> Result<Quote, String> {
+    let pool = format!("{} {}", psc.component.protocol_system, psc.component.id);
+    if requested <= 0. || reference <= 0. {
+        return Err(format!("Invalid amount {} or reference {}", requested, reference));
+    }
+    let amount = mk.config.max_trade_usd.and_then(|max| cap_worth(requested, base_to_quote, context, max)).unwrap_or(requested);
+    let (_, worth_usd) = worth(amount, base_to_quote, context);
+    let (selling, buying) = if base_to_quote { (&mk.base, &mk.quote) } else { (&mk.quote, &mk.base) };
+    let spot = psc.protosim.spot_price(&mk.base, &mk.quote).map_err(|e| format!("Failed to get the spot price of {}: {:?}", pool, e))?;
+    let result = psc
+        .protosim
+        .get_amount_out(powered(amount, selling.decimals), selling, buying)
+        .map_err(|e| format!("Failed to quote {} {} on {}: {:?}", amount, selling.symbol, pool, e))?;
+    let amount_out = normalized(&result.amount, buying.decimals);
+    let gas_units = result.gas.to_u128().unwrap_or_default();
+    let SwapValue {
+        gas_cost_usd,
+        gas_cost_in_output,
+        average_sell_price,
+        average_sell_price_net_gas,
+        profit_bps,
+        ..
+    } = swap_value(amount, amount_out, gas_units, base_to_quote, reference, context);
+    Ok(Quote {
+        pool,
+        selling: selling.symbol.clone(),
+        buying: buying.symbol.clone(),
+        base: mk.base.symbol.clone(),
+        quote: mk.quote.symbol.clone(),
+        requested,
+        amount_in: amount,
+        worth_usd,
+        min_worth_usd: mk.config.min_amount_worth_usd,
+        amount_out,
+        spot,
+        reference,
+        fee_bps: amm_fee_to_bps(psc.component.clone()),
+        gas_units,
+        gas_price_gwei: context.native_gas_price as f64 / 1e9,
+        gas_cost_usd,
+        gas_cost_in_output,
+        price: average_sell_price,
+        price_net_gas: average_sell_price_net_gas,
+        profit_bps,
+        threshold_bps: mk.execution_threshold_bps(),
+    })
+}
+
+/// Quotes of the pools selected by `pool`: the component with this id, or every target for `BEST_TARGET`, best first.
+pub fn select(mk: &MarketMaker, targets: &[ProtoSimComp], pool: &str, amount: f64, base_to_quote: bool, reference: f64, context: &MarketContext) -> Result<Vec<Quote>, String> {
+    let selected = targets
+        .iter()
+        .filter(|psc| pool.eq_ignore_ascii_case(BEST_TARGET) || psc.component.id.to_string().eq_ignore_ascii_case(pool))
+        .collect::<Vec<&ProtoSimComp>>();
+    if selected.is_empty() {
+        return Err(format!("No {}/{} component matching '{}'", mk.base.symbol, mk.quote.symbol, pool));
+    }
+    let mut quotes = vec![];
+    for psc in selected {
+        match quote(mk, psc, amount, base_to_quote, reference, context) {
+            Ok(quote) => quotes.push(quote),
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    if quotes.is_empty() {
+        return Err(format!("No pool matching '{}' could be quoted", pool));
+    }
+    quotes.sort_by(|a, b| b.profit_bps.partial_cmp(&a.profit_bps).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(quotes)
+}
+
+/// Connects to Tycho and the reference feed, then quotes the pools of the config pair selected by `pool` on the first snapshot.
+///
+/// A component named by id only needs to hold both tokens, `BEST_TARGET` ranks the targets of the config (pool lists and hooks applied).
+pub async fn run(config: MarketMakerConfig, env: &EnvConfig, pool: &str, amount: f64, base_to_quote: bool) -> Result<Vec<Quote>, String> {
+    let (mk, tokens) = MarketMakerBuilder::standalone(config, &env.tycho_api_key).await?;
+    let reference = mk.market_data.market_price(&mk).await?;
+    let mut stream = crate::maker::tycho::stream(&mk.config, &env.tycho_api_key, tokens.clone()).await?;
+    let snapshot = match stream.next().await {
+        Some(Ok(update)) => update,
+        Some(Err(e)) => return Err(format!("Tycho stream error: {}", e)),
+        None => return Err("Tycho stream closed before the first snapshot".to_string()),
+    };
+
+    let protosims: HashMap<String, Box<dyn ProtocolSim>> = snapshot.states.iter().map(|(id, state)| (id.to_lowercase(), state.clone())).collect();
+    let components = snapshot.new_pairs.values().filter(|cp| !cp.id.to_string().contains(NULL_ADDRESS)).cloned().collect::<Vec<_>>();
+    let pair = [mk.base.address.to_string().to_lowercase(), mk.quote.address.to_string().to_lowercase()];
+    let targets = components
+        .iter()
+        .filter(|cp| pair.iter().all(|address| cp.tokens.iter().any(|t: &Token| t.address.to_string().to_lowercase() == *address)))
+        .filter(|cp| !pool.eq_ignore_ascii_case(BEST_TARGET) || (mk.config.targets_pool(&cp.id.to_string(), true) && excluded_v4_hook(&mk.config, cp).is_none()))
+        .filter_map(|cp| {
+            protosims.get(&cp.id.to_string().to_lowercase()).map(|state| ProtoSimComp {
+                component: cp.clone(),
+                protosim: state.clone(),
+            })
+        })
+        .collect::<Vec<ProtoSimComp>>();
+    let graph = TokenGraph::new(&components, &protosims);
+    let context = mk
+        .market_data
+        .market_context(&mk, &graph, &protosims, tokens)
+        .await
+        .ok_or_else(|| "Failed to fetch the market context".to_string())?;
+    select(&mk, &targets, pool, amount, base_to_quote, reference, &context)
+}
@@ -0,0 +1,52 @@
+//! Reconnect Module
+//!
+//! A failed or closed Tycho stream is rebuilt after `stream_restart_delay_ms`, doubled on each consecutive reconnect
+//! up to `stream_restart_max_delay_ms`, so a flapping endpoint is not hammered every few seconds. The backoff starts
+//! over once a connection stayed ready for the max delay. The time from a disconnect to the stream being ready again
+//! is measured for the health endpoint, with the reconnect count.
+use std::time::{Duration, Instant};
+
+/// Backoff and recovery time of the stream reconnections.
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    delay: Duration,
+    max_delay: Duration,
+    attempts: u32,                    // Consecutive reconnects, the exponent of the backoff
+    ready_at: Option<Instant>,        // Stream ready on the current connection
+    disconnected_at: Option<Instant>, // First disconnect not recovered from yet
+}
+
+impl Reconnect {
+    pub fn new(delay_ms: u64, max_delay_ms: u64) -> Self {
+        Self {
+            delay: Duration::from_millis(delay_ms),
+            max_delay: Duration::from_millis(max_delay_ms),
+            attempts: 0,
+            ready_at: None,
+            disconnected_at: None,
+        }
+    }
+
+    /// Records the stream lost at `now`, returns the delay before connecting again.
+    pub fn disconnected(&mut self, now: Instant) -> Duration {
+        if self.ready_at.take().is_some_and(|at| now.duration_since(at) >= self.max_delay) {
+            self.attempts = 0;
+        }
+        self.disconnected_at.get_or_insert(now);
+        let delay = self.delay.saturating_mul(2u32.saturating_pow(self.attempts)).min(self.max_delay);
+        self.attempts = self.attempts.saturating_add(1);
+        delay
+    }
+
+    /// Records the stream ready at `now`, returns the time since the disconnect it recovered from (None on the first
+    /// connection).
+    pub fn ready(&mut self, now: Instant) -> Option<Duration> {
+        self.ready_at = Some(now);
+        self.disconnected_at.take().map(|at| now.duration_since(at))
+    }
+
+    /// Consecutive reconnects of the current backoff.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
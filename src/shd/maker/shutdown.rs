@@ -0,0 +1,86 @@
+//! Graceful Shutdown Module
+//!
+//! Process-wide stop flag, flipped on SIGTERM (docker stop) or Ctrl-C. Pair loops check it
+//! at the top of every block and exit between blocks, so an execution in progress completes
+//! and publishes its trades before the instance closes. Publications are synchronous, nothing
+//! is left to flush once the loop has returned.
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+/// Cloneable handle on the shutdown flag, shared by every market maker of the process.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    /// Flips the flag and wakes the loops waiting on their stream.
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once a shutdown is requested.
+    pub async fn requested(&self) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // Registered before checking the flag, so a request in between is not missed
+            notified.as_mut().enable();
+            if self.is_requested() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Spawns the signal handler requesting a shutdown on SIGTERM or Ctrl-C.
+    pub fn listen(&self) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            let mut sigterm = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                Ok(sigterm) => sigterm,
+                Err(e) => {
+                    tracing::error!("Failed to install the SIGTERM handler: {}", e);
+                    return;
+                }
+            };
+            tokio::select! {
+                _ = sigterm.recv() => tracing::warn!("🛑 SIGTERM received, stopping after the current block"),
+                _ = tokio::signal::ctrl_c() => tracing::warn!("🛑 Ctrl-C received, stopping after the current block"),
+            }
+            shutdown.request();
+        });
+    }
+}
+
+/// Runs `run` to completion, or for at most `grace` once a shutdown is requested.
+///
+/// Returns false if the grace period elapsed before `run` returned (e.g. an execution stuck on its receipt).
+pub async fn drain<F: Future>(run: F, shutdown: &Shutdown, grace: Duration) -> bool {
+    tokio::pin!(run);
+    tokio::select! {
+        _ = &mut run => return true,
+        _ = shutdown.requested() => {}
+    }
+    match tokio::time::timeout(grace, run).await {
+        Ok(_) => true,
+        Err(_) => {
+            tracing::error!("Grace period of {} ms elapsed before the market maker stopped", grace.as_millis());
+            false
+        }
+    }
+}
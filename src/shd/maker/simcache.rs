@@ -0,0 +1,225 @@
+//! Simulation Cache Module
+//!
+//! Within a block, the same pool is quoted many times by the searches: the optimizer iterations and the exact out reverse
+//! quote, each starting over the amounts probed by the previous readjustments of the pool. `get_amount_out` is memoized
+//! per (component, block, token sold, amount bucket), the bucket being the amount rounded to
+//! `sim_cache_significant_digits` significant digits, and the cache is cleared on each new block of the stream. A bucket
+//! holds the quote of one exact amount: another amount of the bucket is quoted again, and replaces it. The output and
+//! post-swap state returned are always those of the amount asked. The final quote of `readjust` and the re-validation
+//! before the execution do not go through the cache.
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use tycho_common::{
+    dto::ProtocolStateDelta,
+    models::token::Token,
+    simulation::{
+        errors::{SimulationError, TransitionError},
+        protocol_sim::{Balances, GetAmountOutResult, ProtocolSim},
+    },
+    Bytes,
+};
+
+/// Cache key: component id (lowercase), block, token sold (lowercase address) and amount bucket.
+type SimKey = (String, u64, String, String);
+
+/// Quote of the last amount of a bucket.
+#[derive(Debug)]
+struct SimEntry {
+    amount_in: BigUint,
+    amount_out: BigUint,
+    gas: BigUint,
+    new_state: Box<dyn ProtocolSim>,
+}
+
+/// Lookups of the cache, cumulated since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl SimCacheStats {
+    /// Share of the lookups answered from the cache, 0 without lookup.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Per-block memoization of `get_amount_out`, shared by the market maker and the quoting wrappers.
+#[derive(Debug, Default)]
+pub struct SimCache {
+    digits: u32, // Significant digits of the amount bucket, 0 disables the cache
+    block: AtomicU64,
+    entries: Mutex<HashMap<SimKey, SimEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    block_hits: AtomicU64,
+    block_misses: AtomicU64,
+}
+
+impl SimCache {
+    pub fn new(digits: u32) -> Self {
+        Self { digits, ..Default::default() }
+    }
+
+    /// False with `sim_cache_significant_digits = 0`, the protosims then quoting directly.
+    pub fn enabled(&self) -> bool {
+        self.digits > 0
+    }
+
+    /// Bucket of a raw amount: its value rounded to `digits` significant digits. Amounts differing in one of these
+    /// digits are in different buckets.
+    pub fn bucket(amount: &BigUint, digits: u32) -> String {
+        format!("{:.*e}", digits.saturating_sub(1) as usize, amount.to_f64().unwrap_or(f64::MAX))
+    }
+
+    /// Clears the cache on a new block message, logging the hit rate of the previous one. The states of the message
+    /// may differ from the cached ones even within the same block.
+    pub fn roll(&self, block: u64) {
+        if self.digits == 0 {
+            return;
+        }
+        self.block.store(block, Ordering::SeqCst);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+        let hits = self.block_hits.swap(0, Ordering::SeqCst);
+        let misses = self.block_misses.swap(0, Ordering::SeqCst);
+        if hits + misses > 0 {
+            let stats = self.stats();
+            tracing::debug!(
+                metric = "sim_cache",
+                hits,
+                misses,
+                hit_rate = stats.hit_rate(),
+                "Simulation cache: {} hits, {} misses on the previous block ({:.1}% since startup)",
+                hits,
+                misses,
+                stats.hit_rate() * 100.0
+            );
+        }
+    }
+
+    /// Lookups since startup.
+    pub fn stats(&self) -> SimCacheStats {
+        SimCacheStats {
+            hits: self.hits.load(Ordering::SeqCst),
+            misses: self.misses.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Quote of `amount_in` on the state of the component at the current block, from the cache when the same amount was
+    /// the last quoted of its bucket, quoted again otherwise. Failed quotes are not cached.
+    pub fn amount_out(&self, component: &str, protosim: &dyn ProtocolSim, amount_in: BigUint, token_in: &Token, token_out: &Token) -> Result<GetAmountOutResult, SimulationError> {
+        if self.digits == 0 {
+            return protosim.get_amount_out(amount_in, token_in, token_out);
+        }
+        let key = (
+            component.to_lowercase(),
+            self.block.load(Ordering::SeqCst),
+            token_in.address.to_string().to_lowercase(),
+            Self::bucket(&amount_in, self.digits),
+        );
+        if let Some(entry) = self.entries.lock().ok().and_then(|entries| {
+            entries
+                .get(&key)
+                .filter(|e| e.amount_in == amount_in)
+                .map(|e| (e.amount_out.clone(), e.gas.clone(), e.new_state.clone_box()))
+        }) {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+            self.block_hits.fetch_add(1, Ordering::SeqCst);
+            let (amount, gas, new_state) = entry;
+            return Ok(GetAmountOutResult { amount, gas, new_state });
+        }
+        self.misses.fetch_add(1, Ordering::SeqCst);
+        self.block_misses.fetch_add(1, Ordering::SeqCst);
+        let result = protosim.get_amount_out(amount_in.clone(), token_in, token_out)?;
+        if let Ok(mut entries) = self.entries.lock() {
+            let entry = SimEntry {
+                amount_in,
+                amount_out: result.amount.clone(),
+                gas: result.gas.clone(),
+                new_state: result.new_state.clone_box(),
+            };
+            entries.insert(key, entry);
+        }
+        Ok(result)
+    }
+}
+
+/// Protosim of a component quoting through the cache, passed to the optimizer and the exact out reverse quote.
+#[derive(Debug)]
+pub struct CachedSim {
+    pub component: String,
+    pub inner: Box<dyn ProtocolSim>,
+    pub cache: Arc<SimCache>,
+}
+
+impl CachedSim {
+    pub fn new(component: &str, protosim: &dyn ProtocolSim, cache: Arc<SimCache>) -> Self {
+        Self {
+            component: component.to_lowercase(),
+            inner: protosim.clone_box(),
+            cache,
+        }
+    }
+}
+
+impl ProtocolSim for CachedSim {
+    fn fee(&self) -> f64 {
+        self.inner.fee()
+    }
+
+    fn spot_price(&self, base: &Token, quote: &Token) -> Result<f64, SimulationError> {
+        self.inner.spot_price(base, quote)
+    }
+
+    fn get_amount_out(&self, amount_in: BigUint, token_in: &Token, token_out: &Token) -> Result<GetAmountOutResult, SimulationError> {
+        self.cache.amount_out(&self.component, self.inner.as_ref(), amount_in, token_in, token_out)
+    }
+
+    fn get_limits(&self, sell_token: Bytes, buy_token: Bytes) -> Result<(BigUint, BigUint), SimulationError> {
+        self.inner.get_limits(sell_token, buy_token)
+    }
+
+    fn delta_transition(&mut self, delta: ProtocolStateDelta, tokens: &HashMap<Bytes, Token>, balances: &Balances) -> Result<(), TransitionError<String>> {
+        self.inner.delta_transition(delta, tokens, balances)
+    }
+
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(Self {
+            component: self.component.clone(),
+            inner: self.inner.clone_box(),
+            cache: self.cache.clone(),
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn eq(&self, other: &dyn ProtocolSim) -> bool {
+        match other.as_any().downcast_ref::<CachedSim>() {
+            Some(other) => self.inner.eq(other.inner.as_ref()),
+            None => self.inner.eq(other),
+        }
+    }
+}
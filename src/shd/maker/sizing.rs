@@ -0,0 +1,75 @@
+//! Sizing Module
+//!
+//! First stage of `readjust`: the amount of each readjustment of a block is found on its own pool (balances, optimizer,
+//! decision engine, exact out reverse quote), independently of the other readjustments, so that these run
+//! concurrently. The limits shared by the orders of the block (exposure, notional) are applied afterwards in spread
+//! order. The valuation of the sized swap against the reference, net of its gas, is shared with `maker quote`.
+use crate::{maker::audit::Gate, opti::math::TerminationReason, types::maker::MarketContext, utils::constants::BASIS_POINT_DENO};
+
+/// Readjustment sized on its pool, before the limits shared by the orders of the block.
+#[derive(Debug, Clone)]
+pub struct SizedReadjustment {
+    pub base_to_quote: bool,                 // Selling base for quote
+    pub selling_amount: f64,                 // Normalized amount sold
+    pub exact_out_target: Option<f64>,       // Normalized amount bought exactly, None when sold exact in
+    pub termination: TerminationReason,      // Stop condition of the optimizer
+    pub bracket_width: f64,                  // Final bracket width of the optimizer
+    pub inventory_balance_normalized: f64,   // Inventory of the sold token
+    pub max_alloc: f64,                      // Inventory share available to the readjustment
+    pub pool_buying_balance_normalized: f64, // Pool balance of the bought token
+}
+
+/// Outcome of the sizing of a readjustment.
+#[derive(Debug, Clone)]
+pub enum Sizing {
+    Sized(Box<SizedReadjustment>),
+    Skipped(Gate, Vec<(&'static str, f64)>), // Gate recorded in the audit, with its values
+}
+
+/// Swap valued against the reference, net of its gas, prices in quote per base.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapValue {
+    pub gas_cost_eth: f64,
+    pub gas_cost_usd: f64,
+    pub gas_cost_in_output: f64,         // Gas paid from the bought token
+    pub average_sell_price: f64,         // Before gas
+    pub average_sell_price_net_gas: f64, // Infinite when buying base with the gas eating the whole output
+    pub profit_delta: f64,               // Net price better than the reference
+    pub profit_bps: f64,                 // Profit delta in bps of the reference
+}
+
+/// Values the sale of `selling_amount` for `amount_out` (normalized) with `gas_units`, at the gas price and rates of the
+/// context, as `readjust` decides on it.
+pub fn swap_value(selling_amount: f64, amount_out: f64, gas_units: u128, base_to_quote: bool, reference: f64, context: &MarketContext) -> SwapValue {
+    let gas_cost_eth = gas_units.saturating_mul(context.native_gas_price) as f64 / 1e18;
+    let gas_cost_in_output = if base_to_quote { gas_cost_eth / context.quote_to_eth } else { gas_cost_eth / context.base_to_eth };
+    let (average_sell_price, average_sell_price_net_gas, profit_delta) = net_price(selling_amount, amount_out, gas_cost_in_output, base_to_quote, reference);
+    SwapValue {
+        gas_cost_eth,
+        gas_cost_usd: gas_cost_eth * context.eth_to_usd,
+        gas_cost_in_output,
+        average_sell_price,
+        average_sell_price_net_gas,
+        profit_delta,
+        profit_bps: profit_delta / reference * BASIS_POINT_DENO,
+    }
+}
+
+/// Average price of a sale, before and net of `gas_cost_in_output`, and its profit against the reference.
+pub fn net_price(selling_amount: f64, amount_out: f64, gas_cost_in_output: f64, base_to_quote: bool, reference: f64) -> (f64, f64, f64) {
+    let net = amount_out - gas_cost_in_output;
+    if base_to_quote {
+        (amount_out / selling_amount, net / selling_amount, net / selling_amount - reference)
+    } else {
+        // Gas eating the whole output buys at an infinite price
+        let net_price = if net > 0. { selling_amount / net } else { f64::INFINITY };
+        (selling_amount / amount_out, net_price, reference - net_price)
+    }
+}
+
+/// Runs synchronous simulation work on the blocking thread pool, so that the simulations of concurrent readjustments
+/// neither starve the runtime nor run one after the other.
+pub async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(f)).await.map_err(|e| format!("Simulation task failed: {}", e))
+}
@@ -0,0 +1,231 @@
+//! Snapshot Module
+//!
+//! After a restart, Tycho replays the full snapshot of the network before the first stream message, minutes on
+//! mainnet. The target pools of the pair (component id, protocol, fee and latest spot price) are written every
+//! `snapshot_interval_ms` to `<token_cache_dir>/snapshot.<config id>.json`. Until the stream is ready, a snapshot younger
+//! than `snapshot_max_age_s` is evaluated against the live reference in monitor-only mode: the signals are logged and
+//! the prices published as `degraded`, but no order is ever built from them, the state being possibly stale. The
+//! protosims themselves are not persisted, their state types not being serializable. The rolling state of the adaptive
+//! threshold is written along, and restored at startup whatever the age of the snapshot. The file is written by a
+//! background writer, off the block loop.
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    maker::tycho::amm_fee_to_bps,
+    opti::adaptive::AdaptiveState,
+    types::{
+        config::MarketMakerConfig,
+        maker::{ComponentPriceData, TradeDirection},
+        tycho::ProtoSimComp,
+    },
+    utils::{background::BackgroundWriter, constants::BASIS_POINT_DENO},
+};
+
+/// Target pool of the pair, as written to the snapshot file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotPool {
+    pub address: String, // Component id (lowercase)
+    pub r#type: String,  // Protocol system
+    pub fee_bps: u128,
+    pub price: f64, // Spot price, quote per base
+}
+
+/// Target pools of a pair at one block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PoolSnapshot {
+    pub id: String, // Config id of the pair, stable across restarts
+    pub block: u64,
+    pub saved_at_s: u64, // Unix time of the write
+    pub reference_price: f64,
+    pub pools: Vec<SnapshotPool>,
+    #[serde(default)]
+    pub adaptive: Option<AdaptiveState>, // Adaptive threshold state, None when adaptive mode is off
+}
+
+impl PoolSnapshot {
+    /// Snapshot of the priced targets of a block, `cpds` being the spot prices of the `targets`, in the same order.
+    pub fn new(config: &MarketMakerConfig, block: u64, reference_price: f64, targets: &[ProtoSimComp], cpds: &[ComponentPriceData]) -> Self {
+        let pools = targets
+            .iter()
+            .zip(cpds.iter())
+            .map(|(psc, cpd)| SnapshotPool {
+                address: cpd.address.clone(),
+                r#type: cpd.r#type.clone(),
+                fee_bps: amm_fee_to_bps(psc.component.clone()),
+                price: cpd.price,
+            })
+            .collect();
+        Self {
+            id: config.id(),
+            block,
+            saved_at_s: now_s(),
+            reference_price,
+            pools,
+            adaptive: None,
+        }
+    }
+
+    /// True when written less than `max_age_s` seconds before `now_s`.
+    pub fn fresh(&self, now_s: u64, max_age_s: u64) -> bool {
+        now_s.saturating_sub(self.saved_at_s) < max_age_s
+    }
+
+    /// Spot prices of the snapshot, as published.
+    pub fn prices(&self) -> Vec<ComponentPriceData> {
+        self.pools
+            .iter()
+            .map(|pool| ComponentPriceData {
+                address: pool.address.clone(),
+                r#type: pool.r#type.clone(),
+                price: pool.price,
+            })
+            .collect()
+    }
+}
+
+/// Monitor-only evaluation of a snapshot pool against the live reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegradedSignal {
+    pub component: String,
+    pub spread_bps: f64,
+    pub threshold_bps: f64,
+    pub direction: Option<TradeDirection>, // Readjustment the live evaluation would consider, never executed
+}
+
+/// Evaluates the pools of a snapshot against the live reference, for logging: a pool beyond its watch spread and within
+/// the sanity band gets the direction of the readjustment it would call for.
+pub fn evaluate(config: &MarketMakerConfig, snapshot: &PoolSnapshot, reference: f64) -> Vec<DegradedSignal> {
+    if reference <= 0. || !reference.is_finite() {
+        return vec![];
+    }
+    snapshot
+        .pools
+        .iter()
+        .map(|pool| {
+            let spread_bps = (pool.price - reference) / reference * BASIS_POINT_DENO;
+            let threshold_bps = config.watch_spread_bps(&pool.r#type, pool.fee_bps);
+            let plausible = spread_bps.abs() <= config.max_plausible_spread_bps;
+            let direction = match (plausible && spread_bps.abs() > threshold_bps, spread_bps > 0.) {
+                (false, _) => None,
+                (true, true) => Some(TradeDirection::Buy),
+                (true, false) => Some(TradeDirection::Sell),
+            };
+            DegradedSignal {
+                component: pool.address.clone(),
+                spread_bps,
+                threshold_bps,
+                direction,
+            }
+        })
+        .collect()
+}
+
+/// Snapshot file of the pair of a config.
+pub fn path(config: &MarketMakerConfig) -> PathBuf {
+    Path::new(&config.token_cache_dir).join(format!("snapshot.{}.json", config.id()))
+}
+
+/// Snapshot of the file for the pair `id`. Missing file is None, corrupted or foreign file is None with a warning.
+pub fn load(path: &Path, id: &str) -> Option<PoolSnapshot> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<PoolSnapshot>(&contents) {
+        Ok(snapshot) if snapshot.id == id => Some(snapshot),
+        Ok(snapshot) => {
+            tracing::warn!("📸 Ignoring the snapshot {}: pools of {}, not {}", path.display(), snapshot.id, id);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("📸 Ignoring the corrupted snapshot {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Writes the snapshot to its file, through a temporary file so that a crash never leaves it truncated.
+pub fn save(path: &Path, snapshot: &PoolSnapshot) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, serde_json::to_string(snapshot).map_err(std::io::Error::other)?)?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Unix time in seconds.
+pub fn now_s() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Snapshot of the pair: written on an interval while the stream is live, read back until it is ready.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    id: String,
+    path: PathBuf,
+    interval: Option<Duration>, // None when disabled
+    max_age_s: u64,
+    saved_at: Option<Instant>,
+    latest: Option<PoolSnapshot>,
+    background: Option<BackgroundWriter>, // None when disabled
+}
+
+impl SnapshotStore {
+    pub fn new(config: &MarketMakerConfig) -> Self {
+        Self {
+            id: config.id(),
+            path: path(config),
+            interval: (config.snapshot_interval_ms > 0).then(|| Duration::from_millis(config.snapshot_interval_ms)),
+            max_age_s: config.snapshot_max_age_s,
+            saved_at: None,
+            latest: None,
+            background: (config.snapshot_interval_ms > 0).then(|| BackgroundWriter::spawn("snapshot-writer")),
+        }
+    }
+
+    /// Loads the snapshot file once, if enabled and younger than the max age at `now_s`.
+    pub fn restore(&mut self, now_s: u64) -> Option<&PoolSnapshot> {
+        if self.latest.is_none() && self.interval.is_some() {
+            self.latest = load(&self.path, &self.id).filter(|snapshot| snapshot.fresh(now_s, self.max_age_s));
+        }
+        self.latest.as_ref()
+    }
+
+    /// Returns true if a snapshot is to be written at `now`: enabled, and none written for the interval.
+    pub fn due(&self, now: Instant) -> bool {
+        match (self.interval, self.saved_at) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(interval), Some(saved_at)) => now.duration_since(saved_at) >= interval,
+        }
+    }
+
+    /// Queues the write of a snapshot and keeps it as the latest, a failed write only logged.
+    pub fn record(&mut self, snapshot: PoolSnapshot, now: Instant) {
+        if let Some(background) = self.background.as_ref() {
+            let (path, written) = (self.path.clone(), snapshot.clone());
+            background.run(move || match save(&path, &written) {
+                Ok(()) => tracing::debug!("📸 Saved the {} target pools of block {} to {}", written.pools.len(), written.block, path.display()),
+                Err(e) => tracing::warn!("📸 Failed to write the snapshot {}: {}", path.display(), e),
+            });
+        }
+        self.saved_at = Some(now);
+        self.latest = Some(snapshot);
+    }
+
+    /// Waits for the snapshots recorded so far to be written.
+    pub fn flush(&self) {
+        if let Some(background) = self.background.as_ref() {
+            background.flush();
+        }
+    }
+
+    /// Last snapshot restored or written.
+    pub fn latest(&self) -> Option<&PoolSnapshot> {
+        self.latest.as_ref()
+    }
+}
@@ -0,0 +1,52 @@
+//! Tombstone Module
+//!
+//! A component listed in the `removed_pairs` of a stream update (paused or migrated pool) is no longer tracked by
+//! Tycho, and a simulation against its last state may still pass. Its id is kept for `tombstone_blocks` blocks, and an
+//! order against it is dropped right before its preparation rather than encoded and broadcast into the pool.
+use std::collections::HashMap;
+
+/// Components recently removed from the stream, owned by the market maker so they survive iterations.
+#[derive(Debug, Clone, Default)]
+pub struct Tombstones {
+    blocks: u64,                   // 0 disables the tombstones
+    entries: HashMap<String, u64>, // Component id (lowercase) => block of its removal
+}
+
+impl Tombstones {
+    pub fn new(blocks: u64) -> Self {
+        Self { blocks, ..Default::default() }
+    }
+
+    /// Records the removal of the component from the stream at the given block.
+    pub fn bury(&mut self, component: &str, block: u64) {
+        if self.blocks == 0 {
+            return;
+        }
+        self.entries.insert(component.to_lowercase(), block);
+    }
+
+    /// Forgets the removal of a component listed again by the stream.
+    pub fn revive(&mut self, component: &str) {
+        self.entries.remove(&component.to_lowercase());
+    }
+
+    /// Block of the removal of the component, while within the window at the given block.
+    pub fn removed_at(&self, component: &str, block: u64) -> Option<u64> {
+        self.entries.get(&component.to_lowercase()).copied().filter(|removed| block < removed.saturating_add(self.blocks))
+    }
+
+    /// Drops the entries whose window has elapsed.
+    pub fn prune(&mut self, block: u64) {
+        let blocks = self.blocks;
+        self.entries.retain(|_, removed| block < removed.saturating_add(blocks));
+    }
+
+    /// Number of components currently tombstoned.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
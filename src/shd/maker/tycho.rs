@@ -3,16 +3,27 @@
 //! Integration layer for Tycho protocol providing market data streaming,
 //! protocol state management, and token pair discovery. Handles communication with
 //! Tycho RPC endpoints and manages protocol component streams.
-use std::collections::HashMap;
+use async_trait::async_trait;
+use futures::stream::{LocalBoxStream, StreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tycho_client::feed::component_tracker::ComponentFilter;
+use tycho_client::feed::synchronizer::ComponentWithState;
 use tycho_client::rpc::RPCClient;
 use tycho_client::HttpRPCClient;
 use tycho_common::dto::{PaginationParams, ProtocolStateRequestBody, ResponseToken, TokensRequestBody, VersionParam};
 use tycho_common::models::token::Token;
 use tycho_common::Bytes;
+use tycho_execution::encoding::{
+    evm::encoder_builders::TychoRouterEncoderBuilder,
+    models::{EncodedSolution, Solution, UserTransferType},
+};
 use tycho_simulation::evm::engine_db::tycho_db::PreCachedDB;
 use tycho_simulation::evm::protocol::ekubo::state::EkuboState;
-use tycho_simulation::evm::protocol::filters::{balancer_v2_pool_filter, curve_pool_filter};
+use tycho_simulation::evm::protocol::filters::{balancer_v2_pool_filter, curve_pool_filter, uniswap_v4_pool_with_hook_filter};
 use tycho_simulation::evm::protocol::uniswap_v3::state::UniswapV3State;
 use tycho_simulation::evm::protocol::uniswap_v4::state::UniswapV4State;
 use tycho_simulation::evm::protocol::vm::state::EVMPoolState;
@@ -22,9 +33,10 @@ use tycho_simulation::evm::{protocol::uniswap_v2::state::UniswapV2State, stream:
 use alloy_chains::NamedChain;
 use tycho_simulation::protocol::models::ProtocolComponent;
 
-use crate::types::config::MarketMakerConfig;
-use crate::types::tycho::{AmmType, PsbConfig, TychoSupportedProtocol};
-use crate::utils::constants::BASIS_POINT_DENO;
+use crate::types::config::{MarketMakerConfig, NetworkName};
+use crate::types::moni::{AlertKind, NewAlertMessage};
+use crate::types::tycho::{AmmType, ProtoSimComp, PsbConfig, SharedUpdate, TychoSupportedProtocol};
+use crate::utils::constants::{BASIS_POINT_DENO, TYCHO_AUTH_BACKOFF_MS, TYCHO_AUTH_MAX_ATTEMPTS, TYCHO_AUTH_MAX_BACKOFF_MS, TYCHO_TOKENS_PAGE_SIZE, UNKNOWN_AMM_FEE_BPS};
 
 /// Chain type aliases to resolve library conflicts between different Tycho modules.
 pub type ChainCommon = tycho_common::dto::Chain;
@@ -58,8 +70,11 @@ pub fn get_alloy_chain(network: String) -> Result<NamedChain, String> {
     }
 }
 
+/// Unknown AMM types already warned about, once per `protocol_type_name`.
+static UNKNOWN_AMM_TYPES: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
 /// Converts AMM protocol fees to basis points based on protocol type.
-/// Extracts fee from static_attributes and converts using protocol-specific scaling.
+/// Extracts fee from static_attributes and converts using protocol-specific scaling, saturating on absurd values.
 pub fn amm_fee_to_bps(cp: ProtocolComponent) -> u128 {
     let value = cp
         .static_attributes
@@ -71,15 +86,61 @@ pub fn amm_fee_to_bps(cp: ProtocolComponent) -> u128 {
     let fee = value.trim_start_matches("0x");
     let fee = u128::from_str_radix(fee, 16).unwrap_or(0);
 
-    match AmmType::from(cp.protocol_type_name.as_str()) {
-        AmmType::PancakeswapV2 | AmmType::Sushiswap | AmmType::UniswapV2 => fee, // Already in bps
-        AmmType::PancakeswapV3 | AmmType::UniswapV3 | AmmType::UniswapV4 => fee * (BASIS_POINT_DENO as u128) / 1_000_000,
-        AmmType::Curve => 4,   // Not implemented, assuming 4 bps by default
-        AmmType::EkuboV2 => 0, // Not implemented, assuming 0 bps by default
-        AmmType::Balancer => (fee * (BASIS_POINT_DENO as u128)) / 1e18 as u128,
+    match AmmType::of(&cp) {
+        Some(AmmType::PancakeswapV2 | AmmType::Sushiswap | AmmType::UniswapV2) => fee, // Already in bps
+        Some(AmmType::PancakeswapV3 | AmmType::UniswapV3 | AmmType::UniswapV4) => fee.saturating_mul(BASIS_POINT_DENO as u128) / 1_000_000,
+        Some(AmmType::Curve) if fee > 0 => fee.saturating_mul(BASIS_POINT_DENO as u128) / 10_000_000_000, // 1e10 denominator
+        Some(AmmType::Curve) => 4,                                                                        // Fee not in the static attributes, assuming 4 bps by default
+        Some(AmmType::EkuboV2) => 0,                                                                      // Not implemented, assuming 0 bps by default
+        Some(AmmType::Balancer | AmmType::BalancerV3 | AmmType::MaverickV2) => fee.saturating_mul(BASIS_POINT_DENO as u128) / 1e18 as u128,
+        None => {
+            let mut warned = UNKNOWN_AMM_TYPES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+            if warned.insert(cp.protocol_type_name.clone()) {
+                tracing::warn!("Unknown AMM type {} of {}, assuming a fee of {} bps", cp.protocol_type_name, cp.protocol_system, UNKNOWN_AMM_FEE_BPS);
+            }
+            UNKNOWN_AMM_FEE_BPS
+        }
     }
 }
 
+/// Indices of the base and quote tokens in the tokens of a component, which may list them in any order and hold other
+/// tokens (Balancer, Curve). Err if either is missing or listed more than once.
+pub fn pair_indices(cp: &ProtocolComponent, base: &Token, quote: &Token) -> Result<(usize, usize), String> {
+    let index = |token: &Token| {
+        let address = token.address.to_string().to_lowercase();
+        let found = cp
+            .tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.address.to_string().to_lowercase() == address)
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        match found.as_slice() {
+            [i] => Ok(*i),
+            [] => Err(format!("{} not in the component tokens", token.symbol)),
+            _ => Err(format!("{} listed {} times in the component tokens", token.symbol, found.len())),
+        }
+    };
+    Ok((index(base)?, index(quote)?))
+}
+
+/// Hook address of a Uniswap V4 component, None for other protocols and pools without hook.
+pub fn v4_hook(cp: &ProtocolComponent) -> Option<String> {
+    if cp.protocol_system != TychoSupportedProtocol::UniswapV4.to_string() {
+        return None;
+    }
+    let hooks = cp.static_attributes.get("hooks")?;
+    if hooks.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Some(hooks.to_string().to_lowercase())
+}
+
+/// Hook address of a Uniswap V4 component excluded by `allow_v4_hooked_pools` and `v4_hook_allowlist`, None if the component is accepted.
+pub fn excluded_v4_hook(mmc: &MarketMakerConfig, cp: &ProtocolComponent) -> Option<String> {
+    v4_hook(cp).filter(|hook| !mmc.accepts_v4_hook(hook))
+}
+
 /// Formats protocol component information for readable display.
 /// Returns formatted string with truncated ID, protocol system, and fee in bps.
 pub fn cpname(cp: ProtocolComponent) -> String {
@@ -88,12 +149,34 @@ pub fn cpname(cp: ProtocolComponent) -> String {
     format!("[{} {:>15} {:>3}]", addr, cp.protocol_system, fee)
 }
 
-/// Filters and converts ResponseToken array to valid Token array.
+/// Filters and converts ResponseToken array to valid Token array, on the chain of the network.
 ///
-/// Removes tokens with invalid symbols, addresses, or control characters.
-fn sanitize(input: Vec<ResponseToken>, chain: ChainCommon) -> Vec<Token> {
+/// Removes tokens with missing or zero gas, invalid addresses, and symbols holding anything but printable ASCII
+/// (spaces and control characters included). The length of the symbol is not checked.
+pub fn sanitize(input: Vec<ResponseToken>, chain: ChainCommon) -> Vec<Token> {
+    retain_valid(input.iter().filter_map(|t| convert(t, chain)).collect())
+}
+
+/// Converts a token of the Tycho API, None if its address is invalid.
+fn convert(t: &ResponseToken, chain: ChainCommon) -> Option<Token> {
+    let addr = tycho_simulation::tycho_core::Bytes::from_str(t.address.clone().to_string().as_str()).ok()?;
+    Some(Token {
+        address: addr,
+        decimals: t.decimals, // Now u32, not usize
+        symbol: t.symbol.clone(),
+        // CONSERVATIVE: Token.gas changed from BigUint to Vec<Option<u64>> in tycho-common 0.96.1
+        gas: t.gas.clone(),
+        // FIXED: Extract chain from network instead of hardcoding Ethereum
+        chain: chain.into(), // Use actual network chain
+        quality: 100,        // High quality since we filter by quality
+        tax: 0,              // Assume no tax by default
+    })
+}
+
+/// Removes tokens with missing or zero gas, invalid symbols or addresses, keeping the first gas value only.
+fn retain_valid(input: Vec<Token>) -> Vec<Token> {
     let mut tokens = vec![];
-    for t in input.iter() {
+    for mut t in input.into_iter() {
         let g = match t.gas.first() {
             Some(Some(g)) if *g > 0 => *g,
             _ => {
@@ -101,19 +184,8 @@ fn sanitize(input: Vec<ResponseToken>, chain: ChainCommon) -> Vec<Token> {
                 continue;
             }
         };
-        if let Ok(addr) = tycho_simulation::tycho_core::Bytes::from_str(t.address.clone().to_string().as_str()) {
-            tokens.push(Token {
-                address: addr,
-                decimals: t.decimals, // Now u32, not usize
-                symbol: t.symbol.clone(),
-                // CONSERVATIVE: Token.gas changed from BigUint to Vec<Option<u64>> in tycho-common 0.96.1
-                gas: vec![Some(g)], // Wrap in Vec for new API
-                // FIXED: Extract chain from network instead of hardcoding Ethereum
-                chain: chain.into(), // Use actual network chain
-                quality: 100,        // High quality since we filter by quality
-                tax: 0,              // Assume no tax by default
-            });
-        }
+        t.gas = vec![Some(g)];
+        tokens.push(t);
     }
     tokens
         .into_iter()
@@ -153,21 +225,113 @@ pub async fn scope(config: MarketMakerConfig, key: Option<&str>) -> Vec<Token> {
         .collect::<Vec<Token>>()
 }
 
+/// Failure of a Tycho API call, a rejected API key told apart from the other failures.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TychoApiError {
+    Auth(String),
+    Other(String),
+}
+
+impl TychoApiError {
+    /// Classifies the error message of a Tycho call (RPC client or stream builder).
+    pub fn classify(message: String) -> Self {
+        if auth_failure(&message) {
+            TychoApiError::Auth(message)
+        } else {
+            TychoApiError::Other(message)
+        }
+    }
+}
+
+impl std::fmt::Display for TychoApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TychoApiError::Auth(e) => write!(f, "Tycho API key rejected (expired or invalid TYCHO_API_KEY): {}", e),
+            TychoApiError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// True when a Tycho error reports a rejected API key: a 401 or 403 status, or their reason phrase.
+/// The client only surfaces the status in its error message.
+pub fn auth_failure(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    let status = lower.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| word == "401" || word == "403");
+    status || ["unauthorized", "forbidden", "invalid api key", "invalid token"].iter().any(|phrase| lower.contains(phrase))
+}
+
+/// Delay before the retry following the `attempt`-th rejected call (0-based): TYCHO_AUTH_BACKOFF_MS doubled each time,
+/// capped at TYCHO_AUTH_MAX_BACKOFF_MS.
+pub fn auth_backoff(attempt: u32) -> Duration {
+    let delay = TYCHO_AUTH_BACKOFF_MS.saturating_mul(2u64.saturating_pow(attempt));
+    Duration::from_millis(delay.min(TYCHO_AUTH_MAX_BACKOFF_MS))
+}
+
+/// Waits before retrying a Tycho call rejected for its API key, `what` naming it in the logs and the `TychoAuth` alert.
+/// False, without waiting, for another failure or once TYCHO_AUTH_MAX_ATTEMPTS are spent (`attempt` is 0-based).
+pub async fn retry_auth(mmc: &MarketMakerConfig, what: &str, error: &TychoApiError, attempt: u32) -> bool {
+    if !matches!(error, TychoApiError::Auth(_)) || attempt + 1 >= TYCHO_AUTH_MAX_ATTEMPTS {
+        return false;
+    }
+    let delay = auth_backoff(attempt);
+    let message = format!("{} while {}, retrying in {} s ({}/{})", error, what, delay.as_secs(), attempt + 1, TYCHO_AUTH_MAX_ATTEMPTS);
+    tracing::error!("🔑 {}", message);
+    let alert = NewAlertMessage {
+        identifier: mmc.id(),
+        kind: AlertKind::TychoAuth,
+        message,
+        pnl: None,
+    };
+    if mmc.publish_events {
+        let _ = crate::data::r#pub::alert(alert.clone());
+    }
+    crate::utils::alert::notify(alert);
+    tokio::time::sleep(delay).await;
+    true
+}
+
+/// Runs a Tycho API call, a rejected key alerted and retried after `auth_backoff` instead of failing at once or looping
+/// tightly. Other failures return at once.
+pub async fn with_auth_retry<T, F, Fut>(mmc: &MarketMakerConfig, what: &str, mut call: F) -> Result<T, TychoApiError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, TychoApiError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match call().await {
+            Err(e) => {
+                if !retry_auth(mmc, what, &e, attempt).await {
+                    return Err(e);
+                }
+                attempt += 1;
+            }
+            outcome => return outcome,
+        }
+    }
+}
+
 /// Fetches specific tokens by their addresses from Tycho API.
 /// Queries Tycho API for specific tokens with quality filter of 100.
 pub async fn specific(mmc: MarketMakerConfig, key: Option<&str>, addresses: Vec<String>) -> Option<Vec<Token>> {
+    let network = mmc.network_name.clone();
+    fetch_specific(mmc, key, addresses)
+        .await
+        .inspect_err(|e| tracing::error!("Failed to get tokens on network {}: {}", network, e))
+        .ok()
+}
+
+/// Specific tokens of the Tycho API, see `specific`, the failure classified.
+pub async fn fetch_specific(mmc: MarketMakerConfig, key: Option<&str>, addresses: Vec<String>) -> Result<Vec<Token>, TychoApiError> {
     tracing::info!("Getting specific tokens for network {}", mmc.network_name.as_str().to_string());
 
-    let Ok(client) = HttpRPCClient::new(format!("https://{}", mmc.tycho_api).as_str(), key) else {
-        tracing::error!("Failed to create client");
-        return None;
-    };
+    let client = HttpRPCClient::new(format!("https://{}", mmc.tycho_api).as_str(), key).map_err(|e| TychoApiError::classify(format!("Failed to create client: {}", e)))?;
 
     let addresses = addresses.iter().map(|a| Bytes::from_str(a.to_lowercase().as_str()).unwrap()).collect::<Vec<Bytes>>();
     let (chain, _) = chain(mmc.network_name.as_str().to_string()).expect("Invalid chain");
     let req = TokensRequestBody {
         token_addresses: Some(addresses.clone()),
-        min_quality: Some(100),
+        min_quality: None, // Configured explicitly, never filtered out
         traded_n_days_ago: None,
         chain,
         pagination: PaginationParams { page: 0, page_size: 500_i64 },
@@ -176,73 +340,404 @@ pub async fn specific(mmc: MarketMakerConfig, key: Option<&str>, addresses: Vec<
     match client.get_tokens(&req.clone()).await {
         Ok(result) => {
             let tokens = sanitize(result.tokens, chain); // Pass chain to sanitize
-            Some(tokens)
+            Ok(tokens)
         }
-        Err(e) => {
-            tracing::error!("Failed to get tokens on network {}: {:?}", mmc.network_name.as_str().to_string(), e.to_string());
-            None
+        Err(e) => Err(TychoApiError::classify(e.to_string())),
+    }
+}
+
+/// Pages of the Tycho token list, a trait so that the pagination is tested against a mock client.
+#[async_trait]
+pub trait TokenPages: Send + Sync {
+    /// Tokens of page `page` (from 0), as listed by the API, before conversion and sanitization.
+    async fn page(&self, page: i64, page_size: i64) -> Result<Vec<ResponseToken>, TychoApiError>;
+}
+
+/// Token list of the Tycho RPC, filtered by quality and recency.
+pub struct TychoTokens {
+    pub client: HttpRPCClient,
+    pub chain: ChainCommon,
+    pub min_quality: i32,
+    pub traded_n_days_ago: Option<u64>, // None for every token, traded or not
+}
+
+#[async_trait]
+impl TokenPages for TychoTokens {
+    async fn page(&self, page: i64, page_size: i64) -> Result<Vec<ResponseToken>, TychoApiError> {
+        let req = TokensRequestBody {
+            token_addresses: None,
+            min_quality: Some(self.min_quality),
+            traded_n_days_ago: self.traded_n_days_ago,
+            chain: self.chain,
+            pagination: PaginationParams { page, page_size },
+        };
+        let response = self.client.get_tokens(&req).await.map_err(|e| TychoApiError::classify(e.to_string()))?;
+        Ok(response.tokens)
+    }
+}
+
+/// Reads the pages of the token list until a short one, or until `max` valid tokens (0 for no cap), each page
+/// sanitized for `chain`. A page is short on the tokens listed by the API, not on those left after the sanitization.
+pub async fn paginate(pages: &dyn TokenPages, chain: ChainCommon, page_size: i64, max: usize) -> Result<Vec<Token>, TychoApiError> {
+    let mut tokens = vec![];
+    let mut page = 0;
+    loop {
+        let fetched = pages.page(page, page_size).await?;
+        let last = (fetched.len() as i64) < page_size;
+        tokens.append(&mut sanitize(fetched, chain));
+        if last || (max > 0 && tokens.len() >= max) {
+            break;
         }
+        page += 1;
+    }
+    if max > 0 && tokens.len() > max {
+        tracing::warn!("Token list truncated to token_max = {} tokens", max);
+        tokens.truncate(max);
     }
+    Ok(tokens)
 }
 
 /// Fetches all available tokens from Tycho API for a network.
-/// Retrieves all tokens with quality >= 100, traded in last 7 days, max 3000 tokens.
+/// Retrieves every page of the tokens with quality >= `token_min_quality`, traded in the last `token_traded_days` days,
+/// up to `token_max` tokens.
 pub async fn tokens(mmc: MarketMakerConfig, key: Option<&str>) -> Option<Vec<Token>> {
+    let network = mmc.network_name.clone();
+    fetch_tokens(mmc, key).await.inspect_err(|e| tracing::error!("Failed to get tokens on network {}: {}", network, e)).ok()
+}
+
+/// All the tokens of the Tycho API, see `tokens`, the failure classified.
+pub async fn fetch_tokens(mmc: MarketMakerConfig, key: Option<&str>) -> Result<Vec<Token>, TychoApiError> {
     tracing::info!("Getting tokens for network {}", mmc.network_name.as_str());
 
-    let Ok(client) = HttpRPCClient::new(format!("https://{}", mmc.tycho_api).as_str(), key) else {
-        tracing::error!("Failed to create client");
-        return None;
-    };
+    let client = HttpRPCClient::new(format!("https://{}", mmc.tycho_api).as_str(), key).map_err(|e| TychoApiError::classify(format!("Failed to create client: {}", e)))?;
 
     let start_time = std::time::SystemTime::now();
     let (chain, _) = chain(mmc.network_name.as_str().to_string()).expect("Invalid chain");
+    let pages = TychoTokens {
+        client,
+        chain,
+        min_quality: mmc.token_min_quality,
+        traded_n_days_ago: (mmc.token_traded_days > 0).then_some(mmc.token_traded_days),
+    };
+    let tokens = paginate(&pages, chain, TYCHO_TOKENS_PAGE_SIZE, mmc.token_max).await?;
+    let elapsed = start_time.elapsed().unwrap_or_default().as_millis();
+    tracing::info!("Got {} tokens in {} ms", tokens.len(), elapsed);
+    Ok(tokens)
+}
 
-    match client.get_all_tokens(chain, Some(100), Some(7), 3000).await {
-        Ok(result) => {
-            let tokens = sanitize(result, chain); // Pass chain to sanitize
-            let elapsed = start_time.elapsed().unwrap_or_default().as_millis();
-            tracing::info!("Got {} tokens in {} ms", tokens.len(), elapsed);
-            Some(tokens)
-        }
-        Err(e) => {
-            tracing::error!("Failed to get tokens on network {}: {:?}", mmc.network_name.as_str().to_string(), e.to_string());
-            None
-        }
+/// Protocols registered on the Tycho stream: `tycho_protocols`, or every protocol available on the network if empty.
+pub fn stream_protocols(mmc: &MarketMakerConfig) -> Vec<TychoSupportedProtocol> {
+    if !mmc.tycho_protocols.is_empty() {
+        return mmc.tycho_protocols.iter().filter_map(|name| TychoSupportedProtocol::from_str(name).ok()).collect();
+    }
+    match NetworkName::from_str(mmc.network_name.as_str()) {
+        Ok(network) => TychoSupportedProtocol::ALL.into_iter().filter(|protocol| protocol.is_available_on(&network)).collect(),
+        Err(_) => vec![],
     }
 }
 
+/// Opens the Tycho stream of a config outside of the market maker loop (one-off commands), as owned updates.
+/// The first update is the snapshot of the components.
+pub async fn stream(mmc: &MarketMakerConfig, key: &str, tokens: Vec<Token>) -> Result<LocalBoxStream<'static, Result<SharedUpdate, String>>, String> {
+    let psbc = PsbConfig {
+        filter: ComponentFilter::with_tvl_range(mmc.tvl_remove_threshold, mmc.tvl_add_threshold),
+    };
+    let stream = psb(mmc.clone(), key.to_string(), psbc, tokens)
+        .await
+        .build()
+        .await
+        .map_err(|e| format!("Failed to build the Tycho stream: {}", e))?;
+    Ok(stream.map(|msg| msg.map(SharedUpdate::from).map_err(|e| format!("{:?}", e))).boxed_local())
+}
+
 /// Creates and configures a ProtocolStreamBuilder for streaming AMM updates.
-/// Sets up stream for UniswapV2, V3, V4 protocols with provided filters.
+/// Registers the protocols of `stream_protocols` with their state type and filters.
 pub async fn psb(mmc: MarketMakerConfig, key: String, psbc: PsbConfig, tokens: Vec<Token>) -> ProtocolStreamBuilder {
-    let (_, chain) = crate::types::tycho::chain(mmc.network_name.clone().as_str().to_string()).expect("Invalid chain");
+    let (_, chain) = chain(mmc.network_name.clone().as_str().to_string()).expect("Invalid chain");
     let filter = psbc.filter.clone();
     let mut hmt = HashMap::new();
     tokens.iter().for_each(|t| {
         hmt.insert(t.address.clone(), t.clone());
     });
     tracing::debug!("Tycho endpoint: {} and chain: {}", mmc.tycho_api, chain);
-    let mut psb = ProtocolStreamBuilder::new(&mmc.tycho_api, chain)
-        .exchange::<UniswapV2State>(TychoSupportedProtocol::UniswapV2.to_string().as_str(), filter.clone(), None)
-        .exchange::<UniswapV3State>(TychoSupportedProtocol::UniswapV3.to_string().as_str(), filter.clone(), None)
-        .exchange::<UniswapV4State>(TychoSupportedProtocol::UniswapV4.to_string().as_str(), filter.clone(), None) // Some(u4))
-        .auth_key(Some(key.clone()))
+    // Hooked V4 pools are dropped from the stream unless some can be monitored, then they are filtered at target selection
+    let v4_filter = if mmc.allow_v4_hooked_pools || !mmc.v4_hook_allowlist.is_empty() {
+        None
+    } else {
+        Some(uniswap_v4_pool_with_hook_filter as fn(&ComponentWithState) -> bool)
+    };
+    let protocols = stream_protocols(&mmc);
+    tracing::info!("Streaming protocols: {}", protocols.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(", "));
+    let mut psb = ProtocolStreamBuilder::new(&mmc.tycho_api, chain);
+    for protocol in protocols {
+        let name = protocol.to_string();
+        psb = match protocol {
+            TychoSupportedProtocol::UniswapV2 | TychoSupportedProtocol::Sushiswap | TychoSupportedProtocol::PancakeswapV2 => psb.exchange::<UniswapV2State>(&name, filter.clone(), None),
+            TychoSupportedProtocol::UniswapV3 | TychoSupportedProtocol::PancakeswapV3 => psb.exchange::<UniswapV3State>(&name, filter.clone(), None),
+            TychoSupportedProtocol::UniswapV4 => psb.exchange::<UniswapV4State>(&name, filter.clone(), v4_filter),
+            TychoSupportedProtocol::EkuboV2 => psb.exchange::<EkuboState>(&name, filter.clone(), None),
+            TychoSupportedProtocol::BalancerV2 => psb.exchange::<EVMPoolState<PreCachedDB>>(&name, filter.clone(), Some(balancer_v2_pool_filter)),
+            TychoSupportedProtocol::Curve => psb.exchange::<EVMPoolState<PreCachedDB>>(&name, filter.clone(), Some(curve_pool_filter)),
+        };
+    }
+    psb.auth_key(Some(key.clone()))
         .skip_state_decode_failures(true)
         .set_tokens(hmt.clone()) // ALL Tokens
-        .await;
+        .await
+}
+
+/// Source of the token balances of a pool, bounding the share of it a readjustment may take.
+///
+/// Read from the Tycho RPC when trading, from the fill model of `maker::backtest` when replaying prices.
+#[async_trait]
+pub trait PoolBalances: Send + Sync {
+    /// Raw balances of the component, keyed by lowercase token address. None when unavailable.
+    async fn get(&self, cp: &ProtocolComponent) -> Option<HashMap<String, u128>>;
+
+    /// Raw balances of several components at `block`, keyed by lowercase component id, without the unavailable ones.
+    /// One `get` per component, run concurrently, unless the source batches them.
+    async fn batch(&self, cps: &[ProtocolComponent], _block: u64) -> HashMap<String, HashMap<String, u128>> {
+        let gets = cps.iter().map(|cp| async move { (cp.id.to_string().to_lowercase(), self.get(cp).await) });
+        futures::future::join_all(gets).await.into_iter().filter_map(|(id, b)| b.map(|b| (id, b))).collect()
+    }
+}
+
+/// Protocol state queries of the Tycho RPC, a trait so that the batching is tested against a mock client.
+#[async_trait]
+pub trait ProtocolStates: Send + Sync {
+    /// Raw balances of components of one protocol system, keyed by lowercase component id then lowercase token address.
+    async fn balances(&self, system: &str, ids: Vec<String>) -> Result<HashMap<String, HashMap<String, u128>>, String>;
+}
+
+/// Protocol states of the Tycho RPC of a config.
+pub struct TychoStates {
+    pub config: MarketMakerConfig,
+    pub key: String,
+}
+
+#[async_trait]
+impl ProtocolStates for TychoStates {
+    async fn balances(&self, system: &str, ids: Vec<String>) -> Result<HashMap<String, HashMap<String, u128>>, String> {
+        let client = HttpRPCClient::new(format!("https://{}", self.config.tycho_api).as_str(), Some(self.key.as_str())).map_err(|e| format!("Failed to create client: {:?}", e.to_string()))?;
+        let (chain, _) = chain(self.config.network_name.as_str().to_string()).ok_or_else(|| format!("Invalid chain {}", self.config.network_name))?;
+        let mut output = HashMap::new();
+        // Maximum page size supported is 100
+        for ids in ids.chunks(100) {
+            let body = ProtocolStateRequestBody {
+                protocol_ids: Some(ids.to_vec()),
+                protocol_system: system.to_string(), // Single system per request
+                chain,
+                include_balances: true,
+                version: VersionParam::default(),
+                pagination: PaginationParams { page: 0, page_size: 100 },
+            };
+            let response = client
+                .get_protocol_states(&body)
+                .await
+                .map_err(|e| format!("Failed to get protocol states of {} on {}: {:?}", ids.join(","), system, e.to_string()))?;
+            for state in response.states {
+                let mut balances = HashMap::new();
+                for (token, balance) in state.balances.iter() {
+                    if let Ok(b) = u128::from_str_radix(balance.to_string().trim_start_matches("0x"), 16) {
+                        balances.insert(token.to_string().to_lowercase(), b);
+                    }
+                }
+                output.insert(state.component_id.to_lowercase(), balances);
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Component balances read at a block, served again within the same block only.
+#[derive(Debug, Default)]
+pub struct BalanceCache {
+    entries: Mutex<HashMap<String, (u64, HashMap<String, u128>)>>, // Keyed by lowercase component id, with the block read at
+}
+
+impl BalanceCache {
+    /// Balances of the component read at `block`, None if missing or read at another block.
+    pub fn get(&self, id: &str, block: u64) -> Option<HashMap<String, u128>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(id).filter(|(at, _)| *at == block).map(|(_, balances)| balances.clone())
+    }
+
+    /// Stores the balances of a component read at `block`, dropping the entries of older blocks.
+    pub fn insert(&self, id: &str, block: u64, balances: HashMap<String, u128>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (at, _)| *at >= block);
+        entries.insert(id.to_string(), (block, balances));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
-    if mmc.network_name.as_str() == "ethereum" {
-        tracing::trace!("Adding mainnet-specific exchanges");
-        psb = psb
-            .exchange::<UniswapV2State>(TychoSupportedProtocol::Sushiswap.to_string().as_str(), filter.clone(), None)
-            .exchange::<UniswapV2State>(TychoSupportedProtocol::PancakeswapV2.to_string().as_str(), filter.clone(), None)
-            .exchange::<UniswapV3State>(TychoSupportedProtocol::PancakeswapV3.to_string().as_str(), filter.clone(), None)
-            .exchange::<EkuboState>(TychoSupportedProtocol::EkuboV2.to_string().as_str(), filter.clone(), None)
-            .exchange::<EVMPoolState<PreCachedDB>>(TychoSupportedProtocol::BalancerV2.to_string().as_str(), filter.clone(), Some(balancer_v2_pool_filter))
-            .exchange::<EVMPoolState<PreCachedDB>>(TychoSupportedProtocol::Curve.to_string().as_str(), filter.clone(), Some(curve_pool_filter));
+/// Balances of the components at `block`, keyed by lowercase component id: the cached ones for that block, the others
+/// in one request per protocol system, the systems queried concurrently. A failed system leaves its components out.
+pub async fn batch_component_balances(client: &dyn ProtocolStates, cache: &BalanceCache, cps: &[ProtocolComponent], block: u64) -> HashMap<String, HashMap<String, u128>> {
+    let mut output = HashMap::new();
+    let mut missing: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for cp in cps {
+        let id = cp.id.to_string().to_lowercase();
+        match cache.get(&id, block) {
+            Some(balances) => {
+                output.insert(id, balances);
+            }
+            None => {
+                let ids = missing.entry(cp.protocol_system.clone()).or_default();
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+    let requests = missing.into_iter().map(|(system, ids)| async move { (client.balances(&system, ids).await, system) });
+    for (fetched, system) in futures::future::join_all(requests).await {
+        match fetched {
+            Ok(fetched) => {
+                for (id, balances) in fetched {
+                    cache.insert(&id, block, balances.clone());
+                    output.insert(id, balances);
+                }
+            }
+            Err(e) => tracing::error!("Failed to get the component balances of {}: {}", system, e),
+        }
+    }
+    output
+}
+
+/// Pool balances of the Tycho RPC, batched per protocol system and cached for the block.
+pub struct TychoBalances {
+    pub config: MarketMakerConfig,
+    pub key: String,
+    pub cache: Arc<BalanceCache>,
+}
+
+#[async_trait]
+impl PoolBalances for TychoBalances {
+    async fn get(&self, cp: &ProtocolComponent) -> Option<HashMap<String, u128>> {
+        get_component_balances(self.config.clone(), cp.clone(), self.key.clone()).await
+    }
+
+    async fn batch(&self, cps: &[ProtocolComponent], block: u64) -> HashMap<String, HashMap<String, u128>> {
+        let states = TychoStates {
+            config: self.config.clone(),
+            key: self.key.clone(),
+        };
+        batch_component_balances(&states, &self.cache, cps, block).await
+    }
+}
+
+/// Balances of the component read from its streamed state, keyed by lowercase token address, without any network call.
+/// Only the UniswapV2-style pools (UniswapV2, Sushiswap, PancakeswapV2) hold their reserves in the state, None for the others.
+pub fn component_balances_local(psc: &ProtoSimComp) -> Option<HashMap<String, u128>> {
+    let state = psc.protosim.as_any().downcast_ref::<UniswapV2State>()?;
+    let mut tokens = psc.component.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect::<Vec<_>>();
+    if tokens.len() != 2 {
+        return None;
     }
+    // reserve0 is the balance of the lowest token address, whatever the order of the component tokens
+    tokens.sort();
+    let reserves = [u128::try_from(state.reserve0).ok()?, u128::try_from(state.reserve1).ok()?];
+    Some(tokens.into_iter().zip(reserves).collect())
+}
+
+/// Encodes solutions into router swap data, a trait so that the calldata built around it is tested against a stub.
+pub trait SolutionEncoder: Send + Sync {
+    /// One encoded solution per solution, in order.
+    fn encode(&self, solutions: Vec<Solution>) -> Result<Vec<EncodedSolution>, String>;
+}
+
+/// Encoding run on the encoder thread.
+type EncoderJob = Box<dyn FnOnce() + Send>;
 
-    psb
+/// Dedicated thread of the encodings reading `RPC_URL` from the environment, started on first use.
+static ENCODER: OnceLock<mpsc::Sender<EncoderJob>> = OnceLock::new();
+
+/// Runs `f` on the encoder thread with the `RPC_URL` environment variable set to `url`, restored afterwards, and waits
+/// for its output. The approvals checks of some swap encoders (Curve, Balancer V2) build their own RPC client from that
+/// variable only, tycho-execution takes no RPC in its encoder builder. The encodings of every pair run one at a time on
+/// this thread, the only writer of the variable, so pairs with different RPCs never see each other's.
+pub fn with_rpc_url<T: Send + 'static>(url: &str, f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    let encoder = ENCODER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<EncoderJob>();
+        std::thread::Builder::new()
+            .name("encoder".to_string())
+            .spawn(move || receiver.into_iter().for_each(|job| job()))
+            .expect("Failed to start the encoder thread");
+        sender
+    });
+    let url = url.to_string();
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let job: EncoderJob = Box::new(move || {
+        let previous = std::env::var_os("RPC_URL");
+        // SAFETY: set_var races with readers of the environment on other threads. Readers going through std::env
+        // take the same lock as set_var, and this thread is the only writer, so no two encodings interleave. C code
+        // calling getenv directly on another thread (e.g. a DNS resolution) is the hazard left, which only an
+        // explicit RPC in the upstream encoder builder would remove.
+        unsafe { std::env::set_var("RPC_URL", &url) };
+        let output = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        match previous {
+            Some(previous) => unsafe { std::env::set_var("RPC_URL", previous) },
+            None => unsafe { std::env::remove_var("RPC_URL") },
+        }
+        let _ = sender.send(output);
+    });
+    encoder.send(job).map_err(|_| "Encoder thread stopped".to_string())?;
+    match receiver.recv() {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(_)) => Err("Encoding panicked".to_string()),
+        Err(_) => Err("Encoder thread stopped".to_string()),
+    }
+}
+
+/// Tycho router encoder of the chain.
+pub struct TychoEncoder {
+    pub chain: ChainSimu,
+    pub permit2: bool,   // Transfers through Permit2, for the signed permits of permit2_approval
+    pub rpc_url: String, // RPC of the approvals checks of the swap encoders
+}
+
+impl TychoEncoder {
+    /// Encoder of the network of a config, reading through its RPC pool. None if the network is unsupported.
+    pub fn of(config: &MarketMakerConfig) -> Option<Self> {
+        let (_, chain) = chain(config.network_name.as_str().to_string())?;
+        Some(Self {
+            chain,
+            permit2: config.permit2_approval,
+            rpc_url: crate::utils::evm::RpcPool::of(config).read_url(),
+        })
+    }
+}
+
+impl SolutionEncoder for TychoEncoder {
+    fn encode(&self, solutions: Vec<Solution>) -> Result<Vec<EncodedSolution>, String> {
+        // TransferFrom (direct router approval), unless the swaps carry a signed Permit2 permit
+        // - infinite_approval = true:  Router already approved infinitely, no approval TX
+        // - infinite_approval = false: Approval TX approves router, then router transfers directly
+        // - permit2_approval = true:   TransferFromPermit2, the router pulls through Permit2 with the permit
+        let transfer = if self.permit2 { UserTransferType::TransferFromPermit2 } else { UserTransferType::TransferFrom };
+        tracing::debug!("🔧 Building TychoRouterEncoder with UserTransferType::{:?}", transfer);
+        let chain = self.chain;
+        let span = tracing::Span::current();
+        with_rpc_url(&self.rpc_url, move || {
+            span.in_scope(|| {
+                let encoder = TychoRouterEncoderBuilder::new()
+                    .chain(chain)
+                    .user_transfer_type(transfer)
+                    .build()
+                    .map_err(|e| format!("Failed to build TychoRouterEncoder: {:?}", e))?;
+                tracing::debug!("✅ Encoder built successfully");
+                encoder.encode_solutions(solutions).map_err(|e| format!("Failed to encode solutions: {:?}", e))
+            })
+        })?
+    }
 }
 
 /// Fetches token balances for a specific protocol component (pool).
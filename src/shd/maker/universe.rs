@@ -0,0 +1,169 @@
+//! Token Universe Module
+//!
+//! Fetching the tokens of a network from the Tycho API (`tokens()`, every page) takes several seconds on each start
+//! and is occasionally rate-limited. The sanitized tokens are kept in `<token_cache_dir>/tokens.<network>.json` with
+//! their fetch time: a start within `token_cache_max_age_h` uses them and refreshes the file in the background, a
+//! missing, older or corrupted file (ignored with a warning) falls back to the API. Whatever the age of the cache, the
+//! base and quote tokens of the configs are fetched fresh with `specific()` and replace their cached entries.
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use tycho_common::{models::token::Token, Bytes};
+
+use crate::{
+    maker::tycho::{chain, fetch_specific, fetch_tokens, with_auth_retry, TychoApiError},
+    types::config::MarketMakerConfig,
+};
+
+/// Token of the cache file, its chain being the network of the file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedToken {
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u32,
+    pub gas: Vec<Option<u64>>,
+    pub quality: u32,
+    pub tax: u64,
+}
+
+/// Token universe of a network, as written to the cache file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenUniverse {
+    pub network: String,
+    pub fetched_at_s: u64, // Unix time of the Tycho fetch
+    pub tokens: Vec<CachedToken>,
+}
+
+impl TokenUniverse {
+    pub fn new(network: &str, fetched_at_s: u64, tokens: &[Token]) -> Self {
+        let tokens = tokens
+            .iter()
+            .map(|t| CachedToken {
+                address: t.address.to_string(),
+                symbol: t.symbol.clone(),
+                decimals: t.decimals,
+                gas: t.gas.clone(),
+                quality: t.quality,
+                tax: t.tax,
+            })
+            .collect();
+        Self {
+            network: network.to_string(),
+            fetched_at_s,
+            tokens,
+        }
+    }
+
+    /// True when fetched less than `max_age_h` hours before `now_s`, never with a max age of 0.
+    pub fn fresh(&self, now_s: u64, max_age_h: u64) -> bool {
+        max_age_h > 0 && now_s.saturating_sub(self.fetched_at_s) < max_age_h * 3_600
+    }
+
+    /// Tokens of the universe, None if its network is unknown.
+    pub fn tokens(&self) -> Option<Vec<Token>> {
+        let (chain, _) = chain(self.network.clone())?;
+        let tokens = self
+            .tokens
+            .iter()
+            .filter_map(|t| {
+                Some(Token {
+                    address: Bytes::from_str(&t.address).ok()?,
+                    symbol: t.symbol.clone(),
+                    decimals: t.decimals,
+                    gas: t.gas.clone(),
+                    chain: chain.into(),
+                    quality: t.quality,
+                    tax: t.tax,
+                })
+            })
+            .collect();
+        Some(tokens)
+    }
+}
+
+/// Cache file of the network of a config.
+pub fn path(config: &MarketMakerConfig) -> PathBuf {
+    Path::new(&config.token_cache_dir).join(format!("tokens.{}.json", config.network_name))
+}
+
+/// Universe of the cache file of `network`. Missing file is None, corrupted or foreign file is None with a warning.
+pub fn load(path: &Path, network: &str) -> Option<TokenUniverse> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<TokenUniverse>(&contents) {
+        Ok(universe) if universe.network == network => Some(universe),
+        Ok(universe) => {
+            tracing::warn!("🪙 Ignoring the token cache {}: tokens of {}, not {}", path.display(), universe.network, network);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("🪙 Ignoring the corrupted token cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Writes the universe to the cache file, through a temporary file so that a crash never leaves it truncated.
+pub fn save(path: &Path, universe: &TokenUniverse) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, serde_json::to_string(universe).map_err(std::io::Error::other)?)?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Cached tokens with the entries of `addresses` replaced by the `fresh` ones. An address missing from `fresh` is
+/// dropped rather than served from the cache.
+pub fn merge(cached: Vec<Token>, fresh: Vec<Token>, addresses: &[String]) -> Vec<Token> {
+    let addresses = addresses.iter().map(|a| a.to_lowercase()).collect::<Vec<String>>();
+    let mut tokens = cached.into_iter().filter(|t| !addresses.contains(&t.address.to_string())).collect::<Vec<Token>>();
+    tokens.extend(fresh);
+    tokens
+}
+
+fn now_s() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Fetches the tokens of the network from Tycho and writes them to the cache file, a failed write only logged. The
+/// `addresses` are fetched on their own and merged in, so that a cap or filter of the token list never leaves them out.
+async fn fetch(config: MarketMakerConfig, key: Option<&str>, addresses: &[String]) -> Result<Vec<Token>, TychoApiError> {
+    let fetched = with_auth_retry(&config, "fetching the tokens", || fetch_tokens(config.clone(), key)).await?;
+    let specific = with_auth_retry(&config, "fetching the base and quote tokens", || fetch_specific(config.clone(), key, addresses.to_vec())).await?;
+    let fetched = merge(fetched, specific, addresses);
+    if config.token_cache_max_age_h > 0 {
+        let path = path(&config);
+        match save(&path, &TokenUniverse::new(&config.network_name, now_s(), &fetched)) {
+            Ok(()) => tracing::info!("🪙 Cached {} tokens in {}", fetched.len(), path.display()),
+            Err(e) => tracing::warn!("🪙 Failed to write the token cache {}: {}", path.display(), e),
+        }
+    }
+    Ok(fetched)
+}
+
+/// Tokens of the network of a config: the cached universe when fresh enough, refreshed in the background, with the
+/// `addresses` (base and quote of the configs) fetched fresh. Otherwise fetched from Tycho and cached. A rejected API key
+/// is retried with a backoff before failing with `TychoApiError::Auth`.
+pub async fn universe(config: MarketMakerConfig, key: Option<&str>, addresses: Vec<String>) -> Result<Vec<Token>, TychoApiError> {
+    let path = path(&config);
+    let cached = load(&path, &config.network_name)
+        .filter(|universe| universe.fresh(now_s(), config.token_cache_max_age_h))
+        .and_then(|universe| Some((universe.tokens()?, universe.fetched_at_s)));
+    let Some((cached, fetched_at_s)) = cached else {
+        return fetch(config, key, &addresses).await;
+    };
+    tracing::info!("🪙 Loaded {} tokens from {}, fetched {} s ago", cached.len(), path.display(), now_s().saturating_sub(fetched_at_s));
+    let fresh = with_auth_retry(&config, "fetching the base and quote tokens", || fetch_specific(config.clone(), key, addresses.clone())).await?;
+
+    let (key, refreshed) = (key.map(str::to_string), addresses.clone());
+    tokio::spawn(async move {
+        if let Err(e) = fetch(config, key.as_deref(), &refreshed).await {
+            tracing::warn!("🪙 Failed to refresh the token cache, kept as is: {}", e);
+        }
+    });
+    Ok(merge(cached, fresh, &addresses))
+}
@@ -0,0 +1,157 @@
+//! Valuation Module
+//!
+//! Values token amounts in ETH and USD from the market context (token/ETH quotes routed through
+//! the Tycho pools, ETH/USD from the gas token feed), shared by `readjust`, the exposure limit and
+//! `maker inventory`, which prints the wallet inventory without starting the market maker loop.
+use std::{collections::HashMap, fmt, time::Duration};
+
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::{
+    opti::routing::TokenGraph,
+    types::{
+        builder::MarketMakerBuilder,
+        config::{EnvConfig, MarketMakerConfig},
+        maker::{Inventory, MarketContext, MarketMaker},
+        tycho::TychoStreamState,
+    },
+    utils::constants::{NULL_ADDRESS, PERCENT_MULTIPLIER},
+};
+
+/// Value of `amount` (normalized) of base, or quote if `base_side` is false, as (ETH, USD).
+pub fn worth(amount: f64, base_side: bool, context: &MarketContext) -> (f64, f64) {
+    let eth = amount * if base_side { context.base_to_eth } else { context.quote_to_eth };
+    (eth, eth * context.eth_to_usd)
+}
+
+/// Amount (normalized) scaled down to be worth `max_usd`, or None if `amount` is worth no more than that.
+pub fn cap_worth(amount: f64, base_side: bool, context: &MarketContext, max_usd: f64) -> Option<f64> {
+    let (_, usd) = worth(amount, base_side, context);
+    (usd > max_usd).then(|| amount * max_usd / usd)
+}
+
+/// One row of the inventory table.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TokenBalance {
+    pub token: String,
+    pub address: String,
+    pub raw: u128,
+    pub balance: f64,
+    pub usd: f64,
+    pub share_pct: f64, // Of the base and quote value, 0 for the native balance
+}
+
+/// Wallet inventory valued at a block.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct InventoryReport {
+    pub wallet: String,
+    pub network: String,
+    pub block: u64,
+    pub eth_usd: f64,
+    pub tokens: Vec<TokenBalance>, // Base, then quote
+    pub native: TokenBalance,
+    pub nonce: u64,
+    pub total_usd: f64, // Base and quote, the native gas balance excluded
+}
+
+/// Values the base, quote and native balances of an inventory.
+pub fn report(mk: &MarketMaker, inventory: &Inventory, context: &MarketContext) -> InventoryReport {
+    let mut tokens = [(&mk.base, inventory.base_balance, true), (&mk.quote, inventory.quote_balance, false)]
+        .iter()
+        .map(|(token, raw, base_side)| {
+            let balance = *raw as f64 / 10f64.powi(token.decimals as i32);
+            TokenBalance {
+                token: token.symbol.clone(),
+                address: token.address.to_string(),
+                raw: *raw,
+                balance,
+                usd: worth(balance, *base_side, context).1,
+                share_pct: 0.,
+            }
+        })
+        .collect::<Vec<TokenBalance>>();
+    let total_usd = tokens.iter().map(|t| t.usd).sum::<f64>();
+    if total_usd > 0. && total_usd.is_finite() {
+        for token in tokens.iter_mut() {
+            token.share_pct = token.usd / total_usd * PERCENT_MULTIPLIER;
+        }
+    }
+    let native = inventory.native_balance as f64 / 1e18;
+    InventoryReport {
+        wallet: mk.config.wallet_public_key.clone(),
+        network: mk.config.network_name.clone(),
+        block: context.block,
+        eth_usd: context.eth_to_usd,
+        tokens,
+        native: TokenBalance {
+            token: "native".to_string(),
+            address: NULL_ADDRESS.to_string(),
+            raw: inventory.native_balance,
+            balance: native,
+            usd: native * context.eth_to_usd,
+            share_pct: 0.,
+        },
+        nonce: inventory.nonce,
+        total_usd,
+    }
+}
+
+impl fmt::Display for InventoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Wallet {} on {} | Block {} | ETH {:.2} $ | Nonce {}",
+            self.wallet, self.network, self.block, self.eth_usd, self.nonce
+        )?;
+        writeln!(f, "{:<8} {:>32} {:>18} {:>14} {:>8}", "Token", "Raw balance", "Balance", "USD", "Share")?;
+        for row in self.tokens.iter() {
+            writeln!(f, "{:<8} {:>32} {:>18.6} {:>14.2} {:>7.2}%", row.token, row.raw, row.balance, row.usd, row.share_pct)?;
+        }
+        writeln!(f, "{:<8} {:>32} {:>18.6} {:>14.2} {:>8}", self.native.token, self.native.raw, self.native.balance, self.native.usd, "-")?;
+        write!(f, "{:<8} {:>32} {:>18} {:>14.2}", "Total", "", "", self.total_usd)
+    }
+}
+
+/// Reads and values the inventory of the config wallet, then every `every` if set, passing each report to `print`.
+///
+/// Token/ETH quotes are routed through the pools of a Tycho stream, kept up to date between two refreshes.
+pub async fn watch(config: MarketMakerConfig, env: &EnvConfig, every: Option<Duration>, print: impl Fn(&InventoryReport)) -> Result<(), String> {
+    let (mk, tokens) = MarketMakerBuilder::standalone(config, &env.tycho_api_key).await?;
+    let mut stream = crate::maker::tycho::stream(&mk.config, &env.tycho_api_key, tokens.clone()).await?;
+    let mut state = TychoStreamState {
+        protosims: HashMap::new(),
+        components: HashMap::new(),
+        atks: tokens.clone(),
+        block: 0,
+    };
+    let mut wait = Duration::ZERO;
+    loop {
+        // The first update is the snapshot, the next ones arrive while waiting for the refresh
+        let tick = tokio::time::sleep(wait);
+        tokio::pin!(tick);
+        while state.block == 0 || !tick.is_elapsed() {
+            tokio::select! {
+                next = stream.next() => match next {
+                    Some(Ok(update)) => state.apply(&update),
+                    Some(Err(e)) => return Err(format!("Tycho stream error: {}", e)),
+                    None => return Err("Tycho stream closed".to_string()),
+                },
+                _ = &mut tick, if state.block != 0 => break,
+            }
+        }
+        let components = state.components.values().filter(|cp| !cp.id.to_string().contains(NULL_ADDRESS)).cloned().collect::<Vec<_>>();
+        let graph = TokenGraph::new(&components, &state.protosims);
+        let context = mk
+            .market_data
+            .market_context(&mk, &graph, &state.protosims, tokens.clone())
+            .await
+            .ok_or_else(|| "Failed to fetch the market context".to_string())?;
+        let inventory = mk.market_data.inventory(&mk, env.clone()).await?;
+        print(&report(&mk, &inventory, &context));
+        let Some(every) = every else {
+            return Ok(());
+        };
+        wait = every;
+    }
+}
@@ -0,0 +1,88 @@
+//! Balance Watcher Module
+//!
+//! The inventory cache only follows our own executions, so a deposit or withdrawal made outside of the
+//! bot goes unnoticed until the next refresh, and trades keep being sized against balances that are gone.
+//! Every `balance_watch_interval_ms`, and after a failed broadcast, the cached base and quote balances are
+//! compared with the chain: past `external_movement_threshold_bps`, the movement is logged and alerted, the
+//! cache takes the on-chain balances, and order creation is skipped for one block to re-evaluate exposure.
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{types::maker::Inventory, utils::constants::BASIS_POINT_DENO};
+
+/// Unexplained change of the base and quote balances, raw amounts (on-chain minus cached).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalMovement {
+    pub base_delta: i128,
+    pub quote_delta: i128,
+    pub base_bps: f64, // Of the cached balance, infinite from a zero balance
+    pub quote_bps: f64,
+}
+
+impl fmt::Display for ExternalMovement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "base {:+} ({:+.0} bps), quote {:+} ({:+.0} bps)", self.base_delta, self.base_bps, self.quote_delta, self.quote_bps)
+    }
+}
+
+/// Compares the cached inventory with the chain on an interval.
+#[derive(Debug, Clone, Default)]
+pub struct BalanceWatcher {
+    interval_ms: u64,   // 0 disables the periodic check, failed broadcasts are still checked
+    threshold_bps: f64, // Change of a balance counted as an external movement
+    checked_at: Option<Instant>,
+    paused: bool, // Order creation skipped on the next evaluation
+}
+
+/// Change of `onchain` against `cached`, in bps of `cached`.
+fn change_bps(cached: u128, onchain: u128) -> f64 {
+    let delta = onchain as f64 - cached as f64;
+    match cached {
+        0 if onchain == 0 => 0.,
+        0 => f64::INFINITY,
+        _ => delta / cached as f64 * BASIS_POINT_DENO,
+    }
+}
+
+impl BalanceWatcher {
+    pub fn new(interval_ms: u64, threshold_bps: f64) -> Self {
+        Self {
+            interval_ms,
+            threshold_bps,
+            ..Default::default()
+        }
+    }
+
+    /// True when the balances must be checked: first call, then every interval. Never when disabled.
+    pub fn due(&self, now: Instant) -> bool {
+        self.interval_ms > 0 && self.checked_at.is_none_or(|at| now.saturating_duration_since(at) >= Duration::from_millis(self.interval_ms))
+    }
+
+    /// The movement between a cached and an on-chain inventory, if a base or quote balance changed past the threshold.
+    pub fn compare(&self, cached: &Inventory, onchain: &Inventory) -> Option<ExternalMovement> {
+        let movement = ExternalMovement {
+            base_delta: onchain.base_balance as i128 - cached.base_balance as i128,
+            quote_delta: onchain.quote_balance as i128 - cached.quote_balance as i128,
+            base_bps: change_bps(cached.base_balance, onchain.base_balance),
+            quote_bps: change_bps(cached.quote_balance, onchain.quote_balance),
+        };
+        (movement.base_bps.abs() > self.threshold_bps || movement.quote_bps.abs() > self.threshold_bps).then_some(movement)
+    }
+
+    /// Records a check, the next one is due an interval later.
+    pub fn checked(&mut self, now: Instant) {
+        self.checked_at = Some(now);
+    }
+
+    /// Skips order creation on the next evaluation.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// True once after `pause`: the evaluation must not create orders.
+    pub fn take_pause(&mut self) -> bool {
+        std::mem::take(&mut self.paused)
+    }
+}
@@ -0,0 +1,69 @@
+//! Native Wrapping Module
+//!
+//! With `auto_wrap_native`, the native balance above the gas floor counts as inventory of the
+//! wrapped token (WETH) side of the pair. A trade selling more wrapped token than the wallet holds
+//! is preceded by a `deposit()` of the missing amount. With `unwrap_to_native_above`, a trade buying
+//! the wrapped token is followed by a `withdraw()` of what exceeds the threshold.
+use alloy::{
+    primitives::{Address, Bytes as AlloyBytes, TxKind, U256},
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolValue,
+};
+
+use crate::maker::r#impl::encode_input;
+use crate::utils::constants::{DEFAULT_WRAP_GAS, DEPOSIT_FN_SIGNATURE, WITHDRAW_FN_SIGNATURE};
+
+/// Native amount to wrap before selling `amount_in` of the wrapped token.
+///
+/// None when the wrapped balance already covers the trade, an error when the wrappable native balance cannot.
+pub fn wrap_amount(amount_in: u128, wrapped_balance: u128, wrappable_native: u128) -> Result<Option<u128>, String> {
+    let missing = amount_in.saturating_sub(wrapped_balance);
+    if missing == 0 {
+        return Ok(None);
+    }
+    if missing > wrappable_native {
+        return Err(format!("Not enough native balance to wrap: {} missing, {} wrappable", missing, wrappable_native));
+    }
+    Ok(Some(missing))
+}
+
+/// Wrapped amount to unwrap after buying at least `amount_out_min`, bringing the balance back to `threshold`.
+///
+/// Based on the minimum output, so the withdrawal never exceeds what the swap delivers. None below the threshold, or if disabled (0).
+pub fn unwrap_amount(wrapped_balance: u128, amount_out_min: u128, threshold: u128) -> Option<u128> {
+    if threshold == 0 {
+        return None;
+    }
+    let excess = wrapped_balance.saturating_add(amount_out_min).saturating_sub(threshold);
+    (excess > 0).then_some(excess)
+}
+
+/// `deposit()` call on the wrapped token, sending `amount` of native. Fees, chain and nonce are set by the caller.
+pub fn deposit(token: Address, from: Address, amount: u128) -> TransactionRequest {
+    TransactionRequest {
+        to: Some(TxKind::Call(token)),
+        from: Some(from),
+        value: Some(U256::from(amount)),
+        input: TransactionInput {
+            input: Some(AlloyBytes::from(encode_input(DEPOSIT_FN_SIGNATURE, vec![]))),
+            data: None,
+        },
+        gas: Some(DEFAULT_WRAP_GAS),
+        ..Default::default()
+    }
+}
+
+/// `withdraw(amount)` call on the wrapped token. Fees, chain and nonce are set by the caller.
+pub fn withdraw(token: Address, from: Address, amount: u128) -> TransactionRequest {
+    TransactionRequest {
+        to: Some(TxKind::Call(token)),
+        from: Some(from),
+        value: Some(U256::from(0)),
+        input: TransactionInput {
+            input: Some(AlloyBytes::from(encode_input(WITHDRAW_FN_SIGNATURE, U256::from(amount).abi_encode()))),
+            data: None,
+        },
+        gas: Some(DEFAULT_WRAP_GAS),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,111 @@
+//! Adaptive Threshold Module
+//!
+//! Rolling estimate of the shortfall between the profit expected of the executed trades and what they realized
+//! (slippage and gas above the simulation), added to the execution threshold once enough trades landed, and decaying
+//! back as the realized results catch up with the expectations. The rolling state is kept in the pool snapshot, so that
+//! a restart resumes from it instead of trading on the static threshold until enough trades landed again.
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::maker::{RealizedData, SwapCalculation},
+    utils::constants::BASIS_POINT_DENO,
+};
+
+/// EWMA of the realized-vs-expected shortfall, one sample per included trade.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdaptiveThreshold {
+    lambda: f64,            // Decay of the previous shortfall (0 to 1), higher is smoother
+    max_bps: f64,           // Cap of the widening, 0 disables it
+    min_trades: usize,      // Trades recorded before the widening applies
+    trades: usize,          // Trades recorded since startup
+    shortfall: Option<f64>, // None until the first trade
+    widening_bps: f64,      // Widening currently applied
+}
+
+/// Rolling state of the threshold, as persisted in the pool snapshot.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct AdaptiveState {
+    pub trades: usize,
+    pub shortfall_bps: Option<f64>, // None until the first trade
+    pub widening_bps: f64,
+}
+
+/// Change of the widening after a trade, logged and published.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThresholdAdjustment {
+    pub trades: usize,
+    pub shortfall_bps: f64, // Rolling shortfall, negative when realizing more than expected
+    pub previous_bps: f64,
+    pub widening_bps: f64,
+}
+
+impl AdaptiveThreshold {
+    pub fn new(lambda: f64, max_bps: f64, min_trades: usize) -> Self {
+        Self {
+            lambda: lambda.clamp(0.0, 1.0),
+            max_bps: max_bps.max(0.0),
+            min_trades,
+            ..Default::default()
+        }
+    }
+
+    /// Shortfall of an included trade in bps of its notional: realized slippage, plus the gas paid above the gas
+    /// estimated at evaluation. Negative when the trade did better than expected.
+    pub fn shortfall_bps(calculation: &SwapCalculation, realized: &RealizedData) -> f64 {
+        if calculation.selling_worth_usd <= 0. {
+            return realized.realized_slippage_bps;
+        }
+        let extra_gas_bps = (realized.realized_gas_cost_usd - calculation.gas_cost_usd) / calculation.selling_worth_usd * BASIS_POINT_DENO;
+        realized.realized_slippage_bps + extra_gas_bps
+    }
+
+    /// Adds the shortfall of a trade, returning the adjustment when the widening moved by at least 0.01 bps.
+    pub fn record(&mut self, shortfall_bps: f64) -> Option<ThresholdAdjustment> {
+        if !shortfall_bps.is_finite() {
+            return None;
+        }
+        self.trades += 1;
+        let shortfall = match self.shortfall {
+            Some(previous) => self.lambda * previous + (1.0 - self.lambda) * shortfall_bps,
+            None => shortfall_bps,
+        };
+        self.shortfall = Some(shortfall);
+        let previous_bps = self.widening_bps;
+        self.widening_bps = if self.trades < self.min_trades { 0.0 } else { shortfall.clamp(0.0, self.max_bps) };
+        ((self.widening_bps - previous_bps).abs() >= 0.01).then_some(ThresholdAdjustment {
+            trades: self.trades,
+            shortfall_bps: shortfall,
+            previous_bps,
+            widening_bps: self.widening_bps,
+        })
+    }
+
+    /// Rolling state, to persist.
+    pub fn state(&self) -> AdaptiveState {
+        AdaptiveState {
+            trades: self.trades,
+            shortfall_bps: self.shortfall,
+            widening_bps: self.widening_bps,
+        }
+    }
+
+    /// Resumes from a persisted state, the widening recomputed with the current cap and min trades.
+    pub fn restore(&mut self, state: AdaptiveState) {
+        self.trades = state.trades;
+        self.shortfall = state.shortfall_bps.filter(|shortfall| shortfall.is_finite());
+        self.widening_bps = match self.shortfall {
+            Some(shortfall) if self.trades >= self.min_trades => shortfall.clamp(0.0, self.max_bps),
+            _ => 0.0,
+        };
+    }
+
+    /// Widening added to the execution threshold, between 0 and the cap: the static threshold stays the floor.
+    pub fn widening_bps(&self) -> f64 {
+        self.widening_bps
+    }
+
+    /// Rolling shortfall in bps, 0 before the first trade.
+    pub fn shortfall(&self) -> f64 {
+        self.shortfall.unwrap_or_default()
+    }
+}
@@ -0,0 +1,90 @@
+//! Token Exposure Module
+//!
+//! `max_inventory_ratio` bounds each trade, not their sum: several orders in the same direction
+//! can still park most of the inventory in one token. The exposure is the value share of a token
+//! in the inventory, projected after the orders already accepted, and limited by `max_token_exposure_pct`.
+use tycho_common::models::token::Token;
+
+use crate::maker::valuation::worth;
+use crate::types::maker::{Inventory, MarketContext};
+
+/// Inventory valued in ETH, updated order after order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Exposure {
+    pub base_value: f64,
+    pub quote_value: f64,
+    pub base_to_eth: f64,
+    pub quote_to_eth: f64,
+}
+
+/// Outcome of the exposure limit for one order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExposureLimit {
+    Within,       // Projected exposure at or below the limit
+    Clamped(f64), // Selling amount (normalized) bringing the exposure to the limit
+    Rejected,     // Bought token already at or above the limit
+}
+
+impl Exposure {
+    pub fn new(inventory: &Inventory, base: &Token, quote: &Token, context: &MarketContext) -> Self {
+        Self {
+            base_value: worth(inventory.base_balance as f64 / 10f64.powi(base.decimals as i32), true, context).0,
+            quote_value: worth(inventory.quote_balance as f64 / 10f64.powi(quote.decimals as i32), false, context).0,
+            base_to_eth: context.base_to_eth,
+            quote_to_eth: context.quote_to_eth,
+        }
+    }
+
+    /// Value share (0 to 1) of base, or quote if `base_side` is false. 0 when the inventory cannot be valued.
+    pub fn share(&self, base_side: bool) -> f64 {
+        let total = self.base_value + self.quote_value;
+        if total <= 0. || !total.is_finite() {
+            return 0.;
+        }
+        if base_side {
+            self.base_value / total
+        } else {
+            self.quote_value / total
+        }
+    }
+
+    /// Exposure after selling `selling` for `buying` (normalized amounts). Balances never go below 0.
+    pub fn after(&self, base_to_quote: bool, selling: f64, buying: f64) -> Self {
+        let mut next = *self;
+        if base_to_quote {
+            next.base_value = (self.base_value - selling * self.base_to_eth).max(0.);
+            next.quote_value = self.quote_value + buying * self.quote_to_eth;
+        } else {
+            next.quote_value = (self.quote_value - selling * self.quote_to_eth).max(0.);
+            next.base_value = self.base_value + buying * self.base_to_eth;
+        }
+        next
+    }
+
+    /// Checks an order against `max_share` (0 to 1) of the bought token.
+    ///
+    /// An order exceeding it is clamped to the selling amount reaching the limit, at the same rate.
+    pub fn limit(&self, base_to_quote: bool, selling: f64, buying: f64, max_share: f64) -> ExposureLimit {
+        let buying_base = !base_to_quote;
+        if self.after(base_to_quote, selling, buying).share(buying_base) <= max_share {
+            return ExposureLimit::Within;
+        }
+        let total = self.base_value + self.quote_value;
+        let held = if buying_base { self.base_value } else { self.quote_value };
+        let (selling_to_eth, buying_to_eth) = if base_to_quote {
+            (self.base_to_eth, self.quote_to_eth)
+        } else {
+            (self.quote_to_eth, self.base_to_eth)
+        };
+        let selling_value = selling * selling_to_eth;
+        let headroom = max_share * total - held;
+        // Relative tolerance, so an exposure sitting on the limit is not clamped to dust by rounding
+        if headroom <= total * 1e-9 || selling_value <= 0. {
+            return ExposureLimit::Rejected;
+        }
+        // Bought value per sold value r: (held + r·v) / (total + (r - 1)·v) = max_share
+        let rate = buying * buying_to_eth / selling_value;
+        let value = headroom / (rate * (1. - max_share) + max_share);
+        ExposureLimit::Clamped((value / selling_to_eth).min(selling))
+    }
+}
@@ -0,0 +1,89 @@
+//! Price Impact Module
+//!
+//! Computes depth ladders (execution price and impact at increasing notional sizes) for a pool.
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use tycho_common::models::token::Token;
+
+use crate::types::{
+    maker::{DepthLevel, MarketContext, PoolDepth, TradeDirection},
+    tycho::ProtoSimComp,
+};
+use crate::utils::constants::BASIS_POINT_DENO;
+
+/// Computes the depth ladder of a pool, in both directions, for each USD notional rung.
+///
+/// Rungs above the pool limits (or whose simulation fails) are kept but marked unavailable.
+pub fn ladder(psc: &ProtoSimComp, base: &Token, quote: &Token, context: &MarketContext, rungs_usd: &[f64]) -> Result<PoolDepth, String> {
+    let spot = psc.protosim.spot_price(base, quote).map_err(|e| format!("Failed to get spot price: {:?}", e))?;
+    let base_usd = context.base_to_eth * context.eth_to_usd;
+    let quote_usd = context.quote_to_eth * context.eth_to_usd;
+    if base_usd <= 0. || quote_usd <= 0. {
+        return Err("Cannot derive notional sizes, non-positive USD valuation".to_string());
+    }
+
+    let mut levels = vec![];
+    for direction in [TradeDirection::Buy, TradeDirection::Sell] {
+        // Buy = pool price above reference, selling base. Sell = selling quote. Same convention as evaluate().
+        let (selling, buying, usd) = match direction {
+            TradeDirection::Buy => (base, quote, base_usd),
+            TradeDirection::Sell => (quote, base, quote_usd),
+        };
+        let limit = psc
+            .protosim
+            .get_limits(selling.address.clone(), buying.address.clone())
+            .ok()
+            .and_then(|(max_in, _)| max_in.to_f64())
+            .map(|max_in| max_in / 10f64.powi(selling.decimals as i32));
+        for notional_usd in rungs_usd.iter() {
+            let amount_in = notional_usd / usd;
+            levels.push(level(psc, selling, buying, direction.clone(), *notional_usd, amount_in, spot, limit));
+        }
+    }
+
+    Ok(PoolDepth {
+        address: psc.component.id.to_string().to_lowercase(),
+        r#type: psc.component.protocol_system.to_string(),
+        spot,
+        levels,
+    })
+}
+
+/// Simulates a single rung. Never fails, an unreachable rung is returned as unavailable.
+#[allow(clippy::too_many_arguments)]
+fn level(psc: &ProtoSimComp, selling: &Token, buying: &Token, direction: TradeDirection, notional_usd: f64, amount_in: f64, spot: f64, limit: Option<f64>) -> DepthLevel {
+    let unavailable = DepthLevel {
+        notional_usd,
+        direction: direction.clone(),
+        amount_in,
+        execution_price: None,
+        impact_bps: None,
+        available: false,
+    };
+    if let Some(limit) = limit {
+        if amount_in > limit {
+            return unavailable;
+        }
+    }
+    let powered = BigUint::from((amount_in * 10f64.powi(selling.decimals as i32)).floor() as u128);
+    let Ok(result) = psc.protosim.get_amount_out(powered, selling, buying) else {
+        return unavailable;
+    };
+    let amount_out = result.amount.to_f64().unwrap_or(0.0) / 10f64.powi(buying.decimals as i32);
+    if amount_out <= 0. || amount_in <= 0. {
+        return unavailable;
+    }
+    // Execution price always as base/quote
+    let execution_price = match direction {
+        TradeDirection::Buy => amount_out / amount_in,
+        TradeDirection::Sell => amount_in / amount_out,
+    };
+    DepthLevel {
+        notional_usd,
+        direction,
+        amount_in,
+        execution_price: Some(execution_price),
+        impact_bps: Some((execution_price - spot) / spot * BASIS_POINT_DENO),
+        available: true,
+    }
+}
@@ -1,31 +1,59 @@
 //! Binary Search Optimization Module
 //!
 //! Implements binary search to find optimal swap quantity that stabilizes pool price.
+//! Once the bracket is small enough, a secant refinement phase reuses previous
+//! evaluations to converge with fewer protosim simulations on smooth curves.
 use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
 use tycho_common::models::token::Token;
 use tycho_common::simulation::protocol_sim::ProtocolSim; // ProtocolSim trait for protocol simulation
 
-use crate::utils::constants::{BASIS_POINT_DENO, OPTI_MAX_ITERATIONS, OPTI_TOLERANCE};
+use crate::utils::constants::{BASIS_POINT_DENO, OPTI_MAX_ITERATIONS, OPTI_PRICE_TOLERANCE, OPTI_SECANT_TRIGGER_RATIO, OPTI_TOLERANCE};
+
+/// Why the optimizer stopped searching.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TerminationReason {
+    /// Post-swap price reached the reference within tolerance, or the bracket collapsed
+    #[default]
+    Converged,
+    /// Iteration or simulation budget exhausted before convergence
+    MaxIterations,
+    /// Even the max amount does not reach the reference, max amount returned as best effort
+    TargetUnreachable,
+    /// A simulation failed mid-search, best result so far returned
+    SimulationError,
+}
 
 /// Contains optimal swap amount and metrics.
 #[derive(Default, Debug, Clone)]
 pub struct OptimizationResult {
-    pub optimal_qty: f64,             // Optimal quantity to swap (normalized)
-    pub optimal_qty_powered: BigUint, // Optimal quantity (in token decimals)
-    pub simulation_count: usize,      // Number of simulations performed
-    pub execution_price: f64,         // Expected execution price after swap
-    pub price_impact_bps: f64,        // Price impact vs reference in basis points
+    pub optimal_qty: f64,               // Optimal quantity to swap (normalized)
+    pub optimal_qty_powered: BigUint,   // Optimal quantity (in token decimals)
+    pub simulation_count: usize,        // Number of simulations performed
+    pub execution_price: f64,           // Expected execution price after swap
+    pub price_impact_bps: f64,          // Price impact vs reference in basis points
+    pub termination: TerminationReason, // Why the search stopped
+    pub bracket_width: f64,             // Final search bracket width (normalized), max amount if no bracket was formed
+}
+
+/// One evaluation of the objective: a simulated swap of `qty` and its resulting prices.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    qty: f64,
+    post_swap_price: f64,
+    execution_price: f64,
+    diff: f64, // post_swap_price - reference_price
 }
 
-/// Uses binary search to find swap amount that stabilizes pool price to reference.
+/// Uses binary search, then secant refinement, to find swap amount that stabilizes pool price to reference.
+///
+/// `max_simulations` caps the number of `get_amount_out` calls performed, including the initial probe of `max_amount`.
 pub fn find_optimal_swap_amount(
-    protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, reference_price: f64, base_is_token0: bool, max_amount: f64,
+    protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, reference_price: f64, base_is_token0: bool, max_amount: f64, max_simulations: usize,
 ) -> Result<OptimizationResult, String> {
     let selling_pow = 10f64.powi(selling_token.decimals as i32);
     let buying_pow = 10f64.powi(buying_token.decimals as i32);
-
-    let mut low = 0.0;
-    let mut high = max_amount;
     let mut simulation_count = 0;
 
     // Get initial spot price to understand the direction we need to move
@@ -34,51 +62,52 @@ pub fn find_optimal_swap_amount(
         .map_err(|e| format!("Failed to get initial spot price: {:?}", e))?;
 
     // First check if max amount can reach the target
-    let max_post_swap_price = calculate_post_swap_price(protosim, selling_token, buying_token, max_amount, selling_pow, buying_pow, base_is_token0)?;
-    simulation_count += 1;
-
-    let (_, max_execution_price) = calculate_swap_output(protosim, selling_token, buying_token, max_amount, selling_pow, buying_pow, base_is_token0)?;
+    let max = simulate(protosim, selling_token, buying_token, max_amount, selling_pow, buying_pow, base_is_token0, reference_price)?;
     simulation_count += 1;
 
-    let max_diff = (max_post_swap_price - reference_price).abs();
+    let max_diff = max.diff.abs();
 
     // Check if max amount overshoots the target
     let overshoots = if initial_spot_price < reference_price {
         // Trying to push price up
-        if max_post_swap_price > reference_price {
-            tracing::info!(
-                "Max amount overshoots target: Pool {:.2} → {:.2} (target: {:.2}). Binary search will find exact amount.",
-                initial_spot_price,
-                max_post_swap_price,
-                reference_price
-            );
-            true
-        } else {
-            false
-        }
+        max.post_swap_price > reference_price
     } else {
         // Trying to push price down
-        if max_post_swap_price < reference_price {
-            tracing::info!(
-                "Max amount overshoots target: Pool {:.2} → {:.2} (target: {:.2}). Binary search will find exact amount.",
-                initial_spot_price,
-                max_post_swap_price,
-                reference_price
-            );
-            true
-        } else {
-            false
-        }
+        max.post_swap_price < reference_price
     };
 
-    // If max amount doesn't reach target, use it as best effort
-    if !overshoots && max_diff > 0.0001 {
-        // tracing::info!(
-        //     "Max amount insufficient to reach target. Using max as best effort. Pool: {:.2} → {:.2}, Target: {:.2}",
-        //     initial_spot_price,
-        //     max_post_swap_price,
-        //     reference_price
-        // );
+    if overshoots {
+        tracing::info!(
+            "Max amount overshoots target: Pool {:.2} → {:.2} (target: {:.2}). Binary search will find exact amount.",
+            initial_spot_price,
+            max.post_swap_price,
+            reference_price
+        );
+    }
+
+    // If max amount doesn't reach target (or lands on it), use it as best effort
+    if !overshoots {
+        let termination = if max_diff > OPTI_PRICE_TOLERANCE {
+            // Distinguish a deep pool from a swap moving the price the wrong way
+            if max_diff > (initial_spot_price - reference_price).abs() {
+                tracing::warn!(
+                    "Max amount moves the price away from target: Pool {:.5} → {:.5} (target: {:.5}). Direction may be inverted.",
+                    initial_spot_price,
+                    max.post_swap_price,
+                    reference_price
+                );
+            } else {
+                tracing::debug!(
+                    "Max amount insufficient to reach target: Pool {:.5} → {:.5} (target: {:.5})",
+                    initial_spot_price,
+                    max.post_swap_price,
+                    reference_price
+                );
+            }
+            TerminationReason::TargetUnreachable
+        } else {
+            TerminationReason::Converged
+        };
         // Return max amount as the best we can do
         let optimal_qty_powered = BigUint::from((max_amount * selling_pow).floor() as u128);
         let price_impact_bps = max_diff / reference_price * BASIS_POINT_DENO;
@@ -87,141 +116,178 @@ pub fn find_optimal_swap_amount(
             optimal_qty: max_amount,
             optimal_qty_powered,
             simulation_count,
-            execution_price: max_execution_price,
+            execution_price: max.execution_price,
             price_impact_bps,
+            termination,
+            bracket_width: max_amount,
         });
     }
 
-    let mut best_qty = max_amount;
-    let mut best_price_diff = max_diff;
-    let mut best_execution_price = max_execution_price;
-    let mut best_post_swap_price = max_post_swap_price;
+    // Bracket [low, high] always keeps the root between a sample below and above the target.
+    // The zero-amount side is the current spot price, no simulation needed.
+    let mut low = Sample {
+        qty: 0.0,
+        post_swap_price: initial_spot_price,
+        execution_price: initial_spot_price,
+        diff: initial_spot_price - reference_price,
+    };
+    let mut high = max;
+    let mut best = max;
+    // Last two evaluations, reused by the secant phase
+    let mut previous = low;
+    let mut latest = max;
+    let mut termination = TerminationReason::MaxIterations;
 
-    // Use binary search to find amount that makes post-swap price = reference price
     for _iteration in 0..OPTI_MAX_ITERATIONS {
-        let mid = (low + high) / 2.0;
+        if simulation_count >= max_simulations {
+            tracing::debug!("Optimization simulation budget exhausted ({} simulations)", simulation_count);
+            break;
+        }
+
+        let width = high.qty - low.qty;
+        let mid = (low.qty + high.qty) / 2.0;
+
+        // Secant refinement once the bracket is small, falling back to bisection when the step leaves the bracket
+        let next = if width < max_amount * OPTI_SECANT_TRIGGER_RATIO {
+            match secant(&previous, &latest) {
+                Some(qty) if qty > low.qty && qty < high.qty => qty,
+                _ => mid,
+            }
+        } else {
+            mid
+        };
 
         // Skip if amount is too small
-        if mid < f64::EPSILON {
-            low = mid;
+        if next < f64::EPSILON {
+            low.qty = next;
             continue;
         }
 
-        // Calculate the price after the swap
-        let post_swap_price = calculate_post_swap_price(protosim, selling_token, buying_token, mid, selling_pow, buying_pow, base_is_token0)?;
-        simulation_count += 1;
-
-        // Also get execution price for reporting
-        let (_, execution_price) = calculate_swap_output(protosim, selling_token, buying_token, mid, selling_pow, buying_pow, base_is_token0)?;
+        let sample = match simulate(protosim, selling_token, buying_token, next, selling_pow, buying_pow, base_is_token0, reference_price) {
+            Ok(sample) => sample,
+            Err(e) => {
+                tracing::warn!("Simulation failed during optimization at qty {:.6}: {}", next, e);
+                termination = TerminationReason::SimulationError;
+                break;
+            }
+        };
         simulation_count += 1;
 
-        // Calculate how close the post-swap price is to reference
-        let price_diff = (post_swap_price - reference_price).abs();
-
-        // tracing::debug!(
-        //     "Iteration {}: qty={:.4}, post_swap_price={:.4}, ref_price={:.4}, diff={:.6}, exec_price={:.4}",
-        //     _iteration, mid, post_swap_price, reference_price, price_diff, execution_price
-        // );
-
         // Track best result (minimum difference from reference)
-        if price_diff < best_price_diff {
-            best_price_diff = price_diff;
-            best_qty = mid;
-            best_execution_price = execution_price;
-            best_post_swap_price = post_swap_price;
+        if sample.diff.abs() < best.diff.abs() {
+            best = sample;
         }
 
         // Check convergence
-        if (high - low) < OPTI_TOLERANCE || price_diff < 0.0001 {
+        if sample.diff.abs() < OPTI_PRICE_TOLERANCE {
+            termination = TerminationReason::Converged;
             break;
         }
 
-        // Binary search based on post-swap price vs reference
-        if post_swap_price < reference_price {
-            // Pool price too low after swap, need more aggressive swap
-            // If we're selling base (pushing price up), we need more volume
-            // If we're selling quote (pushing price down), we need less volume
-            if base_is_token0 {
-                low = mid; // Selling base pushes price up, need more
-            } else {
-                high = mid; // Selling quote pushes price down, need less
-            }
+        // Keep the root bracketed: replace the bound whose diff has the same sign
+        if sample.diff.signum() == low.diff.signum() {
+            low = sample;
         } else {
-            // Pool price too high after swap
-            if base_is_token0 {
-                high = mid; // Selling base pushes price up, need less
-            } else {
-                low = mid; // Selling quote pushes price down, need more
-            }
+            high = sample;
+        }
+        previous = latest;
+        latest = sample;
+
+        if (high.qty - low.qty) < OPTI_TOLERANCE {
+            termination = TerminationReason::Converged;
+            break;
         }
     }
 
     // Ensure we found a valid quantity
-    if best_qty < f64::EPSILON {
+    if best.qty < f64::EPSILON {
         return Err("No valid swap amount found".to_string());
     }
 
-    let optimal_qty_powered = BigUint::from((best_qty * selling_pow).floor() as u128);
-    let price_impact_bps = ((best_post_swap_price - reference_price).abs() / reference_price) * BASIS_POINT_DENO;
+    let optimal_qty_powered = BigUint::from((best.qty * selling_pow).floor() as u128);
+    let price_impact_bps = (best.diff.abs() / reference_price) * BASIS_POINT_DENO;
 
     Ok(OptimizationResult {
-        optimal_qty: best_qty,
+        optimal_qty: best.qty,
         optimal_qty_powered,
         simulation_count,
-        execution_price: best_execution_price,
+        execution_price: best.execution_price,
         price_impact_bps,
+        termination,
+        bracket_width: high.qty - low.qty,
     })
 }
 
-/// Calculates the pool's spot price after a swap is executed.
-fn calculate_post_swap_price(
-    protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, amount_normalized: f64, selling_pow: f64, _buying_pow: f64, base_is_token0: bool,
-) -> Result<f64, String> {
-    if amount_normalized < f64::EPSILON {
-        // No swap, return current spot price
-        return protosim
-            .spot_price(if base_is_token0 { selling_token } else { buying_token }, if base_is_token0 { buying_token } else { selling_token })
-            .map_err(|e| format!("Failed to get spot price: {:?}", e));
+/// Reverse quote: finds the smallest input yielding at least `target_out` of the buying token, by bisection on `get_amount_out`.
+///
+/// The search is bounded by `max_amount` and by the pool sell limit from `get_limits`. Amounts are normalized.
+pub fn find_amount_in(protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, target_out: f64, max_amount: f64, max_simulations: usize) -> Result<f64, String> {
+    if target_out <= 0. {
+        return Err("Target output must be > 0".to_string());
     }
+    let selling_pow = 10f64.powi(selling_token.decimals as i32);
+    let buying_pow = 10f64.powi(buying_token.decimals as i32);
+    let amount_out = |amount_in: f64| -> Result<f64, String> {
+        let powered = BigUint::from((amount_in * selling_pow).floor() as u128);
+        let result = protosim.get_amount_out(powered, selling_token, buying_token).map_err(|e| format!("Failed to simulate swap: {:?}", e))?;
+        Ok(result.amount.to_f64().unwrap_or(0.0) / buying_pow)
+    };
+
+    let mut high = max_amount;
+    if let Ok((limit_in, _)) = protosim.get_limits(selling_token.address.clone(), buying_token.address.clone()) {
+        let limit_in = limit_in.to_f64().unwrap_or(f64::MAX) / selling_pow;
+        if limit_in > 0. {
+            high = high.min(limit_in);
+        }
+    }
+    let mut simulation_count = 1;
+    if amount_out(high)? < target_out {
+        return Err(format!("Target output {:.6} {} unreachable with {:.6} {}", target_out, buying_token.symbol, high, selling_token.symbol));
+    }
+
+    // Invariant: output at high >= target, output at low < target
+    let mut low = 0.0;
+    while simulation_count < max_simulations && (high - low) > OPTI_TOLERANCE * high {
+        let mid = (low + high) / 2.0;
+        simulation_count += 1;
+        if amount_out(mid)? >= target_out {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Ok(high)
+}
 
+/// Secant step through the two given samples. Returns None when the slope is flat.
+fn secant(a: &Sample, b: &Sample) -> Option<f64> {
+    let slope = b.diff - a.diff;
+    if slope.abs() < f64::EPSILON {
+        return None;
+    }
+    let qty = b.qty - b.diff * (b.qty - a.qty) / slope;
+    qty.is_finite().then_some(qty)
+}
+
+/// Simulates a swap once and derives both the post-swap spot price and the execution price from it.
+#[allow(clippy::too_many_arguments)]
+fn simulate(
+    protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, amount_normalized: f64, selling_pow: f64, buying_pow: f64, base_is_token0: bool, reference_price: f64,
+) -> Result<Sample, String> {
     let amount_powered = BigUint::from((amount_normalized * selling_pow).floor() as u128);
 
-    // Get the result which includes the new state after the swap
+    // Get the result which includes the amount out and the new state after the swap
     let result = protosim
         .get_amount_out(amount_powered, selling_token, buying_token)
         .map_err(|e| format!("Failed to simulate swap: {:?}", e))?;
 
     // The result.new_state contains the pool state after the swap
-    // Get the spot price from this new state
     let post_swap_price = result
         .new_state
         .spot_price(if base_is_token0 { selling_token } else { buying_token }, if base_is_token0 { buying_token } else { selling_token })
         .map_err(|e| format!("Failed to get post-swap price: {:?}", e))?;
 
-    Ok(post_swap_price)
-}
-
-/// Calculates the output amount and execution price for a given swap.
-fn calculate_swap_output(
-    protosim: &dyn ProtocolSim, selling_token: &Token, buying_token: &Token, amount_normalized: f64, selling_pow: f64, buying_pow: f64, base_is_token0: bool,
-) -> Result<(f64, f64), String> {
-    if amount_normalized < f64::EPSILON {
-        // For zero amount, return zero output and spot price
-        let spot_price = protosim
-            .spot_price(if base_is_token0 { selling_token } else { buying_token }, if base_is_token0 { buying_token } else { selling_token })
-            .unwrap_or(0.0);
-        return Ok((0.0, spot_price));
-    }
-
-    let amount_powered = BigUint::from((amount_normalized * selling_pow).floor() as u128);
-
-    // Get amount out from AMM
-    let result = protosim
-        .get_amount_out(amount_powered, selling_token, buying_token)
-        .map_err(|e| format!("Failed to simulate swap: {:?}", e))?;
-
     let amount_out = result.amount.to_string().parse::<f64>().unwrap_or(0.0) / buying_pow;
-
     if amount_out <= 0.0 {
         return Err("Invalid swap: zero output".to_string());
     }
@@ -235,5 +301,39 @@ fn calculate_swap_output(
         amount_normalized / amount_out
     };
 
-    Ok((amount_out, execution_price))
+    Ok(Sample {
+        qty: amount_normalized,
+        post_swap_price,
+        execution_price,
+        diff: post_swap_price - reference_price,
+    })
+}
+
+/// Raw (powered) amount of `normalized` tokens with `decimals`, floored.
+///
+/// Scaled in BigUint from the exact binary value of the f64, so amounts past 2^53 raw units are not rounded through
+/// another f64 product and never saturate u128. Negative or non-finite amounts are 0.
+pub fn powered(normalized: f64, decimals: u32) -> BigUint {
+    if !normalized.is_finite() || normalized <= 0. {
+        return BigUint::default();
+    }
+    let (mantissa, exponent, _) = num_traits::Float::integer_decode(normalized);
+    let scaled = BigUint::from(mantissa) * BigUint::from(10u32).pow(decimals);
+    if exponent >= 0 {
+        scaled << exponent as usize
+    } else {
+        scaled >> exponent.unsigned_abs() as usize
+    }
+}
+
+/// Normalized amount of a raw (powered) amount with `decimals`.
+pub fn normalized(powered: &BigUint, decimals: u32) -> f64 {
+    powered.to_f64().unwrap_or(f64::MAX) / 10f64.powi(decimals as i32)
+}
+
+/// Raw amount moved by `bps` (negative to lower it), floored, to a 1e-4 bps resolution.
+pub fn shift_bps(amount: &BigUint, bps: f64) -> BigUint {
+    let scale = (BASIS_POINT_DENO * BASIS_POINT_DENO) as u128;
+    let factor = ((BASIS_POINT_DENO + bps).max(0.) * BASIS_POINT_DENO).round() as u128;
+    amount * BigUint::from(factor) / BigUint::from(scale)
 }
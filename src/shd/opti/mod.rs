@@ -1,5 +1,10 @@
 //! Optimization Algorithms Module
 //!
 //! Mathematical optimization algorithms and routing logic for market making.
+pub mod adaptive;
+pub mod exposure;
+pub mod impact;
 pub mod math;
 pub mod routing;
+pub mod skew;
+pub mod volatility;
@@ -1,112 +1,239 @@
+use num_traits::ToPrimitive;
 use std::collections::{HashMap, HashSet, VecDeque};
 use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
 use tycho_simulation::protocol::models::ProtocolComponent;
 
-use crate::types::tycho::{ProtoSimComp, ValorisationPath};
+use crate::types::tycho::ValorisationPath;
 
-/// Finds a conversion path between two tokens using BFS graph traversal.
+/// One component able to convert a token into a neighbor token.
+#[derive(Debug, Clone)]
+struct Edge {
+    component: String,
+    protocol_system: String,
+    liquidity: f64, // Max sellable amount of the source token (raw units), only comparable within the same token pair
+}
+
+/// Token adjacency index (token → neighbor token → components), built once and updated incrementally.
 ///
-/// Builds an adjacency graph from protocol components and finds the shortest
-/// path from input to target token. Returns both the token path and the
-/// component IDs used for pricing.
-pub fn find_path(cps: Vec<ProtocolComponent>, input: String, target: String) -> Result<ValorisationPath, String> {
-    // Build adjacency graph: (destination token address, component id that provides this conversion)
-    let mut graph: HashMap<String, Vec<(String, String)>> = HashMap::new();
-    for comp in cps {
-        let comp_id = comp.id.clone();
-        let addresses: Vec<String> = comp.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect();
-        for token_in in &addresses {
-            for token_out in &addresses {
-                if token_in != token_out {
-                    graph.entry(token_in.clone()).or_default().push((token_out.clone(), comp_id.to_string().clone()));
+/// Parallel edges are kept sorted by liquidity, so queries use the most liquid allowed component
+/// on each pair, and removing it falls back to the next one.
+#[derive(Debug, Default, Clone)]
+pub struct TokenGraph {
+    adjacency: HashMap<String, HashMap<String, Vec<Edge>>>,
+    components: HashMap<String, ProtocolComponent>,
+}
+
+impl TokenGraph {
+    /// Builds the graph from the full component list, ranking parallel edges with the given protosims.
+    pub fn new(cps: &[ProtocolComponent], protosims: &HashMap<String, Box<dyn ProtocolSim>>) -> Self {
+        let mut graph = Self::default();
+        for cp in cps.iter() {
+            let protosim = protosims.get(&cp.id.to_string().to_lowercase()).map(|p| p.as_ref());
+            graph.insert(cp.clone(), protosim);
+        }
+        graph
+    }
+
+    /// Adds a component, or replaces it if already indexed. Without protosim, its edges rank last.
+    pub fn insert(&mut self, cp: ProtocolComponent, protosim: Option<&dyn ProtocolSim>) {
+        let id = cp.id.to_string().to_lowercase();
+        self.remove(&id);
+        for token_in in cp.tokens.iter() {
+            for token_out in cp.tokens.iter() {
+                let (src, dst) = (token_in.address.to_string().to_lowercase(), token_out.address.to_string().to_lowercase());
+                if src == dst {
+                    continue;
                 }
+                let liquidity = protosim
+                    .and_then(|p| p.get_limits(token_in.address.clone(), token_out.address.clone()).ok())
+                    .and_then(|(max_in, _)| max_in.to_f64())
+                    .unwrap_or(0.0);
+                let edges = self.adjacency.entry(src).or_default().entry(dst).or_default();
+                let pos = edges.iter().position(|e| e.liquidity < liquidity).unwrap_or(edges.len());
+                edges.insert(
+                    pos,
+                    Edge {
+                        component: id.clone(),
+                        protocol_system: cp.protocol_system.clone(),
+                        liquidity,
+                    },
+                );
             }
         }
+        self.components.insert(id, cp);
     }
 
-    // For debugging: print the graph
-    // e.g., log::info!("Graph: {:?}", graph);
-    let start = input.to_lowercase();
-    let target = target.to_lowercase();
+    /// Removes a component and all its edges.
+    pub fn remove(&mut self, id: &str) -> Option<ProtocolComponent> {
+        let id = id.to_lowercase();
+        let cp = self.components.remove(&id)?;
+        for token_in in cp.tokens.iter() {
+            let src = token_in.address.to_string().to_lowercase();
+            if let Some(neighbors) = self.adjacency.get_mut(&src) {
+                for token_out in cp.tokens.iter() {
+                    let dst = token_out.address.to_string().to_lowercase();
+                    if let Some(edges) = neighbors.get_mut(&dst) {
+                        edges.retain(|e| e.component != id);
+                        if edges.is_empty() {
+                            neighbors.remove(&dst);
+                        }
+                    }
+                }
+                if neighbors.is_empty() {
+                    self.adjacency.remove(&src);
+                }
+            }
+        }
+        Some(cp)
+    }
 
-    // BFS queue items: (current token, token path, component id path)
-    let mut queue: VecDeque<(String, Vec<String>, Vec<String>)> = VecDeque::new();
-    queue.push_back((start.clone(), vec![start.clone()], vec![]));
-    let mut visited: HashSet<String> = HashSet::new();
+    /// Returns an indexed component by id.
+    pub fn component(&self, id: &str) -> Option<&ProtocolComponent> {
+        self.components.get(&id.to_lowercase())
+    }
 
-    while let Some((current, token_path, comp_path)) = queue.pop_front() {
-        if current == target {
-            return Ok(ValorisationPath { token_path, comp_path });
-        }
-        if visited.contains(&current) {
-            continue;
+    /// Number of indexed components.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Component ids able to convert `token_in` into `token_out`, most liquid first.
+    fn edges(&self, token_in: &str, token_out: &str) -> impl Iterator<Item = &Edge> {
+        self.adjacency.get(token_in).and_then(|n| n.get(token_out)).into_iter().flatten()
+    }
+
+    /// BFS over the graph, using the most liquid allowed component on each hop. An empty whitelist allows every protocol.
+    fn search(&self, input: &str, target: &str, whitelist: &[String]) -> Option<ValorisationPath> {
+        let start = input.to_lowercase();
+        let target = target.to_lowercase();
+        if start == target {
+            return Some(ValorisationPath {
+                token_path: vec![start],
+                comp_path: vec![],
+            });
         }
-        visited.insert(current.clone());
-        if let Some(neighbors) = graph.get(&current) {
-            for (next, comp_id) in neighbors {
-                if token_path.contains(next) {
+
+        // Parent links instead of per-item path copies: token → (previous token, component id)
+        let mut parents: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        let (root, _) = self.adjacency.get_key_value(&start)?;
+        visited.insert(root.as_str());
+        queue.push_back(root.as_str());
+
+        while let Some(current) = queue.pop_front() {
+            let Some(neighbors) = self.adjacency.get(current) else {
+                continue;
+            };
+            for (next, edges) in neighbors.iter() {
+                if visited.contains(next.as_str()) {
+                    continue;
+                }
+                let Some(edge) = edges.iter().find(|e| whitelist.is_empty() || whitelist.contains(&e.protocol_system)) else {
                     continue;
+                };
+                visited.insert(next.as_str());
+                parents.insert(next.as_str(), (current, edge.component.as_str()));
+                if *next == target {
+                    // Walk back the parent links
+                    let mut token_path = vec![next.clone()];
+                    let mut comp_path = vec![];
+                    let mut cursor = next.as_str();
+                    while let Some((previous, component)) = parents.get(cursor) {
+                        token_path.push(previous.to_string());
+                        comp_path.push(component.to_string());
+                        cursor = *previous;
+                    }
+                    token_path.reverse();
+                    comp_path.reverse();
+                    return Some(ValorisationPath { token_path, comp_path });
                 }
-                let mut new_token_path = token_path.clone();
-                new_token_path.push(next.clone());
-                let mut new_comp_path = comp_path.clone();
-                new_comp_path.push(comp_id.clone());
-                queue.push_back((next.clone(), new_token_path, new_comp_path));
+                queue.push_back(next.as_str());
             }
         }
+        None
+    }
+}
+
+/// Finds a conversion path between two tokens using BFS over the token graph.
+///
+/// Returns both the token path and the component IDs used for pricing. Only components
+/// whose `protocol_system` is in `whitelist` are considered (an empty whitelist disables the filter).
+pub fn find_path(graph: &TokenGraph, input: String, target: String, whitelist: &[String]) -> Result<ValorisationPath, String> {
+    if let Some(path) = graph.search(&input, &target, whitelist) {
+        return Ok(path);
+    }
+    // Tell operators when the whitelist is the reason no path was found
+    if !whitelist.is_empty() && graph.search(&input, &target, &[]).is_some() {
+        let total = graph.len();
+        let excluded = graph.components.values().filter(|cp| !whitelist.contains(&cp.protocol_system)).count();
+        return Err(format!(
+            "No path found from {} to {} using routing_protocol_whitelist {:?} ({} of {} components excluded), but a path exists outside of it: widen routing_protocol_whitelist",
+            input, target, whitelist, excluded, total
+        ));
     }
     Err(format!("No path found from {} to {}", input, target))
 }
 
 /// Quotes a token path price using protocol simulations.
 ///
-/// Calculates the cumulative price across a path of tokens by chaining
-/// spot prices from protocol components.
-pub fn quote(pts: Vec<ProtoSimComp>, atks: Vec<Token>, path: Vec<String>) -> Option<f64> {
+/// Calculates the cumulative price across a path of tokens by chaining spot prices,
+/// using the component chosen by `find_path` on each hop, then the other components
+/// of the same pair allowed by `whitelist` (most liquid first) if its spot price fails.
+pub fn quote(graph: &TokenGraph, protosims: &HashMap<String, Box<dyn ProtocolSim>>, atks: &[Token], path: &ValorisationPath, whitelist: &[String]) -> Option<f64> {
     // If ETH, return 1. Else, if the path is empty, return None.
-    if path.len() == 1 {
+    if path.token_path.len() == 1 {
         // tracing::debug!(" - Path is just ETH. Returning quote of 1.0");
         return Some(1.0);
-    } else if path.len() < 2 {
-        tracing::error!("🔺 Path is too short: {:?}", path);
+    } else if path.token_path.len() < 2 {
+        tracing::error!("🔺 Path is too short: {:?}", path.token_path);
         return None;
     }
 
     let mut cumulative_price = 1.0;
 
     // For each consecutive pair in the path ...
-    for window in path.windows(2) {
+    for (hop, window) in path.token_path.windows(2).enumerate() {
         let token_in = window[0].to_lowercase();
         let token_out = window[1].to_lowercase();
 
-        // Find a protocol state that can convert token_in to token_out.
+        // Resolve the tokens from the global list.
+        let base = match atks.iter().find(|t| t.address.to_string().to_lowercase() == token_in) {
+            Some(t) => t.clone(),
+            None => {
+                tracing::warn!("Token not found in list: {}", token_in);
+                return None;
+            }
+        };
+        let quote = match atks.iter().find(|t| t.address.to_string().to_lowercase() == token_out) {
+            Some(t) => t.clone(),
+            None => {
+                tracing::warn!("Token not found in list: {}", token_out);
+                return None;
+            }
+        };
+
+        // Routed component first, then the parallel edges of the whitelist
+        let routed = path.comp_path.get(hop).map(|id| id.to_lowercase());
+        let candidates = routed.iter().cloned().chain(
+            graph
+                .edges(&token_in, &token_out)
+                .filter(|e| whitelist.is_empty() || whitelist.contains(&e.protocol_system))
+                .map(|e| e.component.clone())
+                .filter(|id| Some(id) != routed.as_ref()),
+        );
         let mut found = false;
-        for state in &pts {
-            // Extract the component's token addresses.
-            let comp_tokens: Vec<String> = state.component.tokens.iter().map(|t| t.address.to_string().to_lowercase()).collect();
-            if comp_tokens.contains(&token_in) && comp_tokens.contains(&token_out) {
-                // Resolve the tokens from the global list.
-                let base = match atks.iter().find(|t| t.address.to_string().to_lowercase() == token_in) {
-                    Some(t) => t.clone(),
-                    None => {
-                        tracing::warn!("Token not found in list: {}", token_in);
-                        return None;
-                    }
-                };
-                let quote = match atks.iter().find(|t| t.address.to_string().to_lowercase() == token_out) {
-                    Some(t) => t.clone(),
-                    None => {
-                        tracing::warn!("Token not found in list: {}", token_out);
-                        return None;
-                    }
-                };
-                match state.protosim.spot_price(&base, &quote) {
-                    Ok(rate) => {
-                        cumulative_price *= rate;
-                        found = true;
-                        break;
-                    }
-                    Err(_e) => {}
+        for id in candidates {
+            if let Some(protosim) = protosims.get(&id) {
+                if let Ok(rate) = protosim.spot_price(&base, &quote) {
+                    cumulative_price *= rate;
+                    found = true;
+                    break;
                 }
             }
         }
@@ -115,6 +242,6 @@ pub fn quote(pts: Vec<ProtoSimComp>, atks: Vec<Token>, path: Vec<String>) -> Opt
             return None;
         }
     }
-    // tracing::debug!(" - One unit of token ({:?} to {:?}) quoted to ETH = {}", path.first(), path.last(), cumulative_price);
+    // tracing::debug!(" - One unit of token ({:?} to {:?}) quoted to ETH = {}", path.token_path.first(), path.token_path.last(), cumulative_price);
     Some(cumulative_price)
 }
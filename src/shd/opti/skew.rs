@@ -0,0 +1,93 @@
+//! Inventory Skew Module
+//!
+//! Shifts the evaluation thresholds so trades rebalancing the inventory toward its
+//! target base/quote value ratio trigger earlier, and trades worsening it trigger later.
+use tycho_common::models::token::Token;
+
+use crate::types::maker::{Inventory, MarketContext};
+use crate::utils::constants::PERCENT_MULTIPLIER;
+
+/// Inventory skew and the resulting effective thresholds for one block.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InventorySkew {
+    pub base_ratio: f64,         // Base share of the inventory value (0 to 1)
+    pub skew_pct: f64,           // base_ratio - target, in percentage points. Positive = long base
+    pub buy_threshold_bps: f64,  // Effective min spread for TradeDirection::Buy (selling base)
+    pub sell_threshold_bps: f64, // Effective min spread for TradeDirection::Sell (selling quote)
+}
+
+impl InventorySkew {
+    /// Symmetric thresholds, used when the skew is disabled or cannot be computed.
+    pub fn neutral(threshold_bps: f64, target_ratio: f64) -> Self {
+        Self {
+            base_ratio: target_ratio,
+            skew_pct: 0.0,
+            buy_threshold_bps: threshold_bps,
+            sell_threshold_bps: threshold_bps,
+        }
+    }
+
+    /// Effective threshold for a spread, shifted by `offset_bps` (pool threshold minus min_watch_spread_bps). Never below 0.
+    pub fn threshold_bps(&self, spread_bps: f64, offset_bps: f64) -> f64 {
+        let directional = if spread_bps > 0. { self.buy_threshold_bps } else { self.sell_threshold_bps };
+        (directional + offset_bps).max(0.0)
+    }
+
+    /// Logs the skew and the effective thresholds.
+    pub fn print(&self) {
+        tracing::info!(
+            "⚖️  Inventory skew: base {:.2}% ({:+.2} pct vs target) | Effective thresholds: sell base {:.2} bps, sell quote {:.2} bps",
+            self.base_ratio * PERCENT_MULTIPLIER,
+            self.skew_pct,
+            self.buy_threshold_bps,
+            self.sell_threshold_bps
+        );
+    }
+}
+
+/// Computes the inventory skew from the wallet balances valued in ETH.
+///
+/// Each percentage point away from `target_ratio` moves both thresholds by `gain_bps_per_pct`,
+/// lowering the one of the rebalancing direction and raising the other. Thresholds never go below 0.
+pub fn compute(inventory: &Inventory, base: &Token, quote: &Token, context: &MarketContext, target_ratio: f64, gain_bps_per_pct: f64, threshold_bps: f64) -> InventorySkew {
+    let (base_value, quote_value) = values(inventory, base, quote, context);
+    let total = base_value + quote_value;
+    if total <= 0. || !total.is_finite() {
+        return InventorySkew::neutral(threshold_bps, target_ratio);
+    }
+    let base_ratio = base_value / total;
+    let skew_pct = (base_ratio - target_ratio) * PERCENT_MULTIPLIER;
+    let shift = skew_pct * gain_bps_per_pct;
+    InventorySkew {
+        base_ratio,
+        skew_pct,
+        buy_threshold_bps: (threshold_bps - shift).max(0.0),
+        sell_threshold_bps: (threshold_bps + shift).max(0.0),
+    }
+}
+
+/// Amount (normalized) of base, or quote if `base_side` is false, missing to bring that token back to its
+/// target share of the inventory value (`target_ratio` for base, `1 - target_ratio` for quote).
+///
+/// Returns None when the token is at or above its target share, or when the inventory cannot be valued.
+pub fn deficit(inventory: &Inventory, base: &Token, quote: &Token, context: &MarketContext, target_ratio: f64, base_side: bool) -> Option<f64> {
+    let (base_value, quote_value) = values(inventory, base, quote, context);
+    let total = base_value + quote_value;
+    if total <= 0. || !total.is_finite() {
+        return None;
+    }
+    let (value, target, to_eth) = if base_side {
+        (base_value, target_ratio, context.base_to_eth)
+    } else {
+        (quote_value, 1. - target_ratio, context.quote_to_eth)
+    };
+    let missing = target * total - value;
+    (missing > 0. && to_eth > 0.).then(|| missing / to_eth)
+}
+
+/// Base and quote balances valued in ETH.
+fn values(inventory: &Inventory, base: &Token, quote: &Token, context: &MarketContext) -> (f64, f64) {
+    let base_value = inventory.base_balance as f64 / 10f64.powi(base.decimals as i32) * context.base_to_eth;
+    let quote_value = inventory.quote_balance as f64 / 10f64.powi(quote.decimals as i32) * context.quote_to_eth;
+    (base_value, quote_value)
+}
@@ -0,0 +1,48 @@
+//! Volatility Module
+//!
+//! Rolling estimate of the reference price volatility, used to widen the execution
+//! threshold during volatile periods and keep it at its static level in quiet ones.
+use crate::utils::constants::BASIS_POINT_DENO;
+
+/// EWMA of squared log returns of the reference price, one sample per polled block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VolatilityEstimator {
+    lambda: f64,             // Decay of the previous variance (0 to 1), higher is smoother
+    variance: Option<f64>,   // None until the first return
+    last_price: Option<f64>, // Previous reference price
+}
+
+impl VolatilityEstimator {
+    pub fn new(lambda: f64) -> Self {
+        Self {
+            lambda: lambda.clamp(0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a reference price sample and returns the updated volatility, in bps per sample.
+    pub fn update(&mut self, price: f64) -> f64 {
+        if !(price > 0.0 && price.is_finite()) {
+            return self.sigma_bps();
+        }
+        if let Some(last) = self.last_price {
+            let squared = (price / last).ln().powi(2);
+            self.variance = Some(match self.variance {
+                Some(variance) => self.lambda * variance + (1.0 - self.lambda) * squared,
+                None => squared,
+            });
+        }
+        self.last_price = Some(price);
+        self.sigma_bps()
+    }
+
+    /// Current volatility in bps per sample, 0 until two prices were seen.
+    pub fn sigma_bps(&self) -> f64 {
+        self.variance.map(|variance| variance.sqrt() * BASIS_POINT_DENO).unwrap_or_default()
+    }
+
+    /// Execution threshold widened by the volatility: `base_bps + multiplier * sigma_bps`, the widening capped at `cap_bps`.
+    pub fn threshold_bps(&self, base_bps: f64, multiplier: f64, cap_bps: f64) -> f64 {
+        base_bps + (multiplier * self.sigma_bps()).clamp(0.0, cap_bps.max(0.0))
+    }
+}
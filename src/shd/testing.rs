@@ -0,0 +1,464 @@
+//! Testing Module
+//!
+//! Fixtures to unit-test the maker logic without mainnet, built with the `test-utils` feature (enabled for the
+//! integration tests of the crate): token and component builders, a constant-product `MockProtocolSim` with configurable
+//! reserves and fee, a `ScriptedSim` failing after a number of quotes, pool balances read from the mock reserves,
+//! builders for `ProtoSimComp`, `CompReadjustment`, `SwapCalculation`, `MarketContext`, `Trade` and `MarketMaker`, and the
+//! reference config, inventory and environment of the tests. The tests import them through `tests/common`.
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use alloy::rpc::types::TransactionRequest;
+use alloy_primitives::bytes;
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use tycho_common::{
+    dto::ProtocolStateDelta,
+    models::token::Token,
+    simulation::{
+        errors::{SimulationError, TransitionError},
+        protocol_sim::{Balances, GetAmountOutResult, ProtocolSim},
+    },
+    Bytes,
+};
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+use crate::{
+    maker::{exec::ExecStrategyFactory, feed::PriceFeedFactory, tycho::PoolBalances},
+    opti::math::TerminationReason,
+    types::{
+        builder::MarketMakerBuilder,
+        config::{load_market_maker_config, EnvConfig, EventsTransport, MarketMakerConfig, SignerType},
+        maker::{CompReadjustment, Inventory, MarketContext, MarketMaker, PreTradeData, SwapCalculation, Trade, TradeData, TradeDirection, TradeStatus},
+        tycho::ProtoSimComp,
+    },
+    utils::constants::BASIS_POINT_DENO,
+};
+
+/// Gas of a swap on the mock pools.
+pub const MOCK_SWAP_GAS: u64 = 120_000;
+
+/// Builds a token fixture from a hex address.
+pub fn token(address: &str, symbol: &str, decimals: u32) -> Token {
+    let address = hex::decode(address.trim_start_matches("0x")).unwrap_or_default();
+    Token {
+        address: Bytes(bytes::Bytes::from(address)),
+        symbol: symbol.to_string(),
+        decimals,
+        gas: vec![Some(0)],
+        chain: tycho_common::dto::Chain::Ethereum.into(),
+        quality: 100,
+        tax: 0,
+    }
+}
+
+/// WETH-like base token fixture (18 decimals).
+pub fn base() -> Token {
+    token("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "ETH", 18)
+}
+
+/// USDC-like quote token fixture (6 decimals).
+pub fn quote() -> Token {
+    token("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "USDC", 6)
+}
+
+/// Builds a protocol component fixture for the given protocol system and tokens.
+pub fn component(id: &str, protocol_system: &str, tokens: Vec<Token>) -> ProtocolComponent {
+    let id = Bytes(bytes::Bytes::from(hex::decode(id.trim_start_matches("0x")).unwrap_or_default()));
+    ProtocolComponent::new(
+        id,
+        protocol_system.to_string(),
+        format!("{}_pool", protocol_system.trim_start_matches("vm:")),
+        tycho_common::models::Chain::Ethereum,
+        tokens,
+        vec![],
+        HashMap::new(),
+        Bytes::default(),
+        chrono::NaiveDateTime::default(),
+    )
+}
+
+/// Constant-product pool between token0 and token1, reserves in raw (powered) units.
+#[derive(Debug, Clone)]
+pub struct MockProtocolSim {
+    pub token0: Token,
+    pub token1: Token,
+    pub reserve0: f64,
+    pub reserve1: f64,
+    pub fee: f64,
+}
+
+impl MockProtocolSim {
+    /// Creates a pool from normalized reserves (e.g. 1_000 ETH and 3_000_000 USDC).
+    pub fn new(token0: Token, token1: Token, reserve0: f64, reserve1: f64, fee: f64) -> Self {
+        let reserve0 = reserve0 * 10f64.powi(token0.decimals as i32);
+        let reserve1 = reserve1 * 10f64.powi(token1.decimals as i32);
+        Self {
+            token0,
+            token1,
+            reserve0,
+            reserve1,
+            fee,
+        }
+    }
+
+    fn reserves(&self, token_in: &Token) -> (f64, f64) {
+        if token_in.address == self.token0.address {
+            (self.reserve0, self.reserve1)
+        } else {
+            (self.reserve1, self.reserve0)
+        }
+    }
+}
+
+impl ProtocolSim for MockProtocolSim {
+    fn fee(&self) -> f64 {
+        self.fee
+    }
+
+    fn spot_price(&self, base: &Token, quote: &Token) -> Result<f64, SimulationError> {
+        let (rb, rq) = self.reserves(base);
+        let rb = rb / 10f64.powi(base.decimals as i32);
+        let rq = rq / 10f64.powi(quote.decimals as i32);
+        Ok(rq / rb)
+    }
+
+    fn get_amount_out(&self, amount_in: BigUint, token_in: &Token, _token_out: &Token) -> Result<GetAmountOutResult, SimulationError> {
+        let amount_in = amount_in.to_f64().unwrap_or(0.0);
+        let (rin, rout) = self.reserves(token_in);
+        let effective = amount_in * (1.0 - self.fee);
+        let amount_out = effective * rout / (rin + effective);
+        let mut new_state = self.clone();
+        if token_in.address == self.token0.address {
+            new_state.reserve0 += amount_in;
+            new_state.reserve1 -= amount_out;
+        } else {
+            new_state.reserve1 += amount_in;
+            new_state.reserve0 -= amount_out;
+        }
+        Ok(GetAmountOutResult {
+            amount: BigUint::from(amount_out.floor() as u128),
+            gas: BigUint::from(MOCK_SWAP_GAS),
+            new_state: Box::new(new_state),
+        })
+    }
+
+    fn get_limits(&self, _sell_token: Bytes, _buy_token: Bytes) -> Result<(BigUint, BigUint), SimulationError> {
+        Ok((BigUint::from(self.reserve0 as u128), BigUint::from(self.reserve1 as u128)))
+    }
+
+    fn delta_transition(&mut self, _delta: ProtocolStateDelta, _tokens: &HashMap<Bytes, Token>, _balances: &Balances) -> Result<(), TransitionError<String>> {
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn eq(&self, other: &dyn ProtocolSim) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<MockProtocolSim>()
+            .is_some_and(|o| o.reserve0 == self.reserve0 && o.reserve1 == self.reserve1)
+    }
+}
+
+/// Wraps a `MockProtocolSim` and fails `get_amount_out` once `fail_after` calls have succeeded.
+#[derive(Debug, Clone)]
+pub struct ScriptedSim {
+    pub inner: MockProtocolSim,
+    pub calls: Arc<AtomicUsize>,
+    pub fail_after: usize,
+}
+
+impl ScriptedSim {
+    pub fn new(inner: MockProtocolSim, fail_after: usize) -> Self {
+        Self {
+            inner,
+            calls: Arc::new(AtomicUsize::new(0)),
+            fail_after,
+        }
+    }
+}
+
+impl ProtocolSim for ScriptedSim {
+    fn fee(&self) -> f64 {
+        self.inner.fee()
+    }
+
+    fn spot_price(&self, base: &Token, quote: &Token) -> Result<f64, SimulationError> {
+        self.inner.spot_price(base, quote)
+    }
+
+    fn get_amount_out(&self, amount_in: BigUint, token_in: &Token, token_out: &Token) -> Result<GetAmountOutResult, SimulationError> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) >= self.fail_after {
+            return Err(SimulationError::RecoverableError("scripted failure".to_string()));
+        }
+        self.inner.get_amount_out(amount_in, token_in, token_out)
+    }
+
+    fn get_limits(&self, sell_token: Bytes, buy_token: Bytes) -> Result<(BigUint, BigUint), SimulationError> {
+        self.inner.get_limits(sell_token, buy_token)
+    }
+
+    fn delta_transition(&mut self, delta: ProtocolStateDelta, tokens: &HashMap<Bytes, Token>, balances: &Balances) -> Result<(), TransitionError<String>> {
+        self.inner.delta_transition(delta, tokens, balances)
+    }
+
+    fn clone_box(&self) -> Box<dyn ProtocolSim> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn eq(&self, other: &dyn ProtocolSim) -> bool {
+        other.as_any().downcast_ref::<ScriptedSim>().is_some_and(|o| self.inner.eq(&o.inner))
+    }
+}
+
+/// Pool balances of the components, keyed by component id then lowercased token address (raw units).
+#[derive(Debug, Clone, Default)]
+pub struct MockBalances {
+    pub balances: HashMap<String, HashMap<String, u128>>,
+}
+
+impl MockBalances {
+    /// Balances of the target pools, read from the reserves of their `MockProtocolSim`.
+    pub fn of(targets: &[ProtoSimComp]) -> Self {
+        let mut balances = HashMap::new();
+        for psc in targets.iter() {
+            if let Some(sim) = psc.protosim.as_any().downcast_ref::<MockProtocolSim>() {
+                let reserves = HashMap::from([
+                    (sim.token0.address.to_string().to_lowercase(), sim.reserve0 as u128),
+                    (sim.token1.address.to_string().to_lowercase(), sim.reserve1 as u128),
+                ]);
+                balances.insert(psc.component.id.to_string(), reserves);
+            }
+        }
+        Self { balances }
+    }
+}
+
+#[async_trait]
+impl PoolBalances for MockBalances {
+    async fn get(&self, cp: &ProtocolComponent) -> Option<HashMap<String, u128>> {
+        self.balances.get(&cp.id.to_string()).cloned()
+    }
+}
+
+/// Constant-product target pool of `base_reserve` base against `quote_reserve` quote (normalized), spot = quote / base.
+pub fn pool(id: &str, base_reserve: f64, quote_reserve: f64, fee: f64) -> ProtoSimComp {
+    ProtoSimComp {
+        component: component(id, "uniswap_v2", vec![base(), quote()]),
+        protosim: Box::new(MockProtocolSim::new(base(), quote(), base_reserve, quote_reserve, fee)),
+    }
+}
+
+/// Readjustment of a target pool quoting `spot` against `reference`, in the direction `evaluate` would pick.
+pub fn readjustment(psc: &ProtoSimComp, spot: f64, reference: f64) -> CompReadjustment {
+    let spread = spot - reference;
+    let (direction, selling, buying) = if spread > 0. {
+        (TradeDirection::Buy, base(), quote())
+    } else {
+        (TradeDirection::Sell, quote(), base())
+    };
+    CompReadjustment {
+        psc: psc.clone(),
+        direction,
+        selling,
+        buying,
+        spot,
+        reference,
+        spread,
+        spread_bps: spread / reference * BASIS_POINT_DENO,
+    }
+}
+
+/// Market context of the ETH/USDC fixtures, the gas token priced at `reference` USD.
+pub fn context(reference: f64, gas_price_gwei: f64, block: u64) -> MarketContext {
+    let wei = (gas_price_gwei * 1e9) as u128;
+    MarketContext {
+        base_to_eth: 1.,
+        quote_to_eth: 1. / reference,
+        eth_to_usd: reference,
+        max_fee_per_gas: wei,
+        max_priority_fee_per_gas: wei,
+        native_gas_price: wei,
+        block,
+        age_ms: 0,
+    }
+}
+
+/// Market maker of the ETH/USDC fixtures, with the feed and execution strategy of the config.
+pub fn maker(config: MarketMakerConfig) -> MarketMaker {
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    MarketMakerBuilder::new()
+        .config(config)
+        .feed(feed)
+        .execution(execution)
+        .tokens(base(), quote())
+        .build()
+        .expect("Market maker must build")
+}
+
+/// Mainnet ETH/USDC reference config, without audit log nor published events.
+pub fn config() -> MarketMakerConfig {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    config.audit_log_path = None;
+    config.publish_events = false;
+    config
+}
+
+/// Reference config watching the spreads from 10 bps, the pools of the tests quoting 1% off the reference.
+pub fn watch_config() -> MarketMakerConfig {
+    let mut config = config();
+    config.min_watch_spread_bps = 10.0;
+    config
+}
+
+/// Inventory of 10 ETH and 30,000 USDC, in raw units, without native balance.
+pub fn inventory() -> Inventory {
+    Inventory {
+        base_balance: 10 * 10u128.pow(18),
+        quote_balance: 30_000 * 10u128.pow(6),
+        nonce: 0,
+        native_balance: 0,
+    }
+}
+
+/// Environment in testing mode, with the raw signer and no key.
+pub fn env() -> EnvConfig {
+    EnvConfig {
+        path: String::new(),
+        paths: vec![],
+        testing: true,
+        heartbeat: String::new(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
+        tycho_api_key: String::new(),
+        signer_type: SignerType::Raw,
+        wallet_private_key: String::new(),
+        keystore_path: None,
+        keystore_password: None,
+        remote_signer_url: None,
+        remote_signer_address: None,
+        bundle_signer_key: None,
+        events_transport: EventsTransport::PubSub,
+        redis_legacy_keys: false,
+        health_port: 0,
+        alert_telegram_bot_token: None,
+        alert_telegram_chat_id: None,
+        alert_webhook_url: None,
+    }
+}
+
+/// Exact in calculation of the ETH/USDC fixtures selling `selling` for `out` (normalized), 1% slippage and no gas,
+/// valued with USDC at 1 USD. Other fields are set with the struct update syntax.
+pub fn calculation(base_to_quote: bool, selling: f64, out: f64) -> SwapCalculation {
+    let (selling_decimals, buying_decimals) = if base_to_quote {
+        (base().decimals, quote().decimals)
+    } else {
+        (quote().decimals, base().decimals)
+    };
+    let (selling_pow, buying_pow) = (10f64.powi(selling_decimals as i32), 10f64.powi(buying_decimals as i32));
+    let worth_usd = if base_to_quote { out } else { selling };
+    SwapCalculation {
+        base_to_quote,
+        selling_amount: selling,
+        buying_amount: out,
+        powered_selling_amount: selling * selling_pow,
+        powered_buying_amount: out * buying_pow,
+        amount_out_normalized: out,
+        amount_out_powered: out * buying_pow,
+        amount_out_min_normalized: out * 0.99,
+        amount_out_min_powered: out * 0.99 * buying_pow,
+        average_sell_price: if base_to_quote { out / selling } else { selling / out },
+        average_sell_price_net_gas: if base_to_quote { out / selling } else { selling / out },
+        gas_units: 0,
+        gas_cost_eth: 0.0,
+        gas_cost_usd: 0.0,
+        gas_cost_in_output_token: 0.0,
+        selling_worth_usd: worth_usd,
+        buying_worth_usd: worth_usd,
+        profit_delta_bps: 0.0,
+        profitable: true,
+        termination: TerminationReason::Converged,
+        bracket_width: 0.0,
+        exact_out: false,
+        amount_in_max_normalized: selling,
+        amount_in_max_raw: BigUint::from((selling * selling_pow) as u128),
+        amount_in_raw: BigUint::from((selling * selling_pow) as u128),
+        amount_out_raw: BigUint::from((out * buying_pow) as u128),
+    }
+}
+
+/// Sell trade of 1 ETH for 3000 USDC, pending, without transactions to send.
+pub fn trade(trade_id: &str) -> Trade {
+    Trade {
+        wrap: None,
+        approve: None,
+        swap: TransactionRequest::default(),
+        unwrap: None,
+        metadata: TradeData {
+            trade_id: trade_id.to_string(),
+            status: TradeStatus::Pending,
+            timestamp: 0,
+            context: MarketContext {
+                base_to_eth: 1.0,
+                quote_to_eth: 1.0 / 3_000.0,
+                eth_to_usd: 3_000.0,
+                max_fee_per_gas: 0,
+                max_priority_fee_per_gas: 0,
+                native_gas_price: 0,
+                block: 0,
+                age_ms: 0,
+            },
+            metadata: PreTradeData {
+                pool: "0x0".to_string(),
+                base_token: "ETH".to_string(),
+                quote_token: "USDC".to_string(),
+                trade_direction: TradeDirection::Sell,
+                amount_in_normalized: 1.0,
+                amount_out_expected: 3_000.0,
+                token_out: String::new(),
+                token_out_decimals: 6,
+                spot_price: 3_000.0,
+                reference_price: 3_000.0,
+                slippage_tolerance_bps: 10.0,
+                profit_delta_bps: 1.0,
+                gas_cost_usd: 1.0,
+            },
+            inventory: Inventory {
+                base_balance: 0,
+                quote_balance: 0,
+                nonce: 0,
+                native_balance: 0,
+            },
+            simulation: None,
+            broadcast: None,
+            realized: None,
+        },
+    }
+}
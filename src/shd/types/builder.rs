@@ -1,58 +1,288 @@
 //! MarketMaker Builder Module
+use std::{collections::HashMap, sync::Arc};
+
 use tycho_common::models::token::Token;
 
-use super::maker::MarketMaker;
-use crate::maker::{exec::ExecStrategy, feed::PriceFeed};
+use super::{config::MarketMakerConfig, maker::MarketMaker};
+use crate::error::MarketMakerError;
+use crate::maker::{
+    allowance::RouterAllowances,
+    audit::AuditLog,
+    breaker::CircuitBreaker,
+    control::Control,
+    cooldown::PoolCooldown,
+    engine::{ChainDataSource, DecisionEngine, DefaultEngine, Execution, MarketDataSource, RouterExecution},
+    exec::{dry::DryRunExec, ExecStrategy},
+    feed::{PriceFeed, PriceFeedFactory},
+    health::HealthState,
+    inventory::InventoryCache,
+    journal::TradeJournal,
+    lag::StreamLag,
+    latency::SystemClock,
+    pnl::PnlTracker,
+    pools::StreamPools,
+    quarantine::PoolQuarantine,
+    reconnect::Reconnect,
+    shutdown::Shutdown,
+    simcache::SimCache,
+    snapshot::{self, SnapshotStore},
+    tombstone::Tombstones,
+    tycho::BalanceCache,
+    watcher::BalanceWatcher,
+};
+use crate::opti::{adaptive::AdaptiveThreshold, volatility::VolatilityEstimator};
+use crate::utils::constants::{ADAPTIVE_THRESHOLD_EWMA_LAMBDA, ADAPTIVE_THRESHOLD_MIN_TRADES, PNL_ROLLING_WINDOW_MS, VOLATILITY_EWMA_LAMBDA};
+
+/// Builder for creating MarketMaker instances, each step adding the next required component:
+/// `MarketMakerBuilder::new().config(..).feed(..).execution(..).tokens(..).build()`.
+pub struct MarketMakerBuilder<S = Empty> {
+    state: S,
+}
 
-/// Builder for creating MarketMaker instances.
-pub struct MarketMakerBuilder {
-    config: super::config::MarketMakerConfig,
+/// Builder states, named after the last component added.
+pub struct Empty;
+pub struct WithConfig {
+    config: MarketMakerConfig,
+}
+pub struct WithFeed {
+    config: MarketMakerConfig,
+    feed: Box<dyn PriceFeed>,
+}
+pub struct WithExecution {
+    config: MarketMakerConfig,
     feed: Box<dyn PriceFeed>,
     execution: Box<dyn ExecStrategy>,
 }
+pub struct WithTokens {
+    config: MarketMakerConfig,
+    feed: Box<dyn PriceFeed>,
+    execution: Box<dyn ExecStrategy>,
+    base: Token,
+    quote: Token,
+    engine: Box<dyn DecisionEngine>,
+    market_data: Box<dyn MarketDataSource>,
+    executor: Box<dyn Execution>,
+}
+
+impl Default for MarketMakerBuilder<Empty> {
+    fn default() -> Self {
+        Self { state: Empty }
+    }
+}
+
+impl MarketMakerBuilder<Empty> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn config(self, config: MarketMakerConfig) -> MarketMakerBuilder<WithConfig> {
+        MarketMakerBuilder { state: WithConfig { config } }
+    }
+
+    /// Static factory method to create a MarketMaker instance directly.
+    #[deprecated(note = "use MarketMakerBuilder::new().config(..).feed(..).execution(..).tokens(..).build()")]
+    pub fn create(config: MarketMakerConfig, feed: Box<dyn PriceFeed>, execution: Box<dyn ExecStrategy>, base: Token, quote: Token) -> Result<MarketMaker, String> {
+        Self::new().config(config).feed(feed).execution(execution).tokens(base, quote).build().map_err(|e| e.to_string())
+    }
+}
+
+impl MarketMakerBuilder<WithConfig> {
+    pub fn feed(self, feed: Box<dyn PriceFeed>) -> MarketMakerBuilder<WithFeed> {
+        let WithConfig { config } = self.state;
+        MarketMakerBuilder { state: WithFeed { config, feed } }
+    }
+}
+
+impl MarketMakerBuilder<WithFeed> {
+    pub fn execution(self, execution: Box<dyn ExecStrategy>) -> MarketMakerBuilder<WithExecution> {
+        let WithFeed { config, feed } = self.state;
+        MarketMakerBuilder {
+            state: WithExecution { config, feed, execution },
+        }
+    }
+}
+
+impl MarketMakerBuilder<WithExecution> {
+    pub fn tokens(self, base: Token, quote: Token) -> MarketMakerBuilder<WithTokens> {
+        let WithExecution { config, feed, execution } = self.state;
+        MarketMakerBuilder {
+            state: WithTokens {
+                config,
+                feed,
+                execution,
+                base,
+                quote,
+                engine: Box::new(DefaultEngine),
+                market_data: Box::new(ChainDataSource),
+                executor: Box::new(RouterExecution),
+            },
+        }
+    }
+
+    /// Builds a MarketMaker instance with its tokens.
+    #[deprecated(note = "use .tokens(base, quote).build()")]
+    pub fn build(self, base: Token, quote: Token) -> Result<MarketMaker, String> {
+        self.tokens(base, quote).build().map_err(|e| e.to_string())
+    }
+}
 
-impl MarketMakerBuilder {
-    /// Creates a new MarketMakerBuilder with configuration and strategies.
-    pub fn new(config: super::config::MarketMakerConfig, feed: Box<dyn PriceFeed>, execution: Box<dyn ExecStrategy>) -> Self {
-        Self { config, feed, execution }
+impl MarketMakerBuilder<WithTokens> {
+    /// Replaces the default decision engine, e.g. for a custom sizing.
+    pub fn engine(mut self, engine: Box<dyn DecisionEngine>) -> Self {
+        self.state.engine = engine;
+        self
+    }
+
+    /// Replaces the default market data source (price feed and chain), e.g. with fixed data in tests.
+    pub fn market_data(mut self, market_data: Box<dyn MarketDataSource>) -> Self {
+        self.state.market_data = market_data;
+        self
+    }
+
+    /// Replaces the default execution (router encoding, then the execution strategy).
+    pub fn executor(mut self, executor: Box<dyn Execution>) -> Self {
+        self.state.executor = executor;
+        self
     }
 
     /// Generates a unique identifier for the market maker instance.
     ///
     /// Creates identifier from network, token pair, wallet address prefix, and timestamp.
-    pub fn identifier(&self) -> String {
+    /// Fails if the wallet address is too short for its prefix.
+    pub fn identifier(&self) -> Result<String, MarketMakerError> {
+        let config = &self.state.config;
         let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
         // Merging of config.identifier() and timestamp
-        let f7 = self.config.wallet_public_key[..9].to_string(); // 0x + 7 chars
-        let msg = format!("mmc-{}-{}-{}-{}", self.config.network_name, self.config.base_token, self.config.quote_token, f7);
-        let identifier = format!("{}-instance-{}", msg.to_lowercase(), timestamp);
-        identifier.to_string()
+        let f7 = config
+            .wallet_public_key
+            .get(..9) // 0x + 7 chars
+            .ok_or_else(|| MarketMakerError::Build(format!("Cannot derive the identifier from the wallet address '{}'", config.wallet_public_key)))?;
+        let msg = format!("mmc-{}-{}-{}-{}", config.network_name, config.base_token, config.quote_token, f7);
+        Ok(format!("{}-instance-{}", msg.to_lowercase(), timestamp))
     }
 
     /// Builds a MarketMaker instance from the configured builder.
     ///
-    /// Consumes the builder and creates a configured MarketMaker instance.
-    pub fn build(self, base: Token, quote: Token) -> Result<MarketMaker, String> {
-        let identifier = self.identifier();
+    /// Fails if a token has no decimals, if the execution strategy sends to another network than the config, or if the
+    /// identifier cannot be derived.
+    pub fn build(self) -> Result<MarketMaker, MarketMakerError> {
+        let identifier = self.identifier()?;
+        let WithTokens {
+            config,
+            feed,
+            execution,
+            base,
+            quote,
+            engine,
+            market_data,
+            executor,
+        } = self.state;
+        for token in [&base, &quote] {
+            if token.decimals == 0 {
+                return Err(MarketMakerError::Build(format!("Token {} ({}) has no decimals", token.symbol, token.address)));
+            }
+        }
+        if let Some(network) = execution.network().filter(|network| network.as_str() != config.network_name.as_str()) {
+            return Err(MarketMakerError::Build(format!(
+                "Execution strategy {} sends to {}, not {}",
+                execution.name(),
+                network.as_str(),
+                config.network_name.as_str()
+            )));
+        }
+        tracing::info!(
+            "Building MarketMaker with feed: {}, execution: {}, engine: {}, market data: {} and executor: {}",
+            feed.name(),
+            execution.name(),
+            engine.name(),
+            market_data.name(),
+            executor.name()
+        );
+        let breaker = CircuitBreaker::new(config.max_daily_loss_usd, config.breaker_cooldown_ms as u128);
+        let cooldown = PoolCooldown::new(config.pool_cooldown_blocks);
+        let tombstones = Tombstones::new(config.tombstone_blocks);
+        let journal = TradeJournal::new(&config.id(), config.pool_cooldown_blocks);
+        let quarantine = PoolQuarantine::new(config.max_plausible_spread_bps, config.quarantine_alert_blocks);
+        let audit = AuditLog::new(&config);
+        let lag = StreamLag::new(config.max_stream_lag_blocks, config.stream_lag_sample_every);
+        let inventory = InventoryCache::new(config.inventory_refresh_interval_ms);
+        let watcher = BalanceWatcher::new(config.balance_watch_interval_ms, config.external_movement_threshold_bps);
+        let health = HealthState::new(&identifier, &config);
+        let reconnect = Reconnect::new(config.stream_restart_delay_ms, config.stream_restart_max_delay_ms);
+        let snapshot = SnapshotStore::new(&config);
+        let adaptive_max_bps = if config.adaptive_threshold { config.adaptive_threshold_max_bps } else { 0.0 };
+        let mut adaptive = AdaptiveThreshold::new(ADAPTIVE_THRESHOLD_EWMA_LAMBDA, adaptive_max_bps, ADAPTIVE_THRESHOLD_MIN_TRADES);
+        // Resumed from the previous process, the rolling shortfall outlives the pool states of the snapshot
+        let restored = if config.adaptive_threshold {
+            snapshot::load(&snapshot::path(&config), &config.id()).and_then(|s| s.adaptive)
+        } else {
+            None
+        };
+        if let Some(state) = restored {
+            adaptive.restore(state);
+            tracing::info!("🎚️  Adaptive threshold resumed: widening {:.2} bps after {} trades", adaptive.widening_bps(), state.trades);
+        }
         Ok(MarketMaker {
             ready: false,
+            pools: StreamPools::default(),
+            reconnect,
             identifier,
-            config: self.config,
-            feed: self.feed,
+            config,
+            feed,
             initialised: false,
             base,
             quote,
             single: false,
-            execution: self.execution,
+            execution,
+            engine,
+            market_data,
+            executor,
+            pnl: PnlTracker::new(PNL_ROLLING_WINDOW_MS),
+            breaker,
+            cooldown,
+            tombstones,
+            journal,
+            quarantine,
+            audit,
+            lag,
+            nonce_lock: Arc::new(tokio::sync::Mutex::new(())),
+            control: Control::default(),
+            shutdown: Shutdown::default(),
+            health,
+            inventory,
+            allowances: RouterAllowances::default(),
+            watcher,
+            pool_balances: Arc::new(BalanceCache::default()),
+            volatility: VolatilityEstimator::new(VOLATILITY_EWMA_LAMBDA),
+            adaptive,
+            sim_cache: Arc::new(SimCache::new(config.sim_cache_significant_digits)),
+            clock: Arc::new(SystemClock),
+            evaluated_spots: HashMap::new(),
+            frozen: None,
+            snapshot,
         })
     }
+}
 
-    /// Static factory method to create a MarketMaker instance directly.
-    ///
-    /// Creates builder and immediately builds MarketMaker, logging strategy names.
-    pub fn create(config: super::config::MarketMakerConfig, feed: Box<dyn PriceFeed>, execution: Box<dyn ExecStrategy>, base: Token, quote: Token) -> Result<MarketMaker, String> {
-        tracing::info!("Building MarketMaker with feed: {} and execution: {}", feed.name(), execution.name());
-        let builder = Self::new(config, feed, execution);
-        builder.build(base, quote)
+impl MarketMakerBuilder<Empty> {
+    /// Market maker of a config for the one-off commands (`maker quote`, `maker inventory`), with the dry run strategy
+    /// so it never executes. Its tokens come from the token cache or the Tycho API, returned with it.
+    pub async fn standalone(config: MarketMakerConfig, key: &str) -> Result<(MarketMaker, Vec<Token>), String> {
+        let addresses = vec![config.base_token_address.clone(), config.quote_token_address.clone()];
+        let tokens = crate::maker::universe::universe(config.clone(), Some(key), addresses)
+            .await
+            .map_err(|e| format!("Failed to fetch tokens from Tycho API: {}", e))?;
+        let find = |address: &str| tokens.iter().find(|t| t.address.to_string() == address.to_lowercase()).cloned();
+        let base = find(&config.base_token_address).ok_or_else(|| format!("Base token not found: {}", config.base_token_address))?;
+        let quote = find(&config.quote_token_address).ok_or_else(|| format!("Quote token not found: {}", config.quote_token_address))?;
+        let feed = PriceFeedFactory::create(config.price_feed_config.r#type.as_str());
+        let mk = Self::new()
+            .config(config)
+            .feed(feed)
+            .execution(Box::new(DryRunExec::new()))
+            .tokens(base, quote)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok((mk, tokens))
     }
 }
@@ -0,0 +1,255 @@
+//! Command Line Arguments Module
+//!
+//! Flags of the `maker`, `monitor` and `backtest` binaries. Each flag falls back to the environment
+//! variable or path the binaries used before (`CONFIG_PATHS`/`CONFIG_PATH`, `SECRET_PATH`, `RUST_LOG`),
+//! so existing scripts and containers keep working without arguments.
+use clap::{Parser, Subcommand};
+
+use std::str::FromStr;
+
+use crate::maker::backtest::FillModel;
+use crate::types::config::{config_paths, load_market_maker_config, ConfigError, MarketMakerConfig, NetworkName, Result};
+use crate::utils::constants::{
+    DEFAULT_BACKTEST_BASE_BALANCE, DEFAULT_BACKTEST_FEE_BPS, DEFAULT_BACKTEST_GAS_PRICE_GWEI, DEFAULT_BACKTEST_IMPACT_BPS_PER_PCT, DEFAULT_BACKTEST_IMPACT_EXPONENT, DEFAULT_BACKTEST_LIQUIDITY,
+    DEFAULT_BACKTEST_OUTPUT_DIR, DEFAULT_SWAP_GAS,
+};
+
+/// Secrets file of the monitor, used without `--secrets`.
+pub const DEFAULT_MONITOR_SECRET_PATH: &str = "config/secrets/.env.monitor.global";
+
+/// Arguments of the market maker binary.
+#[derive(Debug, Clone, Default, Parser)]
+#[command(name = "maker", about = "Tycho market maker")]
+pub struct MakerCli {
+    /// Config file, or comma-separated files for a multi-pair process (default: CONFIG_PATHS, then CONFIG_PATH)
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Secrets file loaded into the environment
+    #[arg(long, env = "SECRET_PATH")]
+    pub secrets: Option<String>,
+    /// Simulate trades without broadcasting them, whatever the network
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Log filter, e.g. "info" or "off,maker=trace,shd=debug" (default: RUST_LOG)
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// Network replacing `network_name` (and its chain id) in every config
+    #[arg(long)]
+    pub network_override: Option<String>,
+    /// Load, validate and print the configuration, then exit (non-zero if invalid)
+    #[arg(long)]
+    pub print_config: bool,
+    /// Print the JSON schema of the config files, then exit
+    #[arg(long)]
+    pub schema: bool,
+    /// Check the RPC, Tycho API, price feeds, wallet, Redis and contracts of every config, then exit (non-zero on failure)
+    #[arg(long)]
+    pub preflight: bool,
+    /// JSON fixture of the reference price, market context and inventory, read instead of the network (forces --dry-run)
+    #[arg(long)]
+    pub frozen_context: Option<String>,
+    /// One-off command instead of running the market maker
+    #[command(subcommand)]
+    pub command: Option<MakerCommand>,
+}
+
+/// One-off commands of the maker binary.
+#[derive(Debug, Clone, Subcommand)]
+pub enum MakerCommand {
+    /// Prints the decisions of an audit log within a time range, then exits
+    Audit {
+        /// Audit file (`audit_log_path`), its rotated file is read too
+        file: String,
+        /// Start of the range, UTC, as RFC 3339 or "YYYY-MM-DD HH:MM[:SS]"
+        #[arg(long)]
+        from: Option<String>,
+        /// End of the range, same format
+        #[arg(long)]
+        to: Option<String>,
+        /// Only the decisions of this pool
+        #[arg(long)]
+        pool: Option<String>,
+    },
+    /// Quotes a swap on one pool of the first config with the readjust math, then exits (0 if it would trade, 1 otherwise)
+    Quote {
+        /// Component id, or "best" for the target with the best net price
+        pool: String,
+        /// Amount sold, in base tokens (quote tokens with --sell-quote)
+        #[arg(long)]
+        amount: f64,
+        /// Sell quote tokens for base tokens instead
+        #[arg(long)]
+        sell_quote: bool,
+    },
+    /// Prints the wallet inventory of the first config valued in USD, then exits
+    Inventory {
+        /// Refresh every N seconds instead of exiting
+        #[arg(long)]
+        watch: Option<u64>,
+        /// One JSON object per refresh instead of the table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Sets the allowance of a spender on a token from the wallet of the first config, then exits
+    Approve {
+        /// Token address, or "base" / "quote" for the tokens of the config
+        #[arg(long)]
+        token: String,
+        /// Spender address (default: tycho_router_address)
+        #[arg(long)]
+        spender: Option<String>,
+        /// Amount approved, in tokens
+        #[arg(long, required_unless_present_any = ["max", "revoke"], conflicts_with_all = ["max", "revoke"])]
+        amount: Option<f64>,
+        /// Approve u128::MAX
+        #[arg(long, conflicts_with = "revoke")]
+        max: bool,
+        /// Approve 0
+        #[arg(long)]
+        revoke: bool,
+        /// Approve even when TESTING is set
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl MakerCli {
+    /// Config paths from `--config`, then CONFIG_PATHS, then CONFIG_PATH. Empty if none is set.
+    pub fn paths(&self) -> Vec<String> {
+        if let Some(raw) = self.config.as_deref() {
+            return config_paths(raw);
+        }
+        let paths = config_paths(std::env::var("CONFIG_PATHS").unwrap_or_default().as_str());
+        if !paths.is_empty() {
+            return paths;
+        }
+        config_paths(std::env::var("CONFIG_PATH").unwrap_or_default().as_str())
+    }
+
+    /// Applies the flags overriding the config file, then validates the result.
+    pub fn apply(&self, config: &mut MarketMakerConfig) -> Result<()> {
+        if let Some(network) = self.network_override.as_deref() {
+            let chain = NetworkName::from_str(network).map_err(|e| ConfigError::Config(format!("--network-override: {}", e)))?;
+            config.network_name = chain.as_str().to_string();
+            config.chain_id = chain.chain_id();
+        }
+        if self.dry_run || self.frozen_context.is_some() {
+            config.dry_run = true;
+        }
+        config.validate()
+    }
+
+    /// Loads every config file with the flags applied.
+    pub fn configs(&self) -> Result<Vec<MarketMakerConfig>> {
+        let paths = self.paths();
+        if paths.is_empty() {
+            return Err(ConfigError::Config("No config file, set --config, CONFIG_PATHS or CONFIG_PATH".into()));
+        }
+        let mut configs = vec![];
+        for path in paths.iter() {
+            let mut config = load_market_maker_config(path).map_err(|e| ConfigError::Config(format!("{}: {}", path, e)))?;
+            self.apply(&mut config).map_err(|e| ConfigError::Config(format!("{}: {}", path, e)))?;
+            configs.push(config);
+        }
+        Ok(configs)
+    }
+}
+
+/// Arguments of the monitor binary.
+#[derive(Debug, Clone, Default, Parser)]
+#[command(name = "monitor", about = "Tycho market maker monitor")]
+pub struct MonitorCli {
+    /// Secrets file loaded into the environment
+    #[arg(long, default_value = DEFAULT_MONITOR_SECRET_PATH)]
+    pub secrets: String,
+    /// Log filter, e.g. "info" or "off,monitor=trace,shd=debug" (default: RUST_LOG)
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// One-off command instead of listening to the events
+    #[command(subcommand)]
+    pub command: Option<MonitorCommand>,
+}
+
+/// One-off commands of the monitor binary.
+#[derive(Debug, Clone, Subcommand)]
+pub enum MonitorCommand {
+    /// Replays the dead-lettered messages through the handler, then exits
+    Retry {
+        /// Max messages replayed, oldest first (default: all)
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+}
+
+/// Arguments of the backtest binary.
+#[derive(Debug, Clone, Parser)]
+#[command(name = "backtest", about = "Replays the prices recorded by the monitor through the market maker decisions")]
+pub struct BacktestCli {
+    /// Config file of the replayed market maker, with the spreads to try
+    #[arg(long)]
+    pub config: String,
+    /// Secrets file loaded into the environment, with the DATABASE_URL of the monitor
+    #[arg(long, default_value = DEFAULT_MONITOR_SECRET_PATH)]
+    pub secrets: String,
+    /// Instance identifier, or prefix of the identifiers, whose prices are replayed (default: every run of the config pair)
+    #[arg(long)]
+    pub instance: Option<String>,
+    /// Start of the range, UTC, as RFC 3339 or "YYYY-MM-DD HH:MM[:SS]" (default: first row)
+    #[arg(long)]
+    pub from: Option<String>,
+    /// End of the range, same format (default: now)
+    #[arg(long)]
+    pub to: Option<String>,
+    /// Directory of the series, fills, gate stats, report and decisions of the run
+    #[arg(long, default_value = DEFAULT_BACKTEST_OUTPUT_DIR)]
+    pub out: String,
+    /// Fee of the synthetic pools, in bps
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_FEE_BPS)]
+    pub fee_bps: f64,
+    /// Base token on each side of a synthetic pool
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_LIQUIDITY)]
+    pub liquidity: f64,
+    /// Average execution impact, in bps, of a fill taking 1% of the liquidity
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_IMPACT_BPS_PER_PCT)]
+    pub impact_bps_per_pct: f64,
+    /// Curvature of the impact: 1 linear, 0.5 square root
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_IMPACT_EXPONENT)]
+    pub impact_exponent: f64,
+    /// Gas of a swap
+    #[arg(long, default_value_t = DEFAULT_SWAP_GAS)]
+    pub gas_units: u64,
+    /// Gas price, in gwei
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_GAS_PRICE_GWEI)]
+    pub gas_price_gwei: f64,
+    /// ETH price in USD (default: the reference, when the base is the gas token and the quote a USD stablecoin)
+    #[arg(long)]
+    pub eth_usd: Option<f64>,
+    /// Initial base balance, normalized
+    #[arg(long, default_value_t = DEFAULT_BACKTEST_BASE_BALANCE)]
+    pub base_balance: f64,
+    /// Initial quote balance, normalized (default: the value of the base balance at the first reference price)
+    #[arg(long)]
+    pub quote_balance: Option<f64>,
+    /// Decimals of the base token, the Tycho token list is not fetched
+    #[arg(long, default_value_t = 18)]
+    pub base_decimals: u32,
+    /// Decimals of the quote token
+    #[arg(long, default_value_t = 6)]
+    pub quote_decimals: u32,
+    /// Log filter, e.g. "info" or "off,backtest=info,shd=warn" (default: RUST_LOG)
+    #[arg(long)]
+    pub log_level: Option<String>,
+}
+
+impl BacktestCli {
+    /// Fill model of the synthetic pools.
+    pub fn model(&self) -> FillModel {
+        FillModel {
+            fee_bps: self.fee_bps,
+            liquidity: self.liquidity,
+            impact_bps_per_pct: self.impact_bps_per_pct,
+            impact_exponent: self.impact_exponent,
+            gas_units: self.gas_units,
+        }
+    }
+}
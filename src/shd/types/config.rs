@@ -1,6 +1,21 @@
-use crate::utils::{self, constants::BASIS_POINT_DENO};
+use crate::utils::{
+    self,
+    constants::{
+        BASIS_POINT_DENO, CONFIG_ENV_OVERRIDE_PREFIX, CONFIG_EXTENDS_KEY, DEFAULT_ADAPTIVE_THRESHOLD_MAX_BPS, DEFAULT_AUDIT_LOG_MAX_BYTES, DEFAULT_BALANCE_WATCH_INTERVAL_MS,
+        DEFAULT_BREAKER_COOLDOWN_MS, DEFAULT_DEADLINE_GRACE_BLOCKS, DEFAULT_DEPTH_LADDER_USD, DEFAULT_DEPTH_REPORT_INTERVAL_MS, DEFAULT_EXTERNAL_MOVEMENT_THRESHOLD_BPS, DEFAULT_FEE_HISTORY_BLOCKS,
+        DEFAULT_GAS_ORACLE_JITTER_MS, DEFAULT_GAS_ORACLE_REFRESH_MS, DEFAULT_HEARTBEAT_INTERVAL_S, DEFAULT_HEARTBEAT_TIMEOUT_MS, DEFAULT_INVENTORY_REFRESH_INTERVAL_MS, DEFAULT_MAX_CRASHES_PER_HOUR,
+        DEFAULT_MAX_DAILY_LOSS_USD, DEFAULT_MAX_PLAUSIBLE_SPREAD_BPS, DEFAULT_MAX_POOL_SHARE_BPS, DEFAULT_MAX_STREAM_LAG_BLOCKS, DEFAULT_MAX_TOKEN_EXPOSURE_PCT, DEFAULT_MIN_AMOUNT_WORTH_USD,
+        DEFAULT_MIN_NATIVE_BALANCE_WEI, DEFAULT_MIN_REFERENCE_PRICE_MOVE_BPS, DEFAULT_PERMIT2_EXPIRATION_S, DEFAULT_PNL_REPORT_INTERVAL_MS, DEFAULT_POOL_COOLDOWN_BLOCKS,
+        DEFAULT_PRICE_DOWNSAMPLE_MINUTES, DEFAULT_QUARANTINE_ALERT_BLOCKS, DEFAULT_READJUST_CONCURRENCY, DEFAULT_RPC_MAX_FAILURES, DEFAULT_RPC_PROBE_INTERVAL_MS, DEFAULT_RPC_SLOW_MS,
+        DEFAULT_SHUTDOWN_GRACE_PERIOD_MS, DEFAULT_SKEW_GAIN_BPS_PER_PCT, DEFAULT_SNAPSHOT_INTERVAL_MS, DEFAULT_SNAPSHOT_MAX_AGE_S, DEFAULT_STALE_BLOCK_TOLERANCE, DEFAULT_STREAM_LAG_SAMPLE_EVERY,
+        DEFAULT_STREAM_RESTART_DELAY_MS, DEFAULT_STREAM_RESTART_MAX_DELAY_MS, DEFAULT_STREAM_STALENESS_THRESHOLD_S, DEFAULT_TARGET_INVENTORY_RATIO, DEFAULT_TOKEN_CACHE_DIR,
+        DEFAULT_TOKEN_CACHE_MAX_AGE_H, DEFAULT_TOKEN_MIN_QUALITY, DEFAULT_TOKEN_TRADED_DAYS, DEFAULT_TOMBSTONE_BLOCKS, DEFAULT_TRADE_RECEIPT_MAX_AGE_MINUTES, DEFAULT_TVL_ADD_THRESHOLD,
+        DEFAULT_TVL_REMOVE_THRESHOLD, DEFAULT_VOL_MULTIPLIER, DEFAULT_VOL_WIDENING_CAP_BPS, DEFAULT_WS_MAX_HEAD_AGE_S, MAX_FEE_HISTORY_BLOCKS, MULTICALL3_ADDRESS, OPTI_MAX_SIMULATIONS,
+    },
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{fs, str::FromStr, time::Duration};
+use std::{collections::HashMap, fs, str::FromStr, time::Duration};
 
 // Define local error types since we're not using the global error module
 #[derive(Debug, thiserror::Error)]
@@ -11,7 +26,12 @@ pub enum ConfigError {
 
 pub type Result<T> = std::result::Result<T, ConfigError>;
 
-use super::maker::PriceFeedConfig;
+use super::{maker::PriceFeedConfig, tycho::TychoSupportedProtocol};
+
+/// Helper function to validate component ids (0x-prefixed hex, 20-byte pool addresses or 32-byte pool ids)
+fn is_valid_component_id(id: &str) -> bool {
+    id.len() > 2 && id.starts_with("0x") && id[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
 
 /// Helper function to validate Ethereum addresses
 fn is_valid_eth_address(address: &str) -> bool {
@@ -26,18 +46,82 @@ fn is_valid_eth_address(address: &str) -> bool {
     address[2..].chars().all(|c| c.is_ascii_hexdigit())
 }
 
+/// Validates an error signature, e.g. "MyError(uint256,address)": an identifier, then its argument types in parentheses.
+fn is_error_signature(signature: &str) -> bool {
+    let Some((name, rest)) = signature.split_once('(') else {
+        return false;
+    };
+    let starts = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$');
+    starts && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') && rest.ends_with(')') && !rest.contains(' ')
+}
+
 /// Environment configuration expected
 #[derive(Debug, Clone)]
 pub struct EnvConfig {
     pub path: String,
+    // Config paths of a multi-pair process (CONFIG_PATHS, comma-separated), empty for a single pair
+    pub paths: Vec<String>,
     pub testing: bool,
     // APIs
     pub heartbeat: String,
+    pub heartbeat_interval_s: u64, // HEARTBEAT_INTERVAL_S, between two heartbeat POSTs
+    pub heartbeat_timeout_ms: u64, // HEARTBEAT_TIMEOUT_MS, a slower heartbeat is counted as failed
     pub tycho_api_key: String,
-    // Wallet
-    pub wallet_private_key: String,
+    // Wallet, signed with the key selected by SIGNER_TYPE
+    pub signer_type: SignerType,
+    pub wallet_private_key: String, // Raw signer only, empty otherwise
+    pub keystore_path: Option<String>,
+    pub keystore_password: Option<String>,
+    pub remote_signer_url: Option<String>,
+    pub remote_signer_address: Option<String>,
     // Flashbots bundle signer (persistent for builder reputation)
     pub bundle_signer_key: Option<String>,
+    // Redis transport of the published events (EVENTS_TRANSPORT), must match the monitor
+    pub events_transport: EventsTransport,
+    // Also use the pre-v1 global Redis channels and keys (REDIS_LEGACY_KEYS, true unless "false"), see `data::keys`
+    pub redis_legacy_keys: bool,
+    // Port of the health server (HEALTH_PORT, /healthz and /readyz), 0 to disable it
+    pub health_port: u16,
+    // Push alerts, see `utils::alert`: Telegram bot and chat, and Slack-compatible webhook
+    pub alert_telegram_bot_token: Option<String>,
+    pub alert_telegram_chat_id: Option<String>,
+    pub alert_webhook_url: Option<String>,
+}
+
+/// Where the wallet key lives (SIGNER_TYPE).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SignerType {
+    /// Plaintext WALLET_PRIVATE_KEY (default)
+    #[default]
+    Raw,
+    /// Encrypted JSON keystore at KEYSTORE_PATH, decrypted with KEYSTORE_PASSWORD
+    Keystore,
+    /// External signer at REMOTE_SIGNER_URL (eth_signTransaction) holding REMOTE_SIGNER_ADDRESS
+    Remote,
+}
+
+impl FromStr for SignerType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(SignerType::Raw),
+            "keystore" => Ok(SignerType::Keystore),
+            "remote" => Ok(SignerType::Remote),
+            _ => Err(format!("Unknown signer type: {} (expected raw, keystore or remote)", s)),
+        }
+    }
+}
+
+impl SignerType {
+    /// Converts to string representation.
+    pub fn as_str(&self) -> &str {
+        match self {
+            SignerType::Raw => "raw",
+            SignerType::Keystore => "keystore",
+            SignerType::Remote => "remote",
+        }
+    }
 }
 
 /// Environment configuration expected
@@ -45,8 +129,225 @@ pub struct EnvConfig {
 pub struct MoniEnvConfig {
     pub testing: bool,
     pub heartbeat: String,
+    pub heartbeat_interval_s: u64, // HEARTBEAT_INTERVAL_S
+    pub heartbeat_timeout_ms: u64, // HEARTBEAT_TIMEOUT_MS
     pub database_url: String,
     pub database_name: String,
+    pub database_backend: DatabaseType,    // DATABASE_BACKEND, sqlite for local development
+    pub events_transport: EventsTransport, // EVENTS_TRANSPORT, must match the makers
+    // Price table retention (0 disables): rows older than the downsample horizon are aggregated per bucket, rows older than the retention deleted
+    pub price_retention_days: u64,
+    pub price_downsample_after_days: u64,
+    pub price_downsample_minutes: u64,
+    pub trade_receipt_max_age_minutes: u64, // TRADE_RECEIPT_MAX_AGE_MINUTES, trades still without receipt past it are marked dropped
+    pub redis_legacy_keys: bool,            // REDIS_LEGACY_KEYS, also reads the pre-v1 channel and streams of the makers not upgraded yet
+}
+
+/// How events travel from the makers to the monitor (EVENTS_TRANSPORT).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EventsTransport {
+    /// Redis pub/sub channel, events published while the monitor is down are lost (default, during the migration)
+    #[default]
+    PubSub,
+    /// One Redis stream per message type, consumed by a group and acknowledged once stored
+    Streams,
+}
+
+impl FromStr for EventsTransport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pubsub" => Ok(EventsTransport::PubSub),
+            "streams" => Ok(EventsTransport::Streams),
+            _ => Err(format!("Unknown events transport: {} (expected pubsub or streams)", s)),
+        }
+    }
+}
+
+impl EventsTransport {
+    /// Converts to string representation.
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventsTransport::PubSub => "pubsub",
+            EventsTransport::Streams => "streams",
+        }
+    }
+
+    /// Reads EVENTS_TRANSPORT, pubsub when unset. Exits on an unknown value.
+    pub fn from_env() -> Self {
+        match EventsTransport::from_str(std::env::var("EVENTS_TRANSPORT").unwrap_or_else(|_| "pubsub".into()).as_str()) {
+            Ok(transport) => transport,
+            Err(e) => {
+                eprintln!("Error: EVENTS_TRANSPORT: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Format of the log lines (LOG_FORMAT).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable lines (default)
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields of the current spans (e.g. `trade_id`), for log aggregators
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Unknown log format: {} (expected text or json)", s)),
+        }
+    }
+}
+
+impl LogFormat {
+    /// Converts to string representation.
+    pub fn as_str(&self) -> &str {
+        match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        }
+    }
+
+    /// Reads LOG_FORMAT, text when unset. Exits on an unknown value.
+    pub fn from_env() -> Self {
+        match LogFormat::from_str(std::env::var("LOG_FORMAT").unwrap_or_else(|_| "text".into()).as_str()) {
+            Ok(format) => format,
+            Err(e) => {
+                eprintln!("Error: LOG_FORMAT: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Database of the monitor (DATABASE_BACKEND).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DatabaseType {
+    /// Postgres (Neon), DATABASE_URL postgres://... (default)
+    #[default]
+    Postgres,
+    /// SQLite, DATABASE_URL sqlite::memory: or sqlite://<file>, for local development and tests
+    Sqlite,
+}
+
+impl FromStr for DatabaseType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "postgres" => Ok(DatabaseType::Postgres),
+            "sqlite" => Ok(DatabaseType::Sqlite),
+            _ => Err(format!("Unknown database backend: {} (expected postgres or sqlite)", s)),
+        }
+    }
+}
+
+impl DatabaseType {
+    /// Converts to string representation.
+    pub fn as_str(&self) -> &str {
+        match self {
+            DatabaseType::Postgres => "postgres",
+            DatabaseType::Sqlite => "sqlite",
+        }
+    }
+
+    /// Reads DATABASE_BACKEND, postgres when unset. Exits on an unknown value.
+    pub fn from_env() -> Self {
+        match DatabaseType::from_str(std::env::var("DATABASE_BACKEND").unwrap_or_else(|_| "postgres".into()).as_str()) {
+            Ok(backend) => backend,
+            Err(e) => {
+                eprintln!("Error: DATABASE_BACKEND: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Whether a DATABASE_URL targets this backend.
+    pub fn accepts(&self, url: &str) -> bool {
+        match self {
+            DatabaseType::Postgres => url.starts_with("postgres://") || url.starts_with("postgresql://"),
+            DatabaseType::Sqlite => url.starts_with("sqlite:"),
+        }
+    }
+}
+
+/// How a readjustment sizes its swap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RebalanceMode {
+    /// Sell the optimal input amount (default)
+    #[default]
+    ExactIn,
+    /// Buy an exact amount of the token whose inventory share is below target, sent as the input of its reverse quote
+    /// raised by `max_slippage_pct` (the max input), with the target as the minimum output
+    ExactOut,
+}
+
+/// EIP-1559 fee estimation from `eth_feeHistory`: a percentile of the recent priority fees, on top of the
+/// next base fee with headroom, i.e. `max_fee = base_fee * base_multiplier + priority`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FeeStrategy {
+    pub percentile: f64,        // Of the priority fees paid in each block, 0 to 100
+    pub base_multiplier: f64,   // Headroom on the next base fee, at least 1
+    pub min_priority_gwei: f64, // Floor of the priority fee
+    #[serde(default = "default_fee_history_blocks")]
+    pub blocks: u64, // Recent blocks read with eth_feeHistory
+}
+
+impl FeeStrategy {
+    /// Checks the bounds of the fields.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !(0.0..=100.0).contains(&self.percentile) {
+            return Err(format!("percentile must be between 0 and 100, got {}", self.percentile));
+        }
+        if !self.base_multiplier.is_finite() || self.base_multiplier < 1. {
+            return Err(format!("base_multiplier must be at least 1, got {}", self.base_multiplier));
+        }
+        if !self.min_priority_gwei.is_finite() || self.min_priority_gwei < 0. {
+            return Err(format!("min_priority_gwei must be positive, got {}", self.min_priority_gwei));
+        }
+        if !(1..=MAX_FEE_HISTORY_BLOCKS).contains(&self.blocks) {
+            return Err(format!("blocks must be between 1 and {}, got {}", MAX_FEE_HISTORY_BLOCKS, self.blocks));
+        }
+        Ok(())
+    }
+}
+
+/// Latency budget of the pipeline run on a block update, in milliseconds elapsed since its receipt. A stage started
+/// past its deadline is reported, and the remaining pipeline is aborted past `total_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LatencyBudget {
+    pub context_ms: u64,   // Market context fetch
+    pub inventory_ms: u64, // Inventory fetch
+    pub prepare_ms: u64,   // Calldata encoding of the orders
+    pub simulate_ms: u64,  // Simulation of the trades
+    pub broadcast_ms: u64, // Broadcast of the trades
+    pub total_ms: u64,     // Past it, nothing more is done for the block, 0 disables the budget
+}
+
+impl LatencyBudget {
+    /// Checks the deadlines follow the order of the stages, within the total budget.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.total_ms == 0 {
+            return Ok(());
+        }
+        let deadlines = [self.context_ms, self.inventory_ms, self.prepare_ms, self.simulate_ms, self.broadcast_ms, self.total_ms];
+        if deadlines.windows(2).any(|w| w[0] > w[1]) {
+            return Err(format!("deadlines must not decrease from context_ms to total_ms, got {:?}", deadlines));
+        }
+        Ok(())
+    }
 }
 
 /// Enum for network
@@ -79,6 +380,74 @@ impl NetworkName {
             NetworkName::Unichain => "unichain",
         }
     }
+
+    /// EIP-155 chain id of the network.
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            NetworkName::Ethereum => 1,
+            NetworkName::Base => 8453,
+            NetworkName::Unichain => 130,
+        }
+    }
+
+    /// Default fee strategy of the network. L2 priority fees are tiny and spiky, a low floor and less base fee
+    /// headroom (blocks every 1-2s) avoid overpaying them.
+    pub fn fee_strategy(&self) -> FeeStrategy {
+        match self {
+            NetworkName::Ethereum => FeeStrategy {
+                percentile: 30.,
+                base_multiplier: 2.,
+                min_priority_gwei: 0.01,
+                blocks: DEFAULT_FEE_HISTORY_BLOCKS,
+            },
+            NetworkName::Base | NetworkName::Unichain => FeeStrategy {
+                percentile: 30.,
+                base_multiplier: 1.5,
+                min_priority_gwei: 0.0001,
+                blocks: DEFAULT_FEE_HISTORY_BLOCKS,
+            },
+        }
+    }
+
+    /// Default latency budget of the network: half a block on mainnet, less than a block on the L2s, where an
+    /// opportunity rarely survives the next one.
+    pub fn latency_budget(&self) -> LatencyBudget {
+        match self {
+            NetworkName::Ethereum => LatencyBudget {
+                context_ms: 1_500,
+                inventory_ms: 2_500,
+                prepare_ms: 3_500,
+                simulate_ms: 4_500,
+                broadcast_ms: 5_000,
+                total_ms: 6_000,
+            },
+            NetworkName::Base => LatencyBudget {
+                context_ms: 300,
+                inventory_ms: 500,
+                prepare_ms: 700,
+                simulate_ms: 900,
+                broadcast_ms: 1_100,
+                total_ms: 1_500,
+            },
+            NetworkName::Unichain => LatencyBudget {
+                context_ms: 150,
+                inventory_ms: 250,
+                prepare_ms: 350,
+                simulate_ms: 450,
+                broadcast_ms: 550,
+                total_ms: 750,
+            },
+        }
+    }
+
+    /// Average block time of the network, in seconds.
+    pub fn block_time_s(&self) -> u64 {
+        match self {
+            NetworkName::Ethereum => 12,
+            NetworkName::Base => 2,
+            NetworkName::Unichain => 1,
+        }
+    }
 }
 
 impl Default for EnvConfig {
@@ -98,16 +467,62 @@ fn require_env(name: &str) -> String {
     }
 }
 
+/// Reads an optional numeric environment variable, `default` when unset. Exits if it is not a number.
+fn env_u64(name: &str, default: u64) -> u64 {
+    match std::env::var(name).ok().filter(|s| !s.is_empty()) {
+        Some(val) => val.parse().unwrap_or_else(|_| {
+            eprintln!("Error: {} must be a non-negative integer, got '{}'", name, val);
+            std::process::exit(1);
+        }),
+        None => default,
+    }
+}
+
+/// Splits a comma-separated list of config paths, ignoring blanks.
+pub fn config_paths(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect()
+}
+
 impl EnvConfig {
     /// Creates EnvConfig from environment variables.
     pub fn new() -> Self {
+        Self::from_paths(config_paths(std::env::var("CONFIG_PATHS").unwrap_or_default().as_str()))
+    }
+
+    /// Creates EnvConfig from environment variables, with the config paths given (e.g. by `--config`), CONFIG_PATH if empty.
+    pub fn from_paths(paths: Vec<String>) -> Self {
+        let signer_type = match SignerType::from_str(std::env::var("SIGNER_TYPE").unwrap_or_else(|_| "raw".into()).as_str()) {
+            Ok(signer_type) => signer_type,
+            Err(e) => {
+                eprintln!("Error: SIGNER_TYPE: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let optional = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
         EnvConfig {
-            path: require_env("CONFIG_PATH"),
+            path: paths.first().cloned().unwrap_or_else(|| require_env("CONFIG_PATH")),
+            paths,
             testing: require_env("TESTING") == "true",
             heartbeat: require_env("HEARTBEAT"),
-            wallet_private_key: require_env("WALLET_PRIVATE_KEY"),
+            heartbeat_interval_s: env_u64("HEARTBEAT_INTERVAL_S", DEFAULT_HEARTBEAT_INTERVAL_S),
+            heartbeat_timeout_ms: env_u64("HEARTBEAT_TIMEOUT_MS", DEFAULT_HEARTBEAT_TIMEOUT_MS),
+            signer_type,
+            wallet_private_key: if signer_type == SignerType::Raw { require_env("WALLET_PRIVATE_KEY") } else { String::new() },
+            keystore_path: optional("KEYSTORE_PATH"),
+            keystore_password: optional("KEYSTORE_PASSWORD"),
+            remote_signer_url: optional("REMOTE_SIGNER_URL"),
+            remote_signer_address: optional("REMOTE_SIGNER_ADDRESS"),
             tycho_api_key: require_env("TYCHO_API_KEY"),
             bundle_signer_key: std::env::var("BUNDLE_SIGNER_KEY").ok().filter(|s| !s.is_empty()),
+            events_transport: EventsTransport::from_env(),
+            redis_legacy_keys: std::env::var("REDIS_LEGACY_KEYS").map(|v| v.trim() != "false").unwrap_or(true),
+            health_port: u16::try_from(env_u64("HEALTH_PORT", 0)).unwrap_or_else(|_| {
+                eprintln!("Error: HEALTH_PORT must be a port number (0 to 65535)");
+                std::process::exit(1);
+            }),
+            alert_telegram_bot_token: optional("ALERT_TELEGRAM_BOT_TOKEN"),
+            alert_telegram_chat_id: optional("ALERT_TELEGRAM_CHAT_ID"),
+            alert_webhook_url: optional("ALERT_WEBHOOK_URL"),
         }
     }
 
@@ -116,8 +531,41 @@ impl EnvConfig {
         if self.tycho_api_key.is_empty() {
             return Err(ConfigError::Config("TYCHO_API_KEY cannot be empty".into()));
         }
-        if self.wallet_private_key.is_empty() {
-            return Err(ConfigError::Config("WALLET_PRIVATE_KEY cannot be empty".into()));
+        match self.signer_type {
+            SignerType::Raw => {
+                if self.wallet_private_key.is_empty() {
+                    return Err(ConfigError::Config("WALLET_PRIVATE_KEY cannot be empty".into()));
+                }
+            }
+            SignerType::Keystore => {
+                let path = self.keystore_path.as_deref().unwrap_or_default();
+                if path.is_empty() || !std::path::Path::new(path).is_file() {
+                    return Err(ConfigError::Config(format!("KEYSTORE_PATH must point to a keystore file, got '{}'", path)));
+                }
+                if self.keystore_password.is_none() {
+                    return Err(ConfigError::Config("KEYSTORE_PASSWORD is required with the keystore signer".into()));
+                }
+            }
+            SignerType::Remote => {
+                let url = self.remote_signer_url.as_deref().unwrap_or_default();
+                if url.parse::<url::Url>().is_err() {
+                    return Err(ConfigError::Config(format!("REMOTE_SIGNER_URL must be a valid URL, got '{}'", url)));
+                }
+                if !is_valid_eth_address(self.remote_signer_address.as_deref().unwrap_or_default()) {
+                    return Err(ConfigError::Config("REMOTE_SIGNER_ADDRESS must be a valid Ethereum address".into()));
+                }
+            }
+        }
+        if self.heartbeat_interval_s == 0 || self.heartbeat_timeout_ms == 0 {
+            return Err(ConfigError::Config("HEARTBEAT_INTERVAL_S and HEARTBEAT_TIMEOUT_MS must be positive".into()));
+        }
+        if self.alert_telegram_bot_token.is_some() != self.alert_telegram_chat_id.is_some() {
+            return Err(ConfigError::Config("ALERT_TELEGRAM_BOT_TOKEN and ALERT_TELEGRAM_CHAT_ID must be set together".into()));
+        }
+        if let Some(url) = self.alert_webhook_url.as_deref() {
+            if url.parse::<url::Url>().is_err() {
+                return Err(ConfigError::Config("ALERT_WEBHOOK_URL must be a valid URL".into()));
+            }
         }
         Ok(())
     }
@@ -126,10 +574,37 @@ impl EnvConfig {
     pub fn print(&self) {
         tracing::info!("Environment Configuration:");
         tracing::info!("  Config Path: {}", self.path);
+        if !self.paths.is_empty() {
+            tracing::info!("  Config Paths: {}", self.paths.join(", "));
+        }
         tracing::info!("  Testing Mode: {}", self.testing);
-        tracing::info!("  Heartbeat URL: {}", self.heartbeat);
+        tracing::info!("  Heartbeat URL: {} (every {} s, timeout {} ms)", self.heartbeat, self.heartbeat_interval_s, self.heartbeat_timeout_ms);
         tracing::info!("  Tycho API Key: {}...", &self.tycho_api_key[..8.min(self.tycho_api_key.len())]);
-        tracing::info!("  Wallet Private Key: {}...", &self.wallet_private_key[..8.min(self.wallet_private_key.len())]);
+        tracing::info!("  Signer: {}", self.signer_type.as_str());
+        tracing::info!("  Events Transport: {}", self.events_transport.as_str());
+        tracing::info!("  Redis Legacy Keys: {}", self.redis_legacy_keys);
+        // Alert credentials are secrets, only the channels are printed
+        let alerts = [("telegram", self.alert_telegram_bot_token.is_some()), ("webhook", self.alert_webhook_url.is_some())]
+            .iter()
+            .filter(|(_, set)| *set)
+            .map(|(name, _)| *name)
+            .collect::<Vec<&str>>();
+        tracing::info!("  Alerts: {}", if alerts.is_empty() { "none".to_string() } else { alerts.join(", ") });
+        tracing::info!("  Health Port: {}", if self.health_port == 0 { "disabled".to_string() } else { self.health_port.to_string() });
+        // Only locations are printed, never the keystore password nor the credentials a signer URL may carry
+        match self.signer_type {
+            SignerType::Raw => tracing::info!("  Wallet Private Key: {}...", &self.wallet_private_key[..8.min(self.wallet_private_key.len())]),
+            SignerType::Keystore => tracing::info!("  Keystore Path: {}", self.keystore_path.as_deref().unwrap_or_default()),
+            SignerType::Remote => tracing::info!(
+                "  Remote Signer: {} for {}",
+                self.remote_signer_url
+                    .as_deref()
+                    .and_then(|url| url.parse::<url::Url>().ok())
+                    .map(|url| url.origin().ascii_serialization())
+                    .unwrap_or_default(),
+                self.remote_signer_address.as_deref().unwrap_or_default()
+            ),
+        }
     }
 }
 
@@ -146,9 +621,41 @@ impl MoniEnvConfig {
             // paths: utils::misc::get("CONFIGS_PATHS"),
             testing: utils::misc::get("TESTING") == "true",
             heartbeat: utils::misc::get("HEARTBEAT"),
+            heartbeat_interval_s: env_u64("HEARTBEAT_INTERVAL_S", DEFAULT_HEARTBEAT_INTERVAL_S),
+            heartbeat_timeout_ms: env_u64("HEARTBEAT_TIMEOUT_MS", DEFAULT_HEARTBEAT_TIMEOUT_MS),
             database_url: utils::misc::get("DATABASE_URL"),
             database_name: utils::misc::get("DATABASE_NAME"),
+            database_backend: DatabaseType::from_env(),
+            events_transport: EventsTransport::from_env(),
+            price_retention_days: env_u64("PRICE_RETENTION_DAYS", 0),
+            price_downsample_after_days: env_u64("PRICE_DOWNSAMPLE_AFTER_DAYS", 0),
+            price_downsample_minutes: env_u64("PRICE_DOWNSAMPLE_MINUTES", DEFAULT_PRICE_DOWNSAMPLE_MINUTES),
+            trade_receipt_max_age_minutes: env_u64("TRADE_RECEIPT_MAX_AGE_MINUTES", DEFAULT_TRADE_RECEIPT_MAX_AGE_MINUTES),
+            redis_legacy_keys: std::env::var("REDIS_LEGACY_KEYS").map(|v| v.trim() != "false").unwrap_or(true),
+        }
+    }
+
+    /// Validates the database, retention and backfill settings.
+    pub fn validate(&self) -> Result<()> {
+        if !self.database_backend.accepts(&self.database_url) {
+            return Err(ConfigError::Config(format!("DATABASE_URL does not match DATABASE_BACKEND ({})", self.database_backend.as_str())));
+        }
+        if self.trade_receipt_max_age_minutes == 0 {
+            return Err(ConfigError::Config("TRADE_RECEIPT_MAX_AGE_MINUTES must be positive".into()));
+        }
+        if self.heartbeat_interval_s == 0 || self.heartbeat_timeout_ms == 0 {
+            return Err(ConfigError::Config("HEARTBEAT_INTERVAL_S and HEARTBEAT_TIMEOUT_MS must be positive".into()));
         }
+        if self.price_downsample_after_days > 0 && self.price_downsample_minutes == 0 {
+            return Err(ConfigError::Config("PRICE_DOWNSAMPLE_MINUTES must be positive to downsample".into()));
+        }
+        if self.price_retention_days > 0 && self.price_downsample_after_days >= self.price_retention_days {
+            return Err(ConfigError::Config(format!(
+                "PRICE_DOWNSAMPLE_AFTER_DAYS ({}) must be below PRICE_RETENTION_DAYS ({})",
+                self.price_downsample_after_days, self.price_retention_days
+            )));
+        }
+        Ok(())
     }
 
     /// Prints monitoring environment configuration for debugging.
@@ -156,13 +663,29 @@ impl MoniEnvConfig {
         tracing::debug!("MoniEnvConfig:");
         // tracing::debug!("  Paths:                 {}", self.paths);
         tracing::debug!("  Testing:               {}", self.testing);
-        tracing::debug!("  Heartbeat:             {}", self.heartbeat);
+        tracing::debug!(
+            "  Heartbeat:             {} (every {} s, timeout {} ms)",
+            self.heartbeat,
+            self.heartbeat_interval_s,
+            self.heartbeat_timeout_ms
+        );
         tracing::debug!("  Database URL:          {}", self.database_url);
         tracing::debug!("  Database Name:         {}", self.database_name);
+        tracing::debug!("  Database Backend:      {}", self.database_backend.as_str());
+        tracing::debug!("  Events Transport:      {}", self.events_transport.as_str());
+        tracing::debug!(
+            "  Price Retention:       {} days, downsampled to {} min after {} days (0 = disabled)",
+            self.price_retention_days,
+            self.price_downsample_minutes,
+            self.price_downsample_after_days
+        );
+        tracing::debug!("  Trade Receipt Max Age: {} min", self.trade_receipt_max_age_minutes);
+        tracing::debug!("  Redis Legacy Keys:     {}", self.redis_legacy_keys);
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct MarketMakerConfig {
     pub wallet_public_key: String,
     pub base_token: String,
@@ -174,30 +697,469 @@ pub struct MarketMakerConfig {
     pub chain_id: u64,
     pub gas_token_symbol: String,
     pub gas_token_chainlink_price_feed: String,
-    pub rpc_url: String,
+    pub rpc_url: String, // Primary endpoint, preferred for broadcasts and approvals while healthy
+    #[serde(default)]
+    pub rpc_urls: Vec<String>, // Fallback endpoints, round-robined with rpc_url for reads
+    #[serde(default = "default_rpc_max_failures")]
+    pub rpc_max_failures: u32, // Consecutive errors or slow responses before an endpoint is skipped
+    #[serde(default = "default_rpc_slow_ms")]
+    pub rpc_slow_ms: u64, // Response time counted as a failure
+    #[serde(default = "default_rpc_probe_interval_ms")]
+    pub rpc_probe_interval_ms: u64, // Interval between two probes of an unhealthy endpoint
+    #[serde(default)]
+    pub ws_rpc_url: Option<String>, // Websocket endpoint subscribed to the block heads, read by the market context instead of polling
+    #[serde(default = "default_ws_max_head_age_s")]
+    pub ws_max_head_age_s: u64, // Older block heads are refused, the context then polls over HTTP
     pub explorer_url: String,
     pub min_watch_spread_bps: f64,
     pub min_executable_spread_bps: f64,
     pub max_slippage_pct: f64,
     pub max_inventory_ratio: f64,
+    #[serde(default = "default_min_amount_worth_usd", alias = "min_trade_usd")]
+    pub min_amount_worth_usd: f64, // Swaps selling less (in USD) are skipped
+    #[serde(default)]
+    pub max_trade_usd: Option<f64>, // Swaps selling more (in USD) are clamped down to it, no cap if omitted
+    #[serde(default = "default_max_pool_share_bps")]
+    pub max_pool_share_bps: f64, // Max share of the pool balance of the bought token taken by one swap
     pub tx_gas_limit: u64,
     pub block_offset: u64,
     pub inclusion_block_delay: u64,
+    #[serde(default = "default_deadline_grace_blocks")]
+    pub deadline_grace_blocks: u64, // Blocks after block + inclusion_block_delay until which a swap is valid (its deadline)
     pub min_priority_fee_per_gas: u64,
     pub tycho_api: String,
     pub poll_interval_ms: u64,
     pub permit2_address: String,
     pub tycho_router_address: String,
+    #[serde(default = "default_multicall_address")]
+    pub multicall_address: String, // Multicall3 batching the balance and allowance reads, empty to read them one by one
+    #[serde(default)]
+    pub revert_errors: Vec<String>, // Custom error signatures, e.g. "MyError(uint256)", decoded in revert data on top of the router and Permit2 ones
     pub publish_events: bool,
     pub skip_simulation: bool,
     pub infinite_approval: bool,
+    #[serde(default)]
+    pub fee_strategy: Option<FeeStrategy>, // EIP-1559 fee estimation, the default of the network if unset
+    #[serde(default)]
+    pub latency_budget: Option<LatencyBudget>, // Deadlines of the stages after a block update, the default of the network if unset
+    #[serde(default)]
+    pub permit2_approval: bool, // Signs a Permit2 permit per swap instead of approving the router, exclusive with infinite_approval
+    #[serde(default = "default_permit2_expiration_s")]
+    pub permit2_expiration_s: u64, // Lifetime of a signed permit and of its signature
     pub price_feed_config: PriceFeedConfig,
     pub min_publish_timeframe_ms: u64,
+    #[serde(default = "default_min_reference_price_move_bps")]
     pub min_reference_price_move_bps: f64,
     pub max_gas_multiplier: f64,
+    #[serde(default = "default_opti_max_simulations")]
+    pub opti_max_simulations: usize,
+    #[serde(default = "default_depth_ladder_usd")]
+    pub depth_ladder_usd: Vec<f64>,
+    #[serde(default = "default_depth_report_interval_ms")]
+    pub depth_report_interval_ms: u64,
+    #[serde(default = "default_routing_protocol_whitelist")]
+    pub routing_protocol_whitelist: Vec<String>,
+    #[serde(default)]
+    pub tycho_protocols: Vec<String>, // Protocols streamed from Tycho (empty = all those available on the network)
+    #[serde(default = "default_target_inventory_ratio")]
+    pub target_inventory_ratio: f64,
+    #[serde(default = "default_skew_gain_bps_per_pct")]
+    pub skew_gain_bps_per_pct: f64,
+    #[serde(default = "default_pnl_report_interval_ms")]
+    pub pnl_report_interval_ms: u64,
+    #[serde(default = "default_max_daily_loss_usd")]
+    pub max_daily_loss_usd: f64,
+    #[serde(default = "default_breaker_cooldown_ms")]
+    pub breaker_cooldown_ms: u64,
+    #[serde(default = "default_min_native_balance_wei")]
+    pub min_native_balance_wei: u128,
+    #[serde(default = "default_pool_cooldown_blocks")]
+    pub pool_cooldown_blocks: u64,
+    #[serde(default = "default_tombstone_blocks")]
+    pub tombstone_blocks: u64, // Blocks a component removed from the stream is remembered for, its orders dropped (0 = disabled)
+    #[serde(default = "default_stale_block_tolerance")]
+    pub stale_block_tolerance: u64, // Blocks the chain head may be ahead of the evaluated state when executing, beyond which the trade is aborted
+    #[serde(default)]
+    pub sim_cache_significant_digits: u32, // Amounts quoted on a pool within a block are cached by their leading digits, 0 disables the cache
+    #[serde(default = "default_readjust_concurrency")]
+    pub readjust_concurrency: usize, // Readjustments of a block sized concurrently (balances and simulations), 1 = one after the other
+    #[serde(default)]
+    pub pool_allowlist: Vec<String>, // When set, only these components are monitored (empty = token pair matching)
+    #[serde(default)]
+    pub pool_denylist: Vec<String>, // Components never monitored (empty = no exclusion)
+    #[serde(default)]
+    pub allow_v4_hooked_pools: bool, // Monitor Uniswap V4 pools with any hook, their simulation may diverge from execution
+    #[serde(default)]
+    pub v4_hook_allowlist: Vec<String>, // Hook addresses whose Uniswap V4 pools are monitored anyway
+    #[serde(default = "default_tvl_add_threshold")]
+    pub tvl_add_threshold: f64,
+    #[serde(default = "default_tvl_remove_threshold")]
+    pub tvl_remove_threshold: f64,
+    #[serde(default)]
+    pub rebalance_mode: RebalanceMode,
+    #[serde(default = "default_inventory_refresh_interval_ms")]
+    pub inventory_refresh_interval_ms: u64,
+    #[serde(default = "default_gas_oracle_refresh_ms")]
+    pub gas_oracle_refresh_ms: u64, // Gas fees and ETH/USD price cached for that long across readjustments (0 = read every time)
+    #[serde(default = "default_gas_oracle_jitter_ms")]
+    pub gas_oracle_jitter_ms: u64, // Max random delay added to each refresh, spreading the bots sharing an RPC key
+    #[serde(default = "default_balance_watch_interval_ms")]
+    pub balance_watch_interval_ms: u64, // Between two comparisons of the cached balances with the chain (0 = on failed broadcasts only)
+    #[serde(default = "default_external_movement_threshold_bps")]
+    pub external_movement_threshold_bps: f64, // Unexplained base or quote balance change alerted and pausing order creation for one block
+    #[serde(default = "default_token_cache_dir")]
+    pub token_cache_dir: String, // Directory of the token universe cache, kept across restarts
+    #[serde(default = "default_token_cache_max_age_h")]
+    pub token_cache_max_age_h: u64, // Cached token universe loaded at startup when younger (0 = always fetched from Tycho)
+    #[serde(default = "default_token_min_quality")]
+    pub token_min_quality: i32, // Minimum Tycho quality of the fetched tokens
+    #[serde(default = "default_token_traded_days")]
+    pub token_traded_days: u64, // Fetched tokens traded in the last days (0 = traded or not)
+    #[serde(default)]
+    pub token_max: usize, // Cap of the fetched tokens, every page being read until then (0 = no cap)
+    #[serde(default = "default_snapshot_interval_ms")]
+    pub snapshot_interval_ms: u64, // Between two writes of the target pools snapshot, evaluated monitor-only until the stream is ready (0 = disabled)
+    #[serde(default = "default_snapshot_max_age_s")]
+    pub snapshot_max_age_s: u64, // Snapshot older than this at startup is ignored
+    #[serde(default)]
+    pub auto_wrap_native: bool, // Count native balance as wrapped token inventory, wrapping it when a trade needs it
+    #[serde(default)]
+    pub unwrap_to_native_above: f64, // Wrapped token balance (normalized) above which bought wrapped token is unwrapped, 0 disables
+    #[serde(default = "default_vol_multiplier")]
+    pub vol_multiplier: f64,
+    #[serde(default = "default_vol_widening_cap_bps")]
+    pub vol_widening_cap_bps: f64,
+    #[serde(default)]
+    pub adaptive_threshold: bool, // Widen the execution threshold by the shortfall of the realized profit against the expected one
+    #[serde(default = "default_adaptive_threshold_max_bps")]
+    pub adaptive_threshold_max_bps: f64, // Cap of the adaptive widening
+    #[serde(default)]
+    pub spread_overrides: HashMap<String, f64>, // Min watch spread (bps) per protocol_system, replacing min_watch_spread_bps
+    #[serde(default)]
+    pub add_pool_fee_to_spread: bool, // Add the pool fee (bps) on top of the min watch spread
+    #[serde(default = "default_shutdown_grace_period_ms")]
+    pub shutdown_grace_period_ms: u64,
+    #[serde(default = "default_stream_staleness_threshold_s")]
+    pub stream_staleness_threshold_s: u64, // Age of the last Tycho message past which /readyz reports the instance not ready
+    #[serde(default = "default_stream_restart_delay_ms")]
+    pub stream_restart_delay_ms: u64, // Delay before rebuilding a failed or closed Tycho stream, doubled on each consecutive reconnect
+    #[serde(default = "default_stream_restart_max_delay_ms")]
+    pub stream_restart_max_delay_ms: u64, // Cap of the reconnect backoff, and uptime after which it restarts from stream_restart_delay_ms
+    #[serde(default = "default_max_stream_lag_blocks")]
+    pub max_stream_lag_blocks: u64, // Blocks behind the RPC head past which no order is created (0 = measured only)
+    #[serde(default = "default_stream_lag_sample_every")]
+    pub stream_lag_sample_every: u64, // Polled blocks between two samples of the RPC head
+    #[serde(default = "default_max_token_exposure_pct")]
+    pub max_token_exposure_pct: f64, // Max value share of one token in the inventory after the orders of a block
+    #[serde(default = "default_max_plausible_spread_bps")]
+    pub max_plausible_spread_bps: f64, // Pools deviating more from the reference are quarantined, never evaluated
+    #[serde(default = "default_quarantine_alert_blocks")]
+    pub quarantine_alert_blocks: u64, // Consecutive blocks in quarantine before an alert (0 = no alert)
+    #[serde(default)]
+    pub audit_log_path: Option<String>, // JSONL file of the decision taken on each target pool at each evaluated block (unset = no audit)
+    #[serde(default = "default_audit_log_max_bytes")]
+    pub audit_log_max_bytes: u64, // Size past which the audit file is rotated to <path>.1
+    #[serde(default)]
+    pub audit_publish_every: u64, // Publish one audit record in N on Redis, with publish_events (0 = never)
+    #[serde(default = "default_max_crashes_per_hour")]
+    pub max_crashes_per_hour: u64, // Crashes of the process in the last hour past which the instance starts paused (0 = never)
+    #[serde(default)]
+    pub dry_run: bool, // Simulate trades without broadcasting them (forced by --dry-run)
+    #[serde(skip)]
+    pub overrides: Vec<(String, String)>, // Fields overridden from the environment (field path, variable), not hashed
+    #[serde(skip)]
+    pub extends: Vec<String>, // Parent files merged under this config, nearest first, not hashed (their fields are)
+}
+
+/// Default simulation budget for the swap amount optimizer.
+fn default_opti_max_simulations() -> usize {
+    OPTI_MAX_SIMULATIONS
+}
+
+/// Default number of readjustments sized concurrently.
+fn default_readjust_concurrency() -> usize {
+    DEFAULT_READJUST_CONCURRENCY
+}
+
+/// Default USD notional rungs for the depth report.
+fn default_depth_ladder_usd() -> Vec<f64> {
+    DEFAULT_DEPTH_LADDER_USD.to_vec()
+}
+
+/// Default interval between two depth reports.
+fn default_depth_report_interval_ms() -> u64 {
+    DEFAULT_DEPTH_REPORT_INTERVAL_MS
+}
+
+/// Default protocols allowed for gas token valorisation routing (spot prices are reliable on these).
+fn default_routing_protocol_whitelist() -> Vec<String> {
+    vec![
+        TychoSupportedProtocol::UniswapV2.to_string(),
+        TychoSupportedProtocol::UniswapV3.to_string(),
+        TychoSupportedProtocol::UniswapV4.to_string(),
+    ]
+}
+
+/// Default min amount worth USD to swap.
+fn default_min_amount_worth_usd() -> f64 {
+    DEFAULT_MIN_AMOUNT_WORTH_USD
+}
+
+/// Default pool balance share cap (disabled).
+fn default_max_pool_share_bps() -> f64 {
+    DEFAULT_MAX_POOL_SHARE_BPS
+}
+
+/// Default reference price move publishing prices.
+fn default_min_reference_price_move_bps() -> f64 {
+    DEFAULT_MIN_REFERENCE_PRICE_MOVE_BPS
+}
+
+/// Default target base share of the inventory value.
+fn default_target_inventory_ratio() -> f64 {
+    DEFAULT_TARGET_INVENTORY_RATIO
+}
+
+/// Default inventory skew gain (disabled).
+fn default_skew_gain_bps_per_pct() -> f64 {
+    DEFAULT_SKEW_GAIN_BPS_PER_PCT
+}
+
+/// Default interval between two PnL reports.
+fn default_pnl_report_interval_ms() -> u64 {
+    DEFAULT_PNL_REPORT_INTERVAL_MS
+}
+
+/// Default daily loss limit (disabled).
+fn default_max_daily_loss_usd() -> f64 {
+    DEFAULT_MAX_DAILY_LOSS_USD
+}
+
+/// Default circuit breaker cooldown.
+fn default_breaker_cooldown_ms() -> u64 {
+    DEFAULT_BREAKER_COOLDOWN_MS
+}
+
+/// Default native balance floor kept for gas.
+fn default_min_native_balance_wei() -> u128 {
+    DEFAULT_MIN_NATIVE_BALANCE_WEI
+}
+
+/// Default number of blocks a traded pool is skipped for.
+fn default_pool_cooldown_blocks() -> u64 {
+    DEFAULT_POOL_COOLDOWN_BLOCKS
+}
+
+/// Default number of blocks a component removed from the stream is remembered for.
+fn default_tombstone_blocks() -> u64 {
+    DEFAULT_TOMBSTONE_BLOCKS
+}
+
+/// Default number of blocks the chain head may be ahead of the evaluated state when executing.
+fn default_stale_block_tolerance() -> u64 {
+    DEFAULT_STALE_BLOCK_TOLERANCE
+}
+
+/// Default interval between two on-chain inventory refreshes.
+fn default_inventory_refresh_interval_ms() -> u64 {
+    DEFAULT_INVENTORY_REFRESH_INTERVAL_MS
+}
+
+/// Default blocks a swap stays valid after its inclusion target.
+fn default_deadline_grace_blocks() -> u64 {
+    DEFAULT_DEADLINE_GRACE_BLOCKS
+}
+
+/// Default lifetime of the cached gas fees and ETH/USD price.
+fn default_gas_oracle_refresh_ms() -> u64 {
+    DEFAULT_GAS_ORACLE_REFRESH_MS
+}
+
+/// Default max random delay added to a gas oracle refresh.
+fn default_gas_oracle_jitter_ms() -> u64 {
+    DEFAULT_GAS_ORACLE_JITTER_MS
+}
+
+/// Default interval between two comparisons of the cached balances with the chain.
+fn default_balance_watch_interval_ms() -> u64 {
+    DEFAULT_BALANCE_WATCH_INTERVAL_MS
+}
+
+/// Default balance change counted as an external movement.
+fn default_external_movement_threshold_bps() -> f64 {
+    DEFAULT_EXTERNAL_MOVEMENT_THRESHOLD_BPS
+}
+
+/// Default directory of the token universe cache.
+fn default_token_cache_dir() -> String {
+    DEFAULT_TOKEN_CACHE_DIR.to_string()
+}
+
+/// Default age past which the cached token universe is fetched again.
+fn default_token_cache_max_age_h() -> u64 {
+    DEFAULT_TOKEN_CACHE_MAX_AGE_H
+}
+
+/// Default minimum Tycho quality of the fetched tokens.
+fn default_token_min_quality() -> i32 {
+    DEFAULT_TOKEN_MIN_QUALITY
+}
+
+/// Default recency of the fetched tokens, in days.
+fn default_token_traded_days() -> u64 {
+    DEFAULT_TOKEN_TRADED_DAYS
+}
+
+/// Default interval between two writes of the target pools snapshot.
+fn default_snapshot_interval_ms() -> u64 {
+    DEFAULT_SNAPSHOT_INTERVAL_MS
+}
+
+/// Default age past which the snapshot is ignored at startup.
+fn default_snapshot_max_age_s() -> u64 {
+    DEFAULT_SNAPSHOT_MAX_AGE_S
+}
+
+/// Default threshold widening per bps of volatility (disabled).
+fn default_vol_multiplier() -> f64 {
+    DEFAULT_VOL_MULTIPLIER
+}
+
+/// Default cap of the volatility widening.
+fn default_vol_widening_cap_bps() -> f64 {
+    DEFAULT_VOL_WIDENING_CAP_BPS
+}
+
+/// Default cap of the adaptive widening.
+fn default_adaptive_threshold_max_bps() -> f64 {
+    DEFAULT_ADAPTIVE_THRESHOLD_MAX_BPS
+}
+
+/// Default grace period for the execution in progress on SIGTERM.
+fn default_shutdown_grace_period_ms() -> u64 {
+    DEFAULT_SHUTDOWN_GRACE_PERIOD_MS
+}
+
+/// Default stream staleness threshold of the readiness probe.
+fn default_stream_staleness_threshold_s() -> u64 {
+    DEFAULT_STREAM_STALENESS_THRESHOLD_S
+}
+
+/// Default delay before rebuilding a failed Tycho stream.
+fn default_stream_restart_delay_ms() -> u64 {
+    DEFAULT_STREAM_RESTART_DELAY_MS
+}
+
+/// Default cap of the stream reconnect backoff.
+fn default_stream_restart_max_delay_ms() -> u64 {
+    DEFAULT_STREAM_RESTART_MAX_DELAY_MS
+}
+
+/// Default max token exposure, disabled.
+fn default_max_token_exposure_pct() -> f64 {
+    DEFAULT_MAX_TOKEN_EXPOSURE_PCT
+}
+
+/// Default consecutive failures before an RPC endpoint is skipped.
+fn default_rpc_max_failures() -> u32 {
+    DEFAULT_RPC_MAX_FAILURES
+}
+
+/// Default response time counted as an RPC failure.
+fn default_rpc_slow_ms() -> u64 {
+    DEFAULT_RPC_SLOW_MS
+}
+
+/// Default Multicall3, the canonical deployment.
+fn default_multicall_address() -> String {
+    MULTICALL3_ADDRESS.to_string()
+}
+
+fn default_permit2_expiration_s() -> u64 {
+    DEFAULT_PERMIT2_EXPIRATION_S
+}
+
+/// Default interval between two probes of an unhealthy RPC endpoint.
+fn default_rpc_probe_interval_ms() -> u64 {
+    DEFAULT_RPC_PROBE_INTERVAL_MS
+}
+
+/// Default size of the audit file before rotation.
+fn default_audit_log_max_bytes() -> u64 {
+    DEFAULT_AUDIT_LOG_MAX_BYTES
+}
+
+/// Default crashes per hour past which an instance starts paused.
+fn default_max_crashes_per_hour() -> u64 {
+    DEFAULT_MAX_CRASHES_PER_HOUR
+}
+
+/// Default stream lag past which order creation is suppressed.
+fn default_max_stream_lag_blocks() -> u64 {
+    DEFAULT_MAX_STREAM_LAG_BLOCKS
+}
+
+/// Default polled blocks between two samples of the stream lag.
+fn default_stream_lag_sample_every() -> u64 {
+    DEFAULT_STREAM_LAG_SAMPLE_EVERY
+}
+
+/// Default sanity band around the reference price.
+fn default_max_plausible_spread_bps() -> f64 {
+    DEFAULT_MAX_PLAUSIBLE_SPREAD_BPS
+}
+
+/// Default consecutive blocks in quarantine before an alert.
+fn default_quarantine_alert_blocks() -> u64 {
+    DEFAULT_QUARANTINE_ALERT_BLOCKS
+}
+
+/// Default minimum TVL for a component to enter the stream.
+fn default_tvl_add_threshold() -> f64 {
+    DEFAULT_TVL_ADD_THRESHOLD
+}
+
+/// Default TVL below which a streamed component is removed.
+fn default_tvl_remove_threshold() -> f64 {
+    DEFAULT_TVL_REMOVE_THRESHOLD
+}
+
+/// Default max age of a websocket block head.
+fn default_ws_max_head_age_s() -> u64 {
+    DEFAULT_WS_MAX_HEAD_AGE_S
+}
+
+/// Default number of recent blocks read to estimate the fees.
+fn default_fee_history_blocks() -> u64 {
+    DEFAULT_FEE_HISTORY_BLOCKS
 }
 
 impl MarketMakerConfig {
+    /// Fee strategy of the config, or the default one of its network (mainnet's for an unknown network).
+    pub fn fee_strategy(&self) -> FeeStrategy {
+        self.fee_strategy
+            .unwrap_or_else(|| NetworkName::from_str(&self.network_name).unwrap_or(NetworkName::Ethereum).fee_strategy())
+    }
+
+    /// Latency budget of the config, the default of its network if unset.
+    pub fn latency_budget(&self) -> LatencyBudget {
+        self.latency_budget
+            .unwrap_or_else(|| NetworkName::from_str(&self.network_name).unwrap_or(NetworkName::Ethereum).latency_budget())
+    }
+
+    /// Last block a swap priced on `block` may be sent or mined at: `block + inclusion_block_delay + deadline_grace_blocks`.
+    pub fn valid_until(&self, block: u64) -> u64 {
+        block + self.inclusion_block_delay + self.deadline_grace_blocks
+    }
+
+    /// Expiration (unix seconds) of a Permit2 permit signed at `now`: `permit2_expiration_s`, capped by the swap deadline
+    /// converted with the network block time, so Permit2 rejects the swap on-chain once it is stale.
+    pub fn permit_expiration(&self, now: u64) -> u64 {
+        let block_time = NetworkName::from_str(&self.network_name).unwrap_or(NetworkName::Ethereum).block_time_s();
+        let deadline = (self.inclusion_block_delay + self.deadline_grace_blocks).max(1) * block_time;
+        now + self.permit2_expiration_s.min(deadline)
+    }
+
     /// Generates unique identifier for the market maker configuration.
     pub fn id(&self) -> String {
         let f7 = self.wallet_public_key[..9].to_string(); // 0x + 7 chars
@@ -205,7 +1167,7 @@ impl MarketMakerConfig {
         msg.to_lowercase()
     }
 
-    /// Generates a keccak256 hash of the configuration.
+    /// Generates a keccak256 hash of the effective configuration, environment overrides included.
     pub fn hash(&self) -> String {
         let serialized = serde_json::to_string(self).unwrap();
         let hash = alloy_primitives::keccak256(serialized.as_bytes());
@@ -220,12 +1182,30 @@ impl MarketMakerConfig {
         }
 
         tracing::debug!("Market Maker Config:");
+        if !self.extends.is_empty() {
+            tracing::info!("  Extends:               {}", self.extends.join(" <- "));
+        }
+        for (field, var) in self.overrides.iter() {
+            tracing::info!("  ⚙️  {} overridden by {}", field, var);
+        }
         tracing::debug!("  Network:               {} with ID {}", self.network_name, self.chain_id);
         tracing::debug!("  Tag:                   {}", self.pair_tag);
         tracing::debug!("  Base Token:            {} ({})", self.base_token, self.base_token_address);
         tracing::debug!("  Quote Token:           {} ({})", self.quote_token, self.quote_token_address);
         tracing::debug!("  Wallet Public Key:     {}", self.wallet_public_key);
         tracing::debug!("  RPC:                   {}", self.rpc_url);
+        tracing::debug!("  Fallback RPCs:         {:?}", self.rpc_urls);
+        tracing::debug!(
+            "  Websocket RPC:         {:?} (heads up to {} s old)",
+            self.ws_rpc_url.as_deref().map(crate::utils::misc::redact),
+            self.ws_max_head_age_s
+        );
+        tracing::debug!(
+            "  RPC Health:            {} failures, slow above {} ms, probed every {} ms",
+            self.rpc_max_failures,
+            self.rpc_slow_ms,
+            self.rpc_probe_interval_ms
+        );
         tracing::debug!("  Explorer:              {}", self.explorer_url);
         tracing::debug!("  Gas token:             {}", self.gas_token_symbol);
         tracing::debug!("  Gas Oracle Feed:       {}", self.gas_token_chainlink_price_feed);
@@ -233,22 +1213,145 @@ impl MarketMakerConfig {
         tracing::debug!("  🔸 Min exec spread (bps): {}", self.min_executable_spread_bps);
         tracing::debug!("  🔸 Max Slippage (%):      {}", self.max_slippage_pct);
         tracing::debug!("  Max Inventory Ratio:   {}", self.max_inventory_ratio);
+        tracing::debug!("  Min Amount Worth:      {} USD", self.min_amount_worth_usd);
+        tracing::debug!("  Max Trade:             {:?} USD", self.max_trade_usd);
+        tracing::debug!("  Max Pool Share:        {} bps", self.max_pool_share_bps);
+        tracing::debug!("  Max Token Exposure (%): {}", self.max_token_exposure_pct);
+        tracing::debug!("  Max Plausible Spread:  {} bps", self.max_plausible_spread_bps);
+        tracing::debug!("  Quarantine Alert:      {} blocks", self.quarantine_alert_blocks);
         tracing::debug!("  Gas Limit:             {}", self.tx_gas_limit);
         tracing::debug!("  Block Offset:          {}", self.block_offset);
         tracing::debug!("  Inclusion Block Delay: {}", self.inclusion_block_delay);
+        tracing::debug!("  Deadline Grace:        {} blocks", self.deadline_grace_blocks);
         tracing::debug!("  Tycho API:             {}", self.tycho_api);
         tracing::debug!("  Poll Interval (ms):    {}", self.poll_interval_ms);
         tracing::debug!("  Permit2:               {}", self.permit2_address);
         tracing::debug!("  Tycho Router:          {}", self.tycho_router_address);
+        tracing::debug!("  Multicall:             {}", self.multicall_address);
+        tracing::debug!("  Revert Errors:         {:?}", self.revert_errors);
         tracing::debug!("  Publish Events:        {}", self.publish_events);
         tracing::debug!("  Min Publish Timeframe (ms): {}", self.min_publish_timeframe_ms);
         tracing::debug!("  Min Ref Price Move (bps): {}", self.min_reference_price_move_bps);
         tracing::debug!("  Max Gas Multiplier:    {}", self.max_gas_multiplier);
+        tracing::debug!("  Opti Max Simulations:  {}", self.opti_max_simulations);
+        tracing::debug!("  Depth Ladder (USD):    {:?}", self.depth_ladder_usd);
+        tracing::debug!("  Depth Interval (ms):   {}", self.depth_report_interval_ms);
+        tracing::debug!("  Routing Whitelist:     {:?}", self.routing_protocol_whitelist);
+        tracing::debug!("  Tycho Protocols:       {:?}", self.tycho_protocols);
+        tracing::debug!("  Target Inventory Ratio: {}", self.target_inventory_ratio);
+        tracing::debug!("  Skew Gain (bps/pct):   {}", self.skew_gain_bps_per_pct);
+        tracing::debug!("  PnL Interval (ms):     {}", self.pnl_report_interval_ms);
+        tracing::debug!("  Max Daily Loss (USD):  {}", self.max_daily_loss_usd);
+        tracing::debug!("  Breaker Cooldown (ms): {}", self.breaker_cooldown_ms);
+        tracing::debug!("  Min Native Balance:    {} wei", self.min_native_balance_wei);
+        tracing::debug!("  Pool Cooldown (blocks): {}", self.pool_cooldown_blocks);
+        tracing::debug!("  Tombstones (blocks):   {}", self.tombstone_blocks);
+        tracing::debug!("  Stale Tolerance (blocks): {}", self.stale_block_tolerance);
+        tracing::debug!("  Sim Cache Digits:      {}", self.sim_cache_significant_digits);
+        tracing::debug!("  Readjust Concurrency:  {}", self.readjust_concurrency);
+        tracing::debug!("  TVL Add Threshold:     {}", self.tvl_add_threshold);
+        tracing::debug!("  TVL Remove Threshold:  {}", self.tvl_remove_threshold);
+        tracing::debug!("  Rebalance Mode:        {:?}", self.rebalance_mode);
+        tracing::debug!("  Inventory Refresh (ms): {}", self.inventory_refresh_interval_ms);
+        tracing::debug!(
+            "  Balance Watch (ms):    {} (external movement past {} bps)",
+            self.balance_watch_interval_ms,
+            self.external_movement_threshold_bps
+        );
+        tracing::debug!("  Token Cache:           {} (max age {} h)", self.token_cache_dir, self.token_cache_max_age_h);
+        tracing::debug!(
+            "  Token List:            quality >= {}, traded {} days, max {}",
+            self.token_min_quality,
+            self.token_traded_days,
+            self.token_max
+        );
+        tracing::debug!("  Snapshot (ms):         {} (max age {} s)", self.snapshot_interval_ms, self.snapshot_max_age_s);
+        tracing::debug!("  Gas Oracle (ms):       {} (+ up to {} jitter)", self.gas_oracle_refresh_ms, self.gas_oracle_jitter_ms);
+        tracing::debug!("  Auto Wrap Native:      {}", self.auto_wrap_native);
+        tracing::debug!("  Unwrap Above:          {}", self.unwrap_to_native_above);
+        tracing::debug!("  Vol Multiplier:        {}", self.vol_multiplier);
+        tracing::debug!("  Vol Widening Cap (bps): {}", self.vol_widening_cap_bps);
+        tracing::debug!("  Adaptive Threshold:    {} (max {} bps)", self.adaptive_threshold, self.adaptive_threshold_max_bps);
+        tracing::debug!("  Spread Overrides:      {:?}", self.spread_overrides);
+        tracing::debug!("  Add Pool Fee:          {}", self.add_pool_fee_to_spread);
+        tracing::debug!("  Shutdown Grace (ms):   {}", self.shutdown_grace_period_ms);
+        tracing::debug!("  Stream Staleness (s):  {}", self.stream_staleness_threshold_s);
+        tracing::debug!("  Stream Restart (ms):   {} (doubled up to {})", self.stream_restart_delay_ms, self.stream_restart_max_delay_ms);
+        tracing::debug!(
+            "  Audit Log:             {} (max {} bytes, published 1/{})",
+            self.audit_log_path.as_deref().unwrap_or("disabled"),
+            self.audit_log_max_bytes,
+            self.audit_publish_every
+        );
+        tracing::debug!("  Max Stream Lag:        {} blocks (sampled every {})", self.max_stream_lag_blocks, self.stream_lag_sample_every);
+        tracing::debug!("  Max Crashes Per Hour:  {}", self.max_crashes_per_hour);
+        tracing::debug!("  Pool Allowlist:        {:?}", self.pool_allowlist);
+        tracing::debug!("  Pool Denylist:         {:?}", self.pool_denylist);
+        tracing::debug!("  Allow V4 Hooked Pools: {}", self.allow_v4_hooked_pools);
+        tracing::debug!("  V4 Hook Allowlist:     {:?}", self.v4_hook_allowlist);
         tracing::debug!("  Skip Simulation:       {}", self.skip_simulation);
+        tracing::debug!("  Dry Run:               {}", self.dry_run);
         tracing::debug!("  Skip Approval:      {}", self.infinite_approval);
+        tracing::debug!("  Fee Strategy:          {:?}", self.fee_strategy());
+        tracing::debug!("  Latency Budget:        {:?}", self.latency_budget());
+        tracing::debug!("  Permit2 Approval:      {} ({} s permits)", self.permit2_approval, self.permit2_expiration_s);
         tracing::debug!("  Price Feed Config:     {:?}", self.price_feed_config);
     }
 
+    /// Returns true if the component is selected as a target, given whether it holds both base and quote tokens.
+    ///
+    /// The denylist always excludes. A non-empty allowlist replaces token pair matching as the selection,
+    /// though a listed pool must still hold the pair to be priced.
+    pub fn targets_pool(&self, component_id: &str, holds_pair: bool) -> bool {
+        let id = component_id.to_lowercase();
+        if self.pool_denylist.iter().any(|x| x.to_lowercase() == id) {
+            return false;
+        }
+        if !self.pool_allowlist.is_empty() {
+            return holds_pair && self.pool_allowlist.iter().any(|x| x.to_lowercase() == id);
+        }
+        holds_pair
+    }
+
+    /// Min watch spread (bps) for a pool of `protocol_system` charging `fee_bps`.
+    ///
+    /// Uses the protocol override if any, min_watch_spread_bps otherwise, plus the pool fee with add_pool_fee_to_spread.
+    pub fn watch_spread_bps(&self, protocol_system: &str, fee_bps: u128) -> f64 {
+        let base = self.spread_overrides.get(protocol_system).copied().unwrap_or(self.min_watch_spread_bps);
+        if self.add_pool_fee_to_spread {
+            base + fee_bps as f64
+        } else {
+            base
+        }
+    }
+
+    /// Returns true if Uniswap V4 pools using this hook address can be monitored.
+    pub fn accepts_v4_hook(&self, hook: &str) -> bool {
+        self.allow_v4_hooked_pools || self.v4_hook_allowlist.iter().any(|x| x.eq_ignore_ascii_case(hook))
+    }
+
+    /// RPC endpoints, rpc_url first then the fallbacks, without duplicates.
+    pub fn rpc_endpoints(&self) -> Vec<String> {
+        let mut endpoints = vec![self.rpc_url.clone()];
+        for url in self.rpc_urls.iter() {
+            if !endpoints.contains(url) {
+                endpoints.push(url.clone());
+            }
+        }
+        endpoints
+    }
+
+    /// Side of the pair holding the wrapped native token (gas_token_symbol): Some(true) for base, Some(false) for quote.
+    pub fn wrapped_native_side(&self) -> Option<bool> {
+        if self.base_token_address.eq_ignore_ascii_case(&self.gas_token_symbol) {
+            Some(true)
+        } else if self.quote_token_address.eq_ignore_ascii_case(&self.gas_token_symbol) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     /// Generates a short descriptive name for the market maker instance.
     pub fn shortname(&self) -> String {
         format!("{}-{}-{}-{}", self.network_name, self.base_token, self.quote_token, self.price_feed_config.r#type)
@@ -256,6 +1359,45 @@ impl MarketMakerConfig {
 
     /// Validates market maker configuration parameters.
     pub fn validate(&self) -> Result<()> {
+        // Check network and chain id agree, the RPC is checked at startup
+        let network = NetworkName::from_str(&self.network_name).map_err(ConfigError::Config)?;
+        if self.chain_id != network.chain_id() {
+            return Err(ConfigError::Config(format!(
+                "chain_id {} does not match network '{}' (expected {})",
+                self.chain_id,
+                self.network_name,
+                network.chain_id()
+            )));
+        }
+        if let Some(url) = self.rpc_endpoints().iter().find(|url| url.parse::<url::Url>().is_err()) {
+            return Err(ConfigError::Config(format!("Invalid RPC URL: '{}'", url)));
+        }
+        if self.rpc_max_failures == 0 {
+            return Err(ConfigError::Config("rpc_max_failures must be > 0".into()));
+        }
+        if self.stream_staleness_threshold_s == 0 {
+            return Err(ConfigError::Config("stream_staleness_threshold_s must be > 0".into()));
+        }
+        if self.stream_restart_max_delay_ms < self.stream_restart_delay_ms {
+            return Err(ConfigError::Config("stream_restart_max_delay_ms must be >= stream_restart_delay_ms".into()));
+        }
+        if self.snapshot_interval_ms > 0 && self.snapshot_max_age_s == 0 {
+            return Err(ConfigError::Config("snapshot_max_age_s must be > 0 when snapshot_interval_ms is set".into()));
+        }
+        if self.audit_log_max_bytes == 0 {
+            return Err(ConfigError::Config("audit_log_max_bytes must be > 0".into()));
+        }
+        if self.stream_lag_sample_every == 0 {
+            return Err(ConfigError::Config("stream_lag_sample_every must be > 0".into()));
+        }
+        if self.external_movement_threshold_bps <= 0.0 {
+            return Err(ConfigError::Config("external_movement_threshold_bps must be > 0".into()));
+        }
+        // Transaction links are built as {explorer_url}tx/{hash}
+        if !self.explorer_url.ends_with('/') {
+            return Err(ConfigError::Config(format!("explorer_url must end with '/': '{}'", self.explorer_url)));
+        }
+
         // Check spread bounds
         if self.min_watch_spread_bps > BASIS_POINT_DENO {
             return Err(ConfigError::Config("min_watch_spread_bps must be ≤ 10000 BPS (100%)".into()));
@@ -271,6 +1413,23 @@ impl MarketMakerConfig {
         if !(0.0..=1.0).contains(&self.max_inventory_ratio) {
             return Err(ConfigError::Config("max_inventory_ratio must be between 0.0 and 1.0".into()));
         }
+        if !(self.min_amount_worth_usd >= 0.0 && self.min_amount_worth_usd.is_finite()) {
+            return Err(ConfigError::Config("min_amount_worth_usd must be ≥ 0.0".into()));
+        }
+        if let Some(max) = self.max_trade_usd {
+            if !(max > self.min_amount_worth_usd && max.is_finite()) {
+                return Err(ConfigError::Config(format!("max_trade_usd must be > min_amount_worth_usd ({} USD)", self.min_amount_worth_usd)));
+            }
+        }
+        if !(self.max_pool_share_bps > 0.0 && self.max_pool_share_bps <= BASIS_POINT_DENO) {
+            return Err(ConfigError::Config("max_pool_share_bps must be > 0 and ≤ 10000 bps".into()));
+        }
+        if self.max_token_exposure_pct <= 0.0 || self.max_token_exposure_pct > 100.0 {
+            return Err(ConfigError::Config("max_token_exposure_pct must be > 0 and ≤ 100 (100 disables the limit)".into()));
+        }
+        if self.max_plausible_spread_bps <= self.min_watch_spread_bps {
+            return Err(ConfigError::Config("max_plausible_spread_bps must be > min_watch_spread_bps".into()));
+        }
 
         // Check gas limit
         if self.tx_gas_limit > 1_000_000 {
@@ -298,6 +1457,102 @@ impl MarketMakerConfig {
             return Err(ConfigError::Config("max_gas_multiplier must be ≤ 100.0".into()));
         }
 
+        // Check opti_max_simulations (the max amount probe alone consumes one)
+        if self.opti_max_simulations < 2 {
+            return Err(ConfigError::Config("opti_max_simulations must be ≥ 2".into()));
+        }
+
+        // Check depth ladder rungs
+        if self.depth_ladder_usd.iter().any(|rung| *rung <= 0.0) {
+            return Err(ConfigError::Config("depth_ladder_usd rungs must be > 0".into()));
+        }
+
+        // Check routing whitelist entries are known protocols
+        let supported = TychoSupportedProtocol::vectorize();
+        if let Some(unknown) = self.routing_protocol_whitelist.iter().find(|p| !supported.contains(p)) {
+            return Err(ConfigError::Config(format!("Unknown protocol in routing_protocol_whitelist: {}", unknown)));
+        }
+
+        // Check streamed protocols are known, indexed on the network, and listed once
+        for (i, name) in self.tycho_protocols.iter().enumerate() {
+            let protocol =
+                TychoSupportedProtocol::from_str(name).map_err(|_| ConfigError::Config(format!("Unknown protocol in tycho_protocols: {} (expected one of {})", name, supported.join(", "))))?;
+            if !protocol.is_available_on(&network) {
+                return Err(ConfigError::Config(format!("Protocol {} of tycho_protocols is not available on {}", name, network.as_str())));
+            }
+            if self.tycho_protocols[..i].contains(name) {
+                return Err(ConfigError::Config(format!("Protocol {} is listed twice in tycho_protocols", name)));
+            }
+        }
+
+        // Check inventory skew parameters
+        if !(0.0..=1.0).contains(&self.target_inventory_ratio) {
+            return Err(ConfigError::Config("target_inventory_ratio must be between 0.0 and 1.0".into()));
+        }
+        if self.skew_gain_bps_per_pct < 0.0 {
+            return Err(ConfigError::Config("skew_gain_bps_per_pct must be ≥ 0.0".into()));
+        }
+
+        // Check daily loss limit
+        if self.max_daily_loss_usd < 0.0 {
+            return Err(ConfigError::Config("max_daily_loss_usd must be ≥ 0.0 (0 disables the circuit breaker)".into()));
+        }
+
+        // Check stream TVL thresholds
+        if self.tvl_remove_threshold < 0.0 {
+            return Err(ConfigError::Config("tvl_remove_threshold must be ≥ 0".into()));
+        }
+        if self.tvl_remove_threshold > self.tvl_add_threshold {
+            return Err(ConfigError::Config(format!(
+                "tvl_remove_threshold ({}) must be ≤ tvl_add_threshold ({})",
+                self.tvl_remove_threshold, self.tvl_add_threshold
+            )));
+        }
+
+        // Check pool allow/deny lists hold component ids
+        if let Some(invalid) = self.pool_allowlist.iter().find(|id| !is_valid_component_id(id)) {
+            return Err(ConfigError::Config(format!("Invalid component id in pool_allowlist: '{}' (expected 0x-prefixed hex)", invalid)));
+        }
+        if let Some(invalid) = self.pool_denylist.iter().find(|id| !is_valid_component_id(id)) {
+            return Err(ConfigError::Config(format!("Invalid component id in pool_denylist: '{}' (expected 0x-prefixed hex)", invalid)));
+        }
+        if let Some(invalid) = self.v4_hook_allowlist.iter().find(|hook| !is_valid_eth_address(hook)) {
+            return Err(ConfigError::Config(format!("Invalid hook address in v4_hook_allowlist: '{}'", invalid)));
+        }
+
+        // Check per-protocol spread overrides
+        if let Some((protocol, bps)) = self.spread_overrides.iter().find(|(_, bps)| !(0.0..=BASIS_POINT_DENO).contains(*bps)) {
+            return Err(ConfigError::Config(format!("spread_overrides.{} must be between 0 and 10000 BPS, got {}", protocol, bps)));
+        }
+
+        // Check volatility widening
+        if self.vol_multiplier < 0.0 {
+            return Err(ConfigError::Config("vol_multiplier must be ≥ 0 (0 disables the volatility widening)".into()));
+        }
+        if self.vol_widening_cap_bps < 0.0 {
+            return Err(ConfigError::Config("vol_widening_cap_bps must be ≥ 0".into()));
+        }
+        if self.sim_cache_significant_digits > 15 {
+            return Err(ConfigError::Config("sim_cache_significant_digits must be ≤ 15 (0 disables the simulation cache)".into()));
+        }
+        if self.readjust_concurrency == 0 {
+            return Err(ConfigError::Config("readjust_concurrency must be ≥ 1".into()));
+        }
+        if !(self.adaptive_threshold_max_bps >= 0.0 && self.adaptive_threshold_max_bps.is_finite()) {
+            return Err(ConfigError::Config("adaptive_threshold_max_bps must be ≥ 0".into()));
+        }
+
+        // Check native wrapping settings
+        if self.unwrap_to_native_above < 0.0 {
+            return Err(ConfigError::Config("unwrap_to_native_above must be ≥ 0 (0 disables unwrapping)".into()));
+        }
+        if (self.auto_wrap_native || self.unwrap_to_native_above > 0.0) && self.wrapped_native_side().is_none() {
+            return Err(ConfigError::Config(format!(
+                "auto_wrap_native and unwrap_to_native_above require the base or quote token to be the wrapped gas token ({})",
+                self.gas_token_symbol
+            )));
+        }
+
         // Validate Ethereum addresses
         if !is_valid_eth_address(&self.wallet_public_key) {
             return Err(ConfigError::Config(format!("Invalid wallet_public_key address: {}", self.wallet_public_key)));
@@ -320,6 +1575,35 @@ impl MarketMakerConfig {
         if !is_valid_eth_address(&self.tycho_router_address) {
             return Err(ConfigError::Config(format!("Invalid tycho_router_address: {}", self.tycho_router_address)));
         }
+        if !self.multicall_address.is_empty() && !is_valid_eth_address(&self.multicall_address) {
+            return Err(ConfigError::Config(format!("Invalid multicall_address: {}", self.multicall_address)));
+        }
+        if let Some(url) = self.ws_rpc_url.as_deref().filter(|url| !url.is_empty()) {
+            if !url.starts_with("ws://") && !url.starts_with("wss://") {
+                return Err(ConfigError::Config("Invalid ws_rpc_url: expected a ws:// or wss:// endpoint".to_string()));
+            }
+            if self.ws_max_head_age_s == 0 {
+                return Err(ConfigError::Config("ws_max_head_age_s must be positive with ws_rpc_url".to_string()));
+            }
+        }
+        if let Some(strategy) = &self.fee_strategy {
+            strategy.validate().map_err(|e| ConfigError::Config(format!("Invalid fee_strategy: {}", e)))?;
+        }
+        if let Some(budget) = &self.latency_budget {
+            budget.validate().map_err(|e| ConfigError::Config(format!("Invalid latency_budget: {}", e)))?;
+        }
+        if self.permit2_approval && self.infinite_approval {
+            return Err(ConfigError::Config("permit2_approval and infinite_approval are exclusive, set at most one".to_string()));
+        }
+        if self.permit2_approval && self.permit2_expiration_s == 0 {
+            return Err(ConfigError::Config("permit2_expiration_s must be positive with permit2_approval".to_string()));
+        }
+        if let Some(signature) = self.revert_errors.iter().find(|signature| !is_error_signature(signature)) {
+            return Err(ConfigError::Config(format!(
+                "Invalid revert_errors signature: '{}', expected e.g. 'MyError(uint256,address)'",
+                signature
+            )));
+        }
 
         // Check that token addresses are different
         if self.base_token_address.eq_ignore_ascii_case(&self.quote_token_address) {
@@ -327,14 +1611,14 @@ impl MarketMakerConfig {
         }
 
         // Check if using preconfirmation on Base network
-        if let NetworkName::Base = NetworkName::from_str(&self.network_name).unwrap() {
+        if let NetworkName::Base = network {
             if self.rpc_url.to_lowercase().contains("preconf") && !self.skip_simulation {
                 return Err(ConfigError::Config("skip_simulation must be true when using preconfirmation RPC on Base network".into()));
             }
         }
 
         // Check if skip_simulation is enabled on mainnet (not yet implemented)
-        if let NetworkName::Ethereum = NetworkName::from_str(&self.network_name).unwrap() {
+        if let NetworkName::Ethereum = network {
             if !self.skip_simulation {
                 return Err(ConfigError::Config("skip_simulation must be true on mainnet (bundles)".into()));
             }
@@ -349,24 +1633,230 @@ impl MarketMakerConfig {
     }
 }
 
-/// Loads and validates market maker configuration from TOML file.
+/// Loads and validates market maker configuration from TOML file, with the `MM__<FIELD>` environment overrides.
 pub fn load_market_maker_config(path: &str) -> Result<MarketMakerConfig> {
-    let contents = match fs::read_to_string(path) {
-        Ok(contents) => contents,
-        Err(e) => {
-            return Err(ConfigError::Config(format!("Failed to read config file: {e}")));
-        }
-    };
+    load_market_maker_config_with(path, std::env::vars())
+}
 
-    let config: MarketMakerConfig = match toml::from_str(&contents) {
+/// Loads and validates market maker configuration from TOML file, with the overrides found in `vars`.
+pub fn load_market_maker_config_with<I: IntoIterator<Item = (String, String)>>(path: &str, vars: I) -> Result<MarketMakerConfig> {
+    let mut chain = vec![];
+    let table = read_config_table(path, &mut chain)?;
+    // Checked before the deserialization, which would only report the first unknown key, without suggestion
+    let unknown = unknown_config_fields(&table);
+    if !unknown.is_empty() {
+        return Err(ConfigError::Config(format!("Unknown config field(s) in {path}: {}", unknown.join(", "))));
+    }
+    let config: MarketMakerConfig = match toml::Value::Table(table).try_into() {
         Ok(config) => config,
         Err(e) => {
             return Err(ConfigError::Config(format!("Failed to parse TOML: {e}")));
         }
     };
 
+    let mut overrides = vars.into_iter().filter(|(var, _)| var.starts_with(CONFIG_ENV_OVERRIDE_PREFIX)).collect::<Vec<(String, String)>>();
+    overrides.sort();
+    let mut config = if overrides.is_empty() { config } else { apply_env_overrides(config, &overrides)? };
+    config.extends = chain.into_iter().skip(1).collect();
+
     match config.validate() {
         Ok(()) => Ok(config),
         Err(e) => Err(e),
     }
 }
+
+/// Reads a config file as a TOML table, merged over the file its `extends` key points to (recursively).
+///
+/// `chain` holds the files being read, child first, so a file extending one of its descendants is rejected.
+fn read_config_table(path: &str, chain: &mut Vec<String>) -> Result<toml::Table> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if chain.is_empty() => return Err(ConfigError::Config(format!("Failed to read config file: {e}"))),
+        Err(e) => return Err(ConfigError::Config(format!("Failed to read config file {path}, extended by {}: {e}", chain.last().unwrap()))),
+    };
+    // Canonical paths, so `config/a.toml` and `./config/a.toml` are the same file
+    let id = fs::canonicalize(path).map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|_| path.to_string());
+    if chain
+        .iter()
+        .any(|seen| fs::canonicalize(seen).map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|_| seen.clone()) == id)
+    {
+        return Err(ConfigError::Config(format!("Cyclic config extends: {} -> {}", chain.join(" -> "), path)));
+    }
+    chain.push(path.to_string());
+    let mut table: toml::Table = match toml::from_str(&contents) {
+        Ok(table) => table,
+        Err(e) => return Err(ConfigError::Config(format!("Failed to parse TOML {path}: {e}"))),
+    };
+    match table.remove(CONFIG_EXTENDS_KEY) {
+        None => Ok(table),
+        Some(toml::Value::String(parent)) => {
+            let mut merged = read_config_table(&parent, chain)?;
+            merge_config_table(&mut merged, table);
+            Ok(merged)
+        }
+        Some(other) => Err(ConfigError::Config(format!("{path}: '{CONFIG_EXTENDS_KEY}' must be a file path, got {other}"))),
+    }
+}
+
+/// JSON schema of the config files, exported by `maker --schema` so deployment tooling can validate them.
+pub fn config_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(MarketMakerConfig)).unwrap_or_default()
+}
+
+/// Keys of a config table, nested tables included, that are not config fields, each with the closest field name if any.
+pub fn unknown_config_fields(table: &toml::Table) -> Vec<String> {
+    let schema = config_schema();
+    let mut unknown = vec![];
+    collect_unknown_fields(&schema, &schema, table, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown_fields(root: &serde_json::Value, node: &serde_json::Value, table: &toml::Table, prefix: &str, unknown: &mut Vec<String>) {
+    // Maps (e.g. spread_overrides) have no properties, their keys are free
+    let Some(fields) = schema_fields(root, node) else {
+        return;
+    };
+    for (key, value) in table.iter() {
+        match fields.get(key) {
+            Some(field) => {
+                if let toml::Value::Table(nested) = value {
+                    collect_unknown_fields(root, field, nested, &format!("{prefix}{key}."), unknown);
+                }
+            }
+            None => {
+                let hint = closest_name(key, fields.keys()).map(|name| format!(" (did you mean '{prefix}{name}'?)")).unwrap_or_default();
+                unknown.push(format!("'{prefix}{key}'{hint}"));
+            }
+        }
+    }
+}
+
+/// Removes the keys that are not config fields from a serialized config, nested objects included.
+///
+/// The config is strict when loaded from a file, but a config published by a newer maker may carry fields this
+/// version does not know yet.
+pub fn strip_unknown_config_fields(value: &mut serde_json::Value) {
+    let schema = config_schema();
+    strip_unknown_fields(&schema, &schema, value);
+}
+
+fn strip_unknown_fields(root: &serde_json::Value, node: &serde_json::Value, value: &mut serde_json::Value) {
+    let (Some(fields), Some(object)) = (schema_fields(root, node), value.as_object_mut()) else {
+        return;
+    };
+    object.retain(|key, _| fields.contains_key(key));
+    for (key, nested) in object.iter_mut() {
+        if let Some(field) = fields.get(key) {
+            strip_unknown_fields(root, field, nested);
+        }
+    }
+}
+
+/// Properties of a schema object, following its `$ref` to the definitions.
+fn schema_fields<'a>(root: &'a serde_json::Value, node: &'a serde_json::Value) -> Option<&'a serde_json::Map<String, serde_json::Value>> {
+    let node = match node.get("$ref").and_then(|reference| reference.as_str()) {
+        Some(reference) => root.pointer(reference.trim_start_matches('#'))?,
+        None => node,
+    };
+    node.get("properties")?.as_object()
+}
+
+/// Closest name to a misspelled key, within a third of its length in edits (2 at least).
+fn closest_name<'a>(key: &str, names: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let max = (key.chars().count() / 3).max(2);
+    names
+        .map(|name| (edit_distance(key, name), name))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Overlays the fields of a child config on its parent. Tables are merged key by key, other values (arrays included) replaced.
+fn merge_config_table(parent: &mut toml::Table, child: toml::Table) {
+    for (key, value) in child {
+        match (parent.get_mut(&key), value) {
+            (Some(toml::Value::Table(nested)), toml::Value::Table(overlay)) => merge_config_table(nested, overlay),
+            (_, value) => {
+                parent.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Applies `MM__<FIELD>` overrides on a parsed configuration, `__` separating nested fields (e.g. `MM__PRICE_FEED_CONFIG__SOURCE`).
+///
+/// The value is parsed as the type of the field it replaces. Arrays and maps, and fields absent from the file, are parsed as JSON.
+pub fn apply_env_overrides(config: MarketMakerConfig, overrides: &[(String, String)]) -> Result<MarketMakerConfig> {
+    let mut value = serde_json::to_value(&config).map_err(|e| ConfigError::Config(format!("Failed to serialize config: {e}")))?;
+    let mut fields = vec![];
+    for (var, raw) in overrides.iter() {
+        let path = var[CONFIG_ENV_OVERRIDE_PREFIX.len()..].split("__").map(|key| key.to_lowercase()).collect::<Vec<String>>();
+        if path.iter().any(|key| key.is_empty()) {
+            return Err(ConfigError::Config(format!("{var}: invalid config override name")));
+        }
+        let (field, parents) = path.split_last().expect("Split is never empty");
+        let mut table = &mut value;
+        for key in parents.iter() {
+            table = match table.get_mut(key) {
+                Some(nested) if nested.is_object() => nested,
+                _ => return Err(ConfigError::Config(format!("{var}: unknown config field '{}'", path.join(".")))),
+            };
+        }
+        // Every field is serialized (None as null), a missing key is not a config field
+        if table.get(field).is_none() {
+            let names = table.as_object().map(|fields| fields.keys().cloned().collect::<Vec<String>>()).unwrap_or_default();
+            let hint = closest_name(field, names.iter()).map(|name| format!(" (did you mean '{name}'?)")).unwrap_or_default();
+            return Err(ConfigError::Config(format!("{var}: unknown config field '{}'{hint}", path.join("."))));
+        }
+        let parsed = parse_env_override(var, table.get(field), raw)?;
+        table[field.as_str()] = parsed;
+        fields.push((path.join("."), var.clone()));
+    }
+    let mut config: MarketMakerConfig = serde_json::from_value(value).map_err(|e| ConfigError::Config(format!("Invalid config override: {e}")))?;
+    config.overrides = fields;
+    Ok(config)
+}
+
+/// Parses an override as the type of the value it replaces.
+fn parse_env_override(var: &str, current: Option<&serde_json::Value>, raw: &str) -> Result<serde_json::Value> {
+    let invalid = |expected: &str| ConfigError::Config(format!("{var}: expected {expected}, got '{raw}'"));
+    let raw = raw.trim();
+    match current {
+        Some(serde_json::Value::String(_)) => Ok(serde_json::Value::String(raw.to_string())),
+        Some(serde_json::Value::Bool(_)) => raw.parse::<bool>().map(serde_json::Value::Bool).map_err(|_| invalid("a boolean (true or false)")),
+        Some(serde_json::Value::Number(n)) if n.is_f64() => raw
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| invalid("a number")),
+        Some(serde_json::Value::Number(n)) if n.is_u64() => raw.parse::<u64>().map(serde_json::Value::from).map_err(|_| invalid("a positive integer")),
+        Some(serde_json::Value::Number(_)) => raw.parse::<i64>().map(serde_json::Value::from).map_err(|_| invalid("an integer")),
+        Some(serde_json::Value::Array(_)) => match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(array) if array.is_array() => Ok(array),
+            _ => Err(invalid("a JSON array")),
+        },
+        Some(serde_json::Value::Object(_)) => match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(map) if map.is_object() => Ok(map),
+            _ => Err(invalid("a JSON object")),
+        },
+        // Optional or absent field, typed by the deserialization
+        Some(serde_json::Value::Null) | None => Ok(serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))),
+    }
+}
@@ -2,11 +2,42 @@
 //!
 //! Core type definitions for market making operations including the main market
 //! maker struct, data structures for trades, orders, and market context.
+use std::{collections::HashMap, sync::Arc};
+
 use alloy::rpc::types::TransactionRequest;
+use num_bigint::BigUint;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tycho_common::models::token::Token;
 
-use crate::maker::{exec::ExecStrategy, feed::PriceFeed};
+use crate::error::{ExecError, ExecErrorKind};
+use crate::maker::{
+    allowance::RouterAllowances,
+    audit::AuditLog,
+    breaker::CircuitBreaker,
+    control::Control,
+    cooldown::PoolCooldown,
+    engine::{DecisionEngine, Execution, MarketDataSource},
+    exec::ExecStrategy,
+    feed::PriceFeed,
+    frozen::FrozenContext,
+    health::HealthState,
+    inventory::InventoryCache,
+    journal::TradeJournal,
+    lag::StreamLag,
+    latency::Clock,
+    pnl::PnlTracker,
+    pools::StreamPools,
+    quarantine::PoolQuarantine,
+    reconnect::Reconnect,
+    shutdown::Shutdown,
+    simcache::SimCache,
+    snapshot::SnapshotStore,
+    tombstone::Tombstones,
+    tycho::BalanceCache,
+    watcher::BalanceWatcher,
+};
+use crate::opti::{adaptive::AdaptiveThreshold, math::TerminationReason, volatility::VolatilityEstimator};
 
 use super::{config::MarketMakerConfig, tycho::ProtoSimComp};
 
@@ -14,6 +45,10 @@ use super::{config::MarketMakerConfig, tycho::ProtoSimComp};
 pub struct MarketMaker {
     // Ready when the ProtocolStreamBuilder is initialised
     pub ready: bool,
+    // Components, protosims and token graph of the stream, kept across reconnects
+    pub pools: StreamPools,
+    // Backoff of the stream reconnections and time to ready after a disconnect
+    pub reconnect: Reconnect,
     // Hash of the instance, used to uniquely identify the instance, for external programs (monitoring, etc.)
     pub identifier: String,
     // Configuration for the market maker
@@ -34,10 +69,89 @@ pub struct MarketMaker {
 
     // Execution strategy (dynamic)
     pub execution: Box<dyn ExecStrategy>,
+
+    // Pricing, evaluation and sizing of the readjustments, DefaultEngine unless plugged through the builder
+    pub engine: Box<dyn DecisionEngine>,
+
+    // Reference price, market context and inventory, ChainDataSource unless plugged through the builder
+    pub market_data: Box<dyn MarketDataSource>,
+
+    // Encoding and execution of the orders, RouterExecution unless plugged through the builder
+    pub executor: Box<dyn Execution>,
+
+    // Realized PnL of executed trades
+    pub pnl: PnlTracker,
+
+    // Daily loss circuit breaker, kept here so it survives stream reconnections
+    pub breaker: CircuitBreaker,
+
+    // Pools recently traded, skipped by the evaluation until their state reflects our trade
+    pub cooldown: PoolCooldown,
+
+    // Components recently removed from the stream, orders against them dropped before their preparation
+    pub tombstones: Tombstones,
+
+    // Recent trades persisted in Redis, so a restarted process does not trade the same dislocation again
+    pub journal: TradeJournal,
+
+    // Pools whose spot price is implausibly far from the reference, skipped by the evaluation
+    pub quarantine: PoolQuarantine,
+
+    // Decision audit of the evaluated pools, when audit_log_path is set
+    pub audit: AuditLog,
+
+    // Delay of the stream behind the chain head, suppressing order creation past max_stream_lag_blocks
+    pub lag: StreamLag,
+
+    // Execution lock, shared by the pairs of a multi-pair process trading from the same wallet
+    pub nonce_lock: Arc<tokio::sync::Mutex<()>>,
+
+    // Trading state (running, paused, killed), driven by the Redis control channel
+    pub control: Control,
+
+    // Process-wide stop flag (SIGTERM), the loop exits between blocks once set
+    pub shutdown: Shutdown,
+
+    // Stream, price feed and Redis health, served by the health server (/readyz)
+    pub health: HealthState,
+
+    // Wallet inventory, read from chain on an interval and updated from our own executions in between
+    pub inventory: InventoryCache,
+
+    // Router allowances of the base and quote tokens, an approval being built only when they do not cover the input
+    pub allowances: RouterAllowances,
+
+    // Comparison of the cached balances with the chain, pausing order creation after an external movement
+    pub watcher: BalanceWatcher,
+
+    // Component balances read from Tycho at the current block, shared by the adjustments of the block
+    pub pool_balances: Arc<BalanceCache>,
+
+    // Reference price volatility, widening the execution threshold
+    pub volatility: VolatilityEstimator,
+
+    // Shortfall of the realized profit against the expected one, widening the execution threshold in adaptive mode
+    pub adaptive: AdaptiveThreshold,
+
+    // Quotes of the target pools within the current block, shared by the optimizer and the re-validation
+    pub sim_cache: Arc<SimCache>,
+
+    // Time source of the latency budget of the block updates
+    pub clock: Arc<dyn Clock>,
+
+    // Spot price of each target pool at its last evaluation, keyed by component id
+    pub evaluated_spots: HashMap<String, f64>,
+
+    // Reference price, market context and inventory pinned by --frozen-context, instead of the network
+    pub frozen: Option<FrozenContext>,
+
+    // Target pools snapshot, written while live and evaluated monitor-only until the stream is ready after a restart
+    pub snapshot: SnapshotStore,
 }
 
 /// Configuration for price feed sources.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct PriceFeedConfig {
     pub r#type: String, // "binance" or "chainlink"
     pub source: String, // https if type is "binance", of 0xAddress if type is "chainlink"
@@ -59,6 +173,26 @@ pub struct ComponentPriceData {
     pub price: f64,
 }
 
+/// Execution price and impact for one rung of a depth ladder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub notional_usd: f64,
+    pub direction: TradeDirection,
+    pub amount_in: f64,               // Normalized, in selling token
+    pub execution_price: Option<f64>, // Base/quote, None when unavailable
+    pub impact_bps: Option<f64>,      // Signed, vs pool spot price
+    pub available: bool,              // False when the rung exceeds pool limits or simulation failed
+}
+
+/// Depth report (price impact ladder) for a single pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolDepth {
+    pub address: String,
+    pub r#type: String,
+    pub spot: f64,
+    pub levels: Vec<DepthLevel>,
+}
+
 /// Component readjustment opportunity.
 #[derive(Debug, Clone)]
 pub struct CompReadjustment {
@@ -80,6 +214,8 @@ pub struct Inventory {
     pub base_balance: u128,  // Divided
     pub quote_balance: u128, // Divided
     pub nonce: u64,
+    #[serde(default)]
+    pub native_balance: u128, // Wei
 }
 
 /// Current market context and pricing information.
@@ -93,11 +229,15 @@ pub struct MarketContext {
     pub native_gas_price: u128,         // gwei: to be used for gas cost calculations
     // pub block: alloy::rpc::types::Block,
     pub block: u64,
+    #[serde(default)]
+    pub age_ms: u64, // Age of the cached gas fees and ETH/USD price (GasOracle) the context was built with
 }
 
 /// Complete execution order with adjustment and calculation.
 #[derive(Debug, Clone)]
 pub struct ExecutionOrder {
+    /// Correlation id of the trade, a field of the `trade` span of its preparation and execution
+    pub trade_id: String,
     pub adjustment: CompReadjustment,
     pub calculation: SwapCalculation,
     // pub bribing: BribeCalculation,
@@ -129,20 +269,35 @@ pub struct SwapCalculation {
     // Profitability
     pub profit_delta_bps: f64,
     pub profitable: bool,
+    // Optimizer diagnostics
+    pub termination: TerminationReason,
+    pub bracket_width: f64,
+    // Exact out: amount_out_min is the exact target, selling_amount its reverse quote. The router only swaps exact in, so
+    // the max input (the reverse quote raised by the slippage, within the inventory) is spent for at least the target
+    pub exact_out: bool,
+    pub amount_in_max_normalized: f64,
+    pub amount_in_max_raw: BigUint, // Sent as the router amountIn and approved, the simulated input exact in
+    // Raw amounts as simulated, exact: the input given to the pool and the output it quoted
+    pub amount_in_raw: BigUint,
+    pub amount_out_raw: BigUint,
 }
 
 /// Transaction request for trade execution.
 #[derive(Debug, Clone)]
 pub struct TradeTxRequest {
+    pub wrap: Option<TransactionRequest>, // Native deposit into the wrapped token, when the inventory needs it
     pub approve: Option<TransactionRequest>,
     pub swap: TransactionRequest,
+    pub unwrap: Option<TransactionRequest>, // Wrapped token withdrawal above unwrap_to_native_above
 }
 
 /// Complete trade with transactions and metadata.
 #[derive(Debug, Clone)]
 pub struct Trade {
+    pub wrap: Option<TransactionRequest>,
     pub approve: Option<TransactionRequest>,
     pub swap: TransactionRequest,
+    pub unwrap: Option<TransactionRequest>,
     pub metadata: TradeData,
 }
 
@@ -156,12 +311,16 @@ pub enum TradeStatus {
     BroadcastInProgress,
     BroadcastSucceeded,
     BroadcastFailed,
+    StalePrice, // Aborted before execution, the pool moved or the state fell behind the chain head since its evaluation
+    Timeout,    // Aborted before execution, past the latency budget of its block update
 }
 
 /// Complete trade data with all execution information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeData {
     // Core trade info
+    #[serde(default)]
+    pub trade_id: String, // Correlation id, as logged in the `trade` span
     pub status: TradeStatus,
     pub timestamp: u128,
     // Pre-trade data
@@ -171,13 +330,68 @@ pub struct TradeData {
     // Sim/Exec
     pub simulation: Option<SimulatedData>,
     pub broadcast: Option<BroadcastData>,
+    #[serde(default)]
+    pub realized: Option<RealizedData>, // Set once the receipt of the included trade is known
+}
+
+impl TradeData {
+    /// Compares the receipt of the trade with what was expected, setting `realized`: amount received by `wallet` in
+    /// the output token, slippage against `amount_out_expected`, and gas paid. Warns when the slippage exceeds the
+    /// tolerance, a sign the simulated pool states lag the chain. None without a successful receipt or output token.
+    pub fn realize(&mut self, wallet: &str) -> Option<&RealizedData> {
+        let receipt = self.broadcast.as_ref()?.receipt.as_ref().filter(|receipt| receipt.status)?;
+        let meta = &self.metadata;
+        if meta.token_out.is_empty() {
+            return None;
+        }
+        let received = crate::utils::evm::received(&receipt.transfers, &meta.token_out, wallet);
+        let amount_out = received as f64 / 10f64.powi(meta.token_out_decimals as i32);
+        let slippage_bps = if meta.amount_out_expected > 0. {
+            (meta.amount_out_expected - amount_out) / meta.amount_out_expected * crate::utils::constants::BASIS_POINT_DENO
+        } else {
+            0.
+        };
+        let gas_cost_usd = (receipt.gas_used as f64 * receipt.effective_gas_price as f64) / 1e18 * self.context.eth_to_usd;
+        if slippage_bps > meta.slippage_tolerance_bps {
+            tracing::warn!(
+                metric = "realized_slippage_exceeded",
+                realized_slippage_bps = slippage_bps,
+                tolerance_bps = meta.slippage_tolerance_bps,
+                "📉 Trade {} realized {:.2} bps of slippage, above the {:.2} bps tolerance (expected {:.6} {}, received {:.6}): simulated states are lagging",
+                self.trade_id,
+                slippage_bps,
+                meta.slippage_tolerance_bps,
+                meta.amount_out_expected,
+                meta.token_out,
+                amount_out
+            );
+        }
+        self.realized = Some(RealizedData {
+            realized_amount_out: amount_out,
+            realized_slippage_bps: slippage_bps,
+            realized_gas_cost_usd: gas_cost_usd,
+        });
+        self.realized.as_ref()
+    }
+}
+
+/// Realized execution of an included trade, next to its expectation (`PreTradeData`).
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RealizedData {
+    pub realized_amount_out: f64,   // Normalized, received by the wallet
+    pub realized_slippage_bps: f64, // Against amount_out_expected, positive when receiving less
+    pub realized_gas_cost_usd: f64,
 }
 
 /// Transaction simulation results.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct SimulatedData {
-    pub simulated_at_ms: u128,
+    #[serde(default)]
+    pub trade_id: String, // Trade simulated, the results being matched by id rather than by position
+    pub simulated_at_ms: u128, // Unix time (ms) of the simulation result, failed or not
     pub simulated_took_ms: u128,
+    #[serde(default)]
+    pub simulated_block: u64, // Block the simulation ran on, to compare with the inclusion block (0 if it failed)
     pub estimated_gas: u128,
     pub status: bool,
     pub error: Option<String>,
@@ -186,11 +400,68 @@ pub struct SimulatedData {
 /// Transaction broadcast results.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct BroadcastData {
-    pub broadcasted_at_ms: u128,
+    #[serde(default)]
+    pub trade_id: String, // Trade broadcast, the results being matched by id rather than by position
+    pub broadcasted_at_ms: u128, // Unix time (ms) of the broadcast, or of the attempt when not broadcast
     pub broadcasted_took_ms: u128,
     pub hash: String,
     pub broadcast_error: Option<String>,
-    pub receipt: Option<ReceiptData>, // Fetched in monitor program
+    #[serde(default)]
+    pub error_kind: Option<ExecErrorKind>, // Variant of broadcast_error, None when recorded by an older version
+    pub receipt: Option<ReceiptData>, // Filled at broadcast, fetched again in monitor program
+    #[serde(default)]
+    pub dropped: bool, // Set by the monitor when no receipt showed up within TRADE_RECEIPT_MAX_AGE_MINUTES
+    #[serde(default)]
+    pub dry_run: bool, // Not broadcast by the dry run strategy, neither a success nor an error
+}
+
+impl BroadcastData {
+    /// True when the swap was sent: no error and a transaction hash.
+    pub fn succeeded(&self) -> bool {
+        self.broadcast_error.is_none() && !self.hash.is_empty()
+    }
+
+    /// Attempt of trade `trade_id` not broadcast, failed with `error`.
+    pub fn failed(trade_id: &str, error: ExecError) -> Self {
+        let mut bd = Self {
+            trade_id: trade_id.to_string(),
+            broadcasted_at_ms: crate::utils::misc::now_ms(),
+            ..Default::default()
+        };
+        bd.fail(error);
+        bd
+    }
+
+    /// Trade `trade_id` simulated only, not broadcast by the dry run strategy.
+    pub fn dry(trade_id: &str) -> Self {
+        Self {
+            trade_id: trade_id.to_string(),
+            broadcasted_at_ms: crate::utils::misc::now_ms(),
+            dry_run: true,
+            ..Default::default()
+        }
+    }
+
+    /// Records the error of the broadcast, as displayed, and its variant.
+    pub fn fail(&mut self, error: ExecError) {
+        self.error_kind = Some(error.kind());
+        self.broadcast_error = Some(error.to_string());
+    }
+
+    /// Clears the error of the broadcast.
+    pub fn succeed(&mut self) {
+        self.error_kind = None;
+        self.broadcast_error = None;
+    }
+
+    /// Error of the broadcast, rebuilt from its recorded variant, classified from its text when recorded without.
+    pub fn error(&self) -> Option<ExecError> {
+        let error = self.broadcast_error.as_ref()?;
+        Some(match self.error_kind {
+            Some(kind) => ExecError::of(kind, error.as_str()),
+            None => ExecError::classify(error.as_str()),
+        })
+    }
 }
 
 /// Transaction receipt data from blockchain.
@@ -203,6 +474,17 @@ pub struct ReceiptData {
     pub transaction_index: u64,
     pub block_number: u64,
     pub effective_gas_price: u128,
+    #[serde(default)]
+    pub transfers: Vec<TransferData>, // ERC-20 transfers of the logs, see utils::evm::transfers
+}
+
+/// ERC-20 `Transfer` event of a receipt.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransferData {
+    pub token: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u128, // Raw units
 }
 
 /// Pre-trade analysis and planning data.
@@ -216,6 +498,10 @@ pub struct PreTradeData {
     // Trade amounts
     pub amount_in_normalized: f64,
     pub amount_out_expected: f64,
+    #[serde(default)]
+    pub token_out: String, // Address of the bought token
+    #[serde(default)]
+    pub token_out_decimals: u32,
     // Price information
     pub spot_price: f64,
     pub reference_price: f64,
@@ -225,3 +511,25 @@ pub struct PreTradeData {
     // Gas cost
     pub gas_cost_usd: f64,
 }
+
+/// Realized PnL of a single executed trade, in USD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlEntry {
+    pub timestamp_ms: u128,
+    pub sold_usd: f64,
+    pub bought_usd: f64,
+    pub gas_usd: f64,
+    pub pnl_usd: f64, // bought - sold - gas
+}
+
+/// Realized PnL summary, cumulated since startup and over the rolling window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PnlSnapshot {
+    pub timestamp_ms: u128,
+    pub trades: usize,
+    pub cumulative_usd: f64,
+    pub cumulative_gas_usd: f64,
+    pub window_ms: u128,
+    pub rolling_trades: usize,
+    pub rolling_usd: f64,
+}
@@ -4,6 +4,7 @@
 //! This module contains all the core types, configurations, and data models for
 //! market making operations, blockchain interactions, and system configuration.
 pub mod builder;
+pub mod cli;
 pub mod config;
 pub mod maker;
 pub mod misc;
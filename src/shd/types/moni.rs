@@ -3,14 +3,55 @@ use serde::{Deserialize, Serialize};
 use crate::types::maker::TradeData;
 use serde_json::Value;
 
-use crate::types::{config::MarketMakerConfig, maker::ComponentPriceData};
+use crate::types::{
+    config::MarketMakerConfig,
+    maker::{ComponentPriceData, PnlSnapshot, PoolDepth},
+};
 
-/// Base message structure for all Redis messages
+/// Previous (unversioned) message structure, still read from producers not upgraded yet
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RedisMessage {
     pub message: MessageType,
     pub timestamp: u64,
     pub data: Value,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mirrored: bool, // Copy of an envelope published alongside in compatibility mode, skipped by the monitors reading envelopes
+}
+
+/// Versioned envelope of every published event.
+///
+/// A consumer reads every minor version of its major: fields it does not know are ignored, and a field added to a
+/// message must have a serde default so that older producers stay readable. A major version mismatch is dead-lettered.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Envelope {
+    pub schema_version: String, // "major.minor", see EVENT_SCHEMA_VERSION
+    pub kind: String,           // MessageType, as serialized
+    pub payload: Value,
+    pub published_at: u64,  // Unix ms
+    pub identifier: String, // Instance identifier, empty for pings
+}
+
+impl Envelope {
+    /// Wraps a message in the envelope of the current schema version.
+    pub fn new(kind: &MessageType, identifier: &str, payload: Value) -> Self {
+        Self {
+            schema_version: crate::utils::constants::EVENT_SCHEMA_VERSION.to_string(),
+            kind: kind.as_str().to_string(),
+            payload,
+            published_at: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            identifier: identifier.to_string(),
+        }
+    }
+
+    /// Major version of the schema, None if malformed.
+    pub fn major(&self) -> Option<u64> {
+        schema_major(&self.schema_version)
+    }
+}
+
+/// Major version of a "major.minor" schema version.
+pub fn schema_major(version: &str) -> Option<u64> {
+    version.split('.').next()?.trim().parse().ok()
 }
 
 /// New instance deployment message (simplified)
@@ -28,6 +69,55 @@ pub struct NewPricesMessage {
     pub reference_price: f64,
     pub components: Vec<ComponentPriceData>,
     pub block: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<Vec<PoolDepth>>,
+    #[serde(default)]
+    pub state: TradingState,
+    #[serde(default)]
+    pub sigma_bps: f64, // Reference price volatility (EWMA, per polled block)
+    #[serde(default)]
+    pub execution_threshold_bps: f64, // min_executable_spread_bps widened by the volatility and the adaptive shortfall
+    #[serde(default)]
+    pub adaptive_bps: f64, // Widening of the adaptive threshold, the realized shortfall of the trades
+    #[serde(default)]
+    pub quarantined: usize, // Target pools outside the sanity band around the reference
+    #[serde(default)]
+    pub stream_lag_blocks: Option<u64>, // Last sampled delay of the stream behind the chain head
+    #[serde(default)]
+    pub degraded: bool, // Prices of the persisted snapshot, published monitor-only before the stream is ready
+}
+
+/// Price rows of one instance aggregated over `bucket_minutes`, replacing the raw `NewPricesMessage` rows by the retention job
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DownsampledPrices {
+    pub identifier: String,
+    pub downsampled: bool, // Always true, tells aggregated rows from raw ones
+    pub bucket_minutes: u64,
+    pub rows: usize, // Raw rows aggregated
+    pub first_block: u64,
+    pub last_block: u64,
+    pub reference_price: PriceOhlc,
+    pub components: Vec<ComponentPriceRange>,
+    #[serde(default)]
+    pub max_stream_lag_blocks: Option<u64>,
+}
+
+/// Open, high, low and close of a price over a bucket
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PriceOhlc {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Price range of one component over a bucket
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ComponentPriceRange {
+    pub address: String,
+    pub r#type: String,
+    pub min: f64,
+    pub max: f64,
 }
 
 /// Trade event message (simplified)
@@ -35,6 +125,127 @@ pub struct NewPricesMessage {
 pub struct NewTradeMessage {
     pub identifier: String,
     pub data: TradeData,
+    /// Client-generated key, identifying the trade when it has no broadcast hash (dry run, failed simulation)
+    #[serde(default)]
+    pub idempotency_key: String,
+}
+
+impl NewTradeMessage {
+    /// Key a trade is stored once under: its broadcast hash, else the client key.
+    pub fn key(&self) -> Option<String> {
+        match self.data.broadcast.as_ref().map(|broadcast| broadcast.hash.clone()) {
+            Some(hash) if !hash.is_empty() => Some(hash.to_lowercase()),
+            _ if !self.idempotency_key.is_empty() => Some(self.idempotency_key.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Realized PnL message
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewPnlMessage {
+    pub identifier: String,
+    pub pnl: PnlSnapshot,
+}
+
+/// End of instance message, published once the market maker loop has stopped
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EndInstanceMessage {
+    pub identifier: String,
+    pub reason: String,
+}
+
+/// Kind of operational alert
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AlertKind {
+    CircuitBreakerOpened,
+    CircuitBreakerResumed,
+    LowNativeBalance,
+    Killed,
+    ExposureLimit,
+    PoolQuarantined,
+    ExecutionFailed,   // Broadcast failed, e.g. every builder rejected the bundle
+    StreamReconnect,   // Tycho stream errored or closed, reconnecting
+    PreflightFailed,   // Startup checks failed
+    ExternalMovement,  // Wallet balances moved outside of our executions
+    TychoAuth,         // Tycho API key rejected (401/403), retrying with a backoff
+    Crash,             // Panic, published by the crashing process, or started paused after repeated crashes
+    ThresholdAdjusted, // Adaptive execution threshold widened or narrowed after a trade
+    #[serde(other)]
+    Unknown, // Kind added by a newer maker, read rather than dead-lettered by an older monitor
+}
+
+/// Trading state of an instance, driven by operator commands on the control channel
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingState {
+    #[default]
+    Running, // Streaming, publishing and trading
+    Paused, // Streaming and publishing, no evaluation nor execution
+    Killed, // Exiting
+}
+
+impl TradingState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TradingState::Running => "running",
+            TradingState::Paused => "paused",
+            TradingState::Killed => "killed",
+        }
+    }
+}
+
+/// Heartbeat POSTed as JSON to the HEARTBEAT endpoint, one per instance. Parsed by external tooling, fields are only added
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeartbeatPayload {
+    pub identifier: String,
+    pub commit: String,
+    pub timestamp_ms: u64,
+    pub state: TradingState,
+    pub block: u64,                 // Last block received, 0 before the first message
+    pub last_trade_ms: Option<u64>, // Last executed trade (unix ms), None since start
+    pub stream_ready: bool,
+    pub breaker_open: bool,
+    pub inventory: Option<InventorySummary>, // Last inventory read from chain
+    pub failures: u64,                       // Heartbeats failed since the process started
+}
+
+/// Balances of the wallet of an instance, normalized
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InventorySummary {
+    pub base_symbol: String,
+    pub base_balance: f64,
+    pub quote_symbol: String,
+    pub quote_balance: f64,
+    pub native_balance: f64,
+}
+
+/// Operator command received on the control channel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlCommand {
+    Pause,
+    Resume,
+    Kill,
+    RefreshInventory,
+}
+
+/// Control channel message. Without identifier, the command targets every instance
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ControlMessage {
+    pub command: ControlCommand,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+}
+
+/// Operational alert message
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewAlertMessage {
+    pub identifier: String,
+    pub kind: AlertKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pnl: Option<PnlSnapshot>,
 }
 
 /// Parsed message content
@@ -43,6 +254,9 @@ pub enum ParsedMessage {
     NewInstance(NewInstanceMessage),
     NewPrices(NewPricesMessage),
     NewTrade(NewTradeMessage),
+    NewPnl(NewPnlMessage),
+    NewAlert(NewAlertMessage),
+    EndInstance(EndInstanceMessage),
     Ping,
     Unknown(Value),
 }
@@ -58,4 +272,46 @@ pub enum MessageType {
     NewTrade,
     #[serde(rename = "new_prices")]
     NewPrices,
+    #[serde(rename = "new_pnl")]
+    NewPnl,
+    #[serde(rename = "new_alert")]
+    NewAlert,
+    #[serde(rename = "end_instance")]
+    EndInstance,
+}
+
+impl MessageType {
+    /// Every message type, each with its own Redis stream.
+    pub const ALL: [MessageType; 7] = [
+        MessageType::Ping,
+        MessageType::NewInstance,
+        MessageType::NewTrade,
+        MessageType::NewPrices,
+        MessageType::NewPnl,
+        MessageType::NewAlert,
+        MessageType::EndInstance,
+    ];
+
+    /// Converts to string representation, as serialized.
+    pub fn as_str(&self) -> &str {
+        match self {
+            MessageType::Ping => "ping",
+            MessageType::NewInstance => "new_instance",
+            MessageType::NewTrade => "new_trade",
+            MessageType::NewPrices => "new_prices",
+            MessageType::NewPnl => "new_pnl",
+            MessageType::NewAlert => "new_alert",
+            MessageType::EndInstance => "end_instance",
+        }
+    }
+
+    /// Message type of a serialized kind, None if unknown (e.g. added by a newer producer).
+    pub fn from_kind(kind: &str) -> Option<MessageType> {
+        MessageType::ALL.iter().find(|known| known.as_str() == kind).cloned()
+    }
+
+    /// Redis stream of this message type (streams transport), e.g. `tycho_market_maker:v1:stream:new_trade`.
+    pub fn stream(&self) -> String {
+        crate::data::keys::stream(self)
+    }
 }
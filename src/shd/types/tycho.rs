@@ -3,36 +3,23 @@ use tokio::sync::RwLock;
 use tycho_common::models::token::Token;
 use tycho_common::simulation::protocol_sim::ProtocolSim; // ProtocolSim trait for protocol simulation
 use tycho_simulation::tycho_core::Bytes;
-use tycho_simulation::{protocol::models::ProtocolComponent, tycho_client::feed::component_tracker::ComponentFilter};
+use tycho_simulation::{
+    protocol::models::{ProtocolComponent, Update},
+    tycho_client::feed::component_tracker::ComponentFilter,
+};
 
 #[derive(Clone)]
 pub struct PsbConfig {
     pub filter: ComponentFilter,
 }
 
-/// Chain type aliases to resolve library conflicts between different Tycho modules.
-/// Note: tycho_simulation::tycho_core is a re-export of tycho_common, so we only need ChainCommon.
-pub type ChainCommon = tycho_common::dto::Chain;
-pub type ChainSimu = tycho_simulation::evm::tycho_models::Chain;
-
-/// Returns the chain types for a given network name.
-pub fn chain(name: String) -> Option<(ChainCommon, ChainSimu)> {
-    match name.as_str() {
-        "ethereum" => Some((ChainCommon::Ethereum, ChainSimu::Ethereum)),
-        "base" => Some((ChainCommon::Base, ChainSimu::Base)),
-        "unichain" => Some((ChainCommon::Unichain, ChainSimu::Unichain)),
-        _ => {
-            tracing::error!("Unknown chain: {}", name);
-            None
-        }
-    }
-}
-
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 use strum::VariantNames;
 use strum_macros::{Display, EnumString, VariantNames as VariantNamesMacro};
 
-#[derive(Display, VariantNamesMacro, EnumString)]
+use super::config::NetworkName;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, VariantNamesMacro, EnumString)]
 pub enum TychoSupportedProtocol {
     #[strum(serialize = "pancakeswap_v2")]
     PancakeswapV2,
@@ -55,6 +42,38 @@ pub enum TychoSupportedProtocol {
 }
 
 impl TychoSupportedProtocol {
+    /// Every protocol, in the order they are registered on the stream.
+    pub const ALL: [TychoSupportedProtocol; 9] = [
+        TychoSupportedProtocol::UniswapV2,
+        TychoSupportedProtocol::UniswapV3,
+        TychoSupportedProtocol::UniswapV4,
+        TychoSupportedProtocol::Sushiswap,
+        TychoSupportedProtocol::PancakeswapV2,
+        TychoSupportedProtocol::PancakeswapV3,
+        TychoSupportedProtocol::EkuboV2,
+        TychoSupportedProtocol::BalancerV2,
+        TychoSupportedProtocol::Curve,
+    ];
+
+    /// Protocols indexed by Tycho on a network, in the order they are registered on the stream.
+    pub fn available_on(network: &NetworkName) -> &'static [TychoSupportedProtocol] {
+        match network {
+            NetworkName::Ethereum => &TychoSupportedProtocol::ALL,
+            NetworkName::Base | NetworkName::Unichain => &[
+                TychoSupportedProtocol::UniswapV2,
+                TychoSupportedProtocol::UniswapV3,
+                TychoSupportedProtocol::UniswapV4,
+                TychoSupportedProtocol::BalancerV2,
+                TychoSupportedProtocol::Curve,
+            ],
+        }
+    }
+
+    /// Returns true if Tycho indexes the protocol on the network.
+    pub fn is_available_on(&self, network: &NetworkName) -> bool {
+        TychoSupportedProtocol::available_on(network).contains(self)
+    }
+
     pub fn vectorize() -> Vec<String> {
         TychoSupportedProtocol::VARIANTS
             .iter()
@@ -64,7 +83,7 @@ impl TychoSupportedProtocol {
     }
 }
 
-#[derive(Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
 pub enum AmmType {
     #[strum(serialize = "pancakeswap_v2_pool")]
     PancakeswapV2,
@@ -84,23 +103,64 @@ pub enum AmmType {
     Balancer,
     #[strum(serialize = "curve_pool")]
     Curve,
+    #[strum(serialize = "balancer_v3_pool")]
+    BalancerV3,
+    #[strum(serialize = "maverick_v2_pool")]
+    MaverickV2,
 }
 
-impl From<&str> for AmmType {
-    fn from(s: &str) -> Self {
+impl AmmType {
+    /// Every AMM type, by its canonical `protocol_type_name`.
+    pub const ALL: [AmmType; 11] = [
+        AmmType::PancakeswapV2,
+        AmmType::PancakeswapV3,
+        AmmType::Sushiswap,
+        AmmType::UniswapV2,
+        AmmType::UniswapV3,
+        AmmType::UniswapV4,
+        AmmType::EkuboV2,
+        AmmType::Balancer,
+        AmmType::Curve,
+        AmmType::BalancerV3,
+        AmmType::MaverickV2,
+    ];
+
+    /// AMM type of a `protocol_type_name`. The VM protocols name their pool types per deployment
+    /// (e.g. `curve_stableswap_ng_pool` or `balancer_v2_weighted_pool` on L2s), matched on their prefix.
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
-            "pancakeswap_v2_pool" => AmmType::PancakeswapV2,
-            "pancakeswap_v3_pool" => AmmType::PancakeswapV3,
-            "sushiswap_v2_pool" => AmmType::Sushiswap,
-            "uniswap_v2_pool" => AmmType::UniswapV2,
-            "uniswap_v3_pool" => AmmType::UniswapV3,
-            "uniswap_v4_pool" => AmmType::UniswapV4,
-            "balancer_v2_pool" => AmmType::Balancer,
-            "curve_pool" => AmmType::Curve,      // ?
-            "ekubo_v2_pool" => AmmType::EkuboV2, // ?
-            _ => panic!("Unknown AMM type"),
+            "pancakeswap_v2_pool" => Some(AmmType::PancakeswapV2),
+            "pancakeswap_v3_pool" => Some(AmmType::PancakeswapV3),
+            "sushiswap_v2_pool" => Some(AmmType::Sushiswap),
+            "uniswap_v2_pool" => Some(AmmType::UniswapV2),
+            "uniswap_v3_pool" => Some(AmmType::UniswapV3),
+            "uniswap_v4_pool" => Some(AmmType::UniswapV4),
+            "ekubo_v2_pool" => Some(AmmType::EkuboV2),
+            "maverick_v2_pool" => Some(AmmType::MaverickV2),
+            s if s.starts_with("balancer_v2") => Some(AmmType::Balancer),
+            s if s.starts_with("balancer_v3") => Some(AmmType::BalancerV3),
+            s if s.starts_with("curve") => Some(AmmType::Curve),
+            _ => None,
         }
     }
+
+    /// AMM type of a component, from its `protocol_type_name`, else from its `protocol_system`.
+    pub fn of(cp: &ProtocolComponent) -> Option<Self> {
+        AmmType::parse(&cp.protocol_type_name).or_else(|| {
+            let amm = match TychoSupportedProtocol::from_str(&cp.protocol_system).ok()? {
+                TychoSupportedProtocol::PancakeswapV2 => AmmType::PancakeswapV2,
+                TychoSupportedProtocol::PancakeswapV3 => AmmType::PancakeswapV3,
+                TychoSupportedProtocol::Sushiswap => AmmType::Sushiswap,
+                TychoSupportedProtocol::UniswapV2 => AmmType::UniswapV2,
+                TychoSupportedProtocol::UniswapV3 => AmmType::UniswapV3,
+                TychoSupportedProtocol::UniswapV4 => AmmType::UniswapV4,
+                TychoSupportedProtocol::EkuboV2 => AmmType::EkuboV2,
+                TychoSupportedProtocol::BalancerV2 => AmmType::Balancer,
+                TychoSupportedProtocol::Curve => AmmType::Curve,
+            };
+            Some(amm)
+        })
+    }
 }
 
 pub type SharedTychoStreamState = Arc<RwLock<TychoStreamState>>;
@@ -113,6 +173,67 @@ pub struct TychoStreamState {
     pub components: HashMap<String, ProtocolComponent>,
     // All tokens given Tycho, used to find path, price, etc.
     pub atks: Vec<Token>,
+    // Last block (or timestamp) applied to the cache
+    pub block: u64,
+}
+
+/// Owned copy of a Tycho stream update, shared between the pairs of a multi-pair process.
+#[derive(Clone, Default)]
+pub struct SharedUpdate {
+    pub block_number_or_timestamp: u64,
+    pub states: HashMap<String, Box<dyn ProtocolSim>>,
+    pub new_pairs: HashMap<String, ProtocolComponent>,
+    pub removed_pairs: HashMap<String, ProtocolComponent>,
+}
+
+impl From<Update> for SharedUpdate {
+    fn from(update: Update) -> Self {
+        Self {
+            block_number_or_timestamp: update.block_number_or_timestamp,
+            states: update.states,
+            new_pairs: update.new_pairs,
+            removed_pairs: update.removed_pairs,
+        }
+    }
+}
+
+impl TychoStreamState {
+    /// Applies a stream update to the cache. Keys are lowercased, as in the maker loop.
+    pub fn apply(&mut self, update: &SharedUpdate) {
+        self.block = update.block_number_or_timestamp;
+        for (id, state) in update.states.iter() {
+            self.protosims.insert(id.to_lowercase(), state.clone());
+        }
+        for (id, comp) in update.new_pairs.iter() {
+            self.components.insert(id.to_lowercase(), comp.clone());
+        }
+        for id in update.removed_pairs.keys() {
+            self.components.remove(&id.to_lowercase());
+            self.protosims.remove(&id.to_lowercase());
+        }
+    }
+
+    /// Applies the full snapshot opening a connection as a diff on the cache: the components and states missing from it
+    /// are dropped, the others replaced or added.
+    pub fn resync(&mut self, snapshot: &SharedUpdate) {
+        self.components.retain(|id, _| snapshot.new_pairs.keys().any(|listed| listed.eq_ignore_ascii_case(id)));
+        self.protosims.retain(|id, _| snapshot.states.keys().any(|listed| listed.eq_ignore_ascii_case(id)));
+        self.apply(snapshot);
+    }
+
+    /// Full-state update rebuilt from the cache, used to bootstrap a pair joining the shared stream.
+    /// Returns None until the first update has been applied.
+    pub fn snapshot(&self) -> Option<SharedUpdate> {
+        if self.components.is_empty() {
+            return None;
+        }
+        Some(SharedUpdate {
+            block_number_or_timestamp: self.block,
+            states: self.protosims.clone(),
+            new_pairs: self.components.clone(),
+            removed_pairs: HashMap::new(),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,216 @@
+//! Alerting Module
+//!
+//! Critical events (circuit breaker, low gas balance, failed execution, stream reconnection, preflight failure)
+//! used to end up in the logs and on Redis only, read the next morning. They are now also pushed to a Telegram
+//! chat and/or a Slack-compatible webhook, configured with ALERT_TELEGRAM_BOT_TOKEN + ALERT_TELEGRAM_CHAT_ID and
+//! ALERT_WEBHOOK_URL. `notify` never blocks nor panics: alerts go into a bounded queue delivered by a background
+//! task, and an alert of the same kind for the same instance is sent at most once per `ALERT_RATE_LIMIT_MS`.
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::{
+    types::{config::EnvConfig, moni::NewAlertMessage},
+    utils::constants::{ALERT_QUEUE_CAPACITY, ALERT_RATE_LIMIT_MS, ALERT_SEND_TIMEOUT_MS},
+};
+
+/// Process-wide dispatcher, set by `configure` when at least one alerter is configured.
+static DISPATCHER: OnceLock<Dispatcher> = OnceLock::new();
+
+/// Push notification channel.
+#[async_trait]
+pub trait Alerter: Send + Sync {
+    /// Returns the alerter name for logging purposes.
+    fn name(&self) -> String;
+
+    /// Endpoint the alert is posted to.
+    fn url(&self) -> String;
+
+    /// JSON body posted for an alert.
+    fn payload(&self, alert: &NewAlertMessage) -> Value;
+
+    /// Posts the alert.
+    async fn send(&self, alert: &NewAlertMessage) -> Result<(), String> {
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(ALERT_SEND_TIMEOUT_MS)).build().map_err(|e| e.to_string())?;
+        let response = client
+            .post(self.url())
+            .header("Content-Type", "application/json")
+            .body(self.payload(alert).to_string())
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("{} answered {}", self.name(), response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Plain text of an alert, shared by the alerters.
+pub fn text(alert: &NewAlertMessage) -> String {
+    let mut text = format!("🚨 {:?} | {}\n{}", alert.kind, alert.identifier, alert.message);
+    if let Some(pnl) = alert.pnl.as_ref() {
+        text.push_str(&format!("\nPnL: {:+.2} $ over {} trades ({:+.2} $ rolling)", pnl.cumulative_usd, pnl.trades, pnl.rolling_usd));
+    }
+    text
+}
+
+/// Telegram bot posting to one chat (sendMessage).
+pub struct TelegramAlerter {
+    pub token: String,
+    pub chat_id: String,
+}
+
+#[async_trait]
+impl Alerter for TelegramAlerter {
+    fn name(&self) -> String {
+        "Telegram".to_string()
+    }
+
+    fn url(&self) -> String {
+        format!("https://api.telegram.org/bot{}/sendMessage", self.token)
+    }
+
+    fn payload(&self, alert: &NewAlertMessage) -> Value {
+        json!({ "chat_id": self.chat_id, "text": text(alert), "disable_web_page_preview": true })
+    }
+}
+
+/// Slack incoming webhook, or any webhook accepting Slack's `{"text": ...}` body (Mattermost, Discord `/slack`, ...).
+pub struct WebhookAlerter {
+    pub url: String,
+}
+
+#[async_trait]
+impl Alerter for WebhookAlerter {
+    fn name(&self) -> String {
+        "Webhook".to_string()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn payload(&self, alert: &NewAlertMessage) -> Value {
+        json!({ "text": text(alert) })
+    }
+}
+
+/// Alerters configured in the environment, none if no variable is set.
+pub fn from_env(env: &EnvConfig) -> Vec<Box<dyn Alerter>> {
+    let mut alerters: Vec<Box<dyn Alerter>> = vec![];
+    if let (Some(token), Some(chat_id)) = (env.alert_telegram_bot_token.clone(), env.alert_telegram_chat_id.clone()) {
+        alerters.push(Box::new(TelegramAlerter { token, chat_id }));
+    }
+    if let Some(url) = env.alert_webhook_url.clone() {
+        alerters.push(Box::new(WebhookAlerter { url }));
+    }
+    alerters
+}
+
+/// At most one alert per key (instance and kind) per window.
+#[derive(Debug)]
+pub struct AlertRateLimiter {
+    window_ms: u128,
+    sent: HashMap<String, u128>,
+}
+
+impl AlertRateLimiter {
+    pub fn new(window_ms: u128) -> Self {
+        Self { window_ms, sent: HashMap::new() }
+    }
+
+    /// Returns true if the alert can be sent at `now` (unix ms), and records it.
+    pub fn allow(&mut self, alert: &NewAlertMessage, now: u128) -> bool {
+        let key = format!("{}:{:?}", alert.identifier, alert.kind);
+        match self.sent.get(&key) {
+            Some(last) if now.saturating_sub(*last) < self.window_ms => false,
+            _ => {
+                self.sent.insert(key, now);
+                true
+            }
+        }
+    }
+}
+
+/// Queue shared with the delivery task.
+struct Dispatcher {
+    tx: mpsc::Sender<NewAlertMessage>,
+    limiter: Mutex<AlertRateLimiter>,
+    pending: Arc<AtomicUsize>, // Queued or being delivered
+}
+
+/// Starts the delivery task with the alerters, before the first alert. Returns false if none or already configured.
+pub fn configure(alerters: Vec<Box<dyn Alerter>>) -> bool {
+    if alerters.is_empty() || DISPATCHER.get().is_some() {
+        return false;
+    }
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        tracing::error!("🚨 No Tokio runtime, alerts disabled");
+        return false;
+    };
+    let names = alerters.iter().map(|a| a.name()).collect::<Vec<String>>();
+    let (tx, mut rx) = mpsc::channel::<NewAlertMessage>(ALERT_QUEUE_CAPACITY);
+    let pending = Arc::new(AtomicUsize::new(0));
+    let dispatcher = Dispatcher {
+        tx,
+        limiter: Mutex::new(AlertRateLimiter::new(ALERT_RATE_LIMIT_MS as u128)),
+        pending: pending.clone(),
+    };
+    if DISPATCHER.set(dispatcher).is_err() {
+        return false;
+    }
+    handle.spawn(async move {
+        while let Some(alert) = rx.recv().await {
+            for alerter in alerters.iter() {
+                if let Err(e) = alerter.send(&alert).await {
+                    tracing::warn!("🚨 Failed to deliver {:?} alert via {}: {}", alert.kind, alerter.name(), e);
+                }
+            }
+            pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    });
+    tracing::info!("🚨 Alerts delivered via {}", names.join(", "));
+    true
+}
+
+/// Queues an alert for delivery. Never waits: rate-limited alerts and alerts beyond the queue capacity are dropped.
+pub fn notify(alert: NewAlertMessage) {
+    let Some(dispatcher) = DISPATCHER.get() else {
+        return;
+    };
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+    if !dispatcher.limiter.lock().unwrap().allow(&alert, now) {
+        tracing::debug!("🚨 {:?} alert for {} rate-limited", alert.kind, alert.identifier);
+        return;
+    }
+    dispatcher.pending.fetch_add(1, Ordering::SeqCst);
+    if let Err(e) = dispatcher.tx.try_send(alert) {
+        dispatcher.pending.fetch_sub(1, Ordering::SeqCst);
+        tracing::warn!("🚨 Alert queue full, alert dropped: {}", e);
+    }
+}
+
+/// Waits until the queued alerts are delivered, at most `timeout`, e.g. before exiting. Returns false if some are pending.
+pub async fn flush(timeout: Duration) -> bool {
+    let Some(dispatcher) = DISPATCHER.get() else {
+        return true;
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    while dispatcher.pending.load(Ordering::SeqCst) > 0 {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    true
+}
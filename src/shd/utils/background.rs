@@ -0,0 +1,46 @@
+//! Background Writer Module
+//!
+//! The audit log and the pool snapshot were written to disk from the block loop, a slow disk stalling the Tokio worker
+//! running it. Their writes are queued instead to a dedicated thread, which runs them in order. `flush` waits for the
+//! writes queued so far, at exit or before reading a file back.
+use std::sync::mpsc::{channel, SendError, Sender};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Handle on a thread running the queued jobs in order, stopped once every handle is dropped.
+#[derive(Debug, Clone)]
+pub struct BackgroundWriter {
+    jobs: Sender<Job>,
+}
+
+impl BackgroundWriter {
+    /// Spawns the writer thread, named `name`.
+    pub fn spawn(name: &str) -> Self {
+        let (jobs, queue) = channel::<Job>();
+        let spawned = std::thread::Builder::new().name(name.to_string()).spawn(move || {
+            for job in queue {
+                job();
+            }
+        });
+        if let Err(e) = spawned {
+            tracing::error!("Failed to spawn the {} thread, writing on the caller thread: {}", name, e);
+        }
+        Self { jobs }
+    }
+
+    /// Queues a job, run on the calling thread if the writer thread is gone.
+    pub fn run(&self, job: impl FnOnce() + Send + 'static) {
+        if let Err(SendError(job)) = self.jobs.send(Box::new(job)) {
+            job();
+        }
+    }
+
+    /// Blocks until the jobs queued so far have run.
+    pub fn flush(&self) {
+        let (done, finished) = channel();
+        self.run(move || {
+            let _ = done.send(());
+        });
+        let _ = finished.recv();
+    }
+}
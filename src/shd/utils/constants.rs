@@ -2,23 +2,72 @@
 
 use std::sync::atomic::AtomicBool;
 
-/// Redis channel for pub/sub communication
+/// Prefix and schema version of the Redis channels and keys, see `data::keys`
+pub const REDIS_KEYS_PREFIX: &str = "tycho_market_maker";
+pub const REDIS_KEYS_VERSION: &str = "v1";
+
+/// Legacy global Redis channel of the events (pre-v1), published too in compatibility mode
 pub const CHANNEL_REDIS: &str = "tycho_market_maker";
 
-/// Restart delay in seconds
-pub const RESTART: u64 = 60;
+/// Schema version ("major.minor") of the published event envelopes. Bump the minor for additive changes, the major otherwise
+pub const EVENT_SCHEMA_VERSION: &str = "1.7";
+
+/// Consumer group of the monitor on the event streams, and max entries kept per stream (approximate trim)
+pub const EVENTS_CONSUMER_GROUP: &str = "monitor";
+pub const EVENT_STREAM_MAXLEN: usize = 100_000;
+
+/// Field of a stream entry holding the serialized message
+pub const STREAM_PAYLOAD_FIELD: &str = "payload";
+
+/// Max entries read, or claimed, per stream request
+pub const STREAM_READ_COUNT: usize = 100;
+
+/// Interval between health checks of the monitor database connection
+pub const DB_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Default bucket (minutes) of the downsampled price rows, and interval between runs of the retention job
+pub const DEFAULT_PRICE_DOWNSAMPLE_MINUTES: u64 = 15;
+pub const PRICE_RETENTION_INTERVAL_SECS: u64 = 3_600;
+/// Raw price rows read per page by the retention job
+pub const PRICE_RETENTION_PAGE_ROWS: u64 = 5_000;
+
+/// Default age (minutes) past which a trade still without receipt is marked dropped, and interval between runs of the receipt backfill
+pub const DEFAULT_TRADE_RECEIPT_MAX_AGE_MINUTES: u64 = 60;
+pub const RECEIPT_BACKFILL_INTERVAL_SECS: u64 = 60;
+
+/// Attempts to store a monitor message before it is dead-lettered, and backoff before the second one (doubled each time)
+pub const DEAD_LETTER_MAX_ATTEMPTS: i32 = 3;
+pub const DEAD_LETTER_BACKOFF_MS: u64 = 250;
+/// Interval between two reports of the dead-letter counter by the monitor
+pub const DEAD_LETTER_REPORT_INTERVAL_SECS: u64 = 60;
+
+/// Legacy Redis channel for operator commands (pause, resume, kill), listened to in compatibility mode
+pub const CONTROL_CHANNEL_REDIS: &str = "tycho_market_maker:control";
+
+/// Tycho API authentication constants
+pub const TYCHO_AUTH_BACKOFF_MS: u64 = 5_000; // Delay before retrying a call rejected with 401/403, doubled on each retry
+pub const TYCHO_AUTH_MAX_BACKOFF_MS: u64 = 300_000; // Cap of the doubled delay
+pub const TYCHO_AUTH_MAX_ATTEMPTS: u32 = 5; // Attempts of a rejected call before giving up
+
+/// Stream reconnection constants
+pub const DEFAULT_STREAM_RESTART_DELAY_MS: u64 = 1_000; // Delay before rebuilding a failed or closed Tycho stream, doubled on each consecutive reconnect
+pub const DEFAULT_STREAM_RESTART_MAX_DELAY_MS: u64 = 60_000; // Cap of the doubled delay
 
 /// Basis point denominator (10000 = 100%)
 pub const BASIS_POINT_DENO: f64 = 10_000.0;
 
-/// Price move threshold
-pub const PRICE_MOVE_THRESHOLD: f64 = 0.5;
+/// Fee (bps) assumed for a pool of unknown AMM type, the highest usual tier so that its spread threshold is not underestimated
+pub const UNKNOWN_AMM_FEE_BPS: u128 = 100;
+
+/// Default reference price move (bps) above which prices are published
+pub const DEFAULT_MIN_REFERENCE_PRICE_MOVE_BPS: f64 = 0.5;
 
-/// Add TVL threshold (minimum TVL for components to be monitored)
-pub const ADD_TVL_THRESHOLD: f64 = 20.0;
+/// Default TVL thresholds (in native token) of the stream component filter
+pub const DEFAULT_TVL_ADD_THRESHOLD: f64 = 20.0; // Minimum TVL for a component to enter the stream
+pub const DEFAULT_TVL_REMOVE_THRESHOLD: f64 = 20.0; // TVL below which a streamed component is removed
 
-/// Share pool balance swap basis points
-pub const SHARE_POOL_BAL_SWAP_BPS: f64 = 0.1;
+/// Default max share (bps) of the pool balance of the bought token taken by one swap, 10000 disables the cap
+pub const DEFAULT_MAX_POOL_SHARE_BPS: f64 = 10_000.0;
 
 /// Default approve gas limit
 pub const DEFAULT_APPROVE_GAS: u64 = 75_000;
@@ -26,27 +75,236 @@ pub const DEFAULT_APPROVE_GAS: u64 = 75_000;
 /// Default swap gas limit
 pub const DEFAULT_SWAP_GAS: u64 = 300_000;
 
-/// Min amount worth USD to swap
-pub const MIN_AMOUNT_WORTH_USD: f64 = 10.0;
+/// Default wrap (deposit) and unwrap (withdraw) gas limit
+pub const DEFAULT_WRAP_GAS: u64 = 60_000;
+
+/// Default min amount worth USD to swap
+pub const DEFAULT_MIN_AMOUNT_WORTH_USD: f64 = 10.0;
 
 /// Approve function signature
 pub const APPROVE_FN_SIGNATURE: &str = "approve(address,uint256)";
 
+/// Wrapped native token function signatures
+pub const DEPOSIT_FN_SIGNATURE: &str = "deposit()";
+pub const WITHDRAW_FN_SIGNATURE: &str = "withdraw(uint256)";
+
+/// Standard revert data of `require(cond, "reason")` / `revert("reason")`, and of the compiler checks (overflow, assert...)
+pub const ERROR_STRING_SIGNATURE: &str = "Error(string)";
+pub const PANIC_SIGNATURE: &str = "Panic(uint256)";
+
+/// Custom errors decoded in revert data: Tycho router, then Permit2, then common ERC20 errors. Extended with `revert_errors`.
+pub const DEFAULT_REVERT_ERRORS: &[&str] = &[
+    "TychoRouter__AddressZero()",
+    "TychoRouter__EmptySwaps()",
+    "TychoRouter__NegativeSlippage(uint256,uint256)",
+    "TychoRouter__AmountOutNotFullyReceived(uint256,uint256)",
+    "TychoRouter__MessageValueMismatch(uint256,uint256)",
+    "TychoRouter__InvalidDataLength()",
+    "TychoRouter__UndefinedMinAmountOut()",
+    "Dispatcher__UnapprovedExecutor(address)",
+    "Dispatcher__NonContractExecutor()",
+    "Dispatcher__InvalidDataLength()",
+    "AllowanceExpired(uint256)",
+    "InsufficientAllowance(uint256)",
+    "ExcessiveInvalidation()",
+    "InvalidAmount(uint256)",
+    "InvalidContractSignature()",
+    "InvalidNonce()",
+    "InvalidSignature()",
+    "InvalidSigner()",
+    "LengthMismatch()",
+    "SignatureExpired(uint256)",
+    "ERC20InsufficientBalance(address,uint256,uint256)",
+    "ERC20InsufficientAllowance(address,uint256,uint256)",
+    "TransferFromFailed()",
+    "TransferFailed()",
+];
+
 /// Null address
 pub const NULL_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
+/// Native token sentinel of the EIP-7528 convention, used by other aggregators and token lists
+pub const NATIVE_ADDRESS: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
+
+/// Canonical Multicall3 deployment, at the same address on most EVM chains.
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Default max age of the block head of the websocket subscription, older heads are polled over HTTP instead.
+pub const DEFAULT_WS_MAX_HEAD_AGE_S: u64 = 30;
+
+/// Max delay between two reconnections of the block head subscription.
+pub const WS_MAX_BACKOFF_S: u64 = 60;
+
+/// Default number of recent blocks read with eth_feeHistory to estimate the EIP-1559 fees.
+pub const DEFAULT_FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Max blocks of one eth_feeHistory request, most nodes cap it at 1024.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1_024;
+
+/// Default lifetime of a signed Permit2 allowance (and of its signature), in seconds.
+pub const DEFAULT_PERMIT2_EXPIRATION_S: u64 = 1_800;
+
 /// Has executed flag
 pub static HAS_EXECUTED: AtomicBool = AtomicBool::new(false);
 
 /// Default Redis host
 pub const DEFAULT_REDIS_HOST: &str = "127.0.0.1:42044";
 
+/// Buffered Redis publisher: max queued messages before price updates are dropped, and reconnection backoff
+pub const PUBLISH_QUEUE_CAPACITY: usize = 10_000;
+pub const PUBLISH_BACKOFF_MIN_MS: u64 = 250;
+pub const PUBLISH_BACKOFF_MAX_MS: u64 = 30_000;
+
+/// Max wait at exit for the queued events to reach Redis
+pub const PUBLISH_FLUSH_TIMEOUT_MS: u64 = 5_000;
+
+/// Spans exported over OTLP when OTEL_TRACES_FILTER is unset: the hot path stages, not the dependencies
+pub const DEFAULT_OTEL_TRACES_FILTER: &str = "shd=debug,maker=debug,monitor=debug";
+
 /// Default heartbeat delay
 pub const HEARTBEAT_DELAY: u64 = 300;
+pub const DEFAULT_HEARTBEAT_INTERVAL_S: u64 = HEARTBEAT_DELAY / 2; // Between two heartbeat POSTs
+pub const DEFAULT_HEARTBEAT_TIMEOUT_MS: u64 = 5_000; // A slow endpoint fails the beat instead of delaying the next one
 
 /// Optimization constants
 pub const OPTI_TOLERANCE: f64 = 0.0001; // Stop when change is less than 0.01%
 pub const OPTI_MAX_ITERATIONS: usize = 20;
+pub const OPTI_PRICE_TOLERANCE: f64 = 0.0001; // Stop when post-swap price is this close to the reference
+pub const OPTI_SECANT_TRIGGER_RATIO: f64 = 0.05; // Switch from bisection to secant once the bracket is below 5% of max amount
+pub const OPTI_MAX_SIMULATIONS: usize = 40; // Default simulation budget (get_amount_out calls) per optimization
+pub const DEFAULT_READJUST_CONCURRENCY: usize = 4; // Readjustments of a block sized at the same time
+
+/// Depth report constants
+pub const DEFAULT_DEPTH_LADDER_USD: [f64; 3] = [1_000.0, 10_000.0, 100_000.0];
+pub const DEFAULT_DEPTH_REPORT_INTERVAL_MS: u64 = 300_000; // 0 disables the depth report
+
+/// Inventory skew constants
+pub const DEFAULT_TARGET_INVENTORY_RATIO: f64 = 0.5; // Target base share of the inventory value
+pub const DEFAULT_SKEW_GAIN_BPS_PER_PCT: f64 = 0.0; // Threshold shift per percentage point of skew, 0 disables the skew
+
+/// PnL constants
+pub const PNL_ROLLING_WINDOW_MS: u128 = 86_400_000; // 24h rolling window
+pub const DEFAULT_PNL_REPORT_INTERVAL_MS: u64 = 300_000; // 0 disables the PnL report
+
+/// Circuit breaker constants
+pub const DEFAULT_MAX_DAILY_LOSS_USD: f64 = 0.0; // 0 disables the breaker
+pub const DEFAULT_BREAKER_COOLDOWN_MS: u64 = 3_600_000; // 0 means explicit resume only
+pub const BREAKER_RESUME_KEY: &str = "breaker:resume"; // Legacy Redis key (suffixed with the instance identifier) to resume the breaker
+
+/// Default native balance floor kept for gas (0.005 ETH)
+pub const DEFAULT_MIN_NATIVE_BALANCE_WEI: u128 = 5_000_000_000_000_000;
+
+/// Default max value share of one token in the inventory, 100 disables the limit
+pub const DEFAULT_MAX_TOKEN_EXPOSURE_PCT: f64 = 100.0;
+
+/// Default number of blocks a traded pool is skipped for, 0 disables the cooldown
+pub const DEFAULT_POOL_COOLDOWN_BLOCKS: u64 = 3;
+
+/// Default number of blocks a component removed from the stream is remembered for, 0 disables the tombstones
+pub const DEFAULT_TOMBSTONE_BLOCKS: u64 = 100;
+
+/// Default number of blocks the chain head may be ahead of the evaluated state when a trade is executed
+pub const DEFAULT_STALE_BLOCK_TOLERANCE: u64 = 2;
+
+/// Default RPC endpoint health: consecutive failures before skipping it, slow response time, probe interval
+pub const DEFAULT_RPC_MAX_FAILURES: u32 = 3;
+pub const DEFAULT_RPC_SLOW_MS: u64 = 2_000;
+pub const DEFAULT_RPC_PROBE_INTERVAL_MS: u64 = 30_000;
+
+/// Prefix of the environment variables overriding config fields (e.g. MM__RPC_URL)
+pub const CONFIG_ENV_OVERRIDE_PREFIX: &str = "MM__";
+
+/// Config key naming the parent file a config inherits its fields from
+pub const CONFIG_EXTENDS_KEY: &str = "extends";
+
+/// Default sanity band around the reference, pools deviating more are quarantined (20%)
+pub const DEFAULT_MAX_PLAUSIBLE_SPREAD_BPS: f64 = 2_000.0;
+
+/// Default consecutive blocks in quarantine before an alert, 0 disables the alert
+pub const DEFAULT_QUARANTINE_ALERT_BLOCKS: u64 = 100;
+
+/// Minimum interval between two log lines for the same quarantined pool (1 hour)
+pub const QUARANTINE_LOG_INTERVAL_MS: u64 = 3_600_000;
+
+/// Legacy Redis key (suffixed with the config id) of the recent trades journal, and its expiry
+pub const TRADE_JOURNAL_KEY: &str = "tycho_market_maker:journal";
+pub const TRADE_JOURNAL_TTL_SECS: u64 = 3_600;
+
+/// Volatility widening constants
+pub const VOLATILITY_EWMA_LAMBDA: f64 = 0.94; // Decay of the squared returns EWMA, one sample per polled block
+pub const DEFAULT_VOL_MULTIPLIER: f64 = 0.0; // Threshold widening per bps of volatility, 0 disables the widening
+pub const DEFAULT_VOL_WIDENING_CAP_BPS: f64 = 50.0; // Maximum widening added to min_executable_spread_bps
+
+/// Adaptive threshold constants
+pub const ADAPTIVE_THRESHOLD_EWMA_LAMBDA: f64 = 0.98; // Decay of the shortfall EWMA, one sample per included trade
+pub const ADAPTIVE_THRESHOLD_MIN_TRADES: usize = 50; // Included trades before the shortfall widens the threshold
+pub const DEFAULT_ADAPTIVE_THRESHOLD_MAX_BPS: f64 = 20.0; // Maximum widening added to min_executable_spread_bps
+
+/// Default interval between two on-chain inventory refreshes, 0 refetches at every opportunity
+pub const DEFAULT_INVENTORY_REFRESH_INTERVAL_MS: u64 = 60_000;
+
+/// Default blocks after the inclusion target during which a swap may still be sent, past them it is dropped as stale
+pub const DEFAULT_DEADLINE_GRACE_BLOCKS: u64 = 2;
+
+/// Gas oracle constants
+pub const DEFAULT_GAS_ORACLE_REFRESH_MS: u64 = 10_000; // Age of the cached gas fees and ETH/USD price before a refresh, 0 disables the cache
+pub const DEFAULT_GAS_ORACLE_JITTER_MS: u64 = 2_000; // Max random delay added to each refresh
+
+/// Balance watcher constants
+pub const DEFAULT_BALANCE_WATCH_INTERVAL_MS: u64 = 60_000; // Between two comparisons of the cached balances with the chain, 0 on failed broadcasts only
+pub const DEFAULT_EXTERNAL_MOVEMENT_THRESHOLD_BPS: f64 = 100.0; // Change of the base or quote balance not explained by our executions
+
+/// Token universe cache constants
+pub const DEFAULT_TOKEN_CACHE_DIR: &str = "cache"; // Directory of the tokens.<network>.json files, relative to the working directory
+pub const DEFAULT_TOKEN_CACHE_MAX_AGE_H: u64 = 24; // Age past which the cached tokens are fetched again at startup, 0 disables the cache
+pub const DEFAULT_TOKEN_MIN_QUALITY: i32 = 100; // Minimum Tycho quality of the fetched tokens
+pub const DEFAULT_TOKEN_TRADED_DAYS: u64 = 7; // Fetched tokens traded in the last days
+pub const TYCHO_TOKENS_PAGE_SIZE: i64 = 3_000; // Tokens per page of the Tycho token list
+
+/// Pool snapshot constants
+pub const DEFAULT_SNAPSHOT_INTERVAL_MS: u64 = 60_000; // Between two writes of the target pools snapshot, 0 disables it
+pub const DEFAULT_SNAPSHOT_MAX_AGE_S: u64 = 3_600; // Age past which the snapshot is not evaluated at startup
+pub const SNAPSHOT_MONITOR_INTERVAL_MS: u64 = 12_000; // Between two monitor-only evaluations of the snapshot while the stream is not ready
+
+/// Default time given to the execution in progress to complete and publish on SIGTERM, below the 10s of docker stop
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD_MS: u64 = 8_000;
+
+/// Alerting constants
+pub const ALERT_QUEUE_CAPACITY: usize = 64; // Alerts waiting for delivery, newer ones are dropped beyond
+pub const ALERT_RATE_LIMIT_MS: u64 = 600_000; // One alert per instance and kind per 10 minutes
+pub const ALERT_SEND_TIMEOUT_MS: u64 = 10_000;
+pub const ALERT_FLUSH_TIMEOUT_MS: u64 = 3_000; // Waited at exit for the queued alerts
+
+/// Default age of the last Tycho message past which the instance is reported not ready (`/readyz`)
+pub const DEFAULT_STREAM_STALENESS_THRESHOLD_S: u64 = 120;
+
+/// Default size of the decision audit file before it is rotated (50 MB)
+pub const DEFAULT_AUDIT_LOG_MAX_BYTES: u64 = 50_000_000;
+
+/// Default stream lag (blocks behind the RPC head) past which no order is created, 0 measures without suppressing
+pub const DEFAULT_MAX_STREAM_LAG_BLOCKS: u64 = 0;
+/// Default polled blocks between two samples of the RPC head (eth_blockNumber) for the stream lag
+pub const DEFAULT_STREAM_LAG_SAMPLE_EVERY: u64 = 5;
+
+/// Crash constants
+pub const DEFAULT_MAX_CRASHES_PER_HOUR: u64 = 3; // Crashes of the process in the last hour past which an instance starts paused
+pub const DEFAULT_CRASH_LOG_PATH: &str = "crashes.jsonl"; // Crash records kept across restarts, overridden by CRASH_LOG_PATH
+pub const CRASH_PUBLISH_TIMEOUT_MS: u64 = 2_000; // Max time a panicking process spends publishing its last gasp
+pub const CRASH_LOG_RETENTION_MS: u64 = 86_400_000; // Records older than a day are dropped when a crash is recorded
+pub const CRASH_BACKTRACE_MAX_CHARS: usize = 4_000; // Backtrace kept in the crash record and the alert
+
+/// Backtest constants, defaults of the fill model and of the replayed wallet
+pub const DEFAULT_BACKTEST_FEE_BPS: f64 = 5.0; // Fee of the synthetic pools
+pub const DEFAULT_BACKTEST_LIQUIDITY: f64 = 1_000.0; // Base token on each side of a synthetic pool
+pub const DEFAULT_BACKTEST_IMPACT_BPS_PER_PCT: f64 = 100.0; // Average impact of a fill taking 1% of the liquidity (constant-product like)
+pub const DEFAULT_BACKTEST_IMPACT_EXPONENT: f64 = 1.0; // Linear impact
+pub const DEFAULT_BACKTEST_GAS_PRICE_GWEI: f64 = 1.0;
+pub const DEFAULT_BACKTEST_BASE_BALANCE: f64 = 10.0;
+pub const DEFAULT_BACKTEST_OUTPUT_DIR: &str = "backtest";
+pub const BACKTEST_PAGE_ROWS: u64 = 10_000; // Price rows read and replayed at a time
+
+/// Multi-pair constants
+pub const SHARED_STREAM_CAPACITY: usize = 64; // Updates buffered per pair before it lags and re-bootstraps from the cache
 
 /// Pool price validation constants
 pub const MAX_POOL_PRICE_DEVIATION_PCT: f64 = 5.0; // Maximum allowed price deviation from reference (5%)
@@ -0,0 +1,161 @@
+//! Crash Module
+//!
+//! A panic used to end the process with a trace buried in the logs, and the container restarted as if nothing had
+//! happened. `install` sets a panic hook that logs the message, location and backtrace of every panic. Only a panic
+//! escaping the main future of the process (`guard`) is a crash: it is appended to the crash log (CRASH_LOG_PATH, kept
+//! across restarts), and published as a `Crash` alert for each instance straight to Redis within
+//! `CRASH_PUBLISH_TIMEOUT_MS`, as the queue of `data::publisher` has no time left to drain. The panics caught on the
+//! way (a spawned task, a blocking simulation, the encoder thread) are never counted. The unwind then resumes and the
+//! restart policy takes over. On startup, an instance whose process crashed more than `max_crashes_per_hour` times in
+//! the last hour starts paused: repeated crashes mid-execution have reused nonces.
+use std::{
+    cell::RefCell,
+    fs::File,
+    future::Future,
+    io::{BufRead, BufReader, Write},
+    panic::{AssertUnwindSafe, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::moni::{AlertKind, NewAlertMessage},
+    utils::constants::{CRASH_BACKTRACE_MAX_CHARS, CRASH_LOG_RETENTION_MS, CRASH_PUBLISH_TIMEOUT_MS, DEFAULT_CRASH_LOG_PATH},
+};
+
+/// Crash log and publication of the installed hook.
+static INSTALLED: OnceLock<(PathBuf, bool)> = OnceLock::new();
+
+thread_local! {
+    /// Last panic of the thread, recorded only if it escapes the main future.
+    static PENDING: RefCell<Option<Crash>> = const { RefCell::new(None) };
+}
+
+/// One panic of the process, a line of the crash log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Crash {
+    pub timestamp_ms: u64,
+    pub identifiers: Vec<String>, // Instances of the process
+    pub message: String,
+    pub location: String,  // file:line:column of the panic
+    pub backtrace: String, // Truncated to CRASH_BACKTRACE_MAX_CHARS
+}
+
+/// Crash log of the process (CRASH_LOG_PATH, `crashes.jsonl` in the working directory by default).
+pub fn path() -> PathBuf {
+    PathBuf::from(
+        std::env::var("CRASH_LOG_PATH")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .unwrap_or_else(|| DEFAULT_CRASH_LOG_PATH.to_string()),
+    )
+}
+
+/// Crashes of the log, oldest first. Missing file or unreadable lines count as no crash.
+pub fn load(path: &Path) -> Vec<Crash> {
+    let Ok(file) = File::open(path) else {
+        return vec![];
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<Crash>(&line).ok())
+        .collect()
+}
+
+/// Appends a crash to the log, dropping the records older than `CRASH_LOG_RETENTION_MS`.
+pub fn record(path: &Path, crash: &Crash) -> std::io::Result<()> {
+    let mut crashes = load(path);
+    crashes.retain(|previous| previous.timestamp_ms + CRASH_LOG_RETENTION_MS >= crash.timestamp_ms);
+    crashes.push(crash.clone());
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    for crash in crashes.iter() {
+        writeln!(file, "{}", serde_json::to_string(crash).map_err(std::io::Error::other)?)?;
+    }
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Crashes recorded within `window_ms` before `now` (unix ms).
+pub fn recent(path: &Path, now: u64, window_ms: u64) -> u64 {
+    load(path).iter().filter(|crash| crash.timestamp_ms + window_ms >= now).count() as u64
+}
+
+/// True when the crashes of the last hour exceed the limit, 0 disabling it.
+pub fn exceeded(crashes: u64, max_crashes_per_hour: u64) -> bool {
+    max_crashes_per_hour > 0 && crashes > max_crashes_per_hour
+}
+
+/// Message of a panic, whether raised with a literal or a formatted string.
+pub fn message(info: &PanicHookInfo) -> String {
+    match (info.payload().downcast_ref::<&str>(), info.payload().downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "Box<dyn Any>".to_string(),
+    }
+}
+
+/// Installs the panic hook of the process, the crashes published as the last gasp of each instance when `publish` is set.
+pub fn install(identifiers: Vec<String>, publish: bool) {
+    let _ = INSTALLED.set((path(), publish));
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())).unwrap_or_default();
+        let crash = Crash {
+            timestamp_ms: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            identifiers: identifiers.clone(),
+            message: message(info),
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string().chars().take(CRASH_BACKTRACE_MAX_CHARS).collect(),
+        };
+        tracing::error!("💥 Panic at {}: {}\n{}", crash.location, crash.message, crash.backtrace);
+        PENDING.with(|pending| *pending.borrow_mut() = Some(crash));
+        previous(info);
+    }));
+}
+
+/// Appends a crash to the log, and publishes it for each of its instances when `publish` is set.
+fn report(path: &Path, publish: bool, crash: &Crash) {
+    if let Err(e) = record(path, crash) {
+        tracing::error!("💥 Failed to record the crash in {}: {}", path.display(), e);
+    }
+    if !publish {
+        return;
+    }
+    for identifier in crash.identifiers.iter() {
+        let alert = NewAlertMessage {
+            identifier: identifier.clone(),
+            kind: AlertKind::Crash,
+            message: format!("Panic at {}: {}\n{}", crash.location, crash.message, crash.backtrace),
+            pnl: None,
+        };
+        // Redis down: the other instances would wait as long, give up
+        if let Err(e) = crate::data::r#pub::alert_now(alert, Duration::from_millis(CRASH_PUBLISH_TIMEOUT_MS)) {
+            tracing::error!("💥 Failed to publish the crash: {}", e);
+            break;
+        }
+    }
+}
+
+/// Runs the main future of the process. A panic escaping it is reported as a crash, then the unwind resumes.
+pub async fn guard<F: Future>(future: F) -> F::Output {
+    match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(output) => output,
+        Err(payload) => {
+            // The panic unwound on this thread, the last one it raised
+            if let (Some((path, publish)), Some(crash)) = (INSTALLED.get(), PENDING.with(|pending| pending.borrow_mut().take())) {
+                report(path, *publish, &crash);
+            }
+            std::panic::resume_unwind(payload)
+        }
+    }
+}
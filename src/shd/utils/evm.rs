@@ -1,25 +1,243 @@
-use crate::types::config::{EnvConfig, MarketMakerConfig};
-use std::{str::FromStr, sync::Arc};
+use crate::types::config::{EnvConfig, FeeStrategy, MarketMakerConfig};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
 use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
     providers::{utils::Eip1559Estimation, Provider, ProviderBuilder},
-    rpc::types::TransactionReceipt,
-    signers::local::PrivateKeySigner,
+    rpc::types::{FeeHistory, Log, TransactionReceipt, TransactionRequest},
+    sol_types::{Revert, SolCall, SolError, SolValue},
+    transport::TransportError,
 };
-use alloy_primitives::{B256, U256};
+use alloy_primitives::{keccak256, Address, B256, I256, U256};
 use url;
 
+use crate::types::maker::TransferData;
 use crate::types::sol::IERC20;
+use crate::utils::constants::{DEFAULT_REVERT_ERRORS, ERROR_STRING_SIGNATURE, MULTICALL3_ADDRESS, NATIVE_ADDRESS, NULL_ADDRESS, PANIC_SIGNATURE};
+use crate::utils::multicall::{self, Read};
+use crate::utils::signer::WalletSigner;
+use tycho_common::models::token::Token;
 
 /// Creates an HTTP provider instance from RPC URL.
 pub fn create_provider(rpc: &str) -> impl Provider {
     ProviderBuilder::new().connect_http(rpc.parse().expect("Failed to parse RPC URL"))
 }
 
-/// Retrieves the latest block number from the specified RPC endpoint.
-pub async fn latest(provider: String) -> u64 {
+/// True for the sentinels of the native gas token (ETH), sent as transaction value instead of an ERC20: the zero address
+/// used by Tycho, and the 0xEeee...EEeE address of EIP-7528, in any case.
+pub fn is_native(address: &str) -> bool {
+    address.eq_ignore_ascii_case(NULL_ADDRESS) || address.eq_ignore_ascii_case(NATIVE_ADDRESS)
+}
+
+/// Health of one RPC endpoint, as seen by its `RpcPool`.
+#[derive(Debug, Clone, Default)]
+struct EndpointHealth {
+    failures: u32,               // Consecutive errors or slow responses
+    unhealthy: bool,             // Skipped by the rotation until a probe or a call succeeds
+    checked_at: Option<Instant>, // Last failure or probe, the next probe waits `probe_interval` after it
+}
+
+/// RPC endpoints of a config, round-robined while healthy.
+///
+/// An endpoint is marked unhealthy after `max_failures` consecutive errors or responses slower than `slow`,
+/// and re-probed with eth_blockNumber every `probe_interval`. The first endpoint (`rpc_url`) is the write
+/// endpoint: broadcasts and approvals go through it as long as it is healthy.
+#[derive(Debug)]
+pub struct RpcPool {
+    urls: Vec<String>,
+    health: Mutex<Vec<EndpointHealth>>,
+    cursor: AtomicUsize,
+    max_failures: u32,
+    slow: Duration,
+    probe_interval: Duration,
+}
+
+/// Pools of the process, one per endpoint list and health settings, so the call sites of a config share the health of its endpoints.
+static RPC_POOLS: OnceLock<Mutex<HashMap<String, Arc<RpcPool>>>> = OnceLock::new();
+
+impl RpcPool {
+    pub fn new(urls: Vec<String>, max_failures: u32, slow: Duration, probe_interval: Duration) -> Self {
+        let health = Mutex::new(vec![EndpointHealth::default(); urls.len()]);
+        Self {
+            urls,
+            health,
+            cursor: AtomicUsize::new(0),
+            max_failures: max_failures.max(1),
+            slow,
+            probe_interval,
+        }
+    }
+
+    /// Shared pool of a config, created (with its probe task, inside a runtime) on first use.
+    pub fn of(config: &MarketMakerConfig) -> Arc<RpcPool> {
+        let urls = config.rpc_endpoints();
+        let key = format!("{}|{}|{}|{}", urls.join(","), config.rpc_max_failures, config.rpc_slow_ms, config.rpc_probe_interval_ms);
+        let mut pools = RPC_POOLS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pool) = pools.get(&key) {
+            return pool.clone();
+        }
+        let pool = Arc::new(RpcPool::new(
+            urls,
+            config.rpc_max_failures,
+            Duration::from_millis(config.rpc_slow_ms),
+            Duration::from_millis(config.rpc_probe_interval_ms),
+        ));
+        if pool.urls.len() > 1 && tokio::runtime::Handle::try_current().is_ok() {
+            let probed = pool.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(probed.probe_interval.max(Duration::from_secs(1)));
+                loop {
+                    ticker.tick().await;
+                    probed.probe().await;
+                }
+            });
+        }
+        pools.insert(key, pool.clone());
+        pool
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, Vec<EndpointHealth>> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_healthy(&self, url: &str) -> bool {
+        self.urls.iter().position(|u| u == url).is_some_and(|x| !self.health()[x].unhealthy)
+    }
+
+    /// Endpoints to try for a call: the healthy ones in round-robin order, then the unhealthy ones as a last resort.
+    pub fn candidates(&self) -> Vec<String> {
+        let n = self.urls.len();
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed);
+        let health = self.health();
+        let rotated = (0..n).map(|i| (start + i) % n).collect::<Vec<usize>>();
+        let healthy = rotated.iter().filter(|x| !health[**x].unhealthy);
+        let unhealthy = rotated.iter().filter(|x| health[**x].unhealthy);
+        healthy.chain(unhealthy).map(|x| self.urls[*x].clone()).collect()
+    }
+
+    /// Next healthy endpoint for a read, the first endpoint if none is healthy.
+    pub fn read_url(&self) -> String {
+        self.candidates().into_iter().next().unwrap_or_default()
+    }
+
+    /// Write endpoint (the first one) while healthy, the next healthy endpoint otherwise.
+    pub fn write_url(&self) -> String {
+        match self.urls.first() {
+            Some(primary) if self.is_healthy(primary) => primary.clone(),
+            _ => self.read_url(),
+        }
+    }
+
+    /// Records the outcome of a call. Errors and slow responses count as failures, a fast success resets the endpoint.
+    pub fn report(&self, url: &str, elapsed: Duration, ok: bool) {
+        let Some(x) = self.urls.iter().position(|u| u == url) else {
+            return;
+        };
+        let mut health = self.health();
+        let endpoint = &mut health[x];
+        if ok && elapsed <= self.slow {
+            if endpoint.unhealthy {
+                tracing::info!("🩺 RPC endpoint {} recovered", url);
+            }
+            *endpoint = EndpointHealth::default();
+            return;
+        }
+        endpoint.failures += 1;
+        endpoint.checked_at = Some(Instant::now());
+        if !endpoint.unhealthy && endpoint.failures >= self.max_failures {
+            endpoint.unhealthy = true;
+            let reason = if ok { format!("slower than {} ms", self.slow.as_millis()) } else { "failing".to_string() };
+            tracing::warn!("🔌 RPC endpoint {} marked unhealthy after {} consecutive failures ({})", url, endpoint.failures, reason);
+        }
+    }
+
+    /// Unhealthy endpoints due for a probe at `now`, marked as checked.
+    pub fn due_probes(&self, now: Instant) -> Vec<String> {
+        let mut health = self.health();
+        let mut due = vec![];
+        for (x, endpoint) in health.iter_mut().enumerate() {
+            if endpoint.unhealthy && endpoint.checked_at.is_none_or(|at| now.duration_since(at) >= self.probe_interval) {
+                endpoint.checked_at = Some(now);
+                due.push(self.urls[x].clone());
+            }
+        }
+        due
+    }
+
+    /// Probes the unhealthy endpoints with eth_blockNumber.
+    pub async fn probe(&self) {
+        for url in self.due_probes(Instant::now()) {
+            let start = Instant::now();
+            let ok = matches!(tokio::time::timeout(self.slow, create_provider(&url).get_block_number()).await, Ok(Ok(_)));
+            self.report(&url, start.elapsed(), ok);
+        }
+    }
+
+    /// Runs `f` on the candidate endpoints until one succeeds, reporting every attempt.
+    pub async fn call<T, E, F, Fut>(&self, f: F) -> Result<T, String>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let mut last = "No RPC endpoint configured".to_string();
+        for url in self.candidates() {
+            let start = Instant::now();
+            match f(url.clone()).await {
+                Ok(value) => {
+                    self.report(&url, start.elapsed(), true);
+                    return Ok(value);
+                }
+                Err(e) => {
+                    self.report(&url, start.elapsed(), false);
+                    tracing::warn!("RPC call on {} failed: {}", url, e);
+                    last = e.to_string();
+                }
+            }
+        }
+        Err(last)
+    }
+
+    /// Runs `f` once on the write endpoint, reporting its outcome. Not timed: a write waits for the inclusion of its
+    /// transactions, and is never retried on another endpoint.
+    pub async fn write<T, E, F, Fut>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let url = self.write_url();
+        let result = f(url.clone()).await;
+        self.report(&url, Duration::ZERO, result.is_ok());
+        result
+    }
+}
+
+/// Retrieves the latest block number through the endpoints of a pool, 0 if none answers.
+pub async fn latest(pool: &RpcPool) -> u64 {
+    let number = pool.call(|url| async move {
+        let provider = create_provider(&url);
+        provider.get_block_number().await
+    });
+    number.await.unwrap_or_default()
+}
+
+/// Retrieves the chain id (eth_chainId) of the specified RPC endpoint.
+pub async fn chain_id(provider: String) -> Result<u64, String> {
     let provider = create_provider(&provider);
-    provider.get_block_number().await.unwrap_or_default()
+    provider.get_chain_id().await.map_err(|e| format!("eth_chainId failed: {}", e))
 }
 
 /// Retrieves the current gas price from the specified RPC endpoint.
@@ -28,11 +246,39 @@ pub async fn gas_price(provider: String) -> u128 {
     provider.get_gas_price().await.unwrap_or_default()
 }
 
-/// Estimates EIP-1559 gas fees for the network.
-pub async fn eip1559_fees(provider_url: String) -> Result<Eip1559Estimation, String> {
+/// Fees of the next block from a fee history requested at `strategy.percentile`: the median of the priority fees
+/// at that percentile in each block (empty blocks skipped), floored at `min_priority_gwei`, on top of the next base
+/// fee times `base_multiplier`.
+pub fn fees_from_history(history: &FeeHistory, strategy: &FeeStrategy) -> Result<Eip1559Estimation, String> {
+    let base_fee = history.next_block_base_fee().ok_or_else(|| "Fee history without base fee".to_string())?;
+    let mut rewards = match &history.reward {
+        Some(reward) => reward
+            .iter()
+            .zip(history.gas_used_ratio.iter())
+            .filter(|(_, used)| **used > 0.)
+            .filter_map(|(fees, _)| fees.first().copied())
+            .collect::<Vec<u128>>(),
+        None => vec![],
+    };
+    rewards.sort_unstable();
+    let median = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+    let priority = median.max((strategy.min_priority_gwei * 1e9) as u128);
+    Ok(Eip1559Estimation {
+        max_fee_per_gas: (base_fee as f64 * strategy.base_multiplier).ceil() as u128 + priority,
+        max_priority_fee_per_gas: priority,
+    })
+}
+
+/// Estimates EIP-1559 gas fees for the network with `strategy`, from `eth_feeHistory` over its recent blocks.
+pub async fn eip1559_fees(provider_url: String, strategy: FeeStrategy) -> Result<Eip1559Estimation, String> {
     let provider = create_provider(&provider_url);
+    let estimation = provider
+        .get_fee_history(strategy.blocks, BlockNumberOrTag::Latest, &[strategy.percentile])
+        .await
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|history| fees_from_history(&history, &strategy));
 
-    match provider.estimate_eip1559_fees().await {
+    match estimation {
         Ok(fees) => Ok(fees),
         Err(e) => {
             // Fallback: use legacy gas_price when eth_feeHistory isn't supported
@@ -51,28 +297,108 @@ pub async fn eip1559_fees(provider_url: String) -> Result<Eip1559Estimation, Str
     }
 }
 
-/// Gets token balances for a specific owner address across multiple tokens.
-pub async fn balances(provider: &impl Provider, owner: String, tokens: Vec<String>) -> Result<Vec<u128>, String> {
-    let mut balances = vec![];
-    let client = Arc::new(provider);
+/// Gas fees, gas price and ETH/USD price, read together and cached by a `GasOracle`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasQuote {
+    pub fees: Eip1559Estimation,
+    pub gas_price: u128, // eth_gasPrice, replaced by the websocket head base fee while it is fresh
+    pub eth_to_usd: f64,
+}
 
-    for token in tokens {
-        let contract = IERC20::new(token.parse().unwrap(), client.clone());
+/// Gas quote of a network, refreshed every `refresh` plus a random share of `jitter`, so the bots sharing an RPC key
+/// do not all refresh on the same block. A cached quote is never older than `refresh + jitter`.
+#[derive(Debug, Default)]
+pub struct GasOracle {
+    refresh: Duration, // Zero disables the cache
+    jitter: Duration,
+    entry: RwLock<Option<(GasQuote, Instant, Instant)>>, // Quote, time it was fetched, time it expires
+}
 
-        match contract.balanceOf(owner.parse().unwrap()).call().await {
-            Ok(res) => {
-                // Alloy 1.0: balanceOf returns U256 directly, not wrapped in struct
-                let balance = res.to_string().parse::<u128>().unwrap_or_default();
-                balances.push(balance);
-            }
-            Err(e) => {
-                tracing::error!("Failed to get balance for {}: {:?}", token, e);
-                balances.push(0);
-            }
+/// Oracles of the process, one per endpoint list, price feed and fee strategy, shared by the pairs of a network.
+static GAS_ORACLES: OnceLock<Mutex<HashMap<String, Arc<GasOracle>>>> = OnceLock::new();
+
+impl GasOracle {
+    pub fn new(refresh: Duration, jitter: Duration) -> Self {
+        Self {
+            refresh,
+            jitter,
+            entry: RwLock::new(None),
         }
     }
 
-    Ok(balances)
+    /// Shared oracle of a config, created on first use.
+    pub fn of(config: &MarketMakerConfig) -> Arc<GasOracle> {
+        let key = format!(
+            "{}|{}|{:?}|{}|{}",
+            config.rpc_endpoints().join(","),
+            config.gas_token_chainlink_price_feed,
+            config.fee_strategy(),
+            config.gas_oracle_refresh_ms,
+            config.gas_oracle_jitter_ms
+        );
+        let mut oracles = GAS_ORACLES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner());
+        oracles
+            .entry(key)
+            .or_insert_with(|| Arc::new(GasOracle::new(Duration::from_millis(config.gas_oracle_refresh_ms), Duration::from_millis(config.gas_oracle_jitter_ms))))
+            .clone()
+    }
+
+    /// Cached quote and its age at `now`, None once expired or when nothing is cached.
+    pub fn fresh(&self, now: Instant) -> Option<(GasQuote, Duration)> {
+        let entry = self.entry.read().unwrap_or_else(|e| e.into_inner());
+        let (quote, fetched_at, expires_at) = entry.as_ref()?;
+        (now < *expires_at).then(|| (quote.clone(), now.saturating_duration_since(*fetched_at)))
+    }
+
+    /// Stores a quote fetched at `now`, expiring after the refresh interval plus `jitter` (in [0, 1)) of the max jitter.
+    pub fn store(&self, quote: GasQuote, now: Instant, jitter: f64) {
+        if self.refresh.is_zero() {
+            return;
+        }
+        let expires_at = now + self.refresh + self.jitter.mul_f64(jitter.clamp(0., 1.));
+        *self.entry.write().unwrap_or_else(|e| e.into_inner()) = Some((quote, now, expires_at));
+    }
+}
+
+/// Pseudo-random share in [0, 1) of the refresh jitter, from the sub-second part of the clock.
+pub fn jitter() -> f64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().subsec_nanos() as f64 / 1e9
+}
+
+/// Gets token balances for a specific owner address across multiple tokens, in one call to the canonical Multicall3.
+///
+/// A balance that cannot be read is 0.
+pub async fn balances(provider: &impl Provider, owner: String, tokens: Vec<String>) -> Result<Vec<u128>, String> {
+    let owner = owner.parse::<Address>().map_err(|e| format!("Invalid owner {}: {}", owner, e))?;
+    let mut reads = vec![];
+    for token in tokens.iter() {
+        let token = token.parse::<Address>().map_err(|e| format!("Invalid token {}: {}", token, e))?;
+        reads.push(Read::Balance { token, owner });
+    }
+    Ok(multicall::read(provider, MULTICALL3_ADDRESS, &reads).await.into_iter().map(Option::unwrap_or_default).collect())
+}
+
+/// Gets the allowances of `spender` on each token for the config wallet, in one call to its Multicall3.
+pub async fn allowances(config: &MarketMakerConfig, spender: String, tokens: Vec<String>) -> Result<Vec<u128>, String> {
+    let owner = config.wallet_public_key.parse::<Address>().map_err(|e| format!("Invalid wallet {}: {}", config.wallet_public_key, e))?;
+    let spender = spender.parse::<Address>().map_err(|e| format!("Invalid spender {}: {}", spender, e))?;
+    let mut reads = vec![];
+    for token in tokens.iter() {
+        let token = token.parse::<Address>().map_err(|e| format!("Invalid token {}: {}", token, e))?;
+        reads.push(Read::Allowance { token, owner, spender });
+    }
+    let (reads, tokens) = (&reads, &tokens);
+    RpcPool::of(config)
+        .call(|url| async move {
+            let provider = create_provider(&url);
+            let results = multicall::read(&provider, &config.multicall_address, reads).await;
+            results
+                .into_iter()
+                .zip(tokens.iter())
+                .map(|(allowance, token)| allowance.ok_or_else(|| format!("Failed to get allowance for {}", token)))
+                .collect::<Result<Vec<u128>, String>>()
+        })
+        .await
 }
 
 /// Gets the allowance amount for a specific token between owner and spender.
@@ -92,21 +418,100 @@ pub async fn allowance(rpc: String, owner: String, spender: String, token: Strin
     }
 }
 
+/// Symbol and decimals of an ERC20, as read on-chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Erc20Metadata {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+impl Erc20Metadata {
+    /// Checks the Tycho token data against the on-chain values: same decimals, same symbol (case aside).
+    pub fn matches(&self, token: &Token) -> Result<(), String> {
+        if self.decimals != token.decimals {
+            return Err(format!(
+                "Decimals mismatch for {} ({}): {} from Tycho, {} on-chain",
+                token.symbol, token.address, token.decimals, self.decimals
+            ));
+        }
+        if !self.symbol.trim().eq_ignore_ascii_case(token.symbol.trim()) {
+            return Err(format!("Symbol mismatch for {}: {} from Tycho, {} on-chain", token.address, token.symbol, self.symbol));
+        }
+        Ok(())
+    }
+}
+
+/// On-chain metadata by (chain id, lowercase token address), read once per process.
+static ERC20_METADATA: OnceLock<Mutex<HashMap<(u64, String), Erc20Metadata>>> = OnceLock::new();
+
+/// Decodes the return data of `symbol()`: an ABI string, or a bytes32 padded with zeros for the older tokens (e.g. MKR).
+pub fn decode_symbol(data: &[u8]) -> Result<String, String> {
+    if let Ok(symbol) = String::abi_decode(data) {
+        return Ok(symbol);
+    }
+    if data.len() == 32 {
+        let end = data.iter().position(|b| *b == 0).unwrap_or(32);
+        return String::from_utf8(data[..end].to_vec()).map_err(|e| format!("Invalid bytes32 symbol 0x{}: {}", hex::encode(data), e));
+    }
+    Err(format!("Invalid symbol return data 0x{}", hex::encode(data)))
+}
+
+/// Decodes the return data of `decimals()`, a uint8 word.
+pub fn decode_decimals(data: &[u8]) -> Result<u32, String> {
+    let decimals = U256::abi_decode(data).map_err(|e| format!("Invalid decimals return data 0x{}: {}", hex::encode(data), e))?;
+    if decimals > U256::from(u8::MAX) {
+        return Err(format!("Decimals {} out of the uint8 range", decimals));
+    }
+    Ok(decimals.to::<u32>())
+}
+
+/// Reads `symbol()` and `decimals()` of a token on the RPC pool of the config, cached after the first read.
+pub async fn erc20_metadata(config: &MarketMakerConfig, token: &str) -> Result<Erc20Metadata, String> {
+    let key = (config.chain_id, token.to_lowercase());
+    let cache = ERC20_METADATA.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(metadata) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(metadata.clone());
+    }
+    let address = token.parse::<Address>().map_err(|e| format!("Invalid token {}: {}", token, e))?;
+    let pool = RpcPool::of(config);
+    let call = |data: Vec<u8>| {
+        pool.call(move |url| {
+            let request = TransactionRequest::default().to(address).input(data.clone().into());
+            async move { create_provider(&url).call(request).await }
+        })
+    };
+    let symbol = decode_symbol(&call(IERC20::symbolCall {}.abi_encode()).await.map_err(|e| format!("Failed to read the symbol of {}: {}", token, e))?)?;
+    let decimals = decode_decimals(
+        &call(IERC20::decimalsCall {}.abi_encode())
+            .await
+            .map_err(|e| format!("Failed to read the decimals of {}: {}", token, e))?,
+    )?;
+    let metadata = Erc20Metadata { symbol, decimals };
+    cache.lock().unwrap_or_else(|e| e.into_inner()).insert(key, metadata.clone());
+    Ok(metadata)
+}
+
 /// Approves a spender to spend a specific amount of tokens.
 pub async fn approve(mmc: MarketMakerConfig, env: EnvConfig, spender: String, token: String, amount: u128) -> Result<TransactionReceipt, String> {
-    let rpc = mmc.rpc_url.parse::<url::Url>().unwrap().clone();
-    let pk = env.wallet_private_key.clone();
-    let wallet = PrivateKeySigner::from_bytes(&B256::from_str(&pk).expect("Failed to convert swapper pk to B256")).expect("Failed to private key signer");
-    let signer = alloy::network::EthereumWallet::from(wallet.clone());
-    let provider = ProviderBuilder::new().with_chain_id(mmc.chain_id).wallet(signer.clone()).connect_http(rpc.clone());
+    let wallet = WalletSigner::from_env(&env)?;
+    RpcPool::of(&mmc).write(|endpoint| approve_on(endpoint, &mmc, wallet, spender, token, amount)).await
+}
+
+/// Sends the approval through `endpoint` and waits for its receipt.
+async fn approve_on(endpoint: String, mmc: &MarketMakerConfig, wallet: WalletSigner, spender: String, token: String, amount: u128) -> Result<TransactionReceipt, String> {
+    let rpc = endpoint.parse::<url::Url>().map_err(|e| format!("Invalid RPC URL {}: {}", endpoint, e))?;
+    let provider = ProviderBuilder::new().with_chain_id(mmc.chain_id).wallet(wallet.clone()).connect_http(rpc.clone());
     let client = Arc::new(provider);
     let contract = IERC20::new(token.parse().unwrap(), client.clone());
     // Alloy 1.0: symbol() returns String directly, not wrapped
-    let symbol = contract.symbol().call().await.expect("Failed to get symbol");
+    let symbol = contract.symbol().call().await.map_err(|e| format!("Failed to get the symbol of {}: {:?}", token, e))?;
     let amount = U256::from(amount);
     tracing::info!("Approval: {} at address {} for spender {} and owner {}", symbol, token, spender, wallet.address().to_string());
-    let native_gas_price = crate::utils::evm::eip1559_fees(mmc.rpc_url).await.expect("Failed to get native gas price");
-    let nonce = client.get_transaction_count(wallet.address()).await.expect("Failed to get nonce");
+    let native_gas_price = crate::utils::evm::eip1559_fees(endpoint, mmc.fee_strategy()).await?;
+    let nonce = client
+        .get_transaction_count(wallet.address())
+        .await
+        .map_err(|e| format!("Failed to get the nonce of {}: {:?}", wallet.address(), e))?;
     let call = contract
         .approve(spender.parse().unwrap(), amount)
         .nonce(nonce)
@@ -137,25 +542,41 @@ pub async fn approve(mmc: MarketMakerConfig, env: EnvConfig, spender: String, to
 
 /// Fetches wallet state including token balances and nonce.
 pub async fn fetch_wallet_state(config: MarketMakerConfig) {
-    let provider = create_provider(&config.rpc_url);
+    let pool = RpcPool::of(&config);
     let tokens = vec![config.base_token_address.clone(), config.quote_token_address.clone()];
-    if let Ok(balances) = balances(&provider, config.wallet_public_key.clone(), tokens.clone()).await {
-        tracing::debug!("Balances of sender {}: {:?}", config.wallet_public_key.clone(), balances);
-    } else {
-        tracing::error!("Failed to get balances of sender");
+    let state = pool.call(|url| {
+        let (owner, tokens) = (config.wallet_public_key.clone(), tokens.clone());
+        async move {
+            let provider = create_provider(&url);
+            let wallet = owner.parse::<Address>().map_err(|e| format!("Invalid wallet {}: {}", owner, e))?;
+            let balances = balances(&provider, owner, tokens).await?;
+            let nonce = provider.get_transaction_count(wallet).await.map_err(|e| format!("Failed to get the nonce of {}: {:?}", wallet, e))?;
+            Ok::<_, String>((balances, nonce))
+        }
+    });
+    match state.await {
+        Ok((balances, nonce)) => tracing::debug!("Balances of sender {}: {:?}, nonce {}", config.wallet_public_key, balances, nonce),
+        Err(e) => tracing::error!("Failed to get the state of sender {}: {}", config.wallet_public_key, e),
     }
-    let nonce = provider.get_transaction_count(config.wallet_public_key.to_string().parse().unwrap()).await.unwrap();
-    tracing::debug!("Nonce of sender {}: {}", config.wallet_public_key.clone(), nonce);
+}
+
+/// Receipt of a transaction through the endpoints of a pool, None while not mined.
+async fn receipt(pool: &RpcPool, hash: &str) -> Result<Option<TransactionReceipt>, String> {
+    let tx = hash.parse::<B256>().map_err(|e| format!("Invalid transaction hash {}: {}", hash, e))?;
+    pool.call(|url| async move {
+        let provider = create_provider(&url);
+        provider.get_transaction_receipt(tx).await
+    })
+    .await
 }
 
 /// Fetches the receipt for a specific transaction hash.
-pub async fn fetch_receipt(rpc: String, hash: String) -> Result<TransactionReceipt, String> {
+pub async fn fetch_receipt(pool: &RpcPool, hash: String) -> Result<TransactionReceipt, String> {
     // If it doesn't contain 0x, return error
     if !hash.starts_with("0x") {
         return Err(format!("Invalid transaction hash: {}", hash));
     }
-    let provider = create_provider(&rpc);
-    match provider.get_transaction_receipt(hash.parse().unwrap()).await {
+    match receipt(pool, &hash).await {
         Ok(receipt) => match receipt {
             Some(receipt) => Ok(receipt),
             None => Err(format!("No receipt found for transaction {}", hash)),
@@ -171,16 +592,15 @@ pub async fn fetch_receipt(rpc: String, hash: String) -> Result<TransactionRecei
 ///
 /// Useful for Flashbots bundles and other async transaction submissions
 /// where the transaction may not be immediately available on-chain.
-pub async fn fetch_receipt_with_retry(rpc: String, hash: String, max_attempts: u32, delay_ms: u64) -> Result<TransactionReceipt, String> {
+pub async fn fetch_receipt_with_retry(pool: &RpcPool, hash: String, max_attempts: u32, delay_ms: u64) -> Result<TransactionReceipt, String> {
     if !hash.starts_with("0x") {
         return Err(format!("Invalid transaction hash: {}", hash));
     }
 
-    let provider = create_provider(&rpc);
     let mut last_error = String::new();
 
     for attempt in 1..=max_attempts {
-        match provider.get_transaction_receipt(hash.parse().unwrap()).await {
+        match receipt(pool, &hash).await {
             Ok(Some(receipt)) => {
                 tracing::info!("Receipt found for {} on attempt {}/{}", hash, attempt, max_attempts);
                 return Ok(receipt);
@@ -190,8 +610,8 @@ pub async fn fetch_receipt_with_retry(rpc: String, hash: String, max_attempts: u
                 tracing::debug!("Attempt {}/{}: Receipt not yet available for {}", attempt, max_attempts, hash);
             }
             Err(e) => {
-                last_error = format!("RPC error: {:?}", e);
-                tracing::debug!("Attempt {}/{}: RPC error for {}: {:?}", attempt, max_attempts, hash, e);
+                last_error = format!("RPC error: {}", e);
+                tracing::debug!("Attempt {}/{}: RPC error for {}: {}", attempt, max_attempts, hash, e);
             }
         }
 
@@ -203,3 +623,198 @@ pub async fn fetch_receipt_with_retry(rpc: String, hash: String, max_attempts: u
     tracing::warn!("Failed to fetch receipt for {} after {} attempts: {}", hash, max_attempts, last_error);
     Err(format!("Failed to fetch receipt after {} attempts: {}", max_attempts, last_error))
 }
+
+/// Topic of the ERC-20 `Transfer(address indexed from, address indexed to, uint256 value)` event.
+pub fn transfer_topic() -> B256 {
+    keccak256("Transfer(address,address,uint256)")
+}
+
+/// ERC-20 transfers of receipt logs, in order. Other events, and ERC-721 transfers (4 topics), are skipped.
+pub fn transfers(logs: &[Log]) -> Vec<TransferData> {
+    let topic = transfer_topic();
+    logs.iter()
+        .filter_map(|log| {
+            let topics = log.inner.data.topics();
+            let data = &log.inner.data.data;
+            if topics.len() != 3 || topics[0] != topic || data.len() < 32 {
+                return None;
+            }
+            Some(TransferData {
+                token: log.inner.address.to_string(),
+                from: Address::from_word(topics[1]).to_string(),
+                to: Address::from_word(topics[2]).to_string(),
+                amount: u128::try_from(U256::from_be_slice(&data[..32])).unwrap_or(u128::MAX),
+            })
+        })
+        .collect()
+}
+
+/// Total amount of `token` (raw units) sent by `from` in the transfers.
+pub fn sent(transfers: &[TransferData], token: &str, from: &str) -> u128 {
+    transfers
+        .iter()
+        .filter(|transfer| transfer.token.eq_ignore_ascii_case(token) && transfer.from.eq_ignore_ascii_case(from))
+        .fold(0u128, |total, transfer| total.saturating_add(transfer.amount))
+}
+
+/// Total amount of `token` (raw units) received by `to` in the transfers.
+pub fn received(transfers: &[TransferData], token: &str, to: &str) -> u128 {
+    transfers
+        .iter()
+        .filter(|transfer| transfer.token.eq_ignore_ascii_case(token) && transfer.to.eq_ignore_ascii_case(to))
+        .fold(0u128, |total, transfer| total.saturating_add(transfer.amount))
+}
+
+/// Revert data of a failed call, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedRevert {
+    Empty,                                           // No data: out of gas, bare `revert()` or `require` without a message
+    Reason(String),                                  // Error(string)
+    Panic { code: U256, description: &'static str }, // Panic(uint256), see `panic_description`
+    Custom { name: String, args: Vec<String> },      // Error of the dictionary, static arguments decoded
+    Unknown(String),                                 // Hex data of an unknown selector or a malformed payload
+}
+
+impl fmt::Display for DecodedRevert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedRevert::Empty => write!(f, "reverted without data"),
+            DecodedRevert::Reason(reason) => write!(f, "reverted: {}", reason),
+            DecodedRevert::Panic { code, description } => write!(f, "panic {:#x}: {}", code, description),
+            DecodedRevert::Custom { name, args } => write!(f, "{}({})", name, args.join(", ")),
+            DecodedRevert::Unknown(data) => write!(f, "unknown revert data {}", data),
+        }
+    }
+}
+
+/// Custom errors by selector, seeded with `DEFAULT_REVERT_ERRORS`.
+static REVERT_ERRORS: OnceLock<RwLock<HashMap<[u8; 4], String>>> = OnceLock::new();
+
+fn revert_errors() -> &'static RwLock<HashMap<[u8; 4], String>> {
+    REVERT_ERRORS.get_or_init(|| {
+        let mut errors = HashMap::new();
+        for signature in DEFAULT_REVERT_ERRORS.iter() {
+            errors.entry(selector(signature)).or_insert_with(|| signature.to_string());
+        }
+        RwLock::new(errors)
+    })
+}
+
+/// 4-byte selector of a function or error signature, e.g. "Error(string)".
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Adds custom error signatures (e.g. "MyError(uint256,address)") to the dictionary of `decode_revert`.
+/// A selector already known keeps its first signature.
+pub fn register_errors(signatures: &[String]) {
+    let mut errors = revert_errors().write().unwrap_or_else(|e| e.into_inner());
+    for signature in signatures.iter() {
+        errors.entry(selector(signature)).or_insert_with(|| signature.clone());
+    }
+}
+
+/// Description of a Solidity panic code.
+pub fn panic_description(code: U256) -> &'static str {
+    match u64::try_from(code).unwrap_or(u64::MAX) {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to a zero-initialized function",
+        _ => "unknown panic code",
+    }
+}
+
+/// Arguments of a custom error, one word each for static types. Dynamic or nested arguments are left as hex.
+fn error_args(signature: &str, data: &[u8]) -> Vec<String> {
+    let inner = signature.split_once('(').map(|(_, rest)| rest.strip_suffix(')').unwrap_or(rest)).unwrap_or_default();
+    if inner.is_empty() {
+        return vec![];
+    }
+    let types = inner.split(',').map(str::trim).collect::<Vec<&str>>();
+    let dynamic = |t: &&str| *t == "string" || *t == "bytes" || t.contains('[') || t.contains('(');
+    if types.iter().any(dynamic) || data.len() < types.len() * 32 {
+        return vec![format!("0x{}", hex::encode(data))];
+    }
+    types
+        .iter()
+        .zip(data.chunks(32))
+        .map(|(t, word)| {
+            let word = B256::from_slice(word);
+            if t.starts_with("uint") {
+                U256::from_be_bytes(word.0).to_string()
+            } else if t.starts_with("int") {
+                I256::from_raw(U256::from_be_bytes(word.0)).to_string()
+            } else if *t == "address" {
+                Address::from_word(word).to_string()
+            } else if *t == "bool" {
+                (!word.is_zero()).to_string()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Decodes revert data: Error(string), Panic(uint256), or a custom error of the dictionary (Tycho router, Permit2,
+/// ERC20 and `revert_errors`).
+pub fn decode_revert(data: &[u8]) -> DecodedRevert {
+    let unknown = || DecodedRevert::Unknown(format!("0x{}", hex::encode(data)));
+    if data.is_empty() {
+        return DecodedRevert::Empty;
+    }
+    if data.len() < 4 {
+        return unknown();
+    }
+    let head = [data[0], data[1], data[2], data[3]];
+    let body = &data[4..];
+    if head == selector(ERROR_STRING_SIGNATURE) {
+        return Revert::abi_decode(data).map(|revert| DecodedRevert::Reason(revert.reason)).unwrap_or_else(|_| unknown());
+    }
+    if head == selector(PANIC_SIGNATURE) {
+        if body.len() < 32 {
+            return unknown();
+        }
+        let code = U256::from_be_slice(&body[..32]);
+        return DecodedRevert::Panic {
+            code,
+            description: panic_description(code),
+        };
+    }
+    let signature = revert_errors().read().unwrap_or_else(|e| e.into_inner()).get(&head).cloned();
+    match signature {
+        Some(signature) => DecodedRevert::Custom {
+            name: signature.split('(').next().unwrap_or_default().to_string(),
+            args: error_args(&signature, body),
+        },
+        None => unknown(),
+    }
+}
+
+/// Reason of a failed call: its RPC message, followed by the decoded revert data if any.
+pub fn revert_reason(message: &str, data: &[u8]) -> String {
+    if data.is_empty() {
+        return message.to_string();
+    }
+    format!("{}: {}", message, decode_revert(data))
+}
+
+/// Decoded revert data carried by an RPC error (eth_call, eth_simulateV1, eth_estimateGas), if any.
+pub fn rpc_revert(error: &TransportError) -> Option<DecodedRevert> {
+    error.as_error_resp().and_then(|payload| payload.as_revert_data()).map(|data| decode_revert(&data))
+}
+
+/// Reason of a reverted transaction, replayed with eth_call on the state of its block. None if the replay succeeds.
+pub async fn replay_revert(provider: &impl Provider, request: TransactionRequest, block: u64) -> Option<String> {
+    match provider.call(request).block(BlockId::number(block)).await {
+        Ok(_) => None,
+        Err(e) => Some(rpc_revert(&e).map(|revert| revert.to_string()).unwrap_or_else(|| e.to_string())),
+    }
+}
@@ -0,0 +1,130 @@
+//! Block Head Subscription Module
+//!
+//! Keeps the latest block header (number, base fee, timestamp) of a `newHeads` websocket subscription on
+//! `ws_rpc_url`, so the market context and the stream lag read the chain head without polling it. A background
+//! task reconnects with backoff, and a head older than `ws_max_head_age_s`, or kept while the socket is down,
+//! is refused: the callers then fall back to HTTP polling.
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::providers::{Provider, ProviderBuilder, WsConnect};
+use futures::StreamExt;
+
+use crate::{
+    types::config::MarketMakerConfig,
+    utils::{constants::WS_MAX_BACKOFF_S, misc::redact},
+};
+
+/// Latest block header received on the subscription.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockHead {
+    pub number: u64,
+    pub base_fee: Option<u64>, // None on a chain without EIP-1559
+    pub timestamp: u64,
+    pub received_at: Instant,
+}
+
+impl BlockHead {
+    /// Gas price paid in the next block with `priority_fee`, as eth_gasPrice would answer it.
+    pub fn gas_price(&self, priority_fee: u128) -> Option<u128> {
+        self.base_fee.map(|base_fee| base_fee as u128 + priority_fee)
+    }
+}
+
+/// Latest head of one websocket endpoint, shared by the pairs subscribed to it.
+#[derive(Debug, Default)]
+pub struct HeadCache {
+    head: RwLock<Option<BlockHead>>,
+    connected: AtomicBool,
+}
+
+static HEAD_CACHES: OnceLock<Mutex<HashMap<String, Arc<HeadCache>>>> = OnceLock::new();
+
+impl HeadCache {
+    /// Shared cache of the config websocket endpoint, with its subscription task spawned (inside a runtime) on first use.
+    /// None without `ws_rpc_url`.
+    pub fn of(config: &MarketMakerConfig) -> Option<Arc<HeadCache>> {
+        let url = config.ws_rpc_url.clone().filter(|url| !url.is_empty())?;
+        let mut caches = HEAD_CACHES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cache) = caches.get(&url) {
+            return Some(cache.clone());
+        }
+        let cache = Arc::new(HeadCache::default());
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::spawn(subscribe(url.clone(), cache.clone()));
+        }
+        caches.insert(url, cache.clone());
+        Some(cache)
+    }
+
+    /// Records a head received on a live socket. An older block (reorg or duplicate notification) does not replace a newer one.
+    pub fn update(&self, head: BlockHead) {
+        self.connected.store(true, Ordering::Relaxed);
+        let mut latest = self.head.write().unwrap_or_else(|e| e.into_inner());
+        if latest.is_none_or(|latest| head.number >= latest.number) {
+            *latest = Some(head);
+        }
+    }
+
+    /// Marks the socket as down, the cached head is no longer served until the next one.
+    pub fn disconnected(&self) {
+        self.connected.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Latest head while the socket is up and the head younger than `max_age`, None to fall back to polling.
+    pub fn fresh(&self, max_age: Duration) -> Option<BlockHead> {
+        if !self.is_connected() {
+            return None;
+        }
+        let head = (*self.head.read().unwrap_or_else(|e| e.into_inner()))?;
+        (head.received_at.elapsed() <= max_age).then_some(head)
+    }
+}
+
+/// Delay before a reconnection after `attempt` consecutive failures: 1s, doubling up to `WS_MAX_BACKOFF_S`.
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.min(16)).min(Duration::from_secs(WS_MAX_BACKOFF_S))
+}
+
+/// Subscribes to the new heads of `url` forever, reconnecting with backoff when the socket fails or closes. The URL is
+/// logged redacted, its path usually holding the API key.
+async fn subscribe(url: String, cache: Arc<HeadCache>) {
+    let endpoint = redact(&url);
+    let mut attempt = 0;
+    loop {
+        match ProviderBuilder::new().connect_ws(WsConnect::new(url.clone())).await {
+            Ok(provider) => match provider.subscribe_blocks().await {
+                Ok(subscription) => {
+                    tracing::info!("🔌 Block head subscription up on {}", endpoint);
+                    attempt = 0;
+                    let mut stream = subscription.into_stream();
+                    while let Some(header) = stream.next().await {
+                        cache.update(BlockHead {
+                            number: header.number,
+                            base_fee: header.base_fee_per_gas,
+                            timestamp: header.timestamp,
+                            received_at: Instant::now(),
+                        });
+                    }
+                    tracing::warn!("🔌 Block head subscription on {} closed, polling over HTTP", endpoint);
+                }
+                Err(e) => tracing::warn!("🔌 Failed to subscribe to the block heads of {}: {}", endpoint, e),
+            },
+            Err(e) => tracing::warn!("🔌 Failed to connect to {}: {}", endpoint, e),
+        }
+        cache.disconnected();
+        let delay = backoff(attempt);
+        attempt = attempt.saturating_add(1);
+        tokio::time::sleep(delay).await;
+    }
+}
@@ -6,6 +6,32 @@ use std::{
 };
 
 use serde::{de::DeserializeOwned, Serialize};
+use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::types::config::LogFormat;
+
+/// Initializes the global subscriber. In JSON, each line carries the current span and the span list, so the
+/// `trade_id` of a trade is on every line of its lifecycle. A closing span logs its timing (`time.busy`, `time.idle`),
+/// and the spans are exported over OTLP when configured (`utils::otel`).
+pub fn logging(filter: EnvFilter, format: LogFormat, service: &str) {
+    let fmt = tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE);
+    let fmt = match format {
+        LogFormat::Text => fmt.boxed(),
+        LogFormat::Json => fmt.json().with_current_span(true).with_span_list(true).boxed(),
+    };
+    tracing_subscriber::registry().with(fmt.with_filter(filter)).with(crate::utils::otel::layer(service)).init();
+}
+
+/// Endpoint without its credentials, for the logs. Only the scheme, host and port are kept: providers put the API key in
+/// the path, the query or the user info.
+pub fn redact(url: &str) -> String {
+    let Ok(parsed) = url.parse::<url::Url>() else {
+        return "***".to_string();
+    };
+    let port = parsed.port().map(|port| format!(":{}", port)).unwrap_or_default();
+    let hidden = !matches!(parsed.path(), "" | "/") || parsed.query().is_some() || !parsed.username().is_empty() || parsed.password().is_some();
+    format!("{}://{}{}{}", parsed.scheme(), parsed.host_str().unwrap_or_default(), port, if hidden { "/***" } else { "" })
+}
 
 /// Gets the current Git commit hash from the repository.
 pub fn commit() -> Option<String> {
@@ -24,6 +50,11 @@ pub fn commit() -> Option<String> {
     }
 }
 
+/// Unix time in milliseconds, the unit of the timestamps of the trades.
+pub fn now_ms() -> u128 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis()
+}
+
 /// Retrieves an environment variable value, panics if not found.
 pub fn get(key: &str) -> String {
     std::env::var(key).unwrap_or_else(|_| panic!("Environment variable not found: {}", key))
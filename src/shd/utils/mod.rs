@@ -3,7 +3,14 @@
 //! Collection of utility functions and helper modules for the market maker.
 //! This module provides constants, EVM utilities, miscellaneous helpers, and uptime
 //! tracking functionality used throughout the application.
+pub mod alert;
+pub mod background;
 pub mod constants;
+pub mod crash;
 pub mod evm;
+pub mod head;
 pub mod misc;
+pub mod multicall;
+pub mod otel;
+pub mod signer;
 pub mod uptime;
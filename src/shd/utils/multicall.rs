@@ -0,0 +1,98 @@
+//! Multicall Module
+//!
+//! Batches the wallet reads (ERC20 balances and allowances, native balance) into one `eth_call` to Multicall3,
+//! at `multicall_address` (the canonical deployment by default). An empty address, or a chain without the
+//! contract, falls back to one call per read. The nonce cannot be read by a contract, it stays an RPC call.
+use alloy::{providers::Provider, rpc::types::TransactionRequest, sol_types::SolCall};
+use alloy_primitives::{Address, U256};
+
+use crate::types::sol::{IMulticall3, IERC20};
+
+/// One read of a batch, answered as an amount (raw token units or wei).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Read {
+    Balance { token: Address, owner: Address },
+    Allowance { token: Address, owner: Address, spender: Address },
+    Native { owner: Address }, // Read by Multicall3 itself (getEthBalance)
+}
+
+impl Read {
+    fn call(&self, multicall: Address) -> IMulticall3::Call3 {
+        let (target, data) = match *self {
+            Read::Balance { token, owner } => (token, IERC20::balanceOfCall::new((owner,)).abi_encode()),
+            Read::Allowance { token, owner, spender } => (token, IERC20::allowanceCall::new((owner, spender)).abi_encode()),
+            Read::Native { owner } => (multicall, IMulticall3::getEthBalanceCall::new((owner,)).abi_encode()),
+        };
+        IMulticall3::Call3 {
+            target,
+            allowFailure: true,
+            callData: data.into(),
+        }
+    }
+}
+
+/// Amount above u128 (an uint256 max allowance) saturated to `u128::MAX`.
+fn saturated(value: U256) -> u128 {
+    u128::try_from(value).unwrap_or(u128::MAX)
+}
+
+/// Calldata of the `aggregate3` call batching `reads`, each allowed to fail on its own.
+pub fn encode(multicall: Address, reads: &[Read]) -> Vec<u8> {
+    IMulticall3::aggregate3Call {
+        calls: reads.iter().map(|read| read.call(multicall)).collect(),
+    }
+    .abi_encode()
+}
+
+/// Decodes the `aggregate3` return data of `expected` reads, in order. A failed read is None.
+pub fn decode(expected: usize, data: &[u8]) -> Result<Vec<Option<u128>>, String> {
+    let results = IMulticall3::aggregate3Call::abi_decode_returns(data).map_err(|e| format!("Invalid multicall return data: {}", e))?;
+    if results.len() != expected {
+        return Err(format!("Multicall returned {} results for {} reads", results.len(), expected));
+    }
+    Ok(results
+        .iter()
+        .map(|result| (result.success && result.returnData.len() >= 32).then(|| saturated(U256::from_be_slice(&result.returnData[..32]))))
+        .collect())
+}
+
+/// Batches `reads` into one `eth_call` to the Multicall3 at `multicall`.
+pub async fn multicall(provider: &impl Provider, multicall: Address, reads: &[Read]) -> Result<Vec<Option<u128>>, String> {
+    let request = TransactionRequest::default().to(multicall).input(encode(multicall, reads).into());
+    let data = provider.call(request).await.map_err(|e| format!("Multicall to {} failed: {}", multicall, e))?;
+    decode(reads.len(), &data)
+}
+
+/// Reads one by one, a failed read is None.
+pub async fn sequential(provider: &impl Provider, reads: &[Read]) -> Vec<Option<u128>> {
+    let mut results = vec![];
+    for read in reads.iter() {
+        let result = match *read {
+            Read::Balance { token, owner } => IERC20::new(token, provider).balanceOf(owner).call().await.map_err(|e| e.to_string()),
+            Read::Allowance { token, owner, spender } => IERC20::new(token, provider).allowance(owner, spender).call().await.map_err(|e| e.to_string()),
+            Read::Native { owner } => provider.get_balance(owner).await.map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(value) => results.push(Some(saturated(value))),
+            Err(e) => {
+                tracing::error!("Failed to read {:?}: {}", read, e);
+                results.push(None);
+            }
+        }
+    }
+    results
+}
+
+/// Reads through the Multicall3 at `multicall`, sequentially if it is empty or the batch fails (no contract on the chain).
+pub async fn read(provider: &impl Provider, multicall: &str, reads: &[Read]) -> Vec<Option<u128>> {
+    if !multicall.is_empty() {
+        match multicall.parse::<Address>() {
+            Ok(address) => match self::multicall(provider, address, reads).await {
+                Ok(results) => return results,
+                Err(e) => tracing::debug!("{}, reading sequentially", e),
+            },
+            Err(e) => tracing::warn!("Invalid multicall address {}: {}, reading sequentially", multicall, e),
+        }
+    }
+    sequential(provider, reads).await
+}
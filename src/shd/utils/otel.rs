@@ -0,0 +1,99 @@
+//! OpenTelemetry Module
+//!
+//! Built with the `otel` feature and run with `OTEL_EXPORTER_OTLP_ENDPOINT` set, the spans of the trade hot path
+//! (block, evaluate, readjust, encode, then the `trade` execution with its simulate and broadcast stages) are exported
+//! over OTLP (gRPC) to Tempo, Jaeger or any collector, where each block shows as a flame of its stage latencies.
+//! `OTEL_TRACES_FILTER` selects the exported spans, `OTEL_SERVICE_NAME` names the service (the binary by default).
+//! Without the feature no exporter is compiled in, and without the endpoint none is built.
+use tracing::Subscriber;
+use tracing_subscriber::{registry::LookupSpan, Layer};
+
+/// Layer of the global subscriber, boxed so that the exporter can be compiled out.
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// OTLP endpoint (OTEL_EXPORTER_OTLP_ENDPOINT), None when unset or empty.
+pub fn endpoint() -> Option<String> {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|endpoint| !endpoint.is_empty())
+}
+
+#[cfg(feature = "otel")]
+mod export {
+    use std::sync::OnceLock;
+
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing::Subscriber;
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    use super::BoxedLayer;
+
+    /// Provider of the process, kept to flush the batched spans at exit.
+    static PROVIDER: OnceLock<TracerProvider> = OnceLock::new();
+
+    pub fn layer<S>(endpoint: &str, service: &str) -> Result<BoxedLayer<S>, String>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic().with_endpoint(endpoint).build().map_err(|e| e.to_string())?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new("service.name", service.to_string())]))
+            .build();
+        let tracer = provider.tracer(service.to_string());
+        let _ = PROVIDER.set(provider);
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
+    }
+
+    pub fn shutdown() {
+        if let Some(provider) = PROVIDER.get() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush the OTLP spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Span export layer, None without the endpoint or without the `otel` feature.
+///
+/// Called before the subscriber exists, so problems are printed to stderr.
+pub fn layer<S>(service: &str) -> Option<BoxedLayer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let endpoint = endpoint()?;
+    #[cfg(feature = "otel")]
+    {
+        use crate::utils::constants::DEFAULT_OTEL_TRACES_FILTER;
+        use tracing_subscriber::EnvFilter;
+
+        let service = std::env::var("OTEL_SERVICE_NAME").ok().filter(|name| !name.is_empty()).unwrap_or_else(|| service.to_string());
+        let filter = std::env::var("OTEL_TRACES_FILTER").unwrap_or_else(|_| DEFAULT_OTEL_TRACES_FILTER.to_string());
+        let filter = EnvFilter::try_new(&filter).unwrap_or_else(|e| {
+            eprintln!("Invalid OTEL_TRACES_FILTER '{}': {}, using '{}'", filter, e, DEFAULT_OTEL_TRACES_FILTER);
+            EnvFilter::new(DEFAULT_OTEL_TRACES_FILTER)
+        });
+        match export::layer(&endpoint, &service) {
+            Ok(layer) => Some(layer.with_filter(filter).boxed()),
+            Err(e) => {
+                eprintln!("Failed to build the OTLP exporter for {}: {}, traces not exported", endpoint, e);
+                None
+            }
+        }
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        eprintln!(
+            "OTEL_EXPORTER_OTLP_ENDPOINT is set to {} but the binary was built without the `otel` feature, traces not exported",
+            endpoint
+        );
+        let _ = service;
+        None
+    }
+}
+
+/// Exports the spans still batched, before the process exits. No-op without the exporter.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    export::shutdown();
+}
@@ -0,0 +1,141 @@
+//! Wallet Signer Module
+//!
+//! Signs the maker transactions with the key selected by `SIGNER_TYPE`: a raw key (`WALLET_PRIVATE_KEY`),
+//! an encrypted JSON keystore (`KEYSTORE_PATH`, decrypted with `KEYSTORE_PASSWORD`), or an external
+//! JSON-RPC signer (`REMOTE_SIGNER_URL`) holding the key of `REMOTE_SIGNER_ADDRESS` and answering eth_signTransaction.
+//! A keystore is decrypted once per process, the key never leaves the signer of the remote mode.
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use alloy::{
+    consensus::{TxEnvelope, TypedTransaction},
+    eips::eip2718::Decodable2718,
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::{local::PrivateKeySigner, SignerSync},
+};
+use alloy_primitives::{Address, Signature, B256};
+
+use crate::types::config::{EnvConfig, SignerType};
+
+/// Keystores decrypted by this process, by path.
+static KEYSTORES: OnceLock<Mutex<HashMap<String, PrivateKeySigner>>> = OnceLock::new();
+
+/// External signer answering eth_signTransaction for one address.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    pub url: url::Url,
+    pub address: Address,
+}
+
+impl RemoteSigner {
+    /// Sends the unsigned transaction to the signer and decodes the signed envelope it returns.
+    async fn sign(&self, sender: Address, tx: TypedTransaction) -> alloy::signers::Result<TxEnvelope> {
+        if sender != self.address {
+            return Err(alloy::signers::Error::other(format!("Remote signer holds {}, not {}", self.address, sender)));
+        }
+        let request = TransactionRequest::from_transaction_with_sender(tx, sender);
+        let provider = ProviderBuilder::new().connect_http(self.url.clone());
+        let raw = provider.sign_transaction(request).await.map_err(alloy::signers::Error::other)?;
+        TxEnvelope::decode_2718(&mut raw.as_ref()).map_err(alloy::signers::Error::other)
+    }
+
+    /// Sends EIP-712 typed data to the signer (eth_signTypedData_v4), which hashes and signs it.
+    async fn sign_typed_data(&self, typed_data: &serde_json::Value) -> Result<Signature, String> {
+        let provider = ProviderBuilder::new().connect_http(self.url.clone());
+        let signature = provider
+            .raw_request::<_, String>("eth_signTypedData_v4".into(), (self.address, typed_data))
+            .await
+            .map_err(|e| format!("Remote signer failed to sign typed data: {}", e))?;
+        let bytes = hex::decode(signature.trim_start_matches("0x")).map_err(|e| format!("Invalid signature from the remote signer: {}", e))?;
+        Signature::from_raw(&bytes).map_err(|e| format!("Invalid signature from the remote signer: {}", e))
+    }
+}
+
+/// Wallet of the maker transactions, whatever holds the key.
+#[derive(Debug, Clone)]
+pub enum WalletSigner {
+    Local(PrivateKeySigner, EthereumWallet), // Raw key or decrypted keystore
+    Remote(RemoteSigner),
+}
+
+impl WalletSigner {
+    /// Builds the signer selected by the environment, decrypting the keystore on first use.
+    pub fn from_env(env: &EnvConfig) -> Result<Self, String> {
+        match env.signer_type {
+            SignerType::Raw => {
+                let key = B256::from_str(&env.wallet_private_key).map_err(|e| format!("Invalid WALLET_PRIVATE_KEY: {}", e))?;
+                let signer = PrivateKeySigner::from_bytes(&key).map_err(|e| format!("Invalid WALLET_PRIVATE_KEY: {}", e))?;
+                Ok(Self::local(signer))
+            }
+            SignerType::Keystore => {
+                let path = env.keystore_path.clone().unwrap_or_default();
+                let password = env.keystore_password.clone().unwrap_or_default();
+                Ok(Self::local(keystore(&path, &password)?))
+            }
+            SignerType::Remote => {
+                let url = env.remote_signer_url.as_deref().unwrap_or_default();
+                let url = url.parse::<url::Url>().map_err(|e| format!("Invalid REMOTE_SIGNER_URL: {}", e))?;
+                let address = env.remote_signer_address.as_deref().unwrap_or_default();
+                let address = Address::from_str(address).map_err(|e| format!("Invalid REMOTE_SIGNER_ADDRESS: {}", e))?;
+                Ok(Self::Remote(RemoteSigner { url, address }))
+            }
+        }
+    }
+
+    fn local(signer: PrivateKeySigner) -> Self {
+        Self::Local(signer.clone(), EthereumWallet::from(signer))
+    }
+
+    /// Address the transactions are sent from.
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer, _) => signer.address(),
+            Self::Remote(remote) => remote.address,
+        }
+    }
+
+    /// Signs an EIP-712 message: its `hash` with a local key, its `typed_data` with the remote signer.
+    pub async fn sign_typed_data(&self, hash: B256, typed_data: &serde_json::Value) -> Result<Signature, String> {
+        match self {
+            Self::Local(signer, _) => signer.sign_hash_sync(&hash).map_err(|e| format!("Failed to sign {}: {}", hash, e)),
+            Self::Remote(remote) => remote.sign_typed_data(typed_data).await,
+        }
+    }
+}
+
+impl NetworkWallet<Ethereum> for WalletSigner {
+    fn default_signer_address(&self) -> Address {
+        self.address()
+    }
+
+    fn has_signer_for(&self, address: &Address) -> bool {
+        *address == self.address()
+    }
+
+    fn signer_addresses(&self) -> impl Iterator<Item = Address> {
+        std::iter::once(self.address())
+    }
+
+    async fn sign_transaction_from(&self, sender: Address, tx: TypedTransaction) -> alloy::signers::Result<TxEnvelope> {
+        match self {
+            Self::Local(_, wallet) => <EthereumWallet as NetworkWallet<Ethereum>>::sign_transaction_from(wallet, sender, tx).await,
+            Self::Remote(remote) => remote.sign(sender, tx).await,
+        }
+    }
+}
+
+/// Decrypts a JSON keystore, once per path (scrypt makes it slow on purpose).
+fn keystore(path: &str, password: &str) -> Result<PrivateKeySigner, String> {
+    let cache = KEYSTORES.get_or_init(Default::default);
+    if let Some(signer) = cache.lock().unwrap().get(path) {
+        return Ok(signer.clone());
+    }
+    let signer = PrivateKeySigner::decrypt_keystore(path, password).map_err(|e| format!("Failed to decrypt keystore {}: {}", path, e))?;
+    cache.lock().unwrap().insert(path.to_string(), signer.clone());
+    Ok(signer)
+}
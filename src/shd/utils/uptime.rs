@@ -1,7 +1,21 @@
-use std::{process::Command, time::Duration};
+use std::{
+    process::Command,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 
+use crate::maker::{control::Control, health::HealthState};
+use crate::types::moni::{HeartbeatPayload, TradingState};
 use crate::utils::constants::HEARTBEAT_DELAY;
 
+/// Heartbeats failed (error, timeout or non-2xx status) since the process started.
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of failed heartbeats since the process started.
+pub fn failures() -> u64 {
+    FAILURES.load(Ordering::Relaxed)
+}
+
 /// Sends HTTP GET heartbeat request to check endpoint health.
 pub async fn alive(endpoint: String) -> bool {
     let client = reqwest::Client::new();
@@ -32,35 +46,97 @@ pub fn ghead() -> Option<String> {
     }
 }
 
-/// Sends a heartbeat ping to a specified endpoint for monitoring.
-pub async fn heartbeat(endpoint: String) {
-    ghead();
-    let client = reqwest::Client::new();
-    let _res = match client.get(endpoint.clone()).send().await {
-        Ok(res) => {
-            tracing::info!("Hearbeat Success for {}: {}", endpoint.clone(), res.status());
-            res
-        }
-        Err(e) => {
-            tracing::error!("Hearbeat Error on {}: {}", endpoint, e);
-            return;
+/// POSTs a heartbeat payload to the endpoint, within the timeout of the client. A failure is counted, never raised.
+pub async fn heartbeat(client: &reqwest::Client, endpoint: &str, payload: &HeartbeatPayload) -> bool {
+    let body = serde_json::to_string(payload).unwrap_or_default();
+    let error = match client.post(endpoint).header("Content-Type", "application/json").body(body).send().await {
+        Ok(res) if res.status().is_success() => {
+            tracing::info!("Hearbeat Success for {}: {}", payload.identifier, res.status());
+            return true;
         }
+        Ok(res) => format!("status {}", res.status()),
+        Err(e) => e.to_string(),
     };
+    let failures = FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+    tracing::error!("Hearbeat Error for {} on {}: {} ({} failures since start)", payload.identifier, endpoint, error, failures);
+    false
+}
+
+/// Appends the trading states to the heartbeat endpoint as a `state` query parameter (comma-separated, one per instance).
+pub fn heartbeat_url(endpoint: &str, states: &[TradingState]) -> String {
+    if states.is_empty() {
+        return endpoint.to_string();
+    }
+    let states = states.iter().map(|s| s.as_str()).collect::<Vec<&str>>().join(",");
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    format!("{}{}state={}", endpoint, separator, states)
+}
+
+/// Heartbeat payloads of the instances at `now` (unix ms), or a single payload for `process` when there is none (monitor).
+pub fn payloads(process: &str, commit: &str, instances: &[(Control, HealthState)], now: u64) -> Vec<HeartbeatPayload> {
+    if instances.is_empty() {
+        return vec![HeartbeatPayload {
+            identifier: process.to_string(),
+            commit: commit.to_string(),
+            timestamp_ms: now,
+            failures: failures(),
+            ..Default::default()
+        }];
+    }
+    instances.iter().map(|(control, health)| health.heartbeat(commit, control.state(), now, failures())).collect()
 }
 
 /// Spawns background task for periodic heartbeat monitoring.
-pub async fn heartbeats(testing: bool, heartbeat_endpoint: String) {
+///
+/// Every `interval`, the state of each instance is POSTed as JSON (see `HeartbeatPayload`), its trading state also
+/// appended to the URL. Each POST is bounded by `timeout`, so a slow endpoint cannot delay the next beats.
+pub async fn heartbeats(testing: bool, heartbeat_endpoint: String, interval: Duration, timeout: Duration, process: &str, instances: Vec<(Control, HealthState)>) {
     if testing {
         tracing::info!("Testing mode, heartbeat task not spawned.");
         return;
     }
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Failed to build the heartbeat client, heartbeat task not spawned: {}", e);
+            return;
+        }
+    };
+    let commit = ghead().unwrap_or_default();
+    let process = process.to_string();
     tracing::info!("Spawning heartbeat task.");
     tokio::spawn(async move {
-        let mut hb = tokio::time::interval(Duration::from_secs(HEARTBEAT_DELAY / 2));
+        let mut hb = tokio::time::interval(interval);
+        hb.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         loop {
             hb.tick().await;
-            heartbeat(heartbeat_endpoint.clone()).await;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+            for payload in payloads(&process, &commit, &instances, now) {
+                // The monitor has no trading state
+                let states = if instances.is_empty() { vec![] } else { vec![payload.state] };
+                heartbeat(&client, &heartbeat_url(&heartbeat_endpoint, &states), &payload).await;
+            }
             tracing::debug!("Heartbeat tick. Endpoint: {}", heartbeat_endpoint);
         }
     });
 }
+
+/// Spawns background task writing the trading state and last beat of each instance to its Redis keys (`data::keys`).
+///
+/// Both keys expire after `HEARTBEAT_DELAY`, so the keys of a stopped instance disappear.
+pub fn statuses(instances: Vec<(String, Control)>) {
+    if instances.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut hb = tokio::time::interval(Duration::from_secs(HEARTBEAT_DELAY / 2));
+        loop {
+            hb.tick().await;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+            for (identifier, control) in instances.iter() {
+                crate::data::helpers::set_ex(&crate::data::keys::key_status(identifier), control.state().as_str(), HEARTBEAT_DELAY).await;
+                crate::data::helpers::set_ex(&crate::data::keys::key_heartbeat(identifier), now, HEARTBEAT_DELAY).await;
+            }
+        }
+    });
+}
@@ -0,0 +1,139 @@
+//! Adaptive execution threshold: the rolling shortfall of the realized trades against their expectation widens
+//! `min_executable_spread_bps`, capped, and decays back as the realized results improve.
+mod common;
+
+use common::maker;
+use shd::opti::adaptive::{AdaptiveState, AdaptiveThreshold};
+use shd::types::maker::{RealizedData, SwapCalculation};
+
+const LAMBDA: f64 = 0.9;
+const MIN_TRADES: usize = 10;
+
+/// Calculation selling 1 ETH worth 3000 USD, expecting `gas_cost_usd` of gas.
+fn calculation(gas_cost_usd: f64) -> SwapCalculation {
+    SwapCalculation {
+        average_sell_price_net_gas: 2_997.0,
+        gas_units: 150_000,
+        gas_cost_eth: gas_cost_usd / 3_000.0,
+        gas_cost_usd,
+        gas_cost_in_output_token: gas_cost_usd,
+        profit_delta_bps: 20.0,
+        ..common::calculation(true, 1.0, 3_000.0)
+    }
+}
+
+fn realized(slippage_bps: f64, gas_cost_usd: f64) -> RealizedData {
+    RealizedData {
+        realized_amount_out: 3_000.0 * (1.0 - slippage_bps / 10_000.0),
+        realized_slippage_bps: slippage_bps,
+        realized_gas_cost_usd: gas_cost_usd,
+    }
+}
+
+#[test]
+fn test_shortfall_of_a_trade() {
+    assert_eq!(AdaptiveThreshold::shortfall_bps(&calculation(3.0), &realized(0.0, 3.0)), 0.0, "As expected");
+    // 3 bps of slippage, and 3 USD of gas above the estimate on 3000 USD (10 bps): 13 bps
+    let shortfall = AdaptiveThreshold::shortfall_bps(&calculation(3.0), &realized(3.0, 6.0));
+    assert!((shortfall - 13.0).abs() < 1e-9, "{}", shortfall);
+    assert!(AdaptiveThreshold::shortfall_bps(&calculation(3.0), &realized(-2.0, 3.0)) < 0.0, "Better than expected");
+}
+
+#[test]
+fn test_widening_after_min_trades() {
+    let mut adaptive = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    let adjustments = (0..MIN_TRADES).filter_map(|_| adaptive.record(8.0)).collect::<Vec<_>>();
+    assert_eq!(adjustments.len(), 1, "Only once the min trades are reached");
+    assert_eq!(adjustments[0].trades, MIN_TRADES);
+    assert_eq!(adjustments[0].previous_bps, 0.0);
+    assert!((adjustments[0].widening_bps - 8.0).abs() < 1e-9, "{:?}", adjustments[0]);
+    assert!((adaptive.widening_bps() - 8.0).abs() < 1e-9);
+    assert_eq!(adaptive.record(8.0), None, "Unchanged");
+}
+
+#[test]
+fn test_widening_capped() {
+    let mut adaptive = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    for _ in 0..MIN_TRADES * 5 {
+        adaptive.record(50.0);
+    }
+    assert!(adaptive.shortfall() > 20.0);
+    assert_eq!(adaptive.widening_bps(), 20.0);
+
+    let mut disabled = AdaptiveThreshold::new(LAMBDA, 0.0, MIN_TRADES);
+    assert!((0..MIN_TRADES * 5).all(|_| disabled.record(50.0).is_none()));
+    assert_eq!(disabled.widening_bps(), 0.0);
+}
+
+#[test]
+fn test_decays_back_when_results_improve() {
+    let mut adaptive = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    for _ in 0..MIN_TRADES * 3 {
+        adaptive.record(10.0);
+    }
+    let widened = adaptive.widening_bps();
+    assert!((widened - 10.0).abs() < 1e-9, "{}", widened);
+
+    // Realized as expected from now on, the widening decays trade after trade
+    let mut history = vec![widened];
+    for _ in 0..20 {
+        adaptive.record(0.0);
+        history.push(adaptive.widening_bps());
+    }
+    assert!(history.windows(2).all(|w| w[1] < w[0]), "{:?}", history);
+    assert!(adaptive.widening_bps() < 1.5, "{}", adaptive.widening_bps());
+
+    // Better than expected: the static threshold stays the floor
+    for _ in 0..MIN_TRADES * 5 {
+        adaptive.record(-15.0);
+    }
+    assert!(adaptive.shortfall() < 0.0);
+    assert_eq!(adaptive.widening_bps(), 0.0);
+    assert_eq!(adaptive.record(f64::NAN), None);
+}
+
+#[test]
+fn test_state_restored() {
+    let mut adaptive = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    for _ in 0..MIN_TRADES * 2 {
+        adaptive.record(12.0);
+    }
+    let state = adaptive.state();
+    let mut resumed = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    resumed.restore(state);
+    assert_eq!(resumed.state(), state);
+    assert_eq!(resumed.record(3.0), adaptive.record(3.0), "Same rolling shortfall");
+
+    let mut capped = AdaptiveThreshold::new(LAMBDA, 5.0, MIN_TRADES);
+    capped.restore(state);
+    assert_eq!(capped.widening_bps(), 5.0, "Widening within the current cap");
+    let mut early = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES * 10);
+    early.restore(state);
+    assert_eq!(early.widening_bps(), 0.0, "Below the current min trades");
+    let mut empty = AdaptiveThreshold::new(LAMBDA, 20.0, MIN_TRADES);
+    empty.restore(AdaptiveState::default());
+    assert_eq!(empty.state(), AdaptiveState::default());
+}
+
+#[test]
+fn test_execution_threshold_widened_in_adaptive_mode() {
+    let mut config = common::config();
+    config.vol_multiplier = 0.0;
+    let floor = config.min_executable_spread_bps;
+
+    let mut mk = maker(config.clone());
+    for _ in 0..100 {
+        mk.adapt_threshold(&calculation(3.0), Some(&realized(5.0, 3.0)));
+    }
+    assert_eq!(mk.execution_threshold_bps(), floor, "Adaptive mode off");
+
+    config.adaptive_threshold = true;
+    config.adaptive_threshold_max_bps = 4.0;
+    let mut mk = maker(config);
+    mk.adapt_threshold(&calculation(3.0), None);
+    assert_eq!(mk.execution_threshold_bps(), floor, "Not included, nothing realized");
+    for _ in 0..100 {
+        mk.adapt_threshold(&calculation(3.0), Some(&realized(5.0, 3.0)));
+    }
+    assert!((mk.execution_threshold_bps() - (floor + 4.0)).abs() < 1e-9, "{}", mk.execution_threshold_bps());
+}
@@ -0,0 +1,84 @@
+//! Push alerts: Telegram and Slack-compatible payloads, rate limiting per instance and kind.
+use shd::types::maker::PnlSnapshot;
+use shd::types::moni::{AlertKind, NewAlertMessage};
+use shd::utils::alert::{notify, text, AlertRateLimiter, Alerter, TelegramAlerter, WebhookAlerter};
+
+fn alert(kind: AlertKind, message: &str) -> NewAlertMessage {
+    NewAlertMessage {
+        identifier: "mainnet-eth-usdc-alert".to_string(),
+        kind,
+        message: message.to_string(),
+        pnl: None,
+    }
+}
+
+#[test]
+fn test_payloads() {
+    let mut opened = alert(AlertKind::CircuitBreakerOpened, "Realized loss of 512.00 $ over the last 24h");
+    opened.pnl = Some(PnlSnapshot {
+        timestamp_ms: 0,
+        trades: 12,
+        cumulative_usd: -512.0,
+        cumulative_gas_usd: 40.0,
+        window_ms: 86_400_000,
+        rolling_trades: 12,
+        rolling_usd: -512.0,
+    });
+    let expected = "🚨 CircuitBreakerOpened | mainnet-eth-usdc-alert\nRealized loss of 512.00 $ over the last 24h\nPnL: -512.00 $ over 12 trades (-512.00 $ rolling)";
+    assert_eq!(text(&opened), expected);
+
+    let telegram = TelegramAlerter {
+        token: "123:abc".to_string(),
+        chat_id: "-10042".to_string(),
+    };
+    assert_eq!(telegram.url(), "https://api.telegram.org/bot123:abc/sendMessage");
+    let payload = telegram.payload(&opened);
+    assert_eq!(payload["chat_id"], "-10042");
+    assert_eq!(payload["text"], expected);
+
+    let webhook = WebhookAlerter {
+        url: "https://hooks.slack.com/services/T/B/X".to_string(),
+    };
+    let rejected = alert(AlertKind::ExecutionFailed, "Execution failed: All builders rejected bundle");
+    assert_eq!(
+        webhook.payload(&rejected),
+        serde_json::json!({ "text": "🚨 ExecutionFailed | mainnet-eth-usdc-alert\nExecution failed: All builders rejected bundle" })
+    );
+}
+
+#[test]
+fn test_rate_limit_per_instance_and_kind() {
+    let mut limiter = AlertRateLimiter::new(600_000);
+    let low = alert(AlertKind::LowNativeBalance, "0.01 ETH left");
+    assert!(limiter.allow(&low, 1_000));
+    assert!(!limiter.allow(&alert(AlertKind::LowNativeBalance, "0.009 ETH left"), 300_000));
+    assert!(limiter.allow(&alert(AlertKind::StreamReconnect, "Stream closed, reconnecting"), 300_000));
+    let mut other = low.clone();
+    other.identifier = "mainnet-eth-wbtc-alert".to_string();
+    assert!(limiter.allow(&other, 300_000));
+    assert!(limiter.allow(&low, 601_000));
+}
+
+#[test]
+fn test_notify_without_alerters_is_a_noop() {
+    notify(alert(AlertKind::PreflightFailed, "RPC unreachable"));
+}
+
+#[tokio::test]
+async fn test_send_error_without_the_url() {
+    // Nothing listens, the error must not carry the secret of the endpoint
+    let webhook = WebhookAlerter {
+        url: "http://127.0.0.1:1/bot123:secret/sendMessage".to_string(),
+    };
+    let err = webhook.send(&alert(AlertKind::Crash, "panicked")).await.expect_err("Unreachable");
+    assert!(!err.contains("secret"), "{}", err);
+}
+
+#[test]
+fn test_unknown_kind_of_a_newer_maker() {
+    let json = serde_json::json!({ "identifier": "mainnet-eth-usdc-alert", "kind": "SomeFutureKind", "message": "New", "pnl": null });
+    let alert: NewAlertMessage = serde_json::from_value(json).expect("Read rather than rejected");
+    assert_eq!(alert.kind, AlertKind::Unknown);
+    let known: NewAlertMessage = serde_json::from_value(serde_json::json!({ "identifier": "a", "kind": "ThresholdAdjusted", "message": "", "pnl": null })).unwrap();
+    assert_eq!(known.kind, AlertKind::ThresholdAdjusted);
+}
@@ -0,0 +1,167 @@
+//! End to end on a mainnet fork: a fixed reference price dislocates the Uniswap V2 USDC/WETH pair, the maker readjusts,
+//! encodes the approval and swap, simulates and broadcasts them on anvil. Skipped unless ANVIL_FORK_URL is set (an
+//! archive mainnet RPC), the fork block can be moved with ANVIL_FORK_BLOCK.
+mod common;
+
+use std::collections::HashMap;
+
+use alloy::node_bindings::Anvil;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::sol;
+use alloy_primitives::{keccak256, Address, B256, U256};
+use common::{base, component, context, maker, quote, MockBalances};
+use shd::maker::exec::ExecStrategyFactory;
+use shd::maker::tycho::{chain, TychoEncoder};
+use shd::opti::skew::InventorySkew;
+use shd::types::config::EnvConfig;
+use shd::types::maker::{Inventory, TradeData, TradeStatus};
+use shd::types::tycho::ProtoSimComp;
+use shd::utils::evm::balances;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+use tycho_simulation::evm::protocol::uniswap_v2::state::UniswapV2State;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IUniswapV2Pair {
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+    }
+);
+
+/// Pinned fork block, after the deployment of the Tycho router of the reference config.
+const FORK_BLOCK: u64 = 23_000_000;
+/// Uniswap V2 USDC/WETH pair, token0 is USDC.
+const PAIR: &str = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc";
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+/// Storage slots of the balanceOf mappings of WETH and USDC.
+const WETH_BALANCES_SLOT: u64 = 3;
+const USDC_BALANCES_SLOT: u64 = 9;
+const BASE_BALANCE: u128 = 10 * 10u128.pow(18);
+const QUOTE_BALANCE: u128 = 30_000 * 10u128.pow(6);
+/// Bounds of the gas of a single hop Uniswap V2 swap through the router.
+const SWAP_GAS: std::ops::Range<u128> = 60_000..300_000;
+
+fn env(private_key: String) -> EnvConfig {
+    EnvConfig {
+        testing: false,
+        wallet_private_key: private_key,
+        ..common::env()
+    }
+}
+
+/// Overrides the ERC20 balance of `owner`, stored in the balanceOf mapping at `slot`.
+async fn fund(provider: &impl Provider, token: &str, slot: u64, owner: Address, amount: u128) {
+    let key = keccak256([B256::left_padding_from(owner.as_slice()).0, U256::from(slot).to_be_bytes::<32>()].concat());
+    let value = B256::from(U256::from(amount).to_be_bytes::<32>());
+    provider
+        .raw_request::<_, serde_json::Value>("anvil_setStorageAt".into(), (token.parse::<Address>().unwrap(), key, value))
+        .await
+        .expect("Storage override");
+}
+
+#[tokio::test]
+async fn test_approve_and_swap_on_a_mainnet_fork() {
+    let Ok(fork) = std::env::var("ANVIL_FORK_URL") else {
+        eprintln!("ANVIL_FORK_URL not set, skipping the fork test");
+        return;
+    };
+    let block = std::env::var("ANVIL_FORK_BLOCK").ok().and_then(|block| block.parse().ok()).unwrap_or(FORK_BLOCK);
+    let anvil = Anvil::new().fork(fork).fork_block_number(block).spawn();
+    let wallet = anvil.addresses()[0];
+    let private_key = format!("0x{}", hex::encode(anvil.keys()[0].to_bytes()));
+    let provider = ProviderBuilder::new().connect_http(anvil.endpoint_url());
+
+    // 10 WETH and 30k USDC, on top of the ETH of the anvil account for gas
+    fund(&provider, WETH, WETH_BALANCES_SLOT, wallet, BASE_BALANCE).await;
+    fund(&provider, USDC, USDC_BALANCES_SLOT, wallet, QUOTE_BALANCE).await;
+    let owner = wallet.to_string();
+    let tokens = vec![WETH.to_string(), USDC.to_string()];
+    assert_eq!(balances(&provider, owner.clone(), tokens.clone()).await.unwrap(), vec![BASE_BALANCE, QUOTE_BALANCE]);
+
+    // Pair state read from the fork, priced 2% below the reference: USDC is sold for WETH
+    let reserves = IUniswapV2Pair::new(PAIR.parse().unwrap(), &provider).getReserves().call().await.expect("Pair reserves");
+    let (reserve0, reserve1) = (U256::from(reserves.reserve0), U256::from(reserves.reserve1));
+    let psc = ProtoSimComp {
+        component: component(PAIR, "uniswap_v2", vec![quote(), base()]),
+        protosim: Box::new(UniswapV2State::new(reserve0, reserve1)),
+    };
+    let pool_balances = MockBalances {
+        balances: HashMap::from([(
+            psc.component.id.to_string(),
+            HashMap::from([(USDC.to_lowercase(), reserve0.to::<u128>()), (WETH.to_lowercase(), reserve1.to::<u128>())]),
+        )]),
+    };
+    let spot = psc.protosim.spot_price(&base(), &quote()).unwrap();
+    let reference = spot * 1.02;
+
+    let mut config = common::config();
+    config.wallet_public_key = owner.clone();
+    config.infinite_approval = false;
+    config.auto_wrap_native = false;
+    config.unwrap_to_native_above = 0.;
+    config.skip_simulation = false;
+    let mk = maker(config.clone());
+
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let adjustments = mk.evaluate(std::slice::from_ref(&psc), vec![spot], reference, &skew, block);
+    assert_eq!(adjustments.len(), 1, "Pair dislocated from the reference");
+    let gas_price = provider.get_gas_price().await.unwrap();
+    let context = context(reference, 2. * gas_price as f64 / 1e9, block);
+    let inventory = Inventory {
+        base_balance: BASE_BALANCE,
+        quote_balance: QUOTE_BALANCE,
+        nonce: provider.get_transaction_count(wallet).await.unwrap(),
+        native_balance: provider.get_balance(wallet).await.unwrap().to::<u128>(),
+    };
+    let orders = mk.readjust(context.clone(), inventory.clone(), adjustments, &pool_balances).await;
+    assert_eq!(orders.len(), 1, "Profitable rebalance");
+    let order = orders[0].clone();
+    assert!(!order.calculation.base_to_quote);
+
+    // Prepare: real Tycho encoding, approval then swap
+    let tdata = TradeData {
+        trade_id: order.trade_id.clone(),
+        status: TradeStatus::Pending,
+        timestamp: 0,
+        context: context.clone(),
+        metadata: mk.pre_trade_data(&order),
+        inventory: inventory.clone(),
+        simulation: None,
+        broadcast: None,
+        realized: None,
+    };
+    let (_, simu) = chain("ethereum".to_string()).unwrap();
+    let trades = mk.encode(
+        vec![order.clone()],
+        vec![tdata],
+        context,
+        inventory,
+        &TychoEncoder {
+            chain: simu,
+            permit2: false,
+            rpc_url: anvil.endpoint(),
+        },
+    );
+    assert_eq!(trades.len(), 1);
+    assert!(trades[0].approve.is_some());
+
+    // Simulate and broadcast on the fork, injected into the strategy instead of the RPC pool of the config
+    let strategy = ExecStrategyFactory::with_rpc(config.network_name.as_str(), anvil.endpoint());
+    let trades = strategy.execute(config, trades, env(private_key), "anvil".to_string()).await.expect("Execution");
+    let metadata = &trades[0].metadata;
+    let simulation = metadata.simulation.clone().expect("Simulated");
+    assert!(simulation.status, "Simulation failed: {:?}", simulation.error);
+    assert!(SWAP_GAS.contains(&simulation.estimated_gas), "Simulated gas {}", simulation.estimated_gas);
+    assert_eq!(metadata.status, TradeStatus::BroadcastSucceeded);
+    let receipt = metadata.broadcast.clone().and_then(|bd| bd.receipt).expect("Swap receipt");
+    assert!(receipt.status);
+    assert!(SWAP_GAS.contains(&receipt.gas_used), "Swap gas {}", receipt.gas_used);
+
+    // USDC spent exactly, at least the minimum WETH received
+    let after = balances(&provider, owner, tokens).await.unwrap();
+    let spent = QUOTE_BALANCE - after[1];
+    let received = after[0] - BASE_BALANCE;
+    assert_eq!(spent, order.calculation.amount_in_raw.to_string().parse::<u128>().unwrap());
+    assert!(received as f64 >= order.calculation.amount_out_min_normalized * 1e18, "Received {}", received);
+}
@@ -0,0 +1,75 @@
+//! `maker approve`: token and amount resolution, the testing guard and the command line.
+mod common;
+
+use clap::Parser;
+use common::config;
+use shd::maker::approval::{raw_amount, run, token_address, BASE_TOKEN, QUOTE_TOKEN};
+use shd::types::cli::{MakerCli, MakerCommand};
+use shd::types::config::EnvConfig;
+
+const TOKEN: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+
+fn env(testing: bool) -> EnvConfig {
+    EnvConfig { testing, ..common::env() }
+}
+
+#[test]
+fn test_token_address() {
+    let config = config();
+    assert_eq!(token_address(&config, BASE_TOKEN).unwrap(), config.base_token_address);
+    assert_eq!(token_address(&config, &QUOTE_TOKEN.to_uppercase()).unwrap(), config.quote_token_address);
+    assert_eq!(token_address(&config, &TOKEN.to_lowercase()).unwrap(), TOKEN, "Checksummed");
+    assert!(token_address(&config, "weth").is_err());
+}
+
+#[test]
+fn test_raw_amount() {
+    assert_eq!(raw_amount(Some(1.5), false, false, 6).unwrap(), 1_500_000);
+    assert_eq!(raw_amount(Some(2.0), false, false, 18).unwrap(), 2 * 10u128.pow(18));
+    assert_eq!(raw_amount(None, true, false, 18).unwrap(), u128::MAX);
+    assert_eq!(raw_amount(None, false, true, 18).unwrap(), 0);
+    assert!(raw_amount(Some(0.0), false, false, 18).is_err(), "Revoking is explicit");
+    assert!(raw_amount(Some(f64::NAN), false, false, 18).is_err());
+    assert!(raw_amount(Some(1e30), false, false, 18).is_err(), "Overflows a u128");
+    assert!(raw_amount(None, false, false, 18).is_err());
+    assert!(raw_amount(Some(1.0), true, false, 18).is_err());
+    assert!(raw_amount(None, true, true, 18).is_err());
+}
+
+#[tokio::test]
+async fn test_testing_mode_refuses_without_force() {
+    let err = run(&config(), &env(true), BASE_TOKEN, None, None, true, false, false).await.unwrap_err();
+    assert!(err.contains("--force"), "{}", err);
+    // Forced, it gets past the guard and fails on the missing key, before any RPC call
+    let err = run(&config(), &env(true), BASE_TOKEN, None, None, true, false, true).await.unwrap_err();
+    assert!(err.contains("WALLET_PRIVATE_KEY"), "{}", err);
+}
+
+#[test]
+fn test_approve_command() {
+    let cli = MakerCli::try_parse_from(["maker", "approve", "--token", "base", "--amount", "1.5"]).unwrap();
+    match cli.command {
+        Some(MakerCommand::Approve {
+            token,
+            spender,
+            amount,
+            max,
+            revoke,
+            force,
+        }) => assert_eq!((token.as_str(), spender, amount, max, revoke, force), ("base", None, Some(1.5), false, false, false)),
+        other => panic!("Unexpected command: {:?}", other),
+    }
+    let cli = MakerCli::try_parse_from(["maker", "approve", "--token", TOKEN, "--spender", TOKEN, "--revoke", "--force"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(MakerCommand::Approve {
+            revoke: true,
+            force: true,
+            spender: Some(_),
+            ..
+        })
+    ));
+    assert!(MakerCli::try_parse_from(["maker", "approve", "--token", "base"]).is_err(), "Amount, max or revoke required");
+    assert!(MakerCli::try_parse_from(["maker", "approve", "--token", "base", "--amount", "1", "--max"]).is_err());
+    assert!(MakerCli::try_parse_from(["maker", "approve", "--token", "base", "--max", "--revoke"]).is_err());
+}
@@ -0,0 +1,154 @@
+//! Decision audit: records appended and rotated, read back by time range, and the gate of an evaluation recorded.
+mod common;
+
+use std::collections::BTreeMap;
+
+use clap::Parser;
+use common::{base, component, quote, MockProtocolSim};
+use shd::maker::audit::{describe, parse_time, read, rotated, AuditLog, Decision, Gate};
+use shd::maker::{exec::ExecStrategyFactory, feed::PriceFeedFactory};
+use shd::opti::skew::InventorySkew;
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::cli::{MakerCli, MakerCommand};
+use shd::types::config::MarketMakerConfig;
+use shd::types::tycho::ProtoSimComp;
+use shd::utils::background::BackgroundWriter;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+
+fn path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("mkmk-audit-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(rotated(&path));
+    path.to_string_lossy().to_string()
+}
+
+fn config(path: &str, max_bytes: u64) -> MarketMakerConfig {
+    let mut config = common::config();
+    config.audit_log_path = Some(path.to_string());
+    config.audit_log_max_bytes = max_bytes;
+    config
+}
+
+fn decision(timestamp_ms: u64, gate: Option<Gate>) -> Decision {
+    Decision {
+        timestamp_ms,
+        identifier: "test".to_string(),
+        block: 100,
+        pool: POOL.to_string(),
+        spot: 3_003.0,
+        reference: 3_000.0,
+        spread_bps: 10.0,
+        gate,
+        values: BTreeMap::from([("threshold_bps".to_string(), 15.0)]),
+    }
+}
+
+#[test]
+fn test_disabled_without_path() {
+    let mut config = config("", 1_000);
+    assert!(!AuditLog::new(&config).is_enabled());
+    config.audit_log_path = None;
+    assert!(!AuditLog::new(&config).is_enabled());
+    config.audit_log_max_bytes = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_records_rotated_and_read_by_range() {
+    let file = path("rotation");
+    let line = serde_json::to_string(&decision(0, None)).unwrap().len() as u64 + 1;
+    // Room for two records per file
+    let audit = AuditLog::new(&config(&file, line * 2 + 10));
+    for timestamp in 1..=5 {
+        audit.record(&decision(timestamp * 1_000, Some(Gate::WatchSpread)));
+    }
+    audit.flush();
+    assert!(rotated(std::path::Path::new(&file)).exists());
+    let all = read(&file, None, None).unwrap();
+    // The fifth record rotated away the first two
+    assert_eq!(all.iter().map(|decision| decision.timestamp_ms).collect::<Vec<u64>>(), vec![3_000, 4_000, 5_000]);
+    assert_eq!(all[0], decision(3_000, Some(Gate::WatchSpread)));
+    let range = read(&file, Some(3_500), Some(4_000)).unwrap();
+    assert_eq!(range.len(), 1);
+    assert_eq!(range[0].timestamp_ms, 4_000);
+    assert!(read(&path("missing"), None, None).is_err());
+}
+
+#[test]
+fn test_writes_run_in_order_off_the_caller_thread() {
+    let background = BackgroundWriter::spawn("audit-test-writer");
+    let written = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    for x in 0..100 {
+        let written = written.clone();
+        background.run(move || written.lock().unwrap().push((x, std::thread::current().name().map(str::to_string))));
+    }
+    background.flush();
+    let written = written.lock().unwrap().clone();
+    assert_eq!(
+        written.iter().map(|(x, _)| *x).collect::<Vec<i32>>(),
+        (0..100).collect::<Vec<i32>>(),
+        "In order, all written once flushed"
+    );
+    assert!(written.iter().all(|(_, thread)| thread.as_deref() == Some("audit-test-writer")));
+}
+
+#[test]
+fn test_time_parsing_and_description() {
+    assert_eq!(parse_time("2024-01-01T00:00:00Z").unwrap(), 1_704_067_200_000);
+    assert_eq!(parse_time("2024-01-01 00:01").unwrap(), 1_704_067_260_000);
+    assert_eq!(parse_time("2024-01-01 00:00:30").unwrap(), 1_704_067_230_000);
+    assert!(parse_time("yesterday").is_err());
+
+    let line = describe(&decision(1_704_067_200_000, Some(Gate::WatchSpread)));
+    assert!(line.starts_with("2024-01-01 00:00:00.000 | b#100"));
+    assert!(line.contains("rejected by watch spread (threshold_bps=15.0000)"));
+    assert!(describe(&decision(0, None)).contains("order created"));
+}
+
+#[test]
+fn test_evaluation_records_its_gate() {
+    let file = path("evaluate");
+    let config = config(&file, 1_000_000);
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    let mk = MarketMakerBuilder::new()
+        .config(config)
+        .feed(feed)
+        .execution(execution)
+        .tokens(base(), quote())
+        .build()
+        .expect("Market maker must build");
+    let targets = [ProtoSimComp {
+        component: component(POOL, "uniswap_v2", vec![base(), quote()]),
+        protosim: Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003)),
+    }];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    // Same price as the reference, nothing to do
+    assert!(mk.evaluate(&targets, vec![3_000.0], 3_000.0, &skew, 100).is_empty());
+    // 50x the reference, quarantined
+    assert!(mk.evaluate(&targets, vec![150_000.0], 3_000.0, &skew, 101).is_empty());
+    mk.audit.flush();
+    let decisions = read(&file, None, None).unwrap();
+    assert_eq!(decisions.len(), 2);
+    assert_eq!((decisions[0].block, decisions[0].gate), (100, Some(Gate::WatchSpread)));
+    assert_eq!(decisions[0].pool, POOL);
+    assert!(decisions[0].values.contains_key("threshold_bps"));
+    assert_eq!((decisions[1].block, decisions[1].gate), (101, Some(Gate::SanityBand)));
+}
+
+#[test]
+fn test_audit_command() {
+    let cli = MakerCli::try_parse_from(["maker", "audit", "audit.jsonl", "--from", "2024-01-01 14:30", "--pool", POOL]).unwrap();
+    match cli.command {
+        Some(MakerCommand::Audit { file, from, to, pool }) => {
+            assert_eq!(file, "audit.jsonl");
+            assert_eq!(from.as_deref(), Some("2024-01-01 14:30"));
+            assert!(to.is_none());
+            assert_eq!(pool.as_deref(), Some(POOL));
+        }
+        None => panic!("Audit command expected"),
+    }
+    assert!(MakerCli::try_parse_from(["maker", "audit"]).is_err(), "File required");
+    assert!(MakerCli::try_parse_from(["maker"]).unwrap().command.is_none());
+}
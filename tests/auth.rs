@@ -0,0 +1,53 @@
+//! Tycho API authentication: rejected keys told apart from the other failures and retried with a backoff.
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use shd::error::MarketMakerError;
+use shd::maker::tycho::{auth_backoff, auth_failure, retry_auth, with_auth_retry, TychoApiError};
+use shd::utils::constants::TYCHO_AUTH_MAX_ATTEMPTS;
+
+#[test]
+fn test_auth_failures_classified() {
+    for message in ["HTTP 401 Unauthorized", "status: 403", "Forbidden: invalid api key", "Unauthorized", "invalid token provided"] {
+        assert!(auth_failure(message), "{}", message);
+        assert!(matches!(TychoApiError::classify(message.to_string()), TychoApiError::Auth(_)));
+    }
+    for message in ["block 14013 not found", "HTTP 500 Internal Server Error", "connection reset by peer", "timeout after 4010 ms"] {
+        assert!(!auth_failure(message), "{}", message);
+        assert!(matches!(TychoApiError::classify(message.to_string()), TychoApiError::Other(_)));
+    }
+    let auth = TychoApiError::classify("401 Unauthorized".to_string());
+    assert!(auth.to_string().contains("TYCHO_API_KEY"));
+    assert!(matches!(MarketMakerError::from(auth), MarketMakerError::Auth(_)));
+    assert!(matches!(MarketMakerError::from(TychoApiError::Other("500".to_string())), MarketMakerError::Network(_)));
+}
+
+#[test]
+fn test_auth_backoff_doubles_up_to_the_cap() {
+    let delays = (0..8).map(|attempt| auth_backoff(attempt).as_secs()).collect::<Vec<u64>>();
+    assert_eq!(delays, vec![5, 10, 20, 40, 80, 160, 300, 300]);
+    assert_eq!(auth_backoff(u32::MAX), Duration::from_secs(300));
+}
+
+#[tokio::test]
+async fn test_only_auth_failures_retried() {
+    let config = common::config();
+
+    // Other failures and the last attempt return at once, without waiting
+    assert!(!retry_auth(&config, "testing", &TychoApiError::Other("500".to_string()), 0).await);
+    assert!(!retry_auth(&config, "testing", &TychoApiError::Auth("401".to_string()), TYCHO_AUTH_MAX_ATTEMPTS - 1).await);
+
+    let calls = AtomicU32::new(0);
+    let outcome: Result<(), TychoApiError> = with_auth_retry(&config, "testing", || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(TychoApiError::Other("connection reset".to_string()))
+    })
+    .await;
+    assert_eq!(outcome, Err(TychoApiError::Other("connection reset".to_string())));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let outcome = with_auth_retry(&config, "testing", || async { Ok::<u32, TychoApiError>(7) }).await;
+    assert_eq!(outcome, Ok(7));
+}
@@ -0,0 +1,189 @@
+//! Trade storage: a republished trade is stored once, and trades stored without a receipt are retried, then marked
+//! dropped past the max age.
+mod common;
+
+use chrono::Duration;
+use common::sqlite;
+use sea_orm::{ActiveModelTrait, Set};
+use shd::data::backfill::{backfill, BackfillReport};
+use shd::data::dead_letter::deliver;
+use shd::data::neon::{create, handle, pull, Neon};
+use shd::entity::trade;
+use shd::types::config::{load_market_maker_config, DatabaseType, EventsTransport, MoniEnvConfig};
+use shd::types::maker::{BroadcastData, Inventory, MarketContext, PreTradeData, ReceiptData, TradeData, TradeDirection, TradeStatus};
+use shd::types::moni::{Envelope, MessageType, NewInstanceMessage, NewTradeMessage, ParsedMessage};
+
+fn env() -> MoniEnvConfig {
+    MoniEnvConfig {
+        testing: true,
+        heartbeat: String::new(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
+        database_url: String::new(),
+        database_name: String::new(),
+        database_backend: DatabaseType::Sqlite,
+        events_transport: EventsTransport::PubSub,
+        price_retention_days: 0,
+        price_downsample_after_days: 0,
+        price_downsample_minutes: 15,
+        trade_receipt_max_age_minutes: 60,
+        redis_legacy_keys: false,
+    }
+}
+
+fn trade(identifier: &str, hash: &str) -> NewTradeMessage {
+    NewTradeMessage {
+        identifier: identifier.to_string(),
+        data: TradeData {
+            trade_id: String::new(),
+            status: TradeStatus::BroadcastSucceeded,
+            timestamp: 0,
+            context: MarketContext {
+                base_to_eth: 1.0,
+                quote_to_eth: 1.0 / 3_000.0,
+                eth_to_usd: 3_000.0,
+                max_fee_per_gas: 0,
+                max_priority_fee_per_gas: 0,
+                native_gas_price: 0,
+                block: 0,
+                age_ms: 0,
+            },
+            metadata: PreTradeData {
+                pool: "0x0".to_string(),
+                base_token: "ETH".to_string(),
+                quote_token: "USDC".to_string(),
+                trade_direction: TradeDirection::Sell,
+                amount_in_normalized: 1.0,
+                amount_out_expected: 3_000.0,
+                token_out: String::new(),
+                token_out_decimals: 6,
+                spot_price: 3_000.0,
+                reference_price: 3_000.0,
+                slippage_tolerance_bps: 10.0,
+                profit_delta_bps: 1.0,
+                gas_cost_usd: 1.0,
+            },
+            inventory: Inventory {
+                base_balance: 0,
+                quote_balance: 0,
+                nonce: 0,
+                native_balance: 0,
+            },
+            simulation: None,
+            broadcast: Some(BroadcastData {
+                hash: hash.to_string(),
+                ..Default::default()
+            }),
+            realized: None,
+        },
+        idempotency_key: String::new(),
+    }
+}
+
+fn broadcast(row: &trade::Model) -> BroadcastData {
+    serde_json::from_value::<NewTradeMessage>(row.values.clone()).unwrap().data.broadcast.unwrap()
+}
+
+/// Registers an instance whose RPC is unreachable, so every receipt lookup fails.
+async fn instance(db: &sea_orm::DatabaseConnection, identifier: &str) -> shd::entity::instance::Model {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    config.rpc_url = "http://127.0.0.1:1".to_string(); // Nothing listens, every lookup fails
+    config.rpc_urls = vec![];
+    handle(
+        &ParsedMessage::NewInstance(NewInstanceMessage {
+            config,
+            identifier: identifier.to_string(),
+            commit: "test".to_string(),
+        }),
+        db,
+    )
+    .await
+    .unwrap();
+    pull::instances(db).await.unwrap().into_iter().find(|instance| instance.identifier == identifier).unwrap()
+}
+
+#[tokio::test]
+async fn test_republished_trade_is_stored_once() {
+    let (db, path) = sqlite("idempotency").await;
+    let identifier = "mainnet-eth-usdc-idempotency";
+    let instance = instance(&db, identifier).await;
+
+    // Same swap received twice, the second time with its receipt: merged into the first row
+    let hash = format!("0x{}", "ab".repeat(32));
+    let first = create::trade(&db, &instance, &trade(identifier, &hash)).await.unwrap();
+    let mut mined = trade(identifier, &hash.to_uppercase().replace("0X", "0x"));
+    mined.data.broadcast.as_mut().unwrap().receipt = Some(ReceiptData {
+        status: true,
+        gas_used: 120_000,
+        error: None,
+        transaction_hash: hash.clone(),
+        transaction_index: 0,
+        block_number: 1,
+        effective_gas_price: 1,
+        transfers: vec![],
+    });
+    let second = create::trade(&db, &instance, &mined).await.unwrap();
+    assert_eq!(second.id, first.id);
+    assert!(broadcast(&second).receipt.is_some());
+    // An older copy arriving late keeps the receipt
+    let third = create::trade(&db, &instance, &trade(identifier, &hash)).await.unwrap();
+    assert_eq!(third.id, first.id);
+    assert!(broadcast(&third).receipt.is_some());
+
+    // Dry run (no hash): the same published payload delivered twice, keyed by the client key
+    let neon = Neon::from_connection(db.clone());
+    let mut dry = trade(identifier, "");
+    dry.idempotency_key = "dry-run-1".to_string();
+    let payload = serde_json::to_string(&Envelope::new(&MessageType::NewTrade, identifier, serde_json::to_value(&dry).unwrap())).unwrap();
+    deliver(&neon, &payload).await.unwrap();
+    deliver(&neon, &payload).await.unwrap();
+    dry.idempotency_key = "dry-run-2".to_string();
+    create::trade(&db, &instance, &dry).await.unwrap();
+    let trades = pull::trades(&db).await.unwrap();
+    assert_eq!(trades.len(), 3);
+    assert_eq!(trades.iter().filter(|row| row.idempotency_key.as_deref() == Some("dry-run-1")).count(), 1);
+
+    // The unique index rejects a duplicate written around the upsert
+    let mut duplicate: trade::ActiveModel = first.into();
+    duplicate.id = Set("duplicate".to_string());
+    assert!(duplicate.insert(&db).await.is_err());
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_missing_receipts_are_retried_then_dropped() {
+    let (db, path) = sqlite("backfill").await;
+    let identifier = "mainnet-eth-usdc-backfill".to_string();
+    let instance = instance(&db, &identifier).await;
+
+    let hash = |x: u8| format!("0x{}", format!("{:02x}", x).repeat(32));
+    let old = create::trade(&db, &instance, &trade(&identifier, &hash(1))).await.unwrap();
+    let mut aged: trade::ActiveModel = old.into();
+    aged.created_at = Set(chrono::Utc::now().naive_utc() - Duration::hours(2));
+    aged.update(&db).await.unwrap();
+    create::trade(&db, &instance, &trade(&identifier, &hash(2))).await.unwrap();
+    create::trade(&db, &instance, &trade(&identifier, "")).await.unwrap(); // Never broadcast, nothing to fetch
+    assert_eq!(pull::trades_missing_receipt(&db).await.unwrap().len(), 2);
+
+    let now = chrono::Utc::now().naive_utc();
+    let report = backfill(&db, &env(), now).await.unwrap();
+    assert_eq!(report, BackfillReport { filled: 0, dropped: 1, pending: 1 });
+    let dropped = pull::trades(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| broadcast(&row))
+        .filter(|b| b.dropped)
+        .collect::<Vec<BroadcastData>>();
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].hash, hash(1));
+    assert!(dropped[0].receipt.is_none());
+
+    // The dropped trade is no longer retried, the recent one still is
+    let missing = pull::trades_missing_receipt(&db).await.unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].1.data.broadcast.as_ref().unwrap().hash, hash(2));
+    let report = backfill(&db, &env(), now).await.unwrap();
+    assert_eq!(report, BackfillReport { filled: 0, dropped: 0, pending: 1 });
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,188 @@
+//! Backtest: synthetic pools filling at spot minus fee and impact, recorded rows replayed through evaluate and readjust.
+mod common;
+
+use clap::Parser;
+use common::{base, quote};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use shd::data::neon::{create, pull};
+use shd::maker::audit::{read, rotated};
+use shd::maker::backtest::{context, gates, rows, Backtest, FillModel};
+use shd::maker::{exec::ExecStrategyFactory, feed::PriceFeedFactory};
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::cli::BacktestCli;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::{ComponentPriceData, TradeDirection};
+use shd::types::moni::NewPricesMessage;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+
+fn config(name: &str) -> (MarketMakerConfig, String) {
+    let path = std::env::temp_dir().join(format!("mkmk-backtest-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(rotated(&path));
+    let mut config = common::config();
+    config.audit_log_path = Some(path.to_string_lossy().to_string());
+    (config, path.to_string_lossy().to_string())
+}
+
+fn prices(block: u64, reference: f64, spot: f64) -> NewPricesMessage {
+    serde_json::from_value(serde_json::json!({
+        "identifier": "mmc-ethereum-eth-usdc-0x0af694-instance-1",
+        "reference_price": reference,
+        "components": [{ "address": POOL, "type": "uniswap_v3", "price": spot }],
+        "block": block,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_synthetic_pool_fills_at_spot_minus_fee_and_impact() {
+    let model = FillModel {
+        fee_bps: 5.,
+        liquidity: 1_000.,
+        impact_bps_per_pct: 100.,
+        impact_exponent: 1.,
+        gas_units: 150_000,
+    };
+    // 1% of the liquidity: 100 bps of average impact, 50 bps with a square root curve past 0.25%
+    assert!((model.impact(10.) - 0.01).abs() < 1e-12);
+    assert!((FillModel { impact_exponent: 0.5, ..model }.impact(2.5) - 0.005).abs() < 1e-12);
+    assert_eq!(model.impact(0.), 0.);
+
+    let cpd = ComponentPriceData {
+        address: POOL.to_string(),
+        r#type: "uniswap_v3".to_string(),
+        price: 3_000.,
+    };
+    let target = model.target(&cpd, &base(), &quote());
+    assert_eq!(target.component.protocol_system, "uniswap_v3");
+    assert_eq!(shd::maker::tycho::amm_fee_to_bps(target.component.clone()), 5);
+    assert_eq!(target.protosim.spot_price(&base(), &quote()).unwrap(), 3_000.);
+
+    // Selling 10 ETH: 30_000 USDC minus 5 bps of fee and 100 bps of impact, the marginal price drops twice the impact
+    let result = target.protosim.get_amount_out(BigUint::from(10u128 * 10u128.pow(18)), &base(), &quote()).unwrap();
+    let out = result.amount.to_f64().unwrap() / 1e6;
+    assert!((out - 30_000. * 0.9995 * 0.99).abs() < 1e-3, "{}", out);
+    assert_eq!(result.gas, BigUint::from(150_000u64));
+    assert!((result.new_state.spot_price(&base(), &quote()).unwrap() - 3_000. * 0.98).abs() < 1e-9);
+
+    // Buying with 3_000 USDC (1 ETH worth) pushes the price up
+    let result = target.protosim.get_amount_out(BigUint::from(3_000u128 * 10u128.pow(6)), &quote(), &base()).unwrap();
+    assert!(result.new_state.spot_price(&base(), &quote()).unwrap() > 3_000.);
+}
+
+#[test]
+fn test_context_from_the_reference() {
+    let (config, _) = config("context");
+    let context = context(&config, &base(), &quote(), 3_000., 7, 2., None).unwrap();
+    assert_eq!((context.base_to_eth, context.eth_to_usd, context.block), (1., 3_000., 7));
+    assert!((context.quote_to_eth - 1. / 3_000.).abs() < 1e-15);
+    assert_eq!(context.native_gas_price, 2_000_000_000);
+    assert_eq!(context(&config, &base(), &quote(), 3_000., 7, 2., Some(2_500.)).unwrap().eth_to_usd, 2_500.);
+    assert!(context(&config, &base(), &quote(), 0., 7, 2., None).is_err());
+    // Quote is the gas token: the ETH price must be given
+    assert!(context(&config, &quote(), &base(), 1. / 3_000., 7, 2., None).is_err());
+    assert_eq!(context(&config, &quote(), &base(), 1. / 3_000., 7, 2., Some(3_000.)).unwrap().quote_to_eth, 1.);
+}
+
+#[tokio::test]
+async fn test_rows_replayed_through_the_decisions() {
+    let (config, audit) = config("replay");
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    let mk = MarketMakerBuilder::new()
+        .config(config)
+        .feed(feed)
+        .execution(execution)
+        .tokens(base(), quote())
+        .build()
+        .expect("Market maker must build");
+    let mut bt = Backtest::new(mk, FillModel::default(), 1., None, 10., 30_000.);
+
+    // In line with the reference, then 2% above it: the pool is sold back to the reference
+    bt.step(1_000, &prices(100, 3_000., 3_000.)).await.unwrap();
+    bt.step(2_000, &prices(101, 3_000., 3_060.)).await.unwrap();
+    // Same dislocation right after the fill: the pool is cooling down
+    bt.step(3_000, &prices(102, 3_000., 3_060.)).await.unwrap();
+
+    assert_eq!(bt.fills.len(), 1);
+    let fill = &bt.fills[0];
+    assert_eq!((fill.block, fill.direction.clone(), fill.pool.as_str()), (101, TradeDirection::Buy, POOL));
+    assert!(fill.selling_amount > 0. && fill.selling_amount <= 9.9, "{}", fill.selling_amount);
+    assert!(fill.buying_amount / fill.selling_amount > 3_000., "Sold above the reference");
+
+    assert_eq!(bt.series.len(), 3);
+    assert_eq!(bt.series[0].pnl_quote, 0.);
+    let last = &bt.series[2];
+    assert!(last.base_balance < 10. && last.quote_balance > 30_000.);
+    assert!(last.pnl_quote > 0., "{}", last.pnl_quote);
+    assert_eq!(last.trades, 1);
+
+    bt.mk.audit.flush();
+    let decisions = read(&audit, None, None).unwrap();
+    let report = bt.report("mmc-ethereum-eth-usdc", 0, &decisions);
+    assert_eq!((report.rows, report.first_block, report.last_block, report.trades), (3, 100, 102, 1));
+    let count = |gate: &str| report.gates.iter().find(|stat| stat.gate == gate).map(|stat| stat.count).unwrap_or_default();
+    assert_eq!((count("watch spread"), count("order created"), count("cooldown")), (1, 1, 1));
+    assert_eq!(gates(&[]).len(), 0);
+
+    let dir = std::env::temp_dir().join(format!("mkmk-backtest-report-{}", std::process::id()));
+    report.write(&dir).unwrap();
+    let series = std::fs::read_to_string(dir.join("series.csv")).unwrap();
+    assert_eq!(series.lines().count(), 4);
+    assert!(series.starts_with("timestamp_ms,block,reference,"));
+    assert!(std::fs::read_to_string(dir.join("gates.csv")).unwrap().contains("order created,1,"));
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(json["trades"], 1);
+}
+
+#[tokio::test]
+async fn test_price_rows_pulled_by_instance_and_range() {
+    let (db, _path) = common::sqlite("backtest").await;
+    let (mmc, _) = config("pull");
+    let configuration = create::configuration(&db, mmc.clone()).await.unwrap();
+    let identifier = format!("{}-instance-1", mmc.id());
+    let instance = create::instance(&db, &configuration, mmc.clone(), identifier, "abc".to_string()).await.unwrap();
+    let other = create::instance(&db, &configuration, mmc.clone(), "mmc-other-instance-1".to_string(), "abc".to_string()).await.unwrap();
+    create::price(&db, &instance, &prices(11, 3_000., 3_001.)).await.unwrap();
+    create::price(&db, &instance, &prices(10, 3_000., 3_002.)).await.unwrap();
+    create::price(&db, &other, &prices(12, 3_000., 3_003.)).await.unwrap();
+
+    let instances = pull::instances_matching(&db, &mmc.id()).await.unwrap();
+    assert_eq!(instances.iter().map(|i| i.id.clone()).collect::<Vec<String>>(), vec![instance.id.clone()]);
+    let now = chrono::Utc::now().naive_utc();
+    let (from, to) = (now - chrono::Duration::minutes(1), now + chrono::Duration::minutes(1));
+    let prices = pull::prices_between(&db, &[instance.id.clone()], from, to, None, 100).await.unwrap();
+    assert_eq!(prices.len(), 2);
+    assert!(pull::prices_between(&db, &[instance.id.clone()], to, now + chrono::Duration::minutes(2), None, 100)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Read by pages, each after the last row of the previous one
+    let first = pull::prices_between(&db, &[instance.id.clone()], from, to, None, 1).await.unwrap();
+    let after = Some((first[0].created_at, first[0].id.clone()));
+    let second = pull::prices_between(&db, &[instance.id.clone()], from, to, after, 1).await.unwrap();
+    assert_eq!([first[0].id.clone(), second[0].id.clone()], [prices[0].id.clone(), prices[1].id.clone()]);
+    let after = Some((second[0].created_at, second[0].id.clone()));
+    assert!(pull::prices_between(&db, &[instance.id.clone()], from, to, after, 1).await.unwrap().is_empty());
+
+    // Downsampled rows are not replayable, the others come back by block
+    let mut downsampled = prices[0].clone();
+    downsampled.value = serde_json::json!({ "identifier": "x", "downsampled": true, "reference_price": { "open": 1.0, "high": 1.0, "low": 1.0, "close": 1.0 } });
+    let (replayable, skipped) = rows(vec![prices[0].clone(), prices[1].clone(), downsampled]);
+    assert_eq!(skipped, 1);
+    assert_eq!(replayable.iter().map(|(_, msg)| msg.block).collect::<Vec<u64>>(), vec![10, 11]);
+}
+
+#[test]
+fn test_backtest_command() {
+    let cli = BacktestCli::try_parse_from(["backtest", "--config", "config/mainnet.eth-usdc.toml", "--from", "2024-06-01 14:30", "--fee-bps", "30"]).unwrap();
+    assert_eq!(cli.from.as_deref(), Some("2024-06-01 14:30"));
+    assert_eq!(cli.out, "backtest");
+    let model = cli.model();
+    assert_eq!(model.fee_bps, 30.);
+    assert_eq!(FillModel { fee_bps: 5., ..model }, FillModel::default());
+    assert!(BacktestCli::try_parse_from(["backtest"]).is_err(), "Config required");
+}
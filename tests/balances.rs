@@ -0,0 +1,190 @@
+//! Component balances: read from the streamed UniswapV2 state, else one Tycho request per protocol system, cached for the
+//! block, failed systems left out.
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use alloy::primitives::U256;
+use async_trait::async_trait;
+use common::{base, component, context, maker, pool, quote, readjustment, MockBalances};
+use shd::maker::tycho::{batch_component_balances, component_balances_local, BalanceCache, PoolBalances, ProtocolStates};
+use shd::types::maker::Inventory;
+use shd::types::tycho::ProtoSimComp;
+use tycho_simulation::evm::protocol::uniswap_v2::state::UniswapV2State;
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+const UNIV2_A: &str = "0xaaaa000000000000000000000000000000000001";
+const UNIV2_B: &str = "0xaaaa000000000000000000000000000000000002";
+const UNIV3: &str = "0xbbbb000000000000000000000000000000000001";
+const CURVE: &str = "0xcccc000000000000000000000000000000000001";
+const WETH: u128 = 1_000_000_000_000_000_000; // 18 decimals
+const USDC: u128 = 1_000_000; // 6 decimals
+
+/// Protocol states answering every requested component, recording the requests and failing the listed systems.
+#[derive(Default)]
+struct RecordedStates {
+    requests: Mutex<Vec<(String, Vec<String>)>>,
+    failing: Vec<String>,
+}
+
+impl RecordedStates {
+    fn requests(&self) -> Vec<(String, Vec<String>)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ProtocolStates for RecordedStates {
+    async fn balances(&self, system: &str, ids: Vec<String>) -> Result<HashMap<String, HashMap<String, u128>>, String> {
+        self.requests.lock().unwrap().push((system.to_string(), ids.clone()));
+        if self.failing.iter().any(|s| s == system) {
+            return Err(format!("{} unavailable", system));
+        }
+        Ok(ids.into_iter().map(|id| (id, HashMap::from([(base().address.to_string().to_lowercase(), 1_000u128)]))).collect())
+    }
+}
+
+fn components() -> Vec<ProtocolComponent> {
+    vec![
+        component(UNIV2_A, "uniswap_v2", vec![base(), quote()]),
+        component(UNIV3, "uniswap_v3", vec![base(), quote()]),
+        component(UNIV2_B, "uniswap_v2", vec![base(), quote()]),
+    ]
+}
+
+#[tokio::test]
+async fn test_one_request_per_protocol_system() {
+    let states = RecordedStates::default();
+    let cache = BalanceCache::default();
+    let fetched = batch_component_balances(&states, &cache, &components(), 100).await;
+
+    assert_eq!(fetched.len(), 3);
+    assert_eq!(
+        states.requests(),
+        vec![
+            ("uniswap_v2".to_string(), vec![UNIV2_A.to_string(), UNIV2_B.to_string()]),
+            ("uniswap_v3".to_string(), vec![UNIV3.to_string()]),
+        ]
+    );
+    assert_eq!(fetched[UNIV3][&base().address.to_string().to_lowercase()], 1_000);
+}
+
+#[tokio::test]
+async fn test_cached_within_the_block_only() {
+    let states = RecordedStates::default();
+    let cache = BalanceCache::default();
+    batch_component_balances(&states, &cache, &components(), 100).await;
+    assert_eq!(cache.len(), 3);
+
+    // Same block: served from the cache
+    let fetched = batch_component_balances(&states, &cache, &components(), 100).await;
+    assert_eq!(fetched.len(), 3);
+    assert_eq!(states.requests().len(), 2);
+
+    // A component not seen in the block is the only one requested
+    let mut more = components();
+    more.push(component(CURVE, "vm:curve", vec![base(), quote()]));
+    batch_component_balances(&states, &cache, &more, 100).await;
+    assert_eq!(states.requests().last().unwrap(), &("vm:curve".to_string(), vec![CURVE.to_string()]));
+    assert_eq!(states.requests().len(), 3);
+
+    // New block: read again, the entries of the previous block dropped
+    batch_component_balances(&states, &cache, &components(), 101).await;
+    assert_eq!(states.requests().len(), 5);
+    assert_eq!(cache.len(), 3);
+    assert!(cache.get(UNIV2_A, 100).is_none());
+    assert!(cache.get(UNIV2_A, 101).is_some());
+}
+
+#[tokio::test]
+async fn test_failed_system_leaves_its_components_out() {
+    let states = RecordedStates {
+        failing: vec!["uniswap_v3".to_string()],
+        ..Default::default()
+    };
+    let cache = BalanceCache::default();
+    let fetched = batch_component_balances(&states, &cache, &components(), 100).await;
+    assert_eq!(fetched.len(), 2);
+    assert!(!fetched.contains_key(UNIV3));
+
+    // Not cached, so retried on the next evaluation of the block
+    batch_component_balances(&states, &cache, &components(), 100).await;
+    assert_eq!(states.requests().last().unwrap(), &("uniswap_v3".to_string(), vec![UNIV3.to_string()]));
+}
+
+#[tokio::test]
+async fn test_default_batch_reads_each_component() {
+    let psc = pool("0xAAAA000000000000000000000000000000000001", 1_000., 3_000_000., 0.003);
+    let balances = MockBalances::of(std::slice::from_ref(&psc));
+    let missing = component(UNIV3, "uniswap_v3", vec![base(), quote()]);
+    let fetched = balances.batch(&[psc.component.clone(), missing], 100).await;
+    assert_eq!(fetched.len(), 1);
+    assert_eq!(Some(&fetched[UNIV2_A]), balances.get(&psc.component).await.as_ref());
+}
+
+/// UniswapV2 pool of `eth` ETH against `usdc` USDC. USDC has the lowest address, so it is token0.
+fn univ2(eth: u128, usdc: u128) -> ProtoSimComp {
+    ProtoSimComp {
+        component: component(UNIV2_A, "uniswap_v2", vec![base(), quote()]),
+        protosim: Box::new(UniswapV2State::new(U256::from(usdc * USDC), U256::from(eth * WETH))),
+    }
+}
+
+/// Pool balances counting the reads, none available.
+#[derive(Default)]
+struct CountedBalances {
+    reads: AtomicUsize,
+}
+
+#[async_trait]
+impl PoolBalances for CountedBalances {
+    async fn get(&self, _cp: &ProtocolComponent) -> Option<HashMap<String, u128>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+}
+
+#[tokio::test]
+async fn test_local_balances_match_the_remote_ones() {
+    for (eth, usdc) in [(1_000, 3_000_000), (7, 21_350), (250, 1)] {
+        let local = component_balances_local(&univ2(eth, usdc)).expect("UniswapV2 reserves are in the state");
+        // Same reserves served as the Tycho balances of the component
+        let mock = pool(UNIV2_A, eth as f64, usdc as f64, 0.003);
+        let remote = MockBalances::of(std::slice::from_ref(&mock)).get(&mock.component).await.unwrap();
+        assert_eq!(local, remote);
+        assert_eq!(local[&base().address.to_string().to_lowercase()], eth * WETH);
+        assert_eq!(local[&quote().address.to_string().to_lowercase()], usdc * USDC);
+    }
+
+    // Component tokens listed the other way around: still keyed by their own address
+    let mut reversed = univ2(1_000, 3_000_000);
+    reversed.component.tokens.reverse();
+    assert_eq!(component_balances_local(&reversed), component_balances_local(&univ2(1_000, 3_000_000)));
+
+    assert!(component_balances_local(&pool(UNIV2_A, 1_000., 3_000_000., 0.003)).is_none(), "Not recoverable: HTTP fallback");
+}
+
+#[tokio::test]
+async fn test_readjust_prefers_the_streamed_balances() {
+    let mk = maker(common::config());
+    let inventory = Inventory {
+        base_balance: 10 * WETH,
+        quote_balance: 30_000 * USDC,
+        nonce: 0,
+        native_balance: 0,
+    };
+
+    let psc = univ2(1_000, 3_030_000);
+    let remote = CountedBalances::default();
+    let orders = mk.readjust(context(3_000., 1., 100), inventory.clone(), vec![readjustment(&psc, 3_030., 3_000.)], &remote).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(remote.reads.load(Ordering::SeqCst), 0, "No network read");
+
+    // Mock pool: balances only from the remote source
+    let psc = pool(UNIV2_A, 1_000., 3_030_000., 0.003);
+    let orders = mk.readjust(context(3_000., 1., 100), inventory, vec![readjustment(&psc, 3_030., 3_000.)], &remote).await;
+    assert!(orders.is_empty());
+    assert_eq!(remote.reads.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,75 @@
+use shd::maker::breaker::{BreakerEvent, CircuitBreaker};
+use shd::maker::pnl::PnlTracker;
+use shd::types::maker::PnlEntry;
+
+const HOUR_MS: u128 = 3_600_000;
+
+fn loss(timestamp_ms: u128, usd: f64) -> PnlEntry {
+    PnlEntry {
+        timestamp_ms,
+        sold_usd: usd,
+        bought_usd: 0.0,
+        gas_usd: 0.0,
+        pnl_usd: -usd,
+    }
+}
+
+#[test]
+fn test_breaker_suppresses_orders_once_threshold_crossed() {
+    let mut pnl = PnlTracker::new(24 * HOUR_MS);
+    let mut breaker = CircuitBreaker::new(100.0, HOUR_MS);
+
+    pnl.record(loss(0, 60.0));
+    assert_eq!(breaker.check(&pnl, 0), None);
+    assert_eq!(breaker.gate(vec!["order"]), vec!["order"]);
+
+    pnl.record(loss(10, 50.0));
+    assert_eq!(breaker.check(&pnl, 10), Some(BreakerEvent::Opened { loss_usd: 110.0 }));
+    assert!(breaker.is_open());
+    assert!(breaker.gate(vec!["order"]).is_empty(), "Orders must be suppressed while the breaker is open");
+
+    // Still open before the cooldown
+    assert_eq!(breaker.check(&pnl, HOUR_MS / 2), None);
+    assert!(breaker.gate(vec!["order"]).is_empty());
+}
+
+#[test]
+fn test_breaker_resumes_after_cooldown() {
+    let mut pnl = PnlTracker::new(24 * HOUR_MS);
+    let mut breaker = CircuitBreaker::new(100.0, HOUR_MS);
+    pnl.record(loss(0, 150.0));
+    assert!(matches!(breaker.check(&pnl, 0), Some(BreakerEvent::Opened { .. })));
+
+    assert_eq!(breaker.check(&pnl, HOUR_MS), Some(BreakerEvent::CooldownElapsed));
+    assert!(!breaker.is_open());
+    // Losses realized before the resume do not reopen it
+    assert_eq!(breaker.check(&pnl, HOUR_MS + 1), None);
+    assert_eq!(breaker.gate(vec![1, 2]), vec![1, 2]);
+
+    // New losses after the resume do
+    pnl.record(loss(HOUR_MS + 2, 101.0));
+    assert!(matches!(breaker.check(&pnl, HOUR_MS + 2), Some(BreakerEvent::Opened { .. })));
+}
+
+#[test]
+fn test_breaker_explicit_resume_only() {
+    let mut pnl = PnlTracker::new(24 * HOUR_MS);
+    let mut breaker = CircuitBreaker::new(100.0, 0);
+    pnl.record(loss(0, 150.0));
+    assert!(breaker.check(&pnl, 0).is_some());
+
+    // No cooldown: stays open until resumed
+    assert_eq!(breaker.check(&pnl, 48 * HOUR_MS), None);
+    assert!(breaker.is_open());
+    breaker.resume(48 * HOUR_MS);
+    assert!(!breaker.is_open());
+}
+
+#[test]
+fn test_breaker_disabled() {
+    let mut pnl = PnlTracker::new(24 * HOUR_MS);
+    let mut breaker = CircuitBreaker::new(0.0, HOUR_MS);
+    pnl.record(loss(0, 1_000_000.0));
+    assert_eq!(breaker.check(&pnl, 0), None);
+    assert!(!breaker.is_open());
+}
@@ -0,0 +1,65 @@
+//! Market maker builder: config, feed, execution and tokens added in that order, then validated by `build()`.
+mod common;
+
+use common::{base, config, quote};
+use shd::error::MarketMakerError;
+use shd::maker::exec::{dry::DryRunExec, ExecStrategy, ExecStrategyFactory};
+use shd::maker::feed::PriceFeedFactory;
+use shd::types::builder::{MarketMakerBuilder, WithTokens};
+use shd::types::config::MarketMakerConfig;
+use tycho_common::models::token::Token;
+
+fn builder(config: MarketMakerConfig, execution: Box<dyn ExecStrategy>, base: Token, quote: Token) -> MarketMakerBuilder<WithTokens> {
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    MarketMakerBuilder::new().config(config).feed(feed).execution(execution).tokens(base, quote)
+}
+
+fn build_error(builder: MarketMakerBuilder<WithTokens>) -> String {
+    match builder.build() {
+        Err(MarketMakerError::Build(e)) => e,
+        Err(e) => panic!("Not a build error: {}", e),
+        Ok(_) => panic!("Built"),
+    }
+}
+
+#[test]
+fn test_build_with_every_component() {
+    let config = config();
+    let builder = builder(config.clone(), ExecStrategyFactory::create("ethereum"), base(), quote());
+    let identifier = builder.identifier().unwrap();
+    assert!(
+        identifier.starts_with(&format!("mmc-ethereum-eth-usdc-{}-instance-", &config.wallet_public_key[..9].to_lowercase())),
+        "{}",
+        identifier
+    );
+    let mk = builder.build().expect("Market maker must build");
+    assert_eq!((mk.base.symbol.as_str(), mk.quote.symbol.as_str()), ("ETH", "USDC"));
+    assert!(!mk.ready);
+}
+
+#[test]
+fn test_token_without_decimals() {
+    let mut token = base();
+    token.decimals = 0;
+    assert!(build_error(builder(config(), Box::new(DryRunExec::new()), token, quote())).contains("ETH"));
+    let mut token = quote();
+    token.decimals = 0;
+    assert!(build_error(builder(config(), Box::new(DryRunExec::new()), base(), token)).contains("USDC"));
+}
+
+#[test]
+fn test_execution_of_another_network() {
+    let error = build_error(builder(config(), ExecStrategyFactory::create("base"), base(), quote()));
+    assert!(error.contains("base") && error.contains("ethereum"), "{}", error);
+    // The dry run sends nowhere, whatever the network of the config
+    assert!(builder(config(), Box::new(DryRunExec::new()), base(), quote()).build().is_ok());
+}
+
+#[test]
+fn test_identifier_without_wallet() {
+    let mut config = config();
+    config.wallet_public_key = "0x12".to_string();
+    let builder = builder(config, Box::new(DryRunExec::new()), base(), quote());
+    assert!(matches!(builder.identifier(), Err(MarketMakerError::Build(_))));
+    assert!(build_error(builder).contains("0x12"));
+}
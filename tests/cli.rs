@@ -0,0 +1,82 @@
+use clap::Parser;
+use shd::maker::exec::ExecStrategyFactory;
+use shd::types::cli::{MakerCli, MonitorCli, MonitorCommand, DEFAULT_MONITOR_SECRET_PATH};
+
+const REFERENCE: &str = "config/mainnet.eth-usdc.toml";
+
+#[test]
+fn test_flags_parse() {
+    let cli = MakerCli::try_parse_from([
+        "maker",
+        "--config",
+        REFERENCE,
+        "--secrets",
+        ".env.test",
+        "--dry-run",
+        "--log-level",
+        "info",
+        "--network-override",
+        "base",
+        "--print-config",
+    ])
+    .unwrap();
+    assert_eq!(cli.config.as_deref(), Some(REFERENCE));
+    assert_eq!(cli.secrets.as_deref(), Some(".env.test"));
+    assert!(cli.dry_run);
+    assert_eq!(cli.log_level.as_deref(), Some("info"));
+    assert_eq!(cli.network_override.as_deref(), Some("base"));
+    assert!(cli.print_config);
+    assert!(MakerCli::try_parse_from(["maker", "--unknown"]).is_err());
+}
+
+#[test]
+fn test_config_flag_splits_paths() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", "config/a.toml, config/b.toml"]).unwrap();
+    assert_eq!(cli.paths(), vec!["config/a.toml".to_string(), "config/b.toml".to_string()]);
+}
+
+#[test]
+fn test_dry_run_forces_strategy() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", REFERENCE, "--dry-run"]).unwrap();
+    let configs = cli.configs().expect("Reference config must load");
+    assert!(configs[0].dry_run);
+    assert_eq!(ExecStrategyFactory::from_config(&configs[0]).name(), "DryRun_Strategy");
+
+    let cli = MakerCli::try_parse_from(["maker", "--config", REFERENCE]).unwrap();
+    let configs = cli.configs().expect("Reference config must load");
+    assert_eq!(ExecStrategyFactory::from_config(&configs[0]).name(), "Mainnet_Strategy");
+}
+
+#[test]
+fn test_network_override() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", REFERENCE, "--network-override", "unichain"]).unwrap();
+    let configs = cli.configs().expect("Overridden config must validate");
+    assert_eq!(configs[0].network_name, "unichain");
+    assert_eq!(configs[0].chain_id, 130);
+
+    let cli = MakerCli::try_parse_from(["maker", "--config", REFERENCE, "--network-override", "solana"]).unwrap();
+    assert!(cli.configs().is_err(), "Unsupported network");
+}
+
+#[test]
+fn test_missing_config_fails() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", "config/missing.toml"]).unwrap();
+    assert!(cli.configs().is_err());
+}
+
+#[test]
+fn test_monitor_defaults() {
+    let cli = MonitorCli::try_parse_from(["monitor"]).unwrap();
+    assert_eq!(cli.secrets, DEFAULT_MONITOR_SECRET_PATH);
+    assert!(cli.log_level.is_none());
+    assert!(cli.command.is_none());
+}
+
+#[test]
+fn test_monitor_retry_command() {
+    let cli = MonitorCli::try_parse_from(["monitor", "--secrets", "x.env", "retry", "--limit", "10"]).unwrap();
+    assert_eq!(cli.secrets, "x.env");
+    assert!(matches!(cli.command, Some(MonitorCommand::Retry { limit: Some(10) })));
+    assert!(matches!(MonitorCli::try_parse_from(["monitor", "retry"]).unwrap().command, Some(MonitorCommand::Retry { limit: None })));
+    assert!(MonitorCli::try_parse_from(["monitor", "replay"]).is_err());
+}
@@ -0,0 +1,15 @@
+//! Shared test fixtures: the tokens, pools and mocks of `shd::testing`, and a SQLite database with the monitor tables.
+#![allow(dead_code)]
+
+use sea_orm::{Database, DatabaseConnection};
+use shd::data::migration::migrate;
+pub use shd::testing::*;
+
+/// Opens a SQLite database file with the monitor tables, created by the migrations.
+pub async fn sqlite(name: &str) -> (DatabaseConnection, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("mkmk-monitor-{}-{}.sqlite", name, std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+    migrate(&db).await.unwrap();
+    (db, path)
+}
@@ -0,0 +1,84 @@
+//! Concurrent readjustments: the readjustments of a block are sized concurrently, the slow balance fetches overlapping,
+//! while the orders keep the spread order and the content of a sequential run.
+mod common;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use common::{context, maker, pool, readjustment, MockBalances};
+use shd::maker::tycho::PoolBalances;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::{ExecutionOrder, Inventory};
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+const DELAY: Duration = Duration::from_millis(200);
+const REFERENCE: f64 = 3_000.0;
+
+/// Pool balances answering each component after `DELAY`, as a slow Tycho RPC would.
+struct SlowBalances(MockBalances);
+
+#[async_trait]
+impl PoolBalances for SlowBalances {
+    async fn get(&self, cp: &ProtocolComponent) -> Option<HashMap<String, u128>> {
+        tokio::time::sleep(DELAY).await;
+        self.0.get(cp).await
+    }
+}
+
+fn config(concurrency: usize) -> MarketMakerConfig {
+    let mut config = common::config();
+    config.min_executable_spread_bps = 0.0;
+    config.max_token_exposure_pct = 100.0;
+    config.readjust_concurrency = concurrency;
+    config
+}
+
+/// Orders of four pools quoting above the reference, listed out of spread order, and the time taken.
+async fn orders(concurrency: usize) -> (Vec<ExecutionOrder>, Duration) {
+    let mk = maker(config(concurrency));
+    let spots = [3_060., 3_030., 3_090., 3_045.];
+    let targets = spots
+        .iter()
+        .enumerate()
+        .map(|(i, spot)| pool(&format!("0xaaaa00000000000000000000000000000000000{}", i + 1), 1_000., 1_000. * spot, 0.003))
+        .collect::<Vec<_>>();
+    let adjustments = targets.iter().zip(spots).map(|(psc, spot)| readjustment(psc, spot, REFERENCE)).collect::<Vec<_>>();
+    let inventory = Inventory {
+        base_balance: 100 * 10u128.pow(18),
+        quote_balance: 300_000 * 10u128.pow(6),
+        nonce: 0,
+        native_balance: 0,
+    };
+    let balances = SlowBalances(MockBalances::of(&targets));
+    let start = Instant::now();
+    let orders = mk.readjust(context(REFERENCE, 1.0, 100), inventory, adjustments, &balances).await;
+    (orders, start.elapsed())
+}
+
+#[tokio::test]
+async fn test_slow_balances_fetched_concurrently() {
+    let (orders, elapsed) = orders(4).await;
+    assert_eq!(orders.len(), 4, "Every pool readjusted");
+    assert!(elapsed < DELAY * 2, "{:?}, a sequential fetch takes {:?}", elapsed, DELAY * 4);
+}
+
+#[tokio::test]
+async fn test_orders_in_spread_order() {
+    let (orders, _) = orders(4).await;
+    let spreads = orders.iter().map(|o| o.adjustment.spread_bps).collect::<Vec<_>>();
+    assert!(spreads.windows(2).all(|w| w[0] <= w[1]), "{:?}", spreads);
+}
+
+#[tokio::test]
+async fn test_same_orders_as_sequential() {
+    let (sequential, _) = orders(1).await;
+    let (concurrent, _) = orders(4).await;
+    assert_eq!(sequential.len(), concurrent.len());
+    for (a, b) in sequential.iter().zip(&concurrent) {
+        assert_eq!(a.adjustment.psc.component.id, b.adjustment.psc.component.id);
+        assert_eq!(a.calculation.amount_in_raw, b.calculation.amount_in_raw);
+        assert_eq!(a.calculation.amount_out_raw, b.calculation.amount_out_raw);
+        assert_eq!(a.calculation.profit_delta_bps, b.calculation.profit_delta_bps);
+    }
+}
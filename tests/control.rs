@@ -0,0 +1,87 @@
+use shd::data::sub::parse_control;
+use shd::maker::control::Control;
+use shd::types::moni::{ControlCommand, NewPricesMessage, TradingState};
+use shd::utils::uptime::heartbeat_url;
+
+#[test]
+fn test_pause_resume_transitions() {
+    let control = Control::default();
+    assert_eq!(control.state(), TradingState::Running);
+
+    assert_eq!(control.apply(ControlCommand::Pause), TradingState::Paused);
+    assert!(control.is_paused());
+    assert_eq!(control.apply(ControlCommand::Pause), TradingState::Paused, "Pause is idempotent");
+
+    assert_eq!(control.apply(ControlCommand::Resume), TradingState::Running);
+    assert!(!control.is_paused());
+}
+
+#[test]
+fn test_kill_is_final_and_shared_by_clones() {
+    let control = Control::default();
+    let listener = control.clone();
+    listener.apply(ControlCommand::Pause);
+    listener.apply(ControlCommand::Kill);
+
+    assert!(control.is_killed(), "Clones share the same state");
+    assert_eq!(control.apply(ControlCommand::Resume), TradingState::Killed);
+    assert_eq!(control.apply(ControlCommand::Pause), TradingState::Killed);
+}
+
+#[test]
+fn test_refresh_inventory_is_consumed_once() {
+    let control = Control::default();
+    control.apply(ControlCommand::Pause);
+    assert_eq!(control.apply(ControlCommand::RefreshInventory), TradingState::Paused, "Refresh leaves the trading state");
+    assert!(control.take_refresh());
+    assert!(!control.take_refresh());
+    assert_eq!(parse_control("refresh_inventory").expect("Refresh command").command, ControlCommand::RefreshInventory);
+}
+
+#[tokio::test]
+async fn test_killed_resolves_on_kill() {
+    let control = Control::default();
+    let waiter = control.clone();
+    let handle = tokio::spawn(async move { waiter.killed().await });
+    control.apply(ControlCommand::Pause);
+    control.apply(ControlCommand::Kill);
+    tokio::time::timeout(std::time::Duration::from_secs(1), handle)
+        .await
+        .expect("killed() must resolve")
+        .expect("Task must not panic");
+}
+
+#[test]
+fn test_parse_control_commands() {
+    let message = parse_control("pause").expect("Bare command");
+    assert_eq!(message.command, ControlCommand::Pause);
+    assert!(message.identifier.is_none());
+
+    let message = parse_control(" KILL\n").expect("Case and whitespace insensitive");
+    assert_eq!(message.command, ControlCommand::Kill);
+
+    let message = parse_control(r#"{"command": "resume", "identifier": "mmc-eth-usdc"}"#).expect("JSON command");
+    assert_eq!(message.command, ControlCommand::Resume);
+    assert_eq!(message.identifier.as_deref(), Some("mmc-eth-usdc"));
+
+    assert!(parse_control("stop").is_err());
+    assert!(parse_control(r#"{"command": "stop"}"#).is_err());
+}
+
+#[test]
+fn test_heartbeat_url_reports_states() {
+    let endpoint = "https://uptime.example.com/api/push/abc";
+    assert_eq!(heartbeat_url(endpoint, &[]), endpoint);
+    assert_eq!(heartbeat_url(endpoint, &[TradingState::Paused]), format!("{}?state=paused", endpoint));
+    assert_eq!(
+        heartbeat_url("https://uptime.example.com/api/push/abc?status=up", &[TradingState::Running, TradingState::Killed]),
+        "https://uptime.example.com/api/push/abc?status=up&state=running,killed"
+    );
+}
+
+#[test]
+fn test_prices_message_state_defaults_to_running() {
+    let raw = r#"{"identifier": "mmc", "reference_price": 2500.0, "components": [], "block": 1}"#;
+    let message: NewPricesMessage = serde_json::from_str(raw).expect("Messages published before the state field must still parse");
+    assert_eq!(message.state, TradingState::Running);
+}
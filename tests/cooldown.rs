@@ -0,0 +1,55 @@
+use shd::maker::cooldown::PoolCooldown;
+
+const POOL: &str = "0xB4E16D0168E52D35CACD2C6185B44281EC28C9DC";
+const OTHER: &str = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+
+#[test]
+fn test_pool_skipped_during_window_then_re_enabled() {
+    let mut cooldown = PoolCooldown::new(3);
+    cooldown.record(POOL, 100);
+
+    assert!(cooldown.is_cooling(POOL, 100));
+    assert!(cooldown.is_cooling(POOL, 102));
+    assert!(!cooldown.is_cooling(POOL, 103), "Window of 3 blocks elapsed");
+    assert!(!cooldown.is_cooling(OTHER, 101), "Other pools are not affected");
+}
+
+#[test]
+fn test_ids_are_case_insensitive() {
+    let mut cooldown = PoolCooldown::new(3);
+    cooldown.record(POOL, 100);
+    assert!(cooldown.is_cooling(&POOL.to_lowercase(), 101));
+}
+
+#[test]
+fn test_state_update_after_inclusion_lifts_cooldown() {
+    let mut cooldown = PoolCooldown::new(10);
+    cooldown.record(POOL, 100);
+
+    // Update at the inclusion block may predate our trade in the indexed state
+    cooldown.observe(POOL, 100);
+    assert!(cooldown.is_cooling(POOL, 101));
+
+    cooldown.observe(POOL, 101);
+    assert!(!cooldown.is_cooling(POOL, 101), "State newer than the inclusion block reflects our trade");
+    assert!(cooldown.is_empty());
+}
+
+#[test]
+fn test_prune_drops_elapsed_entries() {
+    let mut cooldown = PoolCooldown::new(2);
+    cooldown.record(POOL, 100);
+    cooldown.record(OTHER, 101);
+
+    cooldown.prune(102);
+    assert_eq!(cooldown.len(), 1);
+    assert!(cooldown.is_cooling(OTHER, 102));
+}
+
+#[test]
+fn test_zero_blocks_disables_cooldown() {
+    let mut cooldown = PoolCooldown::new(0);
+    cooldown.record(POOL, 100);
+    assert!(!cooldown.is_cooling(POOL, 100));
+    assert!(cooldown.is_empty());
+}
@@ -0,0 +1,94 @@
+//! Crash capture: records kept across restarts, counted over the last hour, and the crash loop threshold.
+use shd::types::config::load_market_maker_config;
+use shd::types::moni::{AlertKind, NewAlertMessage};
+use shd::utils::constants::CRASH_LOG_RETENTION_MS;
+use shd::utils::crash::{exceeded, guard, install, load, recent, record, Crash};
+
+const NOW: u64 = 1_750_000_000_000;
+const HOUR: u64 = 3_600_000;
+
+fn path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("mkmk-crash-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn crash(timestamp_ms: u64) -> Crash {
+    Crash {
+        timestamp_ms,
+        identifiers: vec!["mainnet-eth-usdc".to_string()],
+        message: "index out of bounds".to_string(),
+        location: "src/shd/maker/impl.rs:42:9".to_string(),
+        backtrace: "0: shd::maker::impl".to_string(),
+    }
+}
+
+#[test]
+fn test_recorded_and_counted_over_the_window() {
+    let file = path("window");
+    assert_eq!(recent(&file, NOW, HOUR), 0, "No log yet");
+    for timestamp in [NOW - 2 * HOUR, NOW - HOUR + 1, NOW - 60_000, NOW] {
+        record(&file, &crash(timestamp)).unwrap();
+    }
+    assert_eq!(recent(&file, NOW, HOUR), 3);
+    assert_eq!(load(&file)[3], crash(NOW));
+
+    // Records past the retention are dropped at the next crash
+    record(&file, &crash(NOW + CRASH_LOG_RETENTION_MS - 60_000)).unwrap();
+    assert_eq!(
+        load(&file).iter().map(|crash| crash.timestamp_ms).collect::<Vec<u64>>(),
+        vec![NOW - 60_000, NOW, NOW + CRASH_LOG_RETENTION_MS - 60_000]
+    );
+
+    // Unreadable lines are skipped
+    let mut content = std::fs::read_to_string(&file).unwrap();
+    content.push_str("not json\n");
+    std::fs::write(&file, content).unwrap();
+    assert_eq!(load(&file).len(), 3);
+}
+
+#[test]
+fn test_crash_loop_threshold() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    assert_eq!(config.max_crashes_per_hour, 3);
+    assert!(!exceeded(3, config.max_crashes_per_hour));
+    assert!(exceeded(4, config.max_crashes_per_hour));
+    assert!(!exceeded(100, 0), "0 never pauses");
+}
+
+#[test]
+fn test_crash_alert_serialization() {
+    let alert = NewAlertMessage {
+        identifier: "mainnet-eth-usdc".to_string(),
+        kind: AlertKind::Crash,
+        message: "Panic at src/shd/maker/impl.rs:42:9: index out of bounds".to_string(),
+        pnl: None,
+    };
+    let value = serde_json::to_value(&alert).unwrap();
+    assert_eq!(value["kind"], "Crash");
+    assert_eq!(serde_json::from_value::<NewAlertMessage>(value).unwrap().kind, AlertKind::Crash);
+}
+
+#[tokio::test]
+async fn test_only_escaping_panics_recorded() {
+    let file = path("escape");
+    std::env::set_var("CRASH_LOG_PATH", &file);
+    install(vec!["mainnet-eth-usdc".to_string()], false);
+
+    // Caught on the way, the process goes on
+    assert!(tokio::spawn(async { panic!("caught by the runtime") }).await.unwrap_err().is_panic());
+    assert!(std::panic::catch_unwind(|| panic!("caught by catch_unwind")).is_err());
+    assert_eq!(guard(async { 42 }).await, 42);
+    assert!(load(&file).is_empty(), "Caught panics are not crashes");
+
+    // Escaping the main future
+    let escaped = tokio::spawn(guard(async {
+        panic!("escaped");
+    }))
+    .await;
+    assert!(escaped.unwrap_err().is_panic(), "Unwind resumed");
+    let crashes = load(&file);
+    assert_eq!(crashes.len(), 1);
+    assert_eq!((crashes[0].message.as_str(), crashes[0].identifiers.clone()), ("escaped", vec!["mainnet-eth-usdc".to_string()]));
+    assert!(crashes[0].location.contains("crash.rs"), "{}", crashes[0].location);
+}
@@ -0,0 +1,98 @@
+//! Dead letters: messages that cannot be stored are kept with their error, and `monitor retry` replays them.
+mod common;
+
+use common::sqlite;
+use sea_orm::{ConnectionTrait, EntityTrait};
+use shd::data::dead_letter::{dead_lettered, deliver, record, retry, RetryReport};
+use shd::data::neon::{pull, Neon};
+use shd::entity::dead_letter;
+use shd::types::config::load_market_maker_config;
+use shd::types::moni::{MessageType, NewInstanceMessage, NewPricesMessage, RedisMessage, TradingState};
+
+fn payload(message: MessageType, data: serde_json::Value) -> String {
+    serde_json::to_string(&RedisMessage {
+        message,
+        timestamp: 0,
+        data,
+        mirrored: false,
+    })
+    .unwrap()
+}
+
+fn prices() -> String {
+    let msg = NewPricesMessage {
+        identifier: "unknown".to_string(),
+        reference_price: 2500.0,
+        components: vec![],
+        block: 1,
+        depth: None,
+        state: TradingState::Running,
+        sigma_bps: 0.0,
+        execution_threshold_bps: 0.0,
+        adaptive_bps: 0.0,
+        quarantined: 0,
+        stream_lag_blocks: None,
+        degraded: false,
+    };
+    payload(MessageType::NewPrices, serde_json::to_value(msg).unwrap())
+}
+
+#[tokio::test]
+async fn test_unparseable_message_is_dead_lettered() {
+    let (db, path) = sqlite("dead-unparseable").await;
+    let neon = Neon::from_connection(db.clone());
+    deliver(&neon, "{\"message\": \"new_trade\", \"timestamp\": 0, \"data\": 3}").await.unwrap();
+    let letters = dead_letter::Entity::find().all(&db).await.unwrap();
+    assert_eq!(letters.len(), 1);
+    assert!(letters[0].error.contains("NewTrade"), "{}", letters[0].error);
+    assert!(letters[0].resolved_at.is_none());
+    assert!(dead_lettered() >= 1);
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_failed_insert_is_retried_then_dead_lettered() {
+    let (db, path) = sqlite("dead-failed").await;
+    let neon = Neon::from_connection(db.clone());
+    // Handled without error: an unknown instance is skipped, not dead-lettered
+    deliver(&neon, &prices()).await.unwrap();
+    assert!(dead_letter::Entity::find().all(&db).await.unwrap().is_empty());
+
+    db.execute_unprepared("DROP TABLE \"Instance\"").await.unwrap();
+    deliver(&neon, &prices()).await.unwrap();
+    let letters = dead_letter::Entity::find().all(&db).await.unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].attempts, 3, "dead-lettered after the bounded retries");
+    assert_eq!(letters[0].payload, prices());
+
+    // Database down: neither stored nor dead-lettered, the caller keeps the entry
+    db.execute_unprepared("DROP TABLE \"DeadLetter\"").await.unwrap();
+    assert!(deliver(&neon, &prices()).await.is_err());
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_retry_replays_dead_letters() {
+    let (db, path) = sqlite("dead-retry").await;
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let instance = NewInstanceMessage {
+        identifier: format!("{}-instance-1", config.id()),
+        config,
+        commit: "test".to_string(),
+    };
+    record(&db, &payload(MessageType::NewInstance, serde_json::to_value(instance).unwrap()), "connection reset", 3)
+        .await
+        .unwrap();
+    record(&db, "not json", "Failed to parse Redis message", 0).await.unwrap();
+
+    assert_eq!(retry(&db, None).await.unwrap(), RetryReport { replayed: 1, failed: 1 });
+    assert_eq!(pull::instances(&db).await.unwrap().len(), 1);
+    // Resolved letters are not replayed again, failing ones are, with their attempts counted
+    assert_eq!(retry(&db, Some(10)).await.unwrap(), RetryReport { replayed: 0, failed: 1 });
+    let letters = dead_letter::Entity::find().all(&db).await.unwrap();
+    let failing = letters.iter().find(|l| l.payload == "not json").unwrap();
+    assert_eq!(failing.attempts, 2);
+    assert!(failing.resolved_at.is_none());
+    assert_eq!(pull::instances(&db).await.unwrap().len(), 1);
+    std::fs::remove_file(path).ok();
+}
@@ -0,0 +1,97 @@
+//! Property tests: AMM fees scaled into bps per protocol, and decimal scaling between normalized and raw amounts.
+mod common;
+
+use common::{base, component, quote};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use proptest::prelude::*;
+use shd::maker::tycho::amm_fee_to_bps;
+use shd::opti::math::{normalized, powered, shift_bps};
+use tycho_common::Bytes;
+
+/// Component of `protocol_system` with a `fee` static attribute, big-endian like the Tycho attributes.
+fn fee(protocol_system: &str, fee: u128) -> u128 {
+    let mut cp = component("0xaaaa000000000000000000000000000000000001", protocol_system, vec![base(), quote()]);
+    cp.static_attributes.insert("fee".to_string(), Bytes::from(fee.to_be_bytes().to_vec()));
+    amm_fee_to_bps(cp)
+}
+
+proptest! {
+    #[test]
+    fn test_v2_fees_already_in_bps(bps in 0u128..=10_000) {
+        for protocol in ["uniswap_v2", "sushiswap_v2", "pancakeswap_v2"] {
+            prop_assert_eq!(fee(protocol, bps), bps);
+        }
+    }
+
+    #[test]
+    fn test_concentrated_fees_in_pips(pips in 0u128..=1_000_000) {
+        for protocol in ["uniswap_v3", "uniswap_v4", "pancakeswap_v3"] {
+            let bps = fee(protocol, pips);
+            prop_assert!(bps <= 10_000);
+            prop_assert_eq!(bps, pips / 100);
+        }
+    }
+
+    #[test]
+    fn test_balancer_fees_scaled_by_1e18(wad in 0u128..=1_000_000_000_000_000_000) {
+        let bps = fee("vm:balancer_v2", wad);
+        prop_assert!(bps <= 10_000);
+        prop_assert_eq!(bps, wad / 100_000_000_000_000);
+    }
+
+    #[test]
+    fn test_fees_never_overflow(raw in any::<u128>()) {
+        for protocol in ["uniswap_v2", "uniswap_v3", "vm:balancer_v2", "vm:curve", "ekubo_v2"] {
+            fee(protocol, raw);
+        }
+        prop_assert_eq!(fee("ekubo_v2", raw), 0);
+    }
+
+    #[test]
+    fn test_curve_fees_scaled_by_1e10(raw in 1u128..=10_000_000_000) {
+        let bps = fee("vm:curve", raw);
+        prop_assert!(bps <= 10_000);
+        prop_assert_eq!(bps, raw / 1_000_000);
+        // No fee attribute: 4 bps assumed
+        prop_assert_eq!(fee("vm:curve", 0), 4);
+    }
+
+    #[test]
+    fn test_powered_round_trips(amount in 0f64..1e12, decimals in 0u32..=24) {
+        let raw = powered(amount, decimals);
+        prop_assert!(raw.to_u128().is_some(), "Realistic magnitudes fit u128");
+        let back = normalized(&raw, decimals);
+        // Floored to the smallest unit, then one f64 rounding
+        prop_assert!(back <= amount * (1. + 1e-15));
+        prop_assert!(amount - back <= 10f64.powi(-(decimals as i32)) + amount * 1e-15, "{} -> {} -> {}", amount, raw, back);
+    }
+
+    #[test]
+    fn test_powered_exact_past_2_pow_53(units in 1u64..(1u64 << 53), decimals in 0u32..=24) {
+        // Whole token amounts scale exactly, where `(amount * 10^decimals) as u128` rounds through f64
+        prop_assert_eq!(powered(units as f64, decimals), BigUint::from(units) * BigUint::from(10u32).pow(decimals));
+    }
+
+    #[test]
+    fn test_slippage_bounds(raw in any::<u128>(), bps in 0f64..10_000.) {
+        let amount = BigUint::from(raw);
+        let lower = shift_bps(&amount, -bps);
+        prop_assert!(lower <= amount);
+        let expected = raw as f64 * (1. - bps / 10_000.);
+        prop_assert!((lower.to_f64().unwrap() - expected).abs() <= raw as f64 * 1e-8 + 1.);
+        prop_assert!(shift_bps(&amount, bps) >= amount);
+    }
+}
+
+#[test]
+fn test_scaling_edge_cases() {
+    assert_eq!(powered(0., 18), BigUint::default());
+    assert_eq!(powered(-1., 18), BigUint::default());
+    assert_eq!(powered(f64::NAN, 18), BigUint::default());
+    assert_eq!(powered(1.5, 6), BigUint::from(1_500_000u32));
+    assert_eq!(powered(0.1, 18), BigUint::from(100_000_000_000_000_005u128), "0.1 is stored slightly above 1/10");
+    assert_eq!(shift_bps(&BigUint::from(1_000_000u32), 0.), BigUint::from(1_000_000u32));
+    assert_eq!(shift_bps(&BigUint::from(1_000_000u32), -5.), BigUint::from(999_500u32));
+    assert_eq!(normalized(&BigUint::from(1_500_000u32), 6), 1.5);
+}
@@ -0,0 +1,507 @@
+//! Golden encoding: a frozen order, context and inventory produce the same Solution and transaction requests,
+//! byte for byte, with the Tycho encoder stubbed.
+mod common;
+
+use std::{collections::HashMap, str::FromStr};
+
+use alloy::primitives::TxKind;
+use alloy::rpc::types::TransactionRequest;
+use alloy_primitives::{keccak256, Address, U256};
+use common::{context, maker, pool, readjustment};
+use num_bigint::BigUint;
+use shd::maker::allowance::RouterAllowances;
+use shd::maker::permit2::{permit, SignedPermit};
+use shd::maker::tycho::{with_rpc_url, SolutionEncoder, TychoEncoder};
+use shd::opti::math::TerminationReason;
+use shd::types::config::load_market_maker_config;
+use shd::types::maker::{ExecutionOrder, Inventory, MarketMaker, SwapCalculation, Trade, TradeData, TradeStatus};
+use shd::utils::constants::{APPROVE_FN_SIGNATURE, DEFAULT_APPROVE_GAS, DEFAULT_SWAP_GAS, NATIVE_ADDRESS, NULL_ADDRESS};
+use shd::utils::evm::is_native;
+use tycho_common::Bytes;
+use tycho_execution::encoding::models::{EncodedSolution, Solution};
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const WALLET: &str = "0x0af694c17137ad1de34e94335ea09608b715f20a";
+const ROUTER: &str = "0xfd0b31d2e955fa55e3fa641fe90e08b677188d35";
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+const SWAP_DATA: [u8; 6] = [0xde, 0xad, 0xbe, 0xef, 0x00, 0x01];
+const NONCE: u64 = 7;
+const GWEI: u128 = 2_000_000_000;
+
+/// Tycho encoder stub: the router and a fixed swap data for every solution.
+struct StubEncoder;
+
+impl SolutionEncoder for StubEncoder {
+    fn encode(&self, solutions: Vec<Solution>) -> Result<Vec<EncodedSolution>, String> {
+        Ok(solutions
+            .iter()
+            .map(|_| EncodedSolution {
+                swaps: SWAP_DATA.to_vec(),
+                interacting_with: Bytes::from_str(ROUTER).unwrap(),
+                function_signature: "singleSwap(uint256,address,address,uint256,bool,bool,address,bool,bytes)".to_string(),
+                n_tokens: 2,
+                permit: None,
+            })
+            .collect())
+    }
+}
+
+fn mk(infinite_approval: bool) -> MarketMaker {
+    let mut config = common::config();
+    config.infinite_approval = infinite_approval;
+    config.auto_wrap_native = false;
+    config.unwrap_to_native_above = 0.;
+    maker(config)
+}
+
+/// Sells 1.5 ETH for 4500 USDC on a pool quoting 3030 against a 3000 reference. Exact out buys its 4495 USDC target
+/// with the 1.5 ETH of its reverse quote, up to 1.50075 ETH with the 5 bps slippage tolerance.
+fn order(exact_out: bool) -> ExecutionOrder {
+    let psc = pool(POOL, 1_000.0, 3_030_000.0, 0.003);
+    let out_min = if exact_out { 4_495.0 } else { 4_497.75 };
+    let in_max = if exact_out { 1_500_750_000_000_000_000u128 } else { 1_500_000_000_000_000_000u128 };
+    ExecutionOrder {
+        trade_id: "golden-1".to_string(),
+        adjustment: readjustment(&psc, 3_030.0, 3_000.0),
+        calculation: SwapCalculation {
+            base_to_quote: true,
+            selling_amount: 1.5,
+            buying_amount: 4_545.0,
+            powered_selling_amount: 1.5e18,
+            powered_buying_amount: 4_545e6,
+            amount_out_normalized: 4_500.0,
+            amount_out_powered: 4_500e6,
+            amount_out_min_normalized: out_min,
+            amount_out_min_powered: out_min * 1e6,
+            average_sell_price: 3_000.0,
+            average_sell_price_net_gas: 2_999.0,
+            gas_units: 120_000,
+            gas_cost_eth: 0.00024,
+            gas_cost_usd: 0.72,
+            gas_cost_in_output_token: 0.72,
+            selling_worth_usd: 4_500.0,
+            buying_worth_usd: 4_500.0,
+            profit_delta_bps: 3.0,
+            profitable: true,
+            termination: TerminationReason::Converged,
+            bracket_width: 0.0,
+            exact_out,
+            amount_in_max_normalized: in_max as f64 / 1e18,
+            amount_in_max_raw: BigUint::from(in_max),
+            amount_in_raw: BigUint::from(1_500_000_000_000_000_000u128),
+            amount_out_raw: BigUint::from(4_500_000_000u128),
+        },
+    }
+}
+
+fn inventory() -> Inventory {
+    Inventory {
+        base_balance: 10 * 10u128.pow(18),
+        quote_balance: 30_000 * 10u128.pow(6),
+        nonce: NONCE,
+        native_balance: 10u128.pow(18),
+    }
+}
+
+/// Trade data of `order`, on the golden context and inventory.
+fn tdata(mk: &MarketMaker, order: &ExecutionOrder) -> TradeData {
+    TradeData {
+        trade_id: order.trade_id.clone(),
+        status: TradeStatus::Pending,
+        timestamp: 0,
+        context: context(3_000.0, 2.0, 100),
+        metadata: mk.pre_trade_data(order),
+        inventory: inventory(),
+        simulation: None,
+        broadcast: None,
+        realized: None,
+    }
+}
+
+fn encode(mk: &MarketMaker, order: ExecutionOrder) -> Trade {
+    let tdata = tdata(mk, &order);
+    let mut trades = mk.encode(vec![order], vec![tdata], context(3_000.0, 2.0, 100), inventory(), &StubEncoder);
+    assert_eq!(trades.len(), 1);
+    trades.remove(0)
+}
+
+fn bytes(hex: &str) -> Bytes {
+    Bytes::from_str(hex).unwrap()
+}
+
+fn address(hex: &str) -> Address {
+    Address::from_str(hex).unwrap()
+}
+
+fn calldata(tx: &TransactionRequest) -> Vec<u8> {
+    tx.input.input.clone().expect("Calldata in input").to_vec()
+}
+
+/// ABI word of an address or an amount.
+fn word(value: U256) -> Vec<u8> {
+    value.to_be_bytes::<32>().to_vec()
+}
+
+#[test]
+fn test_exact_in_solution() {
+    let solution = mk(false).build_tycho_solution(order(false));
+    assert_eq!((solution.sender.clone(), solution.receiver.clone()), (bytes(WALLET), bytes(WALLET)));
+    assert_eq!((solution.given_token.clone(), solution.checked_token.clone()), (bytes(WETH), bytes(USDC)));
+    assert_eq!(solution.given_amount, BigUint::from(1_500_000_000_000_000_000u128));
+    // 4500 USDC minus the 5 bps slippage tolerance, from the raw quote
+    assert_eq!(solution.checked_amount, BigUint::from(4_497_750_000u128));
+    assert!(!solution.exact_out);
+    assert_eq!(solution.swaps.len(), 1);
+}
+
+#[test]
+fn test_exact_out_solution_and_its_router_view() {
+    let solution = mk(false).build_tycho_solution(order(true));
+    assert_eq!((solution.given_token.clone(), solution.checked_token.clone()), (bytes(USDC), bytes(WETH)));
+    assert_eq!(solution.given_amount, BigUint::from(4_495_000_000u128));
+    assert_eq!(solution.checked_amount, BigUint::from(1_500_750_000_000_000_000u128), "Max input");
+    assert!(solution.exact_out);
+
+    // The router only swaps exact in: the max input spent, the exact output as the minimum
+    let router = MarketMaker::router_solution(&solution);
+    assert_eq!((router.given_token, router.checked_token), (bytes(WETH), bytes(USDC)));
+    assert_eq!(
+        (router.given_amount, router.checked_amount),
+        (BigUint::from(1_500_750_000_000_000_000u128), BigUint::from(4_495_000_000u128))
+    );
+    assert!(!router.exact_out);
+}
+
+#[test]
+fn test_approval_and_swap_requests() {
+    let trade = encode(&mk(false), order(false));
+    assert!(trade.wrap.is_none() && trade.unwrap.is_none());
+    assert_eq!(trade.metadata.trade_id, "golden-1");
+
+    let approve = trade.approve.expect("Approval without infinite_approval");
+    assert_eq!(approve.to, Some(TxKind::Call(address(WETH))));
+    assert_eq!(approve.from, Some(address(WALLET)));
+    assert_eq!((approve.value, approve.gas, approve.chain_id, approve.nonce), (None, Some(DEFAULT_APPROVE_GAS), Some(1), Some(NONCE)));
+    assert_eq!((approve.max_fee_per_gas, approve.max_priority_fee_per_gas), (Some(GWEI), Some(GWEI)));
+    let data = calldata(&approve);
+    assert_eq!(&data[..4], &keccak256(APPROVE_FN_SIGNATURE.as_bytes())[..4]);
+    assert_eq!(hex::encode(&data[..4]), "095ea7b3");
+    assert_eq!(data[4..36].to_vec(), word(U256::from_be_slice(address(ROUTER).as_slice())));
+    assert_eq!(data[36..68].to_vec(), word(U256::from(1_500_000_000_000_000_000u128)));
+    assert_eq!(data.len(), 68);
+
+    let swap = trade.swap;
+    assert_eq!(swap.to, Some(TxKind::Call(address(ROUTER))));
+    assert_eq!(swap.from, Some(address(WALLET)));
+    assert_eq!((swap.value, swap.gas, swap.chain_id, swap.nonce), (Some(U256::ZERO), Some(DEFAULT_SWAP_GAS), Some(1), Some(NONCE + 1)));
+    assert_eq!((swap.max_fee_per_gas, swap.max_priority_fee_per_gas), (Some(GWEI), Some(GWEI)));
+    let data = calldata(&swap);
+    let selector = keccak256("singleSwap(uint256,address,address,uint256,bool,bool,address,bool,bytes)".as_bytes());
+    assert_eq!(&data[..4], &selector[..4]);
+    let head = [
+        word(U256::from(1_500_000_000_000_000_000u128)),       // amountIn
+        word(U256::from_be_slice(address(WETH).as_slice())),   // tokenIn
+        word(U256::from_be_slice(address(USDC).as_slice())),   // tokenOut
+        word(U256::from(4_497_750_000u128)),                   // minAmountOut
+        word(U256::ZERO),                                      // wrapEth
+        word(U256::ZERO),                                      // unwrapEth
+        word(U256::from_be_slice(address(WALLET).as_slice())), // receiver
+        word(U256::from(1u8)),                                 // isTransferFromAllowed
+        word(U256::from(9 * 32u64)),                           // swapData offset
+        word(U256::from(SWAP_DATA.len())),                     // swapData length
+    ]
+    .concat();
+    assert_eq!(data[4..4 + head.len()].to_vec(), head);
+    let mut tail = SWAP_DATA.to_vec();
+    tail.resize(32, 0);
+    assert_eq!(data[4 + head.len()..].to_vec(), tail);
+}
+
+#[test]
+fn test_infinite_approval_and_exact_out_requests() {
+    let trade = encode(&mk(true), order(false));
+    assert!(trade.approve.is_none());
+    assert_eq!(trade.swap.nonce, Some(NONCE), "Swap first without approval");
+
+    // Exact out, sent as its router view: the 1.50075 ETH max input approved and spent for at least the 4495 USDC target
+    let trade = encode(&mk(false), order(true));
+    let approve = trade.approve.unwrap();
+    assert_eq!(calldata(&approve)[36..68].to_vec(), word(U256::from(1_500_750_000_000_000_000u128)));
+    let data = calldata(&trade.swap);
+    assert_eq!(data[4..36].to_vec(), word(U256::from(1_500_750_000_000_000_000u128)));
+    assert_eq!(data[100..132].to_vec(), word(U256::from(4_495_000_000u128)));
+}
+
+#[test]
+fn test_cached_allowance_skips_the_approval() {
+    // Covering the 1.5 ETH input: swap alone, on the first free nonce
+    let mut mm = mk(false);
+    mm.allowances.store(&WETH.to_uppercase(), 2_000_000_000_000_000_000);
+    let trade = encode(&mm, order(false));
+    assert!(trade.approve.is_none());
+    assert_eq!(trade.swap.nonce, Some(NONCE));
+
+    // Short of the input: approval first, swap on the next nonce
+    mm.allowances.store(WETH, 1_000_000_000_000_000_000);
+    let trade = encode(&mm, order(false));
+    assert_eq!(trade.approve.map(|approve| approve.nonce), Some(Some(NONCE)));
+    assert_eq!(trade.swap.nonce, Some(NONCE + 1));
+
+    // Exact out sends its max input, above the 1.5 ETH of its reverse quote
+    mm.allowances.store(WETH, 1_500_000_000_000_000_000);
+    assert!(encode(&mm, order(true)).approve.is_some());
+    assert_eq!(MarketMaker::router_input(&order(true)), 1_500_750_000_000_000_000);
+    assert_eq!(MarketMaker::router_input(&order(false)), 1_500_000_000_000_000_000);
+}
+
+#[test]
+fn test_allowance_settled_from_our_trades() {
+    let mut allowances = RouterAllowances::default();
+    assert!(!allowances.covers(WETH, 1), "Never read: approved");
+
+    allowances.store(WETH, 5_000);
+    allowances.settle(WETH, 2_000, false, true);
+    assert!(allowances.covers(WETH, 3_000) && !allowances.covers(WETH, 3_001), "Spent by the swap");
+
+    allowances.settle(WETH, 1_000, false, false);
+    assert!(!allowances.covers(WETH, 1), "Failed swap: unknown until the next read");
+
+    allowances.store(WETH, u128::MAX);
+    allowances.settle(WETH, 2_000, false, true);
+    assert!(allowances.covers(WETH, u128::MAX), "Max allowance never spent");
+
+    allowances.store(USDC, 10_000);
+    allowances.settle(USDC, 2_000, true, true);
+    assert!(!allowances.covers(USDC, 1), "Approved amount spent by the swap");
+    allowances.invalidate();
+    assert!(allowances.is_empty());
+}
+
+#[test]
+fn test_permit2_swap_without_approval() {
+    let mut config = mk(false).config;
+    config.permit2_approval = true;
+    let mk = maker(config);
+    let order = order(false);
+    let context = context(3_000.0, 2.0, 100);
+    let tdata = TradeData {
+        trade_id: order.trade_id.clone(),
+        status: TradeStatus::Pending,
+        timestamp: 0,
+        context: context.clone(),
+        metadata: mk.pre_trade_data(&order),
+        inventory: inventory(),
+        simulation: None,
+        broadcast: None,
+        realized: None,
+    };
+    let signed = SignedPermit {
+        permit: permit(address(WETH), 1_500_000_000_000_000_000, 1_700_000_000, 3, address(ROUTER)),
+        signature: vec![0xab; 65],
+    };
+    let permits = HashMap::from([(order.trade_id.clone(), signed)]);
+    let mut trades = mk.encode_with_permits(vec![order.clone()], vec![tdata.clone()], context.clone(), inventory(), &StubEncoder, &permits);
+    assert_eq!(trades.len(), 1);
+    let trade = trades.remove(0);
+    assert!(trade.approve.is_none(), "No approval with a signed permit");
+    assert_eq!(trade.swap.nonce, Some(NONCE));
+
+    let data = calldata(&trade.swap);
+    assert_eq!(hex::encode(&data[..4]), "30ace1b1", "singleSwapPermit2");
+    let head = [
+        word(U256::from(1_500_000_000_000_000_000u128)),       // amountIn
+        word(U256::from_be_slice(address(WETH).as_slice())),   // tokenIn
+        word(U256::from_be_slice(address(USDC).as_slice())),   // tokenOut
+        word(U256::from(4_497_750_000u128)),                   // minAmountOut
+        word(U256::ZERO),                                      // wrapEth
+        word(U256::ZERO),                                      // unwrapEth
+        word(U256::from_be_slice(address(WALLET).as_slice())), // receiver
+        word(U256::from_be_slice(address(WETH).as_slice())),   // permitSingle.details.token
+        word(U256::from(1_500_000_000_000_000_000u128)),       // permitSingle.details.amount
+        word(U256::from(1_700_000_000u64)),                    // permitSingle.details.expiration
+        word(U256::from(3u8)),                                 // permitSingle.details.nonce
+        word(U256::from_be_slice(address(ROUTER).as_slice())), // permitSingle.spender
+        word(U256::from(1_700_000_000u64)),                    // permitSingle.sigDeadline
+        word(U256::from(15 * 32u64)),                          // signature offset
+        word(U256::from(19 * 32u64)),                          // swapData offset
+        word(U256::from(65u8)),                                // signature length
+    ]
+    .concat();
+    assert_eq!(data[4..4 + head.len()].to_vec(), head);
+    assert_eq!(data[4 + head.len()..4 + head.len() + 65].to_vec(), vec![0xab; 65]);
+
+    // Without its permit the trade is skipped rather than sent without allowance
+    assert!(mk.encode_with_permits(vec![order], vec![tdata], context, inventory(), &StubEncoder, &HashMap::new()).is_empty());
+}
+
+#[test]
+fn test_encoder_failure_produces_no_trade() {
+    struct Failing;
+    impl SolutionEncoder for Failing {
+        fn encode(&self, _solutions: Vec<Solution>) -> Result<Vec<EncodedSolution>, String> {
+            Err("Failed to encode solutions: stub".to_string())
+        }
+    }
+    let mk = mk(false);
+    let order = order(false);
+    let context = context(3_000.0, 2.0, 100);
+    assert!(mk.encode(vec![order], vec![], context.clone(), inventory(), &Failing).is_empty());
+    assert!(mk.encode(vec![], vec![], context, inventory(), &StubEncoder).is_empty());
+}
+
+#[test]
+fn test_encoded_min_out_follows_the_slippage_config() {
+    // Golden minAmountOut of the 4500 USDC quote for each max_slippage_pct, the router reverting below it
+    for (slippage, min_out) in [(0.0005, 4_497_750_000u128), (0.003, 4_486_500_000), (0.01, 4_455_000_000), (0., 4_500_000_000)] {
+        let mut config = mk(true).config;
+        config.max_slippage_pct = slippage;
+        let mk = maker(config);
+        let order = order(false);
+        assert_eq!(mk.build_tycho_solution(order.clone()).checked_amount, BigUint::from(min_out));
+        let data = calldata(&encode(&mk, order.clone()).swap);
+        assert_eq!(data[100..132].to_vec(), word(U256::from(min_out)), "minAmountOut at {} slippage", slippage);
+        assert_eq!(mk.pre_trade_data(&order).slippage_tolerance_bps, slippage * 10_000.);
+    }
+}
+
+#[test]
+fn test_swap_deadline() {
+    // Mainnet reference config: inclusion_block_delay = 1
+    let mut config = mk(true).config;
+    assert_eq!(config.deadline_grace_blocks, 2);
+    assert_eq!(config.valid_until(100), 103);
+    // The permit expires with the swap deadline (3 blocks of 12s), not after the 30 min permit lifetime
+    assert_eq!(config.permit_expiration(1_700_000_000), 1_700_000_036);
+
+    config.inclusion_block_delay = 0;
+    config.deadline_grace_blocks = 0;
+    assert_eq!(config.valid_until(100), 100);
+    assert_eq!(config.permit_expiration(1_700_000_000), 1_700_000_012, "At least one block");
+    config.permit2_expiration_s = 5;
+    assert_eq!(config.permit_expiration(1_700_000_000), 1_700_000_005);
+
+    let unichain = load_market_maker_config("config/unichain.eth-usdc.toml").expect("Unichain config must load");
+    assert_eq!(unichain.valid_until(100), 102);
+    assert_eq!(unichain.permit_expiration(1_700_000_000), 1_700_000_002);
+}
+
+/// The golden order selling native ETH (the zero address sentinel) instead of WETH.
+fn native_order() -> ExecutionOrder {
+    let mut order = order(false);
+    order.adjustment.selling.address = bytes(NULL_ADDRESS);
+    order
+}
+
+#[test]
+fn test_erc20_input_carries_no_value() {
+    let trade = encode(&mk(false), order(false));
+    assert!(trade.approve.is_some());
+    assert_eq!(trade.swap.value, Some(U256::ZERO));
+    let data = calldata(&trade.swap);
+    assert_eq!(data[4 + 7 * 32..4 + 8 * 32].to_vec(), word(U256::from(1u8)), "isTransferFromAllowed");
+}
+
+#[test]
+fn test_native_input_sent_as_value_without_approval() {
+    let trade = encode(&mk(false), native_order());
+    assert!(trade.approve.is_none(), "Nothing to approve on native input");
+    assert!(trade.wrap.is_none());
+    assert_eq!(trade.swap.nonce, Some(NONCE));
+    assert_eq!(trade.swap.value, Some(U256::from(1_500_000_000_000_000_000u128)));
+    assert_eq!(trade.request().calls().len(), 1);
+
+    let data = calldata(&trade.swap);
+    assert_eq!(
+        hex::encode(&data[..4]),
+        hex::encode(&keccak256("singleSwap(uint256,address,address,uint256,bool,bool,address,bool,bytes)".as_bytes())[..4])
+    );
+    let head = [
+        word(U256::from(1_500_000_000_000_000_000u128)),       // amountIn
+        word(U256::ZERO),                                      // tokenIn, native
+        word(U256::from_be_slice(address(USDC).as_slice())),   // tokenOut
+        word(U256::from(4_497_750_000u128)),                   // minAmountOut
+        word(U256::ZERO),                                      // wrapEth
+        word(U256::ZERO),                                      // unwrapEth
+        word(U256::from_be_slice(address(WALLET).as_slice())), // receiver
+        word(U256::ZERO),                                      // isTransferFromAllowed, nothing to pull
+    ]
+    .concat();
+    assert_eq!(data[4..4 + head.len()].to_vec(), head);
+
+    // With permit2_approval, no permit is needed either
+    let mut config = mk(false).config;
+    config.permit2_approval = true;
+    let mk = maker(config);
+    let order = native_order();
+    let context = context(3_000.0, 2.0, 100);
+    let tdata = TradeData {
+        trade_id: order.trade_id.clone(),
+        status: TradeStatus::Pending,
+        timestamp: 0,
+        context: context.clone(),
+        metadata: mk.pre_trade_data(&order),
+        inventory: inventory(),
+        simulation: None,
+        broadcast: None,
+        realized: None,
+    };
+    let trades = mk.encode_with_permits(vec![order], vec![tdata], context, inventory(), &StubEncoder, &HashMap::new());
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].swap.value, Some(U256::from(1_500_000_000_000_000_000u128)));
+    assert!(trades[0].approve.is_none());
+}
+
+#[test]
+fn test_eip7528_sentinel_is_native() {
+    assert!(is_native(NULL_ADDRESS));
+    assert!(is_native(NATIVE_ADDRESS) && is_native(&NATIVE_ADDRESS.to_lowercase()) && is_native(&NATIVE_ADDRESS.to_uppercase().replace("0X", "0x")));
+    assert!(!is_native(USDC));
+
+    let mut order = order(false);
+    order.adjustment.selling.address = bytes(NATIVE_ADDRESS);
+    let trade = encode(&mk(false), order);
+    assert!(trade.approve.is_none(), "Nothing to approve on native input");
+    assert_eq!(trade.swap.value, Some(U256::from(1_500_000_000_000_000_000u128)));
+    let data = calldata(&trade.swap);
+    assert_eq!(data[4 + 32..4 + 2 * 32].to_vec(), word(U256::ZERO), "tokenIn, sent to the router as the zero address");
+    assert_eq!(data[4 + 7 * 32..4 + 8 * 32].to_vec(), word(U256::ZERO), "isTransferFromAllowed, nothing to pull");
+}
+
+#[test]
+fn test_native_output_keeps_the_erc20_input_shape() {
+    let mut order = order(false);
+    order.adjustment.buying.address = bytes(NULL_ADDRESS);
+    let trade = encode(&mk(false), order);
+    assert!(trade.approve.is_some(), "The sold ERC20 is still approved");
+    assert_eq!(trade.swap.value, Some(U256::ZERO));
+    let data = calldata(&trade.swap);
+    assert_eq!(data[68..100].to_vec(), word(U256::ZERO), "tokenOut, native");
+}
+
+#[test]
+fn test_concurrent_prepares_keep_their_rpc() {
+    let before = std::env::var_os("RPC_URL");
+    let workers = ["https://rpc-a.example.org", "https://rpc-b.example.org"].map(|url| {
+        std::thread::spawn(move || {
+            let mut mk = mk(false);
+            mk.config.rpc_url = url.to_string();
+            mk.config.rpc_urls = vec![];
+            assert_eq!(TychoEncoder::of(&mk.config).expect("Supported network").rpc_url, url);
+            let prepare = || mk.prepare(vec![order(false)], vec![tdata(&mk, &order(false))], context(3_000.0, 2.0, 100), inventory(), &HashMap::new());
+            let reference = prepare();
+            assert_eq!(reference.len(), 1, "Encoded by the Tycho router encoder");
+            for _ in 0..20 {
+                let trades = prepare();
+                assert_eq!(trades.len(), 1);
+                assert_eq!(calldata(&trades[0].swap), calldata(&reference[0].swap));
+                let seen = with_rpc_url(url, || std::env::var("RPC_URL").unwrap()).expect("Encoder thread running");
+                assert_eq!(seen, url, "RPC of the other pair");
+            }
+            calldata(&reference[0].swap)
+        })
+    });
+    let [a, b] = workers.map(|worker| worker.join().unwrap());
+    assert_eq!(a, b, "Same swap whatever the RPC of the pair");
+    assert_eq!(std::env::var_os("RPC_URL"), before, "Restored");
+}
@@ -0,0 +1,177 @@
+//! Decision engine, market data and execution plugged through the builder: the default engine keeps the optimal sizing,
+//! a custom one resizes the readjustments while the rest of the pipeline (caps, profitability, orders) stays the same.
+//! A mocked market data source and execution stand for the price feed, the chain and the router encoder.
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common::{base, context, env, inventory, pool, quote, trade, watch_config, MockBalances};
+use shd::maker::engine::{DecisionEngine, Execution, FractionEngine, MarketDataSource};
+use shd::maker::exec::dry::DryRunExec;
+use shd::maker::feed::PriceFeedFactory;
+use shd::maker::latency::Deadline;
+use shd::maker::permit2::SignedPermit;
+use shd::opti::routing::TokenGraph;
+use shd::opti::skew::InventorySkew;
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::config::EnvConfig;
+use shd::types::maker::{CompReadjustment, ExecutionOrder, Inventory, MarketContext, MarketMaker, Trade, TradeData, TradeStatus};
+use shd::types::tycho::ProtoSimComp;
+use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+
+/// Engine evaluating nothing, whatever the spread.
+struct Idle;
+
+#[async_trait]
+impl DecisionEngine for Idle {
+    fn name(&self) -> String {
+        "Idle".to_string()
+    }
+
+    fn evaluate(&self, _mk: &MarketMaker, _targets: &[ProtoSimComp], _sps: Vec<f64>, _reference: f64, _skew: &InventorySkew, _block: u64) -> Vec<CompReadjustment> {
+        vec![]
+    }
+}
+
+/// Fixed market data, counting the inventory reads.
+#[derive(Default)]
+struct FixedData {
+    inventory_reads: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl MarketDataSource for FixedData {
+    fn name(&self) -> String {
+        "Fixed_Data".to_string()
+    }
+
+    async fn market_price(&self, _mk: &MarketMaker) -> Result<f64, String> {
+        Ok(REFERENCE)
+    }
+
+    async fn market_context(&self, _mk: &MarketMaker, _graph: &TokenGraph, _protosims: &HashMap<String, Box<dyn ProtocolSim>>, _tokens: Vec<Token>) -> Option<MarketContext> {
+        Some(context(REFERENCE, 1.0, 100))
+    }
+
+    async fn inventory(&self, _mk: &MarketMaker, _env: EnvConfig) -> Result<Inventory, String> {
+        self.inventory_reads.fetch_add(1, Ordering::SeqCst);
+        Ok(inventory())
+    }
+}
+
+/// Execution preparing one unencoded trade per order, the simulation and broadcast left to the execution strategy.
+struct Unencoded;
+
+#[async_trait]
+impl Execution for Unencoded {
+    fn name(&self) -> String {
+        "Unencoded".to_string()
+    }
+
+    fn prepare(&self, _mk: &MarketMaker, orders: Vec<ExecutionOrder>, _tdata: Vec<TradeData>, _context: MarketContext, _inventory: Inventory, _permits: &HashMap<String, SignedPermit>) -> Vec<Trade> {
+        orders.iter().map(|order| trade(&order.trade_id)).collect()
+    }
+}
+
+fn maker(engine: Option<Box<dyn DecisionEngine>>) -> MarketMaker {
+    let config = watch_config();
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let builder = MarketMakerBuilder::new().config(config).feed(feed).execution(Box::new(DryRunExec::new())).tokens(base(), quote());
+    match engine {
+        Some(engine) => builder.engine(engine),
+        None => builder,
+    }
+    .build()
+    .expect("Market maker must build")
+}
+
+/// Orders of the engine of `mk` for a pool quoting 3030 against a 3000 reference.
+async fn orders(mk: &MarketMaker) -> Vec<ExecutionOrder> {
+    let targets = vec![pool(POOL, 1_000., 3_030_000., 0.003)];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let sps = mk.engine.prices(mk, &targets, REFERENCE).iter().map(|cpd| cpd.price).collect();
+    let adjustments = mk.engine.evaluate(mk, &targets, sps, REFERENCE, &skew, 100);
+    mk.engine.readjust(mk, context(REFERENCE, 1.0, 100), inventory(), adjustments, &MockBalances::of(&targets)).await
+}
+
+#[tokio::test]
+async fn test_custom_sizing_through_the_builder() {
+    let default = maker(None);
+    assert_eq!(default.engine.name(), "Default_Engine");
+    let optimal = orders(&default).await;
+    assert_eq!(optimal.len(), 1, "Profitable rebalance");
+
+    let half = maker(Some(Box::new(FractionEngine { fraction: 0.5 })));
+    let resized = orders(&half).await;
+    assert_eq!(resized.len(), 1);
+    let (optimal, resized) = (&optimal[0].calculation, &resized[0].calculation);
+    assert!(
+        (resized.selling_amount - optimal.selling_amount / 2.).abs() < 1e-9,
+        "{} vs {}",
+        resized.selling_amount,
+        optimal.selling_amount
+    );
+}
+
+#[tokio::test]
+async fn test_custom_evaluation() {
+    assert!(orders(&maker(Some(Box::new(Idle)))).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_market_data_plugged_through_the_builder() {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let data = FixedData { inventory_reads: reads.clone() };
+    let mut mk = MarketMakerBuilder::new()
+        .config(watch_config())
+        .feed(PriceFeedFactory::create("binance"))
+        .execution(Box::new(DryRunExec::new()))
+        .tokens(base(), quote())
+        .market_data(Box::new(data))
+        .build()
+        .expect("Market maker must build");
+    assert_eq!(mk.market_data.name(), "Fixed_Data");
+    assert_eq!(mk.market_data.market_price(&mk).await, Ok(REFERENCE), "The feed is not called");
+    let graph = TokenGraph::new(&[], &HashMap::new());
+    let market = mk.market_data.market_context(&mk, &graph, &HashMap::new(), vec![]).await.expect("Fixed context");
+    assert_eq!(market.block, 100);
+
+    // The inventory of the maker goes through the source, then its cache
+    assert_eq!(mk.force_refresh_inventory(env()).await.unwrap().base_balance, inventory().base_balance);
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_execution_plugged_through_the_builder() {
+    let default = maker(None);
+    assert_eq!((default.market_data.name(), default.executor.name()), ("Chain_Data_Source".to_string(), "Router_Execution".to_string()));
+
+    let mk = MarketMakerBuilder::new()
+        .config(watch_config())
+        .feed(PriceFeedFactory::create("binance"))
+        .execution(Box::new(DryRunExec::new()))
+        .tokens(base(), quote())
+        .market_data(Box::new(FixedData::default()))
+        .executor(Box::new(Unencoded))
+        .build()
+        .expect("Market maker must build");
+    let orders = orders(&mk).await;
+    assert_eq!(orders.len(), 1);
+    let trades = mk.executor.prepare(&mk, orders.clone(), vec![], context(REFERENCE, 1.0, 100), inventory(), &HashMap::new());
+    assert_eq!(trades[0].metadata.trade_id, orders[0].trade_id);
+    // Executed by the dry run strategy through the default `execute`, never broadcast
+    let executed = mk.executor.execute(&mk, trades, env(), &Deadline::unbounded()).await.expect("Dry run");
+    assert_eq!(executed.len(), 1);
+    // A dry run outcome, not a broadcast error: nothing moved, no balance to read again from chain
+    let broadcast = executed[0].metadata.broadcast.as_ref().expect("Dry run outcome");
+    assert!(broadcast.dry_run && !broadcast.succeeded());
+    assert!(broadcast.error().is_none());
+    assert_ne!(executed[0].metadata.status, TradeStatus::BroadcastFailed);
+}
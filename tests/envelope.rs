@@ -0,0 +1,126 @@
+//! Event envelope: messages of the previous format and of any minor version are read, other majors are dead-lettered.
+mod common;
+
+use common::sqlite;
+use sea_orm::EntityTrait;
+use serde_json::json;
+use shd::data::dead_letter::deliver;
+use shd::data::neon::Neon;
+use shd::data::sub::{mirrored, parse};
+use shd::entity::dead_letter;
+use shd::types::config::load_market_maker_config;
+use shd::types::moni::{Envelope, MessageType, NewInstanceMessage, NewPricesMessage, ParsedMessage, RedisMessage, TradingState};
+
+fn prices() -> NewPricesMessage {
+    NewPricesMessage {
+        identifier: "mainnet-eth-usdc-test".to_string(),
+        reference_price: 2500.0,
+        components: vec![],
+        block: 7,
+        depth: None,
+        state: TradingState::Running,
+        sigma_bps: 0.0,
+        execution_threshold_bps: 0.0,
+        adaptive_bps: 0.0,
+        quarantined: 0,
+        stream_lag_blocks: None,
+        degraded: false,
+    }
+}
+
+fn block(parsed: Result<ParsedMessage, String>) -> u64 {
+    match parsed {
+        Ok(ParsedMessage::NewPrices(msg)) => msg.block,
+        other => panic!("Expected prices, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_previous_and_new_formats() {
+    let previous = RedisMessage {
+        message: MessageType::NewPrices,
+        timestamp: 0,
+        data: serde_json::to_value(prices()).unwrap(),
+        mirrored: false,
+    };
+    assert_eq!(block(parse(&serde_json::to_string(&previous).unwrap())), 7);
+    assert!(!serde_json::to_string(&previous).unwrap().contains("mirrored"), "Shape of the makers not upgraded");
+
+    let envelope = Envelope::new(&MessageType::NewPrices, "mainnet-eth-usdc-test", serde_json::to_value(prices()).unwrap());
+    let serialized = serde_json::to_value(&envelope).unwrap();
+    assert_eq!(serialized["schema_version"], "1.7");
+    assert_eq!(serialized["kind"], "new_prices");
+    assert_eq!(serialized["identifier"], "mainnet-eth-usdc-test");
+    assert!(envelope.published_at > 0);
+    assert_eq!(block(parse(&serialized.to_string())), 7);
+}
+
+#[test]
+fn test_newer_minor_with_unknown_fields() {
+    let mut payload = serde_json::to_value(prices()).unwrap();
+    payload["added_in_1_9"] = json!({ "anything": [1, 2] });
+    let envelope = json!({
+        "schema_version": "1.9",
+        "kind": "new_prices",
+        "payload": payload,
+        "published_at": 1,
+        "identifier": "mainnet-eth-usdc-test",
+        "trace_id": "added in 1.9 too",
+    });
+    assert_eq!(block(parse(&envelope.to_string())), 7);
+
+    // The config of an instance is strict on file loading only
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let mut payload = serde_json::to_value(NewInstanceMessage {
+        config,
+        identifier: "mainnet-eth-usdc-test".to_string(),
+        commit: "test".to_string(),
+    })
+    .unwrap();
+    payload["config"]["added_in_1_9"] = json!(true);
+    payload["config"]["price_feed_config"]["added_in_1_9"] = json!(true);
+    let envelope = Envelope::new(&MessageType::NewInstance, "mainnet-eth-usdc-test", payload);
+    assert!(matches!(parse(&serde_json::to_string(&envelope).unwrap()), Ok(ParsedMessage::NewInstance(_))));
+}
+
+#[test]
+fn test_unknown_kind_and_major_mismatch() {
+    let mut envelope = Envelope::new(&MessageType::NewPrices, "mainnet-eth-usdc-test", serde_json::to_value(prices()).unwrap());
+    envelope.kind = "new_quote".to_string();
+    assert!(matches!(parse(&serde_json::to_string(&envelope).unwrap()), Ok(ParsedMessage::Unknown(_))));
+    envelope.kind = "new_prices".to_string();
+    envelope.schema_version = "2.0".to_string();
+    let err = parse(&serde_json::to_string(&envelope).unwrap()).unwrap_err();
+    assert!(err.contains("Unsupported schema version 2.0"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_major_mismatch_is_dead_lettered() {
+    let (db, path) = sqlite("envelope-major").await;
+    let neon = Neon::from_connection(db.clone());
+    let mut envelope = Envelope::new(&MessageType::NewPrices, "mainnet-eth-usdc-test", serde_json::to_value(prices()).unwrap());
+    envelope.schema_version = "2.0".to_string();
+    deliver(&neon, &serde_json::to_string(&envelope).unwrap()).await.unwrap();
+    let letters = dead_letter::Entity::find().all(&db).await.unwrap();
+    assert_eq!(letters.len(), 1);
+    assert!(letters[0].error.contains("schema version"), "{}", letters[0].error);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_mirrored_copies_skipped() {
+    let previous = RedisMessage {
+        message: MessageType::NewPrices,
+        timestamp: 0,
+        data: serde_json::to_value(prices()).unwrap(),
+        mirrored: false,
+    };
+    assert!(!mirrored(&serde_json::to_string(&previous).unwrap()), "Published by a maker not upgraded");
+    let copy = RedisMessage { mirrored: true, ..previous };
+    assert!(mirrored(&serde_json::to_string(&copy).unwrap()), "Copy of an envelope read on its own");
+    assert_eq!(block(parse(&serde_json::to_string(&copy).unwrap())), 7, "Still readable by the previous monitors");
+
+    let envelope = Envelope::new(&MessageType::NewPrices, "mainnet-eth-usdc-test", serde_json::to_value(prices()).unwrap());
+    assert!(!mirrored(&serde_json::to_string(&envelope).unwrap()));
+    assert!(!mirrored("not json"));
+}
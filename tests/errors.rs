@@ -0,0 +1,125 @@
+//! Execution errors: built from the alloy error of the provider, the text of a legacy record classified into a variant,
+//! and the policy of each variant.
+use alloy::rpc::json_rpc::ErrorPayload;
+use alloy::transports::{RpcError, TransportErrorKind};
+use shd::error::{ExecError, ExecPolicy, MarketMakerError};
+use shd::types::maker::BroadcastData;
+
+#[test]
+fn test_classify_provider_errors() {
+    let cases = [
+        ("server returned an error response: error code -32000: nonce too low: next nonce 42, tx nonce 41", ExecPolicy::Retry),
+        ("server returned an error response: error code -32000: replacement transaction underpriced", ExecPolicy::Retry),
+        ("server returned an error response: error code -32000: insufficient funds for gas * price + value", ExecPolicy::Alert),
+        ("HTTP error 429 with body: {\"error\":\"Too Many Requests\"}", ExecPolicy::Retry),
+        ("error sending request for url (https://rpc.example.org/): operation timed out", ExecPolicy::Retry),
+        ("server returned an error response: error code 3: execution reverted: UniswapV2: K", ExecPolicy::Abort),
+        ("deserialization error: missing field `result` at line 1 column 40", ExecPolicy::Alert),
+    ];
+    for (message, policy) in cases {
+        let error = ExecError::classify(message);
+        assert_eq!(error.policy(), policy, "{} classified as {:?}", message, error);
+        assert_eq!(error.to_string(), message, "Displayed as is");
+    }
+
+    assert!(matches!(ExecError::classify("Nonce too low"), ExecError::NonceConflict(_)));
+    assert!(matches!(ExecError::classify("insufficient funds for transfer"), ExecError::InsufficientFunds(_)));
+    assert!(matches!(ExecError::classify("HTTP error 503 with empty body"), ExecError::Rpc { retryable: true, .. }));
+    assert!(matches!(ExecError::classify("error sending request: operation timed out"), ExecError::Timeout(_)));
+    assert!(matches!(ExecError::classify("execution reverted: STF"), ExecError::Simulation { .. }));
+    assert!(matches!(ExecError::classify("unknown variant `0x5`"), ExecError::Rpc { retryable: false, .. }));
+}
+
+#[test]
+fn test_transport_errors() {
+    let rejected = |code: i64, message: &'static str| {
+        RpcError::ErrorResp(ErrorPayload {
+            code,
+            message: message.into(),
+            data: None,
+        })
+    };
+    let cases = [
+        (rejected(-32000, "nonce too low: next nonce 42, tx nonce 41"), ExecPolicy::Retry),
+        (rejected(-32000, "insufficient funds for gas * price + value"), ExecPolicy::Alert),
+        (rejected(3, "execution reverted: UniswapV2: K"), ExecPolicy::Abort),
+        (TransportErrorKind::http_error(429, "Too Many Requests".to_string()), ExecPolicy::Retry),
+        (RpcError::NullResp, ExecPolicy::Alert),
+    ];
+    for (error, policy) in cases {
+        let built = ExecError::transport("Failed to send swap transaction", &error);
+        assert_eq!(built.policy(), policy, "{} built as {:?}", error, built);
+        assert!(built.to_string().starts_with("Failed to send swap transaction: "), "Prefixed by its context: {}", built);
+    }
+
+    assert!(matches!(ExecError::transport("Swap", &rejected(-32000, "already known")), ExecError::NonceConflict(_)));
+    assert!(matches!(ExecError::transport("Swap", &rejected(3, "execution reverted")), ExecError::Simulation { .. }));
+    // A node message alone does not make a transport error retryable
+    assert!(matches!(ExecError::transport("Swap", &rejected(-32602, "invalid argument 0")), ExecError::Rpc { retryable: false, .. }));
+}
+
+#[test]
+fn test_dry_run_not_an_error() {
+    let bd = BroadcastData::dry("a");
+    assert!(bd.dry_run && bd.broadcasted_at_ms > 0);
+    assert!(!bd.succeeded());
+    assert_eq!(bd.error(), None);
+    let mut legacy = serde_json::to_value(BroadcastData::failed("a", ExecError::Timeout("Deadline passed".to_string()))).unwrap();
+    legacy.as_object_mut().unwrap().remove("dry_run");
+    assert!(!serde_json::from_value::<BroadcastData>(legacy).unwrap().dry_run, "Recorded by an older version");
+}
+
+#[test]
+fn test_display_unchanged() {
+    let error = ExecError::BuilderRejected("All builders rejected the bundle: beaverbuild (400)".to_string());
+    assert_eq!(error.to_string(), "All builders rejected the bundle: beaverbuild (400)");
+    assert_eq!(error.policy(), ExecPolicy::Alert);
+    assert_eq!(ExecError::Encoding("Invalid private key".to_string()).policy(), ExecPolicy::Abort);
+    match MarketMakerError::from(ExecError::Timeout("Deadline passed".to_string())) {
+        MarketMakerError::Execution(e) => assert_eq!(e, "Deadline passed"),
+        e => panic!("Not an execution error: {}", e),
+    }
+}
+
+#[test]
+fn test_broadcast_error_round_trip() {
+    let bd = BroadcastData::failed("a", ExecError::NonceConflict("nonce too low".to_string()));
+    assert_eq!(bd.broadcast_error.as_deref(), Some("nonce too low"));
+    assert!(bd.broadcasted_at_ms > 0);
+    assert!(bd.hash.is_empty() && bd.receipt.is_none());
+    assert_eq!(bd.error(), Some(ExecError::NonceConflict("nonce too low".to_string())));
+
+    let mut bd = BroadcastData::default();
+    assert_eq!(bd.error(), None);
+    bd.fail(ExecError::Timeout("Deadline passed: 1700000000 > 1699999990".to_string()));
+    assert_eq!(bd.error().map(|e| e.policy()), Some(ExecPolicy::Retry));
+}
+
+#[test]
+fn test_broadcast_error_kind_persisted() {
+    // Text classified as a revert, recorded as a builder rejection
+    let bd = BroadcastData::failed("a", ExecError::BuilderRejected("execution reverted: bundle dropped".to_string()));
+    let json = serde_json::to_string(&bd).unwrap();
+    assert!(json.contains("\"error_kind\":\"builder_rejected\""), "{}", json);
+    let bd: BroadcastData = serde_json::from_str(&json).unwrap();
+    assert_eq!(bd.error(), Some(ExecError::BuilderRejected("execution reverted: bundle dropped".to_string())));
+
+    let mut bd = BroadcastData::failed(
+        "a",
+        ExecError::Rpc {
+            message: "nonce too low".to_string(),
+            retryable: true,
+        },
+    );
+    assert!(matches!(bd.error(), Some(ExecError::Rpc { retryable: true, .. })), "Variant kept over the text");
+    bd.succeed();
+    assert_eq!(bd.error(), None);
+    assert_eq!(bd.error_kind, None);
+
+    // Recorded by an older version, without the variant
+    let mut legacy = serde_json::to_value(BroadcastData::failed("a", ExecError::NonceConflict("nonce too low".to_string()))).unwrap();
+    legacy.as_object_mut().unwrap().remove("error_kind");
+    let bd: BroadcastData = serde_json::from_value(legacy).unwrap();
+    assert_eq!(bd.error_kind, None);
+    assert_eq!(bd.error(), Some(ExecError::NonceConflict("nonce too low".to_string())), "Classified from its text");
+}
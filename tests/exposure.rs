@@ -0,0 +1,87 @@
+mod common;
+
+use common::{base, quote};
+use shd::opti::exposure::{Exposure, ExposureLimit};
+use shd::types::maker::{Inventory, MarketContext};
+
+const MAX_SHARE: f64 = 0.7; // 70%
+
+fn context() -> MarketContext {
+    MarketContext {
+        base_to_eth: 1.0,
+        quote_to_eth: 1.0 / 3_000.0,
+        eth_to_usd: 3_000.0,
+        max_fee_per_gas: 0,
+        max_priority_fee_per_gas: 0,
+        native_gas_price: 0,
+        block: 0,
+        age_ms: 0,
+    }
+}
+
+/// Inventory holding `base_eth` ETH and `quote_usdc` USDC.
+fn exposure(base_eth: f64, quote_usdc: f64) -> Exposure {
+    let inventory = Inventory {
+        base_balance: (base_eth * 1e18) as u128,
+        quote_balance: (quote_usdc * 1e6) as u128,
+        nonce: 0,
+        native_balance: 0,
+    };
+    Exposure::new(&inventory, &base(), &quote(), &context())
+}
+
+/// Runs sells of `selling` ETH at 3000 USDC, accepting (possibly clamped) orders like `readjust` does.
+fn sell_base_sequence(mut exposure: Exposure, selling: f64, count: usize) -> Vec<ExposureLimit> {
+    let mut outcomes = vec![];
+    for _ in 0..count {
+        let buying = selling * 3_000.0;
+        let outcome = exposure.limit(true, selling, buying, MAX_SHARE);
+        match outcome {
+            ExposureLimit::Within => exposure = exposure.after(true, selling, buying),
+            ExposureLimit::Clamped(clamped) => exposure = exposure.after(true, clamped, clamped * 3_000.0),
+            ExposureLimit::Rejected => {}
+        }
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+#[test]
+fn test_sequential_sells_clamped_then_rejected() {
+    // 50/50, each order moves 7.5 points of quote share
+    let outcomes = sell_base_sequence(exposure(10.0, 30_000.0), 1.5, 5);
+    assert_eq!(outcomes[0], ExposureLimit::Within, "57.5%");
+    assert_eq!(outcomes[1], ExposureLimit::Within, "65%");
+    match outcomes[2] {
+        ExposureLimit::Clamped(clamped) => assert!((clamped - 1.0).abs() < 1e-9, "Only 1 ETH left to reach 70%: {}", clamped),
+        other => panic!("Expected a clamp, got {:?}", other),
+    }
+    assert_eq!(outcomes[3], ExposureLimit::Rejected, "At the limit");
+    assert_eq!(outcomes[4], ExposureLimit::Rejected);
+}
+
+#[test]
+fn test_clamped_order_lands_on_the_limit() {
+    let start = exposure(10.0, 30_000.0);
+    // Buying at a worse rate than the valuation (2900 instead of 3000 per ETH)
+    let ExposureLimit::Clamped(clamped) = start.limit(true, 8.0, 8.0 * 2_900.0, MAX_SHARE) else {
+        panic!("Expected a clamp");
+    };
+    let share = start.after(true, clamped, clamped * 2_900.0).share(false);
+    assert!((share - MAX_SHARE).abs() < 1e-9, "{}", share);
+}
+
+#[test]
+fn test_opposite_direction_is_not_limited() {
+    // 80% quote, buying base reduces the quote exposure
+    let start = exposure(2.0, 24_000.0);
+    assert!(start.share(false) > MAX_SHARE);
+    assert_eq!(start.limit(false, 6_000.0, 2.0, MAX_SHARE), ExposureLimit::Within);
+    assert_eq!(start.limit(true, 0.5, 1_500.0, MAX_SHARE), ExposureLimit::Rejected, "Already above the limit from external causes");
+}
+
+#[test]
+fn test_disabled_limit_and_unvalued_inventory() {
+    assert_eq!(exposure(10.0, 30_000.0).limit(true, 10.0, 30_000.0, 1.0), ExposureLimit::Within, "100% never limits");
+    assert_eq!(exposure(0.0, 0.0).limit(true, 1.0, 3_000.0, MAX_SHARE), ExposureLimit::Within, "Nothing to value");
+}
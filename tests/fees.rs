@@ -0,0 +1,108 @@
+//! EIP-1559 fee estimation from canned eth_feeHistory responses, mainnet-like and L2-like, and the fee strategy config.
+use alloy::rpc::types::FeeHistory;
+use shd::types::config::{load_market_maker_config, FeeStrategy, NetworkName};
+use shd::utils::evm::fees_from_history;
+
+const GWEI: u128 = 1_000_000_000;
+
+/// 5 full blocks around 20 gwei, 30th percentile tips between 0.5 and 2 gwei.
+const MAINNET: &str = r#"{
+    "oldestBlock": "0x1406f40",
+    "baseFeePerGas": ["0x46c7cfe00", "0x48a4a6300", "0x4b4038a00", "0x49c2c0600", "0x4ae0da900", "0x4a817c800"],
+    "gasUsedRatio": [0.48, 0.62, 0.41, 0.55, 0.49],
+    "reward": [["0x3b9aca00"], ["0x59682f00"], ["0x1dcd6500"], ["0x77359400"], ["0x47868c00"]]
+}"#;
+
+/// 5 blocks at 0.005 gwei, one empty, one tip spiking to 0.5 gwei.
+const L2: &str = r#"{
+    "oldestBlock": "0x2140a10",
+    "baseFeePerGas": ["0x4c4b40", "0x4f5880", "0x4ac4a0", "0x4dd1e0", "0x4c4b40", "0x4c4b40"],
+    "gasUsedRatio": [0.21, 0.0, 0.34, 0.18, 0.27],
+    "reward": [["0xf4240"], ["0x0"], ["0x1e8480"], ["0x1dcd6500"], ["0x16e360"]]
+}"#;
+
+fn history(raw: &str) -> FeeHistory {
+    serde_json::from_str(raw).expect("Canned eth_feeHistory must parse")
+}
+
+fn strategy(percentile: f64, base_multiplier: f64, min_priority_gwei: f64) -> FeeStrategy {
+    FeeStrategy {
+        percentile,
+        base_multiplier,
+        min_priority_gwei,
+        blocks: 5,
+    }
+}
+
+#[test]
+fn test_mainnet_like_profile() {
+    let fees = fees_from_history(&history(MAINNET), &NetworkName::Ethereum.fee_strategy()).unwrap();
+    // Median tip of 0.5, 1, 1.2, 1.5, 2 gwei, on top of twice the next 20 gwei base fee
+    assert_eq!(fees.max_priority_fee_per_gas, 1_200_000_000);
+    assert_eq!(fees.max_fee_per_gas, 40 * GWEI + 1_200_000_000);
+
+    let fees = fees_from_history(&history(MAINNET), &strategy(30., 1.25, 1.5)).unwrap();
+    assert_eq!(fees.max_priority_fee_per_gas, 1_500_000_000, "Floored at min_priority_gwei");
+    assert_eq!(fees.max_fee_per_gas, 25 * GWEI + 1_500_000_000);
+}
+
+#[test]
+fn test_l2_like_profile() {
+    let fees = fees_from_history(&history(L2), &NetworkName::Base.fee_strategy()).unwrap();
+    // Empty block skipped, the 0.5 gwei spike does not move the median of 1, 1.5, 2 and 500 Mwei
+    assert_eq!(fees.max_priority_fee_per_gas, 2_000_000);
+    assert_eq!(fees.max_fee_per_gas, 7_500_000 + 2_000_000);
+    assert!(fees.max_priority_fee_per_gas * 100 < 500_000_000, "Far below the spike");
+
+    // Mainnet defaults on the same blocks pay the 0.01 gwei floor, 5 times the median tip
+    let mainnet = fees_from_history(&history(L2), &NetworkName::Ethereum.fee_strategy()).unwrap();
+    assert_eq!(mainnet.max_priority_fee_per_gas, 10_000_000);
+}
+
+#[test]
+fn test_history_without_rewards_or_base_fee() {
+    let mut empty = history(L2);
+    empty.reward = None;
+    let fees = fees_from_history(&empty, &strategy(30., 1.5, 0.0001)).unwrap();
+    assert_eq!(fees.max_priority_fee_per_gas, 100_000, "Only the floor");
+    assert_eq!(fees.max_fee_per_gas, 7_500_000 + 100_000);
+
+    empty.base_fee_per_gas = vec![];
+    assert!(fees_from_history(&empty, &strategy(30., 1.5, 0.0001)).is_err());
+}
+
+#[test]
+fn test_fee_strategy_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert!(config.fee_strategy.is_none());
+    assert_eq!(config.fee_strategy(), NetworkName::Ethereum.fee_strategy());
+    let unichain = load_market_maker_config("config/unichain.eth-usdc.toml").expect("Unichain config must load");
+    assert_eq!(unichain.fee_strategy().base_multiplier, 1.5);
+
+    let table: toml::Table = toml::from_str("fee_strategy = { percentile = 30, base_multiplier = 1.5, min_priority_gwei = 0.01 }").unwrap();
+    let parsed: FeeStrategy = table["fee_strategy"].clone().try_into().unwrap();
+    assert_eq!(
+        parsed,
+        FeeStrategy {
+            blocks: 10,
+            ..strategy(30., 1.5, 0.01)
+        },
+        "10 blocks by default"
+    );
+
+    config.fee_strategy = Some(strategy(30., 1.5, 0.01));
+    assert_eq!(config.fee_strategy().base_multiplier, 1.5, "Override");
+    config.validate().unwrap();
+    for invalid in [
+        strategy(101., 1.5, 0.01),
+        strategy(30., 0.9, 0.01),
+        strategy(30., 1.5, -1.),
+        FeeStrategy {
+            blocks: 0,
+            ..strategy(30., 1.5, 0.01)
+        },
+    ] {
+        config.fee_strategy = Some(invalid);
+        assert!(config.validate().unwrap_err().to_string().contains("fee_strategy"), "{:?}", invalid);
+    }
+}
@@ -0,0 +1,70 @@
+//! `--frozen-context`: fixture loading, the pinned reads and the forced dry run.
+mod common;
+
+use clap::Parser;
+use common::maker;
+use shd::maker::exec::ExecStrategyName;
+use shd::maker::frozen::FrozenContext;
+use shd::types::cli::MakerCli;
+
+const FIXTURE: &str = "config/frozen/mainnet.eth-usdc.json";
+
+/// Writes `json` to a fixture file of the test, returned with its path.
+fn fixture(name: &str, json: &str) -> String {
+    let path = std::env::temp_dir().join(format!("frozen-{}-{}.json", name, std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_load_the_reference_fixture() {
+    let frozen = FrozenContext::load(FIXTURE).unwrap();
+    assert_eq!(frozen.reference_price, 3_000.0);
+    assert_eq!((frozen.context.eth_to_usd, frozen.context.native_gas_price, frozen.context.block), (3_000.0, 2_000_000_000, 23_000_000));
+    assert_eq!((frozen.inventory.base_balance, frozen.inventory.quote_balance), (10u128.pow(19), 30_000 * 10u128.pow(6)));
+}
+
+#[test]
+fn test_invalid_fixtures_are_rejected() {
+    let raw = std::fs::read_to_string(FIXTURE).unwrap();
+    let mut json = serde_json::from_str::<serde_json::Value>(&raw).unwrap();
+
+    let mut unknown = json.clone();
+    unknown["reference"] = serde_json::json!(3_000.0);
+    let err = FrozenContext::load(&fixture("unknown", &unknown.to_string())).unwrap_err();
+    assert!(err.contains("unknown field `reference`"), "{}", err);
+
+    let mut missing = json.clone();
+    missing["context"].as_object_mut().unwrap().remove("eth_to_usd");
+    let err = FrozenContext::load(&fixture("missing", &missing.to_string())).unwrap_err();
+    assert!(err.contains("missing field `eth_to_usd`"), "{}", err);
+
+    let mut mistyped = json.clone();
+    mistyped["inventory"]["nonce"] = serde_json::json!("7");
+    assert!(FrozenContext::load(&fixture("mistyped", &mistyped.to_string())).is_err());
+
+    json["context"]["quote_to_eth"] = serde_json::json!(0.0);
+    let err = FrozenContext::load(&fixture("zero", &json.to_string())).unwrap_err();
+    assert!(err.contains("context.quote_to_eth must be positive"), "{}", err);
+
+    assert!(FrozenContext::load("config/frozen/missing.json").is_err());
+}
+
+#[tokio::test]
+async fn test_frozen_maker_reads_the_fixture_and_never_broadcasts() {
+    let mut mk = maker(common::config());
+    assert!(!mk.config.dry_run);
+    mk.freeze(FrozenContext::load(FIXTURE).unwrap());
+    assert!(mk.config.dry_run);
+    assert_eq!(mk.execution.name(), ExecStrategyName::DryRunStrategy.as_str());
+    // The reference feed is not called
+    assert_eq!(mk.fetch_market_price().await.unwrap(), 3_000.0);
+}
+
+#[test]
+fn test_frozen_context_flag_forces_dry_run() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", "config/mainnet.eth-usdc.toml", "--frozen-context", FIXTURE]).unwrap();
+    assert_eq!(cli.frozen_context.as_deref(), Some(FIXTURE));
+    let configs = cli.configs().unwrap();
+    assert!(configs[0].dry_run);
+}
@@ -0,0 +1,78 @@
+//! Gas oracle: cached quotes served until their expiry, refresh jitter, sharing and config, on a simulated clock.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy::providers::utils::Eip1559Estimation;
+use shd::types::config::load_market_maker_config;
+use shd::utils::evm::{jitter, GasOracle, GasQuote};
+
+const REFRESH: Duration = Duration::from_secs(10);
+const JITTER: Duration = Duration::from_secs(2);
+
+fn quote(gwei: u128) -> GasQuote {
+    GasQuote {
+        fees: Eip1559Estimation {
+            max_fee_per_gas: 2 * gwei * 1_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+        },
+        gas_price: gwei * 1_000_000_000,
+        eth_to_usd: 3_000.,
+    }
+}
+
+#[test]
+fn test_cached_quote_expires_after_the_refresh_interval() {
+    let oracle = GasOracle::new(REFRESH, JITTER);
+    let t0 = Instant::now();
+    assert!(oracle.fresh(t0).is_none(), "Nothing cached yet");
+
+    oracle.store(quote(20), t0, 0.);
+    let (cached, age) = oracle.fresh(t0 + Duration::from_secs(4)).unwrap();
+    assert_eq!(cached, quote(20));
+    assert_eq!(age, Duration::from_secs(4));
+    assert_eq!(oracle.fresh(t0 + Duration::from_millis(9_999)).unwrap().1.as_millis(), 9_999);
+    assert!(oracle.fresh(t0 + REFRESH).is_none(), "Expired without jitter");
+
+    // Refreshed: the new quote replaces the old one and its age restarts
+    let t1 = t0 + REFRESH;
+    oracle.store(quote(25), t1, 0.);
+    assert_eq!(oracle.fresh(t1 + Duration::from_secs(1)).unwrap(), (quote(25), Duration::from_secs(1)));
+}
+
+#[test]
+fn test_jitter_delays_the_expiry_within_its_bound() {
+    let oracle = GasOracle::new(REFRESH, JITTER);
+    let t0 = Instant::now();
+    oracle.store(quote(20), t0, 0.5);
+    assert!(oracle.fresh(t0 + Duration::from_millis(10_999)).is_some());
+    assert!(oracle.fresh(t0 + Duration::from_secs(11)).is_none());
+
+    // Never older than refresh + jitter
+    oracle.store(quote(20), t0, 7.);
+    assert!(oracle.fresh(t0 + REFRESH + JITTER).is_none());
+
+    for _ in 0..100 {
+        let share = jitter();
+        assert!((0. ..1.).contains(&share), "{}", share);
+    }
+}
+
+#[test]
+fn test_zero_refresh_disables_the_cache() {
+    let oracle = GasOracle::new(Duration::ZERO, JITTER);
+    let t0 = Instant::now();
+    oracle.store(quote(20), t0, 0.9);
+    assert!(oracle.fresh(t0).is_none());
+}
+
+#[test]
+fn test_gas_oracle_config_and_sharing() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!((config.gas_oracle_refresh_ms, config.gas_oracle_jitter_ms), (10_000, 2_000));
+    let oracle = GasOracle::of(&config);
+    assert!(Arc::ptr_eq(&oracle, &GasOracle::of(&config)), "Shared by the pairs of the network");
+
+    let mut other = config.clone();
+    other.gas_oracle_refresh_ms = 0;
+    assert!(!Arc::ptr_eq(&oracle, &GasOracle::of(&other)));
+}
@@ -0,0 +1,191 @@
+//! Evaluation gating: a pool dislocated on-chain is evaluated even when the reference price is stable. The block loop of
+//! the market maker (`MarketMaker::consume`) is fed a mocked stream, with a fixed reference price, and its engine
+//! records the blocks evaluated.
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use common::{base, component, context, env, quote, MockProtocolSim};
+use shd::maker::engine::{DecisionEngine, MarketDataSource};
+use shd::maker::exec::dry::DryRunExec;
+use shd::maker::feed::PriceFeedFactory;
+use shd::maker::lag::StreamLag;
+use shd::maker::tycho::PoolBalances;
+use shd::opti::routing::TokenGraph;
+use shd::opti::skew::InventorySkew;
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::config::{EnvConfig, MarketMakerConfig};
+use shd::types::maker::{CompReadjustment, ComponentPriceData, ExecutionOrder, Inventory, MarketContext, MarketMaker, TradeDirection};
+use shd::types::tycho::{ProtoSimComp, SharedUpdate};
+use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+const BLOCK: u64 = 100;
+
+/// Blocks evaluated, with the direction of their readjustments.
+type Evaluated = Arc<Mutex<Vec<(u64, Vec<TradeDirection>)>>>;
+
+/// Default evaluation, recorded, and no order built.
+struct Recorder(Evaluated);
+
+#[async_trait]
+impl DecisionEngine for Recorder {
+    fn name(&self) -> String {
+        "Recorder".to_string()
+    }
+
+    fn evaluate(&self, mk: &MarketMaker, targets: &[ProtoSimComp], sps: Vec<f64>, reference: f64, skew: &InventorySkew, block: u64) -> Vec<CompReadjustment> {
+        let readjustments = mk.evaluate(targets, sps, reference, skew, block);
+        self.0.lock().unwrap().push((block, readjustments.iter().map(|r| r.direction.clone()).collect()));
+        readjustments
+    }
+
+    async fn readjust(&self, _mk: &MarketMaker, _context: MarketContext, _inventory: Inventory, _adjustments: Vec<CompReadjustment>, _pool_balances: &dyn PoolBalances) -> Vec<ExecutionOrder> {
+        vec![]
+    }
+}
+
+/// Reference price that never moves, in place of the feed and the chain.
+struct StableData;
+
+#[async_trait]
+impl MarketDataSource for StableData {
+    fn name(&self) -> String {
+        "Stable_Data".to_string()
+    }
+
+    async fn market_price(&self, _mk: &MarketMaker) -> Result<f64, String> {
+        Ok(REFERENCE)
+    }
+
+    async fn market_context(&self, _mk: &MarketMaker, _graph: &TokenGraph, _protosims: &HashMap<String, Box<dyn ProtocolSim>>, _tokens: Vec<Token>) -> Option<MarketContext> {
+        Some(context(REFERENCE, 1.0, BLOCK))
+    }
+
+    async fn inventory(&self, _mk: &MarketMaker, _env: EnvConfig) -> Result<Inventory, String> {
+        Ok(Inventory {
+            base_balance: 10 * 10u128.pow(18),
+            quote_balance: 30_000 * 10u128.pow(6),
+            nonce: 0,
+            native_balance: 10u128.pow(18),
+        })
+    }
+}
+
+fn config() -> MarketMakerConfig {
+    let mut config = common::config();
+    config.min_watch_spread_bps = 10.0;
+    config.skew_gain_bps_per_pct = 0.0;
+    config.poll_interval_ms = 0;
+    config.balance_watch_interval_ms = 0;
+    config.snapshot_interval_ms = 0;
+    config.infinite_approval = true;
+    config
+}
+
+fn maker(config: MarketMakerConfig, evaluated: &Evaluated) -> MarketMaker {
+    let mut mk = MarketMakerBuilder::new()
+        .config(config.clone())
+        .feed(PriceFeedFactory::create(&config.price_feed_config.r#type))
+        .execution(Box::new(DryRunExec::new()))
+        .tokens(base(), quote())
+        .engine(Box::new(Recorder(evaluated.clone())))
+        .market_data(Box::new(StableData))
+        .build()
+        .expect("Market maker must build");
+    // Head of the chain never sampled: the first message counted as sampled, the next one u64::MAX messages away
+    mk.lag = StreamLag::new(0, u64::MAX);
+    mk.lag.due();
+    mk
+}
+
+/// Pool with 1000 base against `quote_reserve` quote, spot = quote_reserve / 1000.
+fn target(quote_reserve: f64) -> ProtoSimComp {
+    ProtoSimComp {
+        component: component(POOL, "uniswap_v2", vec![base(), quote()]),
+        protosim: Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, quote_reserve, 0.003)),
+    }
+}
+
+fn prices(targets: &[ProtoSimComp]) -> Vec<ComponentPriceData> {
+    targets
+        .iter()
+        .map(|psc| ComponentPriceData {
+            address: psc.component.id.to_string().to_lowercase(),
+            r#type: psc.component.protocol_system.clone(),
+            price: psc.protosim.spot_price(&base(), &quote()).unwrap(),
+        })
+        .collect()
+}
+
+/// Blocks evaluated by the block loop over a stream of the pool with these quote reserves, the first being its snapshot.
+async fn run(config: MarketMakerConfig, quote_reserves: &[f64]) -> Vec<(u64, Vec<TradeDirection>)> {
+    let evaluated = Evaluated::default();
+    let mut mk = maker(config, &evaluated);
+    let updates = quote_reserves
+        .iter()
+        .enumerate()
+        .map(|(x, reserve)| {
+            let psc = target(*reserve);
+            Ok(SharedUpdate {
+                block_number_or_timestamp: BLOCK + x as u64,
+                states: HashMap::from([(POOL.to_string(), psc.protosim)]),
+                new_pairs: HashMap::from([(POOL.to_string(), psc.component)]),
+                removed_pairs: HashMap::new(),
+            })
+        })
+        .collect::<Vec<Result<SharedUpdate, String>>>();
+    // Returns once the stream closes
+    mk.consume(futures::stream::iter(updates), vec![], env()).await;
+    let evaluated = evaluated.lock().unwrap().clone();
+    evaluated
+}
+
+#[test]
+fn test_unevaluated_pool_counts_as_moved() {
+    let mk = maker(config(), &Evaluated::default());
+    let targets = [target(3_000_000.0)];
+    assert!(mk.pools_moved(&targets, &prices(&targets)));
+}
+
+#[tokio::test]
+async fn test_pool_only_move_produces_readjustments() {
+    // Snapshot, first block (the reference counts as moved), nothing moved, then a large swap moves the pool by 1%
+    let evaluated = run(config(), &[3_000_000.0, 3_000_000.0, 3_000_000.0, 3_030_000.0]).await;
+    assert_eq!(
+        evaluated,
+        vec![(BLOCK + 1, vec![]), (BLOCK + 3, vec![TradeDirection::Buy])],
+        "Nothing moved at {}, nothing evaluated. The pool-only dislocation is evaluated, selling base above the reference",
+        BLOCK + 2
+    );
+}
+
+#[tokio::test]
+async fn test_small_pool_move_below_watch_threshold_is_skipped() {
+    // 5 bps, below the 10 bps watch threshold
+    let evaluated = run(config(), &[3_000_000.0, 3_000_000.0, 3_001_500.0]).await;
+    assert_eq!(evaluated, vec![(BLOCK + 1, vec![])]);
+}
+
+#[tokio::test]
+async fn test_pool_move_gated_on_the_protocol_watch_spread() {
+    // 1% move, below the 150 bps of the protocol override
+    let mut config = config();
+    config.spread_overrides = HashMap::from([("uniswap_v2".to_string(), 150.0)]);
+    let evaluated = run(config.clone(), &[3_000_000.0, 3_000_000.0, 3_030_000.0]).await;
+    assert_eq!(evaluated, vec![(BLOCK + 1, vec![])], "Within the watch spread of the pool");
+
+    // Above the 50 bps of the protocol override, min_watch_spread_bps no longer applying to the pool
+    config.spread_overrides = HashMap::from([("uniswap_v2".to_string(), 50.0)]);
+    let targets = [target(3_000_000.0)];
+    let mut mk = maker(config.clone(), &Evaluated::default());
+    mk.evaluated_spots = prices(&targets).iter().map(|cpd| (cpd.address.clone(), cpd.price)).collect();
+    assert!(!mk.pools_moved(&targets, &prices(&[target(3_012_000.0)])), "40 bps, below the override");
+    assert!(mk.pools_moved(&targets, &prices(&[target(3_030_000.0)])));
+    let evaluated = run(config, &[3_000_000.0, 3_000_000.0, 3_030_000.0]).await;
+    assert_eq!(evaluated, vec![(BLOCK + 1, vec![]), (BLOCK + 2, vec![TradeDirection::Buy])]);
+}
@@ -0,0 +1,99 @@
+//! Websocket block head cache: freshness, the switch back to HTTP polling, reconnection backoff and config.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use shd::types::config::load_market_maker_config;
+use shd::utils::head::{backoff, BlockHead, HeadCache};
+use shd::utils::misc::redact;
+
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+fn head(number: u64, age: Duration) -> BlockHead {
+    BlockHead {
+        number,
+        base_fee: Some(1_000_000_000),
+        timestamp: 1_700_000_000 + number * 12,
+        received_at: Instant::now() - age,
+    }
+}
+
+#[test]
+fn test_fresh_head_and_fallback_switch() {
+    let cache = HeadCache::default();
+    assert!(cache.fresh(MAX_AGE).is_none(), "Nothing received yet: polling");
+
+    cache.update(head(100, Duration::ZERO));
+    assert!(cache.is_connected());
+    assert_eq!(cache.fresh(MAX_AGE).map(|h| h.number), Some(100));
+
+    // Socket down: the last head is refused even if young
+    cache.disconnected();
+    assert!(cache.fresh(MAX_AGE).is_none());
+
+    // Reconnected, the next head is served again
+    cache.update(head(102, Duration::ZERO));
+    assert_eq!(cache.fresh(MAX_AGE).map(|h| h.number), Some(102));
+
+    // Older than the staleness guard while the socket stays up
+    let stale = HeadCache::default();
+    stale.update(head(200, Duration::from_secs(31)));
+    assert!(stale.is_connected() && stale.fresh(MAX_AGE).is_none());
+    assert!(stale.fresh(Duration::from_secs(60)).is_some());
+}
+
+#[test]
+fn test_older_block_does_not_replace_the_head() {
+    let cache = HeadCache::default();
+    cache.update(head(100, Duration::ZERO));
+    cache.update(head(99, Duration::ZERO));
+    assert_eq!(cache.fresh(MAX_AGE).unwrap().number, 100);
+    cache.update(head(100, Duration::ZERO));
+    cache.update(head(101, Duration::ZERO));
+    assert_eq!(cache.fresh(MAX_AGE).unwrap().number, 101);
+}
+
+#[test]
+fn test_gas_price_from_the_base_fee() {
+    assert_eq!(head(1, Duration::ZERO).gas_price(2_000_000), Some(1_002_000_000));
+    let legacy = BlockHead {
+        base_fee: None,
+        ..head(1, Duration::ZERO)
+    };
+    assert_eq!(legacy.gas_price(2_000_000), None, "Polled with eth_gasPrice");
+}
+
+#[test]
+fn test_reconnection_backoff() {
+    let delays = (0..8).map(|attempt| backoff(attempt).as_secs()).collect::<Vec<u64>>();
+    assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(backoff(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+fn test_ws_rpc_url_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert!(config.ws_rpc_url.is_none() && HeadCache::of(&config).is_none(), "Polling only by default");
+    assert_eq!(config.ws_max_head_age_s, 30);
+
+    // Outside a runtime no subscription is spawned, the cache is still shared per endpoint
+    config.ws_rpc_url = Some("wss://mainnet.example.org/ws".to_string());
+    config.validate().unwrap();
+    let cache = HeadCache::of(&config).unwrap();
+    assert!(Arc::ptr_eq(&cache, &HeadCache::of(&config).unwrap()));
+    assert!(!cache.is_connected());
+
+    config.ws_rpc_url = Some("https://mainnet.example.org".to_string());
+    assert!(config.validate().unwrap_err().to_string().contains("ws_rpc_url"));
+    config.ws_rpc_url = Some("ws://127.0.0.1:8546".to_string());
+    config.ws_max_head_age_s = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_endpoint_redacted_for_the_logs() {
+    assert_eq!(redact("wss://eth-mainnet.g.alchemy.com/v2/secret-key"), "wss://eth-mainnet.g.alchemy.com/***");
+    assert_eq!(redact("wss://mainnet.infura.io/ws?apikey=secret-key"), "wss://mainnet.infura.io/***");
+    assert_eq!(redact("ws://user:secret@localhost:8546"), "ws://localhost:8546/***");
+    assert_eq!(redact("ws://localhost:8546"), "ws://localhost:8546", "Nothing to hide");
+    assert_eq!(redact("not a url secret-key"), "***");
+}
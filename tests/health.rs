@@ -0,0 +1,89 @@
+//! Health server: /healthz answers while the process runs, /readyz reports a stale stream, a silent feed or Redis down.
+use shd::data::publisher::PublisherStats;
+use shd::maker::health::{respond, serve, HealthState};
+use shd::types::config::load_market_maker_config;
+use tokio::net::TcpListener;
+
+const NOW: u128 = 1_750_000_000_000;
+
+fn state(publish_events: bool) -> HealthState {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    config.publish_events = publish_events;
+    config.stream_staleness_threshold_s = 60;
+    let state = HealthState::new("mainnet-eth-usdc-health", &config);
+    state.set_ready(true);
+    state.feed(true);
+    state.message(NOW - 5_000);
+    state
+}
+
+#[test]
+fn test_readiness_reasons() {
+    let state = state(true);
+    assert!(state.check(NOW, true).ready);
+
+    state.message(NOW - 61_000);
+    let readiness = state.check(NOW, false);
+    assert!(!readiness.ready);
+    assert_eq!(readiness.reasons.len(), 2);
+    assert!(readiness.reasons[0].contains("stream stale: last Tycho message 61 s ago"), "{:?}", readiness.reasons);
+    assert_eq!(readiness.reasons[1], "Redis unreachable");
+
+    state.message(NOW);
+    state.feed(false);
+    state.set_ready(false);
+    assert_eq!(state.check(NOW, true).reasons, vec!["stream not ready", "price feed not responding"]);
+
+    // Redis is only required when publishing events
+    assert!(self::state(false).check(NOW, false).ready);
+}
+
+#[test]
+fn test_flaps_are_counted() {
+    let state = state(false);
+    assert_eq!(state.probe(NOW, true).flaps, 0);
+    assert_eq!(state.probe(NOW + 120_000, true).flaps, 1); // Stale
+    state.message(NOW + 120_000);
+    assert_eq!(state.probe(NOW + 120_000, true).flaps, 2);
+    assert_eq!(state.probe(NOW + 120_000, true).flaps, 2);
+
+    let publisher = PublisherStats {
+        depth: 3,
+        published: 42,
+        dropped: 1,
+        reconnects: 2,
+        connected: true,
+    };
+    let (status, body) = respond("/readyz", &[state.clone(), self::state(false)], NOW + 600_000, publisher);
+    assert_eq!(status, 503);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["instances"].as_array().unwrap().len(), 2);
+    assert_eq!(body["publisher"], serde_json::json!({ "depth": 3, "published": 42, "dropped": 1, "reconnects": 2, "connected": true }));
+    assert_eq!(respond("/metricz", &[state], NOW, publisher).0, 404);
+}
+
+#[tokio::test]
+async fn test_endpoints_with_a_stale_stream() {
+    let stale = state(false);
+    stale.message(1); // Decades ago
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(listener, vec![stale.clone()]));
+
+    let client = reqwest::Client::new();
+    let alive = client.get(format!("http://{}/healthz", address)).send().await.unwrap();
+    assert_eq!(alive.status().as_u16(), 200);
+
+    let ready = client.get(format!("http://{}/readyz", address)).send().await.unwrap();
+    assert_eq!(ready.status().as_u16(), 503);
+    let body: serde_json::Value = serde_json::from_str(&ready.text().await.unwrap()).unwrap();
+    assert_eq!(body["instances"][0]["identifier"], "mainnet-eth-usdc-health");
+    assert!(body["instances"][0]["reasons"][0].as_str().unwrap().starts_with("stream stale"), "{}", body);
+
+    // A fresh message makes the instance ready again
+    stale.message(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis());
+    let ready = client.get(format!("http://{}/readyz?verbose", address)).send().await.unwrap();
+    assert_eq!(ready.status().as_u16(), 200);
+    assert_eq!(client.get(format!("http://{}/unknown", address)).send().await.unwrap().status().as_u16(), 404);
+}
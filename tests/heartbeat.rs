@@ -0,0 +1,166 @@
+//! Heartbeat: the JSON payload parsed by external tooling, built from the instance state, and POSTed within a timeout.
+use std::time::Duration;
+
+use shd::maker::{control::Control, health::HealthState};
+use shd::types::config::load_market_maker_config;
+use shd::types::moni::{ControlCommand, HeartbeatPayload, InventorySummary, TradingState};
+use shd::utils::uptime::{failures, heartbeat, payloads};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const NOW: u64 = 1_750_000_000_000;
+
+fn inventory() -> InventorySummary {
+    InventorySummary {
+        base_symbol: "WETH".to_string(),
+        base_balance: 1.5,
+        quote_symbol: "USDC".to_string(),
+        quote_balance: 4_200.0,
+        native_balance: 0.1,
+    }
+}
+
+fn instance() -> (Control, HealthState) {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    (Control::default(), HealthState::new("mainnet-eth-usdc-heartbeat", &config))
+}
+
+/// Answers one request with `status`, returning the request body.
+async fn endpoint(status: u16) -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/push/abc", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = vec![];
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse::<usize>().unwrap()))
+                    .unwrap_or_default();
+                if body.len() >= length || read == 0 {
+                    let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    return format!("{}\n{}", head.lines().next().unwrap_or_default(), body);
+                }
+            }
+        }
+    });
+    (url, handle)
+}
+
+#[test]
+fn test_payload_serialization() {
+    let payload = HeartbeatPayload {
+        identifier: "mainnet-eth-usdc".to_string(),
+        commit: "abc123".to_string(),
+        timestamp_ms: NOW,
+        state: TradingState::Paused,
+        block: 21_000_000,
+        last_trade_ms: Some(NOW - 60_000),
+        stream_ready: true,
+        breaker_open: false,
+        inventory: Some(inventory()),
+        failures: 2,
+    };
+    let value = serde_json::to_value(&payload).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "identifier": "mainnet-eth-usdc",
+            "commit": "abc123",
+            "timestamp_ms": NOW,
+            "state": "paused",
+            "block": 21_000_000,
+            "last_trade_ms": NOW - 60_000,
+            "stream_ready": true,
+            "breaker_open": false,
+            "inventory": {
+                "base_symbol": "WETH",
+                "base_balance": 1.5,
+                "quote_symbol": "USDC",
+                "quote_balance": 4_200.0,
+                "native_balance": 0.1
+            },
+            "failures": 2
+        })
+    );
+    assert_eq!(serde_json::from_value::<HeartbeatPayload>(value).unwrap(), payload);
+
+    // Nothing known yet: explicit nulls, not missing keys
+    let empty = serde_json::to_value(HeartbeatPayload::default()).unwrap();
+    assert!(empty["last_trade_ms"].is_null() && empty["inventory"].is_null());
+    assert_eq!(empty["state"], "running");
+}
+
+#[test]
+fn test_payload_reflects_the_instance_state() {
+    let (control, health) = instance();
+    let instances = vec![(control.clone(), health.clone())];
+    let initial = payloads("maker", "abc123", &instances, NOW).remove(0);
+    assert_eq!(initial.identifier, "mainnet-eth-usdc-heartbeat");
+    assert_eq!((initial.block, initial.last_trade_ms, initial.stream_ready, initial.breaker_open), (0, None, false, false));
+    assert!(initial.inventory.is_none());
+
+    health.set_ready(true);
+    health.block(21_000_001);
+    health.traded(NOW - 1_000);
+    health.breaker(true);
+    health.inventory(inventory());
+    control.apply(ControlCommand::Pause);
+    let payload = payloads("maker", "abc123", &instances, NOW).remove(0);
+    assert_eq!(payload.state, TradingState::Paused);
+    assert_eq!(
+        (payload.block, payload.last_trade_ms, payload.stream_ready, payload.breaker_open),
+        (21_000_001, Some(NOW - 1_000), true, true)
+    );
+    assert_eq!(payload.inventory, Some(inventory()));
+    assert_eq!((payload.commit.as_str(), payload.timestamp_ms), ("abc123", NOW));
+
+    // The monitor beats for the process
+    let monitor = payloads("monitor", "abc123", &[], NOW);
+    assert_eq!(monitor.len(), 1);
+    assert_eq!(monitor[0].identifier, "monitor");
+}
+
+#[tokio::test]
+async fn test_heartbeat_posted_as_json() {
+    let (url, request) = endpoint(200).await;
+    let (_, health) = instance();
+    health.block(42);
+    let payload = health.heartbeat("abc123", TradingState::Running, NOW, 0);
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(5)).build().unwrap();
+    assert!(heartbeat(&client, &url, &payload).await);
+    let request = request.await.unwrap();
+    let (line, body) = request.split_once('\n').unwrap();
+    assert!(line.starts_with("POST /api/push/abc"), "{}", line);
+    assert_eq!(serde_json::from_str::<HeartbeatPayload>(body).unwrap(), payload);
+}
+
+#[tokio::test]
+async fn test_failures_counted_never_raised() {
+    let payload = payloads("monitor", "", &[], NOW).remove(0);
+    let client = reqwest::Client::builder().timeout(Duration::from_millis(200)).build().unwrap();
+
+    let (url, _request) = endpoint(500).await;
+    let before = failures();
+    assert!(!heartbeat(&client, &url, &payload).await, "Non-2xx status");
+    assert!(failures() > before);
+
+    // An endpoint accepting the connection but never answering is cut by the timeout
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let _silent = tokio::spawn(async move {
+        let (_socket, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    });
+    let before = failures();
+    let started = std::time::Instant::now();
+    assert!(!heartbeat(&client, &url, &payload).await);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(failures() > before);
+}
@@ -0,0 +1,61 @@
+//! Uniswap V4 hooks: hooked pools are skipped unless the hook is allowed.
+mod common;
+
+use common::{base, component, quote};
+use shd::maker::tycho::{excluded_v4_hook, v4_hook};
+use shd::types::config::{load_market_maker_config, MarketMakerConfig};
+use tycho_common::Bytes;
+use tycho_simulation::protocol::models::ProtocolComponent;
+
+const POOL_V4: &str = "0x21c67e77068de97969ba93d4aab21826d33ca12bb9f565d8496e8fda8a82ca27";
+const HOOK: &str = "0x0010d0d5db05933fa0d9f7038d365e1541a41888";
+
+fn reference_config() -> MarketMakerConfig {
+    load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load")
+}
+
+fn pool(protocol_system: &str, hooks: Option<&str>) -> ProtocolComponent {
+    let mut cp = component(POOL_V4, protocol_system, vec![base(), quote()]);
+    if let Some(hooks) = hooks {
+        cp.static_attributes.insert("hooks".to_string(), Bytes::from(hex::decode(hooks.trim_start_matches("0x")).unwrap()));
+    }
+    cp
+}
+
+#[test]
+fn test_hook_detection() {
+    assert_eq!(v4_hook(&pool("uniswap_v4", Some(HOOK))), Some(HOOK.to_string()));
+    assert_eq!(v4_hook(&pool("uniswap_v4", Some("0x0000000000000000000000000000000000000000"))), None, "Zero hook is a plain pool");
+    assert_eq!(v4_hook(&pool("uniswap_v4", None)), None);
+    assert_eq!(v4_hook(&pool("uniswap_v3", Some(HOOK))), None, "Only Uniswap V4 has hooks");
+}
+
+#[test]
+fn test_hooked_pool_excluded_by_default() {
+    let config = reference_config();
+    assert_eq!(excluded_v4_hook(&config, &pool("uniswap_v4", Some(HOOK))), Some(HOOK.to_string()));
+    assert_eq!(excluded_v4_hook(&config, &pool("uniswap_v4", None)), None);
+}
+
+#[test]
+fn test_hook_allowlist_case_insensitive() {
+    let mut config = reference_config();
+    config.v4_hook_allowlist = vec![HOOK.to_uppercase().replacen("0X", "0x", 1)];
+    assert!(config.validate().is_ok());
+    assert_eq!(excluded_v4_hook(&config, &pool("uniswap_v4", Some(HOOK))), None);
+    assert!(!config.accepts_v4_hook("0x1111111111111111111111111111111111111111"));
+}
+
+#[test]
+fn test_allow_all_hooks() {
+    let mut config = reference_config();
+    config.allow_v4_hooked_pools = true;
+    assert_eq!(excluded_v4_hook(&config, &pool("uniswap_v4", Some(HOOK))), None);
+}
+
+#[test]
+fn test_invalid_hook_address_rejected() {
+    let mut config = reference_config();
+    config.v4_hook_allowlist = vec!["0x1234".to_string()];
+    assert!(config.validate().is_err());
+}
@@ -1,8 +1,9 @@
 use alloy_primitives::bytes;
 use shd::maker::exec::ExecStrategyFactory;
 use shd::maker::feed::PriceFeedFactory;
+use shd::maker::preflight::preflight;
 use shd::types::builder::MarketMakerBuilder;
-use shd::types::config::{load_market_maker_config, EnvConfig};
+use shd::types::config::{load_market_maker_config, EnvConfig, EventsTransport, SignerType};
 use tycho_common::models::token::Token; // Changed from tycho_simulation::models in 0.181.3
 use tycho_simulation::tycho_common::Bytes;
 
@@ -13,10 +14,25 @@ static CONFIG_FILES: &[&str] = &["config/mainnet.eth-usdc.toml", "config/unichai
 fn create_test_env_config() -> EnvConfig {
     EnvConfig {
         path: "test_config".to_string(),
+        paths: vec![],
         testing: true,
         heartbeat: "".to_string(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
         tycho_api_key: "test_api_key".to_string(),
+        signer_type: SignerType::Raw,
         wallet_private_key: "0x0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+        keystore_path: None,
+        keystore_password: None,
+        remote_signer_url: None,
+        remote_signer_address: None,
+        bundle_signer_key: None,
+        events_transport: EventsTransport::PubSub,
+        redis_legacy_keys: false,
+        health_port: 0,
+        alert_telegram_bot_token: None,
+        alert_telegram_chat_id: None,
+        alert_webhook_url: None,
     }
 }
 
@@ -77,11 +93,11 @@ async fn test_market_maker_initialization() {
                 println!("   ✓ Execution strategy created for network: {}", config.network_name);
 
                 // Build market maker
-                let builder = MarketMakerBuilder::new(config.clone(), feed, strategy);
-                let identifier = builder.identifier();
+                let builder = MarketMakerBuilder::new().config(config.clone()).feed(feed).execution(strategy).tokens(base_token, quote_token);
+                let identifier = builder.identifier().expect("Identifier derived from the config");
                 println!("   ✓ Builder created with ID: {}", identifier);
 
-                match builder.build(base_token, quote_token) {
+                match builder.build() {
                     Ok(market_maker) => {
                         // Verify initialization
                         assert_eq!(market_maker.config.network_name, config.network_name);
@@ -169,6 +185,25 @@ async fn test_price_feeds() {
     println!("\n✨ Price feed tests completed!\n");
 }
 
+/// Runs the startup preflight of each config against the live dependencies
+#[tokio::test]
+async fn test_preflight() {
+    println!("\n🛫 Testing startup preflight...\n");
+    let env = create_test_env_config();
+    for config_path in CONFIG_FILES {
+        let config = load_market_maker_config(config_path).expect("Failed to load config");
+        let report = preflight(&config, &env).await;
+        println!("{}\n", report);
+        // Every dependency is reported, whether it is reachable from the test environment or not
+        assert_eq!(report.checks.iter().filter(|check| check.name == "RPC").count(), config.rpc_endpoints().len());
+        for name in ["Tycho API", "Chainlink gas feed", "Price feed", "Signer", "Redis", "Tycho router", "Permit2"] {
+            assert!(report.checks.iter().any(|check| check.name == name), "{} check missing", name);
+        }
+        // The test key is not the key of the configured wallet
+        assert!(!report.passed());
+    }
+}
+
 /// Test 3: Market Context Fetching
 /// Tests market maker's ability to fetch and process market context
 #[tokio::test]
@@ -213,9 +248,9 @@ async fn test_market_context() {
     let exec_result = std::panic::catch_unwind(|| ExecStrategyFactory::create(config.network_name.as_str()));
 
     if let Ok(exec_strategy) = exec_result {
-        let builder = MarketMakerBuilder::new(config.clone(), feed, exec_strategy);
+        let builder = MarketMakerBuilder::new().config(config.clone()).feed(feed).execution(exec_strategy).tokens(base_token, quote_token);
 
-        match builder.build(base_token, quote_token) {
+        match builder.build() {
             Ok(market_maker) => {
                 println!("✓ Market maker built successfully");
 
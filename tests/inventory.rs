@@ -0,0 +1,168 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{base, calculation, pool, quote, readjustment};
+use num_bigint::BigUint;
+use shd::maker::inventory::InventoryCache;
+use shd::types::maker::{ExecutionOrder, Inventory, ReceiptData, SwapCalculation, TransferData};
+
+const WETH: f64 = 1e18; // 18 decimals
+const USDC: f64 = 1e6; // 6 decimals
+const WALLET: &str = "0x1111111111111111111111111111111111111111";
+const ROUTER: &str = "0x2222222222222222222222222222222222222222";
+
+fn inventory() -> Inventory {
+    Inventory {
+        base_balance: (2.0 * WETH) as u128,
+        quote_balance: (5_000.0 * USDC) as u128,
+        nonce: 42,
+        native_balance: 1_000_000_000_000_000_000, // 1 ETH
+    }
+}
+
+fn receipt(status: bool) -> ReceiptData {
+    ReceiptData {
+        status,
+        gas_used: 150_000,
+        error: None,
+        transaction_hash: String::new(),
+        transaction_index: 0,
+        block_number: 100,
+        effective_gas_price: 2_000_000_000, // 2 gwei
+        transfers: vec![],
+    }
+}
+
+const GAS_WEI: u128 = 150_000 * 2_000_000_000;
+
+/// Order of `calculation` on the ETH/USDC pool, in its direction.
+fn order(calculation: SwapCalculation) -> ExecutionOrder {
+    let psc = pool("0xaaaa000000000000000000000000000000000001", 1_000., 3_000_000., 0.003);
+    let spot = if calculation.base_to_quote { 3_030. } else { 2_970. };
+    ExecutionOrder {
+        trade_id: "a".to_string(),
+        adjustment: readjustment(&psc, spot, 3_000.),
+        calculation,
+    }
+}
+
+fn transfer(token: &str, from: &str, to: &str, amount: u128) -> TransferData {
+    TransferData {
+        token: token.to_lowercase(),
+        from: from.to_string(),
+        to: to.to_string(),
+        amount,
+    }
+}
+
+#[test]
+fn test_settle_sell_base_for_quote() {
+    let mut inv = inventory();
+    assert!(inv.settle(&order(calculation(true, 0.5, 1_250.0)), Some(&receipt(true)), WALLET));
+    assert_eq!(inv.base_balance, 1_500_000_000_000_000_000, "2 - 0.5 WETH in wei");
+    assert_eq!(inv.quote_balance, 6_250_000_000, "5000 + 1250 USDC in 6 decimals units");
+    assert_eq!(inv.nonce, 43);
+    assert_eq!(inv.native_balance, 1_000_000_000_000_000_000 - GAS_WEI);
+}
+
+#[test]
+fn test_settle_buy_base_with_quote() {
+    let mut inv = inventory();
+    assert!(inv.settle(&order(calculation(false, 1_000.0, 0.4)), Some(&receipt(true)), WALLET));
+    assert_eq!(inv.quote_balance, 4_000_000_000);
+    assert_eq!(inv.base_balance, 2_400_000_000_000_000_000);
+}
+
+#[test]
+fn test_settle_floors_fractional_units() {
+    let mut inv = inventory();
+    let mut calc = calculation(false, 1.0, 0.0);
+    calc.amount_out_powered = 333_333_333_333_333.7; // Only whole units reach the wallet
+    inv.settle(&order(calc), Some(&receipt(true)), WALLET);
+    assert_eq!(inv.quote_balance, 4_999_000_000, "The raw input sent");
+    assert_eq!(inv.base_balance, 2_000_333_333_333_333_333);
+}
+
+/// Exact out of 0.4 WETH reverse quoted at 1000 USDC, up to 1000.5 USDC sent to the router.
+fn exact_out() -> SwapCalculation {
+    let mut calc = calculation(false, 1_000.0, 0.41);
+    calc.exact_out = true;
+    calc.amount_out_min_powered = 0.4 * WETH;
+    calc.amount_in_max_normalized = 1_000.5;
+    calc.amount_in_max_raw = BigUint::from(1_000_500_000u128);
+    calc
+}
+
+#[test]
+fn test_settle_exact_out_books_the_receipt_transfers() {
+    let mut inv = inventory();
+    let mut landed = receipt(true);
+    landed.transfers = vec![
+        transfer(&quote().address.to_string(), WALLET, ROUTER, 1_000_500_000),
+        transfer(&base().address.to_string(), ROUTER, WALLET, 400_200_000_000_000_000),
+    ];
+    inv.settle(&order(exact_out()), Some(&landed), WALLET);
+    assert_eq!(inv.quote_balance, 3_999_500_000, "The max input, spent by the router");
+    assert_eq!(inv.base_balance, 2_400_200_000_000_000_000, "Received above the target");
+
+    // Without transfers: the max input sent, the target received
+    let mut inv = inventory();
+    inv.settle(&order(exact_out()), Some(&receipt(true)), WALLET);
+    assert_eq!(inv.quote_balance, 3_999_500_000);
+    assert_eq!(inv.base_balance, 2_400_000_000_000_000_000);
+}
+
+#[test]
+fn test_settle_reverted_costs_gas_and_nonce_only() {
+    let mut inv = inventory();
+    assert!(inv.settle(&order(calculation(true, 0.5, 1_250.0)), Some(&receipt(false)), WALLET));
+    assert_eq!(inv.base_balance, inventory().base_balance);
+    assert_eq!(inv.quote_balance, inventory().quote_balance);
+    assert_eq!(inv.nonce, 43);
+    assert_eq!(inv.native_balance, 1_000_000_000_000_000_000 - GAS_WEI);
+}
+
+#[test]
+fn test_settle_without_receipt_is_unknown() {
+    let mut inv = inventory();
+    assert!(!inv.settle(&order(calculation(true, 0.5, 1_250.0)), None, WALLET));
+    assert_eq!(inv.nonce, 42, "Nothing applied");
+}
+
+#[test]
+fn test_settle_saturates_at_zero() {
+    let mut inv = inventory();
+    inv.settle(&order(calculation(true, 3.0, 7_500.0)), Some(&receipt(true)), WALLET);
+    assert_eq!(inv.base_balance, 0);
+}
+
+#[test]
+fn test_cache_expires_after_interval() {
+    let now = Instant::now();
+    let mut cache = InventoryCache::new(1_000);
+    assert!(cache.fresh(now).is_none());
+
+    cache.store(inventory(), now);
+    assert!(cache.fresh(now + Duration::from_millis(999)).is_some());
+    assert!(cache.fresh(now + Duration::from_millis(1_000)).is_none(), "Refetched once the interval elapsed");
+
+    let mut disabled = InventoryCache::new(0);
+    disabled.store(inventory(), now);
+    assert!(disabled.fresh(now).is_none(), "0 refetches at every opportunity");
+}
+
+#[test]
+fn test_cache_settles_and_invalidates() {
+    let now = Instant::now();
+    let mut cache = InventoryCache::new(60_000);
+    cache.store(inventory(), now);
+
+    cache.settle(&order(calculation(true, 0.5, 1_250.0)), Some(&receipt(true)), WALLET);
+    let cached = cache.fresh(now).expect("Settled trade keeps the cache");
+    assert_eq!(cached.nonce, 43);
+    assert_eq!(cached.quote_balance, 6_250_000_000);
+
+    cache.settle(&order(calculation(true, 0.5, 1_250.0)), None, WALLET);
+    assert!(cache.is_empty(), "Unknown outcome drops the cache");
+}
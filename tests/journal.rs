@@ -0,0 +1,124 @@
+mod common;
+
+use common::{calculation, maker, pool, readjustment, trade};
+use shd::maker::journal::{JournalEntry, TradeJournal};
+use shd::types::maker::{BroadcastData, ExecutionOrder, ReceiptData, SimulatedData, TradeDirection, TradeStatus};
+
+const POOL: &str = "0xB4E16D0168E52D35CACD2C6185B44281EC28C9DC";
+const OTHER: &str = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+
+fn entry(component: &str, block: u64) -> JournalEntry {
+    JournalEntry {
+        component: component.to_string(),
+        direction: TradeDirection::Buy,
+        block,
+        amount: 0.5,
+    }
+}
+
+#[test]
+fn test_restored_trade_skipped_within_window() {
+    let mut journal = TradeJournal::new("mmc-ethereum-weth-usdc-0x1234567", 3);
+    assert!(!journal.is_loaded());
+    journal.restore(vec![entry(&POOL.to_lowercase(), 100)], 101);
+    assert!(journal.is_loaded());
+
+    assert!(journal.is_recent(POOL, 101), "Ids are case insensitive");
+    assert!(journal.is_recent(POOL, 102));
+    assert!(!journal.is_recent(POOL, 103), "Window of 3 blocks elapsed");
+    assert!(!journal.is_recent(OTHER, 101));
+}
+
+#[test]
+fn test_expired_entries_are_not_restored() {
+    let mut journal = TradeJournal::new("id", 3);
+    journal.restore(vec![entry(POOL, 90), entry(OTHER, 99)], 100);
+    assert_eq!(journal.restored().len(), 1);
+    assert!(journal.is_recent(OTHER, 100));
+}
+
+#[test]
+fn test_record_prunes_and_carries_restored_entries() {
+    let mut journal = TradeJournal::new("id", 3);
+    journal.restore(vec![entry(OTHER, 99)], 100);
+
+    let persisted = journal.record(entry(POOL, 100), 100).expect("Enabled journal persists");
+    assert_eq!(persisted.len(), 2, "Restored entries are carried over for the next restart");
+    assert_eq!(persisted[1].component, POOL.to_lowercase());
+    assert!(!journal.is_recent(POOL, 100), "Trades of this process are left to the in-memory cooldown");
+
+    let persisted = journal.record(entry(POOL, 102), 102).unwrap();
+    assert_eq!(persisted.len(), 2, "Entry of block 99 expired at block 102");
+    assert!(!journal.is_recent(OTHER, 102));
+}
+
+#[test]
+fn test_zero_window_disables_the_journal() {
+    let mut journal = TradeJournal::new("id", 0);
+    assert!(journal.record(entry(POOL, 100), 100).is_none());
+    journal.restore(vec![entry(POOL, 100)], 100);
+    assert!(!journal.is_recent(POOL, 100));
+}
+
+#[test]
+fn test_key_per_configuration_and_entry_roundtrip() {
+    let journal = TradeJournal::new("mmc-ethereum-weth-usdc-0x1234567", 3);
+    assert_eq!(journal.key(), "tycho_market_maker:v1:journal:mmc-ethereum-weth-usdc-0x1234567");
+    let json = serde_json::to_string(&vec![entry(POOL, 100)]).unwrap();
+    let back: Vec<JournalEntry> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, vec![entry(POOL, 100)]);
+}
+
+#[test]
+fn test_only_sent_trades_journaled() {
+    let mut config = common::config();
+    config.pool_cooldown_blocks = 3;
+    let mut mk = maker(config);
+    let psc = pool(POOL, 1_000., 3_030_000., 0.003);
+    let order = ExecutionOrder {
+        trade_id: "a".to_string(),
+        adjustment: readjustment(&psc, 3_030., 3_000.),
+        calculation: calculation(true, 1.0, 3_030.0),
+    };
+
+    // Failed its simulation, nothing sent
+    let mut failed = trade("a");
+    failed.metadata.status = TradeStatus::SimulationFailed;
+    failed.metadata.simulation = Some(SimulatedData {
+        trade_id: "a".to_string(),
+        error: Some("execution reverted".to_string()),
+        ..Default::default()
+    });
+    assert!(mk.record_traded(&failed, &order, 100).is_none(), "Journal left empty");
+    assert!(!mk.cooldown.is_cooling(POOL, 100));
+
+    // Reverted on chain, the pool is untouched
+    let mut reverted = trade("a");
+    reverted.metadata.broadcast = Some(BroadcastData {
+        trade_id: "a".to_string(),
+        hash: "0xabc".to_string(),
+        receipt: Some(ReceiptData {
+            status: false,
+            gas_used: 0,
+            error: None,
+            transaction_hash: "0xabc".to_string(),
+            transaction_index: 0,
+            block_number: 101,
+            effective_gas_price: 0,
+            transfers: vec![],
+        }),
+        ..Default::default()
+    });
+    assert!(mk.record_traded(&reverted, &order, 100).is_none());
+
+    // Sent, its receipt pending
+    let mut sent = trade("a");
+    sent.metadata.broadcast = Some(BroadcastData {
+        trade_id: "a".to_string(),
+        hash: "0xabc".to_string(),
+        ..Default::default()
+    });
+    let persisted = mk.record_traded(&sent, &order, 100).expect("Sent trade journaled");
+    assert_eq!((persisted.len(), persisted[0].block), (1, 100));
+    assert!(mk.cooldown.is_cooling(POOL, 101));
+}
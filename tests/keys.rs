@@ -0,0 +1,66 @@
+//! Redis names: exact formats, so they cannot drift silently, and the compatibility mode destinations.
+use shd::data::keys;
+use shd::data::r#pub::outgoing;
+use shd::types::moni::{Envelope, MessageType, RedisMessage};
+
+const ID: &str = "mmc-ethereum-weth-usdc-0x1234567-instance-1700000000";
+
+#[test]
+fn test_key_formats() {
+    assert_eq!(keys::prefix(), "tycho_market_maker:v1");
+    assert_eq!(keys::channel_trades(ID), format!("tycho_market_maker:v1:events:{}:new_trade", ID));
+    assert_eq!(keys::channel_prices(ID), format!("tycho_market_maker:v1:events:{}:new_prices", ID));
+    assert_eq!(keys::channel(ID, &MessageType::EndInstance), format!("tycho_market_maker:v1:events:{}:end_instance", ID));
+    assert_eq!(keys::channel_ping(), "tycho_market_maker:v1:events:ping");
+    assert_eq!(keys::channel_events_pattern(), "tycho_market_maker:v1:events:*");
+    assert_eq!(keys::channel_control(), "tycho_market_maker:v1:control");
+    assert_eq!(keys::stream(&MessageType::NewTrade), "tycho_market_maker:v1:stream:new_trade");
+    assert_eq!(keys::key_status(ID), format!("tycho_market_maker:v1:instance:{}:status", ID));
+    assert_eq!(keys::key_heartbeat(ID), format!("tycho_market_maker:v1:instance:{}:heartbeat", ID));
+    assert_eq!(keys::key_breaker_resume(ID), format!("tycho_market_maker:v1:instance:{}:breaker_resume", ID));
+    assert_eq!(keys::key_journal("mmc-ethereum-weth-usdc-0x1234567"), "tycho_market_maker:v1:journal:mmc-ethereum-weth-usdc-0x1234567");
+    // Every instance channel matches the pattern of the monitor
+    let pattern = keys::channel_events_pattern();
+    for kind in MessageType::ALL.iter() {
+        assert!(keys::channel(ID, kind).starts_with(pattern.trim_end_matches('*')));
+    }
+    assert_ne!(keys::key_status(ID), keys::key_status("mmc-ethereum-weth-usdc-0x1234567-instance-1700000001"));
+}
+
+#[test]
+fn test_legacy_formats() {
+    assert_eq!(keys::legacy::channel_events(), "tycho_market_maker");
+    assert_eq!(keys::legacy::channel_control(), "tycho_market_maker:control");
+    assert_eq!(keys::legacy::stream(&MessageType::NewPrices), "tycho_market_maker:new_prices");
+    assert_eq!(keys::legacy::key_breaker_resume(ID), format!("breaker:resume:{}", ID));
+    assert_eq!(keys::legacy::key_journal("id"), "tycho_market_maker:journal:id");
+}
+
+#[test]
+fn test_compatibility_mode() {
+    // Process-wide, so checked before and after in a single test
+    let event = Envelope::new(&MessageType::NewTrade, ID, serde_json::json!({ "identifier": ID }));
+    assert!(!keys::legacy());
+    let messages = outgoing(&event).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].channels, vec![keys::channel_trades(ID)]);
+    assert_eq!(messages[0].streams, vec!["tycho_market_maker:v1:stream:new_trade".to_string()]);
+    assert_eq!(keys::control_channels(), vec![keys::channel_control()]);
+    assert!(keys::configure(true));
+    assert!(!keys::configure(false), "set once at startup");
+
+    // The envelope on the v1 names, the previous shape on the legacy ones
+    let messages = outgoing(&event).unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].channels, vec![keys::channel_trades(ID)]);
+    assert!(serde_json::from_str::<Envelope>(&messages[0].payload).is_ok());
+    assert_eq!(messages[1].channels, vec!["tycho_market_maker".to_string()]);
+    assert_eq!(messages[1].streams, vec!["tycho_market_maker:new_trade".to_string()]);
+    let legacy: RedisMessage = serde_json::from_str(&messages[1].payload).unwrap();
+    assert!(matches!(legacy.message, MessageType::NewTrade));
+    assert_eq!(legacy.timestamp, event.published_at / 1_000, "Unix seconds");
+    assert_eq!(legacy.data, event.payload);
+    assert!(!messages[1].payload.contains("schema_version"));
+    assert_eq!(keys::control_channels(), vec!["tycho_market_maker:v1:control".to_string(), "tycho_market_maker:control".to_string()]);
+    assert_eq!(keys::breaker_resume_keys(ID), vec![keys::key_breaker_resume(ID), format!("breaker:resume:{}", ID)]);
+}
@@ -0,0 +1,55 @@
+//! Stream lag: the head is sampled every N blocks, and a lag past the threshold suppresses order creation once per episode.
+use shd::maker::lag::{LagTransition, StreamLag};
+use shd::types::config::load_market_maker_config;
+
+#[test]
+fn test_sampled_every_n_messages() {
+    let mut lag = StreamLag::new(3, 4);
+    let due = (0..9).map(|_| lag.due()).collect::<Vec<bool>>();
+    assert_eq!(due, vec![true, false, false, false, true, false, false, false, true]);
+    let mut every = StreamLag::new(3, 1);
+    assert!((0..3).all(|_| every.due()));
+}
+
+#[test]
+fn test_one_transition_per_episode() {
+    let mut lag = StreamLag::new(3, 1);
+    assert_eq!(lag.lag_blocks(), None);
+    assert_eq!(lag.observe(100, 102), None);
+    assert_eq!(lag.lag_blocks(), Some(2));
+    assert!(!lag.is_lagging());
+
+    // Past the threshold: logged once, suppressed until it clears
+    assert_eq!(lag.observe(100, 104), Some(LagTransition::Lagging(4)));
+    assert!(lag.is_lagging());
+    assert_eq!(lag.observe(101, 110), None);
+    assert_eq!(lag.lag_blocks(), Some(9));
+    assert!(lag.is_lagging());
+
+    // A failed head lookup (0) changes nothing
+    assert_eq!(lag.observe(110, 0), None);
+    assert_eq!(lag.lag_blocks(), Some(9));
+
+    assert_eq!(lag.observe(110, 113), Some(LagTransition::Recovered(3)));
+    assert!(!lag.is_lagging());
+    // Stream ahead of a lagging RPC
+    assert_eq!(lag.observe(115, 114), None);
+    assert_eq!(lag.lag_blocks(), Some(0));
+}
+
+#[test]
+fn test_measured_only_without_threshold() {
+    let mut lag = StreamLag::new(0, 1);
+    assert_eq!(lag.observe(100, 200), None);
+    assert_eq!(lag.lag_blocks(), Some(100));
+    assert!(!lag.is_lagging());
+}
+
+#[test]
+fn test_config_defaults() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    assert_eq!(config.max_stream_lag_blocks, 0);
+    assert_eq!(config.stream_lag_sample_every, 5);
+    config.stream_lag_sample_every = 0;
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,196 @@
+//! Latency budget: the stages after a block update check the time elapsed since its receipt, on a mocked clock, and the
+//! remaining pipeline is aborted with a `Timeout` past the total budget, its trades published with the `Timeout` status.
+mod common;
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use common::{env, trade};
+use shd::error::{ExecError, ExecPolicy};
+use shd::maker::exec::ExecStrategy;
+use shd::maker::latency::{Clock, Deadline, Stage};
+use shd::types::config::{EnvConfig, LatencyBudget, MarketMakerConfig, NetworkName};
+use shd::types::maker::{BroadcastData, SimulatedData, Trade, TradeStatus};
+
+/// Clock moved forward by the tests only.
+#[derive(Default)]
+struct MockClock(AtomicU64);
+
+impl MockClock {
+    fn advance(&self, ms: u64) {
+        self.0.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+const BUDGET: LatencyBudget = LatencyBudget {
+    context_ms: 100,
+    inventory_ms: 200,
+    prepare_ms: 300,
+    simulate_ms: 400,
+    broadcast_ms: 500,
+    total_ms: 600,
+};
+
+/// Strategy whose simulation takes `simulation_ms` on the mocked clock and succeeds, counting the simulations and
+/// broadcasts, recording the trades published.
+struct SlowExec {
+    clock: Arc<MockClock>,
+    simulation_ms: u64,
+    simulations: AtomicUsize,
+    broadcasts: AtomicUsize,
+    published: Mutex<Vec<(String, TradeStatus)>>,
+}
+
+impl SlowExec {
+    fn new(clock: Arc<MockClock>, simulation_ms: u64) -> Self {
+        Self {
+            clock,
+            simulation_ms,
+            simulations: AtomicUsize::new(0),
+            broadcasts: AtomicUsize::new(0),
+            published: Mutex::new(vec![]),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecStrategy for SlowExec {
+    fn name(&self) -> String {
+        "Slow_Strategy".to_string()
+    }
+
+    async fn simulate(&self, _config: MarketMakerConfig, trades: Vec<Trade>, _env: EnvConfig) -> Result<Vec<SimulatedData>, ExecError> {
+        self.simulations.fetch_add(1, Ordering::SeqCst);
+        self.clock.advance(self.simulation_ms);
+        Ok(trades
+            .iter()
+            .map(|trade| SimulatedData {
+                trade_id: trade.metadata.trade_id.clone(),
+                status: true,
+                ..Default::default()
+            })
+            .collect())
+    }
+
+    async fn broadcast(&self, _prepared: Vec<Trade>, _mmc: MarketMakerConfig, _env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        self.broadcasts.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![])
+    }
+
+    async fn post_hook(&self, _config: &MarketMakerConfig, trades: Vec<Trade>, _identifier: String) {
+        self.published.lock().unwrap().extend(trades.into_iter().map(|t| (t.metadata.trade_id, t.metadata.status)));
+    }
+}
+
+fn trades() -> Vec<Trade> {
+    vec![trade("a"), trade("b")]
+}
+
+fn published(exec: &SlowExec) -> Vec<(String, TradeStatus)> {
+    exec.published.lock().unwrap().clone()
+}
+
+fn config() -> MarketMakerConfig {
+    MarketMakerConfig {
+        skip_simulation: false,
+        ..common::config()
+    }
+}
+
+#[test]
+fn test_stage_deadlines_and_total_budget() {
+    let clock = Arc::new(MockClock::default());
+    clock.advance(1_000_000);
+    let deadline = Deadline::start(BUDGET, clock.clone());
+    assert!(deadline.check(Stage::Context).is_ok());
+    clock.advance(250);
+    assert_eq!(deadline.elapsed_ms(), 250);
+    assert!(deadline.check(Stage::Inventory).is_ok(), "Late, reported only");
+    clock.advance(350);
+    assert!(deadline.check(Stage::Broadcast).is_ok(), "Exactly at the total budget");
+    clock.advance(1);
+    let err = deadline.check(Stage::Prepare).expect_err("Past the total budget");
+    assert!(matches!(err, ExecError::Timeout(_)), "{:?}", err);
+    assert!(err.to_string().contains("prepare"), "{}", err);
+    assert_eq!(err.policy(), ExecPolicy::Retry, "The next block starts a new budget");
+
+    let disabled = Deadline::start(LatencyBudget { total_ms: 0, ..BUDGET }, clock.clone());
+    clock.advance(3_600_000);
+    assert!(disabled.check(Stage::Broadcast).is_ok());
+}
+
+#[tokio::test]
+async fn test_slow_simulation_aborts_the_broadcast() {
+    let clock = Arc::new(MockClock::default());
+    let exec = SlowExec::new(clock.clone(), 700);
+    let deadline = Deadline::start(BUDGET, clock.clone());
+    let err = exec
+        .execute_within(config(), trades(), env(), "test".to_string(), &deadline)
+        .await
+        .expect_err("Past the budget after the simulation");
+    assert!(matches!(err, ExecError::Timeout(_)), "{:?}", err);
+    assert_eq!(exec.simulations.load(Ordering::SeqCst), 1);
+    assert_eq!(exec.broadcasts.load(Ordering::SeqCst), 0, "Nothing sent past the budget");
+    assert_eq!(
+        published(&exec),
+        vec![("a".to_string(), TradeStatus::Timeout), ("b".to_string(), TradeStatus::Timeout)],
+        "Simulated trades published, not dropped"
+    );
+}
+
+#[tokio::test]
+async fn test_late_block_update_not_simulated() {
+    let clock = Arc::new(MockClock::default());
+    let exec = SlowExec::new(clock.clone(), 0);
+    let deadline = Deadline::start(BUDGET, clock.clone());
+    clock.advance(BUDGET.total_ms + 1);
+    assert!(exec.execute_within(config(), trades(), env(), "test".to_string(), &deadline).await.is_err());
+    assert_eq!(exec.simulations.load(Ordering::SeqCst), 0);
+    assert_eq!(exec.broadcasts.load(Ordering::SeqCst), 0);
+    assert_eq!(published(&exec), vec![("a".to_string(), TradeStatus::Timeout), ("b".to_string(), TradeStatus::Timeout)]);
+}
+
+#[tokio::test]
+async fn test_within_budget_executed() {
+    let clock = Arc::new(MockClock::default());
+    let exec = SlowExec::new(clock.clone(), 450);
+    let deadline = Deadline::start(BUDGET, clock.clone());
+    assert!(
+        exec.execute_within(config(), trades(), env(), "test".to_string(), &deadline).await.is_ok(),
+        "Late broadcast, within the budget"
+    );
+    assert_eq!(exec.broadcasts.load(Ordering::SeqCst), 1);
+    assert!(published(&exec).iter().all(|(_, status)| *status == TradeStatus::SimulationSucceeded), "Not timed out");
+
+    // No budget out of the stream loop
+    let exec = SlowExec::new(clock.clone(), 1_000_000);
+    assert!(exec.execute(config(), trades(), env(), "test".to_string()).await.is_ok());
+    assert_eq!(exec.broadcasts.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_network_defaults() {
+    let mut config = config();
+    assert!(config.latency_budget.is_none());
+    assert_eq!(config.latency_budget(), NetworkName::Ethereum.latency_budget());
+    for network in [NetworkName::Ethereum, NetworkName::Base, NetworkName::Unichain] {
+        let budget = network.latency_budget();
+        assert!(budget.validate().is_ok(), "{:?}", network);
+        assert!(budget.total_ms < network.block_time_s() * 1_000, "{:?}: within a block", network);
+    }
+    assert!(NetworkName::Base.latency_budget().total_ms < NetworkName::Ethereum.latency_budget().total_ms);
+
+    config.latency_budget = Some(BUDGET);
+    assert_eq!(config.latency_budget(), BUDGET, "Override");
+    assert!(config.validate().is_ok());
+    config.latency_budget = Some(LatencyBudget { broadcast_ms: 50, ..BUDGET });
+    let err = config.validate().expect_err("Broadcast deadline before the simulation one").to_string();
+    assert!(err.contains("latency_budget"), "{}", err);
+}
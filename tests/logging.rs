@@ -0,0 +1,66 @@
+//! Structured logging: LOG_FORMAT parsing, and the `trade_id` of a trade on the JSON lines of its execution.
+mod common;
+
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use common::{config, env, trade};
+use shd::maker::exec::{dry::DryRunExec, ExecStrategy};
+use shd::types::config::LogFormat;
+use shd::utils::misc::now_ms;
+
+/// Writer appending to a shared buffer, read back by the test.
+#[derive(Clone)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_log_format_parsing() {
+    assert_eq!(LogFormat::default(), LogFormat::Text);
+    assert_eq!(LogFormat::from_str("json").unwrap(), LogFormat::Json);
+    assert_eq!(LogFormat::from_str("text").unwrap().as_str(), "text");
+    assert!(LogFormat::from_str("yaml").unwrap_err().contains("expected text or json"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_trade_id_propagates_into_json_lines() {
+    let config = config();
+    let buffer = Buffer(Arc::new(Mutex::new(vec![])));
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_max_level(tracing::Level::TRACE)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let trades = DryRunExec::new().execute(config, vec![trade("trade-123")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(trades[0].metadata.trade_id, "trade-123");
+    let broadcasted_at_ms = trades[0].metadata.broadcast.as_ref().unwrap().broadcasted_at_ms;
+    assert!(broadcasted_at_ms.abs_diff(now_ms()) < 60_000, "Unix time, not an elapsed time: {}", broadcasted_at_ms);
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let lines = output.lines().map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()).collect::<Vec<serde_json::Value>>();
+    assert!(!lines.is_empty());
+    // Every line of the execution, broadcast included, carries the id of the trade
+    for line in lines.iter() {
+        assert_eq!(line["spans"][0]["name"], "trade");
+        assert_eq!(line["spans"][0]["trade_id"], "trade-123");
+        assert_eq!(line["spans"][0]["strategy"], "DryRun_Strategy");
+    }
+    assert!(lines.iter().any(|line| line["span"]["name"] == "broadcast"), "Broadcast stage span");
+    assert!(lines.iter().any(|line| line["fields"]["message"].as_str().unwrap_or_default().contains("not broadcast")));
+}
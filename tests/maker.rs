@@ -0,0 +1,210 @@
+//! Maker logic on mock pools: evaluate thresholds, readjust sizing and profit math, spot prices token ordering and orientation checks.
+mod common;
+
+use common::{base, component, context, inventory, maker, pool, quote, readjustment, token, watch_config, MockBalances, MockProtocolSim, MOCK_SWAP_GAS};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use shd::maker::tycho::pair_indices;
+use shd::opti::math::shift_bps;
+use shd::opti::skew::InventorySkew;
+use shd::types::config::RebalanceMode;
+use shd::types::maker::{Inventory, TradeDirection};
+use shd::types::tycho::ProtoSimComp;
+use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+
+/// Pool of 1000 ETH quoting `spot`, with a 30 bps fee.
+fn target(spot: f64) -> ProtoSimComp {
+    pool(POOL, 1_000.0, 1_000.0 * spot, 0.003)
+}
+
+#[test]
+fn test_evaluate_threshold_crossing_both_directions() {
+    let mk = maker(watch_config());
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let evaluate = |spot: f64| mk.evaluate(&[target(spot)], vec![spot], REFERENCE, &skew, 100);
+
+    // 9 bps on either side stays below the 10 bps watch threshold
+    assert!(evaluate(REFERENCE * 1.0009).is_empty());
+    assert!(evaluate(REFERENCE * 0.9991).is_empty());
+
+    // Pool above the reference: sell base into it
+    let above = evaluate(REFERENCE * 1.0011);
+    assert_eq!(above.len(), 1);
+    assert_eq!(
+        (above[0].direction.clone(), above[0].selling.symbol.as_str(), above[0].buying.symbol.as_str()),
+        (TradeDirection::Buy, "ETH", "USDC")
+    );
+    assert!((above[0].spread_bps - 11.0).abs() < 1e-9);
+
+    // Pool below the reference: buy base from it
+    let below = evaluate(REFERENCE * 0.9989);
+    assert_eq!(below.len(), 1);
+    assert_eq!(
+        (below[0].direction.clone(), below[0].selling.symbol.as_str(), below[0].buying.symbol.as_str()),
+        (TradeDirection::Sell, "USDC", "ETH")
+    );
+    assert!((below[0].spread_bps + 11.0).abs() < 1e-9);
+
+    assert!(mk.evaluate(&[target(3_030.0)], vec![], REFERENCE, &skew, 100).is_empty(), "No spot price");
+    assert!(mk.evaluate(&[target(3_030.0)], vec![3_030.0, 3_030.0], REFERENCE, &skew, 100).is_empty(), "Length mismatch");
+}
+
+#[tokio::test]
+async fn test_readjust_spread_and_profit_math() {
+    let mk = maker(watch_config());
+    let psc = target(3_030.0);
+    let balances = MockBalances::of(std::slice::from_ref(&psc));
+    let orders = mk.readjust(context(REFERENCE, 1.0, 100), inventory(), vec![readjustment(&psc, 3_030.0, REFERENCE)], &balances).await;
+    assert_eq!(orders.len(), 1);
+    let calculation = &orders[0].calculation;
+    assert!(calculation.base_to_quote);
+    assert!(
+        calculation.selling_amount > 0. && calculation.selling_amount <= 9.9,
+        "Within max_inventory_ratio: {}",
+        calculation.selling_amount
+    );
+
+    // Output quoted by the pool itself, bounded by the slippage tolerance
+    let quoted = psc
+        .protosim
+        .get_amount_out(BigUint::from(calculation.powered_selling_amount.floor() as u128), &base(), &quote())
+        .unwrap();
+    assert!((calculation.amount_out_normalized - quoted.amount.to_f64().unwrap() / 1e6).abs() < 1e-9);
+    assert!((calculation.amount_out_min_normalized - calculation.amount_out_normalized * (1. - mk.config.max_slippage_pct)).abs() < 1e-9);
+    assert!((calculation.average_sell_price - calculation.amount_out_normalized / calculation.selling_amount).abs() < 1e-9);
+    assert!(
+        calculation.average_sell_price < 3_030.0 && calculation.average_sell_price > REFERENCE,
+        "Filled between the reference and the spot"
+    );
+
+    // 120k gas at 1 gwei, ETH at 3000 $: 0.36 $, taken from the USDC output
+    assert_eq!(calculation.gas_units, MOCK_SWAP_GAS as u128);
+    assert!((calculation.gas_cost_usd - 0.36).abs() < 1e-9);
+    assert!((calculation.gas_cost_in_output_token - 0.36).abs() < 1e-9);
+    let net = (calculation.amount_out_normalized - 0.36) / calculation.selling_amount;
+    assert!((calculation.average_sell_price_net_gas - net).abs() < 1e-9);
+    assert!((calculation.profit_delta_bps - (net - REFERENCE) / REFERENCE * 10_000.).abs() < 1e-6);
+    assert!(calculation.profitable && calculation.profit_delta_bps > mk.config.min_executable_spread_bps);
+
+    // Pool below the reference: USDC sold for ETH, profit measured the other way around
+    let psc = target(2_970.0);
+    let balances = MockBalances::of(std::slice::from_ref(&psc));
+    let orders = mk.readjust(context(REFERENCE, 1.0, 100), inventory(), vec![readjustment(&psc, 2_970.0, REFERENCE)], &balances).await;
+    assert_eq!(orders.len(), 1);
+    let calculation = &orders[0].calculation;
+    assert!(!calculation.base_to_quote);
+    assert!(calculation.average_sell_price > 2_970.0 && calculation.average_sell_price < REFERENCE);
+    assert!((calculation.profit_delta_bps - (REFERENCE - calculation.average_sell_price_net_gas) / REFERENCE * 10_000.).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_readjust_exact_out_bounds_the_input_by_the_slippage() {
+    let mut config = watch_config();
+    config.rebalance_mode = RebalanceMode::ExactOut;
+    let mk = maker(config);
+    let psc = target(3_030.0);
+    let balances = MockBalances::of(std::slice::from_ref(&psc));
+    // 10 ETH against 10_000 USDC: USDC is below its target share, bought exactly
+    let inventory = Inventory {
+        quote_balance: 10_000 * 10u128.pow(6),
+        ..inventory()
+    };
+    let orders = mk.readjust(context(REFERENCE, 1.0, 100), inventory, vec![readjustment(&psc, 3_030.0, REFERENCE)], &balances).await;
+    assert_eq!(orders.len(), 1);
+    let calculation = &orders[0].calculation;
+    assert!(calculation.exact_out);
+    assert!(calculation.amount_out_normalized >= calculation.amount_out_min_normalized, "The reverse quote reaches the target");
+
+    // The reverse quote raised by the slippage tolerance is the max input, the target the minimum output
+    let slippage_bps = mk.config.max_slippage_pct * 10_000.;
+    assert_eq!(calculation.amount_in_max_raw, shift_bps(&calculation.amount_in_raw, slippage_bps));
+    assert!(calculation.amount_in_max_raw > calculation.amount_in_raw);
+    assert!((calculation.amount_in_max_normalized - calculation.selling_amount * (1. + mk.config.max_slippage_pct)).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_readjust_skips_unfillable_adjustments() {
+    let mk = maker(watch_config());
+    let psc = target(3_030.0);
+    let balances = MockBalances::of(std::slice::from_ref(&psc));
+    let adjustment = || vec![readjustment(&psc, 3_030.0, REFERENCE)];
+
+    let empty = Inventory { base_balance: 0, ..inventory() };
+    assert!(mk.readjust(context(REFERENCE, 1.0, 100), empty, adjustment(), &balances).await.is_empty(), "No base to sell");
+    assert!(
+        mk.readjust(context(REFERENCE, 1.0, 100), inventory(), adjustment(), &MockBalances::default()).await.is_empty(),
+        "No pool balances"
+    );
+    assert!(mk.readjust(context(REFERENCE, 1.0, 100), inventory(), vec![], &balances).await.is_empty(), "No adjustment");
+
+    // 5 bps of spread does not pay the 30 bps pool fee
+    let thin = target(3_001.5);
+    let balances = MockBalances::of(std::slice::from_ref(&thin));
+    assert!(mk
+        .readjust(context(REFERENCE, 1.0, 100), inventory(), vec![readjustment(&thin, 3_001.5, REFERENCE)], &balances)
+        .await
+        .is_empty());
+}
+
+#[test]
+fn test_prices_quote_per_base_whatever_the_token_order() {
+    let mk = maker(watch_config());
+    let reversed = ProtoSimComp {
+        component: component("0xAAAA000000000000000000000000000000000002", "uniswap_v2", vec![quote(), base()]),
+        protosim: Box::new(MockProtocolSim::new(quote(), base(), 3_000_000.0, 1_000.0, 0.003)),
+    };
+    let prices = mk.prices(&[target(3_030.0), reversed], REFERENCE);
+    assert_eq!(prices.len(), 2);
+    assert!((prices[0].price - 3_030.0).abs() < 1e-9);
+    assert!((prices[1].price - 3_000.0).abs() < 1e-9, "Token order of the component does not invert the price");
+    assert_eq!(prices[1].address, "0xaaaa000000000000000000000000000000000002");
+    assert_eq!(prices[1].r#type, "uniswap_v2");
+}
+
+/// Component of `tokens` over a pool of 1000 ETH quoting `spot`.
+fn listing(id: &str, tokens: Vec<Token>, spot: f64) -> ProtoSimComp {
+    ProtoSimComp {
+        component: component(id, "vm:balancer_v2", tokens),
+        protosim: Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 1_000.0 * spot, 0.003)),
+    }
+}
+
+#[test]
+fn test_prices_locate_the_pair_among_more_tokens() {
+    let mk = maker(watch_config());
+    let dai = token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18);
+    let wbtc = token("0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599", "WBTC", 8);
+    let three = listing("0xaaaa000000000000000000000000000000000003", vec![dai.clone(), quote(), base()], 3_010.0);
+    let four = listing("0xaaaa000000000000000000000000000000000004", vec![base(), wbtc.clone(), dai.clone(), quote()], 2_990.0);
+    let prices = mk.prices(&[three, four], REFERENCE);
+    assert_eq!(prices.len(), 2);
+    assert!((prices[0].price - 3_010.0).abs() < 1e-9, "{}", prices[0].price);
+    assert!((prices[1].price - 2_990.0).abs() < 1e-9, "{}", prices[1].price);
+
+    // Base missing, or listed twice: no price rather than a wrong one
+    let missing = listing("0xaaaa000000000000000000000000000000000005", vec![dai.clone(), quote()], 3_000.0);
+    let twice = listing("0xaaaa000000000000000000000000000000000006", vec![base(), quote(), base()], 3_000.0);
+    assert!(mk.prices(&[missing, twice], REFERENCE).is_empty());
+
+    let cp = component("0xaaaa000000000000000000000000000000000005", "vm:curve", vec![dai, quote(), wbtc]);
+    let err = pair_indices(&cp, &base(), &quote()).unwrap_err();
+    assert!(err.contains("ETH not in the component tokens"), "{}", err);
+}
+
+#[test]
+fn test_prices_skip_inverted_spots() {
+    let mk = maker(watch_config());
+    // Quoting base per quote: 1/3000, far out of the band while its inverse is at the reference
+    let inverted = listing("0xaaaa000000000000000000000000000000000007", vec![base(), quote()], 1.0 / 3_000.0);
+    // Out of the band either way: kept, for the quarantine to report it
+    let broken = target(30_000.0);
+    let prices = mk.prices(&[inverted, broken, target(3_030.0)], REFERENCE);
+    assert_eq!(prices.iter().map(|p| p.price.round()).collect::<Vec<f64>>(), vec![30_000.0, 3_030.0]);
+
+    // No reference, no cross-check
+    assert_eq!(mk.prices(&[listing("0xaaaa000000000000000000000000000000000007", vec![base(), quote()], 1.0 / 3_000.0)], 0.0).len(), 1);
+}
@@ -0,0 +1,68 @@
+//! ERC20 metadata: ABI decoding of `symbol()` and `decimals()`, bytes32 symbols included, and the check against Tycho tokens.
+mod common;
+
+use alloy::sol_types::SolValue;
+use alloy_primitives::U256;
+use common::{base, quote};
+use shd::utils::evm::{decode_decimals, decode_symbol, Erc20Metadata};
+
+/// bytes32 return data of MKR `symbol()`.
+const MKR_SYMBOL: &str = "4d4b520000000000000000000000000000000000000000000000000000000000";
+
+#[test]
+fn test_decode_string_and_bytes32_symbols() {
+    assert_eq!(decode_symbol(&"USDC".to_string().abi_encode()).unwrap(), "USDC");
+    // Return data of USDC symbol(): offset, length, then the padded bytes
+    let raw = [
+        "0000000000000000000000000000000000000000000000000000000000000020",
+        "0000000000000000000000000000000000000000000000000000000000000004",
+        "5553444300000000000000000000000000000000000000000000000000000000",
+    ]
+    .concat();
+    assert_eq!(decode_symbol(&hex::decode(raw).unwrap()).unwrap(), "USDC");
+
+    assert_eq!(decode_symbol(&hex::decode(MKR_SYMBOL).unwrap()).unwrap(), "MKR");
+    let full = [b'A'; 32];
+    assert_eq!(decode_symbol(&full).unwrap(), "A".repeat(32), "bytes32 without padding");
+
+    assert!(decode_symbol(&[]).is_err());
+    assert!(decode_symbol(&[0x4d, 0x4b, 0x52]).is_err(), "Neither a string nor a bytes32");
+    let mut invalid = [0u8; 32];
+    invalid[0] = 0xff;
+    assert!(decode_symbol(&invalid).is_err(), "Not UTF-8");
+}
+
+#[test]
+fn test_decode_decimals() {
+    assert_eq!(decode_decimals(&U256::from(6u8).abi_encode()).unwrap(), 6);
+    assert_eq!(decode_decimals(&U256::from(18u8).abi_encode()).unwrap(), 18);
+    assert!(decode_decimals(&U256::from(256u16).abi_encode()).is_err(), "Beyond uint8");
+    assert!(decode_decimals(&[0x12]).is_err(), "Short return data");
+    assert!(decode_decimals(&[]).is_err(), "No code at the address");
+}
+
+#[test]
+fn test_metadata_against_tycho_tokens() {
+    let usdc = Erc20Metadata {
+        symbol: "USDC".to_string(),
+        decimals: 6,
+    };
+    usdc.matches(&quote()).unwrap();
+    let lowercase = Erc20Metadata {
+        symbol: "usdc".to_string(),
+        decimals: 6,
+    };
+    lowercase.matches(&quote()).unwrap();
+
+    let err = Erc20Metadata { decimals: 18, ..usdc.clone() }.matches(&quote()).unwrap_err();
+    assert!(err.contains("Decimals mismatch") && err.contains("6 from Tycho, 18 on-chain"), "{}", err);
+    let err = usdc.matches(&base()).unwrap_err();
+    assert!(err.contains("Decimals mismatch"), "{}", err);
+    let err = Erc20Metadata {
+        symbol: "WETH".to_string(),
+        decimals: 18,
+    }
+    .matches(&base())
+    .unwrap_err();
+    assert!(err.contains("Symbol mismatch") && err.contains("ETH from Tycho, WETH on-chain"), "{}", err);
+}
@@ -0,0 +1,238 @@
+//! Monitor handler: every message goes through the one shared connection, against a SQLite-backed sea-orm database
+//! created by the migrations, from the payloads received on Redis down to the rows.
+mod common;
+
+use common::sqlite;
+use sea_orm::ConnectionTrait;
+use shd::data::dead_letter::deliver;
+use shd::data::migration::{applied, migrate, MIGRATIONS};
+use shd::data::neon::{handle, pull, Neon};
+use shd::types::config::{load_market_maker_config, DatabaseType, EventsTransport, MarketMakerConfig, MoniEnvConfig};
+use shd::types::moni::{EndInstanceMessage, Envelope, MessageType, NewInstanceMessage, NewPricesMessage, ParsedMessage, TradingState};
+
+fn start(config: &MarketMakerConfig, timestamp: u64) -> (String, ParsedMessage) {
+    let identifier = format!("{}-instance-{}", config.id(), timestamp);
+    let message = ParsedMessage::NewInstance(NewInstanceMessage {
+        config: config.clone(),
+        identifier: identifier.clone(),
+        commit: "test".to_string(),
+    });
+    (identifier, message)
+}
+
+fn prices(identifier: &str, block: u64) -> ParsedMessage {
+    ParsedMessage::NewPrices(NewPricesMessage {
+        identifier: identifier.to_string(),
+        reference_price: 2500.0 + block as f64,
+        components: vec![],
+        block,
+        depth: None,
+        state: TradingState::Running,
+        sigma_bps: 0.0,
+        execution_threshold_bps: 0.0,
+        adaptive_bps: 0.0,
+        quarantined: 0,
+        stream_lag_blocks: None,
+        degraded: false,
+    })
+}
+
+#[tokio::test]
+async fn test_messages_share_one_connection() {
+    let (db, path) = sqlite("pump").await;
+    let neon = Neon::from_connection(db);
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let identifier = "mainnet-eth-usdc-test".to_string();
+
+    let mut messages = vec![ParsedMessage::NewInstance(NewInstanceMessage {
+        config,
+        identifier: identifier.clone(),
+        commit: "test".to_string(),
+    })];
+    messages.extend((1..=98).map(|block| prices(&identifier, block)));
+    messages.push(ParsedMessage::EndInstance(EndInstanceMessage {
+        identifier: identifier.clone(),
+        reason: "shutdown".to_string(),
+    }));
+    assert_eq!(messages.len(), 100);
+    for message in messages.iter() {
+        handle(message, &neon.db().await).await.unwrap();
+    }
+
+    let db = neon.db().await;
+    assert_eq!(pull::configurations(&db).await.unwrap().len(), 1);
+    let instances = pull::instances(&db).await.unwrap();
+    assert_eq!(instances.len(), 1);
+    assert!(instances[0].ended_at.is_some());
+    assert_eq!(pull::prices(&db).await.unwrap().len(), 98);
+    assert_eq!(neon.connects(), 1, "no reconnection while the database answers");
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_database_failure_is_an_error() {
+    let (db, path) = sqlite("failure").await;
+    let neon = Neon::from_connection(db);
+    // Prices of an unknown instance are skipped, not retried
+    handle(&prices("unknown", 1), &neon.db().await).await.unwrap();
+    neon.db().await.execute_unprepared("DROP TABLE \"Instance\"").await.unwrap();
+    assert!(handle(&prices("unknown", 2), &neon.db().await).await.is_err(), "a failed query keeps a stream entry pending");
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_restart_closes_only_its_own_instance() {
+    let (db, path) = sqlite("restart").await;
+    let bot_a = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let mut bot_b = bot_a.clone();
+    bot_b.wallet_public_key = "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf".to_string();
+    bot_b.min_watch_spread_bps += 1.0;
+
+    let (a1, message) = start(&bot_a, 1);
+    handle(&message, &db).await.unwrap();
+    let (b1, message) = start(&bot_b, 2);
+    handle(&message, &db).await.unwrap();
+    let configurations = pull::configurations(&db).await.unwrap();
+    assert_eq!(configurations.len(), 2);
+
+    // Bot A restarts: its previous instance is closed, bot B keeps running
+    let (a2, message) = start(&bot_a, 3);
+    handle(&message, &db).await.unwrap();
+    let instances = pull::instances(&db).await.unwrap();
+    let ended = |identifier: &str| instances.iter().find(|i| i.identifier == identifier).unwrap().ended_at.is_some();
+    assert!(ended(&a1));
+    assert!(!ended(&b1), "another bot's instance is left open");
+    assert!(!ended(&a2));
+
+    let cfg_a = configurations.iter().find(|c| c.hash == bot_a.hash()).unwrap();
+    let open = pull::open_instances_for_configuration(&db, &cfg_a.id).await.unwrap();
+    assert_eq!(open.iter().map(|i| i.identifier.clone()).collect::<Vec<String>>(), vec![a2.clone()]);
+
+    // Bot B restarts: only b1 is closed
+    let (b2, message) = start(&bot_b, 4);
+    handle(&message, &db).await.unwrap();
+    let instances = pull::instances(&db).await.unwrap();
+    let ended = |identifier: &str| instances.iter().find(|i| i.identifier == identifier).unwrap().ended_at.is_some();
+    assert!(ended(&b1) && !ended(&b2) && !ended(&a2));
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_migrations_run_once() {
+    let (db, path) = sqlite("migrations").await;
+    assert_eq!(migrate(&db).await.unwrap(), 0, "already applied by the fixture");
+    let versions = MIGRATIONS.iter().map(|version| version.to_string()).collect::<Vec<String>>();
+    assert_eq!(applied(&db).await.unwrap(), versions);
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_redis_payloads_to_in_memory_sqlite() {
+    let env = MoniEnvConfig {
+        testing: true,
+        heartbeat: String::new(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
+        database_url: "sqlite::memory:".to_string(),
+        database_name: String::new(),
+        database_backend: DatabaseType::Sqlite,
+        events_transport: EventsTransport::PubSub,
+        price_retention_days: 0,
+        price_downsample_after_days: 0,
+        price_downsample_minutes: 15,
+        trade_receipt_max_age_minutes: 60,
+        redis_legacy_keys: false,
+    };
+    env.validate().unwrap();
+    let neon = Neon::connect(env).await.unwrap();
+    assert_eq!(migrate(&neon.db().await).await.unwrap(), MIGRATIONS.len());
+
+    // Payloads as the makers publish them
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let (identifier, _) = start(&config, 1);
+    let envelope = |kind: MessageType, payload: serde_json::Value| serde_json::to_string(&Envelope::new(&kind, &identifier, payload)).unwrap();
+    let mut payloads = vec![envelope(
+        MessageType::NewInstance,
+        serde_json::to_value(NewInstanceMessage {
+            config: config.clone(),
+            identifier: identifier.clone(),
+            commit: "test".to_string(),
+        })
+        .unwrap(),
+    )];
+    for block in 1..=3 {
+        let ParsedMessage::NewPrices(msg) = prices(&identifier, block) else { unreachable!() };
+        payloads.push(envelope(MessageType::NewPrices, serde_json::to_value(msg).unwrap()));
+    }
+    payloads.push(envelope(
+        MessageType::EndInstance,
+        serde_json::to_value(EndInstanceMessage {
+            identifier: identifier.clone(),
+            reason: "shutdown".to_string(),
+        })
+        .unwrap(),
+    ));
+    for payload in payloads.iter() {
+        deliver(&neon, payload).await.unwrap();
+    }
+
+    let db = neon.db().await;
+    let instances = pull::instances(&db).await.unwrap();
+    assert_eq!(instances.len(), 1);
+    assert!(instances[0].ended_at.is_some());
+    assert_eq!(pull::prices(&db).await.unwrap().len(), 3);
+    assert_eq!(shd::data::dead_letter::dead_lettered(), 0, "nothing dead-lettered");
+}
+
+#[tokio::test]
+async fn test_supervisor_reconnects_after_a_disconnect() {
+    let path = std::env::temp_dir().join(format!("mkmk-monitor-reconnect-{}.sqlite", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let env = MoniEnvConfig {
+        testing: true,
+        heartbeat: String::new(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
+        database_url: format!("sqlite://{}", path.display()),
+        database_name: String::new(),
+        database_backend: DatabaseType::Sqlite,
+        events_transport: EventsTransport::PubSub,
+        price_retention_days: 0,
+        price_downsample_after_days: 0,
+        price_downsample_minutes: 15,
+        trade_receipt_max_age_minutes: 60,
+        redis_legacy_keys: false,
+    };
+    let neon = Neon::connect(env).await.unwrap();
+    migrate(&neon.db().await).await.unwrap();
+    neon.supervise();
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let (identifier, message) = start(&config, 1);
+    handle(&message, &neon.db().await).await.unwrap();
+
+    // The pool is closed under the monitor, as a dropped server connection would leave it
+    neon.db().await.close().await.unwrap();
+    assert!(handle(&prices(&identifier, 1), &neon.db().await).await.is_err());
+    assert_eq!(neon.connects(), 1);
+    neon.failed();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while neon.connects() == 1 && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(neon.connects(), 2, "reconnected on the failed ping");
+
+    // The messages go through the new connection
+    handle(&prices(&identifier, 2), &neon.db().await).await.unwrap();
+    assert_eq!(pull::prices(&neon.db().await).await.unwrap().len(), 1);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_database_url_matches_backend() {
+    assert!(DatabaseType::Sqlite.accepts("sqlite::memory:"));
+    assert!(DatabaseType::Sqlite.accepts("sqlite://./monitor.sqlite"));
+    assert!(!DatabaseType::Sqlite.accepts("postgres://user@host/db"));
+    assert!(DatabaseType::Postgres.accepts("postgresql://user@host/db"));
+    assert_eq!("sqlite".parse::<DatabaseType>().unwrap(), DatabaseType::Sqlite);
+    assert!("mysql".parse::<DatabaseType>().is_err());
+}
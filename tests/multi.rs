@@ -0,0 +1,86 @@
+//! Multi-pair runner tests: shared stream cache, per-pair identifiers and config paths parsing.
+mod common;
+
+use std::collections::HashMap;
+
+use common::{base, component, quote, MockProtocolSim};
+use shd::maker::multi::unique_identifiers;
+use shd::types::config::config_paths;
+use shd::types::tycho::{SharedUpdate, TychoStreamState};
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL_A: &str = "0xAAAA000000000000000000000000000000000001";
+const POOL_B: &str = "0xBBBB000000000000000000000000000000000002";
+
+fn empty_state() -> TychoStreamState {
+    TychoStreamState {
+        protosims: HashMap::new(),
+        components: HashMap::new(),
+        atks: vec![base(), quote()],
+        block: 0,
+    }
+}
+
+fn update(block: u64, new: &[&str], removed: &[&str]) -> SharedUpdate {
+    let mut states: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
+    let mut new_pairs = HashMap::new();
+    for id in new {
+        states.insert(id.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003)));
+        new_pairs.insert(id.to_string(), component(id, "uniswap_v2", vec![base(), quote()]));
+    }
+    let removed_pairs = removed.iter().map(|id| (id.to_string(), component(id, "uniswap_v2", vec![base(), quote()]))).collect();
+    SharedUpdate {
+        block_number_or_timestamp: block,
+        states,
+        new_pairs,
+        removed_pairs,
+    }
+}
+
+#[test]
+fn test_snapshot_empty_until_first_update() {
+    let state = empty_state();
+    assert!(state.snapshot().is_none());
+}
+
+#[test]
+fn test_apply_tracks_new_and_removed_pairs() {
+    let mut state = empty_state();
+    state.apply(&update(100, &[POOL_A, POOL_B], &[]));
+    assert_eq!(state.components.len(), 2);
+    assert_eq!(state.protosims.len(), 2);
+    assert!(state.protosims.contains_key(&POOL_A.to_lowercase()), "cache keys are lowercased");
+
+    state.apply(&update(101, &[], &[POOL_B]));
+    assert_eq!(state.components.len(), 1);
+    assert_eq!(state.protosims.len(), 1);
+    assert!(!state.components.contains_key(&POOL_B.to_lowercase()));
+}
+
+#[test]
+fn test_snapshot_bootstraps_a_late_pair() {
+    let mut state = empty_state();
+    state.apply(&update(100, &[POOL_A, POOL_B], &[]));
+    state.apply(&update(101, &[], &[POOL_A]));
+
+    let snapshot = state.snapshot().expect("Snapshot after first update");
+    assert_eq!(snapshot.block_number_or_timestamp, 101);
+    assert_eq!(snapshot.new_pairs.len(), 1);
+    assert_eq!(snapshot.states.len(), 1);
+    assert!(snapshot.new_pairs.contains_key(&POOL_B.to_lowercase()));
+    assert!(snapshot.removed_pairs.is_empty());
+}
+
+#[test]
+fn test_unique_identifiers_suffixes_duplicates_only() {
+    let identifiers = vec!["mmc-eth-usdc".to_string(), "mmc-btc-usdc".to_string(), "mmc-eth-usdc".to_string()];
+    let unique = unique_identifiers(identifiers);
+    assert_eq!(unique, vec!["mmc-eth-usdc-0".to_string(), "mmc-btc-usdc".to_string(), "mmc-eth-usdc-2".to_string()]);
+}
+
+#[test]
+fn test_config_paths_parsing() {
+    assert!(config_paths("").is_empty());
+    assert_eq!(config_paths("config/a.toml"), vec!["config/a.toml".to_string()]);
+    assert_eq!(config_paths(" config/a.toml, ,config/b.toml "), vec!["config/a.toml".to_string(), "config/b.toml".to_string()]);
+}
@@ -0,0 +1,92 @@
+//! Multicall3 batching of the wallet reads: calldata encoding and return data decoding against fixtures.
+use alloy_primitives::{Address, U256};
+use shd::types::config::load_market_maker_config;
+use shd::utils::constants::MULTICALL3_ADDRESS;
+use shd::utils::multicall::{decode, encode, Read};
+
+const WALLET: &str = "0x0af694c17137ad1de34e94335ea09608b715f20a";
+const ROUTER: &str = "0xfd0b31d2e955fa55e3fa641fe90e08b677188d35";
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+
+fn address(hex: &str) -> Address {
+    hex.parse().unwrap()
+}
+
+fn word(value: U256) -> String {
+    hex::encode(value.to_be_bytes::<32>())
+}
+
+fn uint(value: u128) -> String {
+    word(U256::from(value))
+}
+
+fn addr(hex: &str) -> String {
+    word(U256::from_be_slice(address(hex).as_slice()))
+}
+
+/// Inner call of a Call3, padded to a whole number of words.
+fn padded(data: &str) -> String {
+    let mut data = data.to_string();
+    while data.len() % 64 != 0 {
+        data.push('0');
+    }
+    data
+}
+
+#[test]
+fn test_aggregate3_calldata() {
+    let multicall = address(MULTICALL3_ADDRESS);
+    let reads = [
+        Read::Balance {
+            token: address(WETH),
+            owner: address(WALLET),
+        },
+        Read::Allowance {
+            token: address(WETH),
+            owner: address(WALLET),
+            spender: address(ROUTER),
+        },
+        Read::Native { owner: address(WALLET) },
+    ];
+    let balance = format!("70a08231{}", addr(WALLET));
+    let allowance = format!("dd62ed3e{}{}", addr(WALLET), addr(ROUTER));
+    let native = format!("4d2301cc{}", addr(WALLET));
+    // Call3 tuples: target, allowFailure, offset of callData (3 words), callData length and padded content
+    let call = |target: &str, data: &str| format!("{}{}{}{}{}", addr(target), uint(1), uint(0x60), uint(data.len() as u128 / 2), padded(data));
+    let calls = [call(WETH, &balance), call(WETH, &allowance), call(MULTICALL3_ADDRESS, &native)];
+    // Offsets of the tuples, from the start of the array content (after its length)
+    let mut offset = 3 * 32;
+    let mut offsets = String::new();
+    for call in calls.iter() {
+        offsets.push_str(&uint(offset as u128));
+        offset += call.len() / 2;
+    }
+    let expected = format!("82ad56cb{}{}{}{}", uint(0x20), uint(3), offsets, calls.concat());
+    assert_eq!(hex::encode(encode(multicall, &reads)), expected);
+}
+
+#[test]
+fn test_aggregate3_return_data() {
+    // [(true, 1000), (false, ""), (true, uint256 max)]
+    let results = [
+        format!("{}{}{}{}", uint(1), uint(0x40), uint(32), uint(1_000)),
+        format!("{}{}{}", uint(0), uint(0x40), uint(0)),
+        format!("{}{}{}{}", uint(1), uint(0x40), uint(32), word(U256::MAX)),
+    ];
+    let offsets = format!("{}{}{}", uint(0x60), uint(0x60 + 0x80), uint(0x60 + 0x80 + 0x60));
+    let data = hex::decode(format!("{}{}{}{}", uint(0x20), uint(3), offsets, results.concat())).unwrap();
+    // Failed read is None, an uint256 max allowance saturates
+    assert_eq!(decode(3, &data).unwrap(), vec![Some(1_000), None, Some(u128::MAX)]);
+    assert!(decode(2, &data).is_err(), "One result per read");
+    assert!(decode(3, &[]).is_err(), "No contract at the address");
+}
+
+#[test]
+fn test_multicall_address_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!(config.multicall_address, MULTICALL3_ADDRESS, "Canonical deployment by default");
+    config.multicall_address = String::new();
+    assert!(config.validate().is_ok(), "Empty reads one by one");
+    config.multicall_address = "0x1234".to_string();
+    assert!(config.validate().is_err());
+}
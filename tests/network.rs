@@ -0,0 +1,36 @@
+use shd::types::config::{load_market_maker_config, MarketMakerConfig, NetworkName};
+use std::str::FromStr;
+
+fn reference_config() -> MarketMakerConfig {
+    load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load")
+}
+
+#[test]
+fn test_chain_ids() {
+    assert_eq!(NetworkName::from_str("ethereum").unwrap().chain_id(), 1);
+    assert_eq!(NetworkName::from_str("base").unwrap().chain_id(), 8453);
+    assert_eq!(NetworkName::from_str("unichain").unwrap().chain_id(), 130);
+    assert!(reference_config().validate().is_ok());
+}
+
+#[test]
+fn test_chain_id_mismatch_rejected() {
+    let mut config = reference_config();
+    config.chain_id = 8453;
+    let err = config.validate().expect_err("Base chain id on mainnet").to_string();
+    assert!(err.contains("chain_id 8453"), "{}", err);
+}
+
+#[test]
+fn test_unknown_network_rejected() {
+    let mut config = reference_config();
+    config.network_name = "solana".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_explorer_url_needs_trailing_slash() {
+    let mut config = reference_config();
+    config.explorer_url = "https://etherscan.io".to_string();
+    assert!(config.validate().is_err());
+}
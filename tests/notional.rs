@@ -0,0 +1,76 @@
+//! Max trade notional: a readjustment worth more than `max_trade_usd` is clamped down to it, with raw amounts matching
+//! the decimals of the sold token.
+mod common;
+
+use common::{context, maker, pool, MockBalances};
+use shd::maker::valuation::{cap_worth, worth};
+use shd::opti::math::powered;
+use shd::opti::skew::InventorySkew;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::{ExecutionOrder, Inventory};
+
+const REFERENCE: f64 = 3_000.0;
+const BLOCK: u64 = 100;
+
+fn config(max_trade_usd: Option<f64>) -> MarketMakerConfig {
+    let mut config = common::config();
+    config.min_watch_spread_bps = 10.0;
+    config.min_executable_spread_bps = 0.0;
+    config.max_trade_usd = max_trade_usd;
+    config
+}
+
+/// Order of a pool quoting `spot` against the reference, selling ETH above it and USDC below.
+async fn order(max_trade_usd: Option<f64>, spot: f64) -> Option<ExecutionOrder> {
+    let mk = maker(config(max_trade_usd));
+    let targets = vec![pool("0xaaaa000000000000000000000000000000000001", 1_000., 1_000. * spot, 0.003)];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let adjustments = mk.evaluate(&targets, vec![spot], REFERENCE, &skew, BLOCK);
+    let inventory = Inventory {
+        base_balance: 10 * 10u128.pow(18),
+        quote_balance: 30_000 * 10u128.pow(6),
+        nonce: 0,
+        native_balance: 0,
+    };
+    mk.readjust(context(REFERENCE, 1.0, BLOCK), inventory, adjustments, &MockBalances::of(&targets))
+        .await
+        .into_iter()
+        .next()
+}
+
+#[test]
+fn test_cap_worth() {
+    let context = context(REFERENCE, 1.0, BLOCK);
+    assert_eq!(cap_worth(1.0, true, &context, 3_000.0), None, "Exactly at the cap");
+    assert_eq!(cap_worth(500.0, false, &context, 1_000.0), None);
+    let clamped = cap_worth(2.0, true, &context, 1_500.0).unwrap();
+    assert!((clamped - 0.5).abs() < 1e-12, "{}", clamped);
+    let clamped = cap_worth(5_000.0, false, &context, 1_500.0).unwrap();
+    assert!((worth(clamped, false, &context).1 - 1_500.0).abs() < 1e-9, "{}", clamped);
+}
+
+#[tokio::test]
+async fn test_clamped_on_both_sides() {
+    // Selling ETH (18 decimals) then USDC (6 decimals)
+    for (spot, decimals) in [(3_030.0, 18), (2_970.0, 6)] {
+        let uncapped = order(None, spot).await.expect("Profitable rebalance");
+        assert!(uncapped.calculation.selling_worth_usd > 1_000.0, "{}", uncapped.calculation.selling_worth_usd);
+
+        let calc = order(Some(1_000.0), spot).await.expect("Still profitable once clamped").calculation;
+        assert!((calc.selling_worth_usd - 1_000.0).abs() < 1e-6, "{}", calc.selling_worth_usd);
+        assert!(calc.selling_amount < uncapped.calculation.selling_amount);
+        assert!(!calc.exact_out);
+        assert_eq!(calc.amount_in_raw, powered(calc.selling_amount, decimals), "Raw amount in {} decimals", decimals);
+        assert!((calc.powered_selling_amount / 10f64.powi(decimals as i32) - calc.selling_amount).abs() < 1e-9);
+        let ratio = calc.buying_amount / calc.selling_amount;
+        let uncapped_ratio = uncapped.calculation.buying_amount / uncapped.calculation.selling_amount;
+        assert!((ratio - uncapped_ratio).abs() / uncapped_ratio < 1e-9, "Bought in proportion");
+    }
+}
+
+#[tokio::test]
+async fn test_within_cap_unchanged() {
+    let uncapped = order(None, 3_030.0).await.unwrap().calculation;
+    let capped = order(Some(uncapped.selling_worth_usd * 2.), 3_030.0).await.unwrap().calculation;
+    assert_eq!(capped.selling_amount, uncapped.selling_amount);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::{base, quote, MockProtocolSim, ScriptedSim};
+use num_bigint::BigUint;
+use shd::opti::math::{find_amount_in, find_optimal_swap_amount, TerminationReason};
+use shd::utils::constants::{OPTI_MAX_ITERATIONS, OPTI_MAX_SIMULATIONS, OPTI_TOLERANCE};
+use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+/// Post-swap base/quote price after selling `qty` (normalized) of `selling`.
+fn post_swap_price(pool: &MockProtocolSim, selling: &Token, buying: &Token, qty: f64) -> f64 {
+    let powered = BigUint::from((qty * 10f64.powi(selling.decimals as i32)).floor() as u128);
+    let result = pool.get_amount_out(powered, selling, buying).unwrap();
+    result.new_state.spot_price(&base(), &quote()).unwrap()
+}
+
+/// Reference implementation of the previous optimizer: pure bisection, two simulations per step.
+fn bisection_only(pool: &MockProtocolSim, selling: &Token, buying: &Token, reference: f64, max_amount: f64) -> (f64, usize) {
+    let initial = pool.spot_price(&base(), &quote()).unwrap();
+    let (mut low, mut high) = (0.0, max_amount);
+    let mut count = 2;
+    let mut best = (max_amount, (post_swap_price(pool, selling, buying, max_amount) - reference).abs());
+    for _ in 0..OPTI_MAX_ITERATIONS {
+        let mid = (low + high) / 2.0;
+        let price = post_swap_price(pool, selling, buying, mid);
+        count += 2; // post-swap price + execution price
+        let diff = price - reference;
+        if diff.abs() < best.1 {
+            best = (mid, diff.abs());
+        }
+        if (high - low) < OPTI_TOLERANCE || diff.abs() < 0.0001 {
+            break;
+        }
+        if (diff > 0.0) == (initial > reference) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (best.0, count)
+}
+
+#[test]
+fn test_secant_reduces_simulations_selling_base() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let reference = 2_990.0;
+    let max_amount = 100.0;
+
+    let time = std::time::Instant::now();
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), reference, true, max_amount, OPTI_MAX_SIMULATIONS).expect("Optimization failed");
+    let took = time.elapsed();
+    let (baseline_qty, baseline_count) = bisection_only(&pool, &base(), &quote(), reference, max_amount);
+
+    let price = post_swap_price(&pool, &base(), &quote(), result.optimal_qty);
+    let baseline_price = post_swap_price(&pool, &base(), &quote(), baseline_qty);
+    println!(
+        "Secant: {} simulations, qty {:.6}, price {:.6} ({:?}) | Bisection: {} simulations, qty {:.6}, price {:.6}",
+        result.simulation_count, result.optimal_qty, price, took, baseline_count, baseline_qty, baseline_price
+    );
+
+    assert!(
+        (price - reference).abs() <= (baseline_price - reference).abs().max(0.0001),
+        "Secant did not reach the bisection tolerance"
+    );
+    assert!(
+        result.simulation_count < baseline_count,
+        "Expected fewer simulations: {} >= {}",
+        result.simulation_count,
+        baseline_count
+    );
+    assert!(result.simulation_count <= OPTI_MAX_SIMULATIONS);
+}
+
+#[test]
+fn test_secant_reduces_simulations_selling_quote() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let reference = 3_010.0;
+    let max_amount = 300_000.0;
+
+    let result = find_optimal_swap_amount(&pool, &quote(), &base(), reference, false, max_amount, OPTI_MAX_SIMULATIONS).expect("Optimization failed");
+    let (baseline_qty, baseline_count) = bisection_only(&pool, &quote(), &base(), reference, max_amount);
+
+    let price = post_swap_price(&pool, &quote(), &base(), result.optimal_qty);
+    let baseline_price = post_swap_price(&pool, &quote(), &base(), baseline_qty);
+
+    assert!(
+        (price - reference).abs() <= (baseline_price - reference).abs().max(0.0001),
+        "Secant did not reach the bisection tolerance"
+    );
+    assert!(
+        result.simulation_count < baseline_count,
+        "Expected fewer simulations: {} >= {}",
+        result.simulation_count,
+        baseline_count
+    );
+}
+
+#[test]
+fn test_simulation_budget_is_respected() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 100.0, 4).expect("Optimization failed");
+    assert!(result.simulation_count <= 4, "Budget exceeded: {}", result.simulation_count);
+}
+
+#[test]
+fn test_termination_converged() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 100.0, OPTI_MAX_SIMULATIONS).expect("Optimization failed");
+    assert_eq!(result.termination, TerminationReason::Converged);
+    assert!(result.bracket_width < 100.0);
+}
+
+#[test]
+fn test_termination_max_iterations() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 100.0, 3).expect("Optimization failed");
+    assert_eq!(result.termination, TerminationReason::MaxIterations);
+    assert_eq!(result.simulation_count, 3);
+    assert!(result.bracket_width > OPTI_TOLERANCE);
+}
+
+#[test]
+fn test_termination_target_unreachable() {
+    // 0.01 ETH barely moves a 1,000 ETH pool, the reference is out of reach
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 0.01, OPTI_MAX_SIMULATIONS).expect("Optimization failed");
+    assert_eq!(result.termination, TerminationReason::TargetUnreachable);
+    assert_eq!(result.optimal_qty, 0.01);
+    assert_eq!(result.bracket_width, 0.01);
+    assert_eq!(result.simulation_count, 1);
+}
+
+#[test]
+fn test_termination_simulation_error() {
+    // Max probe and first bisection step succeed, the next simulation fails
+    let pool = ScriptedSim::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003), 2);
+    let result = find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 100.0, OPTI_MAX_SIMULATIONS).expect("Optimization failed");
+    assert_eq!(result.termination, TerminationReason::SimulationError);
+    assert_eq!(result.simulation_count, 2);
+    assert!(result.optimal_qty > 0.0);
+}
+
+#[test]
+fn test_initial_probe_failure_is_an_error() {
+    let pool = ScriptedSim::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003), 0);
+    assert!(find_optimal_swap_amount(&pool, &base(), &quote(), 2_990.0, true, 100.0, OPTI_MAX_SIMULATIONS).is_err());
+}
+
+/// Closed-form constant product input for an exact output (normalized amounts).
+fn v2_amount_in(reserve_in: f64, reserve_out: f64, fee: f64, amount_out: f64) -> f64 {
+    reserve_in * amount_out / ((reserve_out - amount_out) * (1.0 - fee))
+}
+
+#[test]
+fn test_reverse_quote_selling_quote() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let target = 1.0; // ETH
+    let amount_in = find_amount_in(&pool, &quote(), &base(), target, 10_000.0, OPTI_MAX_SIMULATIONS).expect("Reverse quote failed");
+    let expected = v2_amount_in(3_000_000.0, 1_000.0, 0.003, target);
+    assert!((amount_in - expected).abs() / expected < 1e-3, "amount_in {} vs closed form {}", amount_in, expected);
+
+    let out = pool.get_amount_out(BigUint::from((amount_in * 1e6) as u128), &quote(), &base()).unwrap().amount;
+    assert!(out >= BigUint::from((target * 1e18) as u128), "Reverse quoted input must yield at least the target");
+}
+
+#[test]
+fn test_reverse_quote_selling_base() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    let target = 3_000.0; // USDC
+    let amount_in = find_amount_in(&pool, &base(), &quote(), target, 100.0, OPTI_MAX_SIMULATIONS).expect("Reverse quote failed");
+    let expected = v2_amount_in(1_000.0, 3_000_000.0, 0.003, target);
+    assert!((amount_in - expected).abs() / expected < 1e-3, "amount_in {} vs closed form {}", amount_in, expected);
+}
+
+#[test]
+fn test_reverse_quote_unreachable_target() {
+    let pool = MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003);
+    assert!(find_amount_in(&pool, &quote(), &base(), 1.0, 100.0, OPTI_MAX_SIMULATIONS).is_err(), "100 USDC cannot buy 1 ETH");
+    assert!(find_amount_in(&pool, &quote(), &base(), 0.0, 100.0, OPTI_MAX_SIMULATIONS).is_err());
+}
@@ -0,0 +1,61 @@
+use shd::types::config::{load_market_maker_config, load_market_maker_config_with};
+
+const REFERENCE: &str = "config/mainnet.eth-usdc.toml";
+
+fn load(vars: &[(&str, &str)]) -> Result<shd::types::config::MarketMakerConfig, String> {
+    load_market_maker_config_with(REFERENCE, vars.iter().map(|(k, v)| (k.to_string(), v.to_string()))).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_typed_overrides() {
+    let config = load(&[
+        ("MM__RPC_URL", "https://rpc.example.org"),
+        ("MM__MIN_EXECUTABLE_SPREAD_BPS", "7.5"),
+        ("MM__POOL_COOLDOWN_BLOCKS", "9"),
+        ("MM__PUBLISH_EVENTS", "false"),
+        ("MM__POOL_DENYLIST", r#"["0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640"]"#),
+        ("MM__PRICE_FEED_CONFIG__SOURCE", "https://api.binance.us/api/v3"),
+        ("OTHER__RPC_URL", "ignored"),
+    ])
+    .unwrap();
+    assert_eq!(config.rpc_url, "https://rpc.example.org");
+    assert_eq!(config.min_executable_spread_bps, 7.5);
+    assert_eq!(config.pool_cooldown_blocks, 9);
+    assert!(!config.publish_events);
+    assert_eq!(config.pool_denylist.len(), 1);
+    assert_eq!(config.price_feed_config.source, "https://api.binance.us/api/v3");
+    assert_eq!(config.overrides.len(), 6);
+    assert!(config.overrides.contains(&("price_feed_config.source".to_string(), "MM__PRICE_FEED_CONFIG__SOURCE".to_string())));
+}
+
+#[test]
+fn test_parse_error_names_the_variable() {
+    let err = load(&[("MM__MIN_EXECUTABLE_SPREAD_BPS", "wide")]).unwrap_err();
+    assert!(err.contains("MM__MIN_EXECUTABLE_SPREAD_BPS"), "{}", err);
+    let err = load(&[("MM__POOL_COOLDOWN_BLOCKS", "-1")]).unwrap_err();
+    assert!(err.contains("MM__POOL_COOLDOWN_BLOCKS"), "{}", err);
+    let err = load(&[("MM__PUBLISH_EVENTS", "yes")]).unwrap_err();
+    assert!(err.contains("MM__PUBLISH_EVENTS"), "{}", err);
+}
+
+#[test]
+fn test_unknown_field_rejected() {
+    let err = load(&[("MM__NOT_A_FIELD", "1")]).unwrap_err();
+    assert!(err.contains("MM__NOT_A_FIELD"), "{}", err);
+    let err = load(&[("MM__RPC_URL__NESTED", "1")]).unwrap_err();
+    assert!(err.contains("MM__RPC_URL__NESTED"), "{}", err);
+}
+
+#[test]
+fn test_overrides_are_validated() {
+    assert!(load(&[("MM__MIN_WATCH_SPREAD_BPS", "20000")]).is_err());
+}
+
+#[test]
+fn test_hash_reflects_overrides() {
+    let file = load_market_maker_config(REFERENCE).unwrap();
+    let same = load(&[("MM__RPC_URL", file.rpc_url.as_str())]).unwrap();
+    assert_eq!(file.hash(), same.hash(), "Overriding with the same value keeps the hash");
+    let changed = load(&[("MM__RPC_URL", "https://rpc.example.org")]).unwrap();
+    assert_ne!(file.hash(), changed.hash());
+}
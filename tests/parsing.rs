@@ -1,6 +1,7 @@
 use alloy::providers::Provider;
 use shd::maker::feed::chainlink;
 use shd::types::config::load_market_maker_config;
+use shd::utils::constants::{DEFAULT_MAX_POOL_SHARE_BPS, DEFAULT_MIN_AMOUNT_WORTH_USD, DEFAULT_MIN_REFERENCE_PRICE_MOVE_BPS, DEFAULT_TVL_ADD_THRESHOLD, DEFAULT_TVL_REMOVE_THRESHOLD};
 use shd::utils::evm::{create_provider, eip1559_fees, gas_price, latest};
 
 // Global list of all config files to test
@@ -114,6 +115,101 @@ fn test_validate_configs() {
     println!("\n✨ Config validation test completed!\n");
 }
 
+#[test]
+fn test_tvl_thresholds_default_when_omitted() {
+    for config_path in CONFIG_FILES {
+        let contents = std::fs::read_to_string(config_path).expect("Config file must be readable");
+        assert!(
+            !contents.contains("tvl_add_threshold") && !contents.contains("tvl_remove_threshold"),
+            "{} is expected to omit the TVL thresholds",
+            config_path
+        );
+        let config = load_market_maker_config(config_path).expect("Config must load without TVL thresholds");
+        assert_eq!(config.tvl_add_threshold, DEFAULT_TVL_ADD_THRESHOLD);
+        assert_eq!(config.tvl_remove_threshold, DEFAULT_TVL_REMOVE_THRESHOLD);
+    }
+}
+
+#[test]
+fn test_tvl_thresholds_validation() {
+    let mut config = load_market_maker_config("config/unichain.eth-usdc.toml").expect("Reference config must load");
+    config.tvl_add_threshold = 50.0;
+    config.tvl_remove_threshold = 10.0;
+    assert!(config.validate().is_ok());
+
+    config.tvl_remove_threshold = 60.0;
+    let err = config.validate().expect_err("remove > add must fail").to_string();
+    assert!(err.contains("tvl_remove_threshold"), "{}", err);
+}
+
+/// Writes the reference config with `edit` applied to its top-level keys (before `[price_feed_config]`), returns its path.
+fn edited_config(name: &str, edit: impl Fn(&str) -> String) -> String {
+    let contents = std::fs::read_to_string("config/mainnet.eth-usdc.toml").expect("Reference config must be readable");
+    let (top, tables) = contents.split_at(contents.find("[price_feed_config]").expect("Reference config has a price feed table"));
+    let path = std::env::temp_dir().join(format!("mkmk-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, format!("{}\n{}", edit(top), tables)).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_trade_knobs_default_when_omitted() {
+    let path = edited_config("knobs-absent", |top| {
+        top.lines().filter(|l| !l.starts_with("min_reference_price_move_bps")).collect::<Vec<_>>().join("\n")
+    });
+    let config = load_market_maker_config(&path).expect("Config must load without the trade knobs");
+    assert_eq!(config.min_amount_worth_usd, DEFAULT_MIN_AMOUNT_WORTH_USD);
+    assert_eq!(config.max_pool_share_bps, DEFAULT_MAX_POOL_SHARE_BPS);
+    assert_eq!(config.min_reference_price_move_bps, DEFAULT_MIN_REFERENCE_PRICE_MOVE_BPS);
+    assert_eq!(config.max_trade_usd, None, "No notional cap");
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_trade_knobs_when_present() {
+    let path = edited_config("knobs-present", |top| format!("{}\nmin_amount_worth_usd = 25.0\nmax_pool_share_bps = 500.0\n", top));
+    let config = load_market_maker_config(&path).expect("Config must load with the trade knobs");
+    assert_eq!(config.min_amount_worth_usd, 25.0);
+    assert_eq!(config.max_pool_share_bps, 500.0);
+    assert_eq!(config.min_reference_price_move_bps, 1.0, "value of the file, not the default");
+    std::fs::remove_file(path).ok();
+
+    let path = edited_config("knobs-trade-usd", |top| format!("{}\nmin_trade_usd = 50.0\nmax_trade_usd = 25000.0\n", top));
+    let config = load_market_maker_config(&path).expect("Config must load with the trade notionals");
+    assert_eq!((config.min_amount_worth_usd, config.max_trade_usd), (50.0, Some(25_000.0)));
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_trade_knobs_validation() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    config.min_amount_worth_usd = 0.0;
+    config.max_pool_share_bps = 10_000.0;
+    assert!(config.validate().is_ok());
+
+    config.min_amount_worth_usd = -1.0;
+    let err = config.validate().expect_err("negative notional must fail").to_string();
+    assert!(err.contains("min_amount_worth_usd"), "{}", err);
+
+    config.min_amount_worth_usd = 10.0;
+    config.max_pool_share_bps = 10_001.0;
+    let err = config.validate().expect_err("share above 100% must fail").to_string();
+    assert!(err.contains("max_pool_share_bps"), "{}", err);
+    config.max_pool_share_bps = 0.0;
+    assert!(config.validate().is_err());
+
+    config.max_pool_share_bps = 10_000.0;
+    config.max_trade_usd = Some(5.0);
+    let err = config.validate().expect_err("max notional below the min must fail").to_string();
+    assert!(err.contains("max_trade_usd"), "{}", err);
+    config.max_trade_usd = Some(10_000.0);
+    assert!(config.validate().is_ok());
+
+    config.readjust_concurrency = 0;
+    assert!(config.validate().is_err(), "At least one readjustment at a time");
+    config.readjust_concurrency = 1;
+    assert!(config.validate().is_ok());
+}
+
 #[tokio::test]
 async fn test_basic_endpoints() {
     println!("\n🔌 Testing basic endpoints for all configs...\n");
@@ -135,7 +231,7 @@ async fn test_basic_endpoints() {
         println!("   ✓ Gas price: {} wei", gas);
 
         // Test 3: Fetch EIP-1559 fees
-        match eip1559_fees(config.rpc_url.clone()).await {
+        match eip1559_fees(config.rpc_url.clone(), config.fee_strategy()).await {
             Ok(fees) => {
                 assert!(fees.max_fee_per_gas > 0, "Max fee per gas should be greater than 0 for {}", config_path);
                 assert!(fees.max_priority_fee_per_gas > 0, "Max priority fee per gas should be greater than 0 for {}", config_path);
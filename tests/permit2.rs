@@ -0,0 +1,98 @@
+//! Permit2 permits: EIP-712 hashing against known vectors, local signatures, typed data and the config mode.
+mod common;
+
+use std::str::FromStr;
+
+use alloy::sol_types::SolStruct;
+use alloy_primitives::{Address, Signature, B256};
+use shd::maker::permit2::{domain, permit, sign, signing_hash, typed_data};
+use shd::maker::r#impl::PermitSingle;
+use shd::types::config::{load_market_maker_config, EnvConfig};
+use shd::utils::signer::WalletSigner;
+
+const PERMIT2: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+const ROUTER: &str = "0xfd0b31d2e955fa55e3fa641fe90e08b677188d35";
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+// First anvil account
+const KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const OWNER: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+fn address(hex: &str) -> Address {
+    Address::from_str(hex).unwrap()
+}
+
+fn hash(hex: &str) -> B256 {
+    B256::from_str(hex).unwrap()
+}
+
+fn permit_with_nonce(nonce: u64) -> PermitSingle {
+    permit(address(WETH), 1_500_000_000_000_000_000, 1_700_000_000, nonce, address(ROUTER))
+}
+
+fn env() -> EnvConfig {
+    EnvConfig {
+        wallet_private_key: KEY.to_string(),
+        ..common::env()
+    }
+}
+
+#[test]
+fn test_eip712_hashes_match_known_vectors() {
+    // DOMAIN_SEPARATOR() of the mainnet Permit2 deployment
+    let domain = domain(1, address(PERMIT2));
+    assert_eq!(domain.separator(), hash("0x866a5aba21966af95d6c7ab78eb2b2fc913915c28be3b9aa07cc04ff903e3f28"));
+
+    let permit = permit_with_nonce(7);
+    // PERMIT_SINGLE_TYPEHASH and PERMIT_DETAILS_TYPEHASH of Permit2 PermitHash
+    assert_eq!(permit.eip712_type_hash(), hash("0xf3841cd1ff0085026a6327b620b67997ce40f282c88a8e905a7a5626e310f3d0"));
+    assert_eq!(permit.details.eip712_type_hash(), hash("0x65626cad6cb96493bf6f5ebea28756c966f023ab9e8a83a7101849d5573b3678"));
+    assert_eq!(permit.eip712_hash_struct(), hash("0xd0bacbf53eb73a80854b7a3eb8ed82ba0011794c4ad2afc2bceda92d73842e9d"));
+    assert_eq!(signing_hash(&permit, &domain), hash("0x45a23010344e7eb38b286308d79538dbbc75b24cedb2b7d5b67cfcb0a407915c"));
+
+    // Any field changes the digest, including the chain of the domain
+    assert_ne!(signing_hash(&permit_with_nonce(8), &domain), signing_hash(&permit, &domain));
+    assert_ne!(signing_hash(&permit, &shd::maker::permit2::domain(8453, address(PERMIT2))), signing_hash(&permit, &domain));
+}
+
+#[tokio::test]
+async fn test_local_signature_recovers_the_wallet() {
+    let signer = WalletSigner::from_env(&env()).unwrap();
+    assert_eq!(signer.address(), address(OWNER));
+    let signed = sign(&signer, permit_with_nonce(7), 1, address(PERMIT2)).await.unwrap();
+    assert_eq!(signed.signature.len(), 65);
+    assert!(matches!(signed.signature[64], 27 | 28), "v as 27 or 28 for Permit2: {}", signed.signature[64]);
+
+    let signature = Signature::from_raw(&signed.signature).unwrap();
+    let digest = signing_hash(&signed.permit, &domain(1, address(PERMIT2)));
+    assert_eq!(signature.recover_address_from_prehash(&digest).unwrap(), address(OWNER));
+}
+
+#[test]
+fn test_typed_data_for_remote_signers() {
+    let data = typed_data(&permit_with_nonce(7), 1, address(PERMIT2));
+    assert_eq!(data["primaryType"], "PermitSingle");
+    assert_eq!(data["domain"]["name"], "Permit2");
+    assert_eq!(data["domain"]["chainId"], 1);
+    assert!(data["domain"].get("version").is_none(), "Permit2 has no version");
+    assert_eq!(data["types"]["PermitDetails"].as_array().unwrap().len(), 4);
+    // Integers as decimal strings, uint160 amounts overflowing JSON numbers
+    assert_eq!(data["message"]["details"]["amount"], "1500000000000000000");
+    assert_eq!(data["message"]["details"]["nonce"], "7");
+    assert_eq!(data["message"]["sigDeadline"], "1700000000");
+    assert_eq!(data["message"]["spender"].as_str().unwrap().to_lowercase(), ROUTER);
+}
+
+#[test]
+fn test_permit2_approval_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert!(!config.permit2_approval, "Off by default");
+    assert_eq!(config.permit2_expiration_s, 1_800);
+    config.validate().unwrap();
+
+    config.permit2_approval = true;
+    assert!(config.validate().is_err(), "Exclusive with infinite_approval");
+    config.infinite_approval = false;
+    config.validate().unwrap();
+    config.permit2_expiration_s = 0;
+    assert!(config.validate().unwrap_err().to_string().contains("permit2_expiration_s"));
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{base, context, pool, quote, readjustment};
+use shd::maker::pnl::PnlTracker;
+use shd::types::maker::{ExecutionOrder, PnlEntry, ReceiptData, TransferData};
+
+const HOUR_MS: u128 = 3_600_000;
+const WALLET: &str = "0x1111111111111111111111111111111111111111";
+const ROUTER: &str = "0x2222222222222222222222222222222222222222";
+
+fn entry(timestamp_ms: u128, pnl_usd: f64) -> PnlEntry {
+    PnlEntry {
+        timestamp_ms,
+        sold_usd: 0.0,
+        bought_usd: 0.0,
+        gas_usd: 0.0,
+        pnl_usd,
+    }
+}
+
+/// Order selling 1 ETH for 3030 USDC, estimated worth 3030 $ at the 3000 $ reference.
+fn order() -> ExecutionOrder {
+    let psc = pool("0xaaaa000000000000000000000000000000000001", 1_000., 3_030_000., 0.003);
+    ExecutionOrder {
+        trade_id: "a".to_string(),
+        adjustment: readjustment(&psc, 3_030., 3_000.),
+        calculation: common::calculation(true, 1.0, 3_030.0),
+    }
+}
+
+fn transfer(token: &str, from: &str, to: &str, amount: u128) -> TransferData {
+    TransferData {
+        token: token.to_lowercase(),
+        from: from.to_string(),
+        to: to.to_string(),
+        amount,
+    }
+}
+
+/// Receipt of the swap, sending 1 ETH and receiving `usdc` USDC.
+fn receipt(status: bool, usdc: f64) -> ReceiptData {
+    ReceiptData {
+        status,
+        gas_used: 100_000,
+        error: None,
+        transaction_hash: String::new(),
+        transaction_index: 0,
+        block_number: 0,
+        effective_gas_price: 10_000_000_000, // 10 gwei
+        transfers: vec![
+            transfer(&base().address.to_string(), WALLET, ROUTER, 10u128.pow(18)),
+            transfer(&quote().address.to_string(), ROUTER, WALLET, (usdc * 1e6) as u128),
+        ],
+    }
+}
+
+#[test]
+fn test_pnl_rolling_window() {
+    let mut tracker = PnlTracker::new(24 * HOUR_MS);
+    tracker.record(entry(0, 10.0));
+    tracker.record(entry(12 * HOUR_MS, -4.0));
+    tracker.record(entry(30 * HOUR_MS, 1.0));
+
+    let snapshot = tracker.snapshot(30 * HOUR_MS);
+    assert_eq!(snapshot.trades, 3);
+    assert!((snapshot.cumulative_usd - 7.0).abs() < 1e-9);
+    // First trade left the 24h window
+    assert_eq!(snapshot.rolling_trades, 2);
+    assert!((snapshot.rolling_usd + 3.0).abs() < 1e-9);
+
+    let later = tracker.snapshot(40 * HOUR_MS);
+    assert_eq!(later.rolling_trades, 1);
+    assert!((later.rolling_usd - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_pnl_record_trade_includes_gas() {
+    let mut tracker = PnlTracker::new(24 * HOUR_MS);
+    let context = context(3_000.0, 10.0, 0);
+    // 100k gas at 10 gwei = 0.001 ETH = 3 $ at 3000 $/ETH. Received 3010 USDC of the 3030 estimated
+    let entry = tracker
+        .record_trade(&order(), Some(&receipt(true, 3_010.0)), WALLET, &context, 0)
+        .expect("Trade with receipt is recorded");
+    assert!((entry.gas_usd - 3.0).abs() < 1e-9);
+    assert!((entry.sold_usd - 3_000.0).abs() < 1e-6, "{}", entry.sold_usd);
+    assert!((entry.bought_usd - 3_010.0).abs() < 1e-6, "Received, not estimated: {}", entry.bought_usd);
+    assert!((entry.pnl_usd - 7.0).abs() < 1e-6);
+
+    // Reverted trade only costs gas
+    let reverted = tracker.record_trade(&order(), Some(&receipt(false, 3_010.0)), WALLET, &context, 1).expect("Reverted trade is recorded");
+    assert!((reverted.pnl_usd + 3.0).abs() < 1e-9);
+
+    // No receipt, the trade did not land
+    assert!(tracker.record_trade(&order(), None, WALLET, &context, 2).is_none());
+
+    let snapshot = tracker.snapshot(2);
+    assert_eq!(snapshot.trades, 2);
+    assert!((snapshot.cumulative_usd - 4.0).abs() < 1e-6);
+    assert!((snapshot.cumulative_gas_usd - 6.0).abs() < 1e-9);
+}
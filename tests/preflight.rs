@@ -0,0 +1,50 @@
+use shd::maker::preflight::{CheckStatus, PreflightReport};
+use shd::types::maker::Inventory;
+
+const ETH: u128 = 1_000_000_000_000_000_000;
+
+fn inventory(native_balance: u128) -> Inventory {
+    Inventory {
+        base_balance: 0,
+        quote_balance: 0,
+        nonce: 0,
+        native_balance,
+    }
+}
+
+#[test]
+fn test_preflight_accepts_sufficient_native_balance() {
+    assert!(inventory(ETH / 10).preflight(ETH / 100, ETH / 1_000).is_ok());
+}
+
+#[test]
+fn test_preflight_rejects_balance_below_floor_plus_gas() {
+    // Above the floor alone, but not once the projected gas is added
+    let err = inventory(ETH / 100).preflight(ETH / 100, ETH / 1_000).expect_err("Balance should be too low");
+    assert!(err.contains("Native balance too low"), "{}", err);
+}
+
+#[test]
+fn test_startup_report_fails_on_any_hard_failure() {
+    let mut report = PreflightReport::new("ethereum-eth-usdc".to_string());
+    report.record("RPC", Ok("https://rpc serves chain 1".to_string()));
+    report.push("Token balances", CheckStatus::Warn, "one side only".to_string());
+    assert!(report.passed(), "warnings do not fail the preflight");
+    report.record("Redis", Err("Connection failed".to_string()));
+    assert!(!report.passed());
+    assert_eq!(report.failures(), 1);
+    assert_eq!(report.checks[2].status, CheckStatus::Fail);
+}
+
+#[test]
+fn test_startup_report_table() {
+    let mut report = PreflightReport::new("ethereum-eth-usdc".to_string());
+    report.record("Tycho API", Ok("ETH and USDC found".to_string()));
+    report.record("Permit2", Err("No code at 0x00".to_string()));
+    let table = report.to_string();
+    let lines = table.lines().collect::<Vec<&str>>();
+    assert_eq!(lines[0], "Preflight ethereum-eth-usdc");
+    assert!(lines[3].contains("Tycho API | PASS") && lines[3].ends_with("ETH and USDC found"), "{}", lines[3]);
+    assert!(lines[4].contains("Permit2   | FAIL"), "{}", lines[4]);
+    assert!(table.ends_with("Preflight failed (1 of 2 checks)"), "{}", table);
+}
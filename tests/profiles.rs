@@ -0,0 +1,100 @@
+//! Config inheritance: `extends` merges the parent file under the child, which wins on every field it sets.
+use shd::types::config::{load_market_maker_config_with, MarketMakerConfig};
+
+const REFERENCE: &str = "config/mainnet.eth-usdc.toml";
+
+/// Temporary directory of one test, so tests running in parallel never share files.
+fn dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("mkmk-profiles-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a config file and returns its path.
+fn write(dir: &std::path::Path, file: &str, contents: &str) -> String {
+    let path = dir.join(file);
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn load(path: &str) -> Result<MarketMakerConfig, String> {
+    load_market_maker_config_with(path, vec![]).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_child_overrides_parent() {
+    let dir = dir("precedence");
+    let reference = std::fs::read_to_string(REFERENCE).unwrap();
+    let parent = write(&dir, "parent.toml", &reference);
+    // Middle profile: one top-level field and one field of a nested table
+    let middle = write(
+        &dir,
+        "middle.toml",
+        &format!(
+            "extends = \"{}\"\nmin_watch_spread_bps = 5.0\npool_cooldown_blocks = 7\n\n[price_feed_config]\nsource = \"https://api.binance.us/api/v3\"\n",
+            parent
+        ),
+    );
+    let child = write(&dir, "child.toml", &format!("extends = \"{}\"\nmin_watch_spread_bps = 6.0\n", middle));
+
+    let config = load(&child).unwrap();
+    assert_eq!(config.min_watch_spread_bps, 6.0, "the child wins");
+    assert_eq!(config.pool_cooldown_blocks, 7, "the middle profile wins over the parent");
+    assert_eq!(config.price_feed_config.source, "https://api.binance.us/api/v3");
+    assert!(!config.price_feed_config.reverse, "nested fields the child omits come from the parent");
+    assert_eq!(config.rpc_url, "https://eth.api.pocket.network");
+    assert_eq!(config.extends, vec![middle.clone(), parent.clone()]);
+
+    // The hash covers the merged fields, so it matches the same config written flat
+    let flat = reference
+        .replace("min_watch_spread_bps = 3.0", "min_watch_spread_bps = 6.0\npool_cooldown_blocks = 7")
+        .replace("source = \"https://api.binance.com/api/v3\"", "source = \"https://api.binance.us/api/v3\"");
+    let flat = load(&write(&dir, "flat.toml", &flat)).unwrap();
+    assert_eq!(config.hash(), flat.hash());
+    assert_ne!(config.hash(), load(&parent).unwrap().hash());
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_merged_config_is_validated() {
+    let dir = dir("validated");
+    let parent = write(&dir, "parent.toml", &std::fs::read_to_string(REFERENCE).unwrap());
+    let child = write(&dir, "child.toml", &format!("extends = \"{}\"\nmax_inventory_ratio = 1.5\n", parent));
+    let err = load(&child).unwrap_err();
+    assert!(err.contains("max_inventory_ratio"), "{}", err);
+    // A partial parent is fine as long as the merged result is complete
+    let partial = write(&dir, "partial.toml", "min_watch_spread_bps = 4.0\n");
+    let reference = std::fs::read_to_string(REFERENCE).unwrap().replace("min_watch_spread_bps = 3.0\n", "");
+    let child = write(&dir, "complete.toml", &format!("extends = \"{}\"\n{}", partial, reference));
+    assert_eq!(load(&child).unwrap().min_watch_spread_bps, 4.0);
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_missing_parent() {
+    let dir = dir("missing");
+    let missing = dir.join("absent.toml").to_string_lossy().to_string();
+    let child = write(&dir, "child.toml", &format!("extends = \"{}\"\nmin_watch_spread_bps = 6.0\n", missing));
+    let err = load(&child).unwrap_err();
+    assert!(err.contains(&missing) && err.contains(&child), "{}", err);
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_cyclic_extends() {
+    let dir = dir("cyclic");
+    let a = dir.join("a.toml").to_string_lossy().to_string();
+    let b = dir.join("b.toml").to_string_lossy().to_string();
+    write(&dir, "a.toml", &format!("extends = \"{}\"\n", b));
+    write(&dir, "b.toml", &format!("extends = \"{}\"\n", a));
+    let err = load(&a).unwrap_err();
+    assert!(err.contains("Cyclic"), "{}", err);
+
+    let own = dir.join("self.toml").to_string_lossy().to_string();
+    write(&dir, "self.toml", &format!("extends = \"{}\"\n", own));
+    assert!(load(&own).unwrap_err().contains("Cyclic"));
+
+    let invalid = write(&dir, "invalid.toml", "extends = 3\n");
+    assert!(load(&invalid).unwrap_err().contains("extends"));
+    std::fs::remove_dir_all(dir).ok();
+}
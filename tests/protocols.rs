@@ -0,0 +1,134 @@
+//! Tycho stream protocols: `tycho_protocols` selects exactly what the stream builder registers, by default the protocols
+//! indexed on the network.
+mod common;
+
+use common::{base, component, quote};
+use shd::maker::tycho::{amm_fee_to_bps, stream_protocols};
+use shd::types::config::{load_market_maker_config, NetworkName};
+use shd::types::tycho::{AmmType, TychoSupportedProtocol};
+use shd::utils::constants::UNKNOWN_AMM_FEE_BPS;
+use tycho_common::Bytes;
+
+fn names(protocols: Vec<TychoSupportedProtocol>) -> Vec<String> {
+    protocols.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn test_default_protocols_per_network() {
+    let mainnet = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    assert!(mainnet.tycho_protocols.is_empty());
+    assert_eq!(stream_protocols(&mainnet), TychoSupportedProtocol::ALL.to_vec());
+
+    let mut l2 = load_market_maker_config("config/unichain.eth-usdc.toml").unwrap();
+    for network in ["unichain", "base"] {
+        l2.network_name = network.to_string();
+        assert_eq!(
+            names(stream_protocols(&l2)),
+            vec!["uniswap_v2", "uniswap_v3", "uniswap_v4", "vm:balancer_v2", "vm:curve"],
+            "{}",
+            network
+        );
+    }
+}
+
+#[test]
+fn test_streamed_protocols_have_an_amm_type_and_fee() {
+    for network in [NetworkName::Ethereum, NetworkName::Base, NetworkName::Unichain] {
+        for protocol in TychoSupportedProtocol::available_on(&network) {
+            assert!(protocol.is_available_on(&network));
+            let mut cp = component("0xaaaa000000000000000000000000000000000001", &protocol.to_string(), vec![base(), quote()]);
+            assert!(AmmType::of(&cp).is_some(), "{} on {}", protocol, network.as_str());
+
+            // 30 bps in the scale of each protocol
+            let raw: u128 = match protocol {
+                TychoSupportedProtocol::UniswapV2 | TychoSupportedProtocol::Sushiswap | TychoSupportedProtocol::PancakeswapV2 => 30,
+                TychoSupportedProtocol::UniswapV3 | TychoSupportedProtocol::UniswapV4 | TychoSupportedProtocol::PancakeswapV3 => 3_000,
+                TychoSupportedProtocol::BalancerV2 => 3_000_000_000_000_000,
+                TychoSupportedProtocol::Curve => 30_000_000,
+                TychoSupportedProtocol::EkuboV2 => continue,
+            };
+            cp.static_attributes.insert("fee".to_string(), Bytes::from(raw.to_be_bytes().to_vec()));
+            assert_eq!(amm_fee_to_bps(cp), 30, "{} on {}", protocol, network.as_str());
+        }
+    }
+    assert!(!TychoSupportedProtocol::EkuboV2.is_available_on(&NetworkName::Base));
+    assert!(!TychoSupportedProtocol::Sushiswap.is_available_on(&NetworkName::Unichain));
+}
+
+#[test]
+fn test_deployment_pool_types() {
+    for (name, amm) in [
+        ("curve_pool", "curve_pool"),
+        ("curve_stableswap_ng_pool", "curve_pool"),
+        ("balancer_v2_pool", "balancer_v2_pool"),
+        ("balancer_v2_weighted_pool", "balancer_v2_pool"),
+        ("uniswap_v3_pool", "uniswap_v3_pool"),
+    ] {
+        assert_eq!(AmmType::parse(name).map(|a| a.to_string()).as_deref(), Some(amm), "{}", name);
+    }
+    assert!(AmmType::parse("aerodrome_slipstream_pool").is_none());
+
+    // Unknown pool type of a known system: typed from the system
+    let mut cp = component("0xaaaa000000000000000000000000000000000001", "vm:curve", vec![base(), quote()]);
+    cp.protocol_type_name = "plain_pool".to_string();
+    assert_eq!(AmmType::of(&cp).map(|a| a.to_string()).as_deref(), Some("curve_pool"));
+    assert_eq!(amm_fee_to_bps(cp.clone()), 4);
+
+    cp.protocol_system = "aerodrome_slipstream".to_string();
+    assert!(AmmType::of(&cp).is_none());
+    assert_eq!(amm_fee_to_bps(cp), UNKNOWN_AMM_FEE_BPS, "Unknown pools are not fatal");
+}
+
+#[test]
+fn test_every_protocol_type_name() {
+    for amm in AmmType::ALL {
+        assert_eq!(AmmType::parse(&amm.to_string()), Some(amm), "{}", amm);
+        let mut cp = component("0xaaaa000000000000000000000000000000000001", "unlisted", vec![base(), quote()]);
+        cp.protocol_type_name = amm.to_string();
+        // 30 bps in the scale of each type
+        let raw: u128 = match amm {
+            AmmType::PancakeswapV2 | AmmType::Sushiswap | AmmType::UniswapV2 => 30,
+            AmmType::PancakeswapV3 | AmmType::UniswapV3 | AmmType::UniswapV4 => 3_000,
+            AmmType::Balancer | AmmType::BalancerV3 | AmmType::MaverickV2 => 3_000_000_000_000_000,
+            AmmType::Curve => 30_000_000,
+            AmmType::EkuboV2 => continue,
+        };
+        cp.static_attributes.insert("fee".to_string(), Bytes::from(raw.to_be_bytes().to_vec()));
+        assert_eq!(amm_fee_to_bps(cp), 30, "{}", amm);
+    }
+
+    // A protocol Tycho added since: conservative fee, whatever its attributes
+    let mut cp = component("0xaaaa000000000000000000000000000000000001", "fluid_v1", vec![base(), quote()]);
+    assert_eq!(AmmType::parse(&cp.protocol_type_name), None);
+    cp.static_attributes.insert("fee".to_string(), Bytes::from(1u128.to_be_bytes().to_vec()));
+    assert_eq!(amm_fee_to_bps(cp.clone()), UNKNOWN_AMM_FEE_BPS);
+    assert_eq!(amm_fee_to_bps(cp), UNKNOWN_AMM_FEE_BPS, "Warned once, same fee");
+}
+
+#[test]
+fn test_requested_protocols_are_registered_exactly() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    // Everything but Curve, which slows the first sync
+    config.tycho_protocols = vec!["uniswap_v3".into(), "uniswap_v4".into(), "vm:balancer_v2".into(), "ekubo_v2".into()];
+    config.validate().unwrap();
+    assert_eq!(names(stream_protocols(&config)), config.tycho_protocols);
+    assert!(!stream_protocols(&config).contains(&TychoSupportedProtocol::Curve));
+}
+
+#[test]
+fn test_protocols_validation() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    config.tycho_protocols = vec!["uniswap_v2".into(), "curve".into()];
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("Unknown protocol in tycho_protocols: curve"), "{}", err);
+
+    config.tycho_protocols = vec!["uniswap_v2".into(), "uniswap_v2".into()];
+    assert!(config.validate().unwrap_err().to_string().contains("twice"));
+
+    let mut unichain = load_market_maker_config("config/unichain.eth-usdc.toml").unwrap();
+    unichain.tycho_protocols = vec!["uniswap_v4".into(), "vm:curve".into()];
+    unichain.validate().unwrap();
+    unichain.tycho_protocols = vec!["uniswap_v4".into(), "ekubo_v2".into()];
+    let err = unichain.validate().unwrap_err().to_string();
+    assert!(err.contains("ekubo_v2") && err.contains("not available on unichain"), "{}", err);
+}
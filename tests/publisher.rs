@@ -0,0 +1,61 @@
+//! Buffered publisher queue: order is kept, price updates are dropped oldest first under pressure, trades never.
+use shd::data::publisher::{PublishQueue, QueuedMessage};
+
+fn message(payload: &str, droppable: bool) -> QueuedMessage {
+    QueuedMessage {
+        channels: vec!["tycho_market_maker:v1:events:test:new_prices".to_string()],
+        streams: vec!["tycho_market_maker:v1:stream:test".to_string()],
+        payload: payload.to_string(),
+        droppable,
+    }
+}
+
+/// Drains the queue as the background task does once Redis is back.
+fn replay(queue: &mut PublishQueue) -> Vec<String> {
+    let mut delivered = vec![];
+    while let Some(next) = queue.front() {
+        delivered.push(next.payload);
+        queue.delivered();
+    }
+    delivered
+}
+
+#[test]
+fn test_replay_in_order() {
+    let mut queue = PublishQueue::new(10);
+    for payload in ["price-1", "trade-1", "price-2", "pnl-1"] {
+        queue.push(message(payload, payload.starts_with("price")));
+    }
+    assert_eq!(queue.stats().depth, 4);
+    // Redis down: the front stays queued until delivered
+    assert_eq!(queue.front().unwrap().payload, "price-1");
+    assert_eq!(queue.len(), 4);
+    assert_eq!(replay(&mut queue), vec!["price-1", "trade-1", "price-2", "pnl-1"]);
+    let stats = queue.stats();
+    assert_eq!((stats.depth, stats.published, stats.dropped), (0, 4, 0));
+}
+
+#[test]
+fn test_oldest_prices_dropped_under_pressure() {
+    let mut queue = PublishQueue::new(3);
+    queue.push(message("price-1", true));
+    queue.push(message("trade-1", false));
+    queue.push(message("price-2", true));
+    queue.push(message("price-3", true));
+    queue.push(message("trade-2", false));
+    assert_eq!(queue.stats().dropped, 2);
+    assert_eq!(replay(&mut queue), vec!["trade-1", "price-3", "trade-2"]);
+}
+
+#[test]
+fn test_trades_never_dropped() {
+    let mut queue = PublishQueue::new(2);
+    for i in 0..4 {
+        queue.push(message(&format!("trade-{}", i), false));
+    }
+    // Full of trades: a new price is the one dropped
+    queue.push(message("price-1", true));
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.stats().dropped, 1);
+    assert_eq!(replay(&mut queue), vec!["trade-0", "trade-1", "trade-2", "trade-3"]);
+}
@@ -0,0 +1,104 @@
+//! Sanity band: pools implausibly far from the reference are quarantined, logged sparingly and alerted on.
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{base, component, quote, MockProtocolSim};
+use shd::maker::quarantine::PoolQuarantine;
+use shd::maker::{exec::ExecStrategyFactory, feed::PriceFeedFactory};
+use shd::opti::skew::InventorySkew;
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::config::load_market_maker_config;
+use shd::types::maker::{ComponentPriceData, MarketMaker};
+use shd::types::tycho::ProtoSimComp;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+
+fn price(spot: f64) -> Vec<ComponentPriceData> {
+    vec![ComponentPriceData {
+        address: POOL.to_string(),
+        r#type: "uniswap_v2".to_string(),
+        price: spot,
+    }]
+}
+
+#[test]
+fn test_band_boundaries() {
+    let quarantine = PoolQuarantine::new(2_000.0, 10);
+    assert!(quarantine.is_plausible(2_000.0));
+    assert!(quarantine.is_plausible(-2_000.0));
+    assert!(!quarantine.is_plausible(2_000.1));
+}
+
+#[test]
+fn test_logs_once_per_interval_and_alerts_once() {
+    let mut quarantine = PoolQuarantine::new(2_000.0, 3);
+    let start = Instant::now();
+    let wild = price(REFERENCE * 50.0);
+
+    let first = quarantine.observe(&wild, REFERENCE, 100, start);
+    assert_eq!(first.len(), 1, "Logged when entering the quarantine");
+    assert!(!first[0].alert);
+    assert_eq!(quarantine.len(), 1);
+
+    assert!(quarantine.observe(&wild, REFERENCE, 101, start + Duration::from_secs(12)).is_empty(), "Not logged every block");
+    let third = quarantine.observe(&wild, REFERENCE, 102, start + Duration::from_secs(24));
+    assert_eq!(third.len(), 1);
+    assert!(third[0].alert, "Alert after 3 consecutive blocks");
+    assert_eq!(third[0].blocks, 3);
+
+    assert!(quarantine.observe(&wild, REFERENCE, 103, start + Duration::from_secs(36)).is_empty(), "Alerted once per episode");
+    let hourly = quarantine.observe(&wild, REFERENCE, 400, start + Duration::from_secs(3_600));
+    assert_eq!(hourly.len(), 1, "Logged again after an hour");
+    assert!(!hourly[0].alert);
+}
+
+#[test]
+fn test_same_block_counted_once() {
+    let mut quarantine = PoolQuarantine::new(2_000.0, 2);
+    let now = Instant::now();
+    quarantine.observe(&price(1.0), REFERENCE, 100, now);
+    assert!(quarantine.observe(&price(1.0), REFERENCE, 100, now).is_empty());
+    assert!(quarantine.observe(&price(1.0), REFERENCE, 101, now)[0].alert);
+}
+
+#[test]
+fn test_release_resets_the_episode() {
+    let mut quarantine = PoolQuarantine::new(2_000.0, 2);
+    let now = Instant::now();
+    quarantine.observe(&price(1.0), REFERENCE, 100, now);
+    quarantine.observe(&price(REFERENCE), REFERENCE, 101, now);
+    assert!(quarantine.is_empty(), "Back within the band");
+    let again = quarantine.observe(&price(1.0), REFERENCE, 102, now);
+    assert_eq!(again[0].blocks, 1, "Consecutive count restarts");
+    assert!(!again[0].alert);
+
+    quarantine.observe(&[], REFERENCE, 103, now);
+    assert!(!quarantine.contains(POOL), "No longer targeted");
+}
+
+fn maker() -> MarketMaker {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    let feed = PriceFeedFactory::create(&config.price_feed_config.r#type);
+    let execution = ExecStrategyFactory::create(config.network_name.as_str());
+    MarketMakerBuilder::new()
+        .config(config)
+        .feed(feed)
+        .execution(execution)
+        .tokens(base(), quote())
+        .build()
+        .expect("Market maker must build")
+}
+
+#[test]
+fn test_quarantined_pool_not_evaluated() {
+    let mk = maker();
+    let targets = [ProtoSimComp {
+        component: component(POOL, "uniswap_v2", vec![base(), quote()]),
+        protosim: Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 150_000_000.0, 0.003)),
+    }];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    assert!(mk.evaluate(&targets, vec![150_000.0], REFERENCE, &skew, 100).is_empty(), "50x the reference is no opportunity");
+    assert_eq!(mk.evaluate(&targets, vec![3_100.0], REFERENCE, &skew, 100).len(), 1, "A 3% spread is still evaluated");
+}
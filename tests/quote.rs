@@ -0,0 +1,123 @@
+//! `maker quote`: readjust math on a user-supplied amount, pool selection and the command line.
+mod common;
+
+use clap::Parser;
+use common::{context, maker, pool, MOCK_SWAP_GAS};
+use shd::maker::quote::{quote, select, BEST_TARGET};
+use shd::types::cli::{MakerCli, MakerCommand};
+use shd::types::maker::MarketMaker;
+use tycho_common::Bytes;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const OTHER: &str = "0xaaaa000000000000000000000000000000000002";
+const REFERENCE: f64 = 3_000.0;
+
+fn mk() -> MarketMaker {
+    maker(common::config())
+}
+
+#[test]
+fn test_quote_selling_base_into_a_pool_above_the_reference() {
+    let mk = mk();
+    let mut psc = pool(POOL, 1_000.0, 3_030_000.0, 0.003);
+    // Uniswap V2 fee attribute, in bps
+    psc.component.static_attributes.insert("fee".to_string(), Bytes::from(vec![0x1e]));
+    let q = quote(&mk, &psc, 2.0, true, REFERENCE, &context(REFERENCE, 1.0, 100)).unwrap();
+    assert_eq!((q.selling.as_str(), q.buying.as_str()), ("ETH", "USDC"));
+    assert!((q.spot - 3_030.0).abs() < 1e-9);
+    assert!((q.spread_bps() - 100.0).abs() < 1e-9);
+    assert_eq!(q.fee_bps, 30);
+
+    // Constant product output of 2 ETH net of the 30 bps fee
+    let expected = 1.994 * 3_030_000.0 / 1_001.994;
+    assert!((q.amount_out - expected).abs() < 1e-5, "{}", q.amount_out);
+    assert!((q.price - expected / 2.0).abs() < 1e-5);
+    // 120k gas at 1 gwei, ETH at 3000 $: 0.36 $, paid in USDC
+    assert_eq!((q.gas_units, q.gas_price_gwei), (MOCK_SWAP_GAS as u128, 1.0));
+    assert!((q.gas_cost_usd - 0.36).abs() < 1e-9 && (q.gas_cost_in_output - 0.36).abs() < 1e-9);
+    assert!((q.price_net_gas - (expected - 0.36) / 2.0).abs() < 1e-5);
+    assert!((q.profit_bps - (q.price_net_gas - REFERENCE) / REFERENCE * 10_000.).abs() < 1e-9);
+    assert_eq!(q.threshold_bps, mk.config.min_executable_spread_bps);
+    assert!(q.clears());
+    let text = q.to_string();
+    assert!(text.contains("2.00000 ETH for") && text.contains("would trade"), "{}", text);
+
+    // Same sale into a pool below the reference loses money
+    let below = pool(POOL, 1_000.0, 2_970_000.0, 0.003);
+    let q = quote(&mk, &below, 2.0, true, REFERENCE, &context(REFERENCE, 1.0, 100)).unwrap();
+    assert!(q.profit_bps < 0. && !q.clears());
+    assert_eq!(q.fee_bps, 0, "No fee attribute");
+    assert!(q.to_string().ends_with("would not trade"));
+}
+
+#[test]
+fn test_quote_selling_quote_and_invalid_amounts() {
+    let mk = mk();
+    let below = pool(POOL, 1_000.0, 2_970_000.0, 0.003);
+    let q = quote(&mk, &below, 6_000.0, false, REFERENCE, &context(REFERENCE, 1.0, 100)).unwrap();
+    assert_eq!((q.selling.as_str(), q.buying.as_str()), ("USDC", "ETH"));
+    // Price paid in USDC per ETH, gas taken from the ETH bought
+    assert!((q.price - 6_000.0 / q.amount_out).abs() < 1e-9);
+    assert!((q.gas_cost_in_output - 0.00012).abs() < 1e-12);
+    assert!(q.price > 2_970.0 && q.price_net_gas > q.price);
+    assert!((q.profit_bps - (REFERENCE - q.price_net_gas) / REFERENCE * 10_000.).abs() < 1e-9);
+    assert!(q.clears());
+
+    // Gas eating the whole output buys at an infinite price
+    let dust = quote(&mk, &below, 0.000_01, false, REFERENCE, &context(REFERENCE, 1_000.0, 100)).unwrap();
+    assert!(dust.price_net_gas.is_infinite() && !dust.clears());
+
+    assert!(quote(&mk, &below, 0.0, true, REFERENCE, &context(REFERENCE, 1.0, 100)).is_err());
+    assert!(quote(&mk, &below, 1.0, true, 0.0, &context(REFERENCE, 1.0, 100)).is_err());
+}
+
+#[test]
+fn test_quote_capped_and_checked_like_readjust() {
+    let psc = pool(POOL, 1_000.0, 3_030_000.0, 0.003);
+    let ctx = context(REFERENCE, 1.0, 100);
+    let mut config = common::config();
+    config.max_trade_usd = Some(3_000.0);
+    let q = quote(&maker(config), &psc, 2.0, true, REFERENCE, &ctx).unwrap();
+    assert_eq!(q.requested, 2.0);
+    assert!((q.amount_in - 1.0).abs() < 1e-9, "Capped to 3000 $ of ETH at 3000 $: {}", q.amount_in);
+    assert!((q.worth_usd - 3_000.0).abs() < 1e-6);
+    assert!(q.to_string().contains("to 1.00000 by max_trade_usd"), "{}", q);
+    assert!(q.clears());
+
+    // Profitable, but below the min notional readjust requires
+    let mut config = common::config();
+    config.min_amount_worth_usd = 1_000.0;
+    let q = quote(&maker(config), &psc, 0.2, true, REFERENCE, &ctx).unwrap();
+    assert_eq!(q.amount_in, 0.2, "No cap");
+    assert!(q.profit_bps > q.threshold_bps && !q.clears(), "{}", q);
+}
+
+#[test]
+fn test_select_by_id_or_best_target() {
+    let mk = mk();
+    let targets = [pool(POOL, 1_000.0, 3_015_000.0, 0.003), pool(OTHER, 1_000.0, 3_030_000.0, 0.003)];
+    let ctx = context(REFERENCE, 1.0, 100);
+
+    let best = select(&mk, &targets, BEST_TARGET, 2.0, true, REFERENCE, &ctx).unwrap();
+    assert_eq!(best.len(), 2);
+    assert!(best[0].pool.contains(OTHER), "Best net price first: {}", best[0].pool);
+    assert!(best[0].profit_bps > best[1].profit_bps);
+
+    let named = select(&mk, &targets, "0xAAAA000000000000000000000000000000000001", 2.0, true, REFERENCE, &ctx).unwrap();
+    assert_eq!(named.len(), 1);
+    assert!(named[0].pool.contains(POOL));
+    assert!(select(&mk, &targets, "0xbbbb000000000000000000000000000000000001", 2.0, true, REFERENCE, &ctx).is_err());
+    assert!(select(&mk, &[], BEST_TARGET, 2.0, true, REFERENCE, &ctx).is_err());
+}
+
+#[test]
+fn test_quote_command() {
+    let cli = MakerCli::try_parse_from(["maker", "--config", "config/mainnet.eth-usdc.toml", "quote", "best", "--amount", "2"]).unwrap();
+    match cli.command {
+        Some(MakerCommand::Quote { pool, amount, sell_quote }) => assert_eq!((pool.as_str(), amount, sell_quote), ("best", 2.0, false)),
+        other => panic!("Unexpected command: {:?}", other),
+    }
+    let cli = MakerCli::try_parse_from(["maker", "quote", POOL, "--amount", "6000", "--sell-quote"]).unwrap();
+    assert!(matches!(cli.command, Some(MakerCommand::Quote { sell_quote: true, .. })));
+    assert!(MakerCli::try_parse_from(["maker", "quote", "best"]).is_err(), "Amount required");
+}
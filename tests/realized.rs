@@ -0,0 +1,129 @@
+//! Realized execution: ERC-20 transfers decoded from receipt logs (fixture of a WETH -> USDC swap), and the
+//! realized amount, slippage and gas of a trade compared with its expectation.
+use alloy::rpc::types::Log;
+use shd::types::maker::{BroadcastData, Inventory, MarketContext, PreTradeData, ReceiptData, TradeData, TradeDirection, TradeStatus, TransferData};
+use shd::utils::evm::{received, transfers};
+
+const WALLET: &str = "0x1111111111111111111111111111111111111111";
+const POOL: &str = "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640";
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+fn fixture() -> Vec<TransferData> {
+    let logs: Vec<Log> = serde_json::from_str(include_str!("fixtures/swap_receipt_logs.json")).expect("Fixture must parse");
+    transfers(&logs)
+}
+
+fn trade(receipt: ReceiptData) -> TradeData {
+    TradeData {
+        trade_id: "trade-1".to_string(),
+        status: TradeStatus::BroadcastSucceeded,
+        timestamp: 0,
+        context: MarketContext {
+            base_to_eth: 1.0,
+            quote_to_eth: 1.0 / 3_000.0,
+            eth_to_usd: 3_000.0,
+            max_fee_per_gas: 0,
+            max_priority_fee_per_gas: 0,
+            native_gas_price: 0,
+            block: 0,
+            age_ms: 0,
+        },
+        metadata: PreTradeData {
+            pool: POOL.to_string(),
+            base_token: "WETH".to_string(),
+            quote_token: "USDC".to_string(),
+            trade_direction: TradeDirection::Sell,
+            amount_in_normalized: 1.0,
+            amount_out_expected: 3_000.0,
+            token_out: USDC.to_lowercase(), // As stored from the Tycho token
+            token_out_decimals: 6,
+            spot_price: 3_000.0,
+            reference_price: 3_000.0,
+            slippage_tolerance_bps: 10.0,
+            profit_delta_bps: 1.0,
+            gas_cost_usd: 1.0,
+        },
+        inventory: Inventory {
+            base_balance: 0,
+            quote_balance: 0,
+            nonce: 0,
+            native_balance: 0,
+        },
+        simulation: None,
+        broadcast: Some(BroadcastData {
+            hash: format!("0x{}", "ab".repeat(32)),
+            receipt: Some(receipt),
+            ..Default::default()
+        }),
+        realized: None,
+    }
+}
+
+fn receipt(status: bool) -> ReceiptData {
+    ReceiptData {
+        status,
+        gas_used: 150_000,
+        error: None,
+        transaction_hash: format!("0x{}", "ab".repeat(32)),
+        transaction_index: 5,
+        block_number: 21_000_000,
+        effective_gas_price: 2_000_000_000, // 2 gwei
+        transfers: fixture(),
+    }
+}
+
+#[test]
+fn test_transfers_decoded_from_receipt_logs() {
+    let decoded = fixture();
+    // The pool Swap event and the ERC-721 transfer (token id indexed) are skipped
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(
+        decoded[0],
+        TransferData {
+            token: WETH.to_string(),
+            from: WALLET.to_string(),
+            to: POOL.to_string(),
+            amount: 1_000_000_000_000_000_000,
+        }
+    );
+    assert_eq!(decoded[1].token, USDC);
+    assert_eq!(decoded[1].amount, 2_995_000_000);
+
+    // Addresses compare case-insensitively, only transfers to the wallet count
+    assert_eq!(received(&decoded, &USDC.to_lowercase(), WALLET), 2_995_000_000);
+    assert_eq!(received(&decoded, WETH, WALLET), 0);
+    assert_eq!(received(&decoded, WETH, POOL), 1_000_000_000_000_000_000);
+    assert!(transfers(&[]).is_empty());
+}
+
+#[test]
+fn test_realized_execution_against_expectation() {
+    let mut data = trade(receipt(true));
+    let realized = data.realize(WALLET).cloned().expect("Successful receipt with transfers");
+    assert!((realized.realized_amount_out - 2_995.0).abs() < 1e-9);
+    // 5 USDC short of 3000: 16.67 bps, above the 10 bps tolerance (warned)
+    assert!((realized.realized_slippage_bps - 5.0 / 3_000.0 * 10_000.0).abs() < 1e-9);
+    // 150k gas at 2 gwei = 0.0003 ETH at 3000 $
+    assert!((realized.realized_gas_cost_usd - 0.9).abs() < 1e-9);
+    assert!(data.realized.is_some());
+
+    // Stored with the trade, and read back from messages of older makers without it
+    let value = serde_json::to_value(&data).unwrap();
+    assert!((value["realized"]["realized_slippage_bps"].as_f64().unwrap() - realized.realized_slippage_bps).abs() < 1e-9);
+    let mut legacy = value.clone();
+    legacy.as_object_mut().unwrap().remove("realized");
+    legacy["metadata"].as_object_mut().unwrap().remove("token_out");
+    legacy["broadcast"]["receipt"].as_object_mut().unwrap().remove("transfers");
+    let mut legacy = serde_json::from_value::<TradeData>(legacy).unwrap();
+    assert!(legacy.realized.is_none());
+    assert!(legacy.realize(WALLET).is_none(), "No output token to compare");
+}
+
+#[test]
+fn test_no_realized_execution_without_successful_receipt() {
+    assert!(trade(receipt(false)).realize(WALLET).is_none(), "Reverted swap");
+    let mut pending = trade(receipt(true));
+    pending.broadcast.as_mut().unwrap().receipt = None;
+    assert!(pending.realize(WALLET).is_none(), "Not mined yet");
+}
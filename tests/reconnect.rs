@@ -0,0 +1,130 @@
+//! Stream reconnection: capped exponential backoff, time to ready, and the snapshot of a new connection applied as a
+//! diff on the pools and the shared cache kept from the previous one.
+mod common;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use common::{base, component, quote, MockProtocolSim};
+use shd::maker::health::HealthState;
+use shd::maker::pools::{SnapshotDiff, StreamPools};
+use shd::maker::reconnect::Reconnect;
+use shd::types::config::load_market_maker_config;
+use shd::types::tycho::{SharedUpdate, TychoStreamState};
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL_A: &str = "0xaaaa000000000000000000000000000000000001";
+const POOL_B: &str = "0xbbbb000000000000000000000000000000000002";
+const POOL_C: &str = "0xcccc000000000000000000000000000000000003";
+
+fn ms(value: u64) -> Duration {
+    Duration::from_millis(value)
+}
+
+/// Update listing `pools`, each quoting 1000 ETH against `usdc` USDC.
+fn update(block: u64, pools: &[&str], usdc: f64) -> SharedUpdate {
+    let mut states: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
+    let mut new_pairs = HashMap::new();
+    for id in pools {
+        states.insert(id.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, usdc, 0.003)));
+        new_pairs.insert(id.to_string(), component(id, "uniswap_v2", vec![base(), quote()]));
+    }
+    SharedUpdate {
+        block_number_or_timestamp: block,
+        states,
+        new_pairs,
+        removed_pairs: HashMap::new(),
+    }
+}
+
+fn reserve1(pools: &StreamPools, id: &str) -> f64 {
+    pools.protosims[id].as_any().downcast_ref::<MockProtocolSim>().unwrap().reserve1
+}
+
+#[test]
+fn test_backoff_doubles_up_to_the_cap() {
+    let mut reconnect = Reconnect::new(1_000, 10_000);
+    let t0 = Instant::now();
+    let delays = (0..6).map(|x| reconnect.disconnected(t0 + ms(x))).collect::<Vec<Duration>>();
+    assert_eq!(delays, vec![ms(1_000), ms(2_000), ms(4_000), ms(8_000), ms(10_000), ms(10_000)]);
+    assert_eq!(reconnect.attempts(), 6);
+
+    // Ready again, measured from the first disconnect of the streak
+    assert_eq!(reconnect.ready(t0 + ms(30_000)), Some(ms(30_000)));
+    assert_eq!(reconnect.ready(t0 + ms(31_000)), None, "Already recovered");
+}
+
+#[test]
+fn test_backoff_restarts_after_a_stable_connection() {
+    let mut reconnect = Reconnect::new(1_000, 10_000);
+    let t0 = Instant::now();
+    assert_eq!(reconnect.ready(t0), None, "First connection");
+    reconnect.disconnected(t0 + ms(1));
+    reconnect.disconnected(t0 + ms(2));
+
+    // Flapping: ready for less than the cap, the backoff keeps growing
+    reconnect.ready(t0 + ms(5_000));
+    assert_eq!(reconnect.disconnected(t0 + ms(6_000)), ms(4_000));
+
+    // Stable: ready for the cap, back to the base delay
+    reconnect.ready(t0 + ms(10_000));
+    assert_eq!(reconnect.disconnected(t0 + ms(20_000)), ms(1_000));
+    assert_eq!(reconnect.attempts(), 1);
+}
+
+#[test]
+fn test_snapshot_resynced_as_a_diff() {
+    let mut pools = StreamPools::default();
+    assert!(pools.is_empty());
+    pools.apply(&update(100, &[POOL_A, POOL_B], 3_000_000.0));
+    assert_eq!(pools.components.len(), 2);
+
+    // New connection: B gone, C listed, A with a new state
+    let diff = pools.resync(&update(200, &[POOL_A, POOL_C], 3_100_000.0));
+    assert_eq!(diff, SnapshotDiff { added: 1, updated: 1, removed: 1 });
+    let mut ids = pools.components.iter().map(|cp| cp.id.to_string()).collect::<Vec<String>>();
+    ids.sort();
+    assert_eq!(ids, vec![POOL_A.to_string(), POOL_C.to_string()]);
+    assert!(!pools.protosims.contains_key(POOL_B));
+    assert_eq!(reserve1(&pools, POOL_A), 3_100_000.0 * 1e6);
+
+    // Listed without a state: left out, as on a cold start
+    let mut partial = update(300, &[POOL_A, POOL_C], 3_100_000.0);
+    partial.states.remove(POOL_C);
+    assert_eq!(pools.resync(&partial), SnapshotDiff { added: 0, updated: 1, removed: 1 });
+    assert_eq!(pools.components.len(), 1);
+}
+
+#[test]
+fn test_shared_cache_resynced_as_a_diff() {
+    let mut state = TychoStreamState {
+        protosims: HashMap::new(),
+        components: HashMap::new(),
+        atks: vec![base(), quote()],
+        block: 0,
+    };
+    state.apply(&update(100, &[POOL_A, POOL_B], 3_000_000.0));
+    state.resync(&update(200, &[POOL_B, POOL_C], 3_000_000.0));
+    let mut ids = state.components.keys().cloned().collect::<Vec<String>>();
+    ids.sort();
+    assert_eq!(ids, vec![POOL_B.to_string(), POOL_C.to_string()]);
+    assert_eq!(state.protosims.len(), 2);
+    assert_eq!(state.block, 200);
+}
+
+#[test]
+fn test_reconnect_config_and_metrics() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!((config.stream_restart_delay_ms, config.stream_restart_max_delay_ms), (1_000, 60_000));
+    config.stream_restart_max_delay_ms = 500;
+    assert!(config.validate().is_err(), "Cap below the base delay");
+
+    let health = HealthState::new("mainnet-eth-usdc-reconnect", &config);
+    let readiness = health.check(0, true);
+    assert_eq!((readiness.reconnects, readiness.time_to_ready_ms), (0, None));
+    health.reconnecting();
+    health.reconnecting();
+    health.recovered(4_200);
+    let readiness = health.check(0, true);
+    assert_eq!((readiness.reconnects, readiness.time_to_ready_ms), (2, Some(4_200)));
+}
@@ -0,0 +1,196 @@
+//! Price retention: raw rows past the downsample horizon become one OHLC row per bucket, rows past the retention are purged.
+mod common;
+
+use chrono::{Duration, NaiveDateTime};
+use common::sqlite;
+use sea_orm::{prelude::Uuid, ActiveModelTrait, Set};
+use serde_json::json;
+use shd::data::neon::{handle, pull};
+use shd::data::retention::{bucket_start, compact, compact_paged, downsample, RetentionReport};
+use shd::entity::price;
+use shd::types::config::{load_market_maker_config, DatabaseType, EventsTransport, MoniEnvConfig};
+use shd::types::maker::ComponentPriceData;
+use shd::types::moni::{DownsampledPrices, NewInstanceMessage, NewPricesMessage, ParsedMessage, TradingState};
+
+fn env() -> MoniEnvConfig {
+    MoniEnvConfig {
+        testing: true,
+        heartbeat: String::new(),
+        heartbeat_interval_s: 150,
+        heartbeat_timeout_ms: 5_000,
+        database_url: String::new(),
+        database_name: String::new(),
+        database_backend: DatabaseType::Sqlite,
+        events_transport: EventsTransport::PubSub,
+        price_retention_days: 30,
+        price_downsample_after_days: 7,
+        price_downsample_minutes: 15,
+        trade_receipt_max_age_minutes: 60,
+        redis_legacy_keys: false,
+    }
+}
+
+fn at(text: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+/// Raw price row of an instance, with one component.
+fn row(instance_id: &str, created_at: NaiveDateTime, block: u64, reference: f64, pool: f64) -> price::Model {
+    let msg = NewPricesMessage {
+        identifier: format!("{}-identifier", instance_id),
+        reference_price: reference,
+        components: vec![ComponentPriceData {
+            address: "0xpool".to_string(),
+            r#type: "uniswap_v3".to_string(),
+            price: pool,
+        }],
+        block,
+        depth: None,
+        state: TradingState::Running,
+        sigma_bps: 0.0,
+        execution_threshold_bps: 0.0,
+        adaptive_bps: 0.0,
+        quarantined: 0,
+        stream_lag_blocks: Some(block), // Grows with the block, the max of a bucket is its last
+        degraded: false,
+    };
+    price::Model {
+        id: Uuid::new_v4().to_string(),
+        created_at,
+        updated_at: created_at,
+        instance_id: instance_id.to_string(),
+        value: json!(msg),
+    }
+}
+
+#[test]
+fn test_downsample_buckets() {
+    assert_eq!(bucket_start(at("2026-01-01 10:14:59"), 15), at("2026-01-01 10:00:00"));
+    assert_eq!(bucket_start(at("2026-01-01 10:15:00"), 15), at("2026-01-01 10:15:00"));
+    let rows = vec![
+        row("a", at("2026-01-01 10:01:00"), 1, 10.0, 10.5),
+        row("a", at("2026-01-01 10:05:00"), 2, 12.0, 11.0),
+        row("a", at("2026-01-01 10:09:00"), 3, 9.0, 9.5),
+        row("a", at("2026-01-01 10:16:00"), 4, 11.0, 11.0),
+        row("b", at("2026-01-01 10:02:00"), 1, 100.0, 100.0),
+    ];
+    let mut aggregated = row("a", at("2026-01-01 09:45:00"), 0, 1.0, 1.0);
+    aggregated.value["downsampled"] = json!(true);
+    let buckets = downsample(&[rows.clone(), vec![aggregated]].concat(), 15);
+    assert_eq!(buckets.len(), 3, "aggregated rows are not aggregated again");
+
+    let first = buckets.iter().find(|b| b.instance_id == "a" && b.start == at("2026-01-01 10:00:00")).unwrap();
+    assert_eq!(first.ids.len(), 3);
+    let ohlc = first.prices.reference_price;
+    assert_eq!((ohlc.open, ohlc.high, ohlc.low, ohlc.close), (10.0, 12.0, 9.0, 9.0));
+    assert_eq!((first.prices.first_block, first.prices.last_block, first.prices.rows), (1, 3, 3));
+    assert_eq!(first.prices.max_stream_lag_blocks, Some(3));
+    assert_eq!((first.prices.components[0].min, first.prices.components[0].max), (9.5, 11.0));
+    assert!(buckets.iter().any(|b| b.instance_id == "b" && b.ids.len() == 1));
+}
+
+#[tokio::test]
+async fn test_compact_is_idempotent() {
+    let (db, path) = sqlite("retention").await;
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let message = ParsedMessage::NewInstance(NewInstanceMessage {
+        identifier: format!("{}-instance-1", config.id()),
+        config,
+        commit: "test".to_string(),
+    });
+    handle(&message, &db).await.unwrap();
+    let instance = pull::instances(&db).await.unwrap().remove(0);
+
+    let now = at("2026-03-01 12:00:00");
+    let rows = vec![
+        row(&instance.id, now - Duration::days(40), 1, 2000.0, 2000.0),  // Past the retention
+        row(&instance.id, at("2026-02-15 08:00:00"), 2, 2500.0, 2490.0), // Downsampled together
+        row(&instance.id, at("2026-02-15 08:05:00"), 3, 2520.0, 2530.0),
+        row(&instance.id, at("2026-02-15 08:10:00"), 4, 2510.0, 2505.0),
+        row(&instance.id, now - Duration::days(1), 5, 2600.0, 2600.0), // Recent, kept raw
+    ];
+    for row in rows {
+        price::ActiveModel::from(row).insert(&db).await.unwrap();
+    }
+
+    let report = compact(&db, &env(), now).await.unwrap();
+    assert_eq!(
+        report,
+        RetentionReport {
+            compacted: 3,
+            downsampled: 1,
+            purged: 1
+        }
+    );
+    let prices = pull::prices(&db).await.unwrap();
+    assert_eq!(prices.len(), 2);
+    let aggregated = prices.iter().find(|p| p.value.get("downsampled").is_some()).unwrap();
+    assert_eq!(aggregated.created_at, at("2026-02-15 08:00:00"));
+    let aggregated: DownsampledPrices = serde_json::from_value(aggregated.value.clone()).unwrap();
+    assert_eq!((aggregated.rows, aggregated.reference_price.high, aggregated.reference_price.close), (3, 2520.0, 2510.0));
+
+    // A second run finds nothing to do
+    assert_eq!(compact(&db, &env(), now).await.unwrap(), RetentionReport::default());
+    assert_eq!(pull::prices(&db).await.unwrap().len(), 2);
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn test_compact_by_pages() {
+    let (db, path) = sqlite("retention-pages").await;
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    let message = ParsedMessage::NewInstance(NewInstanceMessage {
+        identifier: format!("{}-instance-1", config.id()),
+        config,
+        commit: "test".to_string(),
+    });
+    handle(&message, &db).await.unwrap();
+    let instance = pull::instances(&db).await.unwrap().remove(0);
+    let now = at("2026-03-01 12:00:00");
+    let times = [
+        "2026-02-15 08:00:00",
+        "2026-02-15 08:05:00",
+        "2026-02-15 08:10:00",
+        "2026-02-15 08:15:00",
+        "2026-02-15 08:20:00",
+        "2026-02-15 08:30:00",
+    ];
+    let insert = || async {
+        for (x, time) in times.iter().enumerate() {
+            price::ActiveModel::from(row(&instance.id, at(time), x as u64, 2500.0, 2500.0)).insert(&db).await.unwrap();
+        }
+    };
+
+    // The last bucket of a full page is left to the next one, each bucket aggregated once
+    insert().await;
+    let report = compact_paged(&db, &env(), now, 4).await.unwrap();
+    assert_eq!((report.compacted, report.downsampled), (6, 3));
+    let prices = pull::prices(&db).await.unwrap();
+    assert_eq!(prices.len(), 3);
+    let rows = prices
+        .iter()
+        .map(|p| serde_json::from_value::<DownsampledPrices>(p.value.clone()).unwrap().rows)
+        .collect::<Vec<usize>>();
+    assert_eq!(rows.iter().sum::<usize>(), 6);
+    assert!(rows.contains(&3) && rows.contains(&2) && rows.contains(&1), "{:?}", rows);
+    assert_eq!(compact_paged(&db, &env(), now, 4).await.unwrap(), RetentionReport::default());
+
+    // A bucket larger than a page is aggregated page by page, no raw row left behind
+    insert().await;
+    let report = compact_paged(&db, &env(), now, 2).await.unwrap();
+    assert_eq!(report.compacted, 6);
+    assert!(pull::prices(&db).await.unwrap().iter().all(|p| p.value.get("downsampled").is_some()));
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn test_retention_validation() {
+    let mut env = env();
+    env.validate().unwrap();
+    env.price_downsample_after_days = 30;
+    assert!(env.validate().unwrap_err().to_string().contains("PRICE_DOWNSAMPLE_AFTER_DAYS"));
+    env.price_retention_days = 0;
+    env.validate().unwrap();
+    env.price_downsample_minutes = 0;
+    assert!(env.validate().is_err());
+}
@@ -0,0 +1,122 @@
+//! Revert data decoding: Error(string), Panic(uint256), custom errors of the dictionary and malformed payloads.
+use alloy_primitives::{Address, I256, U256};
+use shd::types::config::load_market_maker_config;
+use shd::utils::evm::{decode_revert, register_errors, revert_reason, selector, DecodedRevert};
+
+fn word(value: U256) -> Vec<u8> {
+    value.to_be_bytes::<32>().to_vec()
+}
+
+fn payload(signature: &str, words: &[Vec<u8>]) -> Vec<u8> {
+    [selector(signature).to_vec(), words.concat()].concat()
+}
+
+/// ABI encoded Error(string).
+fn error_string(reason: &str) -> Vec<u8> {
+    let mut padded = reason.as_bytes().to_vec();
+    padded.resize(reason.len().div_ceil(32) * 32, 0);
+    payload("Error(string)", &[word(U256::from(0x20)), word(U256::from(reason.len())), padded])
+}
+
+#[test]
+fn test_standard_selectors() {
+    assert_eq!(hex::encode(selector("Error(string)")), "08c379a0");
+    assert_eq!(hex::encode(selector("Panic(uint256)")), "4e487b71");
+}
+
+#[test]
+fn test_error_string() {
+    let decoded = decode_revert(&error_string("TransferHelper: TRANSFER_FROM_FAILED, a reason longer than one word"));
+    assert_eq!(decoded, DecodedRevert::Reason("TransferHelper: TRANSFER_FROM_FAILED, a reason longer than one word".to_string()));
+    assert_eq!(decode_revert(&error_string("STF")).to_string(), "reverted: STF");
+    // Selector without its string
+    assert!(matches!(decode_revert(&selector("Error(string)")), DecodedRevert::Unknown(_)));
+}
+
+#[test]
+fn test_panic_codes() {
+    let overflow = decode_revert(&payload("Panic(uint256)", &[word(U256::from(0x11))]));
+    assert_eq!(
+        overflow,
+        DecodedRevert::Panic {
+            code: U256::from(0x11),
+            description: "arithmetic overflow or underflow"
+        }
+    );
+    assert_eq!(overflow.to_string(), "panic 0x11: arithmetic overflow or underflow");
+    assert_eq!(
+        decode_revert(&payload("Panic(uint256)", &[word(U256::from(0x12))])).to_string(),
+        "panic 0x12: division or modulo by zero"
+    );
+    assert_eq!(
+        decode_revert(&payload("Panic(uint256)", &[word(U256::from(0x32))])).to_string(),
+        "panic 0x32: array index out of bounds"
+    );
+    assert_eq!(decode_revert(&payload("Panic(uint256)", &[word(U256::from(0x99))])).to_string(), "panic 0x99: unknown panic code");
+    assert!(matches!(decode_revert(&selector("Panic(uint256)")), DecodedRevert::Unknown(_)));
+}
+
+#[test]
+fn test_router_and_permit2_errors() {
+    let slippage = decode_revert(&payload(
+        "TychoRouter__NegativeSlippage(uint256,uint256)",
+        &[word(U256::from(4_497_000_000u64)), word(U256::from(4_497_750_000u64))],
+    ));
+    assert_eq!(
+        slippage,
+        DecodedRevert::Custom {
+            name: "TychoRouter__NegativeSlippage".to_string(),
+            args: vec!["4497000000".to_string(), "4497750000".to_string()]
+        }
+    );
+    assert_eq!(slippage.to_string(), "TychoRouter__NegativeSlippage(4497000000, 4497750000)");
+
+    let executor = "0x1111111111111111111111111111111111111111".parse::<Address>().unwrap();
+    let unapproved = decode_revert(&payload("Dispatcher__UnapprovedExecutor(address)", &[word(U256::from_be_slice(executor.as_slice()))]));
+    assert_eq!(unapproved.to_string(), format!("Dispatcher__UnapprovedExecutor({})", executor));
+    assert_eq!(decode_revert(&payload("TychoRouter__EmptySwaps()", &[])).to_string(), "TychoRouter__EmptySwaps()");
+    assert_eq!(
+        decode_revert(&payload("AllowanceExpired(uint256)", &[word(U256::from(1_700_000_000u64))])).to_string(),
+        "AllowanceExpired(1700000000)"
+    );
+    // Arguments missing, left as hex
+    assert_eq!(decode_revert(&payload("InsufficientAllowance(uint256)", &[])).to_string(), "InsufficientAllowance(0x)");
+}
+
+#[test]
+fn test_registered_errors() {
+    let skewed = payload("Skewed__Test(int256,bool)", &[word(I256::try_from(-42).unwrap().into_raw()), word(U256::from(1))]);
+    assert!(matches!(decode_revert(&skewed), DecodedRevert::Unknown(_)), "Not registered yet");
+    register_errors(&["Skewed__Test(int256,bool)".to_string(), "Named__Test(string)".to_string()]);
+    assert_eq!(decode_revert(&skewed).to_string(), "Skewed__Test(-42, true)");
+
+    // Dynamic arguments are left as hex
+    let named = payload("Named__Test(string)", &[word(U256::from(0x20)), word(U256::from(1)), word(U256::ZERO)]);
+    match decode_revert(&named) {
+        DecodedRevert::Custom { name, args } => assert_eq!((name.as_str(), args.len()), ("Named__Test", 1)),
+        other => panic!("Unexpected decoding: {:?}", other),
+    }
+}
+
+#[test]
+fn test_empty_short_and_unknown_data() {
+    assert_eq!(decode_revert(&[]), DecodedRevert::Empty);
+    assert_eq!(decode_revert(&[]).to_string(), "reverted without data");
+    assert_eq!(decode_revert(&[0xde, 0xad]), DecodedRevert::Unknown("0xdead".to_string()));
+    assert_eq!(decode_revert(&[0xde, 0xad, 0xbe, 0xef, 0x01]).to_string(), "unknown revert data 0xdeadbeef01");
+
+    assert_eq!(revert_reason("execution reverted", &[]), "execution reverted");
+    assert_eq!(revert_reason("execution reverted", &error_string("STF")), "execution reverted: reverted: STF");
+}
+
+#[test]
+fn test_revert_errors_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert!(config.revert_errors.is_empty());
+    config.revert_errors = vec!["MyError(uint256,address)".to_string(), "Bare()".to_string()];
+    assert!(config.validate().is_ok());
+    for invalid in ["MyError", "My Error(uint256)", "MyError(uint256, address)", "1Error()", "(uint256)"] {
+        config.revert_errors = vec![invalid.to_string()];
+        assert!(config.validate().is_err(), "{}", invalid);
+    }
+}
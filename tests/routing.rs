@@ -0,0 +1,145 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{base, component, quote, token, MockProtocolSim};
+use shd::opti::routing::{find_path, quote as quote_path, TokenGraph};
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+fn uniswap() -> Vec<String> {
+    vec!["uniswap_v2".to_string(), "uniswap_v3".to_string(), "uniswap_v4".to_string()]
+}
+
+#[test]
+fn test_find_path_filtered_success() {
+    let dai = token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18);
+    let components = vec![
+        // Direct curve route, excluded by the whitelist
+        component("0x1111111111111111111111111111111111111111", "vm:curve", vec![quote(), base()]),
+        // Two hops through DAI on uniswap
+        component("0x2222222222222222222222222222222222222222", "uniswap_v2", vec![quote(), dai.clone()]),
+        component("0x3333333333333333333333333333333333333333", "uniswap_v3", vec![dai.clone(), base()]),
+    ];
+
+    let graph = TokenGraph::new(&components, &HashMap::new());
+    let path = find_path(&graph, quote().address.to_string(), base().address.to_string(), &uniswap()).expect("Path should be found");
+    assert_eq!(path.token_path.len(), 3, "Expected the uniswap route through DAI: {:?}", path.token_path);
+    assert!(path.comp_path.iter().all(|id| !id.contains("1111")), "Curve component must be filtered out: {:?}", path.comp_path);
+}
+
+#[test]
+fn test_find_path_filtered_empty() {
+    let components = vec![
+        component("0x1111111111111111111111111111111111111111", "vm:curve", vec![quote(), base()]),
+        component("0x4444444444444444444444444444444444444444", "vm:balancer_v2", vec![quote(), base()]),
+    ];
+
+    let graph = TokenGraph::new(&components, &HashMap::new());
+    let err = find_path(&graph, quote().address.to_string(), base().address.to_string(), &uniswap()).expect_err("Whitelist should filter every path");
+    assert!(err.contains("routing_protocol_whitelist"), "Error should point at the whitelist: {}", err);
+
+    // Without the whitelist, the curve route is found
+    assert!(find_path(&graph, quote().address.to_string(), base().address.to_string(), &[]).is_ok());
+}
+
+#[test]
+fn test_find_path_no_route() {
+    let dai = token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18);
+    let components = vec![component("0x2222222222222222222222222222222222222222", "uniswap_v2", vec![quote(), dai])];
+
+    let graph = TokenGraph::new(&components, &HashMap::new());
+    let err = find_path(&graph, quote().address.to_string(), base().address.to_string(), &uniswap()).expect_err("No route exists");
+    assert!(!err.contains("routing_protocol_whitelist"), "Whitelist is not the cause: {}", err);
+}
+
+#[test]
+fn test_graph_keeps_most_liquid_parallel_edge() {
+    let shallow = "0x5555555555555555555555555555555555555555";
+    let deep = "0x6666666666666666666666666666666666666666";
+    let components = vec![component(shallow, "uniswap_v2", vec![base(), quote()]), component(deep, "uniswap_v3", vec![base(), quote()])];
+    let mut protosims: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
+    protosims.insert(shallow.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 10.0, 30_000.0, 0.003)));
+    protosims.insert(deep.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003)));
+
+    let mut graph = TokenGraph::new(&components, &protosims);
+    let path = find_path(&graph, base().address.to_string(), quote().address.to_string(), &uniswap()).expect("Path should be found");
+    assert_eq!(path.comp_path, vec![deep.to_string()]);
+
+    // Removing the deep pool falls back to the shallow one
+    graph.remove(deep);
+    let path = find_path(&graph, base().address.to_string(), quote().address.to_string(), &uniswap()).expect("Path should be found");
+    assert_eq!(path.comp_path, vec![shallow.to_string()]);
+}
+
+#[test]
+fn test_quote_fallback_within_the_whitelist() {
+    let routed = "0x5555555555555555555555555555555555555555";
+    let curve = "0x1111111111111111111111111111111111111111";
+    let components = vec![component(routed, "uniswap_v2", vec![base(), quote()]), component(curve, "vm:curve", vec![base(), quote()])];
+    // The routed pool has no state, its spot price fails
+    let mut protosims: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
+    protosims.insert(curve.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003)));
+
+    let graph = TokenGraph::new(&components, &protosims);
+    let atks = vec![base(), quote()];
+    let path = find_path(&graph, base().address.to_string(), quote().address.to_string(), &uniswap()).expect("Path should be found");
+    assert_eq!(path.comp_path, vec![routed.to_string()]);
+    assert_eq!(quote_path(&graph, &protosims, &atks, &path, &uniswap()), None, "Curve edge outside of the whitelist");
+    let price = quote_path(&graph, &protosims, &atks, &path, &[]).expect("Any parallel edge without a whitelist");
+    assert!(price > 2_900.0 && price < 3_100.0, "{}", price);
+}
+
+#[test]
+fn test_graph_incremental_updates() {
+    let dai = token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18);
+    let mut graph = TokenGraph::new(&[component("0x2222222222222222222222222222222222222222", "uniswap_v2", vec![quote(), dai.clone()])], &HashMap::new());
+    assert!(find_path(&graph, quote().address.to_string(), base().address.to_string(), &[]).is_err());
+
+    graph.insert(component("0x3333333333333333333333333333333333333333", "uniswap_v3", vec![dai.clone(), base()]), None);
+    let path = find_path(&graph, quote().address.to_string(), base().address.to_string(), &[]).expect("Path should be found after insert");
+    assert_eq!(path.token_path.len(), 3);
+
+    assert!(graph.remove("0x3333333333333333333333333333333333333333").is_some());
+    assert!(find_path(&graph, quote().address.to_string(), base().address.to_string(), &[]).is_err());
+    assert_eq!(graph.len(), 1);
+}
+
+#[test]
+fn test_graph_path_query_benchmark() {
+    // Synthetic 5,000 component graph: 1,000 long-tail tokens paired with 4 hubs, plus random long-tail pairs
+    let hubs: Vec<_> = (0..4).map(|i| token(&format!("0x{:040x}", i + 1), &format!("HUB{}", i), 18)).collect();
+    let tails: Vec<_> = (0..1_000).map(|i| token(&format!("0x{:040x}", 0x1000 + i), &format!("TK{}", i), 18)).collect();
+    let protocols = ["uniswap_v2", "uniswap_v3", "uniswap_v4", "vm:curve"];
+    let mut components = vec![];
+    for i in 0..5_000usize {
+        let id = format!("0x{:040x}", 0x10_0000 + i);
+        let tokens = if i < 3 {
+            // Hubs are connected together (HUB0 = ETH)
+            vec![hubs[i + 1].clone(), hubs[0].clone()]
+        } else if i < 2_000 {
+            vec![tails[i % tails.len()].clone(), hubs[1 + i % 3].clone()]
+        } else {
+            let seed = i.wrapping_mul(2_654_435_761) % tails.len();
+            vec![tails[seed].clone(), tails[(seed + 7) % tails.len()].clone()]
+        };
+        // Hub pairs stay inside the whitelist so every token reaches ETH
+        let protocol = if i < 2_000 { protocols[i % 3] } else { protocols[i % protocols.len()] };
+        components.push(component(&id, protocol, tokens));
+    }
+
+    let time = std::time::Instant::now();
+    let graph = TokenGraph::new(&components, &HashMap::new());
+    println!("Graph built in {:?} ({} components)", time.elapsed(), graph.len());
+    assert_eq!(graph.len(), 5_000);
+
+    let queries = 1_000;
+    let time = std::time::Instant::now();
+    for i in 0..queries {
+        let input = tails[i % tails.len()].address.to_string();
+        let path = find_path(&graph, input, hubs[0].address.to_string(), &uniswap()).expect("Path should be found");
+        assert!(path.token_path.len() >= 2);
+    }
+    let average = time.elapsed() / queries as u32;
+    println!("Average path query: {:?}", average);
+    assert!(average < std::time::Duration::from_millis(1), "Path queries too slow: {:?}", average);
+}
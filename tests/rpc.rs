@@ -0,0 +1,141 @@
+//! RPC failover: the pool state machine, driven by a mocked transport (closures standing for the endpoints).
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use shd::types::config::{load_market_maker_config, MarketMakerConfig};
+use shd::utils::evm::RpcPool;
+
+const A: &str = "http://a.rpc";
+const B: &str = "http://b.rpc";
+const C: &str = "http://c.rpc";
+
+fn pool(max_failures: u32) -> RpcPool {
+    RpcPool::new(vec![A.to_string(), B.to_string(), C.to_string()], max_failures, Duration::from_millis(50), Duration::from_millis(100))
+}
+
+/// Transport answering with the endpoint it was called on, failing on `down`.
+async fn transport(url: String, down: &[&str], calls: &AtomicUsize) -> Result<String, String> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    if down.contains(&url.as_str()) {
+        Err(format!("{} unreachable", url))
+    } else {
+        Ok(url)
+    }
+}
+
+#[test]
+fn test_round_robin_over_healthy_endpoints() {
+    let pool = pool(2);
+    let reads = (0..6).map(|_| pool.read_url()).collect::<Vec<String>>();
+    assert_eq!(reads, vec![A, B, C, A, B, C]);
+    assert_eq!(pool.write_url(), A, "Primary is the write endpoint");
+}
+
+#[tokio::test]
+async fn test_failover_and_unhealthy_after_consecutive_errors() {
+    let pool = pool(2);
+    let calls = AtomicUsize::new(0);
+    // Every call fails over to a healthy endpoint, A keeps failing
+    for _ in 0..6 {
+        let served = pool.call(|url| transport(url, &[A], &calls)).await.unwrap();
+        assert_ne!(served, A);
+    }
+    assert!(!pool.is_healthy(A), "Two consecutive errors");
+    assert!(pool.is_healthy(B) && pool.is_healthy(C));
+    assert!((0..4).all(|_| pool.read_url() != A), "Skipped by the rotation");
+    assert_ne!(pool.write_url(), A, "Writes move to a healthy endpoint");
+
+    // Unhealthy endpoints are still tried last when everything else fails
+    let served = pool.call(|url| transport(url, &[B, C], &calls)).await;
+    assert!(served.is_err(), "A still down");
+    let calls_before = calls.load(Ordering::SeqCst);
+    let served = pool.call(|url| transport(url, &[B, C], &calls)).await;
+    assert!(served.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst) - calls_before, 3, "All endpoints tried once");
+}
+
+#[test]
+fn test_single_error_does_not_mark_unhealthy() {
+    let pool = pool(3);
+    pool.report(A, Duration::from_millis(1), false);
+    pool.report(A, Duration::from_millis(1), false);
+    pool.report(A, Duration::from_millis(1), true);
+    pool.report(A, Duration::from_millis(1), false);
+    assert!(pool.is_healthy(A), "A success resets the consecutive count");
+}
+
+#[test]
+fn test_slow_responses_count_as_failures() {
+    let pool = pool(2);
+    pool.report(B, Duration::from_millis(80), true);
+    pool.report(B, Duration::from_millis(80), true);
+    assert!(!pool.is_healthy(B));
+}
+
+#[test]
+fn test_probe_schedule_and_recovery() {
+    let pool = pool(1);
+    pool.report(C, Duration::from_millis(1), false);
+    assert!(!pool.is_healthy(C));
+    let failed = Instant::now();
+    assert!(pool.due_probes(failed).is_empty(), "Probe waits the interval after the failure");
+    let due = pool.due_probes(failed + Duration::from_millis(150));
+    assert_eq!(due, vec![C.to_string()]);
+    assert!(pool.due_probes(failed + Duration::from_millis(200)).is_empty(), "Marked as checked");
+
+    // Successful eth_blockNumber
+    pool.report(C, Duration::from_millis(5), true);
+    assert!(pool.is_healthy(C));
+    assert!(pool.due_probes(failed + Duration::from_secs(10)).is_empty());
+}
+
+#[test]
+fn test_all_unhealthy_falls_back_to_primary_order() {
+    let pool = RpcPool::new(vec![A.to_string(), B.to_string()], 1, Duration::from_millis(50), Duration::from_millis(100));
+    pool.report(A, Duration::ZERO, false);
+    pool.report(B, Duration::ZERO, false);
+    assert!(!pool.read_url().is_empty(), "Still an endpoint to try");
+    assert_eq!(pool.candidates().len(), 2);
+}
+
+#[test]
+fn test_config_endpoints() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!(config.rpc_endpoints(), vec![config.rpc_url.clone()], "Single endpoint by default");
+    config.rpc_urls = vec![A.to_string(), config.rpc_url.clone(), B.to_string()];
+    assert_eq!(config.rpc_endpoints(), vec![config.rpc_url.clone(), A.to_string(), B.to_string()], "Primary first, no duplicates");
+    assert!(config.validate().is_ok());
+    config.rpc_urls = vec!["not a url".to_string()];
+    assert!(config.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_writes_reported_on_the_write_endpoint() {
+    let pool = pool(2);
+    let calls = AtomicUsize::new(0);
+    for _ in 0..2 {
+        let sent = pool.write(|url| transport(url, &[A], &calls)).await;
+        assert!(sent.is_err(), "Never retried on another endpoint");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert!(!pool.is_healthy(A), "Failed writes reported");
+    assert_eq!(pool.write(|url| transport(url, &[], &calls)).await.unwrap(), B, "Writes move to a healthy endpoint");
+}
+
+#[test]
+fn test_pools_keyed_by_health_settings() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    let shared = RpcPool::of(&config);
+    assert!(Arc::ptr_eq(&shared, &RpcPool::of(&config)), "Same endpoints and settings share the pool");
+    let strict = MarketMakerConfig {
+        rpc_max_failures: config.rpc_max_failures + 1,
+        ..config.clone()
+    };
+    assert!(!Arc::ptr_eq(&shared, &RpcPool::of(&strict)), "Own pool for other health settings");
+    let slow = MarketMakerConfig {
+        rpc_slow_ms: config.rpc_slow_ms * 2,
+        ..config.clone()
+    };
+    assert!(!Arc::ptr_eq(&shared, &RpcPool::of(&slow)));
+}
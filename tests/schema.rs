@@ -0,0 +1,63 @@
+//! Strict config parsing: unknown keys are rejected with the closest field name, and the JSON schema export.
+use shd::types::config::{config_schema, load_market_maker_config_with, unknown_config_fields};
+
+const REFERENCE: &str = "config/mainnet.eth-usdc.toml";
+
+/// Loads the reference config with `edit` applied to its contents, without environment overrides.
+fn load_edited(name: &str, edit: impl Fn(String) -> String) -> Result<(), String> {
+    let contents = edit(std::fs::read_to_string(REFERENCE).unwrap());
+    let path = std::env::temp_dir().join(format!("mkmk-schema-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    let loaded = load_market_maker_config_with(path.to_str().unwrap(), vec![]).map(|_| ()).map_err(|e| e.to_string());
+    std::fs::remove_file(path).ok();
+    loaded
+}
+
+#[test]
+fn test_misspelled_key_is_rejected_with_suggestion() {
+    let err = load_edited("typo", |c| c.replace("min_executable_spread_bps", "min_executable_spread_bsp")).unwrap_err();
+    assert!(err.contains("'min_executable_spread_bsp' (did you mean 'min_executable_spread_bps'?)"), "{}", err);
+}
+
+#[test]
+fn test_nested_and_multiple_unknown_keys() {
+    let err = load_edited("nested", |c| {
+        c.replace("reverse = false", "revers = false").replace("pair_tag", "pair_tag = \"x\"\nzzzz_qqqq = 1\npair_tagg")
+    })
+    .unwrap_err();
+    assert!(err.contains("'price_feed_config.revers' (did you mean 'price_feed_config.reverse'?)"), "{}", err);
+    assert!(err.contains("'pair_tagg' (did you mean 'pair_tag'?)"), "{}", err);
+    assert!(err.contains("'zzzz_qqqq'") && !err.contains("zzzz_qqqq' (did you mean"), "{}", err);
+}
+
+#[test]
+fn test_map_keys_are_free() {
+    // spread_overrides is keyed by protocol system, not by config field
+    load_edited("map", |c| {
+        c.replace("[price_feed_config]", "spread_overrides = { \"uniswap_v2\" = 45, \"anything\" = 12 }\n\n[price_feed_config]")
+    })
+    .unwrap();
+    let table: toml::Table = toml::from_str(&std::fs::read_to_string(REFERENCE).unwrap()).unwrap();
+    assert!(unknown_config_fields(&table).is_empty());
+}
+
+#[test]
+fn test_schema_export() {
+    let schema = config_schema();
+    let properties = schema["properties"].as_object().expect("Schema has properties");
+    assert!(properties.contains_key("min_executable_spread_bps"));
+    assert!(properties.contains_key("price_feed_config"));
+    assert!(
+        !properties.contains_key("overrides") && !properties.contains_key("extends"),
+        "runtime fields are not part of the file format"
+    );
+    assert_eq!(schema["additionalProperties"], serde_json::Value::Bool(false));
+    let required = schema["required"]
+        .as_array()
+        .expect("Schema lists required fields")
+        .iter()
+        .filter_map(|f| f.as_str())
+        .collect::<Vec<&str>>();
+    assert!(required.contains(&"rpc_url"));
+    assert!(!required.contains(&"pool_cooldown_blocks"), "fields with a default are optional");
+}
@@ -0,0 +1,59 @@
+use shd::types::config::{load_market_maker_config, MarketMakerConfig};
+
+const POOL_A: &str = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc";
+const POOL_B: &str = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+const POOL_V4: &str = "0x21c67e77068de97969ba93d4aab21826d33ca12bb9f565d8496e8fda8a82ca27";
+
+fn reference_config() -> MarketMakerConfig {
+    load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load")
+}
+
+#[test]
+fn test_empty_lists_keep_token_pair_matching() {
+    let config = reference_config();
+    assert!(config.targets_pool(POOL_A, true));
+    assert!(!config.targets_pool(POOL_A, false));
+}
+
+#[test]
+fn test_denylist_excludes_case_insensitive() {
+    let mut config = reference_config();
+    config.pool_denylist = vec![POOL_A.to_lowercase()];
+    assert!(!config.targets_pool(POOL_A, true));
+    assert!(config.targets_pool(POOL_B, true));
+}
+
+#[test]
+fn test_allowlist_restricts_selection() {
+    let mut config = reference_config();
+    config.pool_allowlist = vec![POOL_V4.to_string()];
+    assert!(config.targets_pool(POOL_V4, true));
+    assert!(!config.targets_pool(POOL_A, true), "Pools outside the allowlist are not monitored");
+    assert!(!config.targets_pool(POOL_V4, false), "A listed pool must still hold the pair");
+}
+
+#[test]
+fn test_denylist_wins_over_allowlist() {
+    let mut config = reference_config();
+    config.pool_allowlist = vec![POOL_A.to_string()];
+    config.pool_denylist = vec![POOL_A.to_string()];
+    assert!(!config.targets_pool(POOL_A, true));
+}
+
+#[test]
+fn test_invalid_ids_fail_validation() {
+    let mut config = reference_config();
+    config.pool_allowlist = vec![POOL_A.to_string(), "b4e16d0168e52d35cacd2c6185b44281ec28c9dc".to_string()];
+    let err = config.validate().expect_err("Missing 0x prefix must fail").to_string();
+    assert!(err.contains("pool_allowlist") && err.contains("b4e16d0168e52d35cacd2c6185b44281ec28c9dc"), "{}", err);
+
+    let mut config = reference_config();
+    config.pool_denylist = vec!["0xnothex".to_string()];
+    let err = config.validate().expect_err("Non-hex id must fail").to_string();
+    assert!(err.contains("pool_denylist") && err.contains("0xnothex"), "{}", err);
+
+    let mut config = reference_config();
+    config.pool_allowlist = vec![POOL_A.to_string()];
+    config.pool_denylist = vec![POOL_V4.to_string()];
+    assert!(config.validate().is_ok());
+}
@@ -0,0 +1,261 @@
+//! Graceful shutdown: the block loop of the market maker (`MarketMaker::consume`) fed by a mocked stream, with mocked
+//! market data and an execution whose broadcast takes a while. A shutdown requested mid-broadcast lets the execution
+//! in progress complete and publish, then the loop returns before the next block, within the grace period.
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use alloy::rpc::types::TransactionRequest;
+use async_trait::async_trait;
+use common::{base, component, context, env, pool, quote, MockBalances};
+use futures::StreamExt;
+use shd::error::ExecError;
+use shd::maker::engine::{DecisionEngine, Execution, MarketDataSource};
+use shd::maker::exec::ExecStrategy;
+use shd::maker::feed::PriceFeedFactory;
+use shd::maker::lag::StreamLag;
+use shd::maker::permit2::SignedPermit;
+use shd::maker::shutdown::{drain, Shutdown};
+use shd::maker::tycho::PoolBalances;
+use shd::opti::routing::TokenGraph;
+use shd::types::builder::MarketMakerBuilder;
+use shd::types::config::{EnvConfig, MarketMakerConfig};
+use shd::types::maker::{BroadcastData, CompReadjustment, ExecutionOrder, Inventory, MarketContext, MarketMaker, Trade, TradeData};
+use shd::types::tycho::SharedUpdate;
+use tycho_common::models::token::Token;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+const BLOCK: u64 = 100;
+
+/// Execution strategy whose broadcast takes `delay`, counting executions started and published.
+struct SlowExec {
+    delay: Duration,
+    started: Arc<AtomicUsize>,
+    published: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl ExecStrategy for SlowExec {
+    fn name(&self) -> String {
+        "Slow_Strategy".to_string()
+    }
+
+    async fn broadcast(&self, _prepared: Vec<Trade>, _mmc: MarketMakerConfig, _env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        Ok(vec![])
+    }
+
+    async fn post_hook(&self, _config: &MarketMakerConfig, _trades: Vec<Trade>, _identifier: String) {
+        self.published.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Fixed reference price, market context of the evaluated block and inventory, in place of the feed and the chain.
+struct FixedData;
+
+#[async_trait]
+impl MarketDataSource for FixedData {
+    fn name(&self) -> String {
+        "Fixed_Data".to_string()
+    }
+
+    async fn market_price(&self, _mk: &MarketMaker) -> Result<f64, String> {
+        Ok(REFERENCE)
+    }
+
+    async fn market_context(&self, _mk: &MarketMaker, _graph: &TokenGraph, _protosims: &HashMap<String, Box<dyn ProtocolSim>>, _tokens: Vec<Token>) -> Option<MarketContext> {
+        Some(context(REFERENCE, 1.0, BLOCK + 1))
+    }
+
+    async fn inventory(&self, _mk: &MarketMaker, _env: EnvConfig) -> Result<Inventory, String> {
+        Ok(Inventory {
+            base_balance: 10 * 10u128.pow(18),
+            quote_balance: 30_000 * 10u128.pow(6),
+            nonce: 0,
+            native_balance: 10u128.pow(18),
+        })
+    }
+}
+
+/// Default engine, the pool balances read from the mocked pools instead of the Tycho RPC.
+struct MockedBalances;
+
+#[async_trait]
+impl DecisionEngine for MockedBalances {
+    fn name(&self) -> String {
+        "Mocked_Balances".to_string()
+    }
+
+    async fn readjust(&self, mk: &MarketMaker, context: MarketContext, inventory: Inventory, adjustments: Vec<CompReadjustment>, _pool_balances: &dyn PoolBalances) -> Vec<ExecutionOrder> {
+        let targets = adjustments.iter().map(|adjustment| adjustment.psc.clone()).collect::<Vec<_>>();
+        mk.readjust(context, inventory, adjustments, &MockBalances::of(&targets)).await
+    }
+}
+
+/// Execution preparing one unencoded trade per order, the simulation and broadcast left to the execution strategy.
+struct Unencoded;
+
+#[async_trait]
+impl Execution for Unencoded {
+    fn name(&self) -> String {
+        "Unencoded".to_string()
+    }
+
+    fn prepare(&self, _mk: &MarketMaker, _orders: Vec<ExecutionOrder>, tdata: Vec<TradeData>, _context: MarketContext, _inventory: Inventory, _permits: &HashMap<String, SignedPermit>) -> Vec<Trade> {
+        tdata
+            .into_iter()
+            .map(|metadata| Trade {
+                wrap: None,
+                approve: None,
+                swap: TransactionRequest::default(),
+                unwrap: None,
+                metadata,
+            })
+            .collect()
+    }
+}
+
+fn config() -> MarketMakerConfig {
+    let mut config = common::config();
+    config.min_watch_spread_bps = 10.0;
+    config.min_executable_spread_bps = 0.0;
+    config.skew_gain_bps_per_pct = 0.0;
+    config.poll_interval_ms = 0;
+    config.balance_watch_interval_ms = 0;
+    config.snapshot_interval_ms = 0;
+    config.infinite_approval = true;
+    config
+}
+
+/// Market maker on the mocked data and execution, its broadcasts taking `delay_ms`.
+fn maker(delay_ms: u64) -> (MarketMaker, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let (started, published) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let exec = SlowExec {
+        delay: Duration::from_millis(delay_ms),
+        started: started.clone(),
+        published: published.clone(),
+    };
+    let config = config();
+    let mut mk = MarketMakerBuilder::new()
+        .config(config.clone())
+        .feed(PriceFeedFactory::create(&config.price_feed_config.r#type))
+        .execution(Box::new(exec))
+        .tokens(base(), quote())
+        .engine(Box::new(MockedBalances))
+        .market_data(Box::new(FixedData))
+        .executor(Box::new(Unencoded))
+        .build()
+        .expect("Market maker must build");
+    // Head of the chain never sampled: the first message counted as sampled, the next one u64::MAX messages away
+    mk.lag = StreamLag::new(0, u64::MAX);
+    mk.lag.due();
+    (mk, started, published)
+}
+
+/// Update of the stream listing the pool quoting 3030 against the 3000 reference.
+fn update(block: u64) -> SharedUpdate {
+    let psc = pool(POOL, 1_000., 3_030_000., 0.003);
+    SharedUpdate {
+        block_number_or_timestamp: block,
+        states: HashMap::from([(POOL.to_string(), psc.protosim)]),
+        new_pairs: HashMap::from([(POOL.to_string(), component(POOL, "uniswap_v2", vec![base(), quote()]))]),
+        removed_pairs: HashMap::new(),
+    }
+}
+
+/// Stream delivering the snapshot, then the block readjusting the pool, then waiting for the next block forever.
+fn stream() -> impl futures::Stream<Item = Result<SharedUpdate, String>> + Unpin {
+    futures::stream::iter(vec![Ok(update(BLOCK)), Ok(update(BLOCK + 1))]).chain(futures::stream::pending())
+}
+
+/// Requests a shutdown once `counter` is set.
+fn flip(shutdown: &Shutdown, counter: &Arc<AtomicUsize>) {
+    let (shutdown, counter) = (shutdown.clone(), counter.clone());
+    tokio::spawn(async move {
+        while counter.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        shutdown.request();
+    });
+}
+
+/// Stream of the market maker reported ready on its health.
+fn ready(mk: &MarketMaker) -> bool {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+    !mk.health.check(now, true).reasons.iter().any(|reason| reason == "stream not ready")
+}
+
+#[tokio::test]
+async fn test_flag_flip_mid_execution_completes_and_publishes() {
+    let (mut mk, started, published) = maker(200);
+    let shutdown = mk.shutdown.clone();
+    flip(&shutdown, &started); // Mid-broadcast of the first block readjusted
+
+    assert!(
+        drain(mk.consume(stream(), vec![], env()), &shutdown, Duration::from_secs(5)).await,
+        "Loop returned within the grace period"
+    );
+    assert_eq!(started.load(Ordering::SeqCst), 1, "No new block after the flag flipped");
+    assert_eq!(published.load(Ordering::SeqCst), 1, "The execution in progress was published");
+    assert!(!ready(&mk), "Closed on the way out");
+}
+
+#[tokio::test]
+async fn test_flag_flip_while_waiting_for_a_block() {
+    let (mut mk, started, published) = maker(10);
+    let shutdown = mk.shutdown.clone();
+    flip(&shutdown, &published); // Block readjusted, the stream waiting for the next one
+
+    assert!(drain(mk.consume(stream(), vec![], env()), &shutdown, Duration::from_secs(5)).await, "Woken by the request");
+    assert_eq!((started.load(Ordering::SeqCst), published.load(Ordering::SeqCst)), (1, 1));
+    assert!(!ready(&mk));
+}
+
+#[tokio::test]
+async fn test_grace_period_bounds_a_stuck_execution() {
+    let (mut mk, started, published) = maker(5_000);
+    let shutdown = mk.shutdown.clone();
+    flip(&shutdown, &started);
+
+    let start = std::time::Instant::now();
+    assert!(!drain(mk.consume(stream(), vec![], env()), &shutdown, Duration::from_millis(100)).await, "Grace period elapsed");
+    assert!(start.elapsed() < Duration::from_secs(4), "Not waiting for the broadcast");
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+    assert_eq!(published.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_requested_wakes_waiters_and_stays_set() {
+    let shutdown = Shutdown::default();
+    assert!(!shutdown.is_requested());
+    let waiter = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { shutdown.requested().await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    shutdown.request();
+    tokio::time::timeout(Duration::from_secs(1), waiter).await.expect("Waiter woken").expect("Waiter task");
+
+    // Already requested resolves immediately
+    tokio::time::timeout(Duration::from_millis(10), shutdown.requested()).await.expect("Flag stays set");
+}
+
+#[tokio::test]
+async fn test_drain_without_shutdown_runs_to_completion() {
+    let shutdown = Shutdown::default();
+    let done = Arc::new(AtomicUsize::new(0));
+    let once = async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        done.fetch_add(1, Ordering::SeqCst);
+    };
+    assert!(drain(once, &shutdown, Duration::ZERO).await);
+    assert_eq!(done.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,73 @@
+//! Wallet signer: SIGNER_TYPE parsing, validation of each mode, and the signer built from the environment.
+mod common;
+
+use std::str::FromStr;
+
+use shd::types::config::{EnvConfig, SignerType};
+use shd::utils::signer::WalletSigner;
+
+/// Address of the private key 0x…01.
+const KEY_ONE_ADDRESS: &str = "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf";
+
+fn env(signer_type: SignerType) -> EnvConfig {
+    EnvConfig {
+        tycho_api_key: "test_api_key".to_string(),
+        signer_type,
+        ..common::env()
+    }
+}
+
+#[test]
+fn test_signer_type_parsing() {
+    assert_eq!(SignerType::from_str("raw").unwrap(), SignerType::Raw);
+    assert_eq!(SignerType::from_str("keystore").unwrap(), SignerType::Keystore);
+    assert_eq!(SignerType::from_str("remote").unwrap(), SignerType::Remote);
+    assert!(SignerType::from_str("ledger").is_err());
+    assert_eq!(SignerType::default(), SignerType::Raw);
+}
+
+#[test]
+fn test_raw_signer() {
+    let mut env = env(SignerType::Raw);
+    assert!(env.validate().is_err(), "an empty raw key is rejected");
+    env.wallet_private_key = "0x0000000000000000000000000000000000000000000000000000000000000001".to_string();
+    env.validate().unwrap();
+    let wallet = WalletSigner::from_env(&env).unwrap();
+    assert_eq!(wallet.address().to_string(), KEY_ONE_ADDRESS);
+    env.wallet_private_key = "0x01".to_string();
+    assert!(WalletSigner::from_env(&env).is_err(), "a malformed key is an error, not a panic");
+}
+
+#[test]
+fn test_keystore_signer_validation() {
+    let mut env = env(SignerType::Keystore);
+    assert!(env.validate().is_err(), "the keystore path is required");
+    env.keystore_path = Some("config/secrets/missing.json".to_string());
+    env.keystore_password = Some("password".to_string());
+    assert!(env.validate().unwrap_err().to_string().contains("KEYSTORE_PATH"));
+
+    let path = std::env::temp_dir().join(format!("mkmk-keystore-{}.json", std::process::id()));
+    std::fs::write(&path, "{}").unwrap();
+    env.keystore_path = Some(path.to_string_lossy().to_string());
+    env.validate().unwrap();
+    env.keystore_password = None;
+    assert!(env.validate().unwrap_err().to_string().contains("KEYSTORE_PASSWORD"));
+
+    env.keystore_password = Some("password".to_string());
+    let err = WalletSigner::from_env(&env).unwrap_err();
+    assert!(err.contains("Failed to decrypt keystore"), "{}", err);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn test_remote_signer() {
+    let mut env = env(SignerType::Remote);
+    assert!(env.validate().is_err(), "the signer URL is required");
+    env.remote_signer_url = Some("http://127.0.0.1:8550".to_string());
+    assert!(env.validate().unwrap_err().to_string().contains("REMOTE_SIGNER_ADDRESS"));
+    env.remote_signer_address = Some(KEY_ONE_ADDRESS.to_lowercase());
+    env.validate().unwrap();
+    let wallet = WalletSigner::from_env(&env).unwrap();
+    assert_eq!(wallet.address().to_string(), KEY_ONE_ADDRESS);
+    assert!(matches!(wallet, WalletSigner::Remote(_)));
+}
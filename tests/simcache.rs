@@ -0,0 +1,193 @@
+//! Simulation cache: quotes of a pool memoized per block, token sold and amount bucket, only ever returned for the exact
+//! amount quoted, and cleared on each block message.
+mod common;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use common::{base, context, maker, pool, quote, MockBalances, MockProtocolSim, ScriptedSim};
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use shd::maker::simcache::{CachedSim, SimCache, SimCacheStats};
+use shd::opti::skew::InventorySkew;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::{ExecutionOrder, Inventory};
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const ETH: u128 = 1_000_000_000_000_000_000;
+
+fn sim() -> ScriptedSim {
+    ScriptedSim::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_030_000.0, 0.003), usize::MAX)
+}
+
+fn out(cache: &SimCache, sim: &ScriptedSim, amount: u128) -> BigUint {
+    cache.amount_out(POOL, sim, BigUint::from(amount), &base(), &quote()).unwrap().amount
+}
+
+proptest! {
+    #[test]
+    fn test_different_amounts_never_share_a_bucket(amount in 1_000_000u128..10u128.pow(30), digits in 1u32..=12, excess in 1.5f64..10.0) {
+        // More than one unit of the last significant digit apart
+        let a = amount as f64;
+        let b = a * (1.0 + excess * 10f64.powi(1 - digits as i32));
+        let (a, b) = (BigUint::from(a as u128), BigUint::from(b as u128));
+        prop_assert_ne!(SimCache::bucket(&a, digits), SimCache::bucket(&b, digits));
+    }
+}
+
+#[test]
+fn test_bucket_of_nearby_amounts() {
+    assert_eq!(SimCache::bucket(&BigUint::from(1_234_400u128), 4), SimCache::bucket(&BigUint::from(1_234_049u128), 4));
+    assert_ne!(SimCache::bucket(&BigUint::from(1_234_400u128), 4), SimCache::bucket(&BigUint::from(1_235_400u128), 4));
+    assert_ne!(
+        SimCache::bucket(&BigUint::from(1_234_400u128), 4),
+        SimCache::bucket(&BigUint::from(12_344_000u128), 4),
+        "Other magnitude"
+    );
+}
+
+#[test]
+fn test_hits_on_the_exact_amount_only() {
+    let sim = sim();
+    let cache = SimCache::new(6);
+    cache.roll(100);
+    let exact = sim.inner.get_amount_out(BigUint::from(ETH), &base(), &quote()).unwrap().amount;
+    assert_eq!(out(&cache, &sim, ETH), exact);
+    assert_eq!(out(&cache, &sim, ETH), exact, "Same amount, same quote");
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 1);
+
+    // Same bucket, other amount: quoted again, never extrapolated
+    let nearby = ETH + ETH / 10_000_000;
+    let quoted = sim.inner.get_amount_out(BigUint::from(nearby), &base(), &quote()).unwrap().amount;
+    assert_eq!(out(&cache, &sim, nearby), quoted);
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(out(&cache, &sim, nearby), quoted, "Replaced the amount of its bucket");
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(cache.stats(), SimCacheStats { hits: 2, misses: 2 });
+
+    // Other bucket, other token sold, other component
+    out(&cache, &sim, ETH + ETH / 10_000);
+    cache.amount_out(POOL, &sim, BigUint::from(3_000_000_000u128), &quote(), &base()).unwrap();
+    cache.amount_out("0xbbbb000000000000000000000000000000000002", &sim, BigUint::from(ETH), &base(), &quote()).unwrap();
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 5);
+    assert!((cache.stats().hit_rate() - 2. / 7.).abs() < 1e-12);
+}
+
+#[test]
+fn test_coarse_buckets_return_the_quote_of_the_amount() {
+    // One significant digit: 1.0 to 1.4 ETH share a bucket
+    let sim = sim();
+    let cache = SimCache::new(1);
+    cache.roll(100);
+    for amount in [ETH, ETH + ETH / 10, ETH + ETH / 4, ETH + ETH / 10, ETH] {
+        let cached = cache.amount_out(POOL, &sim, BigUint::from(amount), &base(), &quote()).unwrap();
+        let quoted = sim.inner.get_amount_out(BigUint::from(amount), &base(), &quote()).unwrap();
+        assert_eq!(cached.amount, quoted.amount, "{}", amount);
+        assert_eq!(
+            cached.new_state.spot_price(&base(), &quote()).unwrap(),
+            quoted.new_state.spot_price(&base(), &quote()).unwrap(),
+            "Post-swap state of the amount"
+        );
+    }
+    assert_eq!(cache.stats().hits, 0, "Each amount after another one of its bucket");
+}
+
+#[test]
+fn test_cleared_on_block_message() {
+    let sim = sim();
+    let cache = SimCache::new(6);
+    cache.roll(100);
+    out(&cache, &sim, ETH);
+    cache.roll(101);
+    out(&cache, &sim, ETH);
+    // A second message of the same block may carry other states
+    cache.roll(101);
+    out(&cache, &sim, ETH);
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 3);
+    assert_eq!(cache.stats().hits, 0);
+}
+
+#[test]
+fn test_failures_not_cached_and_disabled_cache() {
+    let failing = ScriptedSim::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_030_000.0, 0.003), 0);
+    let cache = SimCache::new(6);
+    assert!(cache.amount_out(POOL, &failing, BigUint::from(ETH), &base(), &quote()).is_err());
+    assert!(cache.amount_out(POOL, &failing, BigUint::from(ETH), &base(), &quote()).is_err());
+    assert_eq!(failing.calls.load(Ordering::SeqCst), 2);
+
+    let sim = sim();
+    let disabled = SimCache::new(0);
+    assert!(!disabled.enabled());
+    out(&disabled, &sim, ETH);
+    out(&disabled, &sim, ETH);
+    assert_eq!(sim.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(disabled.stats(), SimCacheStats::default());
+}
+
+#[test]
+fn test_cached_sim_quotes_through_the_cache() {
+    let sim = sim();
+    let cache = Arc::new(SimCache::new(6));
+    let cached = CachedSim::new(POOL, &sim, cache.clone());
+    let first = cached.get_amount_out(BigUint::from(ETH), &base(), &quote()).unwrap();
+    let second = cached.get_amount_out(BigUint::from(ETH), &base(), &quote()).unwrap();
+    assert_eq!(first.amount, second.amount);
+    assert!(first.new_state.eq(second.new_state.as_ref()), "Same post-swap state");
+    assert_eq!(cache.stats(), SimCacheStats { hits: 1, misses: 1 });
+    assert_eq!(cached.spot_price(&base(), &quote()).unwrap(), sim.spot_price(&base(), &quote()).unwrap());
+}
+
+fn config(digits: u32) -> MarketMakerConfig {
+    let mut config = common::config();
+    config.min_watch_spread_bps = 10.0;
+    config.min_executable_spread_bps = 0.0;
+    config.sim_cache_significant_digits = digits;
+    config
+}
+
+async fn order(digits: u32) -> (ExecutionOrder, SimCacheStats) {
+    let mk = maker(config(digits));
+    mk.sim_cache.roll(100);
+    let targets = vec![pool(POOL, 1_000., 3_030_000., 0.003)];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let adjustments = mk.evaluate(&targets, vec![3_030.], 3_000., &skew, 100);
+    let inventory = Inventory {
+        base_balance: 10 * ETH,
+        quote_balance: 30_000 * 10u128.pow(6),
+        nonce: 0,
+        native_balance: 0,
+    };
+    let orders = mk.readjust(context(3_000., 1.0, 100), inventory, adjustments, &MockBalances::of(&targets)).await;
+    assert_eq!(orders.len(), 1, "Profitable rebalance");
+    // The final quote and the re-validation are made on the pool itself
+    let calculation = &orders[0].calculation;
+    let quoted = targets[0].protosim.get_amount_out(calculation.amount_in_raw.clone(), &base(), &quote()).unwrap().amount;
+    assert_eq!(calculation.amount_out_raw, quoted, "Final quote of the exact amount sent");
+    let protosims = std::collections::HashMap::from([(POOL.to_string(), pool(POOL, 1_000., 3_030_000., 0.003).protosim)]);
+    let before = mk.sim_cache.stats();
+    assert!(mk.staleness(&orders[0], &protosims, 100, 100).is_none());
+    assert_eq!(mk.sim_cache.stats(), before, "Re-validation without the cache");
+    (orders[0].clone(), mk.sim_cache.stats())
+}
+
+#[tokio::test]
+async fn test_readjust_with_the_cache() {
+    let (uncached, stats) = order(0).await;
+    assert_eq!(stats, SimCacheStats::default());
+    let (cached, stats) = order(6).await;
+    assert!(stats.misses > 0 && stats.hits > 0, "{:?}", stats);
+    let (a, b) = (&uncached.calculation, &cached.calculation);
+    assert!((a.selling_amount - b.selling_amount).abs() / a.selling_amount < 1e-4, "{} vs {}", a.selling_amount, b.selling_amount);
+    assert!((a.profit_delta_bps - b.profit_delta_bps).abs() < 0.01, "{} vs {}", a.profit_delta_bps, b.profit_delta_bps);
+}
+
+#[tokio::test]
+async fn test_readjust_with_coarse_buckets() {
+    let (uncached, _) = order(0).await;
+    let (coarse, stats) = order(1).await;
+    assert!(stats.misses > 0, "{:?}", stats);
+    let (a, b) = (&uncached.calculation, &coarse.calculation);
+    assert!((a.selling_amount - b.selling_amount).abs() / a.selling_amount < 1e-4, "{} vs {}", a.selling_amount, b.selling_amount);
+    assert!((a.profit_delta_bps - b.profit_delta_bps).abs() < 0.01, "{} vs {}", a.profit_delta_bps, b.profit_delta_bps);
+}
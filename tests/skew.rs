@@ -0,0 +1,97 @@
+mod common;
+
+use common::{base, quote};
+use shd::opti::skew::{compute, deficit};
+use shd::types::maker::{Inventory, MarketContext};
+
+const THRESHOLD_BPS: f64 = 5.0;
+const GAIN: f64 = 0.1; // 0.1 bps per percentage point
+
+fn context() -> MarketContext {
+    MarketContext {
+        base_to_eth: 1.0,
+        quote_to_eth: 1.0 / 3_000.0,
+        eth_to_usd: 3_000.0,
+        max_fee_per_gas: 0,
+        max_priority_fee_per_gas: 0,
+        native_gas_price: 0,
+        block: 0,
+        age_ms: 0,
+    }
+}
+
+/// Inventory holding `base_eth` ETH and `quote_usdc` USDC.
+fn inventory(base_eth: f64, quote_usdc: f64) -> Inventory {
+    Inventory {
+        base_balance: (base_eth * 1e18) as u128,
+        quote_balance: (quote_usdc * 1e6) as u128,
+        nonce: 0,
+        native_balance: 0,
+    }
+}
+
+#[test]
+fn test_skew_balanced_inventory_is_symmetric() {
+    let skew = compute(&inventory(10.0, 30_000.0), &base(), &quote(), &context(), 0.5, GAIN, THRESHOLD_BPS);
+    assert!((skew.base_ratio - 0.5).abs() < 1e-9);
+    assert!(skew.skew_pct.abs() < 1e-6);
+    assert!((skew.buy_threshold_bps - THRESHOLD_BPS).abs() < 1e-6);
+    assert!((skew.sell_threshold_bps - THRESHOLD_BPS).abs() < 1e-6);
+}
+
+#[test]
+fn test_skew_all_base_favors_selling_base() {
+    let skew = compute(&inventory(10.0, 0.0), &base(), &quote(), &context(), 0.5, GAIN, THRESHOLD_BPS);
+    assert!((skew.base_ratio - 1.0).abs() < 1e-9);
+    assert!((skew.skew_pct - 50.0).abs() < 1e-6);
+    // 50 pct * 0.1 bps = 5 bps shift
+    assert!(skew.buy_threshold_bps.abs() < 1e-6, "Selling base should be easier: {:?}", skew);
+    assert!((skew.sell_threshold_bps - 10.0).abs() < 1e-6, "Selling quote should be harder: {:?}", skew);
+}
+
+#[test]
+fn test_skew_all_quote_favors_selling_quote() {
+    let skew = compute(&inventory(0.0, 30_000.0), &base(), &quote(), &context(), 0.5, GAIN, THRESHOLD_BPS);
+    assert!(skew.base_ratio.abs() < 1e-9);
+    assert!((skew.skew_pct + 50.0).abs() < 1e-6);
+    assert!((skew.buy_threshold_bps - 10.0).abs() < 1e-6, "Selling base should be harder: {:?}", skew);
+    assert!(skew.sell_threshold_bps.abs() < 1e-6, "Selling quote should be easier: {:?}", skew);
+}
+
+#[test]
+fn test_skew_thresholds_never_negative() {
+    let skew = compute(&inventory(10.0, 0.0), &base(), &quote(), &context(), 0.5, 1.0, THRESHOLD_BPS);
+    assert_eq!(skew.buy_threshold_bps, 0.0);
+    assert!((skew.sell_threshold_bps - 55.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_skew_empty_inventory_is_neutral() {
+    let skew = compute(&inventory(0.0, 0.0), &base(), &quote(), &context(), 0.5, GAIN, THRESHOLD_BPS);
+    assert_eq!(skew.skew_pct, 0.0);
+    assert_eq!(skew.buy_threshold_bps, THRESHOLD_BPS);
+    assert_eq!(skew.sell_threshold_bps, THRESHOLD_BPS);
+}
+
+#[test]
+fn test_deficit_of_depleted_token() {
+    // 5 ETH and 15 ETH worth of USDC: base share 25% vs 50% target
+    let inventory = inventory(5.0, 45_000.0);
+    let missing = deficit(&inventory, &base(), &quote(), &context(), 0.5, true).expect("Base is below target");
+    assert!((missing - 5.0).abs() < 1e-9, "missing {} ETH", missing);
+    assert_eq!(deficit(&inventory, &base(), &quote(), &context(), 0.5, false), None, "Quote is above target");
+}
+
+#[test]
+fn test_deficit_of_quote_uses_complementary_target() {
+    // 15 ETH and 5 ETH worth of USDC, target 60% base => quote target 40% of 20 ETH = 8 ETH = 24k USDC
+    let inventory = inventory(15.0, 15_000.0);
+    let missing = deficit(&inventory, &base(), &quote(), &context(), 0.6, false).expect("Quote is below target");
+    assert!((missing - 9_000.0).abs() < 1e-6, "missing {} USDC", missing);
+}
+
+#[test]
+fn test_deficit_none_when_balanced_or_empty() {
+    assert_eq!(deficit(&inventory(10.0, 30_000.0), &base(), &quote(), &context(), 0.5, true), None);
+    assert_eq!(deficit(&inventory(0.0, 0.0), &base(), &quote(), &context(), 0.5, true), None);
+}
@@ -0,0 +1,160 @@
+//! Target pools snapshot: written on an interval, read back after a restart and evaluated monitor-only until the stream
+//! is ready.
+mod common;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use common::{maker, pool};
+use shd::maker::snapshot::{evaluate, load, now_s, path, save, PoolSnapshot, SnapshotPool, SnapshotStore};
+use shd::opti::adaptive::AdaptiveState;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::TradeDirection;
+use shd::types::moni::NewPricesMessage;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+
+/// Reference config with its cache in a temporary directory.
+fn config(name: &str) -> MarketMakerConfig {
+    let mut config = common::config();
+    config.token_cache_dir = temporary(name).to_string_lossy().to_string();
+    config.min_watch_spread_bps = 10.0;
+    config.add_pool_fee_to_spread = false;
+    config
+}
+
+fn temporary(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mkmk-snapshot-{}-{}", name, std::process::id()))
+}
+
+fn snapshot(config: &MarketMakerConfig, saved_at_s: u64, prices: &[f64]) -> PoolSnapshot {
+    PoolSnapshot {
+        id: config.id(),
+        block: 100,
+        saved_at_s,
+        reference_price: 3_000.,
+        pools: prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| SnapshotPool {
+                address: format!("0xaaaa00000000000000000000000000000000000{}", i + 1),
+                r#type: "uniswap_v3".to_string(),
+                fee_bps: 5,
+                price: *price,
+            })
+            .collect(),
+        adaptive: None,
+    }
+}
+
+#[test]
+fn test_snapshot_file_round_trip() {
+    let config = config("round-trip");
+    let file = path(&config);
+    assert!(file.ends_with(format!("snapshot.{}.json", config.id())));
+    assert!(load(&file, &config.id()).is_none(), "No file yet");
+
+    let written = snapshot(&config, 1_700_000_000, &[3_030., 2_990.]);
+    save(&file, &written).expect("Cache directory created");
+    assert_eq!(load(&file, &config.id()), Some(written.clone()));
+    assert!(load(&file, "mmc-base-eth-usdc-0x0000000").is_none(), "Pools of another pair");
+
+    // Written before the adaptive state was persisted
+    let mut legacy = serde_json::to_value(&written).unwrap();
+    legacy.as_object_mut().unwrap().remove("adaptive");
+    std::fs::write(&file, legacy.to_string()).unwrap();
+    assert_eq!(load(&file, &config.id()), Some(written.clone()));
+
+    for contents in ["", "{\"id\": 1", "[]"] {
+        std::fs::write(&file, contents).unwrap();
+        assert!(load(&file, &config.id()).is_none(), "{:?}", contents);
+    }
+    std::fs::remove_dir_all(temporary("round-trip")).ok();
+}
+
+#[test]
+fn test_store_writes_on_interval_and_restores_fresh_snapshots() {
+    let mut config = config("store");
+    config.snapshot_interval_ms = 60_000;
+    config.snapshot_max_age_s = 600;
+    let mut store = SnapshotStore::new(&config);
+    let start = Instant::now();
+    assert!(store.due(start), "Nothing written yet");
+
+    // Built from the priced targets of a block
+    let mk = maker(config.clone());
+    let targets = vec![pool(POOL, 1_000., 3_030_000., 0.003)];
+    let cpds = mk.prices(&targets, 3_000.);
+    store.record(PoolSnapshot::new(&config, 100, 3_000., &targets, &cpds), start);
+    store.flush();
+    assert!(!store.due(start + Duration::from_secs(59)));
+    assert!(store.due(start + Duration::from_secs(60)));
+    let latest = store.latest().unwrap();
+    assert_eq!((latest.block, latest.pools.len()), (100, 1));
+    assert!((latest.pools[0].price - 3_030.).abs() < 1e-9);
+    assert_eq!(latest.prices()[0].address, POOL);
+
+    // Next process: restored while younger than the max age
+    assert_eq!(SnapshotStore::new(&config).restore(now_s()).map(|s| s.block), Some(100));
+    assert!(SnapshotStore::new(&config).restore(now_s() + 600).is_none(), "Too old");
+
+    // Disabled: neither written nor restored
+    config.snapshot_interval_ms = 0;
+    let mut disabled = SnapshotStore::new(&config);
+    assert!(!disabled.due(start));
+    assert!(disabled.restore(now_s()).is_none());
+    std::fs::remove_dir_all(temporary("store")).ok();
+}
+
+#[test]
+fn test_monitor_only_signals() {
+    let config = config("signals");
+    let snapshot = snapshot(&config, now_s(), &[3_030., 3_001., 2_970., 30_000.]);
+    let signals = evaluate(&config, &snapshot, 3_000.);
+    let directions = signals.iter().map(|s| s.direction.clone()).collect::<Vec<Option<TradeDirection>>>();
+    assert_eq!(directions, vec![Some(TradeDirection::Buy), None, Some(TradeDirection::Sell), None], "Out of the sanity band: no signal");
+    assert!((signals[0].spread_bps - 100.).abs() < 1e-9);
+    assert_eq!(signals[0].threshold_bps, 10.);
+    assert!(evaluate(&config, &snapshot, 0.).is_empty(), "No reference");
+}
+
+#[test]
+fn test_prices_without_the_degraded_flag_are_live() {
+    let msg: NewPricesMessage = serde_json::from_value(serde_json::json!({
+        "identifier": "mmc-ethereum-eth-usdc-0x0af694-instance-1",
+        "reference_price": 3_000.,
+        "components": [],
+        "block": 100,
+    }))
+    .unwrap();
+    assert!(!msg.degraded);
+}
+
+#[test]
+fn test_adaptive_threshold_resumed_from_the_snapshot() {
+    let mut config = config("adaptive");
+    config.vol_multiplier = 0.0;
+    config.adaptive_threshold = true;
+    config.adaptive_threshold_max_bps = 4.0;
+    let floor = config.min_executable_spread_bps;
+    assert_eq!(maker(config.clone()).execution_threshold_bps(), floor, "No snapshot yet");
+
+    // Older than the max age: the pools are not restored, the rolling shortfall is
+    let mut written = snapshot(&config, 0, &[3_030.]);
+    written.adaptive = Some(AdaptiveState {
+        trades: 50,
+        shortfall_bps: Some(6.0),
+        widening_bps: 6.0,
+    });
+    save(&path(&config), &written).unwrap();
+    let mk = maker(config.clone());
+    assert!(
+        (mk.execution_threshold_bps() - (floor + 4.0)).abs() < 1e-9,
+        "Capped by the current config: {}",
+        mk.execution_threshold_bps()
+    );
+
+    config.adaptive_threshold = false;
+    assert_eq!(maker(config).execution_threshold_bps(), floor, "Adaptive mode off");
+    std::fs::remove_dir_all(temporary("adaptive")).ok();
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{base, component, quote};
+use shd::maker::tycho::amm_fee_to_bps;
+use shd::opti::skew::InventorySkew;
+use shd::types::config::{load_market_maker_config, MarketMakerConfig};
+use tycho_simulation::tycho_common::Bytes;
+
+fn config() -> MarketMakerConfig {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    config.min_watch_spread_bps = 5.0;
+    config.spread_overrides = HashMap::from([("uniswap_v2".to_string(), 45.0), ("uniswap_v3".to_string(), 12.0)]);
+    config
+}
+
+/// True if `spread_bps` on a pool would be traded, with symmetric thresholds.
+fn triggers(config: &MarketMakerConfig, protocol_system: &str, fee_bps: u128, spread_bps: f64) -> bool {
+    let skew = InventorySkew::neutral(config.min_watch_spread_bps, 0.5);
+    let threshold = skew.threshold_bps(spread_bps, config.watch_spread_bps(protocol_system, fee_bps) - config.min_watch_spread_bps);
+    spread_bps.abs() > threshold
+}
+
+#[test]
+fn test_threshold_per_protocol_system() {
+    let config = config();
+    assert_eq!(config.watch_spread_bps("uniswap_v2", 30), 45.0);
+    assert_eq!(config.watch_spread_bps("uniswap_v3", 5), 12.0);
+    assert_eq!(config.watch_spread_bps("uniswap_v4", 5), 5.0, "No override falls back to min_watch_spread_bps");
+
+    assert!(triggers(&config, "uniswap_v3", 5, 20.0));
+    assert!(!triggers(&config, "uniswap_v2", 30, 20.0), "Same spread below the v2 override");
+    assert!(triggers(&config, "uniswap_v4", 5, -6.0));
+}
+
+#[test]
+fn test_pool_fee_flips_boundary_decision() {
+    let mut config = config();
+    assert!(triggers(&config, "uniswap_v3", 5, 15.0));
+
+    config.add_pool_fee_to_spread = true;
+    assert_eq!(config.watch_spread_bps("uniswap_v3", 5), 17.0);
+    assert!(!triggers(&config, "uniswap_v3", 5, 15.0), "15 bps no longer covers 12 bps plus the 5 bps fee");
+    assert!(triggers(&config, "uniswap_v3", 5, -17.5));
+}
+
+#[test]
+fn test_offset_applies_on_top_of_skew() {
+    let skew = InventorySkew {
+        base_ratio: 0.6,
+        skew_pct: 10.0,
+        buy_threshold_bps: 4.0,
+        sell_threshold_bps: 6.0,
+    };
+    assert_eq!(skew.threshold_bps(10.0, 7.0), 11.0, "Positive spread uses the buy threshold");
+    assert_eq!(skew.threshold_bps(-10.0, 7.0), 13.0);
+    assert_eq!(skew.threshold_bps(10.0, -5.0), 0.0, "Never below 0");
+}
+
+#[test]
+fn test_fee_from_component_attributes() {
+    let mut cp = component("0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640", "uniswap_v3", vec![base(), quote()]);
+    cp.static_attributes.insert("fee".to_string(), Bytes::from(vec![0x01, 0xf4])); // 500 = 0.05%
+    assert_eq!(amm_fee_to_bps(cp.clone()), 5);
+    assert_eq!(config().watch_spread_bps(&cp.protocol_system, amm_fee_to_bps(cp)), 12.0);
+}
+
+#[test]
+fn test_overrides_are_validated() {
+    let mut config = config();
+    assert!(config.validate().is_ok());
+    config.spread_overrides.insert("uniswap_v4".to_string(), -1.0);
+    let err = config.validate().expect_err("Negative override").to_string();
+    assert!(err.contains("uniswap_v4"), "{}", err);
+}
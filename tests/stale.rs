@@ -0,0 +1,164 @@
+//! Execution-time re-validation: an order requoted on the latest state, or evaluated too many blocks behind the chain
+//! head, is aborted as `StalePrice` instead of executed.
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::rpc::types::TransactionRequest;
+use async_trait::async_trait;
+use common::{base, component, context, inventory, maker, pool, quote, watch_config, MockBalances};
+use shd::maker::exec::ExecStrategy;
+use shd::maker::pools::StreamPools;
+use shd::opti::skew::InventorySkew;
+use shd::types::config::MarketMakerConfig;
+use shd::types::maker::{ExecutionOrder, MarketMaker, Trade, TradeData, TradeStatus};
+use shd::types::tycho::SharedUpdate;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const REFERENCE: f64 = 3_000.0;
+const BLOCK: u64 = 100;
+
+/// Strategy recording the trades published.
+struct Publisher(Arc<Mutex<Vec<(String, TradeStatus)>>>);
+
+#[async_trait]
+impl ExecStrategy for Publisher {
+    fn name(&self) -> String {
+        "Publisher".to_string()
+    }
+
+    async fn post_hook(&self, _config: &MarketMakerConfig, trades: Vec<Trade>, _identifier: String) {
+        self.0.lock().unwrap().extend(trades.into_iter().map(|trade| (trade.metadata.trade_id, trade.metadata.status)));
+    }
+}
+
+fn config() -> MarketMakerConfig {
+    let mut config = watch_config();
+    config.min_executable_spread_bps = 0.0;
+    config.stale_block_tolerance = 2;
+    config
+}
+
+/// Order selling ETH into a pool quoting 3030 against a 3000 reference.
+async fn order(mk: &MarketMaker) -> ExecutionOrder {
+    let targets = vec![pool(POOL, 1_000., 3_030_000., 0.003)];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let adjustments = mk.evaluate(&targets, vec![3_030.], REFERENCE, &skew, BLOCK);
+    let orders = mk.readjust(context(REFERENCE, 1.0, BLOCK), inventory(), adjustments, &MockBalances::of(&targets)).await;
+    assert_eq!(orders.len(), 1, "Profitable rebalance");
+    orders[0].clone()
+}
+
+/// Protosims of the stream with the pool quoting `quote_reserve` USDC for 1000 ETH.
+fn protosims(quote_reserve: f64) -> HashMap<String, Box<dyn ProtocolSim>> {
+    HashMap::from([(POOL.to_string(), pool(POOL, 1_000., quote_reserve, 0.003).protosim)])
+}
+
+fn trade(order: &ExecutionOrder) -> Trade {
+    Trade {
+        wrap: None,
+        approve: None,
+        swap: TransactionRequest::default(),
+        unwrap: None,
+        metadata: TradeData {
+            trade_id: order.trade_id.clone(),
+            status: TradeStatus::Pending,
+            timestamp: 0,
+            context: context(REFERENCE, 1.0, BLOCK),
+            metadata: maker(config()).pre_trade_data(order),
+            inventory: inventory(),
+            simulation: None,
+            broadcast: None,
+            realized: None,
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_requoted_profit_and_head_distance() {
+    let mk = maker(config());
+    let order = order(&mk).await;
+    let profit = MarketMaker::requote_profit_bps(&order, protosims(3_030_000.)[POOL].as_ref()).unwrap();
+    assert!(
+        (profit - order.calculation.profit_delta_bps).abs() < 1e-6,
+        "Same state, same profit: {} vs {}",
+        profit,
+        order.calculation.profit_delta_bps
+    );
+
+    assert!(mk.staleness(&order, &protosims(3_030_000.), BLOCK, BLOCK + 2).is_none(), "Within the tolerance");
+    let (reason, behind) = mk.staleness(&order, &protosims(3_030_000.), BLOCK, BLOCK + 3).expect("Head too far ahead");
+    assert!(reason.contains("behind"), "{}", reason);
+    assert_eq!(behind, 3.);
+
+    // Arbed back to the reference meanwhile
+    let (reason, profit) = mk.staleness(&order, &protosims(3_000_000.), BLOCK, BLOCK).expect("Profit gone");
+    assert!(profit < 0., "{}", reason);
+    assert!(mk.staleness(&order, &HashMap::new(), BLOCK, BLOCK).is_some(), "Component gone");
+}
+
+#[tokio::test]
+async fn test_stale_trade_published_and_not_executed() {
+    let published = Arc::new(Mutex::new(vec![]));
+    let mut mk = maker(config());
+    mk.execution = Box::new(Publisher(published.clone()));
+    let order = order(&mk).await;
+
+    let (trades, orders) = mk.drop_stale(vec![trade(&order)], vec![order.clone()], &protosims(3_030_000.), BLOCK, BLOCK).await;
+    assert_eq!((trades.len(), orders.len()), (1, 1));
+    assert!(published.lock().unwrap().is_empty());
+
+    let (trades, orders) = mk.drop_stale(vec![trade(&order)], vec![order.clone()], &protosims(3_000_000.), BLOCK, BLOCK + 1).await;
+    assert!(trades.is_empty() && orders.is_empty());
+    assert_eq!(*published.lock().unwrap(), vec![(order.trade_id.clone(), TradeStatus::StalePrice)]);
+}
+
+/// Update of the stream moving the pool to `quote_reserve` USDC for 1000 ETH.
+fn update(block: u64, quote_reserve: f64) -> SharedUpdate {
+    SharedUpdate {
+        block_number_or_timestamp: block,
+        states: protosims(quote_reserve),
+        new_pairs: HashMap::new(),
+        removed_pairs: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_requoted_on_the_updates_received_since() {
+    let published = Arc::new(Mutex::new(vec![]));
+    let mut mk = maker(config());
+    mk.execution = Box::new(Publisher(published.clone()));
+    let order = order(&mk).await;
+    let pools = StreamPools {
+        protosims: protosims(3_030_000.),
+        ..Default::default()
+    };
+    let ids = vec![POOL.to_string()];
+    assert!(mk.staleness(&order, &pools.latest(&ids, &[]), BLOCK, BLOCK).is_none(), "No update since the evaluation");
+
+    // Arbed back to the reference by the next block, received during the preparation
+    let arbed = update(BLOCK + 1, 3_000_000.);
+    let latest = pools.latest(&ids, &[&arbed]);
+    let (trades, _) = mk.drop_stale(vec![trade(&order)], vec![order.clone()], &latest, BLOCK + 1, BLOCK + 1).await;
+    assert!(trades.is_empty(), "Requoted on the update, not on the evaluated state");
+    assert_eq!(*published.lock().unwrap(), vec![(order.trade_id.clone(), TradeStatus::StalePrice)]);
+
+    // Removed by a later update
+    let mut removed = update(BLOCK + 2, 3_030_000.);
+    removed.states.clear();
+    removed.removed_pairs.insert(POOL.to_string(), component(POOL, "uniswap_v2", vec![base(), quote()]));
+    assert!(pools.latest(&ids, &[&arbed, &removed]).is_empty());
+}
+
+#[tokio::test]
+async fn test_trade_without_order_published() {
+    let published = Arc::new(Mutex::new(vec![]));
+    let mut mk = maker(config());
+    mk.execution = Box::new(Publisher(published.clone()));
+    let order = order(&mk).await;
+    let (trades, orders) = mk.drop_stale(vec![trade(&order)], vec![], &protosims(3_030_000.), BLOCK, BLOCK).await;
+    assert!(trades.is_empty() && orders.is_empty());
+    assert_eq!(*published.lock().unwrap(), vec![(order.trade_id.clone(), TradeStatus::StalePrice)], "Not dropped silently");
+}
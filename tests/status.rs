@@ -0,0 +1,230 @@
+//! Trade statuses set by the default execution, one per trade from its own simulation and broadcast outcome.
+mod common;
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use common::{env, trade};
+use shd::error::ExecError;
+use shd::maker::exec::ExecStrategy;
+use shd::types::config::{EnvConfig, MarketMakerConfig};
+use shd::types::maker::{BroadcastData, SimulatedData, Trade, TradeStatus};
+
+/// Strategy returning canned simulation and broadcast results, or the broadcast `failure`, recording the broadcast
+/// calls, the trades broadcast and the trades published with their broadcast error.
+#[derive(Default)]
+struct StubExec {
+    simulations: Vec<SimulatedData>,
+    broadcasts: Vec<BroadcastData>,
+    failure: Option<ExecError>,
+    broadcast: Mutex<Vec<String>>,
+    calls: Mutex<usize>,
+    published: Mutex<Vec<(String, TradeStatus)>>,
+    errors: Mutex<Vec<Option<ExecError>>>,
+}
+
+#[async_trait]
+impl ExecStrategy for StubExec {
+    fn name(&self) -> String {
+        "Stub_Strategy".to_string()
+    }
+
+    async fn simulate(&self, _config: MarketMakerConfig, _trades: Vec<Trade>, _env: EnvConfig) -> Result<Vec<SimulatedData>, ExecError> {
+        Ok(self.simulations.clone())
+    }
+
+    async fn broadcast(&self, prepared: Vec<Trade>, _mmc: MarketMakerConfig, _env: EnvConfig) -> Result<Vec<BroadcastData>, ExecError> {
+        *self.calls.lock().unwrap() += 1;
+        self.broadcast.lock().unwrap().extend(prepared.iter().map(|trade| trade.metadata.trade_id.clone()));
+        match &self.failure {
+            Some(e) => Err(e.clone()),
+            None => Ok(self.broadcasts.clone()),
+        }
+    }
+
+    async fn post_hook(&self, _config: &MarketMakerConfig, trades: Vec<Trade>, _identifier: String) {
+        self.errors
+            .lock()
+            .unwrap()
+            .extend(trades.iter().map(|trade| trade.metadata.broadcast.as_ref().and_then(|bd| bd.error())));
+        self.published.lock().unwrap().extend(trades.into_iter().map(|trade| (trade.metadata.trade_id, trade.metadata.status)));
+    }
+}
+
+fn config(skip_simulation: bool) -> MarketMakerConfig {
+    MarketMakerConfig { skip_simulation, ..common::config() }
+}
+
+fn sent(trade_id: &str, hash: &str) -> BroadcastData {
+    BroadcastData {
+        trade_id: trade_id.to_string(),
+        hash: hash.to_string(),
+        ..Default::default()
+    }
+}
+
+fn failed(trade_id: &str, error: &str) -> BroadcastData {
+    BroadcastData {
+        trade_id: trade_id.to_string(),
+        broadcast_error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn simulated(trade_id: &str, status: bool) -> SimulatedData {
+    SimulatedData {
+        trade_id: trade_id.to_string(),
+        status,
+        error: (!status).then(|| "execution reverted".to_string()),
+        ..Default::default()
+    }
+}
+
+fn statuses(trades: &[Trade]) -> Vec<TradeStatus> {
+    trades.iter().map(|trade| trade.metadata.status.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_status_follows_each_broadcast_outcome() {
+    let exec = StubExec {
+        broadcasts: vec![
+            sent("a", "0xabc"),
+            failed("b", "Failed to send swap transaction: nonce too low"),
+            BroadcastData {
+                trade_id: "c".to_string(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(true), vec![trade("a"), trade("b"), trade("c")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(
+        statuses(&trades),
+        vec![TradeStatus::BroadcastSucceeded, TradeStatus::BroadcastFailed, TradeStatus::BroadcastFailed],
+        "No hash: not sent"
+    );
+    assert_eq!(trades[0].metadata.broadcast.as_ref().unwrap().hash, "0xabc");
+    let published = exec.published.lock().unwrap().clone();
+    assert_eq!(published[1], ("b".to_string(), TradeStatus::BroadcastFailed));
+}
+
+#[tokio::test]
+async fn test_failed_simulation_not_broadcast() {
+    let exec = StubExec {
+        simulations: vec![simulated("a", false), simulated("b", true)],
+        broadcasts: vec![sent("b", "0xdef")],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(false), vec![trade("a"), trade("b")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(exec.broadcast.lock().unwrap().clone(), vec!["b".to_string()]);
+    assert_eq!(statuses(&trades), vec![TradeStatus::SimulationFailed, TradeStatus::BroadcastSucceeded]);
+    assert!(trades[0].metadata.broadcast.is_none());
+    assert_eq!(trades[1].metadata.broadcast.as_ref().unwrap().hash, "0xdef", "Result of the broadcast trade, not of the first one");
+    assert_eq!(exec.published.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_testing_mode_and_mismatched_results_never_panic() {
+    // Testing mode broadcasts nothing: the trades keep their simulation status
+    let exec = StubExec {
+        simulations: vec![simulated("a", true), simulated("b", true)],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(false), vec![trade("a"), trade("b")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(statuses(&trades), vec![TradeStatus::SimulationSucceeded, TradeStatus::SimulationSucceeded]);
+    assert!(trades.iter().all(|trade| trade.metadata.broadcast.is_none()));
+
+    // More results than trades
+    let exec = StubExec {
+        broadcasts: vec![sent("a", "0x1"), sent("z", "0x2")],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(true), vec![trade("a")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(statuses(&trades), vec![TradeStatus::BroadcastSucceeded]);
+
+    // Fewer results than trades
+    let exec = StubExec {
+        broadcasts: vec![sent("a", "0x1")],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(true), vec![trade("a"), trade("b")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(statuses(&trades), vec![TradeStatus::BroadcastSucceeded, TradeStatus::Pending]);
+}
+
+#[tokio::test]
+async fn test_simulation_results_matched_by_trade_id() {
+    // Out of order, one trade left out
+    let exec = StubExec {
+        simulations: vec![simulated("c", true), simulated("a", false)],
+        broadcasts: vec![sent("c", "0xc")],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(false), vec![trade("a"), trade("b"), trade("c")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(statuses(&trades), vec![TradeStatus::SimulationFailed, TradeStatus::SimulationFailed, TradeStatus::BroadcastSucceeded]);
+    let errors = trades
+        .iter()
+        .map(|trade| trade.metadata.simulation.as_ref().and_then(|s| s.error.clone()))
+        .collect::<Vec<Option<String>>>();
+    assert_eq!(errors, vec![Some("execution reverted".to_string()), Some("No simulation result".to_string()), None]);
+    assert!(trades.iter().all(|trade| trade.metadata.simulation.as_ref().unwrap().trade_id == trade.metadata.trade_id));
+    assert_eq!(exec.broadcast.lock().unwrap().clone(), vec!["c".to_string()]);
+    assert_eq!(trades[2].metadata.broadcast.as_ref().unwrap().hash, "0xc");
+}
+
+#[tokio::test]
+async fn test_every_simulation_failed_published_without_broadcast() {
+    let exec = StubExec {
+        simulations: vec![simulated("a", false)],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(false), vec![trade("a")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(*exec.calls.lock().unwrap(), 0, "Broadcast not called");
+    assert_eq!(exec.published.lock().unwrap().clone(), vec![("a".to_string(), TradeStatus::SimulationFailed)]);
+    assert_eq!(trades[0].metadata.simulation.as_ref().unwrap().error.as_deref(), Some("execution reverted"));
+}
+
+#[tokio::test]
+async fn test_broadcast_results_matched_by_trade_id() {
+    // Out of order, one trade left out
+    let exec = StubExec {
+        broadcasts: vec![failed("c", "Deadline passed"), sent("a", "0xa")],
+        ..Default::default()
+    };
+    let trades = exec.execute(config(true), vec![trade("a"), trade("b"), trade("c")], env(), "test".to_string()).await.unwrap();
+    assert_eq!(statuses(&trades), vec![TradeStatus::BroadcastSucceeded, TradeStatus::Pending, TradeStatus::BroadcastFailed]);
+    assert_eq!(trades[0].metadata.broadcast.as_ref().unwrap().hash, "0xa");
+    assert!(trades[1].metadata.broadcast.is_none(), "No result of its own");
+    assert!(trades
+        .iter()
+        .flat_map(|trade| trade.metadata.broadcast.as_ref().map(|bd| (bd, trade)))
+        .all(|(bd, trade)| bd.trade_id == trade.metadata.trade_id));
+}
+
+#[tokio::test]
+async fn test_broadcast_error_published_as_failed() {
+    let rejected = ExecError::BuilderRejected("All builders rejected bundle".to_string());
+    let exec = StubExec {
+        simulations: vec![simulated("a", true), simulated("b", false), simulated("c", true)],
+        failure: Some(rejected.clone()),
+        ..Default::default()
+    };
+    let err = exec
+        .execute(config(false), vec![trade("a"), trade("b"), trade("c")], env(), "test".to_string())
+        .await
+        .expect_err("Broadcast failed");
+    assert_eq!(err, rejected);
+    assert_eq!(
+        exec.published.lock().unwrap().clone(),
+        vec![
+            ("a".to_string(), TradeStatus::BroadcastFailed),
+            ("b".to_string(), TradeStatus::SimulationFailed),
+            ("c".to_string(), TradeStatus::BroadcastFailed)
+        ],
+        "Published, the trade failing its simulation keeping its status"
+    );
+    assert_eq!(
+        exec.errors.lock().unwrap().clone(),
+        vec![Some(rejected.clone()), None, Some(rejected)],
+        "With the error of the broadcast"
+    );
+}
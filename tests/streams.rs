@@ -0,0 +1,85 @@
+//! Event streams: per-type stream keys, and the consumer group cycle (read, acknowledge, reclaim after a restart).
+//! The group tests run against the Redis of REDIS_HOST (a throwaway instance), and are skipped when it is unreachable.
+use shd::data::helpers::{connect, delete, stream_id_order, xack, xadd, xclaim, xgroup, xreadgroup};
+use shd::types::moni::MessageType;
+use shd::utils::constants::STREAM_READ_COUNT;
+
+const GROUP: &str = "monitor-test";
+
+#[test]
+fn test_stream_keys() {
+    assert_eq!(MessageType::NewTrade.stream(), "tycho_market_maker:v1:stream:new_trade");
+    assert_eq!(MessageType::EndInstance.stream(), "tycho_market_maker:v1:stream:end_instance");
+    let mut streams = MessageType::ALL.iter().map(|kind| kind.stream()).collect::<Vec<String>>();
+    streams.dedup();
+    assert_eq!(streams.len(), MessageType::ALL.len());
+    // Entry ids order by time, then sequence, across streams
+    assert!(stream_id_order("1700000000000-2") < stream_id_order("1700000000001-0"));
+    assert!(stream_id_order("1700000000000-2") > stream_id_order("1700000000000-1"));
+}
+
+#[tokio::test]
+async fn test_consumer_group_ack_and_reclaim() {
+    let Ok(mut co) = connect().await else {
+        println!("Redis unreachable, skipping");
+        return;
+    };
+    let key = format!("mkmk-test:{}:events", std::process::id());
+    let keys = vec![key.clone()];
+    xgroup(&mut co, &key, GROUP).await.unwrap();
+    xgroup(&mut co, &key, GROUP).await.expect("an existing group is kept");
+    for payload in ["trade-1", "trade-2", "trade-3"] {
+        xadd(&mut co, &key, payload, 1_000).await.unwrap();
+    }
+
+    // First monitor: reads everything, stores only the first entry before dying
+    let entries = xreadgroup(&mut co, &keys, GROUP, "monitor-a", false, 100).await.unwrap();
+    let payloads = entries.iter().filter_map(|e| e.payload.clone()).collect::<Vec<String>>();
+    assert_eq!(payloads, vec!["trade-1", "trade-2", "trade-3"]);
+    xack(&mut co, &key, GROUP, &entries[0].id).await.unwrap();
+    assert!(
+        xreadgroup(&mut co, &keys, GROUP, "monitor-a", false, 100).await.unwrap().is_empty(),
+        "delivered entries are not read again as new"
+    );
+
+    // Restarted monitor under another name: claims and replays the unacknowledged entries in order
+    assert_eq!(xclaim(&mut co, &key, GROUP, "monitor-b").await.unwrap(), 2);
+    let pending = xreadgroup(&mut co, &keys, GROUP, "monitor-b", true, 0).await.unwrap();
+    let payloads = pending.iter().filter_map(|e| e.payload.clone()).collect::<Vec<String>>();
+    assert_eq!(payloads, vec!["trade-2", "trade-3"]);
+    for entry in pending.iter() {
+        xack(&mut co, &key, GROUP, &entry.id).await.unwrap();
+    }
+    assert!(xreadgroup(&mut co, &keys, GROUP, "monitor-b", true, 0).await.unwrap().is_empty());
+    assert_eq!(xclaim(&mut co, &key, GROUP, "monitor-c").await.unwrap(), 0);
+    delete(&key).await;
+}
+
+#[tokio::test]
+async fn test_reclaim_drains_more_than_a_page() {
+    let Ok(mut co) = connect().await else {
+        println!("Redis unreachable, skipping");
+        return;
+    };
+    let key = format!("mkmk-test:{}:backlog", std::process::id());
+    let keys = vec![key.clone()];
+    xgroup(&mut co, &key, GROUP).await.unwrap();
+    let backlog = STREAM_READ_COUNT * 2 + 10;
+    for x in 0..backlog {
+        xadd(&mut co, &key, &format!("trade-{}", x), 10_000).await.unwrap();
+    }
+    // Delivered to a monitor that died before acknowledging any of them
+    let mut delivered = 0;
+    loop {
+        let entries = xreadgroup(&mut co, &keys, GROUP, "monitor-a", false, 100).await.unwrap();
+        if entries.is_empty() {
+            break;
+        }
+        delivered += entries.len();
+    }
+    assert_eq!(delivered, backlog);
+
+    assert_eq!(xclaim(&mut co, &key, GROUP, "monitor-b").await.unwrap(), backlog, "every page of the pending list claimed");
+    assert_eq!(xclaim(&mut co, &key, GROUP, "monitor-b").await.unwrap(), 0, "already ours");
+    delete(&key).await;
+}
@@ -0,0 +1,54 @@
+//! Timing fields of the trades: Unix time in milliseconds, and the block of the simulation.
+use shd::types::maker::{BroadcastData, SimulatedData};
+use shd::utils::misc::now_ms;
+
+/// 2023-01-01 and 2100-01-01, in Unix milliseconds.
+const PLAUSIBLE_MS: std::ops::Range<u128> = 1_672_531_200_000..4_102_444_800_000;
+
+#[test]
+fn test_now_is_unix_time_in_ms() {
+    let now = now_ms();
+    assert!(PLAUSIBLE_MS.contains(&now), "{}", now);
+    let elapsed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis();
+    assert!(elapsed.abs_diff(now) < 1_000);
+}
+
+#[test]
+fn test_simulated_block_round_trip() {
+    let simulated = SimulatedData {
+        trade_id: "trade-123".to_string(),
+        simulated_at_ms: now_ms(),
+        simulated_took_ms: 120,
+        simulated_block: 21_000_000,
+        estimated_gas: 150_000,
+        status: true,
+        error: None,
+    };
+    let json = serde_json::to_value(&simulated).unwrap();
+    assert_eq!(json["simulated_block"], 21_000_000);
+    let back: SimulatedData = serde_json::from_value(json).unwrap();
+    assert!(PLAUSIBLE_MS.contains(&back.simulated_at_ms));
+    assert_eq!(back.simulated_block, 21_000_000);
+
+    // Trades published before the block was recorded
+    let legacy: SimulatedData = serde_json::from_value(serde_json::json!({
+        "simulated_at_ms": 1_700_000_000_000u64,
+        "simulated_took_ms": 120,
+        "estimated_gas": 150_000,
+        "status": true,
+        "error": null,
+    }))
+    .unwrap();
+    assert_eq!(legacy.simulated_block, 0);
+}
+
+#[test]
+fn test_broadcast_timestamp_round_trip() {
+    let broadcast = BroadcastData {
+        broadcasted_at_ms: now_ms(),
+        broadcast_error: Some("Deadline passed".to_string()),
+        ..Default::default()
+    };
+    let back: BroadcastData = serde_json::from_str(&serde_json::to_string(&broadcast).unwrap()).unwrap();
+    assert!(PLAUSIBLE_MS.contains(&back.broadcasted_at_ms), "{}", back.broadcasted_at_ms);
+}
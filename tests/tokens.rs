@@ -0,0 +1,179 @@
+//! Tycho token list: every page read until a short one, sanitized, and capped by `token_max`.
+mod common;
+
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use common::{base, quote};
+use shd::maker::tycho::{chain, paginate, sanitize, ChainCommon, TokenPages, TychoApiError};
+use shd::maker::universe::merge;
+use shd::types::config::load_market_maker_config;
+use tycho_common::dto::ResponseToken;
+use tycho_common::models::Chain;
+use tycho_common::Bytes;
+
+/// Token list of `total` tokens, the first `invalid` of each page without gas, recording the requested pages, failing
+/// on `failing`.
+struct MockPages {
+    total: usize,
+    invalid: usize,
+    failing: Option<i64>,
+    requested: Mutex<Vec<i64>>,
+}
+
+impl MockPages {
+    fn new(total: usize) -> Self {
+        Self {
+            total,
+            invalid: 0,
+            failing: None,
+            requested: Mutex::new(vec![]),
+        }
+    }
+
+    fn requested(&self) -> Vec<i64> {
+        self.requested.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TokenPages for MockPages {
+    async fn page(&self, page: i64, page_size: i64) -> Result<Vec<ResponseToken>, TychoApiError> {
+        self.requested.lock().unwrap().push(page);
+        if self.failing == Some(page) {
+            return Err(TychoApiError::Other("HTTP 502 Bad Gateway".to_string()));
+        }
+        let start = (page * page_size) as usize;
+        let end = (start + page_size as usize).min(self.total);
+        Ok((start..end)
+            .map(|i| {
+                let gas = if i - start < self.invalid { vec![Some(0)] } else { vec![Some(30_000)] };
+                listed(&format!("0x{:040x}", i + 1), &format!("T{}", i), gas)
+            })
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn test_every_page_read_past_the_first() {
+    let pages = MockPages::new(7_250);
+    let tokens = paginate(&pages, ChainCommon::Ethereum, 3_000, 0).await.unwrap();
+    assert_eq!(tokens.len(), 7_250);
+    assert_eq!(pages.requested(), vec![0, 1, 2]);
+    assert_eq!(tokens.last().unwrap().symbol, "T7249");
+
+    // A full last page is followed by an empty one
+    let pages = MockPages::new(6_000);
+    assert_eq!(paginate(&pages, ChainCommon::Ethereum, 3_000, 0).await.unwrap().len(), 6_000);
+    assert_eq!(pages.requested(), vec![0, 1, 2]);
+
+    let pages = MockPages::new(0);
+    assert!(paginate(&pages, ChainCommon::Ethereum, 3_000, 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_short_page_counted_before_sanitization() {
+    // Full pages of 3000 listed tokens, 2900 valid
+    let pages = MockPages {
+        invalid: 100,
+        ..MockPages::new(7_250)
+    };
+    let tokens = paginate(&pages, ChainCommon::Ethereum, 3_000, 0).await.unwrap();
+    assert_eq!(pages.requested(), vec![0, 1, 2], "Pages past the first read, though left short by the sanitization");
+    assert_eq!(tokens.len(), 7_250 - 3 * 100);
+    assert!(tokens.iter().all(|t| t.gas == vec![Some(30_000)]));
+
+    // The cap counts the valid tokens
+    let pages = MockPages {
+        invalid: 100,
+        ..MockPages::new(10_000)
+    };
+    assert_eq!(paginate(&pages, ChainCommon::Ethereum, 3_000, 5_000).await.unwrap().len(), 5_000);
+    assert_eq!(pages.requested(), vec![0, 1]);
+}
+
+#[tokio::test]
+async fn test_token_max_caps_the_pages() {
+    let pages = MockPages::new(10_000);
+    let tokens = paginate(&pages, ChainCommon::Ethereum, 3_000, 4_000).await.unwrap();
+    assert_eq!(tokens.len(), 4_000);
+    assert_eq!(pages.requested(), vec![0, 1], "No page past the cap");
+}
+
+#[tokio::test]
+async fn test_failed_page_fails_the_list() {
+    let pages = MockPages {
+        failing: Some(1),
+        ..MockPages::new(7_250)
+    };
+    assert_eq!(
+        paginate(&pages, ChainCommon::Ethereum, 3_000, 0).await.unwrap_err(),
+        TychoApiError::Other("HTTP 502 Bad Gateway".to_string())
+    );
+    assert_eq!(pages.requested(), vec![0, 1]);
+}
+
+#[tokio::test]
+async fn test_base_and_quote_merged_past_the_cap() {
+    let pages = MockPages::new(10_000);
+    let listed = paginate(&pages, ChainCommon::Ethereum, 3_000, 3_000).await.unwrap();
+    let addresses = vec![base().address.to_string(), quote().address.to_string()];
+    assert!(!listed.iter().any(|t| t.address == base().address));
+
+    let tokens = merge(listed, vec![base(), quote()], &addresses);
+    assert_eq!(tokens.len(), 3_002);
+    assert!(tokens.iter().any(|t| t.address == base().address) && tokens.iter().any(|t| t.address == quote().address));
+}
+
+#[test]
+fn test_token_list_defaults() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").unwrap();
+    assert_eq!((config.token_min_quality, config.token_traded_days, config.token_max), (100, 7, 0));
+}
+
+/// Token of the Tycho API, as listed by `tokens` and `specific`.
+fn listed(address: &str, symbol: &str, gas: Vec<Option<u64>>) -> ResponseToken {
+    ResponseToken {
+        chain: ChainCommon::Ethereum,
+        address: Bytes::from_str(address).unwrap(),
+        symbol: symbol.to_string(),
+        decimals: 18,
+        tax: 0,
+        gas,
+        quality: 100,
+    }
+}
+
+#[test]
+fn test_sanitize_gas_and_symbols() {
+    let input = vec![
+        listed("0x0000000000000000000000000000000000000001", "WETH", vec![Some(21_000), Some(50_000)]),
+        listed("0x0000000000000000000000000000000000000002", "ZERO", vec![Some(0)]),
+        listed("0x0000000000000000000000000000000000000003", "NONE", vec![None]),
+        listed("0x0000000000000000000000000000000000000004", "EMPTY", vec![]),
+        listed("0x0000000000000000000000000000000000000005", "TWO WORDS", vec![Some(30_000)]),
+        listed("0x0000000000000000000000000000000000000006", "BAD\u{7}", vec![Some(30_000)]),
+        listed("0x0000000000000000000000000000000000000007", "ÉTH", vec![Some(30_000)]),
+        listed("0x0000000000000000000000000000000000000008", "AVERYLONGSYMBOLOFMORETHANTHIRTYTWOCHARACTERS", vec![Some(30_000)]),
+    ];
+    let tokens = sanitize(input, ChainCommon::Base);
+    let symbols = tokens.iter().map(|t| t.symbol.as_str()).collect::<Vec<&str>>();
+    assert_eq!(
+        symbols,
+        vec!["WETH", "AVERYLONGSYMBOLOFMORETHANTHIRTYTWOCHARACTERS"],
+        "Zero or missing gas and non printable symbols excluded, no length cap"
+    );
+    assert_eq!(tokens[0].gas, vec![Some(21_000)], "First gas value only");
+    let network: Chain = ChainCommon::Base.into();
+    assert!(tokens.iter().all(|t| t.chain == network), "Chain of the network, not of the response");
+    assert_eq!((tokens[0].decimals, tokens[0].quality, tokens[0].tax), (18, 100, 0));
+}
+
+#[test]
+fn test_chain_of_the_networks() {
+    for (network, expected) in [("ethereum", ChainCommon::Ethereum), ("base", ChainCommon::Base), ("unichain", ChainCommon::Unichain)] {
+        assert_eq!(chain(network.to_string()).map(|(common, _)| common), Some(expected));
+    }
+    assert!(chain("arbitrum".to_string()).is_none());
+}
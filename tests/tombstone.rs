@@ -0,0 +1,93 @@
+//! Components removed from the stream: remembered for `tombstone_blocks`, their orders dropped before preparation.
+mod common;
+
+use std::collections::HashMap;
+
+use common::{base, component, context, inventory, maker, pool, quote, watch_config, MockBalances, MockProtocolSim};
+use shd::maker::pools::StreamPools;
+use shd::maker::tombstone::Tombstones;
+use shd::opti::skew::InventorySkew;
+use shd::types::config::MarketMakerConfig;
+use shd::types::tycho::SharedUpdate;
+use tycho_common::simulation::protocol_sim::ProtocolSim;
+
+const POOL: &str = "0xaaaa000000000000000000000000000000000001";
+const OTHER: &str = "0xaaaa000000000000000000000000000000000002";
+const REFERENCE: f64 = 3_000.0;
+
+fn config() -> MarketMakerConfig {
+    let mut config = watch_config();
+    config.tombstone_blocks = 10;
+    config
+}
+
+#[test]
+fn test_removed_component_remembered_for_the_window() {
+    let mut tombstones = Tombstones::new(10);
+    tombstones.bury(&POOL.to_uppercase(), 100);
+    assert_eq!(tombstones.removed_at(POOL, 100), Some(100));
+    assert_eq!(tombstones.removed_at(POOL, 109), Some(100));
+    assert_eq!(tombstones.removed_at(POOL, 110), None, "Window of 10 blocks elapsed");
+    assert_eq!(tombstones.removed_at(OTHER, 101), None);
+
+    tombstones.prune(110);
+    assert!(tombstones.is_empty());
+
+    // Listed again by the stream
+    tombstones.bury(POOL, 200);
+    tombstones.revive(POOL);
+    assert_eq!(tombstones.removed_at(POOL, 201), None);
+
+    let mut disabled = Tombstones::new(0);
+    disabled.bury(POOL, 100);
+    assert!(disabled.is_empty());
+}
+
+#[tokio::test]
+async fn test_order_dropped_when_removed_between_evaluate_and_prepare() {
+    let mut mk = maker(config());
+    let (removed, kept) = (pool(POOL, 1_000., 3_030_000., 0.003), pool(OTHER, 1_000., 3_030_000., 0.003));
+    let targets = vec![removed, kept];
+    let skew = InventorySkew::neutral(mk.config.min_watch_spread_bps, mk.config.target_inventory_ratio);
+    let adjustments = mk.evaluate(&targets, vec![3_030., 3_030.], REFERENCE, &skew, 100);
+    assert_eq!(adjustments.len(), 2);
+    let mut orders = vec![];
+    for adjustment in adjustments {
+        orders.extend(mk.readjust(context(REFERENCE, 1.0, 100), inventory(), vec![adjustment], &MockBalances::of(&targets)).await);
+    }
+    assert_eq!(orders.len(), 2);
+
+    // Removed from the stream once the orders are built
+    mk.tombstones.bury(POOL, 101);
+    let prepared = mk.drop_removed(orders.clone(), 101);
+    assert_eq!(prepared.len(), 1);
+    assert_eq!(prepared[0].adjustment.psc.component.id.to_string(), OTHER);
+
+    assert_eq!(mk.drop_removed(orders, 110).len(), 2, "Window elapsed");
+}
+
+#[test]
+fn test_removed_component_dropped_from_the_routing_graph_and_states() {
+    let mut states: HashMap<String, Box<dyn ProtocolSim>> = HashMap::new();
+    let mut new_pairs = HashMap::new();
+    for id in [POOL, OTHER] {
+        states.insert(id.to_string(), Box::new(MockProtocolSim::new(base(), quote(), 1_000.0, 3_000_000.0, 0.003)));
+        new_pairs.insert(id.to_string(), component(id, "uniswap_v2", vec![base(), quote()]));
+    }
+    let mut pools = StreamPools::default();
+    pools.apply(&SharedUpdate {
+        block_number_or_timestamp: 100,
+        states,
+        new_pairs: new_pairs.clone(),
+        removed_pairs: HashMap::new(),
+    });
+    assert_eq!((pools.components.len(), pools.protosims.len(), pools.graph.len()), (2, 2, 2));
+
+    pools.apply(&SharedUpdate {
+        block_number_or_timestamp: 101,
+        removed_pairs: HashMap::from([(POOL.to_string(), new_pairs[POOL].clone())]),
+        ..Default::default()
+    });
+    assert_eq!((pools.components.len(), pools.protosims.len(), pools.graph.len()), (1, 1, 1));
+    assert!(pools.graph.component(POOL).is_none() && !pools.protosims.contains_key(POOL));
+}
@@ -0,0 +1,77 @@
+//! Token universe cache: round trip through the cache file, max age, corrupted or foreign files, fresh base and quote.
+mod common;
+
+use std::path::PathBuf;
+
+use common::{base, quote, token};
+use shd::maker::universe::{load, merge, path, save, TokenUniverse};
+use shd::types::config::load_market_maker_config;
+
+const HOUR: u64 = 3_600;
+
+fn temporary(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mkmk-universe-{}-{}", name, std::process::id())).join("tokens.ethereum.json")
+}
+
+#[test]
+fn test_cache_file_round_trip() {
+    let file = temporary("round-trip");
+    let tokens = vec![base(), quote(), token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18)];
+    let universe = TokenUniverse::new("ethereum", 1_700_000_000, &tokens);
+    save(&file, &universe).expect("Cache directory created");
+
+    let loaded = load(&file, "ethereum").expect("Written just before");
+    assert_eq!(loaded, universe);
+    let restored = loaded.tokens().unwrap();
+    assert_eq!(restored.len(), 3);
+    for (restored, original) in restored.iter().zip(tokens.iter()) {
+        assert_eq!(restored.address, original.address);
+        assert_eq!((restored.symbol.as_str(), restored.decimals), (original.symbol.as_str(), original.decimals));
+        assert_eq!(restored.gas, original.gas);
+    }
+
+    assert!(load(&file, "base").is_none(), "Tokens of another network");
+    std::fs::remove_dir_all(file.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_corrupted_or_missing_cache_is_ignored() {
+    let file = temporary("corrupted");
+    assert!(load(&file, "ethereum").is_none(), "No file yet");
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    for contents in ["", "{\"network\": \"ethereum\", \"fetched_at_s\": 1", "[1, 2, 3]", "\u{0}\u{1}garbage"] {
+        std::fs::write(&file, contents).unwrap();
+        assert!(load(&file, "ethereum").is_none(), "{:?}", contents);
+    }
+    std::fs::remove_dir_all(file.parent().unwrap()).ok();
+}
+
+#[test]
+fn test_cache_max_age() {
+    let universe = TokenUniverse::new("ethereum", 1_000_000, &[base()]);
+    assert!(universe.fresh(1_000_000 + 23 * HOUR, 24));
+    assert!(!universe.fresh(1_000_000 + 24 * HOUR, 24));
+    assert!(universe.fresh(999_000, 24), "Clock behind the fetch");
+    assert!(!universe.fresh(1_000_000, 0), "Disabled");
+}
+
+#[test]
+fn test_config_tokens_replaced_by_the_fresh_ones() {
+    let stale = token("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "USDC", 18);
+    let dai = token("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI", 18);
+    let addresses = vec!["0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string()];
+
+    let merged = merge(vec![base(), stale, dai.clone()], vec![quote()], &addresses);
+    let usdc = merged.iter().find(|t| t.address == quote().address).unwrap();
+    assert_eq!(usdc.decimals, 6, "Fresh entry wins over the cached one");
+    assert!(merged.iter().all(|t| t.address != base().address), "Not returned fresh: dropped, not served from the cache");
+    assert!(merged.iter().any(|t| t.address == dai.address));
+    assert_eq!(merged.len(), 2);
+}
+
+#[test]
+fn test_token_cache_config() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!((config.token_cache_dir.as_str(), config.token_cache_max_age_h), ("cache", 24));
+    assert_eq!(path(&config), PathBuf::from("cache/tokens.ethereum.json"));
+}
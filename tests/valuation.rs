@@ -0,0 +1,65 @@
+//! Valuation: token amounts in ETH and USD, the inventory report of `maker inventory` and its command line.
+mod common;
+
+use clap::Parser;
+use common::{context, maker};
+use shd::maker::valuation::{report, worth};
+use shd::types::cli::{MakerCli, MakerCommand};
+use shd::types::maker::Inventory;
+
+#[test]
+fn test_worth_in_eth_and_usd() {
+    let ctx = context(3_000.0, 1.0, 100);
+    assert_eq!(worth(2.0, true, &ctx), (2.0, 6_000.0));
+    let (eth, usd) = worth(1_500.0, false, &ctx);
+    assert!((eth - 0.5).abs() < 1e-12 && (usd - 1_500.0).abs() < 1e-9);
+    assert_eq!(worth(0.0, true, &ctx), (0.0, 0.0));
+}
+
+#[test]
+fn test_inventory_report() {
+    let mk = maker(common::config());
+    let inventory = Inventory {
+        base_balance: 10 * 10u128.pow(18),
+        quote_balance: 10_000 * 10u128.pow(6),
+        nonce: 42,
+        native_balance: 5 * 10u128.pow(17),
+    };
+    let report = report(&mk, &inventory, &context(3_000.0, 1.0, 100));
+    assert_eq!((report.block, report.nonce, report.eth_usd), (100, 42, 3_000.0));
+    assert_eq!(report.wallet, mk.config.wallet_public_key);
+    let (base, quote) = (&report.tokens[0], &report.tokens[1]);
+    assert_eq!((base.token.as_str(), base.raw, base.balance, base.usd), ("ETH", 10 * 10u128.pow(18), 10.0, 30_000.0));
+    assert_eq!((quote.token.as_str(), quote.raw, quote.balance), ("USDC", 10_000 * 10u128.pow(6), 10_000.0));
+    assert!((quote.usd - 10_000.0).abs() < 1e-6);
+    assert!((report.total_usd - 40_000.0).abs() < 1e-6);
+    assert!((base.share_pct - 75.0).abs() < 1e-9 && (quote.share_pct - 25.0).abs() < 1e-9);
+    // Native gas balance valued apart from the total
+    assert_eq!((report.native.balance, report.native.usd, report.native.share_pct), (0.5, 1_500.0, 0.0));
+
+    let table = report.to_string();
+    assert!(table.contains("Nonce 42") && table.contains("75.00%") && table.contains("40000.00"), "{}", table);
+    let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+    assert_eq!(json["tokens"][0]["raw"], serde_json::json!(10u128 * 10u128.pow(18)));
+    assert_eq!(json["native"]["usd"], 1_500.0);
+
+    // An empty wallet has no share
+    let empty = report(
+        &mk,
+        &Inventory {
+            base_balance: 0,
+            quote_balance: 0,
+            ..inventory
+        },
+        &context(3_000.0, 1.0, 100),
+    );
+    assert!(empty.total_usd == 0. && empty.tokens.iter().all(|t| t.share_pct == 0.));
+}
+
+#[test]
+fn test_inventory_command() {
+    let cli = MakerCli::try_parse_from(["maker", "inventory"]).unwrap();
+    assert!(matches!(cli.command, Some(MakerCommand::Inventory { watch: None, json: false })));
+    let cli = MakerCli::try_parse_from(["maker", "inventory", "--watch", "30", "--json"]).unwrap();
+    assert!(matches!(cli.command, Some(MakerCommand::Inventory { watch: Some(30), json: true })));
+}
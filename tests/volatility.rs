@@ -0,0 +1,65 @@
+use shd::opti::volatility::VolatilityEstimator;
+
+const LAMBDA: f64 = 0.94;
+const STATIC_BPS: f64 = 10.0;
+
+/// Alternating moves of `step_bps` around 2500.
+fn choppy(n: usize, step_bps: f64) -> Vec<f64> {
+    (0..n).map(|x| if x % 2 == 0 { 2500.0 } else { 2500.0 * (1.0 + step_bps / 10_000.0) }).collect()
+}
+
+fn feed(estimator: &mut VolatilityEstimator, prices: &[f64]) -> f64 {
+    prices.iter().fold(0.0, |_, price| estimator.update(*price))
+}
+
+#[test]
+fn test_flat_prices_keep_static_threshold() {
+    let mut estimator = VolatilityEstimator::new(LAMBDA);
+    assert_eq!(estimator.update(2500.0), 0.0, "No return after a single price");
+    feed(&mut estimator, &[2500.0; 50]);
+    assert_eq!(estimator.sigma_bps(), 0.0);
+    assert_eq!(estimator.threshold_bps(STATIC_BPS, 2.0, 50.0), STATIC_BPS);
+}
+
+#[test]
+fn test_threshold_widens_with_volatility() {
+    let mut quiet = VolatilityEstimator::new(LAMBDA);
+    let mut volatile = VolatilityEstimator::new(LAMBDA);
+    feed(&mut quiet, &choppy(100, 1.0));
+    feed(&mut volatile, &choppy(100, 20.0));
+
+    // Constant absolute returns converge to their size
+    assert!((quiet.sigma_bps() - 1.0).abs() < 0.05, "{}", quiet.sigma_bps());
+    assert!((volatile.sigma_bps() - 20.0).abs() < 0.5, "{}", volatile.sigma_bps());
+
+    let quiet_threshold = quiet.threshold_bps(STATIC_BPS, 1.5, 100.0);
+    let volatile_threshold = volatile.threshold_bps(STATIC_BPS, 1.5, 100.0);
+    assert!((quiet_threshold - (STATIC_BPS + 1.5)).abs() < 0.1, "{}", quiet_threshold);
+    assert!((volatile_threshold - (STATIC_BPS + 30.0)).abs() < 1.0, "{}", volatile_threshold);
+}
+
+#[test]
+fn test_widening_is_capped_and_disabled_by_zero_multiplier() {
+    let mut estimator = VolatilityEstimator::new(LAMBDA);
+    feed(&mut estimator, &choppy(100, 50.0));
+    assert_eq!(estimator.threshold_bps(STATIC_BPS, 2.0, 25.0), STATIC_BPS + 25.0);
+    assert_eq!(estimator.threshold_bps(STATIC_BPS, 0.0, 25.0), STATIC_BPS);
+}
+
+#[test]
+fn test_volatility_decays_after_a_shock() {
+    let mut estimator = VolatilityEstimator::new(LAMBDA);
+    feed(&mut estimator, &[2500.0; 10]);
+    let shocked = estimator.update(2550.0); // ~80 bps move
+    assert!(shocked > 15.0, "{}", shocked);
+
+    let calmed = feed(&mut estimator, &[2550.0; 60]);
+    assert!(calmed < shocked / 5.0, "Shock fades once prices settle: {} vs {}", calmed, shocked);
+}
+
+#[test]
+fn test_invalid_prices_are_ignored() {
+    let mut estimator = VolatilityEstimator::new(LAMBDA);
+    feed(&mut estimator, &[2500.0, 0.0, f64::NAN, 2500.0]);
+    assert_eq!(estimator.sigma_bps(), 0.0);
+}
@@ -0,0 +1,104 @@
+//! Balance watcher: external movements against the cached inventory, the forced refresh and the one block pause.
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::maker;
+use shd::maker::watcher::BalanceWatcher;
+use shd::types::config::load_market_maker_config;
+use shd::types::maker::Inventory;
+
+const WETH: u128 = 1_000_000_000_000_000_000; // 18 decimals
+const USDC: u128 = 1_000_000; // 6 decimals
+
+fn inventory(base: u128, quote: u128) -> Inventory {
+    Inventory {
+        base_balance: base,
+        quote_balance: quote,
+        nonce: 42,
+        native_balance: WETH,
+    }
+}
+
+#[test]
+fn test_movement_threshold() {
+    let watcher = BalanceWatcher::new(60_000, 100.);
+    let cached = inventory(2 * WETH, 5_000 * USDC);
+    assert!(watcher.compare(&cached, &cached).is_none());
+
+    // 0.5% of the quote balance, below the 1% threshold
+    assert!(watcher.compare(&cached, &inventory(2 * WETH, 5_025 * USDC)).is_none());
+
+    // Half of the base balance withdrawn
+    let movement = watcher.compare(&cached, &inventory(WETH, 5_000 * USDC)).expect("Withdrawal must be detected");
+    assert_eq!(movement.base_delta, -(WETH as i128));
+    assert_eq!(movement.base_bps, -5_000.);
+    assert_eq!(movement.quote_delta, 0);
+
+    // Deposit on an empty side
+    let movement = watcher.compare(&inventory(2 * WETH, 0), &inventory(2 * WETH, 100 * USDC)).unwrap();
+    assert!(movement.quote_bps.is_infinite());
+
+    // Gas spent on the native balance is not a movement
+    let mut gas = cached.clone();
+    gas.native_balance /= 2;
+    assert!(watcher.compare(&cached, &gas).is_none());
+}
+
+#[test]
+fn test_check_interval() {
+    let now = Instant::now();
+    let mut watcher = BalanceWatcher::new(60_000, 100.);
+    assert!(watcher.due(now), "Checked on the first evaluation");
+    watcher.checked(now);
+    assert!(!watcher.due(now + Duration::from_secs(59)));
+    assert!(watcher.due(now + Duration::from_secs(60)));
+
+    let disabled = BalanceWatcher::new(0, 100.);
+    assert!(!disabled.due(now));
+}
+
+#[test]
+fn test_divergence_refreshes_and_pauses_one_block() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    let mut mk = maker(config);
+    let start = Instant::now();
+    mk.inventory.store(inventory(2 * WETH, 5_000 * USDC), start);
+
+    // 1,000 USDC withdrawn from the wallet outside of the bot
+    let later = start + Duration::from_secs(60);
+    let onchain = inventory(2 * WETH, 4_000 * USDC);
+    let movement = mk.reconcile(onchain.clone(), later).expect("Divergence must be detected");
+    assert_eq!(movement.quote_delta, -1_000 * USDC as i128);
+    let cached = mk.inventory.fresh(later).expect("Cache refreshed with the on-chain balances");
+    assert_eq!((cached.base_balance, cached.quote_balance), (2 * WETH, 4_000 * USDC));
+
+    // Order creation skipped on the next evaluated block only
+    assert!(mk.watcher.take_pause());
+    assert!(!mk.watcher.take_pause());
+    assert!(!mk.watcher.due(later), "Next check an interval later");
+
+    // Balances now match the cache
+    assert!(mk.reconcile(onchain, later + Duration::from_secs(60)).is_none());
+    assert!(!mk.watcher.take_pause());
+}
+
+#[test]
+fn test_refresh_without_cache_is_not_a_movement() {
+    let config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    let mut mk = maker(config);
+    let now = Instant::now();
+    assert!(mk.reconcile(inventory(WETH, USDC), now).is_none(), "Nothing cached to compare with");
+    assert!(!mk.watcher.take_pause());
+    assert!(mk.inventory.fresh(now).is_some());
+}
+
+#[test]
+fn test_balance_watch_config() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!(config.balance_watch_interval_ms, 60_000);
+    assert_eq!(config.external_movement_threshold_bps, 100.);
+    config.validate().unwrap();
+    config.external_movement_threshold_bps = 0.;
+    assert!(config.validate().unwrap_err().to_string().contains("external_movement_threshold_bps"));
+}
@@ -0,0 +1,114 @@
+use alloy::primitives::{Address, TxKind, U256};
+use alloy::rpc::types::TransactionRequest;
+use shd::maker::wrap::{deposit, unwrap_amount, withdraw, wrap_amount};
+use shd::types::config::load_market_maker_config;
+use shd::types::maker::TradeTxRequest;
+
+const ETH: u128 = 1_000_000_000_000_000_000;
+
+fn weth() -> Address {
+    "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap()
+}
+
+fn wallet() -> Address {
+    "0x1111111111111111111111111111111111111111".parse().unwrap()
+}
+
+fn router_call(to: &str) -> TransactionRequest {
+    TransactionRequest {
+        to: Some(TxKind::Call(to.parse().unwrap())),
+        ..Default::default()
+    }
+}
+
+fn target(tx: &TransactionRequest) -> Address {
+    match tx.to {
+        Some(TxKind::Call(address)) => address,
+        _ => panic!("Expected a call"),
+    }
+}
+
+fn selector(tx: &TransactionRequest) -> Vec<u8> {
+    tx.input.input.as_ref().expect("Calldata").to_vec()[..4].to_vec()
+}
+
+#[test]
+fn test_wrap_amount_covers_missing_part_only() {
+    assert_eq!(wrap_amount(ETH, 2 * ETH, 5 * ETH), Ok(None), "Wrapped balance covers the input");
+    assert_eq!(wrap_amount(3 * ETH, ETH, 5 * ETH), Ok(Some(2 * ETH)));
+    assert!(wrap_amount(3 * ETH, ETH, ETH).is_err(), "Not enough wrappable native");
+}
+
+#[test]
+fn test_unwrap_amount_above_threshold() {
+    assert_eq!(unwrap_amount(ETH, ETH, 0), None, "0 disables unwrapping");
+    assert_eq!(unwrap_amount(ETH, ETH, 3 * ETH), None, "Below the threshold");
+    assert_eq!(unwrap_amount(2 * ETH, 2 * ETH, 3 * ETH), Some(ETH));
+}
+
+#[test]
+fn test_deposit_and_withdraw_calls() {
+    let wrap = deposit(weth(), wallet(), ETH);
+    assert_eq!(target(&wrap), weth());
+    assert_eq!(wrap.value, Some(U256::from(ETH)), "Deposit sends the native amount");
+    assert_eq!(selector(&wrap), vec![0xd0, 0xe3, 0x0d, 0xb0], "deposit()");
+
+    let unwrap = withdraw(weth(), wallet(), ETH);
+    assert_eq!(unwrap.value, Some(U256::ZERO));
+    assert_eq!(selector(&unwrap), vec![0x2e, 0x1a, 0x7d, 0x4d], "withdraw(uint256)");
+    let data = unwrap.input.input.as_ref().unwrap();
+    assert_eq!(U256::from_be_slice(&data[4..36]), U256::from(ETH));
+}
+
+#[test]
+fn test_three_call_shape_wrap_approve_swap() {
+    let mut request = TradeTxRequest {
+        wrap: Some(deposit(weth(), wallet(), ETH)),
+        approve: Some(router_call("0x2222222222222222222222222222222222222222")),
+        swap: router_call("0x3333333333333333333333333333333333333333"),
+        unwrap: None,
+    };
+    request.sequence(7);
+
+    let calls = request.calls();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(request.swap_index(), 2);
+    assert_eq!(target(&calls[0]), weth(), "Wrap first");
+    assert_eq!(target(&calls[2]), "0x3333333333333333333333333333333333333333".parse::<Address>().unwrap(), "Swap last");
+    assert_eq!(calls.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![Some(7), Some(8), Some(9)]);
+}
+
+#[test]
+fn test_three_call_shape_approve_swap_unwrap() {
+    let mut request = TradeTxRequest {
+        wrap: None,
+        approve: Some(router_call("0x2222222222222222222222222222222222222222")),
+        swap: router_call("0x3333333333333333333333333333333333333333"),
+        unwrap: Some(withdraw(weth(), wallet(), ETH)),
+    };
+    request.sequence(0);
+
+    let calls = request.calls();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(request.swap_index(), 1);
+    assert_eq!(target(&calls[2]), weth(), "Unwrap after the swap");
+    assert_eq!(request.swap.nonce, Some(1));
+    assert_eq!(request.unwrap.as_ref().and_then(|tx| tx.nonce), Some(2));
+}
+
+#[test]
+fn test_wrapping_requires_wrapped_native_in_pair() {
+    let mut config = load_market_maker_config("config/mainnet.eth-usdc.toml").expect("Reference config must load");
+    assert_eq!(config.wrapped_native_side(), Some(true), "WETH is the base token");
+    config.auto_wrap_native = true;
+    config.unwrap_to_native_above = 10.0;
+    assert!(config.validate().is_ok());
+
+    config.gas_token_symbol = "0x4200000000000000000000000000000000000006".to_string();
+    let err = config.validate().expect_err("Wrapped gas token outside the pair").to_string();
+    assert!(err.contains("auto_wrap_native"), "{}", err);
+
+    config.auto_wrap_native = false;
+    config.unwrap_to_native_above = -1.0;
+    assert!(config.validate().is_err());
+}